@@ -112,7 +112,8 @@ CREATE TYPE public.entry_component_history_action AS ENUM (
 
 CREATE TYPE public.entry_component_payload_type AS ENUM (
     'Json',
-    'Asset'
+    'Asset',
+    'Formula'
 );
 
 
@@ -149,6 +150,46 @@ CREATE TYPE public.identifier_kind AS ENUM (
 );
 
 
+--
+-- Name: report_reason; Type: TYPE; Schema: public; Owner: -
+--
+
+CREATE TYPE public.report_reason AS ENUM (
+    'Spam',
+    'Harassment',
+    'HateSpeech',
+    'SexualContent',
+    'Violence',
+    'IllegalContent',
+    'Impersonation',
+    'Other'
+);
+
+
+--
+-- Name: report_status; Type: TYPE; Schema: public; Owner: -
+--
+
+CREATE TYPE public.report_status AS ENUM (
+    'Open',
+    'Escalated',
+    'Resolved',
+    'Dismissed'
+);
+
+
+--
+-- Name: report_target_kind; Type: TYPE; Schema: public; Owner: -
+--
+
+CREATE TYPE public.report_target_kind AS ENUM (
+    'Message',
+    'Space',
+    'User',
+    'Media'
+);
+
+
 --
 -- Name: scope_kind; Type: TYPE; Schema: public; Owner: -
 --
@@ -217,6 +258,58 @@ CREATE TABLE public._sqlx_migrations (
 );
 
 
+--
+-- Name: abuse_reports; Type: TABLE; Schema: public; Owner: -
+--
+
+CREATE TABLE public.abuse_reports (
+    id uuid DEFAULT uuidv7() NOT NULL,
+    reporter_id uuid,
+    target_kind public.report_target_kind NOT NULL,
+    target_id uuid NOT NULL,
+    space_id uuid,
+    reason public.report_reason NOT NULL,
+    comment text DEFAULT ''::text NOT NULL,
+    snapshot jsonb NOT NULL,
+    status public.report_status DEFAULT 'Open'::public.report_status NOT NULL,
+    handler_id uuid,
+    resolution_note text DEFAULT ''::text NOT NULL,
+    created timestamp with time zone DEFAULT now() NOT NULL,
+    handled timestamp with time zone,
+    CONSTRAINT abuse_report_comment_valid CHECK ((length(comment) <= 1024)),
+    CONSTRAINT abuse_report_resolution_note_valid CHECK ((length(resolution_note) <= 1024))
+);
+
+
+--
+-- Name: admin_audit_log; Type: TABLE; Schema: public; Owner: -
+--
+
+CREATE TABLE public.admin_audit_log (
+    id uuid DEFAULT uuidv7() NOT NULL,
+    actor_id uuid,
+    action text NOT NULL,
+    target_id uuid,
+    detail jsonb DEFAULT '{}'::jsonb NOT NULL,
+    created timestamp with time zone DEFAULT now() NOT NULL
+);
+
+
+--
+-- Name: asset_folders; Type: TABLE; Schema: public; Owner: -
+--
+
+CREATE TABLE public.asset_folders (
+    id uuid DEFAULT uuidv7() NOT NULL,
+    space_id uuid NOT NULL,
+    parent_id uuid,
+    creator_id uuid,
+    name text NOT NULL,
+    created timestamp with time zone DEFAULT now() NOT NULL,
+    CONSTRAINT asset_folder_name_valid CHECK (((length(name) >= 1) AND (length(name) <= 100)))
+);
+
+
 --
 -- Name: assets; Type: TABLE; Schema: public; Owner: -
 --
@@ -229,6 +322,8 @@ CREATE TABLE public.assets (
     name text NOT NULL,
     policy public.asset_policy DEFAULT 'Unlisted'::public.asset_policy NOT NULL,
     created timestamp with time zone DEFAULT now() NOT NULL,
+    folder_id uuid,
+    tags text[] DEFAULT '{}'::text[] NOT NULL,
     CONSTRAINT asset_name_valid CHECK (((length(name) >= 1) AND (length(name) <= 100)))
 );
 
@@ -249,6 +344,26 @@ CREATE TABLE public.channel_members (
 );
 
 
+--
+-- Name: channel_playback; Type: TABLE; Schema: public; Owner: -
+--
+
+CREATE TABLE public.channel_playback (
+    channel_id uuid NOT NULL,
+    space_id uuid NOT NULL,
+    asset_id uuid NOT NULL,
+    playing boolean DEFAULT true NOT NULL,
+    position_ms bigint DEFAULT 0 NOT NULL,
+    looping boolean DEFAULT false NOT NULL,
+    volume real DEFAULT 1 NOT NULL,
+    updated_by uuid,
+    version uuid DEFAULT uuidv7() NOT NULL,
+    updated timestamp with time zone DEFAULT now() NOT NULL,
+    CONSTRAINT channel_playback_position_valid CHECK ((position_ms >= 0)),
+    CONSTRAINT channel_playback_volume_valid CHECK (((volume >= (0)::double precision) AND (volume <= (1)::double precision)))
+);
+
+
 --
 -- Name: channels; Type: TABLE; Schema: public; Owner: -
 --
@@ -266,7 +381,13 @@ CREATE TABLE public.channels (
     is_document boolean DEFAULT false NOT NULL,
     old_name text DEFAULT ''::text NOT NULL,
     type text DEFAULT 'in_game'::text NOT NULL,
-    is_archived boolean DEFAULT false NOT NULL
+    is_archived boolean DEFAULT false NOT NULL,
+    slow_mode_seconds integer DEFAULT 0 NOT NULL,
+    posting_policy text DEFAULT 'everyone'::text NOT NULL,
+    max_message_length integer DEFAULT 0 NOT NULL,
+    CONSTRAINT channel_max_message_length_valid CHECK (((max_message_length >= 0) AND (max_message_length <= 32768))),
+    CONSTRAINT channel_posting_policy_valid CHECK ((posting_policy = ANY (ARRAY['everyone'::text, 'masters_only'::text, 'masters_only_in_game'::text]))),
+    CONSTRAINT channel_slow_mode_valid CHECK (((slow_mode_seconds >= 0) AND (slow_mode_seconds <= 21600)))
 );
 
 
@@ -314,6 +435,60 @@ CREATE TABLE public.characters (
 );
 
 
+--
+-- Name: component_types; Type: TABLE; Schema: public; Owner: -
+--
+
+CREATE TABLE public.component_types (
+    space_id uuid NOT NULL,
+    component_type text NOT NULL,
+    schema_version integer NOT NULL,
+    schema jsonb NOT NULL,
+    migration jsonb DEFAULT '[]'::jsonb NOT NULL,
+    created timestamp with time zone DEFAULT now() NOT NULL,
+    CONSTRAINT component_type_schema_version_valid CHECK ((schema_version > 0))
+);
+
+
+--
+-- Name: encounter_combatants; Type: TABLE; Schema: public; Owner: -
+--
+
+CREATE TABLE public.encounter_combatants (
+    id uuid DEFAULT uuidv7() NOT NULL,
+    channel_id uuid NOT NULL,
+    name text NOT NULL,
+    character_id uuid,
+    entry_id uuid,
+    entry_scope_id uuid,
+    initiative double precision NOT NULL,
+    initiative_message_id uuid,
+    added_by uuid,
+    created timestamp with time zone DEFAULT now() NOT NULL,
+    CONSTRAINT encounter_combatant_entry_scope CHECK (((entry_id IS NULL) = (entry_scope_id IS NULL))),
+    CONSTRAINT encounter_combatant_single_source CHECK (((character_id IS NULL) OR (entry_id IS NULL)))
+);
+
+
+--
+-- Name: encounters; Type: TABLE; Schema: public; Owner: -
+--
+
+CREATE TABLE public.encounters (
+    channel_id uuid NOT NULL,
+    space_id uuid NOT NULL,
+    round integer DEFAULT 1 NOT NULL,
+    current_combatant_id uuid,
+    announce_turns boolean DEFAULT false NOT NULL,
+    duration_component_type text,
+    duration_path text DEFAULT ''::text NOT NULL,
+    version uuid DEFAULT uuidv7() NOT NULL,
+    created timestamp with time zone DEFAULT now() NOT NULL,
+    modified timestamp with time zone DEFAULT now() NOT NULL,
+    CONSTRAINT encounter_round_positive CHECK ((round >= 1))
+);
+
+
 --
 -- Name: entries; Type: TABLE; Schema: public; Owner: -
 --
@@ -380,6 +555,20 @@ CREATE TABLE public.entry_components_asset (
 );
 
 
+--
+-- Name: entry_components_formula; Type: TABLE; Schema: public; Owner: -
+--
+
+CREATE TABLE public.entry_components_formula (
+    entry_id uuid NOT NULL,
+    component_type text NOT NULL,
+    payload_type public.entry_component_payload_type DEFAULT 'Formula'::public.entry_component_payload_type NOT NULL,
+    formula text NOT NULL,
+    CONSTRAINT entry_components_formula_length_valid CHECK (((length(formula) >= 1) AND (length(formula) <= 1024))),
+    CONSTRAINT entry_components_formula_payload_type_valid CHECK ((payload_type = 'Formula'::public.entry_component_payload_type))
+);
+
+
 --
 -- Name: entry_components_json; Type: TABLE; Schema: public; Owner: -
 --
@@ -405,7 +594,8 @@ CREATE TABLE public.entry_effects (
     scope_id uuid NOT NULL,
     operator_id uuid,
     created timestamp with time zone DEFAULT now() NOT NULL,
-    message_id uuid
+    message_id uuid,
+    reverted_by uuid
 );
 
 
@@ -435,6 +625,26 @@ CREATE TABLE public.entry_identifiers (
 );
 
 
+--
+-- Name: entry_templates; Type: TABLE; Schema: public; Owner: -
+--
+
+CREATE TABLE public.entry_templates (
+    id uuid DEFAULT uuidv7() NOT NULL,
+    space_id uuid NOT NULL,
+    name text NOT NULL,
+    key_pattern text NOT NULL,
+    display_name text DEFAULT ''::text NOT NULL,
+    tags text[] DEFAULT '{}'::text[] NOT NULL,
+    reference_note_id uuid,
+    components jsonb DEFAULT '{}'::jsonb NOT NULL,
+    version uuid DEFAULT uuidv7() NOT NULL,
+    created timestamp with time zone DEFAULT now() NOT NULL,
+    modified timestamp with time zone DEFAULT now() NOT NULL,
+    CONSTRAINT entry_template_name_valid CHECK (((length(name) >= 1) AND (length(name) <= 64)))
+);
+
+
 --
 -- Name: events; Type: TABLE; Schema: public; Owner: -
 --
@@ -464,7 +674,11 @@ CREATE TABLE public.media (
     size integer NOT NULL,
     description text DEFAULT ''::text NOT NULL,
     source text DEFAULT ''::text NOT NULL,
-    created timestamp with time zone DEFAULT now() NOT NULL
+    created timestamp with time zone DEFAULT now() NOT NULL,
+    hash_trusted boolean DEFAULT false NOT NULL,
+    last_used timestamp with time zone DEFAULT now() NOT NULL,
+    duration_ms integer,
+    CONSTRAINT media_duration_valid CHECK ((duration_ms >= 0))
 );
 
 
@@ -519,6 +733,24 @@ CREATE TABLE public.note_content_revisions (
 );
 
 
+--
+-- Name: note_links; Type: TABLE; Schema: public; Owner: -
+--
+
+CREATE TABLE public.note_links (
+    note_id uuid NOT NULL,
+    space_id uuid NOT NULL,
+    "position" integer NOT NULL,
+    kind text,
+    target text NOT NULL,
+    target_note_id uuid,
+    target_entry_id uuid,
+    target_character_id uuid,
+    CONSTRAINT note_link_kind_valid CHECK ((kind = ANY (ARRAY['NOTE'::text, 'ENTRY'::text, 'CHARACTER'::text]))),
+    CONSTRAINT note_link_single_target CHECK ((num_nonnulls(target_note_id, target_entry_id, target_character_id) <= 1))
+);
+
+
 --
 -- Name: notes; Type: TABLE; Schema: public; Owner: -
 --
@@ -555,6 +787,24 @@ CREATE TABLE public.proxies (
 );
 
 
+--
+-- Name: random_tables; Type: TABLE; Schema: public; Owner: -
+--
+
+CREATE TABLE public.random_tables (
+    id uuid DEFAULT uuidv7() NOT NULL,
+    space_id uuid NOT NULL,
+    key text NOT NULL,
+    name text NOT NULL,
+    description text DEFAULT ''::text NOT NULL,
+    rows jsonb DEFAULT '[]'::jsonb NOT NULL,
+    version uuid DEFAULT uuidv7() NOT NULL,
+    created timestamp with time zone DEFAULT now() NOT NULL,
+    modified timestamp with time zone DEFAULT now() NOT NULL,
+    CONSTRAINT random_table_name_valid CHECK (((length(name) >= 1) AND (length(name) <= 64)))
+);
+
+
 --
 -- Name: reset_tokens; Type: TABLE; Schema: public; Owner: -
 --
@@ -612,6 +862,21 @@ CREATE TABLE public.space_members (
 );
 
 
+--
+-- Name: space_templates; Type: TABLE; Schema: public; Owner: -
+--
+
+CREATE TABLE public.space_templates (
+    space_id uuid NOT NULL,
+    name text NOT NULL,
+    description text DEFAULT ''::text NOT NULL,
+    is_public boolean DEFAULT false NOT NULL,
+    created timestamp with time zone DEFAULT now() NOT NULL,
+    modified timestamp with time zone DEFAULT now() NOT NULL,
+    CONSTRAINT space_template_name_valid CHECK (((length(name) >= 1) AND (length(name) <= 32)))
+);
+
+
 --
 -- Name: spaces; Type: TABLE; Schema: public; Owner: -
 --
@@ -642,7 +907,12 @@ CREATE TABLE public.spaces (
 
 CREATE TABLE public.spaces_extension (
     space_id uuid NOT NULL,
-    settings jsonb DEFAULT '{}'::jsonb NOT NULL
+    settings jsonb DEFAULT '{}'::jsonb NOT NULL,
+    content_rules jsonb DEFAULT '[]'::jsonb NOT NULL,
+    note_revision_keep_latest integer,
+    note_revision_keep_days integer,
+    CONSTRAINT space_note_revision_keep_days_valid CHECK ((note_revision_keep_days > 0)),
+    CONSTRAINT space_note_revision_keep_latest_valid CHECK ((note_revision_keep_latest > 0))
 );
 
 
@@ -696,6 +966,46 @@ ALTER TABLE ONLY public._sqlx_migrations
     ADD CONSTRAINT _sqlx_migrations_pkey PRIMARY KEY (version);
 
 
+--
+-- Name: abuse_reports abuse_reports_pkey; Type: CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.abuse_reports
+    ADD CONSTRAINT abuse_reports_pkey PRIMARY KEY (id);
+
+
+--
+-- Name: admin_audit_log admin_audit_log_pkey; Type: CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.admin_audit_log
+    ADD CONSTRAINT admin_audit_log_pkey PRIMARY KEY (id);
+
+
+--
+-- Name: asset_folders asset_folder_name_unique; Type: CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.asset_folders
+    ADD CONSTRAINT asset_folder_name_unique UNIQUE NULLS NOT DISTINCT (space_id, parent_id, name);
+
+
+--
+-- Name: asset_folders asset_folder_space_id_unique; Type: CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.asset_folders
+    ADD CONSTRAINT asset_folder_space_id_unique UNIQUE (space_id, id);
+
+
+--
+-- Name: asset_folders asset_folders_pkey; Type: CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.asset_folders
+    ADD CONSTRAINT asset_folders_pkey PRIMARY KEY (id);
+
+
 --
 -- Name: assets asset_space_id_unique; Type: CONSTRAINT; Schema: public; Owner: -
 --
@@ -712,6 +1022,14 @@ ALTER TABLE ONLY public.assets
     ADD CONSTRAINT assets_pkey PRIMARY KEY (id);
 
 
+--
+-- Name: channel_playback channel_playback_pkey; Type: CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.channel_playback
+    ADD CONSTRAINT channel_playback_pkey PRIMARY KEY (channel_id);
+
+
 --
 -- Name: channels channels_pkey; Type: CONSTRAINT; Schema: public; Owner: -
 --
@@ -768,6 +1086,30 @@ ALTER TABLE ONLY public.characters
     ADD CONSTRAINT characters_pkey PRIMARY KEY (id);
 
 
+--
+-- Name: component_types component_types_pkey; Type: CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.component_types
+    ADD CONSTRAINT component_types_pkey PRIMARY KEY (space_id, component_type, schema_version);
+
+
+--
+-- Name: encounter_combatants encounter_combatants_pkey; Type: CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.encounter_combatants
+    ADD CONSTRAINT encounter_combatants_pkey PRIMARY KEY (id);
+
+
+--
+-- Name: encounters encounters_pkey; Type: CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.encounters
+    ADD CONSTRAINT encounters_pkey PRIMARY KEY (channel_id);
+
+
 --
 -- Name: entries entries_pkey; Type: CONSTRAINT; Schema: public; Owner: -
 --
@@ -800,6 +1142,14 @@ ALTER TABLE ONLY public.entry_components_asset
     ADD CONSTRAINT entry_components_asset_pkey PRIMARY KEY (entry_id, component_type);
 
 
+--
+-- Name: entry_components_formula entry_components_formula_pkey; Type: CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.entry_components_formula
+    ADD CONSTRAINT entry_components_formula_pkey PRIMARY KEY (entry_id, component_type);
+
+
 --
 -- Name: entry_components_json entry_components_json_pkey; Type: CONSTRAINT; Schema: public; Owner: -
 --
@@ -856,6 +1206,22 @@ ALTER TABLE ONLY public.entries
     ADD CONSTRAINT entry_scope_position_unique UNIQUE (scope_id, pos) DEFERRABLE;
 
 
+--
+-- Name: entry_templates entry_template_name_unique; Type: CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.entry_templates
+    ADD CONSTRAINT entry_template_name_unique UNIQUE (space_id, name);
+
+
+--
+-- Name: entry_templates entry_templates_pkey; Type: CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.entry_templates
+    ADD CONSTRAINT entry_templates_pkey PRIMARY KEY (id);
+
+
 --
 -- Name: events events_pkey; Type: CONSTRAINT; Schema: public; Owner: -
 --
@@ -888,6 +1254,14 @@ ALTER TABLE ONLY public.note_content_revisions
     ADD CONSTRAINT note_content_revisions_pkey PRIMARY KEY (note_id, revision);
 
 
+--
+-- Name: note_links note_links_pkey; Type: CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.note_links
+    ADD CONSTRAINT note_links_pkey PRIMARY KEY (note_id, "position");
+
+
 --
 -- Name: notes notes_pkey; Type: CONSTRAINT; Schema: public; Owner: -
 --
@@ -912,6 +1286,22 @@ ALTER TABLE ONLY public.proxies
     ADD CONSTRAINT proxies_pkey PRIMARY KEY (name);
 
 
+--
+-- Name: random_tables random_table_key_unique; Type: CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.random_tables
+    ADD CONSTRAINT random_table_key_unique UNIQUE (space_id, key);
+
+
+--
+-- Name: random_tables random_tables_pkey; Type: CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.random_tables
+    ADD CONSTRAINT random_tables_pkey PRIMARY KEY (id);
+
+
 --
 -- Name: reset_tokens reset_tokens_pkey; Type: CONSTRAINT; Schema: public; Owner: -
 --
@@ -952,6 +1342,14 @@ ALTER TABLE ONLY public.spaces
     ADD CONSTRAINT space_scope_id_unique UNIQUE (scope_id);
 
 
+--
+-- Name: space_templates space_templates_pkey; Type: CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.space_templates
+    ADD CONSTRAINT space_templates_pkey PRIMARY KEY (space_id);
+
+
 --
 -- Name: spaces_extension spaces_extension_pkey; Type: CONSTRAINT; Schema: public; Owner: -
 --
@@ -1032,6 +1430,48 @@ ALTER TABLE ONLY public.users
     ADD CONSTRAINT users_username_key UNIQUE (username);
 
 
+--
+-- Name: abuse_report_pending_unique; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE UNIQUE INDEX abuse_report_pending_unique ON public.abuse_reports USING btree (reporter_id, target_kind, target_id) WHERE (status = ANY (ARRAY['Open'::public.report_status, 'Escalated'::public.report_status]));
+
+
+--
+-- Name: abuse_report_queue_index; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX abuse_report_queue_index ON public.abuse_reports USING btree (status, id DESC);
+
+
+--
+-- Name: abuse_report_space_queue_index; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX abuse_report_space_queue_index ON public.abuse_reports USING btree (space_id, status, id DESC) WHERE (space_id IS NOT NULL);
+
+
+--
+-- Name: admin_audit_log_created_index; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX admin_audit_log_created_index ON public.admin_audit_log USING btree (created DESC);
+
+
+--
+-- Name: admin_audit_log_target_index; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX admin_audit_log_target_index ON public.admin_audit_log USING btree (target_id, created DESC) WHERE (target_id IS NOT NULL);
+
+
+--
+-- Name: asset_media_index; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX asset_media_index ON public.assets USING btree (media_id);
+
+
 --
 -- Name: asset_space_created_index; Type: INDEX; Schema: public; Owner: -
 --
@@ -1039,6 +1479,20 @@ ALTER TABLE ONLY public.users
 CREATE INDEX asset_space_created_index ON public.assets USING btree (space_id, created DESC, id DESC);
 
 
+--
+-- Name: asset_space_folder_index; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX asset_space_folder_index ON public.assets USING btree (space_id, folder_id);
+
+
+--
+-- Name: asset_tags_index; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX asset_tags_index ON public.assets USING gin (tags);
+
+
 --
 -- Name: channel_member_character_index; Type: INDEX; Schema: public; Owner: -
 --
@@ -1053,6 +1507,13 @@ CREATE INDEX channel_member_character_index ON public.channel_members USING btre
 CREATE INDEX channel_members_channel_id_is_joined_index ON public.channel_members USING btree (channel_id, is_joined);
 
 
+--
+-- Name: channel_playback_asset_index; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX channel_playback_asset_index ON public.channel_playback USING btree (asset_id);
+
+
 --
 -- Name: character_identifier_character_index; Type: INDEX; Schema: public; Owner: -
 --
@@ -1074,6 +1535,13 @@ CREATE UNIQUE INDEX character_identifier_one_primary ON public.character_identif
 CREATE INDEX character_space_modified_index ON public.characters USING btree (space_id, modified DESC);
 
 
+--
+-- Name: encounter_combatants_channel; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX encounter_combatants_channel ON public.encounter_combatants USING btree (channel_id);
+
+
 --
 -- Name: entry_component_history_entry_effect_index; Type: INDEX; Schema: public; Owner: -
 --
@@ -1151,6 +1619,27 @@ CREATE INDEX entry_reference_note_index ON public.entries USING btree (reference
 CREATE INDEX entry_scope_position_index ON public.entries USING btree (scope_id, pos, id);
 
 
+--
+-- Name: entry_tags_index; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX entry_tags_index ON public.entries USING gin (tags);
+
+
+--
+-- Name: media_hash_index; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX media_hash_index ON public.media USING btree (hash, size) WHERE (hash <> ''::text);
+
+
+--
+-- Name: media_uploader_index; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX media_uploader_index ON public.media USING btree (uploader_id);
+
+
 --
 -- Name: message_character_index; Type: INDEX; Schema: public; Owner: -
 --
@@ -1158,6 +1647,13 @@ CREATE INDEX entry_scope_position_index ON public.entries USING btree (scope_id,
 CREATE INDEX message_character_index ON public.messages USING btree (character_id, created DESC) WHERE (character_id IS NOT NULL);
 
 
+--
+-- Name: message_media_index; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX message_media_index ON public.messages USING btree (media_id) WHERE (media_id IS NOT NULL);
+
+
 --
 -- Name: message_portrait_index; Type: INDEX; Schema: public; Owner: -
 --
@@ -1172,6 +1668,34 @@ CREATE INDEX message_portrait_index ON public.messages USING btree (portrait_id,
 CREATE INDEX message_tags ON public.messages USING gin (tags);
 
 
+--
+-- Name: note_link_target_character_index; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX note_link_target_character_index ON public.note_links USING btree (target_character_id) WHERE (target_character_id IS NOT NULL);
+
+
+--
+-- Name: note_link_target_entry_index; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX note_link_target_entry_index ON public.note_links USING btree (target_entry_id) WHERE (target_entry_id IS NOT NULL);
+
+
+--
+-- Name: note_link_target_note_index; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX note_link_target_note_index ON public.note_links USING btree (target_note_id) WHERE (target_note_id IS NOT NULL);
+
+
+--
+-- Name: note_link_unresolved_index; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX note_link_unresolved_index ON public.note_links USING btree (space_id, target) WHERE ((target_note_id IS NULL) AND (target_entry_id IS NULL) AND (target_character_id IS NULL));
+
+
 --
 -- Name: notes_space_modified_index; Type: INDEX; Schema: public; Owner: -
 --
@@ -1207,6 +1731,52 @@ CREATE INDEX scope_owner_index ON public.scopes USING btree (owner_id, id) WHERE
 CREATE INDEX space_members_space_id_index ON public.space_members USING btree (space_id);
 
 
+--
+-- Name: space_template_public_index; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX space_template_public_index ON public.space_templates USING btree (created DESC) WHERE (is_public = true);
+
+
+--
+-- Name: user_avatar_index; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX user_avatar_index ON public.users USING btree (avatar_id) WHERE (avatar_id IS NOT NULL);
+
+
+--
+-- Name: abuse_reports abuse_report_handler; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.abuse_reports
+    ADD CONSTRAINT abuse_report_handler FOREIGN KEY (handler_id) REFERENCES public.users(id) ON DELETE SET NULL;
+
+
+--
+-- Name: abuse_reports abuse_report_reporter; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.abuse_reports
+    ADD CONSTRAINT abuse_report_reporter FOREIGN KEY (reporter_id) REFERENCES public.users(id) ON DELETE SET NULL;
+
+
+--
+-- Name: abuse_reports abuse_report_space; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.abuse_reports
+    ADD CONSTRAINT abuse_report_space FOREIGN KEY (space_id) REFERENCES public.spaces(id) ON DELETE SET NULL;
+
+
+--
+-- Name: admin_audit_log admin_audit_log_actor; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.admin_audit_log
+    ADD CONSTRAINT admin_audit_log_actor FOREIGN KEY (actor_id) REFERENCES public.users(id) ON DELETE SET NULL;
+
+
 --
 -- Name: assets asset_creator; Type: FK CONSTRAINT; Schema: public; Owner: -
 --
@@ -1215,6 +1785,38 @@ ALTER TABLE ONLY public.assets
     ADD CONSTRAINT asset_creator FOREIGN KEY (creator_id) REFERENCES public.users(id) ON DELETE SET NULL;
 
 
+--
+-- Name: assets asset_folder; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.assets
+    ADD CONSTRAINT asset_folder FOREIGN KEY (space_id, folder_id) REFERENCES public.asset_folders(space_id, id) ON DELETE SET NULL (folder_id);
+
+
+--
+-- Name: asset_folders asset_folder_creator; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.asset_folders
+    ADD CONSTRAINT asset_folder_creator FOREIGN KEY (creator_id) REFERENCES public.users(id) ON DELETE SET NULL;
+
+
+--
+-- Name: asset_folders asset_folder_parent; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.asset_folders
+    ADD CONSTRAINT asset_folder_parent FOREIGN KEY (space_id, parent_id) REFERENCES public.asset_folders(space_id, id);
+
+
+--
+-- Name: asset_folders asset_folder_space; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.asset_folders
+    ADD CONSTRAINT asset_folder_space FOREIGN KEY (space_id) REFERENCES public.spaces(id) ON DELETE CASCADE;
+
+
 --
 -- Name: assets asset_media; Type: FK CONSTRAINT; Schema: public; Owner: -
 --
@@ -1255,6 +1857,38 @@ ALTER TABLE ONLY public.channel_members
     ADD CONSTRAINT channel_member_user FOREIGN KEY (user_id) REFERENCES public.users(id) ON DELETE CASCADE;
 
 
+--
+-- Name: channel_playback channel_playback_asset; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.channel_playback
+    ADD CONSTRAINT channel_playback_asset FOREIGN KEY (space_id, asset_id) REFERENCES public.assets(space_id, id) ON DELETE CASCADE;
+
+
+--
+-- Name: channel_playback channel_playback_channel; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.channel_playback
+    ADD CONSTRAINT channel_playback_channel FOREIGN KEY (channel_id) REFERENCES public.channels(id) ON DELETE CASCADE;
+
+
+--
+-- Name: channel_playback channel_playback_space; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.channel_playback
+    ADD CONSTRAINT channel_playback_space FOREIGN KEY (space_id) REFERENCES public.spaces(id) ON DELETE CASCADE;
+
+
+--
+-- Name: channel_playback channel_playback_updated_by; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.channel_playback
+    ADD CONSTRAINT channel_playback_updated_by FOREIGN KEY (updated_by) REFERENCES public.users(id) ON DELETE SET NULL;
+
+
 --
 -- Name: channels channel_space; Type: FK CONSTRAINT; Schema: public; Owner: -
 --
@@ -1303,6 +1937,70 @@ ALTER TABLE ONLY public.characters
     ADD CONSTRAINT character_space FOREIGN KEY (space_id) REFERENCES public.spaces(id) ON DELETE CASCADE;
 
 
+--
+-- Name: component_types component_type_space; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.component_types
+    ADD CONSTRAINT component_type_space FOREIGN KEY (space_id) REFERENCES public.spaces(id) ON DELETE CASCADE;
+
+
+--
+-- Name: encounters encounter_channel; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.encounters
+    ADD CONSTRAINT encounter_channel FOREIGN KEY (channel_id) REFERENCES public.channels(id) ON DELETE CASCADE;
+
+
+--
+-- Name: encounter_combatants encounter_combatant_added_by; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.encounter_combatants
+    ADD CONSTRAINT encounter_combatant_added_by FOREIGN KEY (added_by) REFERENCES public.users(id) ON DELETE SET NULL;
+
+
+--
+-- Name: encounter_combatants encounter_combatant_character; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.encounter_combatants
+    ADD CONSTRAINT encounter_combatant_character FOREIGN KEY (character_id) REFERENCES public.characters(id) ON DELETE CASCADE;
+
+
+--
+-- Name: encounter_combatants encounter_combatant_encounter; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.encounter_combatants
+    ADD CONSTRAINT encounter_combatant_encounter FOREIGN KEY (channel_id) REFERENCES public.encounters(channel_id) ON DELETE CASCADE;
+
+
+--
+-- Name: encounter_combatants encounter_combatant_entry; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.encounter_combatants
+    ADD CONSTRAINT encounter_combatant_entry FOREIGN KEY (entry_id) REFERENCES public.entries(id) ON DELETE CASCADE;
+
+
+--
+-- Name: encounter_combatants encounter_combatant_initiative_message; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.encounter_combatants
+    ADD CONSTRAINT encounter_combatant_initiative_message FOREIGN KEY (initiative_message_id) REFERENCES public.messages(id) ON DELETE SET NULL;
+
+
+--
+-- Name: encounters encounter_space; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.encounters
+    ADD CONSTRAINT encounter_space FOREIGN KEY (space_id) REFERENCES public.spaces(id) ON DELETE CASCADE;
+
+
 --
 -- Name: entry_components entry_component_entry; Type: FK CONSTRAINT; Schema: public; Owner: -
 --
@@ -1351,6 +2049,14 @@ ALTER TABLE ONLY public.entry_components_asset
     ADD CONSTRAINT entry_components_asset_scope FOREIGN KEY (space_id, scope_id) REFERENCES public.scopes(space_id, id) ON DELETE CASCADE;
 
 
+--
+-- Name: entry_components_formula entry_components_formula_parent; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.entry_components_formula
+    ADD CONSTRAINT entry_components_formula_parent FOREIGN KEY (entry_id, component_type, payload_type) REFERENCES public.entry_components(entry_id, component_type, payload_type) ON DELETE CASCADE;
+
+
 --
 -- Name: entry_components_json entry_components_json_parent; Type: FK CONSTRAINT; Schema: public; Owner: -
 --
@@ -1375,6 +2081,14 @@ ALTER TABLE ONLY public.entry_effects
     ADD CONSTRAINT entry_effect_operator FOREIGN KEY (operator_id) REFERENCES public.users(id) ON DELETE SET NULL;
 
 
+--
+-- Name: entry_effects entry_effect_reverted_by; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.entry_effects
+    ADD CONSTRAINT entry_effect_reverted_by FOREIGN KEY (reverted_by) REFERENCES public.entry_effects(id) ON DELETE SET NULL;
+
+
 --
 -- Name: entry_effects entry_effect_scope; Type: FK CONSTRAINT; Schema: public; Owner: -
 --
@@ -1415,6 +2129,22 @@ ALTER TABLE ONLY public.entries
     ADD CONSTRAINT entry_scope FOREIGN KEY (scope_id) REFERENCES public.scopes(id) ON DELETE CASCADE;
 
 
+--
+-- Name: entry_templates entry_template_reference_note; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.entry_templates
+    ADD CONSTRAINT entry_template_reference_note FOREIGN KEY (reference_note_id) REFERENCES public.notes(id) ON DELETE SET NULL;
+
+
+--
+-- Name: entry_templates entry_template_space; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.entry_templates
+    ADD CONSTRAINT entry_template_space FOREIGN KEY (space_id) REFERENCES public.spaces(id) ON DELETE CASCADE;
+
+
 --
 -- Name: events event_channel; Type: FK CONSTRAINT; Schema: public; Owner: -
 --
@@ -1503,6 +2233,46 @@ ALTER TABLE ONLY public.note_content_revisions
     ADD CONSTRAINT note_content_revision_operator FOREIGN KEY (operator_id) REFERENCES public.users(id) ON DELETE SET NULL;
 
 
+--
+-- Name: note_links note_link_note; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.note_links
+    ADD CONSTRAINT note_link_note FOREIGN KEY (note_id) REFERENCES public.notes(id) ON DELETE CASCADE;
+
+
+--
+-- Name: note_links note_link_space; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.note_links
+    ADD CONSTRAINT note_link_space FOREIGN KEY (space_id) REFERENCES public.spaces(id) ON DELETE CASCADE;
+
+
+--
+-- Name: note_links note_link_target_character; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.note_links
+    ADD CONSTRAINT note_link_target_character FOREIGN KEY (target_character_id) REFERENCES public.characters(id) ON DELETE SET NULL;
+
+
+--
+-- Name: note_links note_link_target_entry; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.note_links
+    ADD CONSTRAINT note_link_target_entry FOREIGN KEY (target_entry_id) REFERENCES public.entries(id) ON DELETE SET NULL;
+
+
+--
+-- Name: note_links note_link_target_note; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.note_links
+    ADD CONSTRAINT note_link_target_note FOREIGN KEY (target_note_id) REFERENCES public.notes(id) ON DELETE SET NULL;
+
+
 --
 -- Name: notes notes_creator; Type: FK CONSTRAINT; Schema: public; Owner: -
 --
@@ -1527,6 +2297,14 @@ ALTER TABLE ONLY public.reset_tokens
     ADD CONSTRAINT password_reset_user FOREIGN KEY (user_id) REFERENCES public.users(id) ON DELETE CASCADE;
 
 
+--
+-- Name: random_tables random_table_space; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.random_tables
+    ADD CONSTRAINT random_table_space FOREIGN KEY (space_id) REFERENCES public.spaces(id) ON DELETE CASCADE;
+
+
 --
 -- Name: restrained_members restrain_operator; Type: FK CONSTRAINT; Schema: public; Owner: -
 --
@@ -1615,6 +2393,14 @@ ALTER TABLE ONLY public.spaces
     ADD CONSTRAINT space_scope FOREIGN KEY (id, scope_id) REFERENCES public.scopes(space_id, id) DEFERRABLE INITIALLY DEFERRED;
 
 
+--
+-- Name: space_templates space_template_space; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.space_templates
+    ADD CONSTRAINT space_template_space FOREIGN KEY (space_id) REFERENCES public.spaces(id) ON DELETE CASCADE;
+
+
 --
 -- Name: users user_avatar; Type: FK CONSTRAINT; Schema: public; Owner: -
 --
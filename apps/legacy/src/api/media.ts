@@ -1,3 +1,3 @@
-import type { Media, PreSign, PreSignResult } from '@boluo/api';
+import type { CompleteUpload, Media, PreSign, PreSignResult } from '@boluo/api';
 
-export type { Media, PreSign, PreSignResult };
+export type { CompleteUpload, Media, PreSign, PreSignResult };
@@ -28,7 +28,7 @@ import {
   type JoinChannel,
 } from './channels';
 import { type AppError, FETCH_FAIL, notJson, UNAUTHENTICATED } from './error';
-import { type Media, type PreSign, type PreSignResult } from './media';
+import { type CompleteUpload, type Media, type PreSign, type PreSignResult } from './media';
 import {
   type ByChannel,
   type EditMessage,
@@ -246,6 +246,11 @@ export function post(
   payload: {},
   query: PreSign,
 ): Promise<AppResult<PreSignResult>>;
+export function post(
+  path: '/media/complete',
+  payload: {},
+  query: CompleteUpload,
+): Promise<AppResult<Media>>;
 export function post<T, U extends object = object, Q extends object = {}>(
   path: string,
   payload: U,
@@ -356,10 +361,14 @@ export async function uploadWithPresigned(
   filename: string,
   mimeType: string,
 ): Promise<AppResult<{ mediaId: string }>> {
+  const digest = await crypto.subtle.digest('SHA-256', await file.arrayBuffer());
+  const bytes = Array.from(new Uint8Array(digest));
+  const hash = bytes.map((byte) => byte.toString(16).padStart(2, '0')).join('');
   const presignQuery: PreSign = {
     filename,
     mimeType,
     size: file.size,
+    hash,
   };
 
   const presignResult = await post('/media/presigned', {}, presignQuery);
@@ -367,7 +376,10 @@ export async function uploadWithPresigned(
     return new Err(presignResult.value);
   }
 
-  const { url, mediaId } = presignResult.value;
+  const { url, mediaId, duplicate } = presignResult.value;
+  if (duplicate) {
+    return new Ok({ mediaId });
+  }
   try {
     const uploadResponse = await fetch(url, {
       method: 'PUT',
@@ -385,7 +397,11 @@ export async function uploadWithPresigned(
       });
     }
 
-    return new Ok({ mediaId });
+    const completeResult = await post('/media/complete', {}, { mediaId, hash });
+    if (!completeResult.isOk) {
+      return new Err(completeResult.value);
+    }
+    return new Ok({ mediaId: completeResult.value.id });
   } catch (e) {
     return new Err({
       code: FETCH_FAIL,
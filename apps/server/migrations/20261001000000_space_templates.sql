@@ -0,0 +1,17 @@
+CREATE TABLE space_templates (
+    space_id uuid PRIMARY KEY
+        CONSTRAINT space_template_space
+        REFERENCES spaces (id)
+        ON DELETE CASCADE,
+    name text NOT NULL
+        CONSTRAINT space_template_name_valid
+        CHECK (length(name) BETWEEN 1 AND 32),
+    description text NOT NULL DEFAULT '',
+    is_public boolean NOT NULL DEFAULT FALSE,
+    created timestamptz NOT NULL DEFAULT now(),
+    modified timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE INDEX space_template_public_index
+    ON space_templates (created DESC)
+    WHERE is_public = TRUE;
@@ -0,0 +1,18 @@
+CREATE TABLE admin_audit_log (
+    id uuid NOT NULL DEFAULT uuidv7() PRIMARY KEY,
+    actor_id uuid
+        CONSTRAINT admin_audit_log_actor
+        REFERENCES users (id)
+        ON DELETE SET NULL,
+    action text NOT NULL,
+    target_id uuid,
+    detail jsonb NOT NULL DEFAULT '{}',
+    created timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE INDEX admin_audit_log_created_index
+    ON admin_audit_log (created DESC);
+
+CREATE INDEX admin_audit_log_target_index
+    ON admin_audit_log (target_id, created DESC)
+    WHERE target_id IS NOT NULL;
@@ -0,0 +1,56 @@
+CREATE TYPE report_target_kind AS ENUM ('Message', 'Space', 'User', 'Media');
+
+CREATE TYPE report_reason AS ENUM (
+    'Spam',
+    'Harassment',
+    'HateSpeech',
+    'SexualContent',
+    'Violence',
+    'IllegalContent',
+    'Impersonation',
+    'Other'
+);
+
+CREATE TYPE report_status AS ENUM ('Open', 'Escalated', 'Resolved', 'Dismissed');
+
+CREATE TABLE abuse_reports (
+    id uuid NOT NULL DEFAULT uuidv7() PRIMARY KEY,
+    reporter_id uuid
+        CONSTRAINT abuse_report_reporter
+        REFERENCES users (id)
+        ON DELETE SET NULL,
+    target_kind report_target_kind NOT NULL,
+    target_id uuid NOT NULL,
+    -- The space whose admins triage the report, `NULL` for site admins only.
+    space_id uuid
+        CONSTRAINT abuse_report_space
+        REFERENCES spaces (id)
+        ON DELETE SET NULL,
+    reason report_reason NOT NULL,
+    comment text NOT NULL DEFAULT ''
+        CONSTRAINT abuse_report_comment_valid
+        CHECK (length(comment) <= 1024),
+    -- A copy of the reported content at the time of the report.
+    snapshot jsonb NOT NULL,
+    status report_status NOT NULL DEFAULT 'Open',
+    handler_id uuid
+        CONSTRAINT abuse_report_handler
+        REFERENCES users (id)
+        ON DELETE SET NULL,
+    resolution_note text NOT NULL DEFAULT ''
+        CONSTRAINT abuse_report_resolution_note_valid
+        CHECK (length(resolution_note) <= 1024),
+    created timestamptz NOT NULL DEFAULT now(),
+    handled timestamptz
+);
+
+CREATE UNIQUE INDEX abuse_report_pending_unique
+    ON abuse_reports (reporter_id, target_kind, target_id)
+    WHERE status IN ('Open', 'Escalated');
+
+CREATE INDEX abuse_report_space_queue_index
+    ON abuse_reports (space_id, status, id DESC)
+    WHERE space_id IS NOT NULL;
+
+CREATE INDEX abuse_report_queue_index
+    ON abuse_reports (status, id DESC);
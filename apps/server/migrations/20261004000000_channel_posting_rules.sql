@@ -0,0 +1,10 @@
+ALTER TABLE channels
+    ADD COLUMN slow_mode_seconds integer NOT NULL DEFAULT 0
+        CONSTRAINT channel_slow_mode_valid
+        CHECK (slow_mode_seconds BETWEEN 0 AND 21600),
+    ADD COLUMN posting_policy text NOT NULL DEFAULT 'everyone'
+        CONSTRAINT channel_posting_policy_valid
+        CHECK (posting_policy IN ('everyone', 'masters_only', 'masters_only_in_game')),
+    ADD COLUMN max_message_length integer NOT NULL DEFAULT 0
+        CONSTRAINT channel_max_message_length_valid
+        CHECK (max_message_length BETWEEN 0 AND 32768);
@@ -0,0 +1,2 @@
+ALTER TABLE spaces_extension
+    ADD COLUMN content_rules jsonb NOT NULL DEFAULT '[]';
@@ -0,0 +1,14 @@
+CREATE TABLE component_types (
+    space_id uuid NOT NULL
+        CONSTRAINT component_type_space
+        REFERENCES spaces (id)
+        ON DELETE CASCADE,
+    component_type text NOT NULL,
+    schema_version integer NOT NULL
+        CONSTRAINT component_type_schema_version_valid
+        CHECK (schema_version > 0),
+    schema jsonb NOT NULL,
+    migration jsonb NOT NULL DEFAULT '[]',
+    created timestamptz NOT NULL DEFAULT now(),
+    PRIMARY KEY (space_id, component_type, schema_version)
+);
@@ -0,0 +1,5 @@
+ALTER TABLE entry_effects
+    ADD COLUMN reverted_by uuid
+        CONSTRAINT entry_effect_reverted_by
+        REFERENCES entry_effects (id)
+        ON DELETE SET NULL;
@@ -0,0 +1 @@
+CREATE INDEX entry_tags_index ON entries USING gin (tags);
@@ -0,0 +1,2 @@
+-- A new enum value can not be used in the transaction that adds it.
+ALTER TYPE entry_component_payload_type ADD VALUE 'Formula';
@@ -0,0 +1,15 @@
+CREATE TABLE entry_components_formula (
+    entry_id uuid NOT NULL,
+    component_type text NOT NULL,
+    payload_type entry_component_payload_type NOT NULL DEFAULT 'Formula'
+        CONSTRAINT entry_components_formula_payload_type_valid
+        CHECK (payload_type = 'Formula'),
+    formula text NOT NULL
+        CONSTRAINT entry_components_formula_length_valid
+        CHECK (length(formula) BETWEEN 1 AND 1024),
+    PRIMARY KEY (entry_id, component_type),
+    CONSTRAINT entry_components_formula_parent
+        FOREIGN KEY (entry_id, component_type, payload_type)
+        REFERENCES entry_components (entry_id, component_type, payload_type)
+        ON DELETE CASCADE
+);
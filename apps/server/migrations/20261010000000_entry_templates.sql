@@ -0,0 +1,22 @@
+CREATE TABLE entry_templates (
+    id uuid PRIMARY KEY DEFAULT uuidv7(),
+    space_id uuid NOT NULL
+        CONSTRAINT entry_template_space
+        REFERENCES spaces (id)
+        ON DELETE CASCADE,
+    name text NOT NULL
+        CONSTRAINT entry_template_name_valid
+        CHECK (length(name) BETWEEN 1 AND 64),
+    key_pattern text NOT NULL,
+    display_name text NOT NULL DEFAULT '',
+    tags text[] NOT NULL DEFAULT '{}',
+    reference_note_id uuid
+        CONSTRAINT entry_template_reference_note
+        REFERENCES notes (id)
+        ON DELETE SET NULL,
+    components jsonb NOT NULL DEFAULT '{}',
+    version uuid NOT NULL DEFAULT uuidv7(),
+    created timestamptz NOT NULL DEFAULT now(),
+    modified timestamptz NOT NULL DEFAULT now(),
+    CONSTRAINT entry_template_name_unique UNIQUE (space_id, name)
+);
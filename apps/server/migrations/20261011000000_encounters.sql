@@ -0,0 +1,54 @@
+CREATE TABLE encounters (
+    channel_id uuid PRIMARY KEY
+        CONSTRAINT encounter_channel
+        REFERENCES channels (id)
+        ON DELETE CASCADE,
+    space_id uuid NOT NULL
+        CONSTRAINT encounter_space
+        REFERENCES spaces (id)
+        ON DELETE CASCADE,
+    round integer NOT NULL DEFAULT 1
+        CONSTRAINT encounter_round_positive
+        CHECK (round >= 1),
+    current_combatant_id uuid,
+    announce_turns boolean NOT NULL DEFAULT false,
+    duration_component_type text,
+    duration_path text NOT NULL DEFAULT '',
+    version uuid NOT NULL DEFAULT uuidv7(),
+    created timestamptz NOT NULL DEFAULT now(),
+    modified timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE TABLE encounter_combatants (
+    id uuid PRIMARY KEY DEFAULT uuidv7(),
+    channel_id uuid NOT NULL
+        CONSTRAINT encounter_combatant_encounter
+        REFERENCES encounters (channel_id)
+        ON DELETE CASCADE,
+    name text NOT NULL,
+    character_id uuid
+        CONSTRAINT encounter_combatant_character
+        REFERENCES characters (id)
+        ON DELETE CASCADE,
+    entry_id uuid
+        CONSTRAINT encounter_combatant_entry
+        REFERENCES entries (id)
+        ON DELETE CASCADE,
+    entry_scope_id uuid,
+    initiative double precision NOT NULL,
+    initiative_message_id uuid
+        CONSTRAINT encounter_combatant_initiative_message
+        REFERENCES messages (id)
+        ON DELETE SET NULL,
+    added_by uuid
+        CONSTRAINT encounter_combatant_added_by
+        REFERENCES users (id)
+        ON DELETE SET NULL,
+    created timestamptz NOT NULL DEFAULT now(),
+    CONSTRAINT encounter_combatant_single_source
+        CHECK (character_id IS NULL OR entry_id IS NULL),
+    CONSTRAINT encounter_combatant_entry_scope
+        CHECK ((entry_id IS NULL) = (entry_scope_id IS NULL))
+);
+
+CREATE INDEX encounter_combatants_channel ON encounter_combatants (channel_id);
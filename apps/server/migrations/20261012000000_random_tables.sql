@@ -0,0 +1,17 @@
+CREATE TABLE random_tables (
+    id uuid PRIMARY KEY DEFAULT uuidv7(),
+    space_id uuid NOT NULL
+        CONSTRAINT random_table_space
+        REFERENCES spaces (id)
+        ON DELETE CASCADE,
+    key text NOT NULL,
+    name text NOT NULL
+        CONSTRAINT random_table_name_valid
+        CHECK (length(name) BETWEEN 1 AND 64),
+    description text NOT NULL DEFAULT '',
+    rows jsonb NOT NULL DEFAULT '[]',
+    version uuid NOT NULL DEFAULT uuidv7(),
+    created timestamptz NOT NULL DEFAULT now(),
+    modified timestamptz NOT NULL DEFAULT now(),
+    CONSTRAINT random_table_key_unique UNIQUE (space_id, key)
+);
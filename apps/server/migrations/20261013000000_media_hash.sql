@@ -0,0 +1,6 @@
+CREATE INDEX media_hash_index ON media (hash, size) WHERE hash <> '';
+
+-- Whether the server hashed the file as it stored it. Only those files are shared between users,
+-- a file uploaded to a presigned URL can be replaced until the URL expires.
+ALTER TABLE media
+    ADD COLUMN hash_trusted boolean NOT NULL DEFAULT false;
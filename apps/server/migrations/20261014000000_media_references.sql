@@ -0,0 +1,12 @@
+-- Looked up by the garbage collection of media.
+CREATE INDEX message_media_index ON messages (media_id) WHERE media_id IS NOT NULL;
+
+CREATE INDEX user_avatar_index ON users (avatar_id) WHERE avatar_id IS NOT NULL;
+
+CREATE INDEX asset_media_index ON assets (media_id);
+
+CREATE INDEX media_uploader_index ON media (uploader_id);
+
+-- Reusing media for a duplicate upload keeps it from the garbage collection for a while.
+ALTER TABLE media
+    ADD COLUMN last_used timestamptz NOT NULL DEFAULT now();
@@ -0,0 +1,36 @@
+CREATE TABLE asset_folders (
+    id uuid NOT NULL DEFAULT uuidv7() PRIMARY KEY,
+    space_id uuid NOT NULL
+        CONSTRAINT asset_folder_space
+        REFERENCES spaces (id)
+        ON DELETE CASCADE,
+    parent_id uuid,
+    creator_id uuid
+        CONSTRAINT asset_folder_creator
+        REFERENCES users (id)
+        ON DELETE SET NULL,
+    name text NOT NULL,
+    created timestamptz NOT NULL DEFAULT now(),
+    CONSTRAINT asset_folder_name_valid CHECK (
+        length(name) BETWEEN 1 AND 100
+    ),
+    CONSTRAINT asset_folder_space_id_unique UNIQUE (space_id, id),
+    CONSTRAINT asset_folder_name_unique UNIQUE NULLS NOT DISTINCT (space_id, parent_id, name),
+    CONSTRAINT asset_folder_parent
+        FOREIGN KEY (space_id, parent_id)
+        REFERENCES asset_folders (space_id, id)
+);
+
+ALTER TABLE assets
+    ADD COLUMN folder_id uuid,
+    ADD COLUMN tags text[] NOT NULL DEFAULT '{}',
+    ADD CONSTRAINT asset_folder
+        FOREIGN KEY (space_id, folder_id)
+        REFERENCES asset_folders (space_id, id)
+        ON DELETE SET NULL (folder_id);
+
+CREATE INDEX asset_space_folder_index
+    ON assets (space_id, folder_id);
+
+CREATE INDEX asset_tags_index
+    ON assets USING gin (tags);
@@ -0,0 +1,37 @@
+ALTER TABLE media
+    ADD COLUMN duration_ms integer
+        CONSTRAINT media_duration_valid
+        CHECK (duration_ms >= 0);
+
+-- What is playing in a channel. The position is where the playback was at `updated`.
+CREATE TABLE channel_playback (
+    channel_id uuid PRIMARY KEY
+        CONSTRAINT channel_playback_channel
+        REFERENCES channels (id)
+        ON DELETE CASCADE,
+    space_id uuid NOT NULL
+        CONSTRAINT channel_playback_space
+        REFERENCES spaces (id)
+        ON DELETE CASCADE,
+    asset_id uuid NOT NULL,
+    playing boolean NOT NULL DEFAULT true,
+    position_ms bigint NOT NULL DEFAULT 0
+        CONSTRAINT channel_playback_position_valid
+        CHECK (position_ms >= 0),
+    looping boolean NOT NULL DEFAULT false,
+    volume real NOT NULL DEFAULT 1
+        CONSTRAINT channel_playback_volume_valid
+        CHECK (volume BETWEEN 0 AND 1),
+    updated_by uuid
+        CONSTRAINT channel_playback_updated_by
+        REFERENCES users (id)
+        ON DELETE SET NULL,
+    version uuid NOT NULL DEFAULT uuidv7(),
+    updated timestamptz NOT NULL DEFAULT now(),
+    CONSTRAINT channel_playback_asset
+        FOREIGN KEY (space_id, asset_id)
+        REFERENCES assets (space_id, id)
+        ON DELETE CASCADE
+);
+
+CREATE INDEX channel_playback_asset_index ON channel_playback (asset_id);
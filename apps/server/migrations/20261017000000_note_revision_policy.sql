@@ -0,0 +1,9 @@
+-- How long the content revisions of notes are kept. A revision is pruned once it is outside
+-- both limits, NULL means no limit. The latest revision is always kept.
+ALTER TABLE spaces_extension
+    ADD COLUMN note_revision_keep_latest integer
+        CONSTRAINT space_note_revision_keep_latest_valid
+        CHECK (note_revision_keep_latest > 0),
+    ADD COLUMN note_revision_keep_days integer
+        CONSTRAINT space_note_revision_keep_days_valid
+        CHECK (note_revision_keep_days > 0);
@@ -0,0 +1,48 @@
+-- The wiki links in the text of notes, for listing backlinks. A link is resolved to its target
+-- when the note is saved, so it follows renames. Links without a target are matched by name when
+-- they are read.
+CREATE TABLE note_links (
+    note_id uuid NOT NULL
+        CONSTRAINT note_link_note
+        REFERENCES notes (id)
+        ON DELETE CASCADE,
+    space_id uuid NOT NULL
+        CONSTRAINT note_link_space
+        REFERENCES spaces (id)
+        ON DELETE CASCADE,
+    position integer NOT NULL,
+    kind text
+        CONSTRAINT note_link_kind_valid
+        CHECK (kind IN ('NOTE', 'ENTRY', 'CHARACTER')),
+    -- Trimmed and in lowercase
+    target text NOT NULL,
+    target_note_id uuid
+        CONSTRAINT note_link_target_note
+        REFERENCES notes (id)
+        ON DELETE SET NULL,
+    target_entry_id uuid
+        CONSTRAINT note_link_target_entry
+        REFERENCES entries (id)
+        ON DELETE SET NULL,
+    target_character_id uuid
+        CONSTRAINT note_link_target_character
+        REFERENCES characters (id)
+        ON DELETE SET NULL,
+    PRIMARY KEY (note_id, position),
+    CONSTRAINT note_link_single_target CHECK (
+        num_nonnulls(target_note_id, target_entry_id, target_character_id) <= 1
+    )
+);
+
+CREATE INDEX note_link_target_note_index
+    ON note_links (target_note_id) WHERE target_note_id IS NOT NULL;
+
+CREATE INDEX note_link_target_entry_index
+    ON note_links (target_entry_id) WHERE target_entry_id IS NOT NULL;
+
+CREATE INDEX note_link_target_character_index
+    ON note_links (target_character_id) WHERE target_character_id IS NOT NULL;
+
+CREATE INDEX note_link_unresolved_index
+    ON note_links (space_id, target)
+    WHERE target_note_id IS NULL AND target_entry_id IS NULL AND target_character_id IS NULL;
@@ -0,0 +1,14 @@
+SELECT
+    id,
+    actor_id,
+    action,
+    target_id,
+    detail,
+    created
+FROM
+    admin_audit_log
+WHERE ($1::uuid IS NULL OR target_id = $1)
+    AND ($2::uuid IS NULL OR id < $2)
+ORDER BY
+    id DESC
+LIMIT $3;
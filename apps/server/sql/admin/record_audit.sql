@@ -0,0 +1,9 @@
+INSERT INTO admin_audit_log (actor_id, action, target_id, detail)
+    VALUES ($1, $2, $3, $4)
+RETURNING
+    id,
+    actor_id,
+    action,
+    target_id,
+    detail,
+    created;
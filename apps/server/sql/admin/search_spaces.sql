@@ -0,0 +1,11 @@
+SELECT
+    spaces AS "space!: Space"
+FROM
+    spaces
+WHERE ($1::text IS NULL
+    OR concat(name, ' ', description) ILIKE $1)
+    AND ($2::uuid IS NULL OR owner_id = $2)
+    AND ($3::boolean OR deleted = FALSE)
+ORDER BY
+    created DESC
+LIMIT $4 OFFSET $5;
@@ -0,0 +1,12 @@
+SELECT
+    users AS "user!: User"
+FROM
+    users
+WHERE ($1::text IS NULL
+    OR username ILIKE $1
+    OR nickname ILIKE $1
+    OR email ILIKE $1)
+    AND ($2::uuid IS NULL OR id = $2)
+ORDER BY
+    joined DESC
+LIMIT $3 OFFSET $4;
@@ -0,0 +1,8 @@
+UPDATE
+    users
+SET
+    deactivated = $2
+WHERE
+    id = $1
+RETURNING
+    users AS "user!: User";
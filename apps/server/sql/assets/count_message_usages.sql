@@ -0,0 +1,4 @@
+SELECT count(*) AS "count!"
+FROM messages
+WHERE portrait_id = $1
+  AND NOT deleted;
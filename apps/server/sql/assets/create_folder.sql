@@ -0,0 +1,3 @@
+INSERT INTO asset_folders (id, space_id, parent_id, creator_id, name)
+VALUES ($1, $2, $3, $4, $5)
+RETURNING id, space_id, parent_id, creator_id, name, created;
@@ -0,0 +1,14 @@
+WITH moved_folders AS (
+    UPDATE asset_folders
+    SET parent_id = $2
+    WHERE parent_id = $1
+    RETURNING id
+), moved_assets AS (
+    UPDATE assets
+    SET folder_id = $2
+    WHERE folder_id = $1
+    RETURNING id
+)
+DELETE FROM asset_folders
+WHERE id = $1
+RETURNING id;
@@ -0,0 +1,4 @@
+SELECT id, space_id, parent_id, creator_id, name, created
+FROM asset_folders
+WHERE space_id = $1
+  AND id = $2;
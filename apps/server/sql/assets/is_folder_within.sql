@@ -0,0 +1,10 @@
+WITH RECURSIVE ancestors AS (
+    SELECT id, space_id, parent_id
+    FROM asset_folders
+    WHERE id = $1
+    UNION
+    SELECT parent.id, parent.space_id, parent.parent_id
+    FROM asset_folders parent
+    JOIN ancestors ON parent.space_id = ancestors.space_id AND parent.id = ancestors.parent_id
+)
+SELECT EXISTS (SELECT 1 FROM ancestors WHERE id = $2) AS "within!";
@@ -2,10 +2,13 @@ SELECT
     asset.id,
     asset.space_id,
     asset.media_id,
+    asset.folder_id,
     asset.creator_id,
     asset.name,
+    asset.tags,
     asset.policy AS "policy!: AssetPolicy",
     media.mime_type,
+    media.duration_ms,
     asset.created
 FROM assets asset
 JOIN media ON media.id = asset.media_id
@@ -0,0 +1,28 @@
+SELECT
+    character.id,
+    character.name,
+    character.main_scope_id
+FROM characters character
+WHERE character.space_id = $1
+  AND (
+      EXISTS (
+          SELECT 1
+          FROM entry_components_asset component
+          LEFT JOIN character_scopes character_scope
+              ON character_scope.scope_id = component.scope_id
+          WHERE component.space_id = $1
+            AND component.asset_id = $2
+            AND (
+                component.scope_id = character.main_scope_id
+                OR character_scope.character_id = character.id
+            )
+      )
+      OR EXISTS (
+          SELECT 1
+          FROM messages message
+          WHERE message.portrait_id = $2
+            AND message.character_id = character.id
+            AND NOT message.deleted
+      )
+  )
+ORDER BY character.name, character.id;
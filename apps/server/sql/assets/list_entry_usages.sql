@@ -0,0 +1,12 @@
+SELECT
+    entry.id AS entry_id,
+    entry.scope_id,
+    entry.display_name,
+    component.component_type
+FROM entry_components_asset component
+JOIN entries entry
+    ON entry.scope_id = component.scope_id
+    AND entry.id = component.entry_id
+WHERE component.space_id = $1
+  AND component.asset_id = $2
+ORDER BY entry.scope_id, entry.pos, entry.id, component.component_type;
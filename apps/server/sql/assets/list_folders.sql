@@ -0,0 +1,19 @@
+WITH RECURSIVE tree AS (
+    SELECT folder.*, 0 AS depth
+    FROM asset_folders folder
+    WHERE folder.space_id = $1
+      AND folder.parent_id IS NULL
+    UNION ALL
+    SELECT child.*, tree.depth + 1
+    FROM asset_folders child
+    JOIN tree ON child.space_id = tree.space_id AND child.parent_id = tree.id
+)
+SELECT
+    id AS "id!",
+    space_id AS "space_id!",
+    parent_id,
+    creator_id,
+    name AS "name!",
+    created AS "created!"
+FROM tree
+ORDER BY depth, lower(name), id;
@@ -0,0 +1,22 @@
+WITH moved AS (
+    UPDATE assets
+    SET folder_id = $3
+    WHERE space_id = $1
+      AND id = ANY($2)
+    RETURNING id, space_id, media_id, folder_id, creator_id, name, tags, policy, created
+)
+SELECT
+    moved.id,
+    moved.space_id,
+    moved.media_id,
+    moved.folder_id,
+    moved.creator_id,
+    moved.name,
+    moved.tags,
+    moved.policy AS "policy!: AssetPolicy",
+    media.mime_type,
+    media.duration_ms,
+    moved.created
+FROM moved
+JOIN media ON media.id = moved.media_id
+ORDER BY moved.created DESC, moved.id DESC;
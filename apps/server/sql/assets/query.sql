@@ -0,0 +1,34 @@
+SELECT
+    asset.id,
+    asset.space_id,
+    asset.media_id,
+    asset.folder_id,
+    asset.creator_id,
+    asset.name,
+    asset.tags,
+    asset.policy AS "policy!: AssetPolicy",
+    media.mime_type,
+    media.duration_ms,
+    asset.created
+FROM assets asset
+JOIN media ON media.id = asset.media_id
+WHERE asset.space_id = $1
+  AND asset.policy <> 'Unlisted'
+  AND ($2::uuid IS NULL OR asset.folder_id = $2)
+  AND (NOT $3::bool OR asset.folder_id IS NULL)
+  AND (
+      $4::text IS NULL
+      OR asset.name ILIKE $4
+      OR EXISTS (SELECT 1 FROM unnest(asset.tags) tag WHERE tag ILIKE $4)
+  )
+  AND ($5::text IS NULL OR asset.tags @> ARRAY[$5::text])
+  AND ($6::text IS NULL OR media.mime_type LIKE $6 || '/%')
+ORDER BY
+    CASE WHEN $7::text = 'name' AND NOT $8::bool THEN lower(asset.name) END ASC,
+    CASE WHEN $7::text = 'name' AND $8::bool THEN lower(asset.name) END DESC,
+    CASE WHEN $7::text = 'created' AND NOT $8::bool THEN asset.created END ASC,
+    CASE WHEN $7::text = 'created' AND $8::bool THEN asset.created END DESC,
+    CASE WHEN $8::bool THEN asset.id END DESC,
+    asset.id ASC
+OFFSET $9
+LIMIT $10;
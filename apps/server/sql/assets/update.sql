@@ -1,17 +1,20 @@
 WITH updated AS (
     UPDATE assets
-    SET name = $2, policy = $3
+    SET name = $2, policy = $3, tags = COALESCE($4, tags)
     WHERE id = $1
-    RETURNING id, space_id, media_id, creator_id, name, policy, created
+    RETURNING id, space_id, media_id, folder_id, creator_id, name, tags, policy, created
 )
 SELECT
     updated.id,
     updated.space_id,
     updated.media_id,
+    updated.folder_id,
     updated.creator_id,
     updated.name,
+    updated.tags,
     updated.policy AS "policy!: AssetPolicy",
     media.mime_type,
+    media.duration_ms,
     updated.created
 FROM updated
 JOIN media ON media.id = updated.media_id;
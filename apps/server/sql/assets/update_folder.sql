@@ -0,0 +1,4 @@
+UPDATE asset_folders
+SET name = $2, parent_id = $3
+WHERE id = $1
+RETURNING id, space_id, parent_id, creator_id, name, created;
@@ -0,0 +1,11 @@
+UPDATE
+    channels
+SET
+    slow_mode_seconds = COALESCE($2, slow_mode_seconds),
+    posting_policy = COALESCE($3, posting_policy),
+    max_message_length = COALESCE($4, max_message_length)
+WHERE
+    id = $1
+    AND deleted = FALSE
+RETURNING
+    channels AS "channel!: Channel";
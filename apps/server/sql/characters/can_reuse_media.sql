@@ -0,0 +1,12 @@
+SELECT EXISTS(
+    SELECT 1
+    FROM media
+    WHERE id = $1
+      AND uploader_id = $2
+) OR EXISTS(
+    SELECT 1
+    FROM assets asset
+    JOIN space_members member ON member.space_id = asset.space_id
+    WHERE asset.media_id = $1
+      AND member.user_id = $2
+) AS "reusable!";
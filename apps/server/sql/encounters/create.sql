@@ -0,0 +1,3 @@
+INSERT INTO encounters (channel_id, space_id, announce_turns, duration_component_type, duration_path)
+VALUES ($1, $2, $3, $4, $5)
+RETURNING channel_id, space_id, round, current_combatant_id, announce_turns, duration_component_type, duration_path, version, created, modified;
@@ -0,0 +1,3 @@
+DELETE FROM encounters
+WHERE space_id = $1
+  AND channel_id = $2;
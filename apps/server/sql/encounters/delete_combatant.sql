@@ -0,0 +1,3 @@
+DELETE FROM encounter_combatants
+WHERE channel_id = $1
+  AND id = $2;
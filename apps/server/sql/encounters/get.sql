@@ -0,0 +1,4 @@
+SELECT channel_id, space_id, round, current_combatant_id, announce_turns, duration_component_type, duration_path, version, created, modified
+FROM encounters
+WHERE space_id = $1
+  AND channel_id = $2;
@@ -0,0 +1,3 @@
+INSERT INTO encounter_combatants (channel_id, name, character_id, entry_id, entry_scope_id, initiative, initiative_message_id, added_by)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+RETURNING id, channel_id, name, character_id, entry_id, entry_scope_id, initiative, initiative_message_id, added_by, created;
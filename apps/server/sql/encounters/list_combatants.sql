@@ -0,0 +1,4 @@
+SELECT id, channel_id, name, character_id, entry_id, entry_scope_id, initiative, initiative_message_id, added_by, created
+FROM encounter_combatants
+WHERE channel_id = $1
+ORDER BY initiative DESC, created, id;
@@ -0,0 +1,26 @@
+SELECT
+    entry.id,
+    entry.scope_id,
+    primary_identifier.value::text AS "key!",
+    component.version,
+    json_component.data,
+    json_component.schema_version
+FROM encounter_combatants combatant
+LEFT JOIN characters character
+  ON character.id = combatant.character_id
+JOIN entries entry
+  ON entry.id = combatant.entry_id
+  OR entry.scope_id = character.main_scope_id
+JOIN entry_identifiers primary_identifier
+  ON primary_identifier.entry_id = entry.id
+ AND primary_identifier.kind = 'Primary'
+JOIN entry_components component
+  ON component.entry_id = entry.id
+ AND component.component_type = $2
+JOIN entry_components_json json_component
+  ON json_component.entry_id = component.entry_id
+ AND json_component.component_type = component.component_type
+ AND json_component.payload_type = component.payload_type
+WHERE combatant.id = $1
+ORDER BY entry.pos, entry.id
+FOR UPDATE OF component;
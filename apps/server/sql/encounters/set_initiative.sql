@@ -0,0 +1,6 @@
+UPDATE encounter_combatants
+SET initiative = $3,
+    initiative_message_id = $4
+WHERE channel_id = $1
+  AND id = $2
+RETURNING id, channel_id, name, character_id, entry_id, entry_scope_id, initiative, initiative_message_id, added_by, created;
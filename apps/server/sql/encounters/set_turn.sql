@@ -0,0 +1,8 @@
+UPDATE encounters
+SET round = $3,
+    current_combatant_id = $4,
+    version = uuidv7(),
+    modified = now()
+WHERE space_id = $1
+  AND channel_id = $2
+RETURNING channel_id, space_id, round, current_combatant_id, announce_turns, duration_component_type, duration_path, version, created, modified;
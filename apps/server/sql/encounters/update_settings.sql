@@ -0,0 +1,9 @@
+UPDATE encounters
+SET announce_turns = $3,
+    duration_component_type = $4,
+    duration_path = $5,
+    version = uuidv7(),
+    modified = now()
+WHERE space_id = $1
+  AND channel_id = $2
+RETURNING channel_id, space_id, round, current_combatant_id, announce_turns, duration_component_type, duration_path, version, created, modified;
@@ -0,0 +1,4 @@
+INSERT INTO component_types (space_id, component_type, schema_version, schema, migration)
+SELECT $2, component_type, schema_version, schema, migration
+FROM component_types
+WHERE space_id = $1;
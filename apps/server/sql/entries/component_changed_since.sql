@@ -0,0 +1,9 @@
+SELECT EXISTS (
+    SELECT 1
+    FROM entry_component_history history
+    JOIN entry_effects effect ON effect.id = history.entry_effect_id
+    WHERE history.entry_id = $1
+      AND history.component_type = $2
+      AND (effect.created, effect.id) > ($3, $4)
+      AND effect.id <> ALL($5)
+) AS "changed!";
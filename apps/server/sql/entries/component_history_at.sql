@@ -0,0 +1,11 @@
+SELECT DISTINCT ON (history.entry_id, history.component_type)
+    history.entry_id,
+    history.key,
+    history.component_type,
+    history.action AS "action!: EntryComponentHistoryAction",
+    history.payload
+FROM entry_component_history history
+JOIN entry_effects effect ON effect.id = history.entry_effect_id
+WHERE effect.scope_id = $1
+  AND effect.created <= $2
+ORDER BY history.entry_id, history.component_type, effect.created DESC, effect.id DESC;
@@ -0,0 +1,10 @@
+SELECT
+    history.action AS "action!: EntryComponentHistoryAction",
+    history.payload
+FROM entry_component_history history
+JOIN entry_effects effect ON effect.id = history.entry_effect_id
+WHERE history.entry_id = $1
+  AND history.component_type = $2
+  AND (effect.created, effect.id) < ($3, $4)
+ORDER BY effect.created DESC, effect.id DESC
+LIMIT 1;
@@ -18,4 +18,5 @@ RETURNING
     scope_id,
     operator_id,
     created,
-    message_id;
+    message_id,
+    reverted_by;
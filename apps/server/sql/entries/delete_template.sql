@@ -0,0 +1,3 @@
+DELETE FROM entry_templates
+WHERE space_id = $1
+  AND id = $2;
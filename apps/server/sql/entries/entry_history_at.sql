@@ -0,0 +1,9 @@
+SELECT DISTINCT ON (history.entry_id)
+    history.entry_id,
+    history.key,
+    history.action AS "action!: EntryHistoryAction"
+FROM entry_history history
+JOIN entry_effects effect ON effect.id = history.entry_effect_id
+WHERE effect.scope_id = $1
+  AND effect.created <= $2
+ORDER BY history.entry_id, effect.created DESC, effect.id DESC;
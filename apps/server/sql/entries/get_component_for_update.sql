@@ -3,7 +3,8 @@ SELECT
     component.version,
     json_component.schema_version AS "schema_version?",
     (json_component.entry_id IS NOT NULL) AS "json_exists!",
-    (asset_component.entry_id IS NOT NULL) AS "asset_exists!"
+    (asset_component.entry_id IS NOT NULL) AS "asset_exists!",
+    (formula_component.entry_id IS NOT NULL) AS "formula_exists!"
 FROM entry_components component
 LEFT JOIN entry_components_json json_component
   ON json_component.entry_id = component.entry_id
@@ -13,6 +14,10 @@ LEFT JOIN entry_components_asset asset_component
   ON asset_component.entry_id = component.entry_id
  AND asset_component.component_type = component.component_type
  AND asset_component.payload_type = component.payload_type
+LEFT JOIN entry_components_formula formula_component
+  ON formula_component.entry_id = component.entry_id
+ AND formula_component.component_type = component.component_type
+ AND formula_component.payload_type = component.payload_type
 WHERE component.entry_id = $1
   AND component.component_type = $2
 FOR UPDATE OF component;
@@ -0,0 +1,10 @@
+SELECT
+    component_type,
+    schema_version,
+    schema,
+    migration,
+    created
+FROM component_types
+WHERE space_id = $1
+  AND component_type = ANY($2)
+ORDER BY component_type, schema_version;
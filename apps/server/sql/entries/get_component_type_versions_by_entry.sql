@@ -0,0 +1,12 @@
+SELECT
+    component_type.component_type,
+    component_type.schema_version,
+    component_type.schema,
+    component_type.migration,
+    component_type.created
+FROM component_types component_type
+JOIN scopes scope ON scope.space_id = component_type.space_id
+JOIN entries entry ON entry.scope_id = scope.id
+WHERE entry.id = $1
+  AND component_type.component_type = ANY($2)
+ORDER BY component_type.component_type, component_type.schema_version;
@@ -0,0 +1,8 @@
+INSERT INTO component_types (space_id, component_type, schema_version, schema, migration)
+VALUES ($1, $2, $3, $4, $5)
+RETURNING
+    component_type,
+    schema_version,
+    schema,
+    migration,
+    created;
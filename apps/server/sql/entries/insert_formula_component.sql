@@ -0,0 +1,14 @@
+WITH component AS (
+    INSERT INTO entry_components (entry_id, component_type, payload_type)
+    VALUES ($1, $2, 'Formula')
+    RETURNING entry_id, component_type, payload_type
+)
+INSERT INTO entry_components_formula (
+    entry_id,
+    component_type,
+    payload_type,
+    formula
+)
+SELECT entry_id, component_type, payload_type, $3
+FROM component
+RETURNING entry_id;
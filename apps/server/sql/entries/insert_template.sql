@@ -0,0 +1,11 @@
+INSERT INTO entry_templates (
+    space_id,
+    name,
+    key_pattern,
+    display_name,
+    tags,
+    reference_note_id,
+    components
+)
+VALUES ($1, $2, $3, $4, $5, $6, $7)
+RETURNING id, space_id, name, key_pattern, display_name, tags, reference_note_id, components, version, created, modified;
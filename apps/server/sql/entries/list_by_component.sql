@@ -24,6 +24,7 @@ SELECT
     json_component.data AS "json_data?",
     json_component.schema_version AS "json_schema_version?",
     asset_component.asset_id AS "asset_id?",
+    formula_component.formula AS "formula?",
     component.version AS component_version,
     component.modified AS component_modified
 FROM entries entry
@@ -41,5 +42,9 @@ LEFT JOIN entry_components_asset asset_component
   ON asset_component.entry_id = component.entry_id
  AND asset_component.component_type = component.component_type
  AND asset_component.payload_type = component.payload_type
+LEFT JOIN entry_components_formula formula_component
+  ON formula_component.entry_id = component.entry_id
+ AND formula_component.component_type = component.component_type
+ AND formula_component.payload_type = component.payload_type
 WHERE entry.scope_id = $1
 ORDER BY entry.pos, entry.id;
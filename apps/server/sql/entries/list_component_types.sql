@@ -0,0 +1,9 @@
+SELECT
+    component_type,
+    schema_version,
+    schema,
+    migration,
+    created
+FROM component_types
+WHERE space_id = $1
+ORDER BY component_type, schema_version;
@@ -4,7 +4,8 @@ SELECT
     scope_id,
     operator_id,
     created,
-    message_id
+    message_id,
+    reverted_by
 FROM entry_effects
 WHERE space_id = $1
   AND id = ANY($2)
@@ -0,0 +1,14 @@
+SELECT
+    id,
+    space_id,
+    scope_id,
+    operator_id,
+    created,
+    message_id,
+    reverted_by
+FROM entry_effects
+WHERE space_id = $1
+  AND message_id = $2
+  AND reverted_by IS NULL
+ORDER BY created, id
+FOR UPDATE;
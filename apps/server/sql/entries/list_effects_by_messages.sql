@@ -4,7 +4,8 @@ SELECT
     effect.scope_id,
     effect.operator_id,
     effect.created,
-    effect.message_id
+    effect.message_id,
+    effect.reverted_by
 FROM entry_effects effect
 JOIN messages message ON message.id = effect.message_id
 JOIN channels channel ON channel.id = message.channel_id
@@ -0,0 +1,13 @@
+SELECT
+    formula.entry_id,
+    ARRAY(
+        SELECT lower(identifier.value::text)
+        FROM entry_identifiers identifier
+        WHERE identifier.entry_id = formula.entry_id
+    ) AS "identifiers!",
+    formula.component_type,
+    formula.formula
+FROM entry_components_formula formula
+JOIN entries entry ON entry.id = formula.entry_id
+WHERE entry.scope_id = (SELECT scope_id FROM entries WHERE id = $1)
+ORDER BY formula.entry_id, formula.component_type;
@@ -0,0 +1,20 @@
+SELECT
+    entry.id AS entry_id,
+    entry.scope_id,
+    primary_identifier.value::text AS "key!: CompactString",
+    json_component.data AS "data?",
+    json_component.schema_version AS "schema_version?"
+FROM entry_components component
+JOIN entries entry ON entry.id = component.entry_id
+JOIN scopes scope ON scope.id = entry.scope_id
+JOIN entry_identifiers primary_identifier
+  ON primary_identifier.entry_id = entry.id
+ AND primary_identifier.kind = 'Primary'
+LEFT JOIN entry_components_json json_component
+  ON json_component.entry_id = component.entry_id
+ AND json_component.component_type = component.component_type
+ AND json_component.payload_type = component.payload_type
+WHERE scope.space_id = $1
+  AND component.component_type = $2
+ORDER BY entry.scope_id, entry.id
+FOR UPDATE OF component;
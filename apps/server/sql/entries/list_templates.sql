@@ -0,0 +1,4 @@
+SELECT id, space_id, name, key_pattern, display_name, tags, reference_note_id, components, version, created, modified
+FROM entry_templates
+WHERE space_id = $1
+ORDER BY name;
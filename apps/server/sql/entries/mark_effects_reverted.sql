@@ -0,0 +1,5 @@
+UPDATE entry_effects
+SET reverted_by = $3
+WHERE id = ANY($1)
+  AND scope_id = $2
+  AND reverted_by IS NULL;
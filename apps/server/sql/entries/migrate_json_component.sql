@@ -0,0 +1,15 @@
+WITH component AS (
+    UPDATE entry_components
+    SET version = uuidv7(),
+        modified = now()
+    WHERE entry_id = $1
+      AND component_type = $2
+      AND payload_type = 'Json'
+    RETURNING entry_id, component_type
+)
+UPDATE entry_components_json json_component
+SET data = $3,
+    schema_version = $4
+FROM component
+WHERE json_component.entry_id = component.entry_id
+  AND json_component.component_type = component.component_type;
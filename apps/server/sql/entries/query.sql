@@ -0,0 +1,106 @@
+WITH matched AS (
+    SELECT
+        entry.id,
+        entry.scope_id,
+        primary_identifier.value::text AS key,
+        entry.display_name,
+        entry.reference_note_id,
+        entry.tags,
+        entry.pos_p,
+        entry.pos_q,
+        entry.pos,
+        entry.metadata_version,
+        entry.components_version,
+        entry.created,
+        entry.modified,
+        CASE $8::text
+            WHEN 'key' THEN lower(primary_identifier.value::text)
+            WHEN 'display_name' THEN lower(entry.display_name)
+        END AS sort_text,
+        CASE $8::text
+            WHEN 'created' THEN entry.created
+            WHEN 'modified' THEN entry.modified
+        END AS sort_time,
+        CASE $8::text
+            WHEN 'component' THEN (
+                SELECT json_component.data #> $10::text[]
+                FROM entry_components_json json_component
+                WHERE json_component.entry_id = entry.id
+                  AND json_component.component_type = $9
+            )
+        END AS sort_value
+    FROM entries entry
+    JOIN entry_identifiers primary_identifier
+      ON primary_identifier.entry_id = entry.id
+     AND primary_identifier.kind = 'Primary'
+    WHERE entry.scope_id = ANY($1)
+      AND (cardinality($2::text[]) = 0 OR entry.tags @> $2)
+      AND (
+          $3::text IS NULL
+          OR EXISTS (
+              SELECT 1
+              FROM entry_identifiers identifier
+              WHERE identifier.entry_id = entry.id
+                AND lower(identifier.value::text) LIKE $3
+          )
+      )
+      AND NOT EXISTS (
+          SELECT 1
+          FROM unnest($4::text[]) required(component_type)
+          WHERE NOT EXISTS (
+              SELECT 1
+              FROM entry_components component
+              WHERE component.entry_id = entry.id
+                AND component.component_type = required.component_type
+          )
+      )
+      AND NOT EXISTS (
+          SELECT 1
+          FROM entry_components component
+          WHERE component.entry_id = entry.id
+            AND component.component_type = ANY($5)
+      )
+      AND NOT EXISTS (
+          SELECT 1
+          FROM unnest($6::text[], $7::text[]) predicate(component_type, path)
+          WHERE NOT EXISTS (
+              SELECT 1
+              FROM entry_components_json json_component
+              WHERE json_component.entry_id = entry.id
+                AND json_component.component_type = predicate.component_type
+                AND json_component.data @@ predicate.path::jsonpath
+          )
+      )
+)
+SELECT
+    matched.id AS "id!",
+    matched.scope_id AS "scope_id!",
+    matched.key AS "key!: CompactString",
+    ARRAY(
+        SELECT identifier.value::text
+        FROM entry_identifiers identifier
+        WHERE identifier.entry_id = matched.id
+          AND identifier.kind = 'Alias'
+        ORDER BY identifier.value
+    ) AS "aliases!: Vec<CompactString>",
+    matched.display_name AS "display_name!: CompactString",
+    matched.reference_note_id,
+    matched.tags AS "tags!: Vec<CompactString>",
+    matched.pos_p AS "pos_p!",
+    matched.pos_q AS "pos_q!",
+    matched.pos AS "pos!",
+    matched.metadata_version AS "metadata_version!",
+    matched.components_version AS "components_version!",
+    matched.created AS "created!",
+    matched.modified AS "modified!"
+FROM matched
+ORDER BY
+    CASE WHEN NOT $11 THEN matched.sort_text END ASC NULLS LAST,
+    CASE WHEN $11 THEN matched.sort_text END DESC NULLS LAST,
+    CASE WHEN NOT $11 THEN matched.sort_time END ASC NULLS LAST,
+    CASE WHEN $11 THEN matched.sort_time END DESC NULLS LAST,
+    CASE WHEN NOT $11 THEN matched.sort_value END ASC NULLS LAST,
+    CASE WHEN $11 THEN matched.sort_value END DESC NULLS LAST,
+    matched.id
+OFFSET $12
+LIMIT $13;
@@ -0,0 +1,4 @@
+SELECT lower(identifier.value::text) AS "identifier!", identifier.entry_id
+FROM entry_identifiers identifier
+WHERE identifier.scope_id = $1
+  AND identifier.value = ANY($2::text[]::citext[]);
@@ -0,0 +1,13 @@
+UPDATE entry_templates
+SET name = $4,
+    key_pattern = $5,
+    display_name = $6,
+    tags = $7,
+    reference_note_id = $8,
+    components = $9,
+    version = uuidv7(),
+    modified = now()
+WHERE space_id = $1
+  AND id = $2
+  AND version = $3
+RETURNING id, space_id, name, key_pattern, display_name, tags, reference_note_id, components, version, created, modified;
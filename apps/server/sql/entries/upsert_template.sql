@@ -0,0 +1,19 @@
+INSERT INTO entry_templates (
+    space_id,
+    name,
+    key_pattern,
+    display_name,
+    tags,
+    reference_note_id,
+    components
+)
+VALUES ($1, $2, $3, $4, $5, $6, $7)
+ON CONFLICT (space_id, name) DO UPDATE
+SET key_pattern = EXCLUDED.key_pattern,
+    display_name = EXCLUDED.display_name,
+    tags = EXCLUDED.tags,
+    reference_note_id = EXCLUDED.reference_note_id,
+    components = EXCLUDED.components,
+    version = uuidv7(),
+    modified = now()
+RETURNING id, space_id, name, key_pattern, display_name, tags, reference_note_id, components, version, created, modified;
@@ -1,5 +1,6 @@
-INSERT INTO media (id, mime_type, uploader_id, filename, original_filename, hash, size, source)
-    VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+-- A hash given on creation was computed by the server from the upload.
+INSERT INTO media (id, mime_type, uploader_id, filename, original_filename, hash, size, source, hash_trusted)
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $6 <> '')
 RETURNING
     media AS "media!: Media";
 
@@ -0,0 +1,36 @@
+DELETE FROM media
+WHERE
+    id = ANY ($1)
+    AND last_used < $2
+    AND NOT EXISTS (
+        SELECT
+            1
+        FROM
+            messages
+        WHERE
+            messages.media_id = media.id)
+    AND NOT EXISTS (
+        SELECT
+            1
+        FROM
+            users
+        WHERE
+            users.avatar_id = media.id)
+    AND NOT EXISTS (
+        SELECT
+            1
+        FROM
+            assets
+        WHERE
+            assets.media_id = media.id)
+    AND NOT EXISTS (
+        SELECT
+            1
+        FROM
+            abuse_reports
+        WHERE
+            abuse_reports.target_kind = 'Media'
+            AND abuse_reports.target_id = media.id
+            AND abuse_reports.status = 'Open')
+RETURNING
+    media AS "media!: Media";
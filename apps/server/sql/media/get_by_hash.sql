@@ -0,0 +1,23 @@
+UPDATE
+    media
+SET
+    last_used = now()
+WHERE
+    id = (
+        SELECT
+            id
+        FROM
+            media
+        WHERE
+            hash = $1
+            AND size = $2
+            AND CASE WHEN $3::uuid IS NULL THEN
+                hash_trusted
+            ELSE
+                uploader_id = $3
+            END
+        ORDER BY
+            created
+        LIMIT 1)
+RETURNING
+    media AS "media!: Media";
@@ -0,0 +1,39 @@
+SELECT
+    media AS "media!: Media"
+FROM
+    media
+WHERE
+    last_used < $1
+    AND NOT EXISTS (
+        SELECT
+            1
+        FROM
+            messages
+        WHERE
+            messages.media_id = media.id)
+    AND NOT EXISTS (
+        SELECT
+            1
+        FROM
+            users
+        WHERE
+            users.avatar_id = media.id)
+    AND NOT EXISTS (
+        SELECT
+            1
+        FROM
+            assets
+        WHERE
+            assets.media_id = media.id)
+    AND NOT EXISTS (
+        SELECT
+            1
+        FROM
+            abuse_reports
+        WHERE
+            abuse_reports.target_kind = 'Media'
+            AND abuse_reports.target_id = media.id
+            AND abuse_reports.status = 'Open')
+ORDER BY
+    last_used
+LIMIT $2;
@@ -0,0 +1,8 @@
+UPDATE
+    media
+SET
+    duration_ms = $2
+WHERE
+    id = $1
+RETURNING
+    media AS "media!: Media";
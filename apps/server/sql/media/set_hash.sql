@@ -0,0 +1,9 @@
+UPDATE
+    media
+SET
+    hash = $2,
+    hash_trusted = $3
+WHERE
+    id = $1
+RETURNING
+    media AS "media!: Media";
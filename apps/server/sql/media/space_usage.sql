@@ -0,0 +1,21 @@
+SELECT
+    coalesce(sum(media.size), 0)::bigint AS "bytes!"
+FROM
+    media
+WHERE
+    media.id IN (
+        SELECT
+            messages.media_id
+        FROM
+            messages
+            JOIN channels ON channels.id = messages.channel_id
+        WHERE
+            channels.space_id = $1
+            AND messages.media_id IS NOT NULL
+        UNION
+        SELECT
+            assets.media_id
+        FROM
+            assets
+        WHERE
+            assets.space_id = $1);
@@ -0,0 +1,6 @@
+SELECT
+    coalesce(sum(size), 0)::bigint AS "bytes!"
+FROM
+    media
+WHERE
+    uploader_id = $1;
@@ -22,6 +22,7 @@ WHERE
     AND msg.deleted = FALSE
     AND ch.id = msg.channel_id
     AND ch.deleted = FALSE
+    AND ch.space_id = $11
     AND (ch.is_document OR msg.sender_id = $10)
     AND ($9::timestamptz IS NULL OR msg.modified = $9)
 RETURNING
@@ -17,6 +17,7 @@ FROM
         AND msg.deleted = FALSE
     LEFT JOIN channels ch ON ch.id = msg.channel_id
         AND ch.deleted = FALSE
+        AND ch.space_id = $4
     LEFT JOIN channel_members cm ON cm.channel_id = msg.channel_id
         AND cm.user_id = $2
         AND cm.is_joined
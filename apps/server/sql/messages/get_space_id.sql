@@ -0,0 +1,8 @@
+SELECT
+    ch.space_id
+FROM
+    messages msg
+    INNER JOIN channels ch ON ch.id = msg.channel_id
+WHERE
+    msg.id = $1
+    AND msg.deleted = FALSE;
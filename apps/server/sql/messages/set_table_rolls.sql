@@ -0,0 +1,8 @@
+UPDATE
+    messages
+SET
+    entities = $2
+WHERE
+    id = $1
+RETURNING
+    messages AS "message!: Message";
@@ -0,0 +1,2 @@
+DELETE FROM note_links
+WHERE note_id = $1;
@@ -0,0 +1,7 @@
+SELECT
+    note_revision_keep_latest AS keep_latest,
+    note_revision_keep_days AS keep_days
+FROM
+    spaces_extension
+WHERE
+    space_id = $1;
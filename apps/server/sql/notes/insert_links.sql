@@ -0,0 +1,27 @@
+INSERT INTO note_links (
+    note_id,
+    space_id,
+    position,
+    kind,
+    target,
+    target_note_id,
+    target_entry_id,
+    target_character_id
+)
+SELECT
+    $1,
+    $2,
+    link.position,
+    link.kind,
+    link.target,
+    link.target_note_id,
+    link.target_entry_id,
+    link.target_character_id
+FROM unnest(
+    $3::integer[],
+    $4::text[],
+    $5::text[],
+    $6::uuid[],
+    $7::uuid[],
+    $8::uuid[]
+) AS link (position, kind, target, target_note_id, target_entry_id, target_character_id);
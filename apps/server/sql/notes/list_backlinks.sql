@@ -0,0 +1,21 @@
+-- $2 and $3 are the kind and the id of the target, $4 the names it's linked by.
+SELECT
+    link.note_id AS "note_id!"
+FROM note_links link
+WHERE link.space_id = $1
+  AND CASE $2::text
+      WHEN 'NOTE' THEN link.target_note_id = $3
+      WHEN 'ENTRY' THEN link.target_entry_id = $3
+      WHEN 'CHARACTER' THEN link.target_character_id = $3
+      ELSE false
+  END
+UNION
+SELECT
+    link.note_id
+FROM note_links link
+WHERE link.space_id = $1
+  AND link.target_note_id IS NULL
+  AND link.target_entry_id IS NULL
+  AND link.target_character_id IS NULL
+  AND (link.kind IS NULL OR link.kind = $2)
+  AND link.target = ANY ($4::text[]);
@@ -0,0 +1,11 @@
+SELECT
+    link.kind,
+    link.target,
+    link.target_note_id,
+    link.target_entry_id,
+    target_entry.scope_id AS "target_entry_scope_id?",
+    link.target_character_id
+FROM note_links link
+LEFT JOIN entries target_entry ON target_entry.id = link.target_entry_id
+WHERE link.note_id = $1
+ORDER BY link.position;
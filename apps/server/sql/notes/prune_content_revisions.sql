@@ -0,0 +1,19 @@
+WITH ranked AS (
+    SELECT
+        revision.note_id,
+        revision.revision,
+        revision.created,
+        row_number() OVER (PARTITION BY revision.note_id ORDER BY revision.revision DESC) AS rank
+    FROM note_content_revisions revision
+    JOIN notes note ON note.id = revision.note_id
+    WHERE note.space_id = $1
+      AND ($2::uuid IS NULL OR note.id = $2)
+)
+DELETE FROM note_content_revisions target
+USING ranked
+WHERE target.note_id = ranked.note_id
+  AND target.revision = ranked.revision
+  AND ranked.rank > 1
+  AND ($3::integer IS NOT NULL OR $4::integer IS NOT NULL)
+  AND ($3::integer IS NULL OR ranked.rank > $3)
+  AND ($4::integer IS NULL OR ranked.created < now() - make_interval(days => $4));
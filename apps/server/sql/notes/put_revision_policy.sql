@@ -0,0 +1,6 @@
+INSERT INTO spaces_extension (space_id, note_revision_keep_latest, note_revision_keep_days)
+    VALUES ($1, $2, $3)
+ON CONFLICT (space_id)
+    DO UPDATE SET
+        note_revision_keep_latest = EXCLUDED.note_revision_keep_latest,
+        note_revision_keep_days = EXCLUDED.note_revision_keep_days;
@@ -0,0 +1,44 @@
+-- Notes first, then characters, then the entries of the space and of characters.
+SELECT
+    candidate.kind AS "kind!",
+    candidate.id AS "id!",
+    candidate.scope_id
+FROM (
+    SELECT 'NOTE' AS kind, note.id, NULL::uuid AS scope_id, 0 AS priority, note.created
+    FROM notes note
+    WHERE note.space_id = $1
+      AND note.archived_at IS NULL
+      AND ($2::text IS NULL OR $2 = 'NOTE')
+      AND (lower(note.title) = $3 OR $3 = ANY (note.keywords))
+    UNION ALL
+    SELECT 'CHARACTER', character.id, NULL, 1, character.created
+    FROM characters character
+    WHERE character.space_id = $1
+      AND ($2::text IS NULL OR $2 = 'CHARACTER')
+      AND (
+          lower(character.name) = $3
+          OR character.id IN (
+              SELECT identifier.character_id
+              FROM character_identifiers identifier
+              WHERE identifier.space_id = $1
+                AND identifier.value = $3::citext
+          )
+      )
+    UNION ALL
+    SELECT 'ENTRY', entry.id, entry.scope_id, CASE WHEN scope.kind = 'Space' THEN 2 ELSE 3 END, entry.created
+    FROM entries entry
+    JOIN scopes scope ON scope.id = entry.scope_id
+    WHERE scope.space_id = $1
+      AND ($2::text IS NULL OR $2 = 'ENTRY')
+      AND (
+          lower(entry.display_name) = $3
+          OR entry.id IN (
+              SELECT identifier.entry_id
+              FROM entry_identifiers identifier
+              WHERE identifier.scope_id = entry.scope_id
+                AND identifier.value = $3::citext
+          )
+      )
+) candidate
+ORDER BY candidate.priority, candidate.created
+LIMIT 1;
@@ -0,0 +1,3 @@
+DELETE FROM channel_playback
+WHERE space_id = $1
+  AND channel_id = $2;
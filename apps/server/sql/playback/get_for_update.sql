@@ -0,0 +1,21 @@
+SELECT
+    playback.channel_id,
+    playback.space_id,
+    playback.asset_id,
+    asset.media_id,
+    asset.name AS asset_name,
+    media.mime_type,
+    media.duration_ms,
+    playback.playing,
+    playback.position_ms,
+    playback.looping,
+    playback.volume,
+    playback.updated_by,
+    playback.version,
+    playback.updated
+FROM channel_playback playback
+JOIN assets asset ON asset.id = playback.asset_id
+JOIN media ON media.id = asset.media_id
+WHERE playback.space_id = $1
+  AND playback.channel_id = $2
+FOR UPDATE OF playback;
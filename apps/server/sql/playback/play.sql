@@ -0,0 +1,35 @@
+WITH playback AS (
+    INSERT INTO channel_playback (
+        channel_id, space_id, asset_id, playing, position_ms, looping, volume, updated_by, updated
+    )
+    VALUES ($2, $1, $3, true, $4, $5, $6, $7, $8)
+    ON CONFLICT (channel_id) DO UPDATE
+    SET asset_id = EXCLUDED.asset_id,
+        playing = EXCLUDED.playing,
+        position_ms = EXCLUDED.position_ms,
+        looping = EXCLUDED.looping,
+        volume = EXCLUDED.volume,
+        updated_by = EXCLUDED.updated_by,
+        updated = EXCLUDED.updated,
+        version = uuidv7()
+    WHERE channel_playback.space_id = EXCLUDED.space_id
+    RETURNING *
+)
+SELECT
+    playback.channel_id,
+    playback.space_id,
+    playback.asset_id,
+    asset.media_id,
+    asset.name AS asset_name,
+    media.mime_type,
+    media.duration_ms,
+    playback.playing,
+    playback.position_ms,
+    playback.looping,
+    playback.volume,
+    playback.updated_by,
+    playback.version,
+    playback.updated
+FROM playback
+JOIN assets asset ON asset.id = playback.asset_id
+JOIN media ON media.id = asset.media_id;
@@ -0,0 +1,31 @@
+WITH playback AS (
+    UPDATE channel_playback
+    SET playing = $3,
+        position_ms = $4,
+        looping = $5,
+        volume = $6,
+        updated_by = $7,
+        updated = $8,
+        version = uuidv7()
+    WHERE space_id = $1
+      AND channel_id = $2
+    RETURNING *
+)
+SELECT
+    playback.channel_id,
+    playback.space_id,
+    playback.asset_id,
+    asset.media_id,
+    asset.name AS asset_name,
+    media.mime_type,
+    media.duration_ms,
+    playback.playing,
+    playback.position_ms,
+    playback.looping,
+    playback.volume,
+    playback.updated_by,
+    playback.version,
+    playback.updated
+FROM playback
+JOIN assets asset ON asset.id = playback.asset_id
+JOIN media ON media.id = asset.media_id;
@@ -0,0 +1,3 @@
+DELETE FROM random_tables
+WHERE space_id = $1
+  AND id = $2;
@@ -0,0 +1,3 @@
+INSERT INTO random_tables (space_id, key, name, description, rows)
+VALUES ($1, $2, $3, $4, $5)
+RETURNING id, space_id, key, name, description, rows, version, created, modified;
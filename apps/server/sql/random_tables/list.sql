@@ -0,0 +1,4 @@
+SELECT id, space_id, key, name, description, rows, version, created, modified
+FROM random_tables
+WHERE space_id = $1
+ORDER BY key;
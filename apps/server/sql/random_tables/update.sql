@@ -0,0 +1,11 @@
+UPDATE random_tables
+SET key = $4,
+    name = $5,
+    description = $6,
+    rows = $7,
+    version = uuidv7(),
+    modified = now()
+WHERE space_id = $1
+  AND id = $2
+  AND version = $3
+RETURNING id, space_id, key, name, description, rows, version, created, modified;
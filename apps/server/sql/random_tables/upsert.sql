@@ -0,0 +1,9 @@
+INSERT INTO random_tables (space_id, key, name, description, rows)
+VALUES ($1, $2, $3, $4, $5)
+ON CONFLICT (space_id, key) DO UPDATE
+SET name = EXCLUDED.name,
+    description = EXCLUDED.description,
+    rows = EXCLUDED.rows,
+    version = uuidv7(),
+    modified = now()
+RETURNING id, space_id, key, name, description, rows, version, created, modified;
@@ -0,0 +1,16 @@
+INSERT INTO abuse_reports (reporter_id, target_kind, target_id, space_id, reason, comment, snapshot)
+    VALUES ($1, $2, $3, $4, $5, $6, $7)
+RETURNING
+    id,
+    reporter_id,
+    target_kind AS "target_kind: ReportTargetKind",
+    target_id,
+    space_id,
+    reason AS "reason: ReportReason",
+    comment,
+    snapshot,
+    status AS "status: ReportStatus",
+    handler_id,
+    resolution_note,
+    created,
+    handled;
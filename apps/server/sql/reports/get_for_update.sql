@@ -0,0 +1,19 @@
+SELECT
+    id,
+    reporter_id,
+    target_kind AS "target_kind: ReportTargetKind",
+    target_id,
+    space_id,
+    reason AS "reason: ReportReason",
+    comment,
+    snapshot,
+    status AS "status: ReportStatus",
+    handler_id,
+    resolution_note,
+    created,
+    handled
+FROM
+    abuse_reports
+WHERE
+    id = $1
+FOR UPDATE;
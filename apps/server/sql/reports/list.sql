@@ -0,0 +1,22 @@
+SELECT
+    id,
+    reporter_id,
+    target_kind AS "target_kind: ReportTargetKind",
+    target_id,
+    space_id,
+    reason AS "reason: ReportReason",
+    comment,
+    snapshot,
+    status AS "status: ReportStatus",
+    handler_id,
+    resolution_note,
+    created,
+    handled
+FROM
+    abuse_reports
+WHERE ($1::uuid IS NULL OR space_id = $1)
+    AND ($2::report_status IS NULL OR status = $2)
+    AND ($3::uuid IS NULL OR id < $3)
+ORDER BY
+    id DESC
+LIMIT $4;
@@ -0,0 +1,23 @@
+UPDATE
+    abuse_reports
+SET
+    status = $2,
+    handler_id = $3,
+    resolution_note = $4,
+    handled = now()
+WHERE
+    id = $1
+RETURNING
+    id,
+    reporter_id,
+    target_kind AS "target_kind: ReportTargetKind",
+    target_id,
+    space_id,
+    reason AS "reason: ReportReason",
+    comment,
+    snapshot,
+    status AS "status: ReportStatus",
+    handler_id,
+    resolution_note,
+    created,
+    handled;
@@ -0,0 +1,116 @@
+-- Every token in $3 has to be in one of the fields. A token counts by the best field it's in,
+-- names and titles first, and an exact match of the whole query $4 with the name comes first.
+SELECT
+    hit.kind AS "kind!",
+    hit.id AS "id!",
+    hit.scope_id,
+    hit.title AS "title!",
+    hit.body,
+    hit.owner_id,
+    hit.access_policy AS "access_policy!: AccessPolicy",
+    hit.access_channel_id,
+    hit.rank AS "rank!"
+FROM (
+    SELECT
+        'NOTE' AS kind,
+        note.id,
+        NULL::uuid AS scope_id,
+        note.title,
+        note.text AS body,
+        note.creator_id AS owner_id,
+        note.access_policy,
+        note.access_channel_id,
+        matched.rank + CASE WHEN lower(note.title) = $4 THEN 16 ELSE 0 END AS rank,
+        note.modified
+    FROM notes note
+    CROSS JOIN LATERAL (
+        SELECT sum(token.weight)::int AS rank, bool_and(token.weight > 0) AS all_tokens
+        FROM (
+            SELECT CASE
+                WHEN strpos(lower(note.title), token) > 0 THEN 8
+                WHEN strpos(lower(array_to_string(note.keywords, ' ')), token) > 0 THEN 4
+                WHEN strpos(lower(note.text), token) > 0 THEN 1
+                ELSE 0
+            END AS weight
+            FROM unnest($3::text[]) token
+        ) token
+    ) matched
+    WHERE note.space_id = $1
+      AND note.archived_at IS NULL
+      AND ($2::text IS NULL OR $2 = 'NOTE')
+      AND matched.all_tokens
+    UNION ALL
+    SELECT
+        'CHARACTER',
+        character.id,
+        NULL,
+        character.name,
+        character.description,
+        scope.owner_id,
+        scope.access_policy,
+        scope.access_channel_id,
+        matched.rank + CASE WHEN lower(character.name) = $4 THEN 16 ELSE 0 END,
+        character.modified
+    FROM characters character
+    JOIN scopes scope ON scope.id = character.main_scope_id
+    CROSS JOIN LATERAL (
+        SELECT lower(string_agg(identifier.value::text, ' ')) AS value
+        FROM character_identifiers identifier
+        WHERE identifier.character_id = character.id
+    ) identifiers
+    CROSS JOIN LATERAL (
+        SELECT sum(token.weight)::int AS rank, bool_and(token.weight > 0) AS all_tokens
+        FROM (
+            SELECT CASE
+                WHEN strpos(lower(character.name), token) > 0 THEN 8
+                WHEN strpos(coalesce(identifiers.value, ''), token) > 0 THEN 4
+                WHEN strpos(lower(character.description), token) > 0 THEN 1
+                ELSE 0
+            END AS weight
+            FROM unnest($3::text[]) token
+        ) token
+    ) matched
+    WHERE character.space_id = $1
+      AND character.archived_at IS NULL
+      AND ($2::text IS NULL OR $2 = 'CHARACTER')
+      AND matched.all_tokens
+    UNION ALL
+    SELECT
+        'ENTRY',
+        entry.id,
+        entry.scope_id,
+        coalesce(nullif(entry.display_name, ''), identifiers.key, ''),
+        NULL,
+        scope.owner_id,
+        scope.access_policy,
+        scope.access_channel_id,
+        matched.rank + CASE WHEN lower(entry.display_name) = $4 THEN 16 ELSE 0 END,
+        entry.modified
+    FROM entries entry
+    JOIN scopes scope ON scope.id = entry.scope_id
+    CROSS JOIN LATERAL (
+        SELECT
+            max(identifier.value::text) FILTER (WHERE identifier.kind = 'Primary') AS key,
+            lower(string_agg(identifier.value::text, ' ')) AS value
+        FROM entry_identifiers identifier
+        WHERE identifier.scope_id = entry.scope_id
+          AND identifier.entry_id = entry.id
+    ) identifiers
+    CROSS JOIN LATERAL (
+        SELECT sum(token.weight)::int AS rank, bool_and(token.weight > 0) AS all_tokens
+        FROM (
+            SELECT CASE
+                WHEN strpos(lower(entry.display_name), token) > 0 THEN 8
+                WHEN strpos(coalesce(identifiers.value, ''), token) > 0 THEN 4
+                WHEN strpos(lower(array_to_string(entry.tags, ' ')), token) > 0 THEN 2
+                ELSE 0
+            END AS weight
+            FROM unnest($3::text[]) token
+        ) token
+    ) matched
+    WHERE scope.space_id = $1
+      AND ($2::text IS NULL OR $2 = 'ENTRY')
+      AND matched.all_tokens
+) hit
+ORDER BY hit.rank DESC, hit.modified DESC, hit.id
+LIMIT $5 OFFSET $6;
@@ -0,0 +1,61 @@
+INSERT INTO messages (
+    id,
+    sender_id,
+    channel_id,
+    name,
+    media_id,
+    seed,
+    in_game,
+    is_action,
+    is_master,
+    pinned,
+    tags,
+    folded,
+    text,
+    whisper_to_users,
+    entities,
+    created,
+    modified,
+    pos_p,
+    pos_q,
+    color,
+    character_id,
+    portrait_id
+)
+SELECT
+    uuidv7(),
+    message.sender_id,
+    channel_map.new_id,
+    message.name,
+    message.media_id,
+    message.seed,
+    message.in_game,
+    message.is_action,
+    message.is_master,
+    message.pinned,
+    message.tags,
+    message.folded,
+    message.text,
+    message.whisper_to_users,
+    message.entities,
+    message.created,
+    message.modified,
+    message.pos_p,
+    message.pos_q,
+    message.color,
+    character_map.new_id,
+    asset_map.new_id
+FROM messages message
+JOIN unnest($1::uuid[], $2::uuid[]) AS channel_map (old_id, new_id)
+  ON channel_map.old_id = message.channel_id
+LEFT JOIN unnest($3::uuid[], $4::uuid[]) AS character_map (old_id, new_id)
+  ON character_map.old_id = message.character_id
+LEFT JOIN unnest($5::uuid[], $6::uuid[]) AS asset_map (old_id, new_id)
+  ON asset_map.old_id = message.portrait_id
+WHERE message.deleted = FALSE
+  -- Only the whispers the operator sent or received are copied.
+  AND (
+      message.whisper_to_users IS NULL
+      OR message.sender_id = $7
+      OR $7 = ANY (message.whisper_to_users)
+  );
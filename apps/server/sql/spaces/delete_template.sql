@@ -0,0 +1,2 @@
+DELETE FROM space_templates
+WHERE space_id = $1;
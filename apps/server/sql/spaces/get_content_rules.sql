@@ -0,0 +1,6 @@
+SELECT
+    content_rules
+FROM
+    spaces_extension
+WHERE
+    space_id = $1;
@@ -0,0 +1,12 @@
+SELECT
+    template.space_id,
+    space.owner_id,
+    template.name,
+    template.description,
+    template.is_public,
+    template.created,
+    template.modified
+FROM space_templates template
+JOIN spaces space ON space.id = template.space_id
+WHERE template.space_id = $1
+  AND space.deleted = FALSE;
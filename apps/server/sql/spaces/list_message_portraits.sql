@@ -0,0 +1,5 @@
+SELECT DISTINCT message.portrait_id AS "portrait_id!"
+FROM messages message
+WHERE message.channel_id = ANY($1)
+  AND message.deleted = FALSE
+  AND message.portrait_id IS NOT NULL;
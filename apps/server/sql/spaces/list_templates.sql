@@ -0,0 +1,14 @@
+SELECT
+    template.space_id,
+    space.owner_id,
+    template.name,
+    template.description,
+    template.is_public,
+    template.created,
+    template.modified
+FROM space_templates template
+JOIN spaces space ON space.id = template.space_id
+WHERE space.deleted = FALSE
+  AND (template.is_public = TRUE OR space.owner_id = $1)
+ORDER BY template.created DESC
+LIMIT 256;
@@ -0,0 +1,5 @@
+INSERT INTO spaces_extension (space_id, content_rules)
+    VALUES ($1, $2)
+ON CONFLICT (space_id)
+    DO UPDATE SET
+        content_rules = EXCLUDED.content_rules;
@@ -0,0 +1,20 @@
+WITH upserted AS (
+    INSERT INTO space_templates (space_id, name, description, is_public)
+    VALUES ($1, $2, $3, $4)
+    ON CONFLICT (space_id) DO UPDATE
+    SET name = excluded.name,
+        description = excluded.description,
+        is_public = excluded.is_public,
+        modified = now()
+    RETURNING space_id, name, description, is_public, created, modified
+)
+SELECT
+    upserted.space_id,
+    space.owner_id,
+    upserted.name,
+    upserted.description,
+    upserted.is_public,
+    upserted.created,
+    upserted.modified
+FROM upserted
+JOIN spaces space ON space.id = upserted.space_id;
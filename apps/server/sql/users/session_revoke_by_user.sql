@@ -0,0 +1,9 @@
+UPDATE
+    user_sessions
+SET
+    active = FALSE
+WHERE
+    user_id = $1
+    AND active = TRUE
+RETURNING
+    id;
@@ -0,0 +1,6 @@
+pub mod api;
+mod handlers;
+pub mod models;
+
+pub use handlers::{is_site_admin, router};
+pub use models::{AdminAction, AdminAuditEntry, AdminSpace, AdminUser};
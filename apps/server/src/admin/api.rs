@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::space_runtime::SpaceRuntimeStats;
+
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminSearchUsers {
+    /// Matches username, nickname or email
+    #[serde(default)]
+    pub query: Option<String>,
+    #[serde(default)]
+    pub id: Option<Uuid>,
+    #[serde(default)]
+    #[specta(type = Option<f64>)]
+    pub limit: Option<i64>,
+    #[serde(default)]
+    #[specta(type = Option<f64>)]
+    pub offset: Option<i64>,
+}
+
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminSearchSpaces {
+    /// Matches name or description
+    #[serde(default)]
+    pub query: Option<String>,
+    #[serde(default)]
+    pub owner_id: Option<Uuid>,
+    #[serde(default)]
+    pub include_deleted: bool,
+    #[serde(default)]
+    #[specta(type = Option<f64>)]
+    pub limit: Option<i64>,
+    #[serde(default)]
+    #[specta(type = Option<f64>)]
+    pub offset: Option<i64>,
+}
+
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminUserAction {
+    pub user_id: Uuid,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminDeleteSpace {
+    pub space_id: Uuid,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminPurgeCache {
+    /// One of the `CacheType` names, e.g. `Session`
+    pub cache: String,
+}
+
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminCollectMedia {
+    /// Only report the media which would be removed
+    pub dry_run: bool,
+    /// Keep media created in this many hours, 7 days by default
+    #[serde(default)]
+    pub grace_hours: Option<u32>,
+    #[serde(default)]
+    #[specta(type = Option<f64>)]
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminPurgeCacheResult {
+    pub cache: String,
+    /// The entries removed from the cache of the node which served the request
+    pub removed: usize,
+}
+
+#[derive(Serialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminRuntimeOverview {
+    pub mailboxes: usize,
+    pub runtimes: Vec<SpaceRuntimeStats>,
+    /// Pending actions of each space mailbox, keyed by space id
+    pub mailbox_queue_depths: HashMap<Uuid, usize>,
+}
+
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminAuditQuery {
+    #[serde(default)]
+    pub target_id: Option<Uuid>,
+    /// The id of the last entry of the previous page
+    #[serde(default)]
+    pub before: Option<Uuid>,
+    #[serde(default)]
+    #[specta(type = Option<f64>)]
+    pub limit: Option<i64>,
+}
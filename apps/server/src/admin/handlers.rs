@@ -0,0 +1,385 @@
+use std::collections::HashMap;
+
+use super::api::{
+    AdminAuditQuery, AdminCollectMedia, AdminDeleteSpace, AdminPurgeCache, AdminPurgeCacheResult,
+    AdminRuntimeOverview, AdminSearchSpaces, AdminSearchUsers, AdminUserAction,
+};
+use super::models::{AdminAction, AdminAuditEntry, AdminSpace, AdminUser};
+use crate::cache::{CACHE, CacheStats, CacheType};
+use crate::committed_changes::CommittedChanges;
+use crate::csrf::authenticate;
+use crate::error::{AppError, Find};
+use crate::interface::{self, Response, missing, parse_query, response};
+use crate::media::gc::{self, MediaGcReport};
+use crate::server_metrics::ServerMetricsSnapshot;
+use crate::session::{Session, revoke_user_sessions};
+use crate::spaces::Space;
+use hyper::Request;
+use hyper::body::Body;
+use serde_json::json;
+use uuid::Uuid;
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+fn page(limit: Option<i64>, offset: Option<i64>) -> (i64, i64) {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let offset = offset.unwrap_or(0).max(0);
+    (limit, offset)
+}
+
+/// Site admins are configured with `SITE_ADMINS`, there is no way to grant it through the API.
+pub fn is_site_admin(ctx: &crate::context::AppContext, user_id: &Uuid) -> bool {
+    ctx.config.site_admin_ids.contains(user_id)
+}
+
+async fn authenticate_site_admin(
+    ctx: &crate::context::AppContext,
+    req: &Request<impl Body>,
+) -> Result<Session, AppError> {
+    let session = authenticate(ctx, req).await?;
+    if !is_site_admin(ctx, &session.user_id) {
+        tracing::warn!(
+            user_id = %session.user_id,
+            path = req.uri().path(),
+            "A non-admin user tried to access the admin API"
+        );
+        return Err(AppError::NoPermission(
+            "Only site admins can do this".to_string(),
+        ));
+    }
+    Ok(session)
+}
+
+async fn users(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Vec<AdminUser>, AppError> {
+    let session = authenticate_site_admin(ctx, &req).await?;
+    let AdminSearchUsers {
+        query,
+        id,
+        limit,
+        offset,
+    } = parse_query(req.uri())?;
+    let (limit, offset) = page(limit, offset);
+    let users = AdminUser::search(&ctx.db, query.as_deref(), id, limit, offset).await?;
+    AdminAuditEntry::record(
+        &ctx.db,
+        session.user_id,
+        AdminAction::SearchUsers,
+        id,
+        json!({ "query": query, "limit": limit, "offset": offset }),
+    )
+    .await?;
+    Ok(users)
+}
+
+async fn spaces(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Vec<AdminSpace>, AppError> {
+    let session = authenticate_site_admin(ctx, &req).await?;
+    let AdminSearchSpaces {
+        query,
+        owner_id,
+        include_deleted,
+        limit,
+        offset,
+    } = parse_query(req.uri())?;
+    let (limit, offset) = page(limit, offset);
+    let spaces = AdminSpace::search(
+        &ctx.db,
+        query.as_deref(),
+        owner_id,
+        include_deleted,
+        limit,
+        offset,
+    )
+    .await?;
+    AdminAuditEntry::record(
+        &ctx.db,
+        session.user_id,
+        AdminAction::SearchSpaces,
+        owner_id,
+        json!({
+            "query": query,
+            "includeDeleted": include_deleted,
+            "limit": limit,
+            "offset": offset,
+        }),
+    )
+    .await?;
+    Ok(spaces)
+}
+
+async fn set_user_deactivated(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+    deactivated: bool,
+) -> Result<AdminUser, AppError> {
+    let session = authenticate_site_admin(ctx, &req).await?;
+    let AdminUserAction { user_id, reason } = interface::parse_body(req).await?;
+    if deactivated && user_id == session.user_id {
+        return Err(AppError::BadRequest(
+            "You can not deactivate yourself".to_string(),
+        ));
+    }
+    let mut trans = ctx.db.begin().await?;
+    let user = AdminUser::set_deactivated(&mut *trans, user_id, deactivated)
+        .await
+        .or_not_found()?;
+    let action = if deactivated {
+        AdminAction::DeactivateUser
+    } else {
+        AdminAction::ReactivateUser
+    };
+    AdminAuditEntry::record(
+        &mut *trans,
+        session.user_id,
+        action,
+        Some(user_id),
+        json!({ "reason": reason }),
+    )
+    .await?;
+    trans.commit().await?;
+
+    let redis = ctx.redis.as_ref();
+    CACHE.invalidate(redis, CacheType::User, user_id).await;
+    CACHE.invalidate(redis, CacheType::UserExt, user_id).await;
+    if deactivated {
+        let revoked = revoke_user_sessions(&ctx.db, redis, user_id).await?;
+        tracing::info!(
+            admin_id = %session.user_id,
+            %user_id,
+            revoked_sessions = revoked,
+            "A user was deactivated by a site admin"
+        );
+    } else {
+        tracing::info!(
+            admin_id = %session.user_id,
+            %user_id,
+            "A user was reactivated by a site admin"
+        );
+    }
+    Ok(user)
+}
+
+async fn delete_space(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Space, AppError> {
+    let session = authenticate_site_admin(ctx, &req).await?;
+    let AdminDeleteSpace { space_id, reason } = interface::parse_body(req).await?;
+    let mutation = ctx.space_store.acquire_mutation(space_id).await?;
+    let mut trans = ctx.db.begin().await?;
+    let space = Space::get_by_id(&mut *trans, &space_id)
+        .await
+        .or_not_found()?;
+    let member_user_ids = Space::delete(&mut trans, space_id)
+        .await?
+        .ok_or(AppError::NotFound("space"))?;
+    AdminAuditEntry::record(
+        &mut *trans,
+        session.user_id,
+        AdminAction::DeleteSpace,
+        Some(space_id),
+        json!({ "reason": reason, "ownerId": space.owner_id, "name": space.name }),
+    )
+    .await?;
+    let mutation = mutation.commit(trans).await?;
+    let mut changes = CommittedChanges::default();
+    changes.space_deleted(space_id, member_user_ids);
+    changes.apply_with_mutation(ctx, &mutation).await;
+    tracing::info!(
+        admin_id = %session.user_id,
+        %space_id,
+        "A space was force-deleted by a site admin"
+    );
+    Ok(space)
+}
+
+async fn metrics(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<ServerMetricsSnapshot, AppError> {
+    let session = authenticate_site_admin(ctx, &req).await?;
+    let snapshot = crate::server_metrics::snapshot(&ctx.db).await;
+    AdminAuditEntry::record(
+        &ctx.db,
+        session.user_id,
+        AdminAction::ReadMetrics,
+        None,
+        json!({}),
+    )
+    .await?;
+    Ok(snapshot)
+}
+
+async fn cache_stats(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Vec<CacheStats>, AppError> {
+    let session = authenticate_site_admin(ctx, &req).await?;
+    let stats = CACHE.stats();
+    AdminAuditEntry::record(
+        &ctx.db,
+        session.user_id,
+        AdminAction::ReadCacheStats,
+        None,
+        json!({}),
+    )
+    .await?;
+    Ok(stats)
+}
+
+/// Purges a cache on this node and publishes the purge to the other nodes.
+async fn purge_cache(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<AdminPurgeCacheResult, AppError> {
+    let session = authenticate_site_admin(ctx, &req).await?;
+    let AdminPurgeCache { cache } = interface::parse_body(req).await?;
+    let Some(cache_type) = CacheType::from_str(&cache) else {
+        return Err(AppError::BadRequest(format!("Unknown cache type: {cache}")));
+    };
+    let removed = CACHE.purge(ctx.redis.as_ref(), cache_type).await;
+    AdminAuditEntry::record(
+        &ctx.db,
+        session.user_id,
+        AdminAction::PurgeCache,
+        None,
+        json!({ "cache": cache_type.to_str(), "removed": removed }),
+    )
+    .await?;
+    tracing::info!(
+        admin_id = %session.user_id,
+        cache = cache_type.to_str(),
+        removed,
+        "A cache was purged by a site admin"
+    );
+    Ok(AdminPurgeCacheResult { cache, removed })
+}
+
+async fn collect_media(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<MediaGcReport, AppError> {
+    let session = authenticate_site_admin(ctx, &req).await?;
+    let AdminCollectMedia {
+        dry_run,
+        grace_hours,
+        limit,
+    } = interface::parse_body(req).await?;
+    let grace_period = grace_hours.map_or(gc::DEFAULT_GRACE_PERIOD, |hours| {
+        std::time::Duration::from_secs(u64::from(hours) * 60 * 60)
+    });
+    let (limit, _) = page(limit, None);
+    let report = gc::collect(ctx, grace_period, limit, dry_run).await?;
+    AdminAuditEntry::record(
+        &ctx.db,
+        session.user_id,
+        AdminAction::CollectMedia,
+        None,
+        json!({
+            "dryRun": dry_run,
+            "graceHours": grace_hours,
+            "removed": report.media_ids.len(),
+            "bytes": report.bytes,
+            "failed": report.failed_ids,
+        }),
+    )
+    .await?;
+    Ok(report)
+}
+
+async fn runtimes(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<AdminRuntimeOverview, AppError> {
+    let session = authenticate_site_admin(ctx, &req).await?;
+    let runtimes = ctx.space_store.runtime_stats();
+    let mailbox_queue_depths: HashMap<_, _> = runtimes
+        .iter()
+        .filter_map(|runtime| {
+            crate::events::context::mailbox_queue_depth(&runtime.space_id)
+                .map(|depth| (runtime.space_id, depth))
+        })
+        .collect();
+    let overview = AdminRuntimeOverview {
+        mailboxes: crate::events::context::mailbox_count(),
+        runtimes,
+        mailbox_queue_depths,
+    };
+    AdminAuditEntry::record(
+        &ctx.db,
+        session.user_id,
+        AdminAction::InspectRuntimes,
+        None,
+        json!({ "runtimes": overview.runtimes.len() }),
+    )
+    .await?;
+    Ok(overview)
+}
+
+async fn audit(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Vec<AdminAuditEntry>, AppError> {
+    let session = authenticate_site_admin(ctx, &req).await?;
+    let AdminAuditQuery {
+        target_id,
+        before,
+        limit,
+    } = parse_query(req.uri())?;
+    let (limit, _) = page(limit, None);
+    let entries = AdminAuditEntry::list(&ctx.db, target_id, before, limit).await?;
+    AdminAuditEntry::record(
+        &ctx.db,
+        session.user_id,
+        AdminAction::ReadAuditLog,
+        target_id,
+        json!({ "before": before, "limit": limit }),
+    )
+    .await?;
+    Ok(entries)
+}
+
+pub async fn router(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+    path: &str,
+) -> Result<Response, AppError> {
+    use hyper::Method;
+
+    match (path, req.method().clone()) {
+        ("/users", Method::GET) => response(users(ctx, req).await).await,
+        ("/spaces", Method::GET) => response(spaces(ctx, req).await).await,
+        ("/deactivate_user", Method::POST) => {
+            response(set_user_deactivated(ctx, req, true).await).await
+        }
+        ("/reactivate_user", Method::POST) => {
+            response(set_user_deactivated(ctx, req, false).await).await
+        }
+        ("/delete_space", Method::POST) => response(delete_space(ctx, req).await).await,
+        ("/metrics", Method::GET) => response(metrics(ctx, req).await).await,
+        ("/cache", Method::GET) => response(cache_stats(ctx, req).await).await,
+        ("/purge_cache", Method::POST) => response(purge_cache(ctx, req).await).await,
+        ("/collect_media", Method::POST) => response(collect_media(ctx, req).await).await,
+        ("/runtimes", Method::GET) => response(runtimes(ctx, req).await).await,
+        ("/audit", Method::GET) => response(audit(ctx, req).await).await,
+        _ => missing(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_is_clamped() {
+        assert_eq!(page(None, None), (DEFAULT_PAGE_SIZE, 0));
+        assert_eq!(page(Some(0), Some(-3)), (1, 0));
+        assert_eq!(page(Some(10_000), Some(20)), (MAX_PAGE_SIZE, 20));
+    }
+}
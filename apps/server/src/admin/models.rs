@@ -0,0 +1,270 @@
+use serde::Serialize;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::spaces::Space;
+use crate::users::User;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdminAction {
+    SearchUsers,
+    SearchSpaces,
+    DeactivateUser,
+    ReactivateUser,
+    DeleteSpace,
+    ReadMetrics,
+    ReadCacheStats,
+    PurgeCache,
+    InspectRuntimes,
+    ReadAuditLog,
+    ListReports,
+    HandleReport,
+    CollectMedia,
+}
+
+impl AdminAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AdminAction::SearchUsers => "search_users",
+            AdminAction::SearchSpaces => "search_spaces",
+            AdminAction::DeactivateUser => "deactivate_user",
+            AdminAction::ReactivateUser => "reactivate_user",
+            AdminAction::DeleteSpace => "delete_space",
+            AdminAction::ReadMetrics => "read_metrics",
+            AdminAction::ReadCacheStats => "read_cache_stats",
+            AdminAction::PurgeCache => "purge_cache",
+            AdminAction::InspectRuntimes => "inspect_runtimes",
+            AdminAction::ReadAuditLog => "read_audit_log",
+            AdminAction::ListReports => "list_reports",
+            AdminAction::HandleReport => "handle_report",
+            AdminAction::CollectMedia => "collect_media",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, specta::Type, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminAuditEntry {
+    pub id: Uuid,
+    /// `None` once the acting user has been removed.
+    pub actor_id: Option<Uuid>,
+    pub action: String,
+    pub target_id: Option<Uuid>,
+    pub detail: serde_json::Value,
+    #[specta(type = String)]
+    #[serde(with = "time::serde::rfc3339")]
+    pub created: OffsetDateTime,
+}
+
+impl AdminAuditEntry {
+    pub async fn record<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        actor_id: Uuid,
+        action: AdminAction,
+        target_id: Option<Uuid>,
+        detail: serde_json::Value,
+    ) -> Result<AdminAuditEntry, sqlx::Error> {
+        sqlx::query_file_as!(
+            AdminAuditEntry,
+            "sql/admin/record_audit.sql",
+            actor_id,
+            action.as_str(),
+            target_id,
+            detail
+        )
+        .fetch_one(db)
+        .await
+    }
+
+    /// Newest first. `before` is the id of the last entry of the previous page.
+    pub async fn list<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        target_id: Option<Uuid>,
+        before: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<AdminAuditEntry>, sqlx::Error> {
+        sqlx::query_file_as!(
+            AdminAuditEntry,
+            "sql/admin/list_audit.sql",
+            target_id,
+            before,
+            limit
+        )
+        .fetch_all(db)
+        .await
+    }
+}
+
+/// A user as seen by site admins, including the fields `User` never serializes.
+#[derive(Debug, Serialize, Clone, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminUser {
+    pub id: Uuid,
+    pub email: String,
+    pub username: String,
+    pub nickname: String,
+    #[specta(type = String)]
+    #[serde(with = "time::serde::rfc3339")]
+    pub joined: OffsetDateTime,
+    pub deactivated: bool,
+    pub avatar_id: Option<Uuid>,
+}
+
+impl From<User> for AdminUser {
+    fn from(user: User) -> Self {
+        AdminUser {
+            id: user.id,
+            email: user.email,
+            username: user.username,
+            nickname: user.nickname,
+            joined: user.joined,
+            deactivated: user.deactivated,
+            avatar_id: user.avatar_id,
+        }
+    }
+}
+
+impl AdminUser {
+    /// Unlike the public user queries, deactivated users are included.
+    pub async fn search<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        query: Option<&str>,
+        id: Option<Uuid>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<AdminUser>, sqlx::Error> {
+        let pattern = query.map(crate::utils::like_pattern);
+        let users =
+            sqlx::query_file_scalar!("sql/admin/search_users.sql", pattern, id, limit, offset)
+                .fetch_all(db)
+                .await?;
+        Ok(users.into_iter().map(AdminUser::from).collect())
+    }
+
+    pub async fn set_deactivated<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        id: Uuid,
+        deactivated: bool,
+    ) -> Result<Option<AdminUser>, sqlx::Error> {
+        let user = sqlx::query_file_scalar!("sql/admin/set_user_deactivated.sql", id, deactivated)
+            .fetch_optional(db)
+            .await?;
+        Ok(user.map(AdminUser::from))
+    }
+}
+
+#[derive(Debug, Serialize, Clone, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminSpace {
+    pub space: Space,
+    pub deleted: bool,
+}
+
+impl AdminSpace {
+    pub async fn search<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        query: Option<&str>,
+        owner_id: Option<Uuid>,
+        include_deleted: bool,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<AdminSpace>, sqlx::Error> {
+        let pattern = query.map(crate::utils::like_pattern);
+        let spaces = sqlx::query_file_scalar!(
+            "sql/admin/search_spaces.sql",
+            pattern,
+            owner_id,
+            include_deleted,
+            limit,
+            offset
+        )
+        .fetch_all(db)
+        .await?;
+        Ok(spaces
+            .into_iter()
+            .map(|space| AdminSpace {
+                deleted: space.deleted,
+                space,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn create_test_user(pool: &sqlx::PgPool, email: &str, username: &str) -> User {
+        User::register(pool, email, username, "Admin Tester", "AdminPass123!")
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn db_test_admin_user_search_includes_deactivated(pool: sqlx::PgPool) {
+        let user = create_test_user(&pool, "admin-search@example.com", "admin_search").await;
+
+        let deactivated = AdminUser::set_deactivated(&pool, user.id, true)
+            .await
+            .unwrap()
+            .expect("user should exist");
+        assert!(deactivated.deactivated);
+        assert!(User::get_by_id(&pool, &user.id).await.unwrap().is_none());
+
+        let found = AdminUser::search(&pool, Some("ADMIN_SEARCH"), None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].email, "admin-search@example.com");
+        assert!(found[0].deactivated);
+
+        let found = AdminUser::search(&pool, Some("admin%"), None, 10, 0)
+            .await
+            .unwrap();
+        assert!(found.is_empty());
+
+        let reactivated = AdminUser::set_deactivated(&pool, user.id, false)
+            .await
+            .unwrap()
+            .expect("user should exist");
+        assert!(!reactivated.deactivated);
+        assert!(User::get_by_id(&pool, &user.id).await.unwrap().is_some());
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn db_test_admin_audit_log_pages_newest_first(pool: sqlx::PgPool) {
+        let admin = create_test_user(&pool, "admin-audit@example.com", "admin_audit").await;
+        let target = crate::utils::id();
+
+        let first = AdminAuditEntry::record(
+            &pool,
+            admin.id,
+            AdminAction::DeactivateUser,
+            Some(target),
+            serde_json::json!({ "reason": "spam" }),
+        )
+        .await
+        .unwrap();
+        let second = AdminAuditEntry::record(
+            &pool,
+            admin.id,
+            AdminAction::ReadMetrics,
+            None,
+            serde_json::json!({}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(first.action, "deactivate_user");
+
+        let page = AdminAuditEntry::list(&pool, None, None, 1).await.unwrap();
+        assert_eq!(page, vec![second.clone()]);
+        let page = AdminAuditEntry::list(&pool, None, Some(second.id), 10)
+            .await
+            .unwrap();
+        assert_eq!(page, vec![first.clone()]);
+        let by_target = AdminAuditEntry::list(&pool, Some(target), None, 10)
+            .await
+            .unwrap();
+        assert_eq!(by_target, vec![first]);
+    }
+}
@@ -3,4 +3,4 @@ pub(crate) mod handlers;
 pub(crate) mod models;
 
 pub use handlers::router;
-pub use models::{Asset, AssetPolicy};
+pub use models::{Asset, AssetFilter, AssetFolder, AssetPolicy};
@@ -1,7 +1,8 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use super::AssetPolicy;
+use super::models::{AssetCharacterUsage, AssetEntryUsage, AssetSort};
 
 #[derive(Debug, Deserialize, specta::Type)]
 #[serde(rename_all = "camelCase")]
@@ -14,6 +15,37 @@ pub struct QueryAsset {
 #[serde(rename_all = "camelCase")]
 pub struct ListAssets {
     pub space_id: Uuid,
+    /// Only the Assets in this folder, not including its subfolders
+    #[serde(default)]
+    pub folder_id: Option<Uuid>,
+    /// Only the Assets not in any folder
+    #[serde(default)]
+    pub root: bool,
+    /// Search the names and tags
+    #[serde(default)]
+    pub query: Option<String>,
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// Such as `image` or `audio`
+    #[serde(default)]
+    pub mime_family: Option<String>,
+    #[serde(default)]
+    pub sort: AssetSort,
+    /// Defaults to the newest first, or names from A to Z
+    #[serde(default)]
+    pub descending: Option<bool>,
+    #[serde(default)]
+    #[specta(type = f64)]
+    pub offset: i64,
+    #[serde(default)]
+    #[specta(type = Option<f64>)]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ListAssetFolders {
+    pub space_id: Uuid,
 }
 
 #[derive(Debug, Deserialize, specta::Type)]
@@ -24,6 +56,10 @@ pub struct CreateAsset {
     pub name: String,
     #[serde(default)]
     pub policy: AssetPolicy,
+    #[serde(default)]
+    pub folder_id: Option<Uuid>,
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 #[derive(Debug, Deserialize, specta::Type)]
@@ -32,6 +68,9 @@ pub struct UpdateAsset {
     pub asset_id: Uuid,
     pub name: String,
     pub policy: AssetPolicy,
+    /// Keep the tags if absent
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
 }
 
 #[derive(Debug, Deserialize, specta::Type)]
@@ -39,3 +78,53 @@ pub struct UpdateAsset {
 pub struct DeleteAsset {
     pub asset_id: Uuid,
 }
+
+#[derive(Debug, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveAssets {
+    pub space_id: Uuid,
+    pub asset_ids: Vec<Uuid>,
+    /// Move to the top level if absent
+    #[serde(default)]
+    pub folder_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateAssetFolder {
+    pub space_id: Uuid,
+    #[serde(default)]
+    pub parent_id: Option<Uuid>,
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateAssetFolder {
+    pub folder_id: Uuid,
+    pub name: String,
+    /// Move to the top level if absent
+    #[serde(default)]
+    pub parent_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteAssetFolder {
+    pub folder_id: Uuid,
+}
+
+/// What refers to an Asset, to check before deleting it.
+#[derive(Debug, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetUsages {
+    /// The Entry components using the Asset, in the Scopes the user can view
+    pub entries: Vec<AssetEntryUsage>,
+    /// The number of Entry components in Scopes the user can't view
+    #[specta(type = f64)]
+    pub hidden_entries: i64,
+    pub characters: Vec<AssetCharacterUsage>,
+    /// The messages with the Asset as the portrait, which lose it if the Asset is deleted
+    #[specta(type = f64)]
+    pub messages: i64,
+}
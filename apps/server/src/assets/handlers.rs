@@ -1,13 +1,25 @@
 use hyper::Request;
 use hyper::body::Body;
+use std::collections::HashMap;
+use uuid::Uuid;
 
-use super::Asset;
-use super::api::{CreateAsset, DeleteAsset, ListAssets, QueryAsset, UpdateAsset};
+use super::api::{
+    AssetUsages, CreateAsset, CreateAssetFolder, DeleteAsset, DeleteAssetFolder, ListAssetFolders,
+    ListAssets, MoveAssets, QueryAsset, UpdateAsset, UpdateAssetFolder,
+};
+use super::models::AssetSort;
+use super::{Asset, AssetFilter, AssetFolder};
 use crate::csrf::{authenticate, authenticate_optional};
+use crate::entries::handlers::can_view_scope;
 use crate::error::{AppError, Find};
 use crate::interface::{missing, parse_body, parse_query, response};
 use crate::media::models::Media;
-use crate::spaces::{SpaceMember, resolve_space_access};
+use crate::media::{audio, ensure_audio_duration};
+use crate::spaces::{SpaceAccess, SpaceMember, resolve_space_access};
+
+const DEFAULT_PAGE_SIZE: i64 = 100;
+const MAX_PAGE_SIZE: i64 = 500;
+const MAX_MOVE_ASSETS: usize = 500;
 
 async fn query(
     ctx: &crate::context::AppContext,
@@ -44,17 +56,100 @@ async fn by_space(
     req: Request<impl Body>,
 ) -> Result<Vec<Asset>, AppError> {
     let session = authenticate_optional(ctx, &req).await?;
-    let ListAssets { space_id } = parse_query(req.uri())?;
+    let ListAssets {
+        space_id,
+        folder_id,
+        root,
+        query,
+        tag,
+        mime_family,
+        sort,
+        descending,
+        offset,
+        limit,
+    } = parse_query(req.uri())?;
     let access =
         resolve_space_access(ctx, space_id, session.map(|session| session.user_id)).await?;
+    check_library_access(access)?;
+    if offset < 0 {
+        return Err(AppError::BadRequest(
+            "The offset can not be negative".to_string(),
+        ));
+    }
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let filter = AssetFilter {
+        folder_id,
+        root,
+        query,
+        tag,
+        mime_family,
+        sort,
+        descending: descending.unwrap_or(sort == AssetSort::Created),
+    };
+    Asset::query(&ctx.db, space_id, &filter, offset, limit)
+        .await
+        .map_err(Into::into)
+}
+
+fn check_library_access(access: SpaceAccess) -> Result<(), AppError> {
     if !access.is_member && !access.can_manage() {
         return Err(AppError::NoPermission(
             "You don't have permission to view Assets in this Space".to_string(),
         ));
     }
-    Asset::list_by_space(&ctx.db, space_id)
+    Ok(())
+}
+
+async fn usages(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<AssetUsages, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let QueryAsset { space_id, asset_id } = parse_query(req.uri())?;
+    let asset = Asset::get_by_id_in_space(&ctx.db, space_id, asset_id)
         .await
-        .map_err(Into::into)
+        .or_not_found()?;
+    let access = resolve_space_access(ctx, space_id, Some(session.user_id)).await?;
+    if !asset
+        .policy
+        .can_edit(asset.creator_id, session.user_id, access)
+    {
+        return Err(AppError::NoPermission(
+            "You don't have permission to view the usages of this Asset".to_string(),
+        ));
+    }
+    let entry_usages = Asset::list_entry_usages(&ctx.db, space_id, asset_id).await?;
+    let character_usages = Asset::list_character_usages(&ctx.db, space_id, asset_id).await?;
+    let messages = Asset::count_message_usages(&ctx.db, asset_id).await?;
+
+    let mut viewable_scopes: HashMap<Uuid, bool> = HashMap::new();
+    let scope_ids = entry_usages
+        .iter()
+        .map(|usage| usage.scope_id)
+        .chain(character_usages.iter().map(|usage| usage.main_scope_id));
+    for scope_id in scope_ids {
+        if viewable_scopes.contains_key(&scope_id) {
+            continue;
+        }
+        let viewable = match ctx.space_store.resolve_scope(space_id, scope_id).await? {
+            Some(scope) => can_view_scope(ctx, &scope, Some(session.user_id)).await?,
+            None => false,
+        };
+        viewable_scopes.insert(scope_id, viewable);
+    }
+    let (entries, hidden): (Vec<_>, Vec<_>) = entry_usages
+        .into_iter()
+        .partition(|usage| viewable_scopes[&usage.scope_id]);
+    let characters = character_usages
+        .into_iter()
+        .filter(|usage| viewable_scopes[&usage.main_scope_id])
+        .collect();
+    Ok(AssetUsages {
+        entries,
+        hidden_entries: hidden.len() as i64,
+        characters,
+        messages,
+    })
 }
 
 async fn create(
@@ -67,11 +162,18 @@ async fn create(
         media_id,
         name,
         policy,
+        folder_id,
+        tags,
     } = parse_body(req).await?;
     let mut transaction = ctx.db.begin().await?;
     SpaceMember::get(&mut *transaction, &session.user_id, &space_id)
         .await?
         .or_no_permission()?;
+    if let Some(folder_id) = folder_id {
+        AssetFolder::get_in_space(&mut *transaction, space_id, folder_id)
+            .await
+            .or_not_found()?;
+    }
     let media = Media::get_by_id(&mut *transaction, &media_id)
         .await
         .or_not_found()?;
@@ -80,12 +182,18 @@ async fn create(
             "Only the uploader can register this Media as an Asset".to_string(),
         ));
     }
-    let asset = Asset::create(
+    if audio::is_audio(&media.mime_type) {
+        // Only audio that can be read becomes an Asset, with its duration known.
+        ensure_audio_duration(ctx, media).await?;
+    }
+    let asset = Asset::create_in_folder(
         &mut transaction,
         space_id,
         media_id,
+        folder_id,
         session.user_id,
         &name,
+        tags,
         policy,
     )
     .await?;
@@ -102,6 +210,7 @@ async fn update(
         asset_id,
         name,
         policy,
+        tags,
     } = parse_body(req).await?;
     let mut transaction = ctx.db.begin().await?;
     let asset = Asset::get_by_id_for_update(&mut transaction, asset_id)
@@ -122,7 +231,7 @@ async fn update(
             "Only the Asset creator can change its policy".to_string(),
         ));
     }
-    let asset = Asset::update(&mut transaction, asset_id, &name, policy).await?;
+    let asset = Asset::update(&mut transaction, asset_id, &name, policy, tags).await?;
     transaction.commit().await?;
     Ok(asset)
 }
@@ -153,6 +262,146 @@ async fn delete(
     Ok(asset)
 }
 
+async fn move_assets(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Vec<Asset>, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let MoveAssets {
+        space_id,
+        mut asset_ids,
+        folder_id,
+    } = parse_body(req).await?;
+    asset_ids.sort_unstable();
+    asset_ids.dedup();
+    if asset_ids.len() > MAX_MOVE_ASSETS {
+        return Err(AppError::BadRequest(format!(
+            "At most {MAX_MOVE_ASSETS} Assets can be moved at once"
+        )));
+    }
+    let access = resolve_space_access(ctx, space_id, Some(session.user_id)).await?;
+    let mut transaction = ctx.db.begin().await?;
+    if let Some(folder_id) = folder_id {
+        AssetFolder::get_in_space(&mut *transaction, space_id, folder_id)
+            .await
+            .or_not_found()?;
+    }
+    let assets = Asset::list_by_ids_for_update(&mut transaction, space_id, &asset_ids).await?;
+    if assets.len() != asset_ids.len() {
+        return Err(AppError::NotFound("Asset"));
+    }
+    if !assets.iter().all(|asset| {
+        asset
+            .policy
+            .can_edit(asset.creator_id, session.user_id, access)
+    }) {
+        return Err(AppError::NoPermission(
+            "You don't have permission to edit these Assets".to_string(),
+        ));
+    }
+    let assets = Asset::move_to_folder(&mut transaction, space_id, &asset_ids, folder_id).await?;
+    transaction.commit().await?;
+    Ok(assets)
+}
+
+async fn folders(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Vec<AssetFolder>, AppError> {
+    let session = authenticate_optional(ctx, &req).await?;
+    let ListAssetFolders { space_id } = parse_query(req.uri())?;
+    let access =
+        resolve_space_access(ctx, space_id, session.map(|session| session.user_id)).await?;
+    check_library_access(access)?;
+    AssetFolder::list_by_space(&ctx.db, space_id)
+        .await
+        .map_err(Into::into)
+}
+
+async fn create_folder(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<AssetFolder, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let CreateAssetFolder {
+        space_id,
+        parent_id,
+        name,
+    } = parse_body(req).await?;
+    let mut transaction = ctx.db.begin().await?;
+    SpaceMember::get(&mut *transaction, &session.user_id, &space_id)
+        .await?
+        .or_no_permission()?;
+    if let Some(parent_id) = parent_id {
+        AssetFolder::get_in_space(&mut *transaction, space_id, parent_id)
+            .await
+            .or_not_found()?;
+    }
+    let folder = AssetFolder::create(
+        &mut transaction,
+        space_id,
+        parent_id,
+        session.user_id,
+        &name,
+    )
+    .await?;
+    transaction.commit().await?;
+    Ok(folder)
+}
+
+async fn update_folder(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<AssetFolder, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let UpdateAssetFolder {
+        folder_id,
+        name,
+        parent_id,
+    } = parse_body(req).await?;
+    let mut transaction = ctx.db.begin().await?;
+    let folder = AssetFolder::get_for_update(&mut transaction, folder_id)
+        .await
+        .or_not_found()?;
+    let access = resolve_space_access(ctx, folder.space_id, Some(session.user_id)).await?;
+    if !folder.can_edit(session.user_id, access) {
+        return Err(AppError::NoPermission(
+            "You don't have permission to edit this folder".to_string(),
+        ));
+    }
+    if let Some(parent_id) = parent_id {
+        AssetFolder::get_in_space(&mut *transaction, folder.space_id, parent_id)
+            .await
+            .or_not_found()?;
+    }
+    let folder = AssetFolder::update(&mut transaction, &folder, &name, parent_id).await?;
+    transaction.commit().await?;
+    Ok(folder)
+}
+
+async fn delete_folder(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<AssetFolder, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let DeleteAssetFolder { folder_id } = parse_body(req).await?;
+    let mut transaction = ctx.db.begin().await?;
+    let folder = AssetFolder::get_for_update(&mut transaction, folder_id)
+        .await
+        .or_not_found()?;
+    let access = resolve_space_access(ctx, folder.space_id, Some(session.user_id)).await?;
+    if !folder.can_edit(session.user_id, access) {
+        return Err(AppError::NoPermission(
+            "You don't have permission to delete this folder".to_string(),
+        ));
+    }
+    if !AssetFolder::delete(&mut transaction, &folder).await? {
+        return Err(AppError::NotFound("folder"));
+    }
+    transaction.commit().await?;
+    Ok(folder)
+}
+
 pub async fn router(
     ctx: &crate::context::AppContext,
     req: Request<impl Body>,
@@ -167,6 +416,12 @@ pub async fn router(
         ("/create", Method::POST) => response(create(ctx, req).await).await,
         ("/update", Method::POST) => response(update(ctx, req).await).await,
         ("/delete", Method::POST) => response(delete(ctx, req).await).await,
+        ("/usages", Method::GET) => response(usages(ctx, req).await).await,
+        ("/move", Method::POST) => response(move_assets(ctx, req).await).await,
+        ("/folders", Method::GET) => response(folders(ctx, req).await).await,
+        ("/create_folder", Method::POST) => response(create_folder(ctx, req).await).await,
+        ("/update_folder", Method::POST) => response(update_folder(ctx, req).await).await,
+        ("/delete_folder", Method::POST) => response(delete_folder(ctx, req).await).await,
         _ => missing(),
     }
 }
@@ -3,7 +3,7 @@ use time::OffsetDateTime;
 use unicode_normalization::UnicodeNormalization;
 use uuid::Uuid;
 
-use crate::error::ModelError;
+use crate::error::{ModelError, ValidationFailed};
 use crate::spaces::SpaceAccess;
 
 #[derive(
@@ -37,16 +37,52 @@ impl AssetPolicy {
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub enum AssetSort {
+    #[default]
+    Created,
+    Name,
+}
+
+impl AssetSort {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::Name => "name",
+        }
+    }
+}
+
+/// Which listed Assets of a Space to search for.
+#[derive(Debug, Default)]
+pub struct AssetFilter {
+    pub folder_id: Option<Uuid>,
+    /// Only the Assets not in any folder
+    pub root: bool,
+    /// Matched anywhere in the name or a tag, case-insensitively
+    pub query: Option<String>,
+    pub tag: Option<String>,
+    /// The type part of the MIME type, such as `image` or `audio`
+    pub mime_family: Option<String>,
+    pub sort: AssetSort,
+    pub descending: bool,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type, sqlx::FromRow)]
 #[serde(rename_all = "camelCase")]
 pub struct Asset {
     pub id: Uuid,
     pub space_id: Uuid,
     pub media_id: Uuid,
+    pub folder_id: Option<Uuid>,
     pub creator_id: Option<Uuid>,
     pub name: String,
+    pub tags: Vec<String>,
     pub policy: AssetPolicy,
     pub mime_type: String,
+    /// The length of audio Assets
+    pub duration_ms: Option<i32>,
     #[specta(type = String)]
     #[serde(with = "time::serde::rfc3339")]
     pub created: OffsetDateTime,
@@ -60,17 +96,44 @@ impl Asset {
         creator_id: Uuid,
         name: &str,
         policy: AssetPolicy,
+    ) -> Result<Asset, ModelError> {
+        Self::create_in_folder(
+            db,
+            space_id,
+            media_id,
+            None,
+            creator_id,
+            name,
+            Vec::new(),
+            policy,
+        )
+        .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_in_folder(
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        space_id: Uuid,
+        media_id: Uuid,
+        folder_id: Option<Uuid>,
+        creator_id: Uuid,
+        name: &str,
+        tags: Vec<String>,
+        policy: AssetPolicy,
     ) -> Result<Asset, ModelError> {
         let name = name.trim().nfc().collect::<String>();
         crate::validators::ASSET_NAME.run(&name)?;
+        let tags = crate::validators::normalize_tags(tags)?;
         sqlx::query_file_as!(
             Asset,
             "sql/assets/create.sql",
             Uuid::now_v7(),
             space_id,
             media_id,
+            folder_id,
             creator_id,
             name,
+            &tags,
             policy as AssetPolicy,
         )
         .fetch_one(&mut **db)
@@ -106,6 +169,62 @@ impl Asset {
             .await
     }
 
+    /// Search the listed Assets of a Space, a page at a time.
+    pub async fn query<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+        filter: &AssetFilter,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<Asset>, sqlx::Error> {
+        let pattern = filter
+            .query
+            .as_deref()
+            .filter(|query| !query.trim().is_empty())
+            .map(crate::utils::like_pattern);
+        let tag = filter
+            .tag
+            .as_deref()
+            .map(|tag| tag.trim().nfc().collect::<String>())
+            .filter(|tag| !tag.is_empty());
+        let mime_family = filter
+            .mime_family
+            .as_deref()
+            .map(|family| family.trim().to_ascii_lowercase())
+            .filter(|family| !family.is_empty());
+        sqlx::query_file_as!(
+            Asset,
+            "sql/assets/query.sql",
+            space_id,
+            filter.folder_id,
+            filter.root,
+            pattern,
+            tag,
+            mime_family,
+            filter.sort.as_str(),
+            filter.descending,
+            offset,
+            limit,
+        )
+        .fetch_all(db)
+        .await
+    }
+
+    pub async fn list_by_ids_for_update(
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        space_id: Uuid,
+        asset_ids: &[Uuid],
+    ) -> Result<Vec<Asset>, sqlx::Error> {
+        sqlx::query_file_as!(
+            Asset,
+            "sql/assets/list_by_ids_for_update.sql",
+            space_id,
+            asset_ids,
+        )
+        .fetch_all(&mut **db)
+        .await
+    }
+
     pub async fn list_by_creator<'c, T: sqlx::PgExecutor<'c>>(
         db: T,
         creator_id: Uuid,
@@ -120,21 +239,78 @@ impl Asset {
         asset_id: Uuid,
         name: &str,
         policy: AssetPolicy,
+        tags: Option<Vec<String>>,
     ) -> Result<Asset, ModelError> {
         let name = name.trim().nfc().collect::<String>();
         crate::validators::ASSET_NAME.run(&name)?;
+        let tags = tags.map(crate::validators::normalize_tags).transpose()?;
         sqlx::query_file_as!(
             Asset,
             "sql/assets/update.sql",
             asset_id,
             name,
             policy as AssetPolicy,
+            tags.as_deref(),
         )
         .fetch_one(&mut **db)
         .await
         .map_err(Into::into)
     }
 
+    /// Move Assets of the Space into the folder, or to the top level if it's `None`.
+    pub async fn move_to_folder(
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        space_id: Uuid,
+        asset_ids: &[Uuid],
+        folder_id: Option<Uuid>,
+    ) -> Result<Vec<Asset>, ModelError> {
+        sqlx::query_file_as!(Asset, "sql/assets/move.sql", space_id, asset_ids, folder_id)
+            .fetch_all(&mut **db)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn list_entry_usages<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+        asset_id: Uuid,
+    ) -> Result<Vec<AssetEntryUsage>, sqlx::Error> {
+        sqlx::query_file_as!(
+            AssetEntryUsage,
+            "sql/assets/list_entry_usages.sql",
+            space_id,
+            asset_id
+        )
+        .fetch_all(db)
+        .await
+    }
+
+    /// Characters with an Entry using the Asset in their Scopes, or with messages using it as
+    /// the portrait.
+    pub async fn list_character_usages<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+        asset_id: Uuid,
+    ) -> Result<Vec<AssetCharacterUsage>, sqlx::Error> {
+        sqlx::query_file_as!(
+            AssetCharacterUsage,
+            "sql/assets/list_character_usages.sql",
+            space_id,
+            asset_id
+        )
+        .fetch_all(db)
+        .await
+    }
+
+    pub async fn count_message_usages<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        asset_id: Uuid,
+    ) -> Result<i64, sqlx::Error> {
+        sqlx::query_file_scalar!("sql/assets/count_message_usages.sql", asset_id)
+            .fetch_one(db)
+            .await
+    }
+
     pub async fn delete(
         db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         asset_id: Uuid,
@@ -166,6 +342,146 @@ impl Asset {
     }
 }
 
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetEntryUsage {
+    pub entry_id: Uuid,
+    pub scope_id: Uuid,
+    pub display_name: String,
+    pub component_type: String,
+}
+
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetCharacterUsage {
+    pub id: Uuid,
+    pub name: String,
+    #[serde(skip)]
+    pub(crate) main_scope_id: Uuid,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetFolder {
+    pub id: Uuid,
+    pub space_id: Uuid,
+    pub parent_id: Option<Uuid>,
+    pub creator_id: Option<Uuid>,
+    pub name: String,
+    #[specta(type = String)]
+    #[serde(with = "time::serde::rfc3339")]
+    pub created: OffsetDateTime,
+}
+
+impl AssetFolder {
+    pub fn can_edit(&self, user_id: Uuid, access: SpaceAccess) -> bool {
+        self.creator_id == Some(user_id) || access.can_manage()
+    }
+
+    pub async fn create(
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        space_id: Uuid,
+        parent_id: Option<Uuid>,
+        creator_id: Uuid,
+        name: &str,
+    ) -> Result<AssetFolder, ModelError> {
+        let name = name.trim().nfc().collect::<String>();
+        crate::validators::ASSET_NAME.run(&name)?;
+        sqlx::query_file_as!(
+            AssetFolder,
+            "sql/assets/create_folder.sql",
+            Uuid::now_v7(),
+            space_id,
+            parent_id,
+            creator_id,
+            name,
+        )
+        .fetch_one(&mut **db)
+        .await
+        .map_err(Into::into)
+    }
+
+    pub async fn get_in_space<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+        folder_id: Uuid,
+    ) -> Result<Option<AssetFolder>, sqlx::Error> {
+        sqlx::query_file_as!(
+            AssetFolder,
+            "sql/assets/get_folder.sql",
+            space_id,
+            folder_id
+        )
+        .fetch_optional(db)
+        .await
+    }
+
+    pub async fn get_for_update(
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        folder_id: Uuid,
+    ) -> Result<Option<AssetFolder>, sqlx::Error> {
+        sqlx::query_file_as!(
+            AssetFolder,
+            "sql/assets/get_folder_for_update.sql",
+            folder_id
+        )
+        .fetch_optional(&mut **db)
+        .await
+    }
+
+    /// The folders of a Space, parents before their children.
+    pub async fn list_by_space<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+    ) -> Result<Vec<AssetFolder>, sqlx::Error> {
+        sqlx::query_file_as!(AssetFolder, "sql/assets/list_folders.sql", space_id)
+            .fetch_all(db)
+            .await
+    }
+
+    /// Rename the folder or move it into another one.
+    pub async fn update(
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        folder: &AssetFolder,
+        name: &str,
+        parent_id: Option<Uuid>,
+    ) -> Result<AssetFolder, ModelError> {
+        let name = name.trim().nfc().collect::<String>();
+        crate::validators::ASSET_NAME.run(&name)?;
+        if let Some(parent_id) = parent_id {
+            let within =
+                sqlx::query_file_scalar!("sql/assets/is_folder_within.sql", parent_id, folder.id)
+                    .fetch_one(&mut **db)
+                    .await?;
+            if within {
+                return Err(ValidationFailed("A folder can't be moved into itself.").into());
+            }
+        }
+        sqlx::query_file_as!(
+            AssetFolder,
+            "sql/assets/update_folder.sql",
+            folder.id,
+            name,
+            parent_id,
+        )
+        .fetch_one(&mut **db)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Delete the folder, its subfolders and Assets are moved to its parent.
+    pub async fn delete(
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        folder: &AssetFolder,
+    ) -> Result<bool, ModelError> {
+        let deleted =
+            sqlx::query_file_scalar!("sql/assets/delete_folder.sql", folder.id, folder.parent_id)
+                .fetch_optional(&mut **db)
+                .await?;
+        Ok(deleted.is_some())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -195,4 +511,223 @@ mod tests {
         assert!(!AssetPolicy::Listed.can_edit(Some(creator_id), other_id, member));
         assert!(!AssetPolicy::Listed.can_delete(Some(creator_id), other_id, member));
     }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn db_test_asset_folders_search_and_usages(pool: sqlx::PgPool) {
+        use crate::characters::Character;
+        use crate::entries::models::{
+            CORE_PORTRAIT_COMPONENT_TYPE, Entry, EntryComponentPayloadInput,
+        };
+        use crate::media::models::Media;
+        use crate::spaces::{AccessPolicy, Space};
+        use crate::users::User;
+        use std::collections::BTreeMap;
+
+        let user = User::register(
+            &pool,
+            "asset-library@example.com",
+            "asset_library",
+            "Asset Library Tester",
+            "AssetPass123!",
+        )
+        .await
+        .expect("create user failed");
+        let space = Space::create(
+            &pool,
+            "Asset library".to_string(),
+            &user.id,
+            String::new(),
+            None,
+            Some("d20"),
+        )
+        .await
+        .expect("create space failed");
+        let user_id = user.id;
+        let media = |mime_type: &'static str, filename: &'static str| {
+            let pool = pool.clone();
+            async move {
+                Media::create(
+                    &pool,
+                    &Uuid::now_v7(),
+                    mime_type,
+                    user_id,
+                    filename,
+                    filename,
+                    String::new(),
+                    1024,
+                    "test",
+                )
+                .await
+                .expect("create media failed")
+            }
+        };
+        let image = media("image/png", "map.png").await;
+        let audio = media("audio/ogg", "theme.ogg").await;
+
+        let mut transaction = pool.begin().await.expect("begin failed");
+        let maps = AssetFolder::create(&mut transaction, space.id, None, user.id, "Maps")
+            .await
+            .expect("create folder failed");
+        let dungeons = AssetFolder::create(
+            &mut transaction,
+            space.id,
+            Some(maps.id),
+            user.id,
+            "Dungeons",
+        )
+        .await
+        .expect("create subfolder failed");
+        let map = Asset::create_in_folder(
+            &mut transaction,
+            space.id,
+            image.id,
+            Some(dungeons.id),
+            user.id,
+            "Crypt",
+            vec![
+                " Night ".to_string(),
+                "Night".to_string(),
+                "Map".to_string(),
+            ],
+            AssetPolicy::Listed,
+        )
+        .await
+        .expect("create Asset failed");
+        assert_eq!(map.tags, vec!["Night", "Map"]);
+        let theme = Asset::create_in_folder(
+            &mut transaction,
+            space.id,
+            audio.id,
+            None,
+            user.id,
+            "Battle theme",
+            vec!["Music".to_string()],
+            AssetPolicy::Listed,
+        )
+        .await
+        .expect("create Asset failed");
+
+        let search = |filter: AssetFilter| {
+            let pool = pool.clone();
+            let space_id = space.id;
+            async move {
+                Asset::query(&pool, space_id, &filter, 0, 10)
+                    .await
+                    .expect("query Assets failed")
+                    .into_iter()
+                    .map(|asset| asset.id)
+                    .collect::<Vec<_>>()
+            }
+        };
+        transaction.commit().await.expect("commit failed");
+        let mut conflicting = pool.begin().await.expect("begin failed");
+        assert!(matches!(
+            AssetFolder::create(&mut conflicting, space.id, None, user.id, " Maps ").await,
+            Err(ModelError::Conflict(_))
+        ));
+        drop(conflicting);
+        let all = AssetFilter::default();
+        assert_eq!(search(all).await, vec![map.id, theme.id]);
+        let by_name = AssetFilter {
+            sort: AssetSort::Name,
+            ..AssetFilter::default()
+        };
+        assert_eq!(search(by_name).await, vec![theme.id, map.id]);
+        let root = AssetFilter {
+            root: true,
+            ..AssetFilter::default()
+        };
+        assert_eq!(search(root).await, vec![theme.id]);
+        let in_folder = AssetFilter {
+            folder_id: Some(dungeons.id),
+            ..AssetFilter::default()
+        };
+        assert_eq!(search(in_folder).await, vec![map.id]);
+        let by_tag_text = AssetFilter {
+            query: Some("nig".to_string()),
+            ..AssetFilter::default()
+        };
+        assert_eq!(search(by_tag_text).await, vec![map.id]);
+        let by_tag = AssetFilter {
+            tag: Some("Music".to_string()),
+            ..AssetFilter::default()
+        };
+        assert_eq!(search(by_tag).await, vec![theme.id]);
+        let by_family = AssetFilter {
+            mime_family: Some("audio".to_string()),
+            ..AssetFilter::default()
+        };
+        assert_eq!(search(by_family).await, vec![theme.id]);
+
+        let mut transaction = pool.begin().await.expect("begin failed");
+        assert!(matches!(
+            AssetFolder::update(&mut transaction, &maps, "Maps", Some(dungeons.id)).await,
+            Err(ModelError::Validation(_))
+        ));
+        assert!(
+            AssetFolder::delete(&mut transaction, &dungeons)
+                .await
+                .expect("delete folder failed")
+        );
+        let moved = Asset::get_by_id_in_space(&mut *transaction, space.id, map.id)
+            .await
+            .expect("get Asset failed")
+            .expect("Asset should exist");
+        assert_eq!(moved.folder_id, Some(maps.id));
+
+        let character = Character::create(
+            &mut transaction,
+            space.id,
+            user.id,
+            "Cartographer",
+            "cartographer",
+            Vec::new(),
+            "",
+            "",
+            AccessPolicy::Personal,
+            None,
+            Vec::new(),
+        )
+        .await
+        .expect("create Character failed");
+        let entry = Entry::create(
+            &mut transaction,
+            character.scope_id,
+            "crypt_map".to_string(),
+            Vec::new(),
+            "Crypt map".to_string(),
+            None,
+            BTreeMap::from([(
+                CORE_PORTRAIT_COMPONENT_TYPE.to_string(),
+                EntryComponentPayloadInput::Asset { asset_id: map.id },
+            )]),
+            Vec::new(),
+            None,
+        )
+        .await
+        .expect("create Entry failed");
+        let entries = Asset::list_entry_usages(&mut *transaction, space.id, map.id)
+            .await
+            .expect("list Entry usages failed");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].entry_id, entry.metadata.id);
+        assert_eq!(entries[0].component_type, CORE_PORTRAIT_COMPONENT_TYPE);
+        let characters = Asset::list_character_usages(&mut *transaction, space.id, map.id)
+            .await
+            .expect("list Character usages failed");
+        assert_eq!(characters.len(), 1);
+        assert_eq!(characters[0].id, character.id);
+        assert!(
+            Asset::list_character_usages(&mut *transaction, space.id, theme.id)
+                .await
+                .expect("list Character usages failed")
+                .is_empty()
+        );
+        assert_eq!(
+            Asset::count_message_usages(&mut *transaction, map.id)
+                .await
+                .expect("count message usages failed"),
+            0
+        );
+    }
 }
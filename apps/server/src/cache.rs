@@ -12,6 +12,8 @@ trait GetCacheType {
     fn tag() -> CacheType;
 }
 
+#[derive(Debug, Clone, serde::Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
 pub struct CacheStats {
     name: &'static str,
     items: usize,
@@ -80,6 +82,19 @@ macro_rules! define_caches {
                 }
             }
 
+            /// Drop every local entry of a cache type, returning the number of entries removed.
+            pub fn purge_local(&self, cache_type: CacheType) -> usize {
+                match cache_type {
+                    $(
+                        CacheType::$type => {
+                            let items = self.$type.len();
+                            self.$type.clear();
+                            items
+                        }
+                    )*
+                }
+            }
+
             pub async fn invalidate(
                 &self,
                 redis: Option<&redis::aio::ConnectionManager>,
@@ -87,7 +102,23 @@ macro_rules! define_caches {
                 key: Uuid,
             ) {
                 self.invalidate_local(cache_type, key);
-                self.notify_invalidate(redis, cache_type, key).await;
+                let topic = cache_type.to_str();
+                let msg = crate::pubsub::PubSubMessage::invalidate(topic.into(), key);
+                self.publish(redis, topic, msg).await;
+            }
+
+            /// Drop every entry of a cache type on this node and publish the purge to the
+            /// other nodes, returning the number of local entries removed.
+            pub async fn purge(
+                &self,
+                redis: Option<&redis::aio::ConnectionManager>,
+                cache_type: CacheType,
+            ) -> usize {
+                let removed = self.purge_local(cache_type);
+                let topic = cache_type.to_str();
+                let msg = crate::pubsub::PubSubMessage::purge(topic.into());
+                self.publish(redis, topic, msg).await;
+                removed
             }
 
             fn expiry(&self) {
@@ -119,7 +150,7 @@ macro_rules! define_caches {
                 );
             }
 
-            fn stats(&self) -> Vec<CacheStats> {
+            pub(crate) fn stats(&self) -> Vec<CacheStats> {
                 vec![
                     $(
                         CacheStats {
@@ -138,19 +169,17 @@ macro_rules! define_caches {
 }
 
 impl CacheStore {
-    async fn notify_invalidate(
+    async fn publish(
         &self,
         redis: Option<&redis::aio::ConnectionManager>,
-        cache_type: CacheType,
-        key: Uuid,
+        topic: &'static str,
+        msg: crate::pubsub::PubSubMessage,
     ) {
         use redis::AsyncCommands as _;
 
         let Some(mut redis) = redis.cloned() else {
             return;
         };
-        let topic = cache_type.to_str();
-        let msg = crate::pubsub::PubSubMessage::invalidate(topic.into(), key);
         let Ok(msg) = sonic_rs::to_string(&msg) else {
             return;
         };
@@ -3,4 +3,4 @@ pub mod handlers;
 pub mod models;
 
 pub use handlers::{router, start_rate_limiter_cleanup};
-pub use models::{Channel, ChannelMember, ChannelType};
+pub use models::{Channel, ChannelMember, ChannelPostingPolicy, ChannelType};
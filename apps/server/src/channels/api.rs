@@ -1,5 +1,5 @@
 use super::{
-    ChannelType,
+    ChannelPostingPolicy, ChannelType,
     models::{Channel, ChannelMember},
 };
 use crate::spaces::Space;
@@ -49,6 +49,12 @@ pub struct EditChannel {
     pub is_archived: Option<bool>,
     #[serde(rename = "type")]
     pub _type: Option<ChannelType>,
+    #[serde(default)]
+    pub slow_mode_seconds: Option<i32>,
+    #[serde(default)]
+    pub posting_policy: Option<ChannelPostingPolicy>,
+    #[serde(default)]
+    pub max_message_length: Option<i32>,
 }
 
 #[derive(Deserialize, Debug, specta::Type)]
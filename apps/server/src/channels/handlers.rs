@@ -410,11 +410,24 @@ async fn edit(
         is_public,
         is_document,
         is_archived,
+        slow_mode_seconds,
+        posting_policy,
+        max_message_length,
     } = interface::parse_body(req).await?;
 
     let mutation_space_id = Channel::resolve_owning_space_id(&ctx.db, &channel_id)
         .await
         .or_not_found()?;
+    let topic = match topic {
+        Some(topic) => {
+            let content_filter = ctx
+                .space_store
+                .resolve_content_filter(mutation_space_id)
+                .await?;
+            Some(content_filter.check(&topic)?.into_owned())
+        }
+        None => None,
+    };
     let mutation = ctx.space_store.acquire_mutation(mutation_space_id).await?;
     let mut trans = ctx.db.begin().await?;
 
@@ -447,6 +460,21 @@ async fn edit(
         is_archived,
     )
     .await?;
+    let channel = if slow_mode_seconds.is_some()
+        || posting_policy.is_some()
+        || max_message_length.is_some()
+    {
+        Channel::set_posting_rules(
+            &mut *trans,
+            &channel_id,
+            slow_mode_seconds,
+            posting_policy,
+            max_message_length,
+        )
+        .await?
+    } else {
+        channel
+    };
     let should_push_members = !(grant_masters.is_empty() && remove_masters.is_empty());
     let mut changed_members = Vec::new();
     for user_id in grant_masters {
@@ -493,6 +521,11 @@ async fn edit_topic(
     let mutation_space_id = Channel::resolve_owning_space_id(&ctx.db, &channel_id)
         .await
         .or_not_found()?;
+    let content_filter = ctx
+        .space_store
+        .resolve_content_filter(mutation_space_id)
+        .await?;
+    let topic = content_filter.check(&topic)?;
     let mutation = ctx.space_store.acquire_mutation(mutation_space_id).await?;
     let channel_member = ctx
         .space_store
@@ -526,7 +559,7 @@ async fn edit_topic(
         &mut *trans,
         &channel_id,
         None,
-        Some(topic.as_str()),
+        Some(&*topic),
         None,
         None,
         None,
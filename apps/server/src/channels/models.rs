@@ -4,7 +4,7 @@ use time::OffsetDateTime;
 use uuid::Uuid;
 
 use crate::channels::api::{ChannelMemberWithUser, ChannelWithMaybeMember, ChannelWithMember};
-use crate::error::ModelError;
+use crate::error::{ModelError, ValidationFailed};
 use crate::spaces::{Space, SpaceMember};
 use crate::users::User;
 use crate::utils::{is_false, merge_blank};
@@ -49,6 +49,41 @@ impl ChannelType {
     }
 }
 
+/// Who can post in a channel. Read-only channels, such as announcements, are `MastersOnly`.
+/// `MastersOnlyInGame` lets everyone chat out-of-game but keeps in-game messages to masters.
+#[derive(
+    Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, specta::Type, sqlx::Type,
+)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum ChannelPostingPolicy {
+    #[default]
+    Everyone,
+    MastersOnly,
+    MastersOnlyInGame,
+}
+
+impl ChannelPostingPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChannelPostingPolicy::Everyone => "everyone",
+            ChannelPostingPolicy::MastersOnly => "masters_only",
+            ChannelPostingPolicy::MastersOnlyInGame => "masters_only_in_game",
+        }
+    }
+
+    pub fn allows(&self, is_master: bool, in_game: bool) -> bool {
+        match self {
+            ChannelPostingPolicy::Everyone => true,
+            ChannelPostingPolicy::MastersOnly => is_master,
+            ChannelPostingPolicy::MastersOnlyInGame => is_master || !in_game,
+        }
+    }
+}
+
+pub const MAX_SLOW_MODE_SECONDS: i32 = 6 * 60 * 60;
+pub const MAX_MESSAGE_LENGTH_LIMIT: i32 = 32768;
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, specta::Type, sqlx::Type)]
 #[sqlx(type_name = "channels")]
 #[serde(rename_all = "camelCase")]
@@ -75,6 +110,14 @@ pub struct Channel {
     pub r#type: ChannelType,
     #[serde(default, skip_serializing_if = "is_false")]
     pub is_archived: bool,
+    /// Seconds a non-master has to wait between two messages, 0 if slow mode is off
+    #[serde(default)]
+    pub slow_mode_seconds: i32,
+    #[serde(default)]
+    pub posting_policy: ChannelPostingPolicy,
+    /// The maximum characters of a message, 0 if there is no channel limit
+    #[serde(default)]
+    pub max_message_length: i32,
 }
 
 impl Channel {
@@ -244,6 +287,61 @@ impl Channel {
         .map_err(Into::into)
     }
 
+    pub async fn set_posting_rules<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        id: &Uuid,
+        slow_mode_seconds: Option<i32>,
+        posting_policy: Option<ChannelPostingPolicy>,
+        max_message_length: Option<i32>,
+    ) -> Result<Channel, ModelError> {
+        if slow_mode_seconds.is_some_and(|seconds| !(0..=MAX_SLOW_MODE_SECONDS).contains(&seconds))
+        {
+            return Err(ValidationFailed("Slow mode shall be between 0 and 6 hours.").into());
+        }
+        if max_message_length
+            .is_some_and(|length| !(0..=MAX_MESSAGE_LENGTH_LIMIT).contains(&length))
+        {
+            return Err(
+                ValidationFailed("Max message length shall be between 0 and 32768.").into(),
+            );
+        }
+        sqlx::query_file_scalar!(
+            "sql/channels/set_posting_rules.sql",
+            id,
+            slow_mode_seconds,
+            posting_policy.as_ref().map(ChannelPostingPolicy::as_str),
+            max_message_length,
+        )
+        .fetch_one(db)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Check whether a message can be posted, slow mode aside.
+    pub fn check_posting(
+        &self,
+        is_master: bool,
+        in_game: bool,
+        text: &str,
+    ) -> Result<(), crate::error::AppError> {
+        use crate::error::AppError;
+
+        if !self.posting_policy.allows(is_master, in_game) {
+            let message = if in_game {
+                "Only masters can post in-game messages in this channel"
+            } else {
+                "Only masters can post in this channel"
+            };
+            return Err(AppError::NoPermission(message.to_string()));
+        }
+        if self.max_message_length > 0 && text.chars().count() > self.max_message_length as usize {
+            return Err(AppError::Validation(ValidationFailed(
+                "The message is longer than this channel allows.",
+            )));
+        }
+        Ok(())
+    }
+
     pub async fn get_by_user(
         db: &mut sqlx::PgConnection,
         user_id: Uuid,
@@ -1131,4 +1229,55 @@ mod tests {
             .expect("owner channels after space removal failed");
         assert!(owner_channels_after.is_empty());
     }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn db_test_channel_posting_rules(pool: sqlx::PgPool) {
+        let owner = create_test_user(&pool, "posting_owner").await;
+        let space = create_test_space(&pool, &owner, "posting_space").await;
+        let channel = Channel::create(
+            &pool,
+            &space.id,
+            "Posting Rules",
+            true,
+            Some("d20"),
+            ChannelType::InGame,
+        )
+        .await
+        .expect("failed to create channel");
+        assert_eq!(channel.slow_mode_seconds, 0);
+        assert_eq!(channel.posting_policy, ChannelPostingPolicy::Everyone);
+        assert!(channel.check_posting(false, true, "hello").is_ok());
+
+        let channel = Channel::set_posting_rules(
+            &pool,
+            &channel.id,
+            Some(30),
+            Some(ChannelPostingPolicy::MastersOnlyInGame),
+            Some(5),
+        )
+        .await
+        .expect("failed to set posting rules");
+        assert_eq!(channel.slow_mode_seconds, 30);
+        assert_eq!(channel.max_message_length, 5);
+        assert!(channel.check_posting(false, false, "hello").is_ok());
+        assert!(channel.check_posting(true, true, "hello").is_ok());
+        assert!(channel.check_posting(false, true, "hello").is_err());
+        assert!(channel.check_posting(true, true, "hello!").is_err());
+
+        let channel = Channel::set_posting_rules(&pool, &channel.id, None, None, Some(0))
+            .await
+            .expect("failed to clear max message length");
+        assert_eq!(channel.slow_mode_seconds, 30);
+        assert_eq!(channel.max_message_length, 0);
+
+        let invalid = Channel::set_posting_rules(
+            &pool,
+            &channel.id,
+            Some(MAX_SLOW_MODE_SECONDS + 1),
+            None,
+            None,
+        )
+        .await;
+        assert!(matches!(invalid, Err(ModelError::Validation(_))));
+    }
 }
@@ -1,4 +1,5 @@
 pub mod api;
+mod bundle;
 pub(crate) mod handlers;
 mod models;
 
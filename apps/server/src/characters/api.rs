@@ -1,3 +1,4 @@
+use super::bundle::CharacterBundle;
 use crate::channels::{Channel, ChannelMember};
 use crate::users::User;
 use serde::{Deserialize, Serialize};
@@ -93,6 +94,22 @@ pub struct EditCharacter {
     pub tags: Vec<String>,
 }
 
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportCharacterBundle {
+    pub space_id: Uuid,
+    pub bundle: CharacterBundle,
+    /// Replaces the key of the bundle, if it is taken in the space.
+    #[serde(default)]
+    pub key: Option<String>,
+    /// Replaces the aliases of the bundle, if some are taken in the space.
+    #[serde(default)]
+    pub aliases: Option<Vec<String>>,
+    pub access_policy: AccessPolicy,
+    #[serde(default)]
+    pub access_channel_id: Option<Uuid>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,430 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::models::Character;
+use crate::assets::{Asset, AssetPolicy};
+use crate::entries::import::{EntryImportRow, MAX_IMPORT_ROWS};
+use crate::entries::models::{
+    EntryComponent, EntryComponentPayloadInput, EntryEffect, EntryMetadata, stored_components,
+};
+use crate::error::{ModelError, ValidationFailed};
+use crate::spaces::{AccessPolicy, ContentFilter};
+
+/// Bumped when the bundle changes in a way older servers can not read.
+pub const CHARACTER_BUNDLE_FORMAT: u32 = 1;
+const MAX_BUNDLE_ASSETS: usize = 64;
+
+/// The fields of a Character which can be moved to another space.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CharacterBundleCharacter {
+    pub name: String,
+    pub key: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub color: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// An Asset referenced by the Asset components of the bundle. Media are shared between the
+/// spaces of a server, so the Asset is registered again in the space it is imported into.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CharacterBundleAsset {
+    /// The id the Asset components of the bundle refer to
+    pub id: Uuid,
+    pub media_id: Uuid,
+    pub name: String,
+    pub mime_type: String,
+}
+
+/// A Character with the Entries of its Scope, in a format which can be imported into another
+/// space.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CharacterBundle {
+    pub format: u32,
+    pub character: CharacterBundleCharacter,
+    /// In the order of the Scope
+    #[serde(default)]
+    pub entries: Vec<EntryImportRow>,
+    #[serde(default)]
+    pub assets: Vec<CharacterBundleAsset>,
+}
+
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CharacterBundleImport {
+    pub character: Character,
+    pub entries: Vec<EntryMetadata>,
+    pub assets: Vec<Asset>,
+    /// The Assets of the bundle whose media is missing or not available to the user. The
+    /// components referring to them are left out.
+    pub skipped_assets: Vec<Uuid>,
+}
+
+impl CharacterBundle {
+    pub async fn export(
+        db: &mut sqlx::PgConnection,
+        character: &Character,
+    ) -> Result<Self, ModelError> {
+        let metadata = EntryMetadata::list_by_scope(&mut *db, character.scope_id).await?;
+        let mut entries = Vec::with_capacity(metadata.len());
+        let mut assets = BTreeMap::new();
+        for entry in metadata {
+            let mut components = BTreeMap::new();
+            for (component_type, component) in stored_components(&mut *db, entry.id).await? {
+                let payload = match component {
+                    EntryComponent::Json {
+                        data,
+                        schema_version,
+                        ..
+                    } => EntryComponentPayloadInput::json_with_schema(data, Some(schema_version)),
+                    EntryComponent::Asset { asset_id, .. } => {
+                        if !assets.contains_key(&asset_id) {
+                            let asset =
+                                Asset::get_by_id_in_space(&mut *db, character.space_id, asset_id)
+                                    .await?
+                                    .ok_or(ModelError::NotFound("Asset"))?;
+                            assets.insert(
+                                asset_id,
+                                CharacterBundleAsset {
+                                    id: asset.id,
+                                    media_id: asset.media_id,
+                                    name: asset.name,
+                                    mime_type: asset.mime_type,
+                                },
+                            );
+                        }
+                        EntryComponentPayloadInput::Asset { asset_id }
+                    }
+                    EntryComponent::Formula { formula, .. } => {
+                        EntryComponentPayloadInput::Formula { formula }
+                    }
+                };
+                components.insert(component_type.to_string(), payload);
+            }
+            entries.push(EntryImportRow {
+                key: entry.key.to_string(),
+                aliases: entry.aliases.iter().map(ToString::to_string).collect(),
+                display_name: entry.display_name.to_string(),
+                tags: entry.tags.iter().map(ToString::to_string).collect(),
+                components,
+            });
+        }
+        Ok(CharacterBundle {
+            format: CHARACTER_BUNDLE_FORMAT,
+            character: CharacterBundleCharacter {
+                name: character.name.to_string(),
+                key: character.key.to_string(),
+                aliases: character.aliases.iter().map(ToString::to_string).collect(),
+                description: character.description.clone(),
+                color: character.color.to_string(),
+                tags: character.tags.iter().map(ToString::to_string).collect(),
+            },
+            entries,
+            assets: assets.into_values().collect(),
+        })
+    }
+
+    fn validate(&self) -> Result<(), ValidationFailed> {
+        if self.format != CHARACTER_BUNDLE_FORMAT {
+            return Err(ValidationFailed(
+                "The format of the Character bundle is not supported.",
+            ));
+        }
+        if self.entries.len() > MAX_IMPORT_ROWS {
+            return Err(ValidationFailed(
+                "A Character bundle can have at most 500 Entries.",
+            ));
+        }
+        if self.assets.len() > MAX_BUNDLE_ASSETS {
+            return Err(ValidationFailed(
+                "A Character bundle can have at most 64 Assets.",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl CharacterBundleImport {
+    /// Create the Character, its Assets and its Entries with new ids. The key and the aliases
+    /// must be free in the space, see `check_identifier`.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn import(
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        space_id: Uuid,
+        user_id: Uuid,
+        content_filter: &ContentFilter,
+        bundle: CharacterBundle,
+        access_policy: AccessPolicy,
+        access_channel_id: Option<Uuid>,
+    ) -> Result<Self, ModelError> {
+        bundle.validate()?;
+        let CharacterBundle {
+            character,
+            entries: rows,
+            assets: bundle_assets,
+            ..
+        } = bundle;
+        let name = content_filter.check(&character.name)?.into_owned();
+        if Character::exists_identifier(
+            &mut **db,
+            space_id,
+            Some(&character.key),
+            Some(&character.aliases),
+            None,
+        )
+        .await?
+        {
+            return Err(ModelError::Conflict("character_identifiers".to_string()));
+        }
+        let character = Character::create(
+            db,
+            space_id,
+            user_id,
+            &name,
+            &character.key,
+            character.aliases,
+            &character.description,
+            &character.color,
+            access_policy,
+            access_channel_id,
+            character.tags,
+        )
+        .await?;
+
+        let mut asset_ids = HashMap::with_capacity(bundle_assets.len());
+        let mut assets = Vec::with_capacity(bundle_assets.len());
+        let mut skipped_assets = Vec::new();
+        for bundle_asset in bundle_assets {
+            let reusable = sqlx::query_file_scalar!(
+                "sql/characters/can_reuse_media.sql",
+                bundle_asset.media_id,
+                user_id,
+            )
+            .fetch_one(&mut **db)
+            .await?;
+            if !reusable {
+                skipped_assets.push(bundle_asset.id);
+                continue;
+            }
+            let asset = Asset::create(
+                db,
+                space_id,
+                bundle_asset.media_id,
+                user_id,
+                &bundle_asset.name,
+                AssetPolicy::Unlisted,
+            )
+            .await?;
+            asset_ids.insert(bundle_asset.id, asset.id);
+            assets.push(asset);
+        }
+
+        let mut entries = Vec::with_capacity(rows.len());
+        if !rows.is_empty() {
+            let effect = EntryEffect::create(db, space_id, character.scope_id, user_id).await?;
+            for mut row in rows {
+                let mut components = BTreeMap::new();
+                for (component_type, payload) in row.components {
+                    let payload = match payload {
+                        EntryComponentPayloadInput::Asset { asset_id } => {
+                            if skipped_assets.contains(&asset_id) {
+                                continue;
+                            }
+                            let asset_id = asset_ids.get(&asset_id).copied().ok_or(
+                                ValidationFailed(
+                                    "An Asset component refers to an Asset which is not in the bundle.",
+                                ),
+                            )?;
+                            EntryComponentPayloadInput::Asset { asset_id }
+                        }
+                        payload => payload,
+                    };
+                    components.insert(component_type, payload);
+                }
+                row.components = components;
+                let entry = row.create_entry(db, &effect, character.scope_id).await?;
+                entries.push(entry.metadata);
+            }
+        }
+        Ok(CharacterBundleImport {
+            character,
+            entries,
+            assets,
+            skipped_assets,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::models::Media;
+    use crate::spaces::{Space, SpaceMember};
+    use crate::users::User;
+    use serde_json::json;
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn db_test_bundle_moves_character_between_spaces(pool: sqlx::PgPool) {
+        let raw = Uuid::new_v4().simple().to_string();
+        let user = User::register(
+            &pool,
+            &format!("character_bundle_{raw}@example.com"),
+            &format!("cbundle_{}", &raw[..8]),
+            "Character Bundle Tester",
+            "CharacterBundlePass123!",
+        )
+        .await
+        .expect("create user failed");
+        let mut spaces = Vec::new();
+        for name in ["from", "to"] {
+            let space = Space::create(
+                &pool,
+                format!("bundle_{name}_{}", &raw[..8]),
+                &user.id,
+                "Character bundle test".to_string(),
+                None,
+                Some("d20"),
+            )
+            .await
+            .expect("create space failed");
+            SpaceMember::add_user(&pool, &user.id, &space.id)
+                .await
+                .expect("join space failed");
+            spaces.push(space);
+        }
+        let media = Media::create(
+            &pool,
+            &Uuid::new_v4(),
+            "image/png",
+            user.id,
+            &format!("portrait_{raw}.png"),
+            "portrait.png",
+            raw.clone(),
+            1024,
+            "upload",
+        )
+        .await
+        .expect("create media failed");
+
+        let mut transaction = pool.begin().await.unwrap();
+        let character = Character::create(
+            &mut transaction,
+            spaces[0].id,
+            user.id,
+            "Hero",
+            "hero",
+            vec!["champion".to_string()],
+            "A hero",
+            "",
+            AccessPolicy::Personal,
+            None,
+            vec!["pc".to_string()],
+        )
+        .await
+        .expect("create character failed");
+        let asset = Asset::create(
+            &mut transaction,
+            spaces[0].id,
+            media.id,
+            user.id,
+            "Portrait",
+            AssetPolicy::Unlisted,
+        )
+        .await
+        .expect("create asset failed");
+        let row = EntryImportRow {
+            key: "hero".to_string(),
+            aliases: Vec::new(),
+            display_name: "Hero".to_string(),
+            tags: Vec::new(),
+            components: BTreeMap::from([
+                (
+                    "example/stats".to_string(),
+                    EntryComponentPayloadInput::json(json!({ "hp": 12 })),
+                ),
+                (
+                    "core/portrait".to_string(),
+                    EntryComponentPayloadInput::Asset { asset_id: asset.id },
+                ),
+            ]),
+        };
+        let effect =
+            EntryEffect::create(&mut transaction, spaces[0].id, character.scope_id, user.id)
+                .await
+                .unwrap();
+        row.create_entry(&mut transaction, &effect, character.scope_id)
+            .await
+            .expect("create entry failed");
+        transaction.commit().await.unwrap();
+
+        let mut conn = pool.acquire().await.unwrap();
+        let bundle = CharacterBundle::export(&mut conn, &character)
+            .await
+            .expect("export failed");
+        assert_eq!(bundle.character.aliases, ["champion"]);
+        assert_eq!(bundle.entries.len(), 1);
+        assert_eq!(bundle.assets.len(), 1);
+        let bundle: CharacterBundle =
+            serde_json::from_value(serde_json::to_value(&bundle).unwrap()).unwrap();
+
+        let content_filter = ContentFilter::default();
+        let mut transaction = pool.begin().await.unwrap();
+        let imported = CharacterBundleImport::import(
+            &mut transaction,
+            spaces[1].id,
+            user.id,
+            &content_filter,
+            bundle.clone(),
+            AccessPolicy::Personal,
+            None,
+        )
+        .await
+        .expect("import failed");
+        transaction.commit().await.unwrap();
+        assert_eq!(imported.character.space_id, spaces[1].id);
+        assert_eq!(imported.character.key, "hero");
+        assert_ne!(imported.character.id, character.id);
+        assert!(imported.skipped_assets.is_empty());
+        assert_eq!(imported.assets[0].space_id, spaces[1].id);
+        assert_eq!(imported.assets[0].media_id, media.id);
+        let components = stored_components(&mut conn, imported.entries[0].id)
+            .await
+            .unwrap();
+        assert!(matches!(
+            components.get("core/portrait"),
+            Some(EntryComponent::Asset { asset_id, .. }) if *asset_id == imported.assets[0].id
+        ));
+        assert_eq!(components["example/stats"].json_data(), json!({ "hp": 12 }));
+
+        let mut transaction = pool.begin().await.unwrap();
+        assert!(matches!(
+            CharacterBundleImport::import(
+                &mut transaction,
+                spaces[1].id,
+                user.id,
+                &content_filter,
+                bundle.clone(),
+                AccessPolicy::Personal,
+                None,
+            )
+            .await,
+            Err(ModelError::Conflict(_))
+        ));
+        transaction.rollback().await.unwrap();
+
+        let unsupported = CharacterBundle {
+            format: CHARACTER_BUNDLE_FORMAT + 1,
+            ..bundle
+        };
+        assert!(unsupported.validate().is_err());
+    }
+}
@@ -1,7 +1,8 @@
 use super::api::{
     ArchiveCharacter, CharacterUsage, CheckCharacterIdentifier, CreateCharacter, EditCharacter,
-    ListCharacters, QueryCharacter, RestoreCharacter,
+    ImportCharacterBundle, ListCharacters, QueryCharacter, RestoreCharacter,
 };
+use super::bundle::{CharacterBundle, CharacterBundleImport};
 use super::models::Character;
 use crate::channels::{ChannelMember, handlers::push_refreshed_members};
 use crate::committed_changes::CommittedChanges;
@@ -213,6 +214,8 @@ async fn create(
         access_channel_id,
         tags,
     } = parse_body(req).await?;
+    let content_filter = ctx.space_store.resolve_content_filter(space_id).await?;
+    let name = content_filter.check(&name)?;
     let mutation = ctx.space_store.acquire_mutation(space_id).await?;
     let mut trans = ctx.db.begin().await?;
     if !ctx
@@ -276,6 +279,8 @@ async fn edit(
         access_channel_id,
         tags,
     } = parse_body(req).await?;
+    let content_filter = ctx.space_store.resolve_content_filter(space_id).await?;
+    let name = content_filter.check(&name)?.into_owned();
     let mutation = ctx.space_store.acquire_mutation(space_id).await?;
     let mut trans = ctx.db.begin().await?;
     let character = Character::get_by_id_in_space(&mut *trans, space_id, &character_id)
@@ -418,6 +423,92 @@ async fn restore(
     .await
 }
 
+async fn export(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<CharacterBundle, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let QueryCharacter {
+        space_id,
+        character_id,
+    } = parse_query(req.uri())?;
+    let character = ctx
+        .space_store
+        .resolve_character(space_id, character_id)
+        .await?
+        .or_not_found()?;
+    if !can_view_character_in_space(ctx, &character, Some(session.user_id)).await? {
+        return Err(AppError::NoPermission(
+            "You don't have permission to view this character".to_string(),
+        ));
+    }
+    let mut conn = ctx.db.acquire().await?;
+    Ok(CharacterBundle::export(&mut conn, &character).await?)
+}
+
+async fn import(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<CharacterBundleImport, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let ImportCharacterBundle {
+        space_id,
+        mut bundle,
+        key,
+        aliases,
+        access_policy,
+        access_channel_id,
+    } = parse_body(req).await?;
+    if let Some(key) = key {
+        bundle.character.key = key;
+    }
+    if let Some(aliases) = aliases {
+        bundle.character.aliases = aliases;
+    }
+    let access = resolve_space_access(ctx, space_id, Some(session.user_id)).await?;
+    let context =
+        resolve_resource_access_context(ctx, space_id, access_channel_id, Some(session.user_id))
+            .await?;
+    if !access.is_member || !access_policy.can_edit(Some(session.user_id), session.user_id, context)
+    {
+        return Err(AppError::NoPermission(
+            "You cannot edit characters with this access policy and context".to_string(),
+        ));
+    }
+    let content_filter = ctx.space_store.resolve_content_filter(space_id).await?;
+    let mutation = ctx.space_store.acquire_mutation(space_id).await?;
+    let mut trans = ctx.db.begin().await?;
+    let imported = CharacterBundleImport::import(
+        &mut trans,
+        space_id,
+        session.user_id,
+        &content_filter,
+        bundle,
+        access_policy,
+        access_channel_id,
+    )
+    .await?;
+    let scope = Scope::get_by_id(&mut *trans, imported.character.scope_id)
+        .await?
+        .or_not_found()?;
+    let mutation = mutation.commit(trans).await?;
+    let mut changes = CommittedChanges::default();
+    changes.character_updated(&imported.character);
+    changes.scope_updated(&scope);
+    for entry in &imported.entries {
+        changes.entry_updated(space_id, entry);
+    }
+    changes.apply_with_mutation(ctx, &mutation).await;
+    tracing::info!(
+        %space_id,
+        user_id = %session.user_id,
+        character_id = %imported.character.id,
+        entries = imported.entries.len(),
+        "A character bundle was imported"
+    );
+    Ok(imported)
+}
+
 pub async fn router(
     ctx: &crate::context::AppContext,
     req: Request<impl Body>,
@@ -434,6 +525,8 @@ pub async fn router(
         ("/edit", Method::PUT) => response(edit(ctx, req).await).await,
         ("/archive", Method::POST) => response(archive(ctx, req).await).await,
         ("/restore", Method::POST) => response(restore(ctx, req).await).await,
+        ("/export", Method::GET) => response(export(ctx, req).await).await,
+        ("/import", Method::POST) => response(import(ctx, req).await).await,
         _ => missing(),
     }
 }
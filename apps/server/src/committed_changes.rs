@@ -1,4 +1,5 @@
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::cache::{CACHE, CacheType};
@@ -12,7 +13,7 @@ use crate::scopes::models::Scope;
 use crate::space_runtime::{
     CommittedSpaceMutation, SpaceDelta, SpaceMutationProof, SpaceRuntimeError,
 };
-use crate::spaces::{Space, SpaceMember};
+use crate::spaces::{ContentFilter, Space, SpaceMember};
 
 /// Domain changes that are safe to apply to process-wide state after a successful database commit.
 ///
@@ -64,6 +65,17 @@ impl CommittedChanges {
             .push(SpaceDelta::SettingsUpdated(settings));
     }
 
+    pub(crate) fn space_content_rules_updated(
+        &mut self,
+        space_id: Uuid,
+        content_filter: Arc<ContentFilter>,
+    ) {
+        self.space_deltas
+            .entry(space_id)
+            .or_default()
+            .push(SpaceDelta::ContentRulesUpdated(content_filter));
+    }
+
     pub(crate) fn space_invite_token_updated(&mut self, space_id: Uuid, token: Uuid) {
         self.space_deltas
             .entry(space_id)
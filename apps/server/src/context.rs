@@ -47,6 +47,11 @@ pub struct AppConfig {
     pub secret: String,
     pub mail: crate::mail::Config,
     pub entry_component_cache_capacity: u64,
+    pub site_admin_ids: Vec<uuid::Uuid>,
+    /// Bytes of media each user can upload, `None` for no limit.
+    pub user_media_quota: Option<u64>,
+    /// Bytes of media the messages and assets of each space can use, `None` for no limit.
+    pub space_media_quota: Option<u64>,
 }
 
 #[cfg(test)]
@@ -65,6 +70,9 @@ impl Default for AppConfig {
             secret: "just a test".to_owned(),
             mail: crate::mail::Config::default(),
             entry_component_cache_capacity: crate::entries::component_cache::DEFAULT_CACHE_BYTES,
+            site_admin_ids: Vec::new(),
+            user_media_quota: None,
+            space_media_quota: None,
         }
     }
 }
@@ -87,7 +95,7 @@ pub struct AppContext {
     pub(crate) space_store: crate::space_runtime::SpaceStore,
     pub(crate) space_activity_notifier: crate::notify::SpaceActivityNotifier,
     pub(crate) space_list_cache: Arc<SpaceListCache>,
-    pub(crate) storage: Arc<crate::s3::Storage>,
+    pub(crate) storage: Arc<dyn crate::storage::Storage>,
     pub(crate) signer: Signer,
     pub config: AppConfig,
 }
@@ -103,7 +111,7 @@ impl AppContext {
             db,
             redis,
             AppConfig::default(),
-            Arc::new(crate::s3::Storage::disabled()),
+            Arc::new(crate::storage::Disabled),
         )
     }
 
@@ -111,7 +119,7 @@ impl AppContext {
         db: sqlx::Pool<sqlx::Postgres>,
         redis: Option<redis::aio::ConnectionManager>,
         config: AppConfig,
-        storage: Arc<crate::s3::Storage>,
+        storage: Arc<dyn crate::storage::Storage>,
     ) -> Self {
         let signer = Signer::new(&config.secret);
         let space_store = crate::space_runtime::SpaceStore::with_entry_component_cache_capacity(
@@ -162,7 +170,7 @@ impl AppContext {
         &self.signer
     }
 
-    pub(crate) fn storage(&self) -> &crate::s3::Storage {
-        &self.storage
+    pub(crate) fn storage(&self) -> &dyn crate::storage::Storage {
+        self.storage.as_ref()
     }
 }
@@ -0,0 +1,6 @@
+pub mod api;
+pub(crate) mod handlers;
+mod models;
+
+pub use handlers::router;
+pub use models::{Combatant, Encounter};
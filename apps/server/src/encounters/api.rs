@@ -0,0 +1,96 @@
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryEncounter {
+    pub space_id: Uuid,
+    pub channel_id: Uuid,
+}
+
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct StartEncounter {
+    pub space_id: Uuid,
+    pub channel_id: Uuid,
+    #[serde(default)]
+    pub announce_turns: bool,
+    #[serde(default)]
+    pub duration_component_type: Option<String>,
+    #[serde(default)]
+    pub duration_path: String,
+}
+
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EditEncounter {
+    pub space_id: Uuid,
+    pub channel_id: Uuid,
+    pub expected_version: Uuid,
+    pub announce_turns: bool,
+    pub duration_component_type: Option<String>,
+    #[serde(default)]
+    pub duration_path: String,
+}
+
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EndEncounter {
+    pub space_id: Uuid,
+    pub channel_id: Uuid,
+}
+
+/// Who takes part in the encounter.
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(tag = "kind", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CombatantSource {
+    #[serde(rename_all = "camelCase")]
+    Character { character_id: Uuid },
+    #[serde(rename_all = "camelCase")]
+    Entry { scope_id: Uuid, entry_id: Uuid },
+    /// A combatant without a sheet, such as a nameless monster.
+    Custom { name: String },
+}
+
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AddCombatant {
+    pub space_id: Uuid,
+    pub channel_id: Uuid,
+    pub source: CombatantSource,
+    /// Overrides the name of the character or the entry.
+    #[serde(default)]
+    pub name: Option<String>,
+    pub initiative: f64,
+    /// The roll message in the channel the initiative was taken from.
+    #[serde(default)]
+    pub initiative_message_id: Option<Uuid>,
+}
+
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SetInitiative {
+    pub space_id: Uuid,
+    pub channel_id: Uuid,
+    pub combatant_id: Uuid,
+    pub initiative: f64,
+    #[serde(default)]
+    pub initiative_message_id: Option<Uuid>,
+}
+
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoveCombatant {
+    pub space_id: Uuid,
+    pub channel_id: Uuid,
+    pub combatant_id: Uuid,
+}
+
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AdvanceTurn {
+    pub space_id: Uuid,
+    pub channel_id: Uuid,
+    /// The version of the encounter the client saw, so that a double click does not skip a turn.
+    pub expected_version: Uuid,
+}
@@ -0,0 +1,493 @@
+use super::api::{
+    AddCombatant, AdvanceTurn, CombatantSource, EditEncounter, EndEncounter, QueryEncounter,
+    RemoveCombatant, SetInitiative, StartEncounter,
+};
+use super::models::{Combatant, Encounter, TurnChange};
+use crate::characters::handlers::can_edit_character_in_space;
+use crate::committed_changes::CommittedChanges;
+use crate::csrf::{authenticate, authenticate_optional};
+use crate::entries::handlers::can_edit_scope;
+use crate::entries::models::Entry;
+use crate::error::{AppError, Find};
+use crate::events::Update;
+use crate::interface::{missing, parse_body, parse_query, response};
+use crate::messages::{Entities, Message};
+use crate::notify;
+use crate::spaces::resolve_resource_access_context;
+use hyper::Request;
+use hyper::body::Body;
+use shared_types::entities::{Entity, Span};
+use uuid::Uuid;
+
+/// Whether the user runs the encounter. Others may only act on their own combatants.
+async fn resolve_encounter_access(
+    ctx: &crate::context::AppContext,
+    space_id: Uuid,
+    channel_id: Uuid,
+    user_id: Option<Uuid>,
+    mutate: bool,
+) -> Result<bool, AppError> {
+    let context = resolve_resource_access_context(ctx, space_id, Some(channel_id), user_id).await?;
+    let is_master = context.is_game_master || context.can_manage;
+    if !context.can_view || (mutate && !is_master && !context.is_member) {
+        return Err(AppError::NoPermission(
+            "You don't have permission to access this encounter".to_string(),
+        ));
+    }
+    Ok(is_master)
+}
+
+async fn can_control_combatant(
+    ctx: &crate::context::AppContext,
+    space_id: Uuid,
+    combatant: &Combatant,
+    user_id: Uuid,
+) -> Result<bool, AppError> {
+    if let Some(character_id) = combatant.character_id {
+        let Some(character) = ctx
+            .space_store
+            .resolve_character(space_id, character_id)
+            .await?
+        else {
+            return Ok(false);
+        };
+        return can_edit_character_in_space(ctx, &character, user_id).await;
+    }
+    if let Some(scope_id) = combatant.entry_scope_id {
+        let Some(scope) = ctx.space_store.resolve_scope(space_id, scope_id).await? else {
+            return Ok(false);
+        };
+        return can_edit_scope(ctx, &scope, user_id).await;
+    }
+    Ok(combatant.added_by == Some(user_id))
+}
+
+async fn check_initiative_message(
+    ctx: &crate::context::AppContext,
+    channel_id: Uuid,
+    message_id: Option<Uuid>,
+    user_id: Uuid,
+    is_master: bool,
+) -> Result<(), AppError> {
+    let Some(message_id) = message_id else {
+        return Ok(());
+    };
+    let message = Message::get(&ctx.db, &message_id, Some(&user_id))
+        .await
+        .or_not_found()?;
+    if message.channel_id != channel_id || message.deleted {
+        return Err(AppError::NotFound("message"));
+    }
+    if !message
+        .entities
+        .0
+        .iter()
+        .any(|entity| matches!(entity, Entity::Expr(_)))
+    {
+        return Err(AppError::BadRequest(
+            "The initiative message has no roll".to_string(),
+        ));
+    }
+    if message.sender_id != user_id && !is_master {
+        return Err(AppError::NoPermission(
+            "Only the sender can take the initiative from this roll".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+async fn announce_turn(
+    ctx: &crate::context::AppContext,
+    encounter: &Encounter,
+    turn: &TurnChange,
+    user_id: Uuid,
+) -> Result<(), AppError> {
+    let Some(combatant) = encounter.combatant(turn.combatant_id) else {
+        return Ok(());
+    };
+    // Space admins outside the channel cannot post into it.
+    let Some(member) = ctx
+        .space_store
+        .resolve_channel_member(encounter.space_id, encounter.channel_id, user_id)
+        .await?
+    else {
+        return Ok(());
+    };
+    let text = if turn.new_round {
+        format!("Round {}: {}'s turn", turn.round, combatant.name)
+    } else {
+        format!("{}'s turn", combatant.name)
+    };
+    let len = text.encode_utf16().count() as i32;
+    let message = Message::create(
+        &ctx.db,
+        None,
+        encounter.channel_id,
+        encounter.space_id,
+        &user_id,
+        &member.channel.character_name,
+        "",
+        None,
+        None,
+        &text,
+        Entities(vec![Entity::Text(Span { start: 0, len })]),
+        false,
+        false,
+        member.channel.is_master,
+        None,
+        None,
+        None,
+        String::new(),
+    )
+    .await?;
+    notify::space_activity(ctx, encounter.space_id, Some(message.created));
+    Update::new_message(encounter.space_id, message, None).await;
+    Ok(())
+}
+
+async fn locked_encounter(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    space_id: Uuid,
+    channel_id: Uuid,
+) -> Result<Encounter, AppError> {
+    Encounter::get_for_update(transaction, space_id, channel_id)
+        .await?
+        .ok_or(AppError::NotFound("encounter"))
+}
+
+async fn query(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Option<Encounter>, AppError> {
+    let session = authenticate_optional(ctx, &req).await?;
+    let QueryEncounter {
+        space_id,
+        channel_id,
+    } = parse_query(req.uri())?;
+    resolve_encounter_access(
+        ctx,
+        space_id,
+        channel_id,
+        session.map(|session| session.user_id),
+        false,
+    )
+    .await?;
+    let mut conn = ctx.db.acquire().await?;
+    Ok(Encounter::get(&mut conn, space_id, channel_id).await?)
+}
+
+async fn start(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Encounter, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let StartEncounter {
+        space_id,
+        channel_id,
+        announce_turns,
+        duration_component_type,
+        duration_path,
+    } = parse_body(req).await?;
+    if !resolve_encounter_access(ctx, space_id, channel_id, Some(session.user_id), true).await? {
+        return Err(AppError::NoPermission(
+            "Only the game master can start an encounter".to_string(),
+        ));
+    }
+    let mut transaction = ctx.db.begin().await?;
+    let encounter = Encounter::create(
+        &mut transaction,
+        space_id,
+        channel_id,
+        announce_turns,
+        duration_component_type,
+        duration_path,
+    )
+    .await
+    .map_err(|error| match error {
+        crate::error::ModelError::Conflict(_) => {
+            AppError::Conflict("The channel already has an encounter".to_string())
+        }
+        error => error.into(),
+    })?;
+    transaction.commit().await?;
+    Update::encounter_changed(space_id, channel_id);
+    Ok(encounter)
+}
+
+async fn edit(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Encounter, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let EditEncounter {
+        space_id,
+        channel_id,
+        expected_version,
+        announce_turns,
+        duration_component_type,
+        duration_path,
+    } = parse_body(req).await?;
+    if !resolve_encounter_access(ctx, space_id, channel_id, Some(session.user_id), true).await? {
+        return Err(AppError::NoPermission(
+            "Only the game master can edit the encounter".to_string(),
+        ));
+    }
+    let mut transaction = ctx.db.begin().await?;
+    let encounter = locked_encounter(&mut transaction, space_id, channel_id).await?;
+    if encounter.version != expected_version {
+        return Err(AppError::Conflict("Encounter version is stale".to_string()));
+    }
+    let encounter = encounter
+        .update_settings(
+            &mut transaction,
+            announce_turns,
+            duration_component_type,
+            duration_path,
+        )
+        .await?;
+    transaction.commit().await?;
+    Update::encounter_changed(space_id, channel_id);
+    Ok(encounter)
+}
+
+async fn end(ctx: &crate::context::AppContext, req: Request<impl Body>) -> Result<(), AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let EndEncounter {
+        space_id,
+        channel_id,
+    } = parse_body(req).await?;
+    if !resolve_encounter_access(ctx, space_id, channel_id, Some(session.user_id), true).await? {
+        return Err(AppError::NoPermission(
+            "Only the game master can end the encounter".to_string(),
+        ));
+    }
+    if !Encounter::delete(&ctx.db, space_id, channel_id).await? {
+        return Err(AppError::NotFound("encounter"));
+    }
+    Update::encounter_changed(space_id, channel_id);
+    Ok(())
+}
+
+async fn add_combatant(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Encounter, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let AddCombatant {
+        space_id,
+        channel_id,
+        source,
+        name,
+        initiative,
+        initiative_message_id,
+    } = parse_body(req).await?;
+    let user_id = session.user_id;
+    let is_master =
+        resolve_encounter_access(ctx, space_id, channel_id, Some(user_id), true).await?;
+    let (source_name, character_id, entry) = match source {
+        CombatantSource::Character { character_id } => {
+            let character = ctx
+                .space_store
+                .resolve_character(space_id, character_id)
+                .await?
+                .or_not_found()?;
+            if character.archived_at.is_some() {
+                return Err(AppError::BadRequest(
+                    "Archived characters cannot join an encounter".to_string(),
+                ));
+            }
+            if !is_master && !can_edit_character_in_space(ctx, &character, user_id).await? {
+                return Err(AppError::NoPermission(
+                    "You don't have permission to control this character".to_string(),
+                ));
+            }
+            (character.name.to_string(), Some(character.id), None)
+        }
+        CombatantSource::Entry { scope_id, entry_id } => {
+            let scope = ctx
+                .space_store
+                .resolve_scope(space_id, scope_id)
+                .await?
+                .or_not_found()?;
+            if !is_master && !can_edit_scope(ctx, &scope, user_id).await? {
+                return Err(AppError::NoPermission(
+                    "You don't have permission to edit this scope".to_string(),
+                ));
+            }
+            let entry = Entry::get_by_id(&ctx.db, scope.id, entry_id)
+                .await
+                .or_not_found()?;
+            (entry.metadata.display_name.to_string(), None, Some(entry))
+        }
+        CombatantSource::Custom { name } => (name, None, None),
+    };
+    check_initiative_message(ctx, channel_id, initiative_message_id, user_id, is_master).await?;
+    let mut transaction = ctx.db.begin().await?;
+    let encounter = locked_encounter(&mut transaction, space_id, channel_id).await?;
+    let encounter = encounter
+        .add_combatant(
+            &mut transaction,
+            name.as_deref().unwrap_or(&source_name),
+            character_id,
+            entry.as_ref().map(|entry| &entry.metadata),
+            initiative,
+            initiative_message_id,
+            user_id,
+        )
+        .await
+        .map_err(|error| match error {
+            crate::error::ModelError::Conflict(_) => {
+                AppError::Conflict("The combatant is already in the encounter".to_string())
+            }
+            error => error.into(),
+        })?;
+    transaction.commit().await?;
+    Update::encounter_changed(space_id, channel_id);
+    Ok(encounter)
+}
+
+async fn set_initiative(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Encounter, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let SetInitiative {
+        space_id,
+        channel_id,
+        combatant_id,
+        initiative,
+        initiative_message_id,
+    } = parse_body(req).await?;
+    let user_id = session.user_id;
+    let is_master =
+        resolve_encounter_access(ctx, space_id, channel_id, Some(user_id), true).await?;
+    let mut transaction = ctx.db.begin().await?;
+    let encounter = locked_encounter(&mut transaction, space_id, channel_id).await?;
+    let combatant = encounter
+        .combatant(combatant_id)
+        .ok_or(AppError::NotFound("combatant"))?;
+    if !is_master && !can_control_combatant(ctx, space_id, combatant, user_id).await? {
+        return Err(AppError::NoPermission(
+            "You don't have permission to control this combatant".to_string(),
+        ));
+    }
+    check_initiative_message(ctx, channel_id, initiative_message_id, user_id, is_master).await?;
+    let encounter = encounter
+        .set_initiative(
+            &mut transaction,
+            combatant_id,
+            initiative,
+            initiative_message_id,
+        )
+        .await?
+        .ok_or(AppError::NotFound("combatant"))?;
+    transaction.commit().await?;
+    Update::encounter_changed(space_id, channel_id);
+    Ok(encounter)
+}
+
+async fn remove_combatant(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Encounter, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let RemoveCombatant {
+        space_id,
+        channel_id,
+        combatant_id,
+    } = parse_body(req).await?;
+    let user_id = session.user_id;
+    let is_master =
+        resolve_encounter_access(ctx, space_id, channel_id, Some(user_id), true).await?;
+    let mut transaction = ctx.db.begin().await?;
+    let encounter = locked_encounter(&mut transaction, space_id, channel_id).await?;
+    let combatant = encounter
+        .combatant(combatant_id)
+        .ok_or(AppError::NotFound("combatant"))?;
+    if !is_master && !can_control_combatant(ctx, space_id, combatant, user_id).await? {
+        return Err(AppError::NoPermission(
+            "You don't have permission to control this combatant".to_string(),
+        ));
+    }
+    let encounter = encounter
+        .remove_combatant(&mut transaction, combatant_id)
+        .await?
+        .ok_or(AppError::NotFound("combatant"))?;
+    transaction.commit().await?;
+    Update::encounter_changed(space_id, channel_id);
+    Ok(encounter)
+}
+
+async fn advance(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Encounter, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let AdvanceTurn {
+        space_id,
+        channel_id,
+        expected_version,
+    } = parse_body(req).await?;
+    let user_id = session.user_id;
+    let is_master =
+        resolve_encounter_access(ctx, space_id, channel_id, Some(user_id), true).await?;
+    // Counting down durations edits Entries.
+    let mutation = ctx.space_store.acquire_mutation(space_id).await?;
+    let mut transaction = ctx.db.begin().await?;
+    let encounter = locked_encounter(&mut transaction, space_id, channel_id).await?;
+    if encounter.version != expected_version {
+        return Err(AppError::Conflict("Encounter version is stale".to_string()));
+    }
+    let ending = encounter.current_combatant().cloned();
+    if !is_master {
+        let can_end_turn = match &ending {
+            Some(combatant) => can_control_combatant(ctx, space_id, combatant, user_id).await?,
+            None => false,
+        };
+        if !can_end_turn {
+            return Err(AppError::NoPermission(
+                "Only the game master or the current combatant can end the turn".to_string(),
+            ));
+        }
+    }
+    let changed_entries = match &ending {
+        Some(combatant) => {
+            encounter
+                .count_down_durations(&mut transaction, combatant.id, user_id)
+                .await?
+        }
+        None => Vec::new(),
+    };
+    let (encounter, turn) = encounter.advance(&mut transaction).await?;
+    let mutation = mutation.commit(transaction).await?;
+    let mut changes = CommittedChanges::default();
+    for entry in &changed_entries {
+        changes.entry_updated(space_id, entry);
+    }
+    changes.apply_with_mutation(ctx, &mutation).await;
+    Update::encounter_changed(space_id, channel_id);
+    if encounter.announce_turns {
+        announce_turn(ctx, &encounter, &turn, user_id).await?;
+    }
+    Ok(encounter)
+}
+
+pub async fn router(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+    path: &str,
+) -> Result<hyper::Response<Vec<u8>>, AppError> {
+    use hyper::Method;
+
+    match (path, req.method().clone()) {
+        ("/query", Method::GET) => response(query(ctx, req).await).await,
+        ("/start", Method::POST) => response(start(ctx, req).await).await,
+        ("/edit", Method::PUT) => response(edit(ctx, req).await).await,
+        ("/end", Method::POST) => response(end(ctx, req).await).await,
+        ("/add_combatant", Method::POST) => response(add_combatant(ctx, req).await).await,
+        ("/set_initiative", Method::PUT) => response(set_initiative(ctx, req).await).await,
+        ("/remove_combatant", Method::POST) => response(remove_combatant(ctx, req).await).await,
+        ("/advance", Method::POST) => response(advance(ctx, req).await).await,
+        _ => missing(),
+    }
+}
@@ -0,0 +1,729 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::entries::models::{
+    Entry, EntryComponentHistory, EntryComponentMutation, EntryComponentPayloadInput, EntryEffect,
+    EntryMetadata, validate_component_type,
+};
+use crate::entries::query::{is_index, path_segments};
+use crate::error::{ModelError, ValidationFailed};
+
+pub const MAX_COMBATANTS: usize = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, specta::Type, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Combatant {
+    pub id: Uuid,
+    pub channel_id: Uuid,
+    pub name: String,
+    pub character_id: Option<Uuid>,
+    pub entry_id: Option<Uuid>,
+    pub entry_scope_id: Option<Uuid>,
+    pub initiative: f64,
+    /// The roll message the initiative came from
+    pub initiative_message_id: Option<Uuid>,
+    pub added_by: Option<Uuid>,
+    #[specta(type = String)]
+    #[serde(with = "time::serde::rfc3339")]
+    pub created: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct EncounterRow {
+    channel_id: Uuid,
+    space_id: Uuid,
+    round: i32,
+    current_combatant_id: Option<Uuid>,
+    announce_turns: bool,
+    duration_component_type: Option<String>,
+    duration_path: String,
+    version: Uuid,
+    created: OffsetDateTime,
+    modified: OffsetDateTime,
+}
+
+/// The turn order of a channel. A channel has at most one encounter.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Encounter {
+    pub channel_id: Uuid,
+    pub space_id: Uuid,
+    pub round: i32,
+    /// `None` before the first turn
+    pub current_combatant_id: Option<Uuid>,
+    /// Post a message into the channel when the turn changes.
+    pub announce_turns: bool,
+    /// At the end of the turn of a combatant, the number at `durationPath` in this JSON component
+    /// counts down on its Entry, or on the Entries of its Character.
+    pub duration_component_type: Option<String>,
+    pub duration_path: String,
+    /// In turn order, by initiative from high to low.
+    pub combatants: Vec<Combatant>,
+    pub version: Uuid,
+    #[specta(type = String)]
+    #[serde(with = "time::serde::rfc3339")]
+    pub created: OffsetDateTime,
+    #[specta(type = String)]
+    #[serde(with = "time::serde::rfc3339")]
+    pub modified: OffsetDateTime,
+}
+
+/// Where the turn moved, for announcing it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TurnChange {
+    pub round: i32,
+    pub combatant_id: Uuid,
+    pub new_round: bool,
+}
+
+pub(crate) fn validate_duration_hook(
+    component_type: Option<&str>,
+    path: &str,
+) -> Result<(), ValidationFailed> {
+    if let Some(component_type) = component_type {
+        validate_component_type(component_type)?;
+    }
+    path_segments(path)?;
+    Ok(())
+}
+
+/// Count the number at the path down by one, stopping at zero. Returns whether it changed.
+fn count_down(data: &mut Value, segments: &[&str]) -> bool {
+    let mut value = data;
+    for segment in segments {
+        let next = match value {
+            Value::Object(fields) => fields.get_mut(*segment),
+            Value::Array(items) if is_index(segment) => segment
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| items.get_mut(index)),
+            _ => None,
+        };
+        let Some(next) = next else {
+            return false;
+        };
+        value = next;
+    }
+    let remaining = match value.as_i64() {
+        Some(number) if number > 0 => Value::from(number - 1),
+        Some(_) => return false,
+        None => match value.as_f64() {
+            Some(number) if number > 0.0 => Value::from((number - 1.0).max(0.0)),
+            _ => return false,
+        },
+    };
+    *value = remaining;
+    true
+}
+
+impl Encounter {
+    fn from_row(row: EncounterRow, combatants: Vec<Combatant>) -> Self {
+        Encounter {
+            channel_id: row.channel_id,
+            space_id: row.space_id,
+            round: row.round,
+            current_combatant_id: row.current_combatant_id,
+            announce_turns: row.announce_turns,
+            duration_component_type: row.duration_component_type,
+            duration_path: row.duration_path,
+            combatants,
+            version: row.version,
+            created: row.created,
+            modified: row.modified,
+        }
+    }
+
+    async fn with_combatants(
+        db: &mut sqlx::PgConnection,
+        row: EncounterRow,
+    ) -> Result<Self, ModelError> {
+        let combatants = sqlx::query_file_as!(
+            Combatant,
+            "sql/encounters/list_combatants.sql",
+            row.channel_id
+        )
+        .fetch_all(&mut *db)
+        .await?;
+        Ok(Self::from_row(row, combatants))
+    }
+
+    pub async fn get(
+        db: &mut sqlx::PgConnection,
+        space_id: Uuid,
+        channel_id: Uuid,
+    ) -> Result<Option<Self>, ModelError> {
+        let Some(row) =
+            sqlx::query_file_as!(EncounterRow, "sql/encounters/get.sql", space_id, channel_id)
+                .fetch_optional(&mut *db)
+                .await?
+        else {
+            return Ok(None);
+        };
+        Self::with_combatants(db, row).await.map(Some)
+    }
+
+    /// Lock the encounter until the transaction ends.
+    pub async fn get_for_update(
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        space_id: Uuid,
+        channel_id: Uuid,
+    ) -> Result<Option<Self>, ModelError> {
+        let Some(row) = sqlx::query_file_as!(
+            EncounterRow,
+            "sql/encounters/get_for_update.sql",
+            space_id,
+            channel_id
+        )
+        .fetch_optional(&mut **db)
+        .await?
+        else {
+            return Ok(None);
+        };
+        Self::with_combatants(db, row).await.map(Some)
+    }
+
+    pub async fn create(
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        space_id: Uuid,
+        channel_id: Uuid,
+        announce_turns: bool,
+        duration_component_type: Option<String>,
+        duration_path: String,
+    ) -> Result<Self, ModelError> {
+        let duration_path = duration_path.trim().to_string();
+        validate_duration_hook(duration_component_type.as_deref(), &duration_path)?;
+        let row = sqlx::query_file_as!(
+            EncounterRow,
+            "sql/encounters/create.sql",
+            channel_id,
+            space_id,
+            announce_turns,
+            duration_component_type,
+            duration_path,
+        )
+        .fetch_one(&mut **db)
+        .await?;
+        Ok(Self::from_row(row, Vec::new()))
+    }
+
+    pub async fn update_settings(
+        &self,
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        announce_turns: bool,
+        duration_component_type: Option<String>,
+        duration_path: String,
+    ) -> Result<Self, ModelError> {
+        let duration_path = duration_path.trim().to_string();
+        validate_duration_hook(duration_component_type.as_deref(), &duration_path)?;
+        let row = sqlx::query_file_as!(
+            EncounterRow,
+            "sql/encounters/update_settings.sql",
+            self.space_id,
+            self.channel_id,
+            announce_turns,
+            duration_component_type,
+            duration_path,
+        )
+        .fetch_one(&mut **db)
+        .await?;
+        Self::with_combatants(db, row).await
+    }
+
+    pub async fn delete<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+        channel_id: Uuid,
+    ) -> Result<bool, ModelError> {
+        let result = sqlx::query_file!("sql/encounters/delete.sql", space_id, channel_id)
+            .execute(db)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Bump the version after a change of the combatants, and read the encounter again.
+    async fn touch(
+        &self,
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Self, ModelError> {
+        let row = sqlx::query_file_as!(
+            EncounterRow,
+            "sql/encounters/touch.sql",
+            self.space_id,
+            self.channel_id
+        )
+        .fetch_one(&mut **db)
+        .await?;
+        Self::with_combatants(db, row).await
+    }
+
+    async fn set_turn(
+        &self,
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        round: i32,
+        current_combatant_id: Option<Uuid>,
+    ) -> Result<Self, ModelError> {
+        let row = sqlx::query_file_as!(
+            EncounterRow,
+            "sql/encounters/set_turn.sql",
+            self.space_id,
+            self.channel_id,
+            round,
+            current_combatant_id,
+        )
+        .fetch_one(&mut **db)
+        .await?;
+        Self::with_combatants(db, row).await
+    }
+
+    pub fn combatant(&self, combatant_id: Uuid) -> Option<&Combatant> {
+        self.combatants
+            .iter()
+            .find(|combatant| combatant.id == combatant_id)
+    }
+
+    pub fn current_combatant(&self) -> Option<&Combatant> {
+        self.current_combatant_id
+            .and_then(|combatant_id| self.combatant(combatant_id))
+    }
+
+    /// The turn after the current one. When the current combatant is leaving, it is skipped.
+    fn next_turn(&self, leaving: bool) -> Option<TurnChange> {
+        let current = self.current_combatant().map(|combatant| combatant.id);
+        let is_candidate = |combatant: &&Combatant| !leaving || Some(combatant.id) != current;
+        let position = current.and_then(|id| {
+            self.combatants
+                .iter()
+                .position(|combatant| combatant.id == id)
+        });
+        let Some(position) = position else {
+            return self.combatants.first().map(|first| TurnChange {
+                round: self.round,
+                combatant_id: first.id,
+                new_round: false,
+            });
+        };
+        if let Some(next) = self.combatants[position + 1..].iter().find(is_candidate) {
+            return Some(TurnChange {
+                round: self.round,
+                combatant_id: next.id,
+                new_round: false,
+            });
+        }
+        self.combatants
+            .iter()
+            .find(is_candidate)
+            .map(|first| TurnChange {
+                round: self.round + 1,
+                combatant_id: first.id,
+                new_round: true,
+            })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn add_combatant(
+        &self,
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        name: &str,
+        character_id: Option<Uuid>,
+        entry: Option<&EntryMetadata>,
+        initiative: f64,
+        initiative_message_id: Option<Uuid>,
+        added_by: Uuid,
+    ) -> Result<Self, ModelError> {
+        if self.combatants.len() >= MAX_COMBATANTS {
+            return Err(ValidationFailed("An encounter can have up to 100 combatants.").into());
+        }
+        let name = name.trim();
+        crate::validators::CHARACTER_NAME.run(name)?;
+        validate_initiative(initiative)?;
+        let entry_id = entry.map(|entry| entry.id);
+        let already_added = self.combatants.iter().any(|combatant| {
+            (character_id.is_some() && combatant.character_id == character_id)
+                || (entry_id.is_some() && combatant.entry_id == entry_id)
+        });
+        if already_added {
+            return Err(ModelError::Conflict("encounter_combatants".to_string()));
+        }
+        sqlx::query_file_as!(
+            Combatant,
+            "sql/encounters/insert_combatant.sql",
+            self.channel_id,
+            name,
+            character_id,
+            entry_id,
+            entry.map(|entry| entry.scope_id),
+            initiative,
+            initiative_message_id,
+            added_by,
+        )
+        .fetch_one(&mut **db)
+        .await?;
+        self.touch(db).await
+    }
+
+    pub async fn set_initiative(
+        &self,
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        combatant_id: Uuid,
+        initiative: f64,
+        initiative_message_id: Option<Uuid>,
+    ) -> Result<Option<Self>, ModelError> {
+        validate_initiative(initiative)?;
+        let updated = sqlx::query_file_as!(
+            Combatant,
+            "sql/encounters/set_initiative.sql",
+            self.channel_id,
+            combatant_id,
+            initiative,
+            initiative_message_id,
+        )
+        .fetch_optional(&mut **db)
+        .await?;
+        if updated.is_none() {
+            return Ok(None);
+        }
+        self.touch(db).await.map(Some)
+    }
+
+    /// Remove a combatant. The turn of a removed current combatant passes to the next one.
+    pub async fn remove_combatant(
+        &self,
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        combatant_id: Uuid,
+    ) -> Result<Option<Self>, ModelError> {
+        if self.combatant(combatant_id).is_none() {
+            return Ok(None);
+        }
+        let is_current = self.current_combatant_id == Some(combatant_id);
+        let next_turn = if is_current {
+            self.next_turn(true)
+        } else {
+            None
+        };
+        sqlx::query_file!(
+            "sql/encounters/delete_combatant.sql",
+            self.channel_id,
+            combatant_id
+        )
+        .execute(&mut **db)
+        .await?;
+        if is_current {
+            let (round, current) = match next_turn {
+                Some(turn) => (turn.round, Some(turn.combatant_id)),
+                None => (self.round, None),
+            };
+            return self.set_turn(db, round, current).await.map(Some);
+        }
+        self.touch(db).await.map(Some)
+    }
+
+    /// Pass the turn to the next combatant, starting a new round after the last one.
+    pub async fn advance(
+        &self,
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(Self, TurnChange), ModelError> {
+        let turn = self
+            .next_turn(false)
+            .ok_or(ValidationFailed("The encounter has no combatants."))?;
+        let encounter = self
+            .set_turn(db, turn.round, Some(turn.combatant_id))
+            .await?;
+        Ok((encounter, turn))
+    }
+
+    /// Count down the durations of a combatant whose turn ended. Returns the changed Entries.
+    pub async fn count_down_durations(
+        &self,
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        combatant_id: Uuid,
+        operator_id: Uuid,
+    ) -> Result<Vec<EntryMetadata>, ModelError> {
+        let Some(component_type) = self.duration_component_type.as_deref() else {
+            return Ok(Vec::new());
+        };
+        let segments = path_segments(&self.duration_path)?;
+        let rows = sqlx::query_file!(
+            "sql/encounters/list_durations.sql",
+            combatant_id,
+            component_type
+        )
+        .fetch_all(&mut **db)
+        .await?;
+        let mut effects: Vec<EntryEffect> = Vec::new();
+        let mut changed = Vec::new();
+        for row in rows {
+            let mut data = row.data;
+            if !count_down(&mut data, &segments) {
+                continue;
+            }
+            let effect = match effects
+                .iter()
+                .find(|effect| effect.scope_id == row.scope_id)
+            {
+                Some(effect) => effect.clone(),
+                None => {
+                    let effect =
+                        EntryEffect::create(db, self.space_id, row.scope_id, operator_id).await?;
+                    effects.push(effect.clone());
+                    effect
+                }
+            };
+            let mutation = EntryComponentMutation::Set {
+                component_type: component_type.to_string(),
+                expected_version: Some(row.version),
+                payload: EntryComponentPayloadInput::Json {
+                    schema_version: Some(row.schema_version),
+                    data,
+                },
+            };
+            let result = Entry::apply_component_mutations(db, row.id, &[mutation]).await?;
+            EntryComponentHistory::record(db, effect.id, row.id, &row.key, &result.history_changes)
+                .await?;
+            let entry = Entry::get_by_id_in_transaction(db, row.scope_id, row.id)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("The counted down Entry is missing"))?;
+            changed.push(entry.metadata);
+        }
+        Ok(changed)
+    }
+}
+
+fn validate_initiative(initiative: f64) -> Result<(), ValidationFailed> {
+    if !initiative.is_finite() || initiative.abs() > 1_000_000.0 {
+        return Err(ValidationFailed("The initiative is out of range."));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn encounter(initiatives: &[f64], current: Option<usize>) -> Encounter {
+        let created = OffsetDateTime::UNIX_EPOCH;
+        let combatants = initiatives
+            .iter()
+            .enumerate()
+            .map(|(index, initiative)| Combatant {
+                id: Uuid::from_u128(index as u128 + 1),
+                channel_id: Uuid::nil(),
+                name: format!("Combatant {index}"),
+                character_id: None,
+                entry_id: None,
+                entry_scope_id: None,
+                initiative: *initiative,
+                initiative_message_id: None,
+                added_by: None,
+                created,
+            })
+            .collect::<Vec<_>>();
+        Encounter {
+            channel_id: Uuid::nil(),
+            space_id: Uuid::nil(),
+            round: 1,
+            current_combatant_id: current.map(|index| combatants[index].id),
+            announce_turns: false,
+            duration_component_type: None,
+            duration_path: String::new(),
+            combatants,
+            version: Uuid::nil(),
+            created,
+            modified: created,
+        }
+    }
+
+    #[test]
+    fn turns_go_around() {
+        let id = |n| Uuid::from_u128(n);
+        let not_started = encounter(&[18.0, 12.0, 5.0], None);
+        assert_eq!(
+            not_started.next_turn(false),
+            Some(TurnChange {
+                round: 1,
+                combatant_id: id(1),
+                new_round: false,
+            })
+        );
+        let last = encounter(&[18.0, 12.0, 5.0], Some(2));
+        assert_eq!(
+            last.next_turn(false),
+            Some(TurnChange {
+                round: 2,
+                combatant_id: id(1),
+                new_round: true,
+            })
+        );
+        // Removing the current combatant passes the turn on.
+        let middle = encounter(&[18.0, 12.0, 5.0], Some(1));
+        assert_eq!(middle.next_turn(true).unwrap().combatant_id, id(3));
+        assert_eq!(last.next_turn(true).unwrap().combatant_id, id(1));
+        assert_eq!(encounter(&[10.0], Some(0)).next_turn(true), None);
+        assert_eq!(encounter(&[], None).next_turn(false), None);
+    }
+
+    #[test]
+    fn durations_count_down() {
+        let mut data = json!({ "rounds": 2, "effects": [{ "left": 0.5 }] });
+        assert!(count_down(&mut data, &["rounds"]));
+        assert!(count_down(&mut data, &["effects", "0", "left"]));
+        assert!(!count_down(&mut data, &["missing"]));
+        assert_eq!(data, json!({ "rounds": 1, "effects": [{ "left": 0.0 }] }));
+        let mut expired = json!(0);
+        assert!(!count_down(&mut expired, &[]));
+        let mut text = json!("3");
+        assert!(!count_down(&mut text, &[]));
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn db_test_turns_count_down_durations(pool: sqlx::PgPool) {
+        use crate::channels::{Channel, ChannelType};
+        use crate::entries::models::EntryComponent;
+        use crate::spaces::Space;
+        use crate::users::User;
+        use std::collections::BTreeMap;
+
+        let raw = Uuid::new_v4().simple().to_string();
+        let user = User::register(
+            &pool,
+            &format!("encounter_{raw}@example.com"),
+            &format!("encounter_{}", &raw[..8]),
+            "Encounter Tester",
+            "EncounterPass123!",
+        )
+        .await
+        .expect("create user failed");
+        let space = Space::create(
+            &pool,
+            format!("encounter_{}", &raw[..8]),
+            &user.id,
+            "Encounter test".to_string(),
+            None,
+            Some("d20"),
+        )
+        .await
+        .expect("create space failed");
+        let channel = Channel::create(
+            &pool,
+            &space.id,
+            "Battle",
+            true,
+            Some("d20"),
+            ChannelType::InGame,
+        )
+        .await
+        .expect("create channel failed");
+
+        let mut transaction = pool.begin().await.unwrap();
+        let goblin = Entry::create(
+            &mut transaction,
+            space.scope_id,
+            "goblin".to_string(),
+            Vec::new(),
+            "Goblin".to_string(),
+            None,
+            BTreeMap::from([(
+                "example/conditions".to_string(),
+                EntryComponentPayloadInput::json(json!({ "stunned": 2 })),
+            )]),
+            Vec::new(),
+            None,
+        )
+        .await
+        .expect("create entry failed");
+        let encounter = Encounter::create(
+            &mut transaction,
+            space.id,
+            channel.id,
+            false,
+            Some("example/conditions".to_string()),
+            " stunned ".to_string(),
+        )
+        .await
+        .expect("start encounter failed");
+        let encounter = encounter
+            .add_combatant(
+                &mut transaction,
+                "Goblin",
+                None,
+                Some(&goblin.metadata),
+                12.0,
+                None,
+                user.id,
+            )
+            .await
+            .expect("add goblin failed");
+        assert!(
+            encounter
+                .add_combatant(
+                    &mut transaction,
+                    "Goblin again",
+                    None,
+                    Some(&goblin.metadata),
+                    3.0,
+                    None,
+                    user.id,
+                )
+                .await
+                .is_err(),
+            "an entry joins an encounter once"
+        );
+        let encounter = encounter
+            .add_combatant(&mut transaction, " Orc ", None, None, 15.0, None, user.id)
+            .await
+            .expect("add orc failed");
+        let names: Vec<_> = encounter
+            .combatants
+            .iter()
+            .map(|combatant| combatant.name.as_str())
+            .collect();
+        assert_eq!(names, ["Orc", "Goblin"]);
+        let goblin_id = encounter.combatants[1].id;
+
+        let (encounter, _) = encounter.advance(&mut transaction).await.unwrap();
+        let (encounter, turn) = encounter.advance(&mut transaction).await.unwrap();
+        assert_eq!(turn.combatant_id, goblin_id);
+        let changed = encounter
+            .count_down_durations(&mut transaction, goblin_id, user.id)
+            .await
+            .expect("count down failed");
+        assert_eq!(changed.len(), 1);
+        let (encounter, turn) = encounter.advance(&mut transaction).await.unwrap();
+        assert!(turn.new_round);
+        assert_eq!(encounter.round, 2);
+        let goblin = Entry::get_by_id_in_transaction(&mut transaction, space.scope_id, goblin.id)
+            .await
+            .unwrap()
+            .unwrap();
+        let Some(EntryComponent::Json { data, .. }) = goblin.components.get("example/conditions")
+        else {
+            panic!("the conditions of the goblin are missing");
+        };
+        assert_eq!(data, &json!({ "stunned": 1 }));
+
+        let orc_id = encounter.current_combatant_id.unwrap();
+        let encounter = encounter
+            .remove_combatant(&mut transaction, orc_id)
+            .await
+            .unwrap()
+            .expect("the orc is in the encounter");
+        assert_eq!(encounter.current_combatant_id, Some(goblin_id));
+        assert_eq!(encounter.round, 2);
+        transaction.commit().await.unwrap();
+
+        assert!(
+            Encounter::delete(&pool, space.id, channel.id)
+                .await
+                .unwrap()
+        );
+        let mut conn = pool.acquire().await.unwrap();
+        assert!(
+            Encounter::get(&mut conn, space.id, channel.id)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
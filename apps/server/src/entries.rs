@@ -1,6 +1,13 @@
 mod api;
 pub(crate) mod component_cache;
+pub(crate) mod component_types;
+pub(crate) mod formula;
 pub(crate) mod handlers;
+pub(crate) mod import;
 pub(crate) mod models;
+pub(crate) mod query;
+pub(crate) mod revert;
+pub(crate) mod templates;
+pub(crate) mod timeline;
 
 pub use handlers::router;
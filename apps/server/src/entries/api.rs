@@ -1,8 +1,13 @@
 use serde::Deserialize;
 use std::collections::BTreeMap;
+use time::OffsetDateTime;
 use uuid::Uuid;
 
+use super::component_types::ComponentMigrationStep;
+use super::import::{EntryImportSource, EntryImportTarget};
 use super::models::{EntryComponentMutation, EntryComponentPayloadInput};
+use super::query::{EntryFilter, EntrySort};
+use super::templates::EntryTemplateInput;
 
 #[derive(Debug, Deserialize, specta::Type)]
 #[serde(rename_all = "camelCase")]
@@ -19,6 +24,28 @@ pub struct ListEntriesByComponent {
     pub component_type: String,
 }
 
+/// Filter and sort the Entries of a Space, across the Scopes the user can view.
+#[derive(Debug, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryEntries {
+    pub space_id: Uuid,
+    /// Only query these Scopes. All viewable Scopes of the Space if empty.
+    #[serde(default)]
+    pub scope_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub filter: EntryFilter,
+    #[serde(default)]
+    pub sort: EntrySort,
+    #[serde(default)]
+    pub descending: bool,
+    #[serde(default)]
+    #[specta(type = f64)]
+    pub offset: i64,
+    #[serde(default)]
+    #[specta(type = Option<f64>)]
+    pub limit: Option<i64>,
+}
+
 #[derive(Debug, Deserialize, specta::Type)]
 #[serde(rename_all = "camelCase")]
 pub struct QueryEntry {
@@ -54,6 +81,41 @@ pub struct QueryEntryEffectsByMessages {
     pub message_ids: Vec<Uuid>,
 }
 
+/// A point in the history is either a time, or a message, which means the state when the
+/// message was sent, before its own Entry Effects. Defaults to now.
+#[derive(Debug, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ScopeStateQuery {
+    pub space_id: Uuid,
+    pub scope_id: Uuid,
+    #[serde(default)]
+    #[specta(type = Option<String>)]
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub at: Option<OffsetDateTime>,
+    #[serde(default)]
+    pub message_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ScopeStateDiffQuery {
+    pub space_id: Uuid,
+    pub scope_id: Uuid,
+    #[serde(default)]
+    #[specta(type = Option<String>)]
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub from: Option<OffsetDateTime>,
+    #[serde(default)]
+    pub from_message_id: Option<Uuid>,
+    /// Defaults to now.
+    #[serde(default)]
+    #[specta(type = Option<String>)]
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub to: Option<OffsetDateTime>,
+    #[serde(default)]
+    pub to_message_id: Option<Uuid>,
+}
+
 #[derive(Debug, Deserialize, specta::Type)]
 #[serde(rename_all = "camelCase")]
 pub struct CheckEntryIdentifier {
@@ -123,6 +185,106 @@ pub struct EditEntryComponents {
     pub changes: Vec<EntryComponentMutation>,
 }
 
+#[derive(Debug, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ListComponentTypes {
+    pub space_id: Uuid,
+}
+
+#[derive(Debug, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterComponentType {
+    pub space_id: Uuid,
+    pub component_type: String,
+    /// Must be the next version of the type, starting from 1.
+    pub schema_version: i32,
+    /// A JSON Schema for the component data.
+    pub schema: serde_json::Value,
+    /// Upgrades the stored components from the previous schema version.
+    #[serde(default)]
+    pub migration: Vec<ComponentMigrationStep>,
+}
+
+#[derive(Debug, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportEntries {
+    pub space_id: Uuid,
+    pub target: EntryImportTarget,
+    pub source: EntryImportSource,
+    /// Check every row without importing anything.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ListEntryTemplates {
+    pub space_id: Uuid,
+}
+
+#[derive(Debug, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateEntryTemplate {
+    pub space_id: Uuid,
+    pub template: EntryTemplateInput,
+}
+
+#[derive(Debug, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EditEntryTemplate {
+    pub space_id: Uuid,
+    pub template_id: Uuid,
+    pub expected_version: Uuid,
+    pub template: EntryTemplateInput,
+}
+
+#[derive(Debug, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteEntryTemplate {
+    pub space_id: Uuid,
+    pub template_id: Uuid,
+}
+
+#[derive(Debug, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportEntryTemplates {
+    pub space_id: Uuid,
+    pub templates: Vec<EntryTemplateInput>,
+    /// Replace the templates with the same names, instead of failing.
+    #[serde(default)]
+    pub replace_existing: bool,
+}
+
+#[derive(Debug, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct InstantiateEntryTemplate {
+    pub space_id: Uuid,
+    pub template_id: Uuid,
+    pub scope_id: Uuid,
+    /// How many Entries to create, 1 to 50.
+    #[serde(default = "default_instance_count")]
+    pub count: u32,
+    /// Omit or set to `null` to append the Entries to the end.
+    #[serde(default)]
+    pub before_entry_id: Option<Uuid>,
+    #[serde(default)]
+    pub message_id: Option<Uuid>,
+}
+
+fn default_instance_count() -> u32 {
+    1
+}
+
+#[derive(Debug, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RevertMessageEffects {
+    pub space_id: Uuid,
+    pub message_id: Uuid,
+    /// Revert the rest when some components conflict with later changes, instead of nothing.
+    #[serde(default)]
+    pub skip_conflicts: bool,
+}
+
 #[derive(Debug, Deserialize, specta::Type)]
 #[serde(rename_all = "camelCase")]
 pub struct DeleteEntry {
@@ -0,0 +1,1141 @@
+use compact_str::CompactString;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use super::models::{
+    EntryComponentHistory, EntryComponentHistoryChange, EntryEffect, EntryMetadata,
+    validate_component_type,
+};
+use crate::error::{ModelError, ValidationFailed};
+
+/// Component types in this namespace are built into the server and can not be registered.
+const BUILTIN_NAMESPACE: &str = "core/";
+const MAX_SCHEMA_BYTES: usize = 64 * 1024;
+const MAX_SCHEMA_DEPTH: usize = 32;
+const MAX_MIGRATION_STEPS: usize = 64;
+const PATTERN_SIZE_LIMIT: usize = 256 * 1024;
+/// Keywords that only describe the data, which are accepted but not checked.
+const ANNOTATION_KEYWORDS: &[&str] = &[
+    "$schema",
+    "$comment",
+    "title",
+    "description",
+    "default",
+    "examples",
+];
+
+const TYPE_ERROR: &str = "A component field has the wrong type.";
+const VALUE_ERROR: &str = "A component field is not one of the allowed values.";
+const RANGE_ERROR: &str = "A component field is out of range.";
+const LENGTH_ERROR: &str = "A component field has the wrong length.";
+const PATTERN_ERROR: &str = "A component field does not match its pattern.";
+const REQUIRED_ERROR: &str = "A required component field is missing.";
+const NOT_ALLOWED_ERROR: &str = "A component field is not allowed.";
+const PATH_ERROR: &str = "A migration step can not reach its path.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JsonType {
+    Null,
+    Boolean,
+    Integer,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+impl JsonType {
+    fn parse(value: &Value) -> Result<Self, ValidationFailed> {
+        Ok(match value.as_str() {
+            Some("null") => Self::Null,
+            Some("boolean") => Self::Boolean,
+            Some("integer") => Self::Integer,
+            Some("number") => Self::Number,
+            Some("string") => Self::String,
+            Some("array") => Self::Array,
+            Some("object") => Self::Object,
+            _ => return Err(ValidationFailed("A component schema has an unknown type.")),
+        })
+    }
+
+    fn matches(self, value: &Value) -> bool {
+        match (self, value) {
+            (Self::Null, Value::Null)
+            | (Self::Boolean, Value::Bool(_))
+            | (Self::Number, Value::Number(_))
+            | (Self::String, Value::String(_))
+            | (Self::Array, Value::Array(_))
+            | (Self::Object, Value::Object(_)) => true,
+            (Self::Integer, Value::Number(number)) => {
+                number.is_i64()
+                    || number.is_u64()
+                    || number.as_f64().is_some_and(|float| float.fract() == 0.0)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// A compiled JSON Schema.
+///
+/// Only the validation keywords which are common in component data are supported. Unknown
+/// keywords are rejected instead of ignored, so a schema never looks stricter than it is.
+#[derive(Debug, Default)]
+pub struct ComponentSchema {
+    /// Compiled from the `false` schema.
+    reject_all: bool,
+    types: Vec<JsonType>,
+    allowed: Option<Vec<Value>>,
+    constant: Option<Value>,
+    minimum: Option<f64>,
+    maximum: Option<f64>,
+    exclusive_minimum: Option<f64>,
+    exclusive_maximum: Option<f64>,
+    min_length: Option<u64>,
+    max_length: Option<u64>,
+    pattern: Option<Regex>,
+    items: Option<Box<ComponentSchema>>,
+    min_items: Option<u64>,
+    max_items: Option<u64>,
+    properties: BTreeMap<String, ComponentSchema>,
+    required: Vec<String>,
+    additional_properties: Option<Box<ComponentSchema>>,
+}
+
+fn schema_number(value: &Value) -> Result<f64, ValidationFailed> {
+    value.as_f64().ok_or(ValidationFailed(
+        "A component schema bound must be a number.",
+    ))
+}
+
+fn schema_count(value: &Value) -> Result<u64, ValidationFailed> {
+    value.as_u64().ok_or(ValidationFailed(
+        "A component schema count must be a non-negative integer.",
+    ))
+}
+
+impl ComponentSchema {
+    pub fn compile(schema: &Value) -> Result<ComponentSchema, ValidationFailed> {
+        if schema.to_string().len() > MAX_SCHEMA_BYTES {
+            return Err(ValidationFailed(
+                "A component schema shall not be more than 64 KiB.",
+            ));
+        }
+        Self::compile_at(schema, 0)
+    }
+
+    fn compile_at(schema: &Value, depth: usize) -> Result<ComponentSchema, ValidationFailed> {
+        if depth > MAX_SCHEMA_DEPTH {
+            return Err(ValidationFailed("A component schema is nested too deeply."));
+        }
+        let keywords = match schema {
+            Value::Bool(accept) => {
+                return Ok(ComponentSchema {
+                    reject_all: !accept,
+                    ..ComponentSchema::default()
+                });
+            }
+            Value::Object(keywords) => keywords,
+            _ => {
+                return Err(ValidationFailed(
+                    "A component schema must be an object or a boolean.",
+                ));
+            }
+        };
+        let mut compiled = ComponentSchema::default();
+        for (keyword, value) in keywords {
+            match keyword.as_str() {
+                "type" => {
+                    compiled.types = match value {
+                        Value::Array(types) => types
+                            .iter()
+                            .map(JsonType::parse)
+                            .collect::<Result<_, _>>()?,
+                        value => vec![JsonType::parse(value)?],
+                    }
+                }
+                "enum" => {
+                    let Value::Array(values) = value else {
+                        return Err(ValidationFailed(
+                            "A component schema enum must be an array.",
+                        ));
+                    };
+                    compiled.allowed = Some(values.clone());
+                }
+                "const" => compiled.constant = Some(value.clone()),
+                "minimum" => compiled.minimum = Some(schema_number(value)?),
+                "maximum" => compiled.maximum = Some(schema_number(value)?),
+                "exclusiveMinimum" => compiled.exclusive_minimum = Some(schema_number(value)?),
+                "exclusiveMaximum" => compiled.exclusive_maximum = Some(schema_number(value)?),
+                "minLength" => compiled.min_length = Some(schema_count(value)?),
+                "maxLength" => compiled.max_length = Some(schema_count(value)?),
+                "pattern" => {
+                    let pattern = value.as_str().ok_or(ValidationFailed(
+                        "A component schema pattern must be a string.",
+                    ))?;
+                    let pattern = RegexBuilder::new(pattern)
+                        .size_limit(PATTERN_SIZE_LIMIT)
+                        .build()
+                        .map_err(|_| {
+                            ValidationFailed("A component schema pattern is not a valid regex.")
+                        })?;
+                    compiled.pattern = Some(pattern);
+                }
+                "items" => compiled.items = Some(Box::new(Self::compile_at(value, depth + 1)?)),
+                "minItems" => compiled.min_items = Some(schema_count(value)?),
+                "maxItems" => compiled.max_items = Some(schema_count(value)?),
+                "properties" => {
+                    let Value::Object(properties) = value else {
+                        return Err(ValidationFailed(
+                            "Component schema properties must be an object.",
+                        ));
+                    };
+                    for (name, schema) in properties {
+                        compiled
+                            .properties
+                            .insert(name.clone(), Self::compile_at(schema, depth + 1)?);
+                    }
+                }
+                "required" => {
+                    compiled.required = value
+                        .as_array()
+                        .and_then(|names| {
+                            names
+                                .iter()
+                                .map(|name| name.as_str().map(ToString::to_string))
+                                .collect()
+                        })
+                        .ok_or(ValidationFailed(
+                            "A component schema required list must contain strings.",
+                        ))?;
+                }
+                "additionalProperties" => {
+                    compiled.additional_properties =
+                        Some(Box::new(Self::compile_at(value, depth + 1)?));
+                }
+                keyword if ANNOTATION_KEYWORDS.contains(&keyword) => {}
+                _ => {
+                    return Err(ValidationFailed(
+                        "A component schema uses an unsupported keyword.",
+                    ));
+                }
+            }
+        }
+        Ok(compiled)
+    }
+
+    pub fn validate(&self, value: &Value) -> Result<(), ValidationFailed> {
+        if self.reject_all {
+            return Err(ValidationFailed(NOT_ALLOWED_ERROR));
+        }
+        if !self.types.is_empty() && !self.types.iter().any(|ty| ty.matches(value)) {
+            return Err(ValidationFailed(TYPE_ERROR));
+        }
+        if self
+            .allowed
+            .as_ref()
+            .is_some_and(|allowed| !allowed.contains(value))
+            || self
+                .constant
+                .as_ref()
+                .is_some_and(|constant| constant != value)
+        {
+            return Err(ValidationFailed(VALUE_ERROR));
+        }
+        match value {
+            Value::Number(number) => {
+                let number = number.as_f64().unwrap_or_default();
+                if self.minimum.is_some_and(|minimum| number < minimum)
+                    || self.maximum.is_some_and(|maximum| number > maximum)
+                    || self
+                        .exclusive_minimum
+                        .is_some_and(|minimum| number <= minimum)
+                    || self
+                        .exclusive_maximum
+                        .is_some_and(|maximum| number >= maximum)
+                {
+                    return Err(ValidationFailed(RANGE_ERROR));
+                }
+            }
+            Value::String(text) => {
+                let length = text.chars().count() as u64;
+                if self.min_length.is_some_and(|min| length < min)
+                    || self.max_length.is_some_and(|max| length > max)
+                {
+                    return Err(ValidationFailed(LENGTH_ERROR));
+                }
+                if self
+                    .pattern
+                    .as_ref()
+                    .is_some_and(|pattern| !pattern.is_match(text))
+                {
+                    return Err(ValidationFailed(PATTERN_ERROR));
+                }
+            }
+            Value::Array(items) => {
+                let length = items.len() as u64;
+                if self.min_items.is_some_and(|min| length < min)
+                    || self.max_items.is_some_and(|max| length > max)
+                {
+                    return Err(ValidationFailed(LENGTH_ERROR));
+                }
+                if let Some(schema) = &self.items {
+                    items.iter().try_for_each(|item| schema.validate(item))?;
+                }
+            }
+            Value::Object(fields) => {
+                if self.required.iter().any(|name| !fields.contains_key(name)) {
+                    return Err(ValidationFailed(REQUIRED_ERROR));
+                }
+                for (name, field) in fields {
+                    match self.properties.get(name) {
+                        Some(schema) => schema.validate(field)?,
+                        None => {
+                            if let Some(schema) = &self.additional_properties {
+                                schema.validate(field)?;
+                            }
+                        }
+                    }
+                }
+            }
+            Value::Null | Value::Bool(_) => {}
+        }
+        Ok(())
+    }
+}
+
+/// One step of a migration, which upgrades component data from the previous schema version.
+///
+/// Paths are JSON Pointers, such as `/stats/hp`. `SET` replaces the field, while `DEFAULT` only
+/// fills it in if it is missing. Steps whose source is missing do nothing.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, specta::Type)]
+#[serde(
+    tag = "op",
+    rename_all = "SCREAMING_SNAKE_CASE",
+    rename_all_fields = "camelCase"
+)]
+pub enum ComponentMigrationStep {
+    Set { path: String, value: Value },
+    Default { path: String, value: Value },
+    Rename { from: String, to: String },
+    Copy { from: String, to: String },
+    Remove { path: String },
+}
+
+impl ComponentMigrationStep {
+    fn paths(&self) -> impl Iterator<Item = &str> {
+        let (first, second) = match self {
+            Self::Set { path, .. } | Self::Default { path, .. } | Self::Remove { path } => {
+                (path, None)
+            }
+            Self::Rename { from, to } | Self::Copy { from, to } => (from, Some(to)),
+        };
+        std::iter::once(first.as_str()).chain(second.map(String::as_str))
+    }
+
+    fn apply(&self, data: &mut Value) -> Result<(), ValidationFailed> {
+        match self {
+            Self::Set { path, value } => put(data, path, value.clone()),
+            Self::Default { path, value } => match data.pointer(path) {
+                Some(_) => Ok(()),
+                None => put(data, path, value.clone()),
+            },
+            Self::Rename { from, to } => match take(data, from) {
+                Some(value) => put(data, to, value),
+                None => Ok(()),
+            },
+            Self::Copy { from, to } => match data.pointer(from).cloned() {
+                Some(value) => put(data, to, value),
+                None => Ok(()),
+            },
+            Self::Remove { path } => {
+                take(data, path);
+                Ok(())
+            }
+        }
+    }
+}
+
+fn validate_migration(steps: &[ComponentMigrationStep]) -> Result<(), ValidationFailed> {
+    if steps.len() > MAX_MIGRATION_STEPS {
+        return Err(ValidationFailed(
+            "A migration shall not have more than 64 steps.",
+        ));
+    }
+    if steps
+        .iter()
+        .flat_map(ComponentMigrationStep::paths)
+        .any(|path| !path.starts_with('/'))
+    {
+        return Err(ValidationFailed(
+            "A migration path must be a JSON Pointer to a field.",
+        ));
+    }
+    Ok(())
+}
+
+fn unescape_token(token: &str) -> Cow<'_, str> {
+    if token.contains('~') {
+        Cow::Owned(token.replace("~1", "/").replace("~0", "~"))
+    } else {
+        Cow::Borrowed(token)
+    }
+}
+
+fn take(data: &mut Value, pointer: &str) -> Option<Value> {
+    let (parent, token) = pointer.rsplit_once('/')?;
+    let token = unescape_token(token);
+    match data.pointer_mut(parent)? {
+        Value::Object(fields) => fields.remove(token.as_ref()),
+        Value::Array(items) => {
+            let index = token.parse::<usize>().ok()?;
+            (index < items.len()).then(|| items.remove(index))
+        }
+        _ => None,
+    }
+}
+
+/// Set the value at the pointer, creating the missing objects on the way.
+fn put(data: &mut Value, pointer: &str, value: Value) -> Result<(), ValidationFailed> {
+    let tokens: Vec<_> = pointer.split('/').skip(1).map(unescape_token).collect();
+    let Some((last, parents)) = tokens.split_last() else {
+        return Err(ValidationFailed(PATH_ERROR));
+    };
+    let mut target = data;
+    for token in parents {
+        target = match target {
+            Value::Object(fields) => fields
+                .entry(token.as_ref())
+                .or_insert_with(|| Value::Object(serde_json::Map::new())),
+            Value::Array(items) => token
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| items.get_mut(index))
+                .ok_or(ValidationFailed(PATH_ERROR))?,
+            _ => return Err(ValidationFailed(PATH_ERROR)),
+        };
+    }
+    match target {
+        Value::Object(fields) => {
+            fields.insert(last.to_string(), value);
+        }
+        Value::Array(items) if last == "-" => items.push(value),
+        Value::Array(items) => {
+            let item = last
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| items.get_mut(index))
+                .ok_or(ValidationFailed(PATH_ERROR))?;
+            *item = value;
+        }
+        _ => return Err(ValidationFailed(PATH_ERROR)),
+    }
+    Ok(())
+}
+
+/// A schema version of a component type registered by a space.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ComponentTypeVersion {
+    pub component_type: String,
+    pub schema_version: i32,
+    pub schema: Value,
+    /// Upgrades data of the previous schema version to this one.
+    pub migration: Vec<ComponentMigrationStep>,
+    #[specta(type = String)]
+    #[serde(with = "time::serde::rfc3339")]
+    pub created: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct ComponentTypeRow {
+    component_type: String,
+    schema_version: i32,
+    schema: Value,
+    migration: Value,
+    created: OffsetDateTime,
+}
+
+impl TryFrom<ComponentTypeRow> for ComponentTypeVersion {
+    type Error = ModelError;
+
+    fn try_from(row: ComponentTypeRow) -> Result<Self, Self::Error> {
+        let migration = serde_json::from_value(row.migration).map_err(|err| {
+            ModelError::Unexpected(anyhow::anyhow!(
+                "Failed to decode the migration of {} v{}: {err}",
+                row.component_type,
+                row.schema_version
+            ))
+        })?;
+        Ok(ComponentTypeVersion {
+            component_type: row.component_type,
+            schema_version: row.schema_version,
+            schema: row.schema,
+            migration,
+            created: row.created,
+        })
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct StoredComponentRow {
+    entry_id: Uuid,
+    scope_id: Uuid,
+    key: CompactString,
+    data: Option<Value>,
+    schema_version: Option<i32>,
+}
+
+#[derive(Debug)]
+struct CompiledVersion {
+    schema: ComponentSchema,
+    migration: Vec<ComponentMigrationStep>,
+}
+
+/// The registered schema versions of component types, compiled for validating components.
+///
+/// Component types which are not registered keep accepting any JSON data.
+#[derive(Debug, Default)]
+pub(crate) struct ComponentTypeRegistry {
+    /// The versions of each type, starting from version 1.
+    types: HashMap<String, Vec<CompiledVersion>>,
+}
+
+impl ComponentTypeRegistry {
+    fn from_versions(versions: Vec<ComponentTypeVersion>) -> Result<Self, ModelError> {
+        let mut types: HashMap<String, Vec<CompiledVersion>> = HashMap::new();
+        for version in versions {
+            let schema = ComponentSchema::compile(&version.schema).map_err(|err| {
+                ModelError::Unexpected(anyhow::anyhow!(
+                    "Failed to compile the schema of {} v{}: {err}",
+                    version.component_type,
+                    version.schema_version
+                ))
+            })?;
+            types
+                .entry(version.component_type)
+                .or_default()
+                .push(CompiledVersion {
+                    schema,
+                    migration: version.migration,
+                });
+        }
+        Ok(ComponentTypeRegistry { types })
+    }
+
+    fn from_rows(rows: Vec<ComponentTypeRow>) -> Result<Self, ModelError> {
+        let versions = rows
+            .into_iter()
+            .map(ComponentTypeVersion::try_from)
+            .collect::<Result<_, _>>()?;
+        Self::from_versions(versions)
+    }
+
+    pub(crate) async fn load_by_scope(
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        scope_id: Uuid,
+        component_types: &[String],
+    ) -> Result<Self, ModelError> {
+        if component_types.is_empty() {
+            return Ok(Self::default());
+        }
+        let rows = sqlx::query_file_as!(
+            ComponentTypeRow,
+            "sql/entries/get_component_type_versions_by_scope.sql",
+            scope_id,
+            component_types,
+        )
+        .fetch_all(&mut **db)
+        .await?;
+        Self::from_rows(rows)
+    }
+
+    pub(crate) async fn load_by_entry(
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        entry_id: Uuid,
+        component_types: &[String],
+    ) -> Result<Self, ModelError> {
+        if component_types.is_empty() {
+            return Ok(Self::default());
+        }
+        let rows = sqlx::query_file_as!(
+            ComponentTypeRow,
+            "sql/entries/get_component_type_versions_by_entry.sql",
+            entry_id,
+            component_types,
+        )
+        .fetch_all(&mut **db)
+        .await?;
+        Self::from_rows(rows)
+    }
+
+    pub(crate) fn is_registered(&self, component_type: &str) -> bool {
+        self.types.contains_key(component_type)
+    }
+
+    pub(crate) fn check_non_json_payload(
+        &self,
+        component_type: &str,
+    ) -> Result<(), ValidationFailed> {
+        if self.is_registered(component_type) {
+            return Err(ValidationFailed(
+                "A registered component type requires a JSON payload.",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check the data against its schema version, and upgrade it to the latest version.
+    ///
+    /// Data without a schema version is taken as the latest version. Unregistered types are
+    /// returned as they are.
+    pub(crate) fn upgrade<'a>(
+        &self,
+        component_type: &str,
+        data: &'a Value,
+        schema_version: Option<i32>,
+    ) -> Result<(Cow<'a, Value>, Option<i32>), ValidationFailed> {
+        let Some(versions) = self.types.get(component_type) else {
+            return Ok((Cow::Borrowed(data), schema_version));
+        };
+        let latest = versions.len();
+        let version = schema_version.map_or(latest, |version| version as usize);
+        let Some(compiled) = version.checked_sub(1).and_then(|index| versions.get(index)) else {
+            return Err(ValidationFailed(
+                "The component schema version is not registered.",
+            ));
+        };
+        compiled.schema.validate(data)?;
+        let data = migrate(versions, Cow::Borrowed(data), version)?;
+        Ok((data, Some(latest as i32)))
+    }
+}
+
+/// Apply the migrations after `from_version`, and check the result against the latest schema.
+fn migrate<'a>(
+    versions: &[CompiledVersion],
+    mut data: Cow<'a, Value>,
+    from_version: usize,
+) -> Result<Cow<'a, Value>, ValidationFailed> {
+    if from_version >= versions.len() {
+        return Ok(data);
+    }
+    for compiled in &versions[from_version..] {
+        for step in &compiled.migration {
+            step.apply(data.to_mut())?;
+        }
+    }
+    if let Some(latest) = versions.last() {
+        latest.schema.validate(&data)?;
+    }
+    Ok(data)
+}
+
+impl ComponentTypeVersion {
+    pub async fn list_by_space<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+    ) -> Result<Vec<Self>, ModelError> {
+        sqlx::query_file_as!(
+            ComponentTypeRow,
+            "sql/entries/list_component_types.sql",
+            space_id
+        )
+        .fetch_all(db)
+        .await?
+        .into_iter()
+        .map(TryFrom::try_from)
+        .collect()
+    }
+
+    pub async fn copy_to_space(
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        source_space_id: Uuid,
+        space_id: Uuid,
+    ) -> Result<(), ModelError> {
+        sqlx::query_file!(
+            "sql/entries/clone_component_types.sql",
+            source_space_id,
+            space_id
+        )
+        .execute(&mut **db)
+        .await?;
+        Ok(())
+    }
+
+    /// Register the next schema version of a component type, and migrate the stored
+    /// components of the type in the space to it.
+    ///
+    /// Each migrated component is recorded in the component history, with one Entry Effect per
+    /// Scope. Returns the Entries whose components were migrated.
+    pub async fn register(
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        space_id: Uuid,
+        operator_id: Uuid,
+        component_type: &str,
+        schema_version: i32,
+        schema: Value,
+        migration: Vec<ComponentMigrationStep>,
+    ) -> Result<(ComponentTypeVersion, Vec<EntryMetadata>), ModelError> {
+        validate_component_type(component_type)?;
+        if component_type.starts_with(BUILTIN_NAMESPACE) {
+            return Err(ValidationFailed(
+                "The core/ namespace is reserved for built-in component types.",
+            )
+            .into());
+        }
+        ComponentSchema::compile(&schema)?;
+        validate_migration(&migration)?;
+        if schema_version == 1 && !migration.is_empty() {
+            return Err(
+                ValidationFailed("The first schema version can not have a migration.").into(),
+            );
+        }
+        let component_types = [component_type.to_string()];
+        let mut versions = sqlx::query_file_as!(
+            ComponentTypeRow,
+            "sql/entries/get_component_type_versions.sql",
+            space_id,
+            &component_types[..],
+        )
+        .fetch_all(&mut **db)
+        .await?
+        .into_iter()
+        .map(ComponentTypeVersion::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+        if schema_version as usize != versions.len() + 1 {
+            return Err(ValidationFailed("Schema versions must be registered in order.").into());
+        }
+        let migration_value =
+            serde_json::to_value(&migration).map_err(|err| ModelError::Unexpected(err.into()))?;
+        let registered: ComponentTypeVersion = sqlx::query_file_as!(
+            ComponentTypeRow,
+            "sql/entries/insert_component_type.sql",
+            space_id,
+            component_type,
+            schema_version,
+            schema,
+            migration_value,
+        )
+        .fetch_one(&mut **db)
+        .await?
+        .try_into()?;
+        versions.push(registered.clone());
+        let registry = ComponentTypeRegistry::from_versions(versions)?;
+        let versions = &registry.types[component_type];
+
+        let stored = sqlx::query_file_as!(
+            StoredComponentRow,
+            "sql/entries/list_stored_components_by_type.sql",
+            space_id,
+            component_type,
+        )
+        .fetch_all(&mut **db)
+        .await?;
+        let mut effects: HashMap<Uuid, Uuid> = HashMap::new();
+        let mut migrated = Vec::new();
+        for row in stored {
+            let (Some(data), Some(stored_version)) = (row.data, row.schema_version) else {
+                return Err(ValidationFailed(
+                    "Some components of this type do not have a JSON payload.",
+                )
+                .into());
+            };
+            if stored_version > schema_version {
+                return Err(ValidationFailed(
+                    "Some components of this type have a newer schema version.",
+                )
+                .into());
+            }
+            let upgraded = if stored_version == schema_version {
+                versions[versions.len() - 1]
+                    .schema
+                    .validate(&data)
+                    .map(|_| None)
+            } else {
+                migrate(versions, Cow::Borrowed(&data), stored_version as usize)
+                    .map(|upgraded| Some(upgraded.into_owned()))
+            }
+            .map_err(|_| {
+                ValidationFailed("A stored component does not match the new schema version.")
+            })?;
+            let Some(upgraded) = upgraded else {
+                continue;
+            };
+            sqlx::query_file!(
+                "sql/entries/migrate_json_component.sql",
+                row.entry_id,
+                component_type,
+                upgraded,
+                schema_version,
+            )
+            .execute(&mut **db)
+            .await?;
+            let effect_id = match effects.get(&row.scope_id) {
+                Some(effect_id) => *effect_id,
+                None => {
+                    let effect =
+                        EntryEffect::create(db, space_id, row.scope_id, operator_id).await?;
+                    effects.insert(row.scope_id, effect.id);
+                    effect.id
+                }
+            };
+            EntryComponentHistory::record(
+                db,
+                effect_id,
+                row.entry_id,
+                &row.key,
+                &[EntryComponentHistoryChange::migrate(
+                    component_type,
+                    &upgraded,
+                    schema_version,
+                    stored_version,
+                )],
+            )
+            .await?;
+            let metadata = EntryMetadata::bump_components_version(db, row.scope_id, row.entry_id)
+                .await?
+                .ok_or(ModelError::NotFound("Entry"))?;
+            migrated.push(metadata);
+        }
+        Ok((registered, migrated))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema(schema: Value) -> ComponentSchema {
+        ComponentSchema::compile(&schema).expect("schema should compile")
+    }
+
+    #[test]
+    fn schema_validates_common_keywords() {
+        let schema = schema(json!({
+            "type": "object",
+            "required": ["hp"],
+            "properties": {
+                "hp": { "type": "integer", "minimum": 0, "maximum": 999 },
+                "name": { "type": "string", "maxLength": 4, "pattern": "^[a-z]+$" },
+                "tags": { "type": "array", "items": { "enum": ["a", "b"] }, "maxItems": 2 },
+                "note": { "type": ["string", "null"], "description": "Free text" }
+            },
+            "additionalProperties": false
+        }));
+        assert_eq!(schema.validate(&json!({ "hp": 3 })), Ok(()));
+        assert_eq!(
+            schema.validate(&json!({ "hp": 3.0, "name": "ogre", "tags": ["a"], "note": null })),
+            Ok(())
+        );
+        assert_eq!(
+            schema.validate(&json!({})),
+            Err(ValidationFailed(REQUIRED_ERROR))
+        );
+        assert_eq!(
+            schema.validate(&json!({ "hp": 1.5 })),
+            Err(ValidationFailed(TYPE_ERROR))
+        );
+        assert_eq!(
+            schema.validate(&json!({ "hp": -1 })),
+            Err(ValidationFailed(RANGE_ERROR))
+        );
+        assert_eq!(
+            schema.validate(&json!({ "hp": 1, "name": "giant" })),
+            Err(ValidationFailed(LENGTH_ERROR))
+        );
+        assert_eq!(
+            schema.validate(&json!({ "hp": 1, "name": "Orc" })),
+            Err(ValidationFailed(PATTERN_ERROR))
+        );
+        assert_eq!(
+            schema.validate(&json!({ "hp": 1, "tags": ["c"] })),
+            Err(ValidationFailed(VALUE_ERROR))
+        );
+        assert_eq!(
+            schema.validate(&json!({ "hp": 1, "mp": 1 })),
+            Err(ValidationFailed(NOT_ALLOWED_ERROR))
+        );
+    }
+
+    #[test]
+    fn unsupported_schemas_are_rejected() {
+        assert!(ComponentSchema::compile(&json!({ "oneOf": [] })).is_err());
+        assert!(ComponentSchema::compile(&json!({ "type": "date" })).is_err());
+        assert!(ComponentSchema::compile(&json!({ "pattern": "(" })).is_err());
+        assert!(ComponentSchema::compile(&json!("object")).is_err());
+        let mut nested = json!(true);
+        for _ in 0..=MAX_SCHEMA_DEPTH {
+            nested = json!({ "items": nested });
+        }
+        assert!(ComponentSchema::compile(&nested).is_err());
+    }
+
+    #[test]
+    fn migration_steps_rewrite_data() {
+        let steps: Vec<ComponentMigrationStep> = serde_json::from_value(json!([
+            { "op": "RENAME", "from": "/hp", "to": "/stats/hp" },
+            { "op": "DEFAULT", "path": "/stats/mp", "value": 0 },
+            { "op": "DEFAULT", "path": "/stats/hp", "value": 0 },
+            { "op": "COPY", "from": "/name", "to": "/a~1b" },
+            { "op": "SET", "path": "/tags/-", "value": "migrated" },
+            { "op": "REMOVE", "path": "/legacy" },
+            { "op": "REMOVE", "path": "/missing" }
+        ]))
+        .unwrap();
+        assert_eq!(validate_migration(&steps), Ok(()));
+        let mut data = json!({ "hp": 7, "name": "ogre", "tags": [], "legacy": true });
+        for step in &steps {
+            step.apply(&mut data).unwrap();
+        }
+        assert_eq!(
+            data,
+            json!({
+                "stats": { "hp": 7, "mp": 0 },
+                "name": "ogre",
+                "a/b": "ogre",
+                "tags": ["migrated"]
+            })
+        );
+        let bad_path = ComponentMigrationStep::Set {
+            path: "/name/first".to_string(),
+            value: json!(1),
+        };
+        assert_eq!(bad_path.apply(&mut data), Err(ValidationFailed(PATH_ERROR)));
+        assert!(
+            validate_migration(&[ComponentMigrationStep::Remove {
+                path: String::new()
+            }])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn registry_upgrades_old_versions() {
+        let now = OffsetDateTime::now_utc();
+        let version = |schema_version, schema, migration| ComponentTypeVersion {
+            component_type: "example/counter".to_string(),
+            schema_version,
+            schema,
+            migration,
+            created: now,
+        };
+        let registry = ComponentTypeRegistry::from_versions(vec![
+            version(
+                1,
+                json!({ "type": "object", "required": ["count"] }),
+                Vec::new(),
+            ),
+            version(
+                2,
+                json!({ "type": "object", "required": ["value"] }),
+                vec![ComponentMigrationStep::Rename {
+                    from: "/count".to_string(),
+                    to: "/value".to_string(),
+                }],
+            ),
+        ])
+        .unwrap();
+
+        let old = json!({ "count": 1 });
+        let (data, version) = registry.upgrade("example/counter", &old, Some(1)).unwrap();
+        assert_eq!(data.into_owned(), json!({ "value": 1 }));
+        assert_eq!(version, Some(2));
+
+        let latest = json!({ "value": 2 });
+        let (data, version) = registry.upgrade("example/counter", &latest, None).unwrap();
+        assert!(matches!(data, Cow::Borrowed(_)));
+        assert_eq!(version, Some(2));
+
+        assert!(registry.upgrade("example/counter", &old, None).is_err());
+        assert!(
+            registry
+                .upgrade("example/counter", &latest, Some(3))
+                .is_err()
+        );
+        assert!(
+            registry
+                .upgrade("example/counter", &latest, Some(0))
+                .is_err()
+        );
+        assert!(registry.check_non_json_payload("example/counter").is_err());
+
+        let (data, version) = registry.upgrade("example/other", &old, Some(5)).unwrap();
+        assert!(matches!(data, Cow::Borrowed(_)));
+        assert_eq!(version, Some(5));
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn db_test_register_component_type_migrates_stored_components(pool: sqlx::PgPool) {
+        use crate::entries::models::{Entry, EntryComponentMutation, EntryComponentPayloadInput};
+        use crate::spaces::Space;
+        use crate::users::User;
+
+        let raw = Uuid::new_v4().simple().to_string();
+        let user = User::register(
+            &pool,
+            &format!("component_type_{raw}@example.com"),
+            &format!("ctype_{}", &raw[..8]),
+            "Component Type Tester",
+            "ComponentTypePass123!",
+        )
+        .await
+        .expect("create user failed");
+        let space = Space::create(
+            &pool,
+            format!("component_type_{}", &raw[..8]),
+            &user.id,
+            "Component type test".to_string(),
+            None,
+            Some("d20"),
+        )
+        .await
+        .expect("create space failed");
+        let counter = "example/counter";
+
+        let mut transaction = pool.begin().await.unwrap();
+        let entry = Entry::create(
+            &mut transaction,
+            space.scope_id,
+            "goblin".to_string(),
+            Vec::new(),
+            "Goblin".to_string(),
+            None,
+            BTreeMap::from([(
+                counter.to_string(),
+                EntryComponentPayloadInput::json(json!({ "count": 3 })),
+            )]),
+            Vec::new(),
+            None,
+        )
+        .await
+        .expect("create entry failed");
+        let builtin = ComponentTypeVersion::register(
+            &mut transaction,
+            space.id,
+            user.id,
+            "core/counter",
+            1,
+            json!(true),
+            Vec::new(),
+        )
+        .await;
+        assert!(matches!(builtin, Err(ModelError::Validation(_))));
+        let (v1, migrated) = ComponentTypeVersion::register(
+            &mut transaction,
+            space.id,
+            user.id,
+            counter,
+            1,
+            json!({ "type": "object", "required": ["count"] }),
+            Vec::new(),
+        )
+        .await
+        .expect("register v1 failed");
+        assert_eq!(v1.schema_version, 1);
+        assert!(migrated.is_empty());
+        let skipped = ComponentTypeVersion::register(
+            &mut transaction,
+            space.id,
+            user.id,
+            counter,
+            3,
+            json!(true),
+            Vec::new(),
+        )
+        .await;
+        assert!(matches!(skipped, Err(ModelError::Validation(_))));
+        let (_, migrated) = ComponentTypeVersion::register(
+            &mut transaction,
+            space.id,
+            user.id,
+            counter,
+            2,
+            json!({
+                "type": "object",
+                "required": ["value"],
+                "properties": { "value": { "type": "integer", "minimum": 0 } },
+                "additionalProperties": false
+            }),
+            vec![ComponentMigrationStep::Rename {
+                from: "/count".to_string(),
+                to: "/value".to_string(),
+            }],
+        )
+        .await
+        .expect("register v2 failed");
+        assert_eq!(migrated.len(), 1);
+        assert_ne!(
+            migrated[0].components_version,
+            entry.metadata.components_version
+        );
+
+        let migrated_entry =
+            Entry::get_by_id_in_transaction(&mut transaction, space.scope_id, entry.id)
+                .await
+                .unwrap()
+                .unwrap();
+        assert_eq!(
+            migrated_entry.components[counter].json_data(),
+            json!({ "value": 3 })
+        );
+        assert_eq!(migrated_entry.components[counter].schema_version(), 2);
+        transaction.commit().await.unwrap();
+
+        let mut transaction = pool.begin().await.unwrap();
+        let invalid = Entry::apply_component_mutations(
+            &mut transaction,
+            entry.id,
+            &[EntryComponentMutation::Set {
+                component_type: counter.to_string(),
+                expected_version: Some(migrated_entry.components[counter].version()),
+                payload: EntryComponentPayloadInput::json(json!({ "value": -1 })),
+            }],
+        )
+        .await;
+        assert!(matches!(invalid, Err(ModelError::Validation(_))));
+        drop(transaction);
+        let mut transaction = pool.begin().await.unwrap();
+        let upgraded = Entry::apply_component_mutations(
+            &mut transaction,
+            entry.id,
+            &[EntryComponentMutation::Set {
+                component_type: counter.to_string(),
+                expected_version: Some(migrated_entry.components[counter].version()),
+                payload: EntryComponentPayloadInput::json_with_schema(
+                    json!({ "count": 5 }),
+                    Some(1),
+                ),
+            }],
+        )
+        .await
+        .expect("set an old schema version failed");
+        assert_eq!(
+            upgraded.history_changes[0].payload,
+            Some(json!({ "payloadType": "JSON", "schemaVersion": 2, "data": { "value": 5 } }))
+        );
+        transaction.commit().await.unwrap();
+
+        let history = EntryComponentHistory::list_by_entry(&pool, space.scope_id, entry.id)
+            .await
+            .unwrap();
+        assert!(history.iter().any(|change| change.payload
+            == Some(json!({
+                "payloadType": "JSON",
+                "schemaVersion": 2,
+                "migratedFrom": 1,
+                "data": { "value": 3 }
+            }))));
+        let versions = ComponentTypeVersion::list_by_space(&pool, space.id)
+            .await
+            .unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[1].migration.len(), 1);
+    }
+}
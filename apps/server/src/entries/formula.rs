@@ -0,0 +1,866 @@
+//! Formula components, computed from other components when an Entry is read.
+//!
+//! A formula is arithmetic over numbers and references to JSON values, such as
+//! `floor(({dnd/abilities:str} - 10) / 2)`. A reference is written in braces:
+//!
+//! - `{dnd/stats}`: the data of a component of the same Entry
+//! - `{dnd/stats:hp.max}`: a dot-separated path into the data, numeric segments index arrays
+//! - `{strength@dnd/stats:value}`: a component of another Entry of the Scope, by key or alias
+//!
+//! A reference to another formula component takes its result. Functions are `floor`, `ceil`,
+//! `round`, `trunc`, `abs`, `min` and `max`.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use compact_str::CompactString;
+use serde_json::Value;
+use uuid::Uuid;
+
+use super::models::{EntryComponent, stored_components, validate_component_type};
+use super::query::{is_index, path_segments};
+use crate::characters::normalize_ident;
+use crate::error::{ModelError, ValidationFailed};
+
+const MAX_FORMULA_LENGTH: usize = 1024;
+const MAX_REFERENCES: usize = 32;
+const MAX_NESTING: usize = 32;
+/// Bounds the Entries loaded to evaluate the formulas of one Entry.
+const MAX_REFERENCED_ENTRIES: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct FormulaReference {
+    /// The lowercased key or alias of an Entry, `None` for the Entry of the formula
+    pub entry: Option<String>,
+    pub component_type: String,
+    pub path: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Function {
+    Floor,
+    Ceil,
+    Round,
+    Trunc,
+    Abs,
+    Min,
+    Max,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Function> {
+        Some(match name {
+            "floor" => Function::Floor,
+            "ceil" => Function::Ceil,
+            "round" => Function::Round,
+            "trunc" => Function::Trunc,
+            "abs" => Function::Abs,
+            "min" => Function::Min,
+            "max" => Function::Max,
+            _ => return None,
+        })
+    }
+
+    fn is_variadic(self) -> bool {
+        matches!(self, Function::Min | Function::Max)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Number(f64),
+    /// An index into the references of the formula
+    Reference(usize),
+    Neg(Box<Node>),
+    Binary(BinaryOp, Box<Node>, Box<Node>),
+    Call(Function, Vec<Node>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Formula {
+    root: Node,
+    references: Vec<FormulaReference>,
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    position: usize,
+    depth: usize,
+    references: Vec<FormulaReference>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&mut self) -> Option<char> {
+        let rest = &self.source[self.position..];
+        let trimmed = rest.trim_start();
+        self.position += rest.len() - trimmed.len();
+        trimmed.chars().next()
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.position += expected.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ValidationFailed> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(ValidationFailed("The formula has unbalanced parentheses."))
+        }
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let rest = &self.source[self.position..];
+        let length = rest.find(|c| !predicate(c)).unwrap_or(rest.len());
+        self.position += length;
+        &rest[..length]
+    }
+
+    fn descend(&mut self) -> Result<(), ValidationFailed> {
+        self.depth += 1;
+        if self.depth > MAX_NESTING {
+            return Err(ValidationFailed("The formula is nested too deeply."));
+        }
+        Ok(())
+    }
+
+    fn expression(&mut self) -> Result<Node, ValidationFailed> {
+        self.descend()?;
+        let mut node = self.term()?;
+        loop {
+            let op = match self.peek() {
+                Some('+') => BinaryOp::Add,
+                Some('-') => BinaryOp::Sub,
+                _ => break,
+            };
+            self.position += 1;
+            node = Node::Binary(op, Box::new(node), Box::new(self.term()?));
+        }
+        self.depth -= 1;
+        Ok(node)
+    }
+
+    fn term(&mut self) -> Result<Node, ValidationFailed> {
+        let mut node = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some('*') => BinaryOp::Mul,
+                Some('/') => BinaryOp::Div,
+                Some('%') => BinaryOp::Rem,
+                _ => break,
+            };
+            self.position += 1;
+            node = Node::Binary(op, Box::new(node), Box::new(self.unary()?));
+        }
+        Ok(node)
+    }
+
+    fn unary(&mut self) -> Result<Node, ValidationFailed> {
+        if self.eat('-') {
+            self.descend()?;
+            let node = Node::Neg(Box::new(self.unary()?));
+            self.depth -= 1;
+            return Ok(node);
+        }
+        if self.eat('+') {
+            self.descend()?;
+            let node = self.unary()?;
+            self.depth -= 1;
+            return Ok(node);
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Node, ValidationFailed> {
+        match self.peek() {
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let number = self
+                    .take_while(|c| c.is_ascii_digit() || c == '.')
+                    .parse::<f64>()
+                    .map_err(|_| ValidationFailed("The formula has an invalid number."))?;
+                Ok(Node::Number(number))
+            }
+            Some('{') => {
+                self.position += 1;
+                let reference = self.take_while(|c| c != '}');
+                let reference = parse_reference(reference)?;
+                if !self.eat('}') {
+                    return Err(ValidationFailed("The formula has an unclosed reference."));
+                }
+                if self.references.len() >= MAX_REFERENCES {
+                    return Err(ValidationFailed(
+                        "A formula shall not have more than 32 references.",
+                    ));
+                }
+                self.references.push(reference);
+                Ok(Node::Reference(self.references.len() - 1))
+            }
+            Some('(') => {
+                self.position += 1;
+                let node = self.expression()?;
+                self.expect(')')?;
+                Ok(node)
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                let name = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+                let function = Function::from_name(name)
+                    .ok_or(ValidationFailed("The formula has an unknown function."))?;
+                self.expect('(')?;
+                let mut arguments = vec![self.expression()?];
+                while self.eat(',') {
+                    arguments.push(self.expression()?);
+                }
+                self.expect(')')?;
+                if !function.is_variadic() && arguments.len() != 1 {
+                    return Err(ValidationFailed(
+                        "A function of the formula has the wrong number of arguments.",
+                    ));
+                }
+                Ok(Node::Call(function, arguments))
+            }
+            Some(_) => Err(ValidationFailed("The formula has an unexpected character.")),
+            None => Err(ValidationFailed("The formula ends unexpectedly.")),
+        }
+    }
+}
+
+fn parse_reference(reference: &str) -> Result<FormulaReference, ValidationFailed> {
+    let (entry, rest) = match reference.split_once('@') {
+        Some((key, rest)) => (Some(normalize_ident(key)?.to_lowercase()), rest),
+        None => (None, reference),
+    };
+    let (component_type, path) = rest.split_once(':').unwrap_or((rest, ""));
+    let component_type = component_type.trim();
+    validate_component_type(component_type)?;
+    let path = path_segments(path.trim())?
+        .into_iter()
+        .map(ToString::to_string)
+        .collect();
+    Ok(FormulaReference {
+        entry,
+        component_type: component_type.to_string(),
+        path,
+    })
+}
+
+type Evaluated = Result<f64, &'static str>;
+
+impl Formula {
+    pub(crate) fn parse(source: &str) -> Result<Formula, ValidationFailed> {
+        if source.trim().is_empty() {
+            return Err(ValidationFailed("A formula can not be empty."));
+        }
+        if source.len() > MAX_FORMULA_LENGTH {
+            return Err(ValidationFailed(
+                "A formula shall not be more than 1024 bytes.",
+            ));
+        }
+        let mut parser = Parser {
+            source,
+            position: 0,
+            depth: 0,
+            references: Vec::new(),
+        };
+        let root = parser.expression()?;
+        if parser.peek().is_some() {
+            return Err(ValidationFailed("The formula has an unexpected character."));
+        }
+        Ok(Formula {
+            root,
+            references: parser.references,
+        })
+    }
+
+    pub(crate) fn references(&self) -> &[FormulaReference] {
+        &self.references
+    }
+
+    fn evaluate(&self, resolve: &mut dyn FnMut(&FormulaReference) -> Evaluated) -> Evaluated {
+        self.evaluate_node(&self.root, resolve)
+    }
+
+    fn evaluate_node(
+        &self,
+        node: &Node,
+        resolve: &mut dyn FnMut(&FormulaReference) -> Evaluated,
+    ) -> Evaluated {
+        let value = match node {
+            Node::Number(number) => *number,
+            Node::Reference(index) => resolve(&self.references[*index])?,
+            Node::Neg(node) => -self.evaluate_node(node, resolve)?,
+            Node::Binary(op, left, right) => {
+                let left = self.evaluate_node(left, resolve)?;
+                let right = self.evaluate_node(right, resolve)?;
+                match op {
+                    BinaryOp::Add => left + right,
+                    BinaryOp::Sub => left - right,
+                    BinaryOp::Mul => left * right,
+                    BinaryOp::Div | BinaryOp::Rem if right == 0.0 => {
+                        return Err("The formula divides by zero.");
+                    }
+                    BinaryOp::Div => left / right,
+                    BinaryOp::Rem => left % right,
+                }
+            }
+            Node::Call(function, arguments) => {
+                let mut values = Vec::with_capacity(arguments.len());
+                for argument in arguments {
+                    values.push(self.evaluate_node(argument, resolve)?);
+                }
+                match function {
+                    Function::Floor => values[0].floor(),
+                    Function::Ceil => values[0].ceil(),
+                    Function::Round => values[0].round(),
+                    Function::Trunc => values[0].trunc(),
+                    Function::Abs => values[0].abs(),
+                    Function::Min => values.into_iter().fold(f64::INFINITY, f64::min),
+                    Function::Max => values.into_iter().fold(f64::NEG_INFINITY, f64::max),
+                }
+            }
+        };
+        if !value.is_finite() {
+            return Err("The result of the formula is too large.");
+        }
+        Ok(value)
+    }
+}
+
+fn value_at<'a>(data: &'a Value, path: &[String]) -> Option<&'a Value> {
+    path.iter().try_fold(data, |value, segment| match value {
+        Value::Array(items) if is_index(segment) => items.get(segment.parse::<usize>().ok()?),
+        Value::Object(fields) => fields.get(segment),
+        _ => None,
+    })
+}
+
+fn parse_stored(formula: &str) -> Option<Formula> {
+    // Stored formulas have been validated when they were saved.
+    Formula::parse(formula).ok()
+}
+
+/// The Entries the formulas of an Entry can reference, by their lowercased keys and aliases.
+struct FormulaContext<'a> {
+    identifiers: HashMap<String, Uuid>,
+    entries: HashMap<Uuid, Cow<'a, BTreeMap<CompactString, EntryComponent>>>,
+}
+
+impl<'a> FormulaContext<'a> {
+    fn new(entry_id: Uuid, components: &'a BTreeMap<CompactString, EntryComponent>) -> Self {
+        FormulaContext {
+            identifiers: HashMap::new(),
+            entries: HashMap::from([(entry_id, Cow::Borrowed(components))]),
+        }
+    }
+
+    /// Load the other Entries of the Scope referenced by the formulas, and by theirs in turn.
+    async fn load(
+        db: &mut sqlx::PgConnection,
+        scope_id: Uuid,
+        entry_id: Uuid,
+        components: &'a BTreeMap<CompactString, EntryComponent>,
+    ) -> Result<Self, sqlx::Error> {
+        let mut context = FormulaContext::new(entry_id, components);
+        let mut looked_up = HashSet::new();
+        let mut pending = external_keys(components);
+        while !pending.is_empty() && context.entries.len() < MAX_REFERENCED_ENTRIES {
+            let keys = pending
+                .drain()
+                .filter(|key| looked_up.insert(key.clone()))
+                .collect::<Vec<_>>();
+            if keys.is_empty() {
+                break;
+            }
+            let rows = sqlx::query_file!("sql/entries/resolve_identifiers.sql", scope_id, &keys)
+                .fetch_all(&mut *db)
+                .await?;
+            for row in rows {
+                context.identifiers.insert(row.identifier, row.entry_id);
+                if context.entries.contains_key(&row.entry_id)
+                    || context.entries.len() >= MAX_REFERENCED_ENTRIES
+                {
+                    continue;
+                }
+                let components = stored_components(&mut *db, row.entry_id).await?;
+                pending.extend(external_keys(&components));
+                context.entries.insert(row.entry_id, Cow::Owned(components));
+            }
+        }
+        Ok(context)
+    }
+
+    fn resolve(
+        &self,
+        entry_id: Uuid,
+        reference: &FormulaReference,
+        memo: &mut HashMap<(Uuid, CompactString), Evaluated>,
+        visiting: &mut HashSet<(Uuid, CompactString)>,
+    ) -> Evaluated {
+        let target_id = match &reference.entry {
+            None => entry_id,
+            Some(key) => *self
+                .identifiers
+                .get(key)
+                .ok_or("A referenced Entry does not exist.")?,
+        };
+        let component = self
+            .entries
+            .get(&target_id)
+            .and_then(|components| components.get(reference.component_type.as_str()))
+            .ok_or("A referenced component does not exist.")?;
+        match component {
+            EntryComponent::Json { data, .. } => value_at(data, &reference.path)
+                .ok_or("A referenced value does not exist.")?
+                .as_f64()
+                .ok_or("A referenced value is not a number."),
+            EntryComponent::Formula { .. } if reference.path.is_empty() => {
+                self.evaluate_component(target_id, &reference.component_type, memo, visiting)
+            }
+            _ => Err("A referenced value is not a number."),
+        }
+    }
+
+    fn evaluate_component(
+        &self,
+        entry_id: Uuid,
+        component_type: &str,
+        memo: &mut HashMap<(Uuid, CompactString), Evaluated>,
+        visiting: &mut HashSet<(Uuid, CompactString)>,
+    ) -> Evaluated {
+        let node = (entry_id, CompactString::new(component_type));
+        if let Some(evaluated) = memo.get(&node) {
+            return *evaluated;
+        }
+        let Some(EntryComponent::Formula { formula, .. }) = self
+            .entries
+            .get(&entry_id)
+            .and_then(|components| components.get(component_type))
+        else {
+            return Err("A referenced component does not exist.");
+        };
+        // Cycles are rejected when formulas are saved, but renaming an Entry may still form one.
+        if !visiting.insert(node.clone()) {
+            return Err("The formula depends on itself.");
+        }
+        let evaluated = match parse_stored(formula) {
+            Some(formula) => {
+                formula.evaluate(&mut |reference| self.resolve(entry_id, reference, memo, visiting))
+            }
+            None => Err("The formula is invalid."),
+        };
+        visiting.remove(&node);
+        memo.insert(node, evaluated);
+        evaluated
+    }
+
+    fn evaluate_entry(&self, entry_id: Uuid) -> Vec<(CompactString, Evaluated)> {
+        let Some(components) = self.entries.get(&entry_id) else {
+            return Vec::new();
+        };
+        let mut memo = HashMap::new();
+        let mut visiting = HashSet::new();
+        components
+            .iter()
+            .filter(|(_, component)| matches!(component, EntryComponent::Formula { .. }))
+            .map(|(component_type, _)| {
+                let evaluated =
+                    self.evaluate_component(entry_id, component_type, &mut memo, &mut visiting);
+                (component_type.clone(), evaluated)
+            })
+            .collect()
+    }
+}
+
+/// The keys of the other Entries the formula components refer to.
+fn external_keys(components: &BTreeMap<CompactString, EntryComponent>) -> HashSet<String> {
+    components
+        .values()
+        .filter_map(|component| match component {
+            EntryComponent::Formula { formula, .. } => parse_stored(formula),
+            _ => None,
+        })
+        .flat_map(|formula| formula.references)
+        .filter_map(|reference| reference.entry)
+        .collect()
+}
+
+fn apply(
+    components: &mut BTreeMap<CompactString, EntryComponent>,
+    results: Vec<(CompactString, Evaluated)>,
+) {
+    for (component_type, evaluated) in results {
+        if let Some(EntryComponent::Formula { value, error, .. }) =
+            components.get_mut(&component_type)
+        {
+            *value = evaluated.ok();
+            *error = evaluated.err().map(ToString::to_string);
+        }
+    }
+}
+
+fn has_formulas(components: &BTreeMap<CompactString, EntryComponent>) -> bool {
+    components
+        .values()
+        .any(|component| matches!(component, EntryComponent::Formula { .. }))
+}
+
+/// Evaluate the formulas which only refer to their own Entry, which the components version of
+/// the Entry covers. The others fail as if the Entries they refer to do not exist.
+pub(crate) fn evaluate_local_formulas(
+    entry_id: Uuid,
+    components: &mut BTreeMap<CompactString, EntryComponent>,
+) {
+    if !has_formulas(components) {
+        return;
+    }
+    let results = FormulaContext::new(entry_id, components).evaluate_entry(entry_id);
+    apply(components, results);
+}
+
+pub(crate) fn refers_to_other_entries(
+    components: &BTreeMap<CompactString, EntryComponent>,
+) -> bool {
+    has_formulas(components) && !external_keys(components).is_empty()
+}
+
+/// Evaluate the formulas of an Entry, loading the other Entries of the Scope they refer to.
+pub(crate) async fn evaluate_formulas(
+    db: &mut sqlx::PgConnection,
+    scope_id: Uuid,
+    entry_id: Uuid,
+    components: &mut BTreeMap<CompactString, EntryComponent>,
+) -> Result<(), sqlx::Error> {
+    if !has_formulas(components) {
+        return Ok(());
+    }
+    let results = FormulaContext::load(db, scope_id, entry_id, components)
+        .await?
+        .evaluate_entry(entry_id);
+    apply(components, results);
+    Ok(())
+}
+
+/// Reject formula components which depend on themselves, directly or through other formulas in
+/// the Scope of the Entry.
+pub(crate) async fn check_formula_cycles(
+    db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    entry_id: Uuid,
+) -> Result<(), ModelError> {
+    let rows = sqlx::query_file!("sql/entries/list_formulas_by_scope.sql", entry_id)
+        .fetch_all(&mut **db)
+        .await?;
+    let mut identifiers = HashMap::new();
+    for row in &rows {
+        for identifier in &row.identifiers {
+            identifiers.insert(identifier.as_str(), row.entry_id);
+        }
+    }
+    let nodes = rows
+        .iter()
+        .map(|row| (row.entry_id, row.component_type.as_str()))
+        .collect::<HashSet<_>>();
+    let mut edges = HashMap::new();
+    for row in &rows {
+        let Some(formula) = parse_stored(&row.formula) else {
+            continue;
+        };
+        let dependencies = formula
+            .references()
+            .iter()
+            .filter_map(|reference| {
+                let target_id = match &reference.entry {
+                    None => row.entry_id,
+                    Some(key) => *identifiers.get(key.as_str())?,
+                };
+                let target = (target_id, reference.component_type.as_str());
+                nodes.contains(&target).then_some(target)
+            })
+            .collect::<Vec<_>>();
+        edges.insert((row.entry_id, row.component_type.as_str()), dependencies);
+    }
+    if has_cycle(&edges) {
+        return Err(ValidationFailed("A formula can not depend on itself.").into());
+    }
+    Ok(())
+}
+
+fn has_cycle<N: Copy + Eq + std::hash::Hash>(edges: &HashMap<N, Vec<N>>) -> bool {
+    let mut done = HashSet::new();
+    for &start in edges.keys() {
+        if done.contains(&start) {
+            continue;
+        }
+        // Depth-first, with the nodes on the current path and the next edge of each.
+        let mut on_path = HashSet::from([start]);
+        let mut stack = vec![(start, 0)];
+        while let Some((node, next)) = stack.last_mut() {
+            let node = *node;
+            let Some(&target) = edges.get(&node).and_then(|targets| targets.get(*next)) else {
+                on_path.remove(&node);
+                done.insert(node);
+                stack.pop();
+                continue;
+            };
+            *next += 1;
+            if on_path.contains(&target) {
+                return true;
+            }
+            if !done.contains(&target) {
+                on_path.insert(target);
+                stack.push((target, 0));
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use time::OffsetDateTime;
+
+    fn json_component(data: Value) -> EntryComponent {
+        EntryComponent::Json {
+            data,
+            schema_version: 1,
+            version: Uuid::nil(),
+            modified: OffsetDateTime::UNIX_EPOCH,
+        }
+    }
+
+    fn formula_component(formula: &str) -> EntryComponent {
+        EntryComponent::Formula {
+            formula: formula.to_string(),
+            value: None,
+            error: None,
+            version: Uuid::nil(),
+            modified: OffsetDateTime::UNIX_EPOCH,
+        }
+    }
+
+    fn result(
+        components: &BTreeMap<CompactString, EntryComponent>,
+        component_type: &str,
+    ) -> Evaluated {
+        match components.get(component_type) {
+            Some(EntryComponent::Formula {
+                value: Some(value), ..
+            }) => Ok(*value),
+            Some(EntryComponent::Formula {
+                error: Some(error), ..
+            }) => Err(if error.contains("itself") {
+                "cycle"
+            } else {
+                "error"
+            }),
+            _ => panic!("expected an evaluated formula"),
+        }
+    }
+
+    #[test]
+    fn formulas_parse_references() {
+        let formula = Formula::parse("floor(({dnd/abilities:str} - 10) / 2)").unwrap();
+        assert_eq!(
+            formula.references(),
+            [FormulaReference {
+                entry: None,
+                component_type: "dnd/abilities".to_string(),
+                path: vec!["str".to_string()],
+            }]
+        );
+        let formula = Formula::parse("{ Strength@dnd/stats : items.0 } * 15").unwrap();
+        assert_eq!(formula.references()[0].entry.as_deref(), Some("strength"));
+        assert_eq!(formula.references()[0].path, ["items", "0"]);
+
+        for invalid in [
+            "",
+            "1 +",
+            "(1",
+            "1)",
+            "{dnd/stats",
+            "{Stats}",
+            "{dnd/stats:a..b}",
+            "sqrt(4)",
+            "abs(1, 2)",
+            "1..2",
+            "1 $ 2",
+        ] {
+            assert!(Formula::parse(invalid).is_err(), "{invalid}");
+        }
+        assert!(Formula::parse(&"(".repeat(40)).is_err());
+        assert!(Formula::parse(&"-".repeat(40)).is_err());
+    }
+
+    #[test]
+    fn formulas_are_evaluated_over_the_entry() {
+        let entry_id = Uuid::now_v7();
+        let mut components = BTreeMap::from([
+            (
+                CompactString::new("dnd/abilities"),
+                json_component(json!({ "str": 15, "name": "Strong" })),
+            ),
+            (
+                CompactString::new("dnd/modifier"),
+                formula_component("floor(({dnd/abilities:str} - 10) / 2)"),
+            ),
+            (
+                CompactString::new("dnd/carry"),
+                formula_component("{dnd/abilities:str} * 15 + max({dnd/modifier}, 0, -1)"),
+            ),
+            (
+                CompactString::new("dnd/broken"),
+                formula_component("{dnd/abilities:name} / 0"),
+            ),
+            (
+                CompactString::new("dnd/elsewhere"),
+                formula_component("{other@dnd/abilities:str}"),
+            ),
+            (
+                CompactString::new("dnd/loop"),
+                formula_component("{dnd/loop} + 1"),
+            ),
+        ]);
+        evaluate_local_formulas(entry_id, &mut components);
+        assert_eq!(result(&components, "dnd/modifier"), Ok(2.0));
+        assert_eq!(result(&components, "dnd/carry"), Ok(227.0));
+        assert_eq!(result(&components, "dnd/broken"), Err("error"));
+        assert_eq!(result(&components, "dnd/elsewhere"), Err("error"));
+        assert_eq!(result(&components, "dnd/loop"), Err("cycle"));
+        assert!(refers_to_other_entries(&components));
+    }
+
+    #[test]
+    fn cycles_are_found() {
+        let edges = HashMap::from([(1, vec![2, 3]), (2, vec![3]), (3, vec![])]);
+        assert!(!has_cycle(&edges));
+        let edges = HashMap::from([(1, vec![2]), (2, vec![3]), (3, vec![1]), (4, vec![1])]);
+        assert!(has_cycle(&edges));
+        assert!(has_cycle(&HashMap::from([(1, vec![1])])));
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn db_test_formulas_over_entries(pool: sqlx::PgPool) {
+        use crate::entries::models::{Entry, EntryComponentMutation, EntryComponentPayloadInput};
+        use crate::spaces::Space;
+        use crate::users::User;
+
+        let raw = Uuid::new_v4().simple().to_string();
+        let user = User::register(
+            &pool,
+            &format!("entry_formula_{raw}@example.com"),
+            &format!("eformula_{}", &raw[..8]),
+            "Entry Formula Tester",
+            "EntryFormulaPass123!",
+        )
+        .await
+        .expect("create user failed");
+        let space = Space::create(
+            &pool,
+            format!("entry_formula_{}", &raw[..8]),
+            &user.id,
+            "Entry formula test".to_string(),
+            None,
+            Some("d20"),
+        )
+        .await
+        .expect("create space failed");
+        let formula = |formula: &str| EntryComponentPayloadInput::Formula {
+            formula: formula.to_string(),
+        };
+
+        let mut transaction = pool.begin().await.unwrap();
+        let strength = Entry::create(
+            &mut transaction,
+            space.scope_id,
+            "Strength".to_string(),
+            vec!["str".to_string()],
+            "Strength".to_string(),
+            None,
+            BTreeMap::from([
+                (
+                    "dnd/ability".to_string(),
+                    EntryComponentPayloadInput::json(json!({ "score": 16 })),
+                ),
+                (
+                    "dnd/modifier".to_string(),
+                    formula("floor(({dnd/ability:score} - 10) / 2)"),
+                ),
+            ]),
+            Vec::new(),
+            None,
+        )
+        .await
+        .expect("create entry failed");
+        let carry = Entry::create(
+            &mut transaction,
+            space.scope_id,
+            "carry".to_string(),
+            Vec::new(),
+            "Carrying".to_string(),
+            None,
+            BTreeMap::from([(
+                "dnd/capacity".to_string(),
+                formula("{STR@dnd/ability:score} * 15 + {strength@dnd/modifier}"),
+            )]),
+            Vec::new(),
+            None,
+        )
+        .await
+        .expect("create entry failed");
+        transaction.commit().await.unwrap();
+
+        let value = |entry: &Entry, component_type: &str| match entry.components.get(component_type)
+        {
+            Some(EntryComponent::Formula { value, .. }) => *value,
+            _ => panic!("expected a formula component"),
+        };
+        assert_eq!(value(&strength, "dnd/modifier"), Some(3.0));
+        assert_eq!(value(&carry, "dnd/capacity"), Some(243.0));
+
+        let mut transaction = pool.begin().await.unwrap();
+        Entry::apply_component_mutations(
+            &mut transaction,
+            strength.id,
+            &[EntryComponentMutation::Set {
+                component_type: "dnd/ability".to_string(),
+                expected_version: Some(strength.components["dnd/ability"].version()),
+                payload: EntryComponentPayloadInput::json(json!({ "score": 10 })),
+            }],
+        )
+        .await
+        .expect("edit components failed");
+        transaction.commit().await.unwrap();
+        let carry = Entry::get_by_id(&pool, space.scope_id, carry.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(value(&carry, "dnd/capacity"), Some(150.0));
+
+        let mut transaction = pool.begin().await.unwrap();
+        let cycle = Entry::apply_component_mutations(
+            &mut transaction,
+            strength.id,
+            &[EntryComponentMutation::Set {
+                component_type: "dnd/modifier".to_string(),
+                expected_version: Some(strength.components["dnd/modifier"].version()),
+                payload: formula("{carry@dnd/capacity} / 100"),
+            }],
+        )
+        .await;
+        assert!(matches!(cycle, Err(ModelError::Validation(_))));
+    }
+}
@@ -1,13 +1,23 @@
 use super::api::{
-    CheckEntryIdentifier, CreateEntry, DeleteEntry, EditEntry, EditEntryComponents,
-    EntryComponentHistoryQuery, EntryHistoryQuery, ListEntries, ListEntriesByComponent, MoveEntry,
-    QueryEntry, QueryEntryEffectsByMessages,
+    CheckEntryIdentifier, CreateEntry, CreateEntryTemplate, DeleteEntry, DeleteEntryTemplate,
+    EditEntry, EditEntryComponents, EditEntryTemplate, EntryComponentHistoryQuery,
+    EntryHistoryQuery, ImportEntryTemplates, InstantiateEntryTemplate, ListComponentTypes,
+    ListEntries, ListEntriesByComponent, ListEntryTemplates, MoveEntry, QueryEntries, QueryEntry,
+    QueryEntryEffectsByMessages, RegisterComponentType, RevertMessageEffects, ScopeStateDiffQuery,
+    ScopeStateQuery,
 };
+use super::component_types::ComponentTypeVersion;
+use super::import::{EntryImportReport, EntryImportTarget};
 use super::models::{
     Entry, EntryComponentHistory, EntryComponentMatch, EntryEffect, EntryEffectHistory,
     EntryHistory, EntryHistoryAction, EntryMetadata, MessageEntryEffects,
     components_as_set_history_changes,
 };
+use super::query::EntryQueryPage;
+use super::revert::MessageEffectsRevert;
+use super::templates::{EntryTemplate, EntryTemplateExport};
+use super::timeline::{ScopeState, ScopeStateDiff};
+use crate::channels::{Channel, ChannelMember};
 use crate::committed_changes::CommittedChanges;
 use crate::csrf::{authenticate, authenticate_optional};
 use crate::error::{AppError, Find};
@@ -15,10 +25,11 @@ use crate::events::Update;
 use crate::interface::{missing, parse_body, parse_query, response};
 use crate::messages::Message;
 use crate::scopes::models::Scope;
-use crate::spaces::resolve_resource_access_context;
+use crate::spaces::{resolve_resource_access_context, resolve_space_access};
 use hyper::Request;
 use hyper::body::Body;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use time::OffsetDateTime;
 use uuid::Uuid;
 
 pub(crate) async fn can_view_scope(
@@ -60,7 +71,7 @@ async fn resolve_scope(
 
 async fn ensure_reference_access(
     ctx: &crate::context::AppContext,
-    scope: &Scope,
+    space_id: Uuid,
     note_id: Option<Uuid>,
     user_id: Uuid,
 ) -> Result<(), AppError> {
@@ -69,7 +80,7 @@ async fn ensure_reference_access(
     };
     let note = ctx
         .space_store
-        .resolve_note_metadata(scope.space_id, note_id)
+        .resolve_note_metadata(space_id, note_id)
         .await?
         .or_not_found()?;
     if !crate::notes::handlers::can_view_note(ctx, &note, Some(user_id)).await? {
@@ -145,6 +156,78 @@ async fn list_entries_by_component(
         .map_err(Into::into)
 }
 
+const DEFAULT_QUERY_PAGE_SIZE: i64 = 50;
+const MAX_QUERY_PAGE_SIZE: i64 = 200;
+
+async fn query_entries(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<EntryQueryPage, AppError> {
+    let session = authenticate_optional(ctx, &req).await?;
+    let user_id = session.map(|session| session.user_id);
+    let QueryEntries {
+        space_id,
+        scope_ids,
+        filter,
+        sort,
+        descending,
+        offset,
+        limit,
+    } = parse_body(req).await?;
+    if offset < 0 {
+        return Err(AppError::BadRequest(
+            "The offset can not be negative".to_string(),
+        ));
+    }
+    let limit = limit
+        .unwrap_or(DEFAULT_QUERY_PAGE_SIZE)
+        .clamp(1, MAX_QUERY_PAGE_SIZE);
+    let requested: HashSet<Uuid> = scope_ids.into_iter().collect();
+    let scopes = ctx.space_store.list_scopes(space_id).await?;
+    if scopes.is_empty() {
+        return Err(AppError::NotFound("space"));
+    }
+    if requested
+        .iter()
+        .any(|scope_id| !scopes.iter().any(|scope| scope.id == *scope_id))
+    {
+        return Err(AppError::NotFound("scope"));
+    }
+    // Scopes gated by the same channel share the access check.
+    let mut contexts = HashMap::new();
+    let mut visible = Vec::new();
+    for scope in scopes {
+        if !requested.is_empty() && !requested.contains(&scope.id) {
+            continue;
+        }
+        let context = match contexts.get(&scope.access_channel_id) {
+            Some(context) => *context,
+            None => {
+                let context = resolve_resource_access_context(
+                    ctx,
+                    space_id,
+                    scope.access_channel_id,
+                    user_id,
+                )
+                .await?;
+                contexts.insert(scope.access_channel_id, context);
+                context
+            }
+        };
+        if scope.can_view(user_id, context) {
+            visible.push(scope.id);
+        }
+    }
+    if visible.is_empty() && !requested.is_empty() {
+        return Err(AppError::NoPermission(
+            "You don't have permission to view these entries".to_string(),
+        ));
+    }
+    EntryQueryPage::load(&ctx.db, &visible, &filter, &sort, descending, offset, limit)
+        .await
+        .map_err(Into::into)
+}
+
 async fn query_entry(
     ctx: &crate::context::AppContext,
     req: Request<impl Body>,
@@ -180,7 +263,13 @@ async fn create_entry(
             "You don't have permission to edit this scope".to_string(),
         ));
     }
-    ensure_reference_access(ctx, &scope, payload.reference_note_id, session.user_id).await?;
+    ensure_reference_access(
+        ctx,
+        scope.space_id,
+        payload.reference_note_id,
+        session.user_id,
+    )
+    .await?;
     let mut transaction = ctx.db.begin().await?;
     let entry = Entry::create(
         &mut transaction,
@@ -245,7 +334,13 @@ async fn edit_entry(
             "You don't have permission to edit this entry".to_string(),
         ));
     }
-    ensure_reference_access(ctx, &scope, payload.reference_note_id, session.user_id).await?;
+    ensure_reference_access(
+        ctx,
+        scope.space_id,
+        payload.reference_note_id,
+        session.user_id,
+    )
+    .await?;
     let mut transaction = ctx.db.begin().await?;
     let previous = EntryMetadata::get_by_id_for_update(&mut transaction, payload.entry_id)
         .await?
@@ -589,6 +684,201 @@ async fn effects_by_messages(
         .collect())
 }
 
+/// Revert the Entry Effects of a message. Nothing is changed if some components conflict with
+/// later changes and `skip_conflicts` is not set; the conflicts are returned either way.
+pub(crate) async fn revert_message_effects(
+    ctx: &crate::context::AppContext,
+    space_id: Uuid,
+    message_id: Uuid,
+    user_id: Uuid,
+    skip_conflicts: bool,
+) -> Result<MessageEffectsRevert, AppError> {
+    let mutation = ctx.space_store.acquire_mutation(space_id).await?;
+    let mut transaction = ctx.db.begin().await?;
+    let (revert, changes) = revert_message_effects_in(
+        ctx,
+        &mut transaction,
+        space_id,
+        message_id,
+        user_id,
+        skip_conflicts,
+    )
+    .await?;
+    if revert.reverted.is_empty() {
+        return Ok(revert);
+    }
+    let mutation = mutation.commit(transaction).await?;
+    changes.apply_with_mutation(ctx, &mutation).await;
+    Ok(revert)
+}
+
+/// Revert the Entry Effects of a message in a transaction, under the mutation of the space held
+/// by the caller. The changes are applied after the transaction is committed.
+pub(crate) async fn revert_message_effects_in(
+    ctx: &crate::context::AppContext,
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    space_id: Uuid,
+    message_id: Uuid,
+    user_id: Uuid,
+    skip_conflicts: bool,
+) -> Result<(MessageEffectsRevert, CommittedChanges), AppError> {
+    let access = resolve_space_access(ctx, space_id, Some(user_id)).await?;
+    let effects =
+        EntryEffect::list_by_message_for_update(transaction, space_id, message_id).await?;
+    if !access.is_game_master
+        && !access.can_manage()
+        && effects
+            .iter()
+            .any(|effect| effect.operator_id != Some(user_id))
+    {
+        return Err(AppError::NoPermission(
+            "Only the sender, game masters and admins can revert these effects".to_string(),
+        ));
+    }
+    let mut scope_ids = HashSet::new();
+    for effect in &effects {
+        if !scope_ids.insert(effect.scope_id) {
+            continue;
+        }
+        let scope = resolve_scope(ctx, space_id, effect.scope_id).await?;
+        if !can_edit_scope(ctx, &scope, user_id).await? {
+            return Err(AppError::NoPermission(
+                "You don't have permission to edit this scope".to_string(),
+            ));
+        }
+    }
+    let (revert, entries) =
+        MessageEffectsRevert::apply(transaction, message_id, user_id, &effects, skip_conflicts)
+            .await?;
+    let mut changes = CommittedChanges::default();
+    for entry in &entries.updated {
+        changes.entry_updated(space_id, entry);
+    }
+    for (scope_id, entry_id) in entries.deleted {
+        changes.entry_deleted(space_id, scope_id, entry_id);
+    }
+    if !revert.reverted.is_empty() {
+        tracing::info!(
+            %space_id,
+            %message_id,
+            %user_id,
+            reverted = revert.reverted.len(),
+            conflicts = revert.conflicts.len(),
+            "The entry effects of a message were reverted"
+        );
+    }
+    Ok((revert, changes))
+}
+
+async fn revert_by_message(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<MessageEffectsRevert, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let RevertMessageEffects {
+        space_id,
+        message_id,
+        skip_conflicts,
+    } = parse_body(req).await?;
+    revert_message_effects(ctx, space_id, message_id, session.user_id, skip_conflicts).await
+}
+
+/// Resolve a point in the history of a space, given as a time or a message.
+async fn resolve_point(
+    ctx: &crate::context::AppContext,
+    space_id: Uuid,
+    at: Option<OffsetDateTime>,
+    message_id: Option<Uuid>,
+    user_id: Option<Uuid>,
+) -> Result<Option<OffsetDateTime>, AppError> {
+    let message_id = match (at, message_id) {
+        (at, None) => return Ok(at),
+        (None, Some(message_id)) => message_id,
+        (Some(_), Some(_)) => {
+            return Err(AppError::BadRequest(
+                "A point can not be both a time and a message".to_string(),
+            ));
+        }
+    };
+    let message = Message::get(&ctx.db, &message_id, user_id.as_ref())
+        .await?
+        .or_not_found()?;
+    let channel = Channel::get_by_id(&ctx.db, &message.channel_id)
+        .await?
+        .filter(|channel| channel.space_id == space_id)
+        .or_not_found()?;
+    if !channel.is_public {
+        let user_id = user_id.or_no_permission()?;
+        let mut conn = ctx.db.acquire().await?;
+        ChannelMember::get(&mut conn, user_id, space_id, channel.id)
+            .await
+            .or_no_permission()?;
+    }
+    Ok(Some(message.created))
+}
+
+async fn view_scope(
+    ctx: &crate::context::AppContext,
+    space_id: Uuid,
+    scope_id: Uuid,
+    user_id: Option<Uuid>,
+) -> Result<(), AppError> {
+    let scope = resolve_scope(ctx, space_id, scope_id).await?;
+    if !can_view_scope(ctx, &scope, user_id).await? {
+        return Err(AppError::NoPermission(
+            "You don't have permission to view these entries".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+async fn state(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<ScopeState, AppError> {
+    let session = authenticate_optional(ctx, &req).await?;
+    let ScopeStateQuery {
+        space_id,
+        scope_id,
+        at,
+        message_id,
+    } = parse_query(req.uri())?;
+    let user_id = session.map(|session| session.user_id);
+    view_scope(ctx, space_id, scope_id, user_id).await?;
+    let at = resolve_point(ctx, space_id, at, message_id, user_id)
+        .await?
+        .unwrap_or_else(OffsetDateTime::now_utc);
+    ScopeState::load(&ctx.db, scope_id, at)
+        .await
+        .map_err(Into::into)
+}
+
+async fn state_diff(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<ScopeStateDiff, AppError> {
+    let session = authenticate_optional(ctx, &req).await?;
+    let ScopeStateDiffQuery {
+        space_id,
+        scope_id,
+        from,
+        from_message_id,
+        to,
+        to_message_id,
+    } = parse_query(req.uri())?;
+    let user_id = session.map(|session| session.user_id);
+    view_scope(ctx, space_id, scope_id, user_id).await?;
+    let from = resolve_point(ctx, space_id, from, from_message_id, user_id)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("The start point is required".to_string()))?;
+    let to = resolve_point(ctx, space_id, to, to_message_id, user_id)
+        .await?
+        .unwrap_or_else(OffsetDateTime::now_utc);
+    let from = ScopeState::load(&ctx.db, scope_id, from).await?;
+    let to = ScopeState::load(&ctx.db, scope_id, to).await?;
+    Ok(ScopeStateDiff::between(&from, &to))
+}
+
 async fn history(
     ctx: &crate::context::AppContext,
     req: Request<impl Body>,
@@ -658,6 +948,372 @@ async fn check_identifier(
     )
 }
 
+async fn component_types(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Vec<ComponentTypeVersion>, AppError> {
+    let session = authenticate_optional(ctx, &req).await?;
+    let ListComponentTypes { space_id } = parse_query(req.uri())?;
+    let access =
+        resolve_space_access(ctx, space_id, session.map(|session| session.user_id)).await?;
+    if !access.can_access {
+        return Err(AppError::NoPermission(
+            "You don't have permission to view this space".to_string(),
+        ));
+    }
+    ComponentTypeVersion::list_by_space(&ctx.db, space_id)
+        .await
+        .map_err(Into::into)
+}
+
+async fn register_component_type(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<ComponentTypeVersion, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let RegisterComponentType {
+        space_id,
+        component_type,
+        schema_version,
+        schema,
+        migration,
+    } = parse_body(req).await?;
+    let access = resolve_space_access(ctx, space_id, Some(session.user_id)).await?;
+    if !access.can_manage() {
+        return Err(AppError::NoPermission(
+            "Only admins can register component types".to_string(),
+        ));
+    }
+    let mutation = ctx.space_store.acquire_mutation(space_id).await?;
+    let mut transaction = ctx.db.begin().await?;
+    let (registered, migrated) = ComponentTypeVersion::register(
+        &mut transaction,
+        space_id,
+        session.user_id,
+        &component_type,
+        schema_version,
+        schema,
+        migration,
+    )
+    .await?;
+    let mutation = mutation.commit(transaction).await?;
+    let mut changes = CommittedChanges::default();
+    for entry in &migrated {
+        changes.entry_updated(space_id, entry);
+    }
+    changes.apply_with_mutation(ctx, &mutation).await;
+    tracing::info!(
+        %space_id,
+        user_id = %session.user_id,
+        component_type = %component_type,
+        schema_version,
+        migrated = migrated.len(),
+        "A component type schema was registered"
+    );
+    Ok(registered)
+}
+
+async fn import_entries(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<EntryImportReport, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let ImportEntries {
+        space_id,
+        target,
+        source,
+        dry_run,
+    } = parse_body(req).await?;
+    let rows = source.into_rows()?;
+    match target {
+        EntryImportTarget::Scope { scope_id } => {
+            let scope = resolve_scope(ctx, space_id, scope_id).await?;
+            if !can_edit_scope(ctx, &scope, session.user_id).await? {
+                return Err(AppError::NoPermission(
+                    "You don't have permission to edit this scope".to_string(),
+                ));
+            }
+        }
+        EntryImportTarget::Characters {
+            access_policy,
+            access_channel_id,
+        } => {
+            let access = resolve_space_access(ctx, space_id, Some(session.user_id)).await?;
+            let context = resolve_resource_access_context(
+                ctx,
+                space_id,
+                access_channel_id,
+                Some(session.user_id),
+            )
+            .await?;
+            if !access.is_member
+                || !access_policy.can_edit(Some(session.user_id), session.user_id, context)
+            {
+                return Err(AppError::NoPermission(
+                    "You cannot edit characters with this access policy and context".to_string(),
+                ));
+            }
+        }
+    }
+    let content_filter = ctx.space_store.resolve_content_filter(space_id).await?;
+    let mutation = ctx.space_store.acquire_mutation(space_id).await?;
+    let mut transaction = ctx.db.begin().await?;
+    let mut report = EntryImportReport::import(
+        &mut transaction,
+        space_id,
+        session.user_id,
+        &content_filter,
+        &target,
+        rows,
+    )
+    .await?;
+    if dry_run || !report.errors.is_empty() {
+        transaction.rollback().await?;
+        return Ok(report);
+    }
+    let mut scopes = Vec::with_capacity(report.characters.len());
+    for character in &report.characters {
+        scopes.push(
+            Scope::get_by_id(&mut *transaction, character.scope_id)
+                .await?
+                .or_not_found()?,
+        );
+    }
+    let mutation = mutation.commit(transaction).await?;
+    report.committed = true;
+    // One set of changes, so the space is refreshed once for the whole import.
+    let mut changes = CommittedChanges::default();
+    for character in &report.characters {
+        changes.character_updated(character);
+    }
+    for scope in &scopes {
+        changes.scope_updated(scope);
+    }
+    for entry in &report.entries {
+        changes.entry_updated(space_id, entry);
+    }
+    changes.apply_with_mutation(ctx, &mutation).await;
+    tracing::info!(
+        %space_id,
+        user_id = %session.user_id,
+        entries = report.entries.len(),
+        characters = report.characters.len(),
+        "Entries were imported"
+    );
+    Ok(report)
+}
+
+async fn resolve_template_access(
+    ctx: &crate::context::AppContext,
+    space_id: Uuid,
+    user_id: Option<Uuid>,
+    manage: bool,
+) -> Result<(), AppError> {
+    let access = resolve_space_access(ctx, space_id, user_id).await?;
+    if manage {
+        if !access.is_game_master && !access.can_manage() {
+            return Err(AppError::NoPermission(
+                "Only game masters can manage Entry templates".to_string(),
+            ));
+        }
+    } else if !access.can_access {
+        return Err(AppError::NoPermission(
+            "You don't have permission to view this space".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+async fn list_templates(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Vec<EntryTemplate>, AppError> {
+    let session = authenticate_optional(ctx, &req).await?;
+    let ListEntryTemplates { space_id } = parse_query(req.uri())?;
+    let user_id = session.map(|session| session.user_id);
+    resolve_template_access(ctx, space_id, user_id, false).await?;
+    EntryTemplate::list_by_space(&ctx.db, space_id)
+        .await
+        .map_err(Into::into)
+}
+
+async fn export_templates(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<EntryTemplateExport, AppError> {
+    let session = authenticate_optional(ctx, &req).await?;
+    let ListEntryTemplates { space_id } = parse_query(req.uri())?;
+    let user_id = session.map(|session| session.user_id);
+    resolve_template_access(ctx, space_id, user_id, false).await?;
+    let templates = EntryTemplate::list_by_space(&ctx.db, space_id).await?;
+    Ok(EntryTemplateExport {
+        templates: templates
+            .into_iter()
+            .map(|template| template.template)
+            .collect(),
+    })
+}
+
+async fn create_template(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<EntryTemplate, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let CreateEntryTemplate { space_id, template } = parse_body(req).await?;
+    resolve_template_access(ctx, space_id, Some(session.user_id), true).await?;
+    ensure_reference_access(ctx, space_id, template.reference_note_id, session.user_id).await?;
+    EntryTemplate::create(&ctx.db, space_id, template)
+        .await
+        .map_err(Into::into)
+}
+
+async fn edit_template(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<EntryTemplate, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let EditEntryTemplate {
+        space_id,
+        template_id,
+        expected_version,
+        template,
+    } = parse_body(req).await?;
+    resolve_template_access(ctx, space_id, Some(session.user_id), true).await?;
+    ensure_reference_access(ctx, space_id, template.reference_note_id, session.user_id).await?;
+    EntryTemplate::get_by_id(&ctx.db, space_id, template_id)
+        .await?
+        .or_not_found()?;
+    EntryTemplate::update(&ctx.db, space_id, template_id, expected_version, template)
+        .await?
+        .ok_or_else(|| AppError::Conflict("Entry template version is stale".to_string()))
+}
+
+async fn delete_template(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<bool, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let DeleteEntryTemplate {
+        space_id,
+        template_id,
+    } = parse_body(req).await?;
+    resolve_template_access(ctx, space_id, Some(session.user_id), true).await?;
+    EntryTemplate::delete(&ctx.db, space_id, template_id)
+        .await
+        .map_err(Into::into)
+}
+
+async fn import_templates(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Vec<EntryTemplate>, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let ImportEntryTemplates {
+        space_id,
+        mut templates,
+        replace_existing,
+    } = parse_body(req).await?;
+    resolve_template_access(ctx, space_id, Some(session.user_id), true).await?;
+    // Exports may come from another space, whose notes do not exist here.
+    for template in &mut templates {
+        let Some(note_id) = template.reference_note_id else {
+            continue;
+        };
+        let note = ctx
+            .space_store
+            .resolve_note_metadata(space_id, note_id)
+            .await?;
+        let viewable = match note {
+            Some(note) => {
+                crate::notes::handlers::can_view_note(ctx, &note, Some(session.user_id)).await?
+            }
+            None => false,
+        };
+        if !viewable {
+            template.reference_note_id = None;
+        }
+    }
+    let mut transaction = ctx.db.begin().await?;
+    let imported =
+        EntryTemplate::import(&mut transaction, space_id, templates, replace_existing).await?;
+    transaction.commit().await?;
+    Ok(imported)
+}
+
+async fn instantiate_template(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Vec<Entry>, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let payload: InstantiateEntryTemplate = parse_body(req).await?;
+    let mutation = ctx.space_store.acquire_mutation(payload.space_id).await?;
+    let scope = resolve_scope(ctx, payload.space_id, payload.scope_id).await?;
+    if !can_edit_scope(ctx, &scope, session.user_id).await? {
+        return Err(AppError::NoPermission(
+            "You don't have permission to edit this scope".to_string(),
+        ));
+    }
+    let template = EntryTemplate::get_by_id(&ctx.db, payload.space_id, payload.template_id)
+        .await?
+        .or_not_found()?;
+    ensure_reference_access(
+        ctx,
+        scope.space_id,
+        template.template.reference_note_id,
+        session.user_id,
+    )
+    .await?;
+    let mut transaction = ctx.db.begin().await?;
+    let entries = template
+        .instantiate(
+            &mut transaction,
+            scope.id,
+            payload.count,
+            payload.before_entry_id,
+        )
+        .await?;
+    let effect = EntryEffect::create(
+        &mut transaction,
+        payload.space_id,
+        scope.id,
+        session.user_id,
+    )
+    .await?;
+    for entry in &entries {
+        EntryHistory::record(
+            &mut transaction,
+            effect.id,
+            entry.id,
+            &entry.key,
+            EntryHistoryAction::Create,
+        )
+        .await?;
+        EntryComponentHistory::record(
+            &mut transaction,
+            effect.id,
+            entry.id,
+            &entry.key,
+            &components_as_set_history_changes(&entry.components),
+        )
+        .await?;
+    }
+    let attached_message = attach_message(
+        &mut transaction,
+        payload.message_id,
+        session.user_id,
+        effect.id,
+    )
+    .await?;
+    let mutation = mutation.commit(transaction).await?;
+    let mut changes = CommittedChanges::default();
+    for entry in &entries {
+        changes.entry_updated(payload.space_id, &entry.metadata);
+    }
+    changes.apply_with_mutation(ctx, &mutation).await;
+    publish_attached_message(payload.space_id, attached_message).await;
+    Ok(entries)
+}
+
 pub async fn router(
     ctx: &crate::context::AppContext,
     req: Request<impl Body>,
@@ -669,6 +1325,7 @@ pub async fn router(
         ("/by_scope", Method::GET) => response(list_entries(ctx, req).await).await,
         ("/by_component", Method::GET) => response(list_entries_by_component(ctx, req).await).await,
         ("/query", Method::GET) => response(query_entry(ctx, req).await).await,
+        ("/filter", Method::POST) => response(query_entries(ctx, req).await).await,
         ("/check_identifier", Method::GET) => response(check_identifier(ctx, req).await).await,
         ("/create", Method::POST) => response(create_entry(ctx, req).await).await,
         ("/edit", Method::PUT) => response(edit_entry(ctx, req).await).await,
@@ -677,9 +1334,28 @@ pub async fn router(
         ("/delete", Method::POST) => response(delete_entry(ctx, req).await).await,
         ("/history", Method::GET) => response(history(ctx, req).await).await,
         ("/component_history", Method::GET) => response(component_history(ctx, req).await).await,
+        ("/state", Method::GET) => response(state(ctx, req).await).await,
+        ("/state_diff", Method::GET) => response(state_diff(ctx, req).await).await,
+        ("/component_types", Method::GET) => response(component_types(ctx, req).await).await,
+        ("/register_component_type", Method::POST) => {
+            response(register_component_type(ctx, req).await).await
+        }
+        ("/import", Method::POST) => response(import_entries(ctx, req).await).await,
+        ("/templates", Method::GET) => response(list_templates(ctx, req).await).await,
+        ("/export_templates", Method::GET) => response(export_templates(ctx, req).await).await,
+        ("/create_template", Method::POST) => response(create_template(ctx, req).await).await,
+        ("/edit_template", Method::PUT) => response(edit_template(ctx, req).await).await,
+        ("/delete_template", Method::POST) => response(delete_template(ctx, req).await).await,
+        ("/import_templates", Method::POST) => response(import_templates(ctx, req).await).await,
+        ("/instantiate_template", Method::POST) => {
+            response(instantiate_template(ctx, req).await).await
+        }
         ("/effects_by_messages", Method::POST) => {
             response(effects_by_messages(ctx, req).await).await
         }
+        ("/revert_message_effects", Method::POST) => {
+            response(revert_by_message(ctx, req).await).await
+        }
         _ => missing(),
     }
 }
@@ -0,0 +1,671 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use super::models::{
+    Entry, EntryComponentHistory, EntryComponentPayloadInput, EntryEffect, EntryHistory,
+    EntryHistoryAction, EntryMetadata, components_as_set_history_changes,
+};
+use super::query::{is_index, path_segments};
+use crate::characters::Character;
+use crate::error::{ModelError, ValidationFailed};
+use crate::spaces::{AccessPolicy, ContentFilter};
+
+pub const MAX_IMPORT_ROWS: usize = 500;
+/// Separates the aliases and the tags in a CSV cell.
+const LIST_SEPARATOR: char = ';';
+/// Keeps a CSV column from making a huge array.
+const MAX_ARRAY_INDEX: usize = 255;
+
+/// One Entry or Character to import, in the shape of `CreateEntry`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryImportRow {
+    pub key: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// The key is used if empty.
+    #[serde(default)]
+    pub display_name: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub components: BTreeMap<String, EntryComponentPayloadInput>,
+}
+
+/// A CSV column holding a value in the data of a JSON component.
+#[derive(Debug, Clone, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvComponentColumn {
+    pub column: String,
+    pub component_type: String,
+    /// A dot-separated path into the component data, empty for the data itself.
+    /// Numeric segments index arrays.
+    #[serde(default)]
+    pub path: String,
+}
+
+/// The CSV columns of the fields, by their headers. Aliases and tags are separated by `;`.
+#[derive(Debug, Clone, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvColumnMapping {
+    pub key: String,
+    #[serde(default)]
+    pub aliases: Option<String>,
+    #[serde(default)]
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub tags: Option<String>,
+    #[serde(default)]
+    pub components: Vec<CsvComponentColumn>,
+}
+
+#[derive(Debug, Clone, Deserialize, specta::Type)]
+#[serde(
+    tag = "format",
+    rename_all = "SCREAMING_SNAKE_CASE",
+    rename_all_fields = "camelCase"
+)]
+pub enum EntryImportSource {
+    /// The first record is the header.
+    Csv {
+        data: String,
+        mapping: CsvColumnMapping,
+    },
+    Json {
+        rows: Vec<EntryImportRow>,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize, specta::Type)]
+#[serde(
+    tag = "kind",
+    rename_all = "SCREAMING_SNAKE_CASE",
+    rename_all_fields = "camelCase"
+)]
+pub enum EntryImportTarget {
+    /// Import the rows as Entries of the Scope.
+    Scope { scope_id: Uuid },
+    /// Import the rows as Characters. The components of a row go to an Entry in the Scope of the
+    /// Character, with the key of the Character.
+    Characters {
+        access_policy: AccessPolicy,
+        #[serde(default)]
+        access_channel_id: Option<Uuid>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryImportRowError {
+    /// Counted from 1, not counting the CSV header.
+    #[specta(type = f64)]
+    pub row: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryImportReport {
+    /// Nothing is imported unless every row is valid and it is not a dry run.
+    pub committed: bool,
+    pub errors: Vec<EntryImportRowError>,
+    /// The created Entries. A dry run lists the Entries it would create.
+    pub entries: Vec<EntryMetadata>,
+    pub characters: Vec<Character>,
+}
+
+/// Parse CSV as in RFC 4180, accepting `\n` line ends. Blank lines are skipped.
+fn parse_csv(data: &str) -> Result<Vec<Vec<String>>, ValidationFailed> {
+    let data = data.strip_prefix('\u{feff}').unwrap_or(data);
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut after_quote = false;
+    let mut chars = data.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            if c != '"' {
+                field.push(c);
+            } else if chars.peek() == Some(&'"') {
+                chars.next();
+                field.push('"');
+            } else {
+                quoted = false;
+                after_quote = true;
+            }
+            continue;
+        }
+        match c {
+            ',' => {
+                record.push(std::mem::take(&mut field));
+                after_quote = false;
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                if record.is_empty() && field.is_empty() && !after_quote {
+                    continue;
+                }
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+                after_quote = false;
+            }
+            _ if after_quote => {
+                return Err(ValidationFailed(
+                    "A quoted CSV field must end at a comma or a line end.",
+                ));
+            }
+            '"' if field.is_empty() => quoted = true,
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(ValidationFailed("A quoted CSV field is not closed."));
+    }
+    if !record.is_empty() || !field.is_empty() || after_quote {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Cells holding JSON, such as `12` or `true`, are read as JSON, others as strings.
+fn cell_value(cell: &str) -> Option<Value> {
+    let cell = cell.trim();
+    if cell.is_empty() {
+        return None;
+    }
+    Some(serde_json::from_str(cell).unwrap_or_else(|_| Value::String(cell.to_string())))
+}
+
+fn split_list(cell: &str) -> Vec<String> {
+    cell.split(LIST_SEPARATOR)
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(ToString::to_string)
+        .collect()
+}
+
+fn set_path(data: &mut Value, segments: &[&str], value: Value) -> Result<(), ()> {
+    let Some((segment, rest)) = segments.split_first() else {
+        if !data.is_null() {
+            return Err(());
+        }
+        *data = value;
+        return Ok(());
+    };
+    if data.is_null() {
+        *data = if is_index(segment) {
+            Value::Array(Vec::new())
+        } else {
+            Value::Object(Default::default())
+        };
+    }
+    let child = match data {
+        Value::Array(items) if is_index(segment) => {
+            let index: usize = segment.parse().map_err(|_| ())?;
+            if index > MAX_ARRAY_INDEX {
+                return Err(());
+            }
+            if index >= items.len() {
+                items.resize(index + 1, Value::Null);
+            }
+            &mut items[index]
+        }
+        Value::Object(fields) => fields.entry(segment.to_string()).or_insert(Value::Null),
+        _ => return Err(()),
+    };
+    set_path(child, rest, value)
+}
+
+struct ColumnIndexes<'a> {
+    key: usize,
+    aliases: Option<usize>,
+    display_name: Option<usize>,
+    tags: Option<usize>,
+    components: Vec<(usize, &'a CsvComponentColumn, Vec<&'a str>)>,
+}
+
+fn csv_rows(
+    data: &str,
+    mapping: &CsvColumnMapping,
+) -> Result<Vec<Result<EntryImportRow, String>>, ValidationFailed> {
+    let mut records = parse_csv(data)?.into_iter();
+    let header = records
+        .next()
+        .ok_or(ValidationFailed("The CSV has no header."))?;
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|header| header.trim() == name.trim())
+            .ok_or(ValidationFailed(
+                "A mapped column is not in the CSV header.",
+            ))
+    };
+    let optional_column = |name: &Option<String>| name.as_deref().map(column).transpose();
+    let mut components = Vec::with_capacity(mapping.components.len());
+    for component in &mapping.components {
+        super::models::validate_component_type(&component.component_type)?;
+        components.push((
+            column(&component.column)?,
+            component,
+            path_segments(&component.path)?,
+        ));
+    }
+    let columns = ColumnIndexes {
+        key: column(&mapping.key)?,
+        aliases: optional_column(&mapping.aliases)?,
+        display_name: optional_column(&mapping.display_name)?,
+        tags: optional_column(&mapping.tags)?,
+        components,
+    };
+    Ok(records.map(|record| csv_row(&record, &columns)).collect())
+}
+
+fn csv_row(record: &[String], columns: &ColumnIndexes) -> Result<EntryImportRow, String> {
+    let cell = |index: usize| record.get(index).map(String::as_str).unwrap_or_default();
+    let optional_cell = |index: Option<usize>| index.map(cell).unwrap_or_default();
+    let mut data: BTreeMap<&str, Value> = BTreeMap::new();
+    for (index, column, segments) in &columns.components {
+        let Some(value) = cell_value(cell(*index)) else {
+            continue;
+        };
+        let component = data
+            .entry(column.component_type.as_str())
+            .or_insert(Value::Null);
+        set_path(component, segments, value).map_err(|_| {
+            format!(
+                "The column \"{}\" overlaps another column of the component, \
+                 or its array index is over 255.",
+                column.column
+            )
+        })?;
+    }
+    Ok(EntryImportRow {
+        key: cell(columns.key).trim().to_string(),
+        aliases: split_list(optional_cell(columns.aliases)),
+        display_name: optional_cell(columns.display_name).trim().to_string(),
+        tags: split_list(optional_cell(columns.tags)),
+        components: data
+            .into_iter()
+            .map(|(component_type, data)| {
+                (
+                    component_type.to_string(),
+                    EntryComponentPayloadInput::json(data),
+                )
+            })
+            .collect(),
+    })
+}
+
+impl EntryImportSource {
+    /// The rows to import. A row which can not be read is an error of the row.
+    pub fn into_rows(self) -> Result<Vec<Result<EntryImportRow, String>>, ValidationFailed> {
+        let rows = match self {
+            EntryImportSource::Csv { data, mapping } => csv_rows(&data, &mapping)?,
+            EntryImportSource::Json { rows } => rows.into_iter().map(Ok).collect(),
+        };
+        if rows.is_empty() {
+            return Err(ValidationFailed("There is nothing to import."));
+        }
+        if rows.len() > MAX_IMPORT_ROWS {
+            return Err(ValidationFailed("Too many rows to import (max 500)."));
+        }
+        Ok(rows)
+    }
+}
+
+/// Errors which are the fault of the row, rather than of the import.
+fn row_error(error: ModelError) -> Result<String, ModelError> {
+    match error {
+        ModelError::Validation(_) | ModelError::NotFound(_) => Ok(error.to_string()),
+        ModelError::Conflict(_) => Ok("The key or an alias is already used.".to_string()),
+        error => Err(error),
+    }
+}
+
+impl EntryImportRow {
+    fn display_name(&self) -> String {
+        if self.display_name.trim().is_empty() {
+            self.key.clone()
+        } else {
+            self.display_name.clone()
+        }
+    }
+
+    pub(crate) async fn create_entry(
+        self,
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        effect: &EntryEffect,
+        scope_id: Uuid,
+    ) -> Result<Entry, ModelError> {
+        if Entry::exists_identifier(&mut **db, scope_id, Some(&self.key), &self.aliases).await? {
+            return Err(ModelError::Conflict("entry_identifiers".to_string()));
+        }
+        let display_name = self.display_name();
+        let entry = Entry::create(
+            db,
+            scope_id,
+            self.key,
+            self.aliases,
+            display_name,
+            None,
+            self.components,
+            self.tags,
+            None,
+        )
+        .await?;
+        EntryHistory::record(
+            db,
+            effect.id,
+            entry.id,
+            &entry.key,
+            EntryHistoryAction::Create,
+        )
+        .await?;
+        EntryComponentHistory::record(
+            db,
+            effect.id,
+            entry.id,
+            &entry.key,
+            &components_as_set_history_changes(&entry.components),
+        )
+        .await?;
+        Ok(entry)
+    }
+
+    async fn create_character(
+        self,
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        space_id: Uuid,
+        user_id: Uuid,
+        content_filter: &ContentFilter,
+        access_policy: AccessPolicy,
+        access_channel_id: Option<Uuid>,
+    ) -> Result<(Character, Option<Entry>), ModelError> {
+        let name = content_filter.check(&self.display_name())?.into_owned();
+        if Character::exists_identifier(
+            &mut **db,
+            space_id,
+            Some(&self.key),
+            Some(&self.aliases),
+            None,
+        )
+        .await?
+        {
+            return Err(ModelError::Conflict("character_identifiers".to_string()));
+        }
+        let character = Character::create(
+            db,
+            space_id,
+            user_id,
+            &name,
+            &self.key,
+            self.aliases,
+            "",
+            "",
+            access_policy,
+            access_channel_id,
+            self.tags.clone(),
+        )
+        .await?;
+        if self.components.is_empty() {
+            return Ok((character, None));
+        }
+        let effect = EntryEffect::create(db, space_id, character.scope_id, user_id).await?;
+        let row = EntryImportRow {
+            key: character.key.to_string(),
+            aliases: Vec::new(),
+            display_name: name,
+            tags: self.tags,
+            components: self.components,
+        };
+        let entry = row.create_entry(db, &effect, character.scope_id).await?;
+        Ok((character, Some(entry)))
+    }
+}
+
+impl EntryImportReport {
+    /// Import the rows in the transaction. Each row runs in a savepoint, so a failed row is
+    /// reported and the rest are still checked. The caller commits only if there are no errors.
+    pub async fn import(
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        space_id: Uuid,
+        user_id: Uuid,
+        content_filter: &ContentFilter,
+        target: &EntryImportTarget,
+        rows: Vec<Result<EntryImportRow, String>>,
+    ) -> Result<Self, ModelError> {
+        let mut report = EntryImportReport::default();
+        let scope_effect = match target {
+            EntryImportTarget::Scope { scope_id } => {
+                Some(EntryEffect::create(db, space_id, *scope_id, user_id).await?)
+            }
+            EntryImportTarget::Characters { .. } => None,
+        };
+        for (index, row) in rows.into_iter().enumerate() {
+            let row_number = index + 1;
+            let row = match row {
+                Ok(row) => row,
+                Err(message) => {
+                    report.errors.push(EntryImportRowError {
+                        row: row_number,
+                        message,
+                    });
+                    continue;
+                }
+            };
+            let mut savepoint = sqlx::Acquire::begin(&mut *db).await?;
+            let result = match (target, &scope_effect) {
+                (
+                    EntryImportTarget::Characters {
+                        access_policy,
+                        access_channel_id,
+                    },
+                    _,
+                ) => row
+                    .create_character(
+                        &mut savepoint,
+                        space_id,
+                        user_id,
+                        content_filter,
+                        *access_policy,
+                        *access_channel_id,
+                    )
+                    .await
+                    .map(|(character, entry)| (Some(character), entry)),
+                (_, Some(effect)) => row
+                    .create_entry(&mut savepoint, effect, effect.scope_id)
+                    .await
+                    .map(|entry| (None, Some(entry))),
+                (EntryImportTarget::Scope { .. }, None) => {
+                    return Err(anyhow::anyhow!("The Entry Effect of the import is missing").into());
+                }
+            };
+            match result {
+                Ok((character, entry)) => {
+                    savepoint.commit().await?;
+                    report.characters.extend(character);
+                    report.entries.extend(entry.map(|entry| entry.metadata));
+                }
+                Err(error) => {
+                    savepoint.rollback().await?;
+                    report.errors.push(EntryImportRowError {
+                        row: row_number,
+                        message: row_error(error)?,
+                    });
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn csv_is_parsed() {
+        let records = parse_csv(
+            "\u{feff}key,name,note\r\ngoblin,\"Goblin, the \"\"Small\"\"\",\"two\nlines\"\n\n,,\nlast,x,",
+        )
+        .unwrap();
+        assert_eq!(
+            records,
+            [
+                vec!["key", "name", "note"],
+                vec!["goblin", "Goblin, the \"Small\"", "two\nlines"],
+                vec!["", "", ""],
+                vec!["last", "x", ""],
+            ]
+        );
+        assert!(parse_csv("a,\"b").is_err());
+        assert!(parse_csv("a,\"b\"c").is_err());
+    }
+
+    #[test]
+    fn csv_columns_are_mapped() {
+        let mapping: CsvColumnMapping = serde_json::from_value(json!({
+            "key": "Key",
+            "displayName": "Name",
+            "tags": "Tags",
+            "components": [
+                { "column": "HP", "componentType": "dnd/stats", "path": "hp.max" },
+                { "column": "AC", "componentType": "dnd/stats", "path": "ac" },
+                { "column": "Notes", "componentType": "core/notes" },
+            ],
+        }))
+        .unwrap();
+        let data =
+            "Key,Name,HP,AC,Tags,Notes\ngoblin,Goblin,7,15,monster; small,sneaky\norc,,15,,,\n";
+        let rows = csv_rows(data, &mapping).unwrap();
+        assert_eq!(
+            rows[0],
+            Ok(EntryImportRow {
+                key: "goblin".to_string(),
+                aliases: Vec::new(),
+                display_name: "Goblin".to_string(),
+                tags: vec!["monster".to_string(), "small".to_string()],
+                components: BTreeMap::from([
+                    (
+                        "core/notes".to_string(),
+                        EntryComponentPayloadInput::json(json!("sneaky"))
+                    ),
+                    (
+                        "dnd/stats".to_string(),
+                        EntryComponentPayloadInput::json(json!({ "hp": { "max": 7 }, "ac": 15 }))
+                    ),
+                ]),
+            })
+        );
+        let orc = rows[1].as_ref().unwrap();
+        assert_eq!(orc.display_name(), "orc");
+        assert_eq!(orc.components.len(), 1);
+
+        let overlapping: CsvColumnMapping = serde_json::from_value(json!({
+            "key": "Key",
+            "components": [
+                { "column": "HP", "componentType": "dnd/stats" },
+                { "column": "AC", "componentType": "dnd/stats", "path": "ac" },
+            ],
+        }))
+        .unwrap();
+        let rows = csv_rows("Key,HP,AC\ngoblin,7,15\n", &overlapping).unwrap();
+        assert!(rows[0].is_err());
+        assert!(csv_rows("Name\ngoblin\n", &mapping).is_err());
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn db_test_import_reports_rows(pool: sqlx::PgPool) {
+        use crate::spaces::Space;
+        use crate::users::User;
+
+        let raw = Uuid::new_v4().simple().to_string();
+        let user = User::register(
+            &pool,
+            &format!("entry_import_{raw}@example.com"),
+            &format!("eimport_{}", &raw[..8]),
+            "Entry Import Tester",
+            "EntryImportPass123!",
+        )
+        .await
+        .expect("create user failed");
+        let space = Space::create(
+            &pool,
+            format!("entry_import_{}", &raw[..8]),
+            &user.id,
+            "Entry import test".to_string(),
+            None,
+            Some("d20"),
+        )
+        .await
+        .expect("create space failed");
+        let row = |key: &str| EntryImportRow {
+            key: key.to_string(),
+            aliases: Vec::new(),
+            display_name: String::new(),
+            tags: Vec::new(),
+            components: BTreeMap::from([(
+                "example/stats".to_string(),
+                EntryComponentPayloadInput::json(json!({ "hp": 7 })),
+            )]),
+        };
+        let content_filter = ContentFilter::default();
+        let target = EntryImportTarget::Scope {
+            scope_id: space.scope_id,
+        };
+
+        let mut transaction = pool.begin().await.unwrap();
+        let report = EntryImportReport::import(
+            &mut transaction,
+            space.id,
+            user.id,
+            &content_filter,
+            &target,
+            vec![
+                Ok(row("goblin")),
+                Ok(row("Goblin")),
+                Err("unreadable".to_string()),
+                Ok(row("bad key!")),
+                Ok(row("orc")),
+            ],
+        )
+        .await
+        .expect("import failed");
+        let failed_rows: Vec<_> = report.errors.iter().map(|error| error.row).collect();
+        assert_eq!(failed_rows, [2, 3, 4]);
+        assert_eq!(report.entries.len(), 2);
+        transaction.rollback().await.unwrap();
+
+        let characters = EntryImportTarget::Characters {
+            access_policy: AccessPolicy::Personal,
+            access_channel_id: None,
+        };
+        let mut transaction = pool.begin().await.unwrap();
+        let report = EntryImportReport::import(
+            &mut transaction,
+            space.id,
+            user.id,
+            &content_filter,
+            &characters,
+            vec![Ok(row("hero")), Ok(row("sidekick"))],
+        )
+        .await
+        .expect("import failed");
+        assert!(report.errors.is_empty());
+        assert_eq!(report.characters.len(), 2);
+        assert_eq!(report.entries[0].scope_id, report.characters[0].scope_id);
+        assert_eq!(report.entries[0].key, "hero");
+        transaction.commit().await.unwrap();
+        assert!(
+            Entry::exists_identifier(&pool, report.characters[1].scope_id, Some("sidekick"), &[])
+                .await
+                .unwrap()
+        );
+    }
+}
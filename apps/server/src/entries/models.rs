@@ -6,6 +6,8 @@ use std::ops::Deref;
 use time::OffsetDateTime;
 use uuid::Uuid;
 
+use super::component_types::ComponentTypeRegistry;
+use super::formula::{Formula, check_formula_cycles, evaluate_formulas, evaluate_local_formulas};
 use crate::characters::{normalize_aliases, normalize_ident};
 use crate::error::{ModelError, ValidationFailed};
 
@@ -20,6 +22,7 @@ const PORTRAIT_COMPONENT_LIMIT_ERROR: &str =
 pub(crate) enum EntryComponentPayloadType {
     Json,
     Asset,
+    Formula,
 }
 
 impl EntryComponentPayloadType {
@@ -27,6 +30,7 @@ impl EntryComponentPayloadType {
         match self {
             Self::Json => "JSON",
             Self::Asset => "ASSET",
+            Self::Formula => "FORMULA",
         }
     }
 }
@@ -38,6 +42,7 @@ struct EntryComponentJoinedRow {
     json_data: Option<Value>,
     json_schema_version: Option<i32>,
     asset_id: Option<Uuid>,
+    formula: Option<String>,
     version: Uuid,
     modified: OffsetDateTime,
 }
@@ -63,6 +68,7 @@ struct EntryComponentMatchRow {
     json_data: Option<Value>,
     json_schema_version: Option<i32>,
     asset_id: Option<Uuid>,
+    formula: Option<String>,
     component_version: Uuid,
     component_modified: OffsetDateTime,
 }
@@ -74,6 +80,7 @@ struct EntryComponentForUpdateRow {
     schema_version: Option<i32>,
     json_exists: bool,
     asset_exists: bool,
+    formula_exists: bool,
 }
 
 impl EntryComponentForUpdateRow {
@@ -81,6 +88,7 @@ impl EntryComponentForUpdateRow {
         match self.payload_type {
             EntryComponentPayloadType::Json => self.json_exists,
             EntryComponentPayloadType::Asset => self.asset_exists,
+            EntryComponentPayloadType::Formula => self.formula_exists,
         }
     }
 
@@ -178,6 +186,18 @@ pub enum EntryComponent {
         version: Uuid,
         modified: OffsetDateTime,
     },
+    /// Computed from other components when the Entry is read.
+    Formula {
+        formula: String,
+        /// The result, or `null` if the formula failed
+        #[serde(default)]
+        value: Option<f64>,
+        /// Why the formula failed, such as a missing reference
+        #[serde(default)]
+        error: Option<String>,
+        version: Uuid,
+        modified: OffsetDateTime,
+    },
 }
 
 impl EntryComponent {
@@ -186,10 +206,13 @@ impl EntryComponent {
             + match self {
                 Self::Json { data, .. } => crate::utils::estimated_json_value_size(data),
                 Self::Asset { .. } => 0,
+                Self::Formula { formula, error, .. } => {
+                    formula.len() + error.as_ref().map_or(0, String::len)
+                }
             }
     }
 
-    fn history_payload(&self) -> Value {
+    pub(crate) fn history_payload(&self) -> Value {
         match self {
             Self::Json {
                 data,
@@ -197,6 +220,7 @@ impl EntryComponent {
                 ..
             } => json_component_history_payload(data, *schema_version),
             Self::Asset { asset_id, .. } => asset_component_history_payload(*asset_id),
+            Self::Formula { formula, .. } => formula_component_history_payload(formula),
         }
     }
 
@@ -204,7 +228,7 @@ impl EntryComponent {
     pub(crate) fn json_data(&self) -> serde_json::Value {
         match self {
             Self::Json { data, .. } => data.clone(),
-            Self::Asset { .. } => panic!("expected a JSON Entry Component"),
+            _ => panic!("expected a JSON Entry Component"),
         }
     }
 
@@ -212,14 +236,15 @@ impl EntryComponent {
     pub(crate) fn schema_version(&self) -> i32 {
         match self {
             Self::Json { schema_version, .. } => *schema_version,
-            Self::Asset { .. } => panic!("expected a JSON Entry Component"),
+            _ => panic!("expected a JSON Entry Component"),
         }
     }
 
-    #[cfg(test)]
     pub(crate) fn version(&self) -> Uuid {
         match self {
-            Self::Json { version, .. } | Self::Asset { version, .. } => *version,
+            Self::Json { version, .. }
+            | Self::Asset { version, .. }
+            | Self::Formula { version, .. } => *version,
         }
     }
 }
@@ -232,6 +257,15 @@ fn json_component_history_payload(data: &Value, schema_version: i32) -> Value {
     })
 }
 
+/// The history payload of a migrated component notes the version it was migrated from.
+pub(crate) fn without_migration_note(payload: &Value) -> Value {
+    let mut payload = payload.clone();
+    if let Some(payload) = payload.as_object_mut() {
+        payload.remove("migratedFrom");
+    }
+    payload
+}
+
 fn asset_component_history_payload(asset_id: Uuid) -> Value {
     serde_json::json!({
         "payloadType": "ASSET",
@@ -239,6 +273,13 @@ fn asset_component_history_payload(asset_id: Uuid) -> Value {
     })
 }
 
+fn formula_component_history_payload(formula: &str) -> Value {
+    serde_json::json!({
+        "payloadType": "FORMULA",
+        "formula": formula,
+    })
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, specta::Type, sqlx::FromRow)]
 #[serde(rename_all = "camelCase")]
 pub struct EntryEffect {
@@ -250,6 +291,8 @@ pub struct EntryEffect {
     #[serde(with = "time::serde::rfc3339")]
     pub created: OffsetDateTime,
     pub message_id: Option<Uuid>,
+    /// The Entry Effect which reverted this one
+    pub reverted_by: Option<Uuid>,
 }
 
 impl EntryEffect {
@@ -302,6 +345,22 @@ impl EntryEffect {
         .fetch_all(db)
         .await
     }
+
+    /// The Entry Effects of a message which have not been reverted, oldest first.
+    pub(crate) async fn list_by_message_for_update(
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        space_id: Uuid,
+        message_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_file_as!(
+            EntryEffect,
+            "sql/entries/list_effects_by_message_for_update.sql",
+            space_id,
+            message_id,
+        )
+        .fetch_all(&mut **db)
+        .await
+    }
 }
 
 impl Deref for Entry {
@@ -349,6 +408,20 @@ impl EntryMetadata {
             .fetch_optional(&mut **db)
             .await
     }
+
+    /// Bump the components version, so the cached components of the Entry are reloaded.
+    pub(crate) async fn bump_components_version(
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        scope_id: Uuid,
+        entry_id: Uuid,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_file_scalar!("sql/entries/bump_components_version.sql", entry_id)
+            .fetch_optional(&mut **db)
+            .await?;
+        sqlx::query_file_as!(EntryMetadata, "sql/entries/get.sql", scope_id, entry_id)
+            .fetch_optional(&mut **db)
+            .await
+    }
 }
 
 impl EntryComponentJoinedRow {
@@ -386,6 +459,23 @@ impl EntryComponentJoinedRow {
                     modified: self.modified,
                 }
             }
+            EntryComponentPayloadType::Formula => {
+                let Some(formula) = self.formula else {
+                    report_missing_component_payload(
+                        entry_id,
+                        &self.component_type,
+                        self.payload_type,
+                    );
+                    return None;
+                };
+                EntryComponent::Formula {
+                    formula,
+                    value: None,
+                    error: None,
+                    version: self.version,
+                    modified: self.modified,
+                }
+            }
         };
         Some((self.component_type, component))
     }
@@ -400,6 +490,7 @@ impl EntryComponentMatchRow {
             json_data: self.json_data,
             json_schema_version: self.json_schema_version,
             asset_id: self.asset_id,
+            formula: self.formula,
             version: self.component_version,
             modified: self.component_modified,
         }
@@ -436,11 +527,13 @@ impl EntryComponentsSnapshot {
         )
         .fetch_all(db)
         .await?;
-        let components = rows
-            .into_iter()
-            .filter_map(|row| row.into_response(entry_id))
-            .collect();
-        Ok(Self { components })
+        let mut components = components_from_rows(entry_id, rows);
+        // Formulas over other Entries are evaluated again on read, as their results do not
+        // follow the components version of this Entry.
+        evaluate_local_formulas(entry_id, &mut components);
+        Ok(Self {
+            components: components.into_iter().collect(),
+        })
     }
 
     pub(crate) fn to_response(&self) -> BTreeMap<CompactString, EntryComponent> {
@@ -451,7 +544,7 @@ impl EntryComponentsSnapshot {
     }
 }
 
-fn validate_components(
+pub(crate) fn validate_components(
     components: &BTreeMap<String, EntryComponentPayloadInput>,
 ) -> Result<(), ValidationFailed> {
     for (component_type, payload) in components {
@@ -461,6 +554,38 @@ fn validate_components(
     Ok(())
 }
 
+/// Check the JSON payloads of registered component types, and upgrade them to the latest
+/// schema version.
+fn upgrade_components(
+    registry: &ComponentTypeRegistry,
+    components: BTreeMap<String, EntryComponentPayloadInput>,
+) -> Result<BTreeMap<String, EntryComponentPayloadInput>, ValidationFailed> {
+    components
+        .into_iter()
+        .map(|(component_type, payload)| {
+            if !registry.is_registered(&component_type) {
+                return Ok((component_type, payload));
+            }
+            let payload = match payload {
+                EntryComponentPayloadInput::Json {
+                    schema_version,
+                    data,
+                } => {
+                    let (data, schema_version) =
+                        registry.upgrade(&component_type, &data, schema_version)?;
+                    EntryComponentPayloadInput::json_with_schema(data.into_owned(), schema_version)
+                }
+                EntryComponentPayloadInput::Asset { .. }
+                | EntryComponentPayloadInput::Formula { .. } => {
+                    registry.check_non_json_payload(&component_type)?;
+                    payload
+                }
+            };
+            Ok((component_type, payload))
+        })
+        .collect()
+}
+
 fn validate_component_payload(
     component_type: &str,
     payload: &EntryComponentPayloadInput,
@@ -475,9 +600,9 @@ fn validate_component_payload(
     payload.validate()
 }
 
-fn validate_component_type(component_type: &str) -> Result<(), ValidationFailed> {
-    // Component types are namespaced identifiers. By convention, `core/` is
-    // reserved for built-in components; ownership is not enforced yet.
+pub(crate) fn validate_component_type(component_type: &str) -> Result<(), ValidationFailed> {
+    // Component types are namespaced identifiers. `core/` is reserved for
+    // built-in components, which spaces can not register schemas for.
     crate::validators::NAMESPACED_TYPE.run(component_type)
 }
 
@@ -491,6 +616,21 @@ fn components_from_rows(
         .collect()
 }
 
+/// The components of an Entry as they are stored, without the results of formulas.
+pub(crate) async fn stored_components(
+    db: &mut sqlx::PgConnection,
+    entry_id: Uuid,
+) -> Result<BTreeMap<CompactString, EntryComponent>, sqlx::Error> {
+    let rows = sqlx::query_file_as!(
+        EntryComponentJoinedRow,
+        "sql/entries/get_components.sql",
+        entry_id
+    )
+    .fetch_all(db)
+    .await?;
+    Ok(components_from_rows(entry_id, rows))
+}
+
 fn report_missing_component_payload(
     entry_id: Uuid,
     component_type: &str,
@@ -543,6 +683,16 @@ async fn insert_components(
                     return Err(ModelError::NotFound("Asset"));
                 }
             }
+            EntryComponentPayloadInput::Formula { formula } => {
+                sqlx::query_file_scalar!(
+                    "sql/entries/insert_formula_component.sql",
+                    entry_id,
+                    component_type,
+                    formula,
+                )
+                .fetch_one(&mut **db)
+                .await?;
+            }
         }
     }
     Ok(())
@@ -676,10 +826,23 @@ impl Entry {
         )
         .fetch_all(db)
         .await?;
-        Ok(rows
+        let mut matches = rows
             .into_iter()
             .filter_map(EntryComponentMatchRow::into_response)
-            .collect())
+            .collect::<Vec<_>>();
+        for matched in &mut matches {
+            if !matches!(matched.component, EntryComponent::Formula { .. }) {
+                continue;
+            }
+            let entry_id = matched.metadata.id;
+            let mut conn = db.acquire().await?;
+            let mut components = stored_components(&mut conn, entry_id).await?;
+            evaluate_formulas(&mut conn, scope_id, entry_id, &mut components).await?;
+            if let Some(component) = components.remove(component_type) {
+                matched.component = component;
+            }
+        }
+        Ok(matches)
     }
 
     pub async fn get_by_id(
@@ -694,16 +857,10 @@ impl Entry {
         else {
             return Ok(None);
         };
-        let components = sqlx::query_file_as!(
-            EntryComponentJoinedRow,
-            "sql/entries/get_components.sql",
-            entry_id
-        )
-        .fetch_all(db)
-        .await?;
-        Ok(Some(entry.with_components(components_from_rows(
-            entry_id, components,
-        ))))
+        let mut conn = db.acquire().await?;
+        let mut components = stored_components(&mut conn, entry_id).await?;
+        evaluate_formulas(&mut conn, scope_id, entry_id, &mut components).await?;
+        Ok(Some(entry.with_components(components)))
     }
 
     pub(crate) async fn get_by_id_in_transaction(
@@ -718,16 +875,9 @@ impl Entry {
         else {
             return Ok(None);
         };
-        let components = sqlx::query_file_as!(
-            EntryComponentJoinedRow,
-            "sql/entries/get_components.sql",
-            entry_id
-        )
-        .fetch_all(&mut **db)
-        .await?;
-        Ok(Some(entry.with_components(components_from_rows(
-            entry_id, components,
-        ))))
+        let mut components = stored_components(&mut **db, entry_id).await?;
+        evaluate_formulas(&mut **db, scope_id, entry_id, &mut components).await?;
+        Ok(Some(entry.with_components(components)))
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -747,6 +897,9 @@ impl Entry {
         let display_name = display_name.trim().to_string();
         crate::validators::DISPLAY_NAME.run(&display_name)?;
         validate_components(&components)?;
+        let component_types = components.keys().cloned().collect::<Vec<_>>();
+        let registry = ComponentTypeRegistry::load_by_scope(db, scope_id, &component_types).await?;
+        let components = upgrade_components(&registry, components)?;
         for (component_type, payload) in &components {
             if let EntryComponentPayloadInput::Asset { asset_id } = payload {
                 validate_asset_component(db, component_type, *asset_id).await?;
@@ -783,6 +936,12 @@ impl Entry {
         }
         insert_identifiers(db, scope_id, entry_id, &key, &aliases).await?;
         insert_components(db, entry_id, &components).await?;
+        if components
+            .values()
+            .any(EntryComponentPayloadInput::is_formula)
+        {
+            check_formula_cycles(db, entry_id).await?;
+        }
         Self::get_by_id_in_transaction(db, scope_id, entry_id)
             .await?
             .ok_or(ModelError::NotFound("Entry"))
@@ -824,6 +983,8 @@ impl Entry {
             .execute(&mut **db)
             .await?;
         insert_identifiers(db, scope_id, entry_id, &key, &aliases).await?;
+        // A formula of another Entry may now refer to this Entry by its new key.
+        check_formula_cycles(db, entry_id).await?;
         Self::get_by_id_in_transaction(db, scope_id, entry_id)
             .await
             .map_err(Into::into)
@@ -1048,7 +1209,7 @@ pub enum EntryComponentMutation {
     },
 }
 
-#[derive(Debug, Clone, Deserialize, specta::Type)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, specta::Type)]
 #[serde(
     tag = "payloadType",
     rename_all = "SCREAMING_SNAKE_CASE",
@@ -1063,6 +1224,9 @@ pub enum EntryComponentPayloadInput {
     Asset {
         asset_id: Uuid,
     },
+    Formula {
+        formula: String,
+    },
 }
 
 impl EntryComponentPayloadInput {
@@ -1088,8 +1252,15 @@ impl EntryComponentPayloadInput {
                 "Component schema version must be positive.",
             ));
         }
+        if let Self::Formula { formula } = self {
+            Formula::parse(formula)?;
+        }
         Ok(())
     }
+
+    fn is_formula(&self) -> bool {
+        matches!(self, Self::Formula { .. })
+    }
 }
 
 impl EntryComponentMutation {
@@ -1138,6 +1309,12 @@ impl Entry {
             }
         }
 
+        let component_types = component_types
+            .into_iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        let registry = ComponentTypeRegistry::load_by_entry(db, entry_id, &component_types).await?;
+
         let mut history_changes = Vec::with_capacity(changes.len());
         for change in changes {
             let component_type = change.component_type();
@@ -1167,7 +1344,11 @@ impl Entry {
                     {
                         validate_portrait_capacity_by_entry(db, entry_id).await?;
                     }
+                    if payload.is_formula() {
+                        registry.check_non_json_payload(component_type)?;
+                    }
                     if let EntryComponentPayloadInput::Asset { asset_id } = payload {
+                        registry.check_non_json_payload(component_type)?;
                         validate_asset_component(db, component_type, *asset_id).await?;
                         let valid = sqlx::query_file_scalar!(
                             "sql/entries/validate_component_asset.sql",
@@ -1202,18 +1383,20 @@ impl Entry {
                                         .flatten()
                                 })
                             });
+                            let (data, schema_version) =
+                                registry.upgrade(component_type, data, schema_version)?;
                             let schema_version = sqlx::query_file_scalar!(
                                 "sql/entries/insert_json_component.sql",
                                 entry_id,
                                 component_type,
-                                data,
+                                data.as_ref(),
                                 schema_version.as_ref(),
                             )
                             .fetch_one(&mut **db)
                             .await?;
                             history_changes.push(EntryComponentHistoryChange::set(
                                 component_type,
-                                json_component_history_payload(data, schema_version),
+                                json_component_history_payload(&data, schema_version),
                             ));
                         }
                         EntryComponentPayloadInput::Asset { asset_id } => {
@@ -1233,6 +1416,20 @@ impl Entry {
                                 asset_component_history_payload(*asset_id),
                             ));
                         }
+                        EntryComponentPayloadInput::Formula { formula } => {
+                            sqlx::query_file_scalar!(
+                                "sql/entries/insert_formula_component.sql",
+                                entry_id,
+                                component_type,
+                                formula,
+                            )
+                            .fetch_one(&mut **db)
+                            .await?;
+                            history_changes.push(EntryComponentHistoryChange::set(
+                                component_type,
+                                formula_component_history_payload(formula),
+                            ));
+                        }
                     }
                 }
                 EntryComponentMutation::Remove {
@@ -1257,6 +1454,11 @@ impl Entry {
                 }
             }
         }
+        if changes.iter().any(|change| {
+            matches!(change, EntryComponentMutation::Set { payload, .. } if payload.is_formula())
+        }) {
+            check_formula_cycles(db, entry_id).await?;
+        }
         sqlx::query_file_scalar!("sql/entries/bump_components_version.sql", entry_id)
             .fetch_one(&mut **db)
             .await?;
@@ -1300,6 +1502,18 @@ impl EntryComponentHistoryChange {
         }
     }
 
+    /// A component upgraded by a registered schema migration.
+    pub(crate) fn migrate(
+        component_type: &str,
+        data: &Value,
+        schema_version: i32,
+        migrated_from: i32,
+    ) -> Self {
+        let mut payload = json_component_history_payload(data, schema_version);
+        payload["migratedFrom"] = migrated_from.into();
+        Self::set(component_type, payload)
+    }
+
     fn remove(component_type: &str) -> Self {
         Self {
             component_type: CompactString::new(component_type),
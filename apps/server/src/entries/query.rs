@@ -0,0 +1,557 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use super::models::{EntryMetadata, validate_component_type};
+use crate::error::{ModelError, ValidationFailed};
+
+const MAX_CONDITIONS: usize = 16;
+const MAX_COMPONENT_TYPES: usize = 32;
+const MAX_PATH_SEGMENTS: usize = 16;
+const MAX_PATH_LENGTH: usize = 256;
+const MAX_STRING_VALUE_LENGTH: usize = 256;
+const MAX_PREFIX_LENGTH: usize = 64;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, specta::Type)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ComponentConditionOp {
+    Eq,
+    Ne,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+    /// The path exists, the value is ignored
+    Exists,
+    /// The value at the path is a string starting with the value
+    StartsWith,
+}
+
+/// A condition on the data of a JSON component, such as `stats.hp < 10`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ComponentCondition {
+    pub component_type: String,
+    /// A dot-separated path into the component data, empty for the data itself.
+    /// Numeric segments index arrays.
+    #[serde(default)]
+    pub path: String,
+    pub op: ComponentConditionOp,
+    /// A string, number, boolean or null
+    #[serde(default)]
+    pub value: Value,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryFilter {
+    /// Entries with all of these tags
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Matches the start of the key or an alias, ignoring case
+    #[serde(default)]
+    pub prefix: Option<String>,
+    #[serde(default)]
+    pub has_components: Vec<String>,
+    #[serde(default)]
+    pub lacks_components: Vec<String>,
+    /// Entries matching all of the conditions
+    #[serde(default)]
+    pub conditions: Vec<ComponentCondition>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, specta::Type)]
+#[serde(
+    tag = "by",
+    rename_all = "SCREAMING_SNAKE_CASE",
+    rename_all_fields = "camelCase"
+)]
+pub enum EntrySort {
+    #[default]
+    Key,
+    DisplayName,
+    Created,
+    Modified,
+    /// By the JSON value at the path, Entries without it last
+    Component {
+        component_type: String,
+        #[serde(default)]
+        path: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryQueryPage {
+    pub entries: Vec<EntryMetadata>,
+    #[specta(type = Option<f64>)]
+    pub next_offset: Option<i64>,
+}
+
+/// The filter as query parameters. Conditions on the same component type are joined into one
+/// `jsonpath` predicate, so each component is only looked up once.
+#[derive(Debug, Default, PartialEq)]
+struct CompiledFilter {
+    tags: Vec<String>,
+    prefix_pattern: Option<String>,
+    has_components: Vec<String>,
+    lacks_components: Vec<String>,
+    predicate_types: Vec<String>,
+    predicates: Vec<String>,
+}
+
+pub(crate) fn path_segments(path: &str) -> Result<Vec<&str>, ValidationFailed> {
+    if path.len() > MAX_PATH_LENGTH {
+        return Err(ValidationFailed("The path is too long."));
+    }
+    if path.is_empty() {
+        return Ok(Vec::new());
+    }
+    let segments = path.split('.').collect::<Vec<_>>();
+    if segments.len() > MAX_PATH_SEGMENTS {
+        return Err(ValidationFailed("The path is too deep."));
+    }
+    if segments.iter().any(|segment| segment.is_empty()) {
+        return Err(ValidationFailed("The path has an empty segment."));
+    }
+    Ok(segments)
+}
+
+pub(crate) fn is_index(segment: &str) -> bool {
+    segment.len() <= 9 && segment.bytes().all(|byte| byte.is_ascii_digit())
+}
+
+fn quote(text: &str) -> String {
+    // JSON string literals are valid `jsonpath` string literals.
+    serde_json::to_string(text).expect("strings can always be serialized")
+}
+
+fn compile_path(path: &str) -> Result<String, ValidationFailed> {
+    let mut compiled = String::from("$");
+    for segment in path_segments(path)? {
+        if is_index(segment) {
+            compiled.push('[');
+            compiled.push_str(segment);
+            compiled.push(']');
+        } else {
+            compiled.push('.');
+            compiled.push_str(&quote(segment));
+        }
+    }
+    Ok(compiled)
+}
+
+fn compile_literal(value: &Value) -> Result<String, ValidationFailed> {
+    match value {
+        Value::Null => Ok("null".to_string()),
+        Value::Bool(value) => Ok(value.to_string()),
+        Value::Number(number) => Ok(number.to_string()),
+        Value::String(text) if text.chars().count() <= MAX_STRING_VALUE_LENGTH => Ok(quote(text)),
+        Value::String(_) => Err(ValidationFailed("The condition value is too long.")),
+        Value::Array(_) | Value::Object(_) => Err(ValidationFailed(
+            "The condition value must be a string, number, boolean or null.",
+        )),
+    }
+}
+
+impl ComponentCondition {
+    fn compile(&self) -> Result<String, ValidationFailed> {
+        let path = compile_path(&self.path)?;
+        let operator = match self.op {
+            ComponentConditionOp::Exists => return Ok(format!("exists({path})")),
+            ComponentConditionOp::StartsWith => {
+                if !self.value.is_string() {
+                    return Err(ValidationFailed("STARTS_WITH requires a string value."));
+                }
+                return Ok(format!(
+                    "{path} starts with {}",
+                    compile_literal(&self.value)?
+                ));
+            }
+            ComponentConditionOp::Eq => "==",
+            ComponentConditionOp::Ne => "!=",
+            ComponentConditionOp::Lt => "<",
+            ComponentConditionOp::Lte => "<=",
+            ComponentConditionOp::Gt => ">",
+            ComponentConditionOp::Gte => ">=",
+        };
+        Ok(format!(
+            "{path} {operator} {}",
+            compile_literal(&self.value)?
+        ))
+    }
+}
+
+fn escape_like(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + 1);
+    for c in text.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn check_component_types(component_types: &[String]) -> Result<(), ValidationFailed> {
+    if component_types.len() > MAX_COMPONENT_TYPES {
+        return Err(ValidationFailed("Too many component types in the filter."));
+    }
+    component_types
+        .iter()
+        .try_for_each(|component_type| validate_component_type(component_type))
+}
+
+impl EntryFilter {
+    fn compile(&self) -> Result<CompiledFilter, ValidationFailed> {
+        let tags = crate::validators::normalize_tags(self.tags.clone())?;
+        let prefix_pattern = match self.prefix.as_deref().map(str::trim) {
+            None | Some("") => None,
+            Some(prefix) if prefix.chars().count() > MAX_PREFIX_LENGTH => {
+                return Err(ValidationFailed("The prefix is too long."));
+            }
+            Some(prefix) => Some(format!("{}%", escape_like(&prefix.to_lowercase()))),
+        };
+        check_component_types(&self.has_components)?;
+        check_component_types(&self.lacks_components)?;
+        if self.conditions.len() > MAX_CONDITIONS {
+            return Err(ValidationFailed("Too many conditions in the filter."));
+        }
+        let mut predicates: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        for condition in &self.conditions {
+            validate_component_type(&condition.component_type)?;
+            predicates
+                .entry(&condition.component_type)
+                .or_default()
+                .push(condition.compile()?);
+        }
+        let (predicate_types, predicates) = predicates
+            .into_iter()
+            .map(|(component_type, predicates)| {
+                let predicate = if predicates.len() == 1 {
+                    predicates.into_iter().next().unwrap_or_default()
+                } else {
+                    predicates
+                        .iter()
+                        .map(|predicate| format!("({predicate})"))
+                        .collect::<Vec<_>>()
+                        .join(" && ")
+                };
+                (component_type.to_string(), predicate)
+            })
+            .unzip();
+        Ok(CompiledFilter {
+            tags,
+            prefix_pattern,
+            has_components: self.has_components.clone(),
+            lacks_components: self.lacks_components.clone(),
+            predicate_types,
+            predicates,
+        })
+    }
+}
+
+impl EntrySort {
+    /// The sort key, the component type and the path for the query.
+    fn compile(&self) -> Result<(&'static str, Option<&str>, Vec<String>), ValidationFailed> {
+        Ok(match self {
+            Self::Key => ("key", None, Vec::new()),
+            Self::DisplayName => ("display_name", None, Vec::new()),
+            Self::Created => ("created", None, Vec::new()),
+            Self::Modified => ("modified", None, Vec::new()),
+            Self::Component {
+                component_type,
+                path,
+            } => {
+                validate_component_type(component_type)?;
+                let path = path_segments(path)?
+                    .into_iter()
+                    .map(ToString::to_string)
+                    .collect();
+                ("component", Some(component_type), path)
+            }
+        })
+    }
+}
+
+impl EntryQueryPage {
+    /// Query the Entries of the Scopes. The caller decides which Scopes can be viewed.
+    pub async fn load<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        scope_ids: &[Uuid],
+        filter: &EntryFilter,
+        sort: &EntrySort,
+        descending: bool,
+        offset: i64,
+        limit: i64,
+    ) -> Result<EntryQueryPage, ModelError> {
+        let filter = filter.compile()?;
+        let (sort_key, sort_component_type, sort_path) = sort.compile()?;
+        let mut entries = sqlx::query_file_as!(
+            EntryMetadata,
+            "sql/entries/query.sql",
+            scope_ids,
+            &filter.tags,
+            filter.prefix_pattern,
+            &filter.has_components,
+            &filter.lacks_components,
+            &filter.predicate_types,
+            &filter.predicates,
+            sort_key,
+            sort_component_type,
+            &sort_path,
+            descending,
+            offset,
+            limit + 1,
+        )
+        .fetch_all(db)
+        .await?;
+        let next_offset = if entries.len() as i64 > limit {
+            entries.truncate(limit as usize);
+            Some(offset + limit)
+        } else {
+            None
+        };
+        Ok(EntryQueryPage {
+            entries,
+            next_offset,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn condition(path: &str, op: ComponentConditionOp, value: Value) -> ComponentCondition {
+        ComponentCondition {
+            component_type: "example/stats".to_string(),
+            path: path.to_string(),
+            op,
+            value,
+        }
+    }
+
+    #[test]
+    fn conditions_compile_to_jsonpath() {
+        use ComponentConditionOp as Op;
+
+        assert_eq!(
+            condition("hp", Op::Lt, json!(10)).compile().unwrap(),
+            r#"$."hp" < 10"#
+        );
+        assert_eq!(
+            condition("items.0.name", Op::Eq, json!("Sword \"of\" $x"))
+                .compile()
+                .unwrap(),
+            r#"$."items"[0]."name" == "Sword \"of\" $x""#
+        );
+        assert_eq!(
+            condition("", Op::Gte, json!(-1.5)).compile().unwrap(),
+            "$ >= -1.5"
+        );
+        assert_eq!(
+            condition("a.b", Op::Exists, Value::Null).compile().unwrap(),
+            r#"exists($."a"."b")"#
+        );
+        assert_eq!(
+            condition("name", Op::StartsWith, json!("gob"))
+                .compile()
+                .unwrap(),
+            r#"$."name" starts with "gob""#
+        );
+        assert!(
+            condition("name", Op::StartsWith, json!(1))
+                .compile()
+                .is_err()
+        );
+        assert!(condition("a..b", Op::Eq, json!(1)).compile().is_err());
+        assert!(condition("hp", Op::Eq, json!([1])).compile().is_err());
+        assert!(
+            condition("hp", Op::Eq, json!({ "a": 1 }))
+                .compile()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn filter_joins_conditions_by_component_type() {
+        let filter = EntryFilter {
+            tags: vec![" boss ".to_string(), "boss".to_string()],
+            prefix: Some("Gob_".to_string()),
+            conditions: vec![
+                condition("hp", ComponentConditionOp::Lt, json!(10)),
+                ComponentCondition {
+                    component_type: "example/status".to_string(),
+                    path: "poisoned".to_string(),
+                    op: ComponentConditionOp::Eq,
+                    value: json!(true),
+                },
+                condition("hp", ComponentConditionOp::Gt, json!(0)),
+            ],
+            ..EntryFilter::default()
+        };
+        assert_eq!(
+            filter.compile().unwrap(),
+            CompiledFilter {
+                tags: vec!["boss".to_string()],
+                prefix_pattern: Some(r"gob\_%".to_string()),
+                has_components: Vec::new(),
+                lacks_components: Vec::new(),
+                predicate_types: vec!["example/stats".to_string(), "example/status".to_string()],
+                predicates: vec![
+                    r#"($."hp" < 10) && ($."hp" > 0)"#.to_string(),
+                    r#"$."poisoned" == true"#.to_string(),
+                ],
+            }
+        );
+        let invalid = EntryFilter {
+            has_components: vec!["Stats".to_string()],
+            ..EntryFilter::default()
+        };
+        assert!(invalid.compile().is_err());
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn db_test_query_entries(pool: sqlx::PgPool) {
+        use crate::entries::models::{Entry, EntryComponentPayloadInput};
+        use crate::spaces::Space;
+        use crate::users::User;
+
+        let raw = Uuid::new_v4().simple().to_string();
+        let user = User::register(
+            &pool,
+            &format!("entry_query_{raw}@example.com"),
+            &format!("equery_{}", &raw[..8]),
+            "Entry Query Tester",
+            "EntryQueryPass123!",
+        )
+        .await
+        .expect("create user failed");
+        let space = Space::create(
+            &pool,
+            format!("entry_query_{}", &raw[..8]),
+            &user.id,
+            "Entry query test".to_string(),
+            None,
+            Some("d20"),
+        )
+        .await
+        .expect("create space failed");
+
+        let mut transaction = pool.begin().await.unwrap();
+        for (key, alias, tags, hp) in [
+            ("goblin", "gob", vec!["monster"], Some(4)),
+            ("orc", "brute", vec!["monster", "boss"], Some(12)),
+            ("potion", "vial", vec!["item"], None),
+        ] {
+            let components = hp
+                .map(|hp| {
+                    (
+                        "example/stats".to_string(),
+                        EntryComponentPayloadInput::json(json!({ "hp": hp })),
+                    )
+                })
+                .into_iter()
+                .collect();
+            Entry::create(
+                &mut transaction,
+                space.scope_id,
+                key.to_string(),
+                vec![alias.to_string()],
+                key.to_string(),
+                None,
+                components,
+                tags.into_iter().map(ToString::to_string).collect(),
+                None,
+            )
+            .await
+            .expect("create entry failed");
+        }
+        transaction.commit().await.unwrap();
+
+        let keys = |page: &EntryQueryPage| {
+            page.entries
+                .iter()
+                .map(|entry| entry.key.to_string())
+                .collect::<Vec<_>>()
+        };
+        let scope_ids = [space.scope_id];
+        let query = |filter: EntryFilter, sort: EntrySort, descending: bool| {
+            let pool = pool.clone();
+            async move {
+                EntryQueryPage::load(&pool, &scope_ids, &filter, &sort, descending, 0, 10)
+                    .await
+                    .expect("query failed")
+            }
+        };
+
+        let all = query(EntryFilter::default(), EntrySort::Key, false).await;
+        assert_eq!(keys(&all), ["goblin", "orc", "potion"]);
+        assert_eq!(all.next_offset, None);
+
+        let monsters = EntryFilter {
+            tags: vec!["monster".to_string()],
+            ..EntryFilter::default()
+        };
+        let by_hp = EntrySort::Component {
+            component_type: "example/stats".to_string(),
+            path: "hp".to_string(),
+        };
+        assert_eq!(
+            keys(&query(monsters.clone(), by_hp.clone(), true).await),
+            ["orc", "goblin"]
+        );
+        let hurt = EntryFilter {
+            conditions: vec![condition("hp", ComponentConditionOp::Lt, json!(10))],
+            ..monsters
+        };
+        assert_eq!(keys(&query(hurt, EntrySort::Key, false).await), ["goblin"]);
+        let by_alias = EntryFilter {
+            prefix: Some("BRU".to_string()),
+            ..EntryFilter::default()
+        };
+        assert_eq!(keys(&query(by_alias, EntrySort::Key, false).await), ["orc"]);
+        let without_stats = EntryFilter {
+            lacks_components: vec!["example/stats".to_string()],
+            ..EntryFilter::default()
+        };
+        assert_eq!(
+            keys(&query(without_stats, EntrySort::Key, false).await),
+            ["potion"]
+        );
+        assert_eq!(
+            keys(&query(EntryFilter::default(), by_hp, false).await),
+            ["goblin", "orc", "potion"]
+        );
+
+        let first_page = EntryQueryPage::load(
+            &pool,
+            &scope_ids,
+            &EntryFilter::default(),
+            &EntrySort::Key,
+            false,
+            0,
+            2,
+        )
+        .await
+        .unwrap();
+        assert_eq!(keys(&first_page), ["goblin", "orc"]);
+        assert_eq!(first_page.next_offset, Some(2));
+        let hidden = EntryQueryPage::load(
+            &pool,
+            &[],
+            &EntryFilter::default(),
+            &EntrySort::Key,
+            false,
+            0,
+            10,
+        )
+        .await
+        .unwrap();
+        assert!(hidden.entries.is_empty());
+    }
+}
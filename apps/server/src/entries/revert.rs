@@ -0,0 +1,523 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::models::{
+    Entry, EntryComponent, EntryComponentHistory, EntryComponentHistoryAction,
+    EntryComponentMutation, EntryComponentPayloadInput, EntryEffect, EntryHistory,
+    EntryHistoryAction, EntryMetadata, without_migration_note,
+};
+use crate::error::ModelError;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, specta::Type)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum EntryRevertConflictReason {
+    /// Another Entry Effect changed the component after the message
+    ChangedLater,
+    /// The component is not in the state the message left it in
+    Modified,
+    /// The Entry has been deleted
+    EntryDeleted,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryRevertConflict {
+    /// The last Entry Effect of the message which changed the component
+    pub entry_effect_id: Uuid,
+    pub entry_id: Uuid,
+    pub key: String,
+    pub component_type: String,
+    pub reason: EntryRevertConflictReason,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageEffectsRevert {
+    pub message_id: Uuid,
+    /// The Entry Effects of the message which have been reverted
+    pub reverted: Vec<Uuid>,
+    /// The Entry Effects recording the revert, one for each Scope
+    pub revert_effects: Vec<EntryEffect>,
+    pub conflicts: Vec<EntryRevertConflict>,
+}
+
+/// The Entries changed by a revert, for the space events.
+#[derive(Debug, Default)]
+pub(crate) struct RevertedEntries {
+    pub updated: Vec<EntryMetadata>,
+    /// `(scope_id, entry_id)`
+    pub deleted: Vec<(Uuid, Uuid)>,
+}
+
+struct EntryRevertPlan {
+    entry: EntryMetadata,
+    created_by_message: bool,
+    mutations: Vec<EntryComponentMutation>,
+}
+
+impl MessageEffectsRevert {
+    /// Restore the components changed by the Entry Effects of a message to their state before
+    /// the first of those effects.
+    ///
+    /// `effects` are the effects of the message, oldest first and locked. A component changed by
+    /// another Entry Effect since, or not in the state the message left it in, is a conflict.
+    /// Nothing is changed if there are conflicts, unless `skip_conflicts` is set, in which case
+    /// the conflicting components are left as they are.
+    ///
+    /// Entries created by the message are deleted once they have no components left. Renames
+    /// are not reverted, and Entries deleted by the message are reported as conflicts.
+    pub(crate) async fn apply(
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        message_id: Uuid,
+        operator_id: Uuid,
+        effects: &[EntryEffect],
+        skip_conflicts: bool,
+    ) -> Result<(MessageEffectsRevert, RevertedEntries), ModelError> {
+        let mut revert = MessageEffectsRevert {
+            message_id,
+            reverted: Vec::new(),
+            revert_effects: Vec::new(),
+            conflicts: Vec::new(),
+        };
+        let mut reverted_entries = RevertedEntries::default();
+        let Some(first_effect) = effects.first() else {
+            return Ok((revert, reverted_entries));
+        };
+        let effect_ids = effects.iter().map(|effect| effect.id).collect::<Vec<_>>();
+        let entry_history = EntryHistory::list_by_effects(&mut **db, &effect_ids).await?;
+        let component_history =
+            EntryComponentHistory::list_by_effects(&mut **db, &effect_ids).await?;
+
+        let created = entry_history
+            .iter()
+            .filter(|history| history.action == EntryHistoryAction::Create)
+            .map(|history| history.entry_id)
+            .collect::<HashSet<_>>();
+        let deleted = entry_history
+            .iter()
+            .filter(|history| history.action == EntryHistoryAction::Delete)
+            .map(|history| history.entry_id)
+            .collect::<HashSet<_>>();
+        // The history is newest first, so the first change of each component is what the
+        // message left.
+        let mut changes_by_entry: BTreeMap<Uuid, Vec<&EntryComponentHistory>> = created
+            .iter()
+            .map(|entry_id| (*entry_id, Vec::new()))
+            .collect();
+        let mut seen = HashSet::new();
+        for history in &component_history {
+            if seen.insert((history.entry_id, history.component_type.as_str())) {
+                changes_by_entry
+                    .entry(history.entry_id)
+                    .or_default()
+                    .push(history);
+            }
+        }
+
+        let mut plans = Vec::with_capacity(changes_by_entry.len());
+        for (entry_id, changes) in changes_by_entry {
+            let entry = if deleted.contains(&entry_id) {
+                None
+            } else {
+                EntryMetadata::get_by_id_for_update(db, entry_id).await?
+            };
+            let Some(entry) = entry else {
+                revert
+                    .conflicts
+                    .extend(changes.iter().map(|history| EntryRevertConflict {
+                        entry_effect_id: history.entry_effect_id,
+                        entry_id,
+                        key: history.key.clone(),
+                        component_type: history.component_type.clone(),
+                        reason: EntryRevertConflictReason::EntryDeleted,
+                    }));
+                continue;
+            };
+            let current = Entry::get_by_id_in_transaction(db, entry.scope_id, entry_id)
+                .await?
+                .ok_or(ModelError::NotFound("Entry"))?
+                .components;
+            let mut mutations = Vec::with_capacity(changes.len());
+            for history in changes {
+                let component_type = history.component_type.as_str();
+                let component = current.get(component_type);
+                let current_payload = component.map(EntryComponent::history_payload);
+                let left_payload = match history.action {
+                    EntryComponentHistoryAction::Set => history.payload.as_ref(),
+                    EntryComponentHistoryAction::Remove => None,
+                };
+                let changed_later = sqlx::query_file_scalar!(
+                    "sql/entries/component_changed_since.sql",
+                    entry_id,
+                    component_type,
+                    first_effect.created,
+                    first_effect.id,
+                    &effect_ids,
+                )
+                .fetch_one(&mut **db)
+                .await?;
+                let reason = if changed_later {
+                    Some(EntryRevertConflictReason::ChangedLater)
+                } else if current_payload.as_ref() != left_payload {
+                    Some(EntryRevertConflictReason::Modified)
+                } else {
+                    None
+                };
+                if let Some(reason) = reason {
+                    revert.conflicts.push(EntryRevertConflict {
+                        entry_effect_id: history.entry_effect_id,
+                        entry_id,
+                        key: entry.key.to_string(),
+                        component_type: history.component_type.clone(),
+                        reason,
+                    });
+                    continue;
+                }
+
+                let before = sqlx::query_file!(
+                    "sql/entries/component_state_before.sql",
+                    entry_id,
+                    component_type,
+                    first_effect.created,
+                    first_effect.id,
+                )
+                .fetch_optional(&mut **db)
+                .await?
+                .filter(|before| before.action == EntryComponentHistoryAction::Set)
+                .and_then(|before| before.payload);
+                if before.as_ref().map(without_migration_note) == current_payload {
+                    continue;
+                }
+                let expected_version = component.map(EntryComponent::version);
+                mutations.push(match before {
+                    Some(payload) => EntryComponentMutation::Set {
+                        component_type: component_type.to_string(),
+                        expected_version,
+                        payload: serde_json::from_value::<EntryComponentPayloadInput>(payload)
+                            .map_err(|err| ModelError::Unexpected(err.into()))?,
+                    },
+                    None => EntryComponentMutation::Remove {
+                        component_type: component_type.to_string(),
+                        expected_version,
+                    },
+                });
+            }
+            plans.push(EntryRevertPlan {
+                created_by_message: created.contains(&entry_id),
+                entry,
+                mutations,
+            });
+        }
+        if !revert.conflicts.is_empty() && !skip_conflicts {
+            return Ok((revert, reverted_entries));
+        }
+
+        let mut revert_effects: HashMap<Uuid, EntryEffect> = HashMap::new();
+        for effect in effects {
+            if !revert_effects.contains_key(&effect.scope_id) {
+                let revert_effect =
+                    EntryEffect::create(db, effect.space_id, effect.scope_id, operator_id).await?;
+                revert_effects.insert(effect.scope_id, revert_effect.clone());
+                revert.revert_effects.push(revert_effect);
+            }
+        }
+        for plan in plans {
+            let EntryRevertPlan {
+                entry,
+                created_by_message,
+                mutations,
+            } = plan;
+            let revert_effect_id = revert_effects
+                .get(&entry.scope_id)
+                .map(|effect| effect.id)
+                .ok_or(ModelError::NotFound("Entry Effect"))?;
+            if !mutations.is_empty() {
+                let result = Entry::apply_component_mutations(db, entry.id, &mutations).await?;
+                EntryComponentHistory::record(
+                    db,
+                    revert_effect_id,
+                    entry.id,
+                    &entry.key,
+                    &result.history_changes,
+                )
+                .await?;
+            }
+            let updated = Entry::get_by_id_in_transaction(db, entry.scope_id, entry.id)
+                .await?
+                .ok_or(ModelError::NotFound("Entry"))?;
+            if created_by_message && updated.components.is_empty() {
+                EntryHistory::record(
+                    db,
+                    revert_effect_id,
+                    entry.id,
+                    &entry.key,
+                    EntryHistoryAction::Delete,
+                )
+                .await?;
+                if !Entry::delete(db, entry.scope_id, entry.id, entry.metadata_version).await? {
+                    return Err(ModelError::Conflict("Entry".to_string()));
+                }
+                reverted_entries.deleted.push((entry.scope_id, entry.id));
+            } else if !mutations.is_empty() {
+                reverted_entries.updated.push(updated.metadata);
+            }
+        }
+        for (scope_id, revert_effect) in &revert_effects {
+            sqlx::query_file!(
+                "sql/entries/mark_effects_reverted.sql",
+                &effect_ids,
+                *scope_id,
+                revert_effect.id,
+            )
+            .execute(&mut **db)
+            .await?;
+        }
+        revert.reverted = effect_ids;
+        Ok((revert, reverted_entries))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entries::models::components_as_set_history_changes;
+    use crate::spaces::Space;
+    use crate::users::User;
+    use serde_json::{Value, json};
+
+    const HP: &str = "example/hp";
+
+    async fn create_entry(
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        space: &Space,
+        user: &User,
+        key: &str,
+        hp: i64,
+    ) -> (EntryEffect, Entry) {
+        let entry = Entry::create(
+            db,
+            space.scope_id,
+            key.to_string(),
+            Vec::new(),
+            key.to_string(),
+            None,
+            BTreeMap::from([(HP.to_string(), EntryComponentPayloadInput::json(json!(hp)))]),
+            Vec::new(),
+            None,
+        )
+        .await
+        .expect("create entry failed");
+        let effect = EntryEffect::create(db, space.id, space.scope_id, user.id)
+            .await
+            .unwrap();
+        EntryHistory::record(
+            db,
+            effect.id,
+            entry.id,
+            &entry.key,
+            EntryHistoryAction::Create,
+        )
+        .await
+        .unwrap();
+        EntryComponentHistory::record(
+            db,
+            effect.id,
+            entry.id,
+            &entry.key,
+            &components_as_set_history_changes(&entry.components),
+        )
+        .await
+        .unwrap();
+        (effect, entry)
+    }
+
+    async fn set_component(
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        space: &Space,
+        user: &User,
+        entry: &EntryMetadata,
+        component_type: &str,
+        data: Value,
+    ) -> EntryEffect {
+        let current = Entry::get_by_id_in_transaction(db, space.scope_id, entry.id)
+            .await
+            .unwrap()
+            .unwrap();
+        let result = Entry::apply_component_mutations(
+            db,
+            entry.id,
+            &[EntryComponentMutation::Set {
+                component_type: component_type.to_string(),
+                expected_version: current
+                    .components
+                    .get(component_type)
+                    .map(EntryComponent::version),
+                payload: EntryComponentPayloadInput::json(data),
+            }],
+        )
+        .await
+        .expect("set component failed");
+        let effect = EntryEffect::create(db, space.id, space.scope_id, user.id)
+            .await
+            .unwrap();
+        EntryComponentHistory::record(db, effect.id, entry.id, &entry.key, &result.history_changes)
+            .await
+            .unwrap();
+        effect
+    }
+
+    async fn components(
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        space: &Space,
+        entry_id: Uuid,
+    ) -> Option<BTreeMap<String, Value>> {
+        let entry = Entry::get_by_id_in_transaction(db, space.scope_id, entry_id)
+            .await
+            .unwrap()?;
+        Some(
+            entry
+                .components
+                .iter()
+                .map(|(component_type, component)| {
+                    (component_type.to_string(), component.json_data())
+                })
+                .collect(),
+        )
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn db_test_revert_message_effects(pool: sqlx::PgPool) {
+        let raw = Uuid::new_v4().simple().to_string();
+        let user = User::register(
+            &pool,
+            &format!("revert_{raw}@example.com"),
+            &format!("revert_{}", &raw[..8]),
+            "Revert Tester",
+            "RevertPass123!",
+        )
+        .await
+        .expect("create user failed");
+        let space = Space::create(
+            &pool,
+            format!("revert_{}", &raw[..8]),
+            &user.id,
+            "Revert test".to_string(),
+            None,
+            Some("d20"),
+        )
+        .await
+        .expect("create space failed");
+        let message_id = Uuid::now_v7();
+
+        let mut transaction = pool.begin().await.unwrap();
+        let (_, goblin) = create_entry(&mut transaction, &space, &user, "goblin", 10).await;
+        let (_, orc) = create_entry(&mut transaction, &space, &user, "orc", 5).await;
+        transaction.commit().await.unwrap();
+
+        // The message hurts and poisons the goblin, hurts the orc and drops some loot.
+        let mut transaction = pool.begin().await.unwrap();
+        let hurt = set_component(&mut transaction, &space, &user, &goblin, HP, json!(7)).await;
+        let poisoned = set_component(
+            &mut transaction,
+            &space,
+            &user,
+            &goblin,
+            "example/poisoned",
+            json!(true),
+        )
+        .await;
+        let hurt_orc = set_component(&mut transaction, &space, &user, &orc, HP, json!(3)).await;
+        let (dropped, loot) = create_entry(&mut transaction, &space, &user, "loot", 1).await;
+        transaction.commit().await.unwrap();
+        let effects = [hurt, poisoned, hurt_orc, dropped];
+
+        // Later, the orc is healed and the goblin gets a mood.
+        let mut transaction = pool.begin().await.unwrap();
+        set_component(&mut transaction, &space, &user, &orc, HP, json!(4)).await;
+        set_component(
+            &mut transaction,
+            &space,
+            &user,
+            &goblin,
+            "example/mood",
+            json!("angry"),
+        )
+        .await;
+        transaction.commit().await.unwrap();
+
+        let mut transaction = pool.begin().await.unwrap();
+        let (revert, reverted) =
+            MessageEffectsRevert::apply(&mut transaction, message_id, user.id, &effects, false)
+                .await
+                .expect("revert failed");
+        assert!(revert.reverted.is_empty());
+        assert!(revert.revert_effects.is_empty());
+        assert_eq!(
+            revert.conflicts,
+            vec![EntryRevertConflict {
+                entry_effect_id: effects[2].id,
+                entry_id: orc.id,
+                key: "orc".to_string(),
+                component_type: HP.to_string(),
+                reason: EntryRevertConflictReason::ChangedLater,
+            }]
+        );
+        assert!(reverted.updated.is_empty() && reverted.deleted.is_empty());
+        drop(transaction);
+
+        let mut transaction = pool.begin().await.unwrap();
+        let (revert, reverted) =
+            MessageEffectsRevert::apply(&mut transaction, message_id, user.id, &effects, true)
+                .await
+                .expect("revert failed");
+        assert_eq!(
+            revert.reverted,
+            effects.iter().map(|effect| effect.id).collect::<Vec<_>>()
+        );
+        assert_eq!(revert.revert_effects.len(), 1);
+        assert_eq!(revert.conflicts.len(), 1);
+        assert_eq!(
+            reverted
+                .updated
+                .iter()
+                .map(|entry| entry.id)
+                .collect::<Vec<_>>(),
+            vec![goblin.id]
+        );
+        assert_eq!(reverted.deleted, vec![(space.scope_id, loot.id)]);
+        assert_eq!(
+            components(&mut transaction, &space, goblin.id).await,
+            Some(BTreeMap::from([
+                (HP.to_string(), json!(10)),
+                ("example/mood".to_string(), json!("angry")),
+            ]))
+        );
+        assert_eq!(
+            components(&mut transaction, &space, orc.id).await,
+            Some(BTreeMap::from([(HP.to_string(), json!(4))]))
+        );
+        assert_eq!(components(&mut transaction, &space, loot.id).await, None);
+
+        let revert_effect_id = revert.revert_effects[0].id;
+        let marked = EntryEffect::list_by_ids(&mut *transaction, space.id, &revert.reverted)
+            .await
+            .unwrap();
+        assert!(
+            marked
+                .iter()
+                .all(|effect| effect.reverted_by == Some(revert_effect_id))
+        );
+        let history =
+            EntryComponentHistory::list_by_effects(&mut *transaction, &[revert_effect_id])
+                .await
+                .unwrap();
+        assert_eq!(history.len(), 3);
+        let entry_history = EntryHistory::list_by_effects(&mut *transaction, &[revert_effect_id])
+            .await
+            .unwrap();
+        assert_eq!(entry_history.len(), 1);
+        assert_eq!(entry_history[0].action, EntryHistoryAction::Delete);
+    }
+}
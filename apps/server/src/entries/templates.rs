@@ -0,0 +1,495 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use super::models::{Entry, EntryComponentPayloadInput, validate_components};
+use crate::characters::normalize_ident;
+use crate::error::{ModelError, ValidationFailed};
+
+const NUMBER_PLACEHOLDER: &str = "{n}";
+pub const MAX_INSTANCES: u32 = 50;
+/// How far instantiation looks for free numbers before giving up.
+const MAX_INSTANCE_NUMBER: u32 = 1000;
+const MAX_TEMPLATE_NAME_LENGTH: usize = 64;
+
+/// A named bundle of Entry defaults, which can be exported and imported as JSON.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryTemplateInput {
+    pub name: String,
+    /// The key of the instances, where `{n}` is replaced with the number of the instance.
+    /// The number is appended as `-{n}` if the pattern has no `{n}`.
+    pub key_pattern: String,
+    /// Replaces `{n}` like the key pattern. The instances are named after their keys if empty.
+    #[serde(default)]
+    pub display_name: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Dropped on import if the note is not in the space.
+    #[serde(default)]
+    pub reference_note_id: Option<Uuid>,
+    #[serde(default)]
+    pub components: BTreeMap<String, EntryComponentPayloadInput>,
+}
+
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryTemplate {
+    pub id: Uuid,
+    pub space_id: Uuid,
+    #[serde(flatten)]
+    pub template: EntryTemplateInput,
+    pub version: Uuid,
+    #[specta(type = String)]
+    #[serde(with = "time::serde::rfc3339")]
+    pub created: OffsetDateTime,
+    #[specta(type = String)]
+    #[serde(with = "time::serde::rfc3339")]
+    pub modified: OffsetDateTime,
+}
+
+/// The templates of a space, in the format they are imported.
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryTemplateExport {
+    pub templates: Vec<EntryTemplateInput>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct EntryTemplateRow {
+    id: Uuid,
+    space_id: Uuid,
+    name: String,
+    key_pattern: String,
+    display_name: String,
+    tags: Vec<String>,
+    reference_note_id: Option<Uuid>,
+    components: Value,
+    version: Uuid,
+    created: OffsetDateTime,
+    modified: OffsetDateTime,
+}
+
+impl TryFrom<EntryTemplateRow> for EntryTemplate {
+    type Error = ModelError;
+
+    fn try_from(row: EntryTemplateRow) -> Result<Self, Self::Error> {
+        let components = serde_json::from_value(row.components).map_err(|err| {
+            ModelError::Unexpected(anyhow::anyhow!(
+                "Failed to decode the components of Entry template {}: {err}",
+                row.id
+            ))
+        })?;
+        Ok(EntryTemplate {
+            id: row.id,
+            space_id: row.space_id,
+            template: EntryTemplateInput {
+                name: row.name,
+                key_pattern: row.key_pattern,
+                display_name: row.display_name,
+                tags: row.tags,
+                reference_note_id: row.reference_note_id,
+                components,
+            },
+            version: row.version,
+            created: row.created,
+            modified: row.modified,
+        })
+    }
+}
+
+fn replace_number(pattern: &str, number: u32) -> String {
+    pattern.replace(NUMBER_PLACEHOLDER, &number.to_string())
+}
+
+impl EntryTemplateInput {
+    fn normalize(self) -> Result<Self, ValidationFailed> {
+        let name = self.name.trim().to_string();
+        if name.is_empty() {
+            return Err(ValidationFailed("Template name can not be empty."));
+        }
+        if name.chars().count() > MAX_TEMPLATE_NAME_LENGTH {
+            return Err(ValidationFailed(
+                "Template name shall not be more than 64 characters.",
+            ));
+        }
+        let key_pattern = self.key_pattern.trim().to_string();
+        if key_pattern.is_empty() {
+            return Err(ValidationFailed("Key pattern can not be empty."));
+        }
+        let display_name = self.display_name.trim().to_string();
+        let template = EntryTemplateInput {
+            name,
+            key_pattern,
+            display_name,
+            tags: crate::validators::normalize_tags(self.tags)?,
+            reference_note_id: self.reference_note_id,
+            components: self.components,
+        };
+        // The largest number gives the longest key and name.
+        normalize_ident(&template.instance_key(MAX_INSTANCE_NUMBER))?;
+        if !template.display_name.is_empty() {
+            crate::validators::DISPLAY_NAME
+                .run(&replace_number(&template.display_name, MAX_INSTANCE_NUMBER))?;
+        }
+        validate_components(&template.components)?;
+        if template
+            .components
+            .values()
+            .any(|payload| matches!(payload, EntryComponentPayloadInput::Asset { .. }))
+        {
+            // Assets belong to a space, and the templates can be imported into any space.
+            return Err(ValidationFailed(
+                "An Entry template can not have Asset components.",
+            ));
+        }
+        Ok(template)
+    }
+
+    pub fn instance_key(&self, number: u32) -> String {
+        if self.key_pattern.contains(NUMBER_PLACEHOLDER) {
+            replace_number(&self.key_pattern, number)
+        } else {
+            format!("{}-{number}", self.key_pattern)
+        }
+    }
+
+    fn instance_display_name(&self, key: &str, number: u32) -> String {
+        if self.display_name.is_empty() {
+            key.to_string()
+        } else {
+            replace_number(&self.display_name, number)
+        }
+    }
+
+    fn components_value(&self) -> Result<Value, ModelError> {
+        serde_json::to_value(&self.components).map_err(|err| ModelError::Unexpected(err.into()))
+    }
+}
+
+impl EntryTemplate {
+    pub async fn list_by_space<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+    ) -> Result<Vec<Self>, ModelError> {
+        sqlx::query_file_as!(EntryTemplateRow, "sql/entries/list_templates.sql", space_id)
+            .fetch_all(db)
+            .await?
+            .into_iter()
+            .map(TryFrom::try_from)
+            .collect()
+    }
+
+    pub async fn get_by_id<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+        id: Uuid,
+    ) -> Result<Option<Self>, ModelError> {
+        sqlx::query_file_as!(
+            EntryTemplateRow,
+            "sql/entries/get_template.sql",
+            space_id,
+            id
+        )
+        .fetch_optional(db)
+        .await?
+        .map(TryFrom::try_from)
+        .transpose()
+    }
+
+    pub async fn create<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+        template: EntryTemplateInput,
+    ) -> Result<Self, ModelError> {
+        let template = template.normalize()?;
+        sqlx::query_file_as!(
+            EntryTemplateRow,
+            "sql/entries/insert_template.sql",
+            space_id,
+            template.name,
+            template.key_pattern,
+            template.display_name,
+            &template.tags,
+            template.reference_note_id,
+            template.components_value()?,
+        )
+        .fetch_one(db)
+        .await?
+        .try_into()
+    }
+
+    /// Returns `None` if the template has been changed since `expected_version`.
+    pub async fn update<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+        id: Uuid,
+        expected_version: Uuid,
+        template: EntryTemplateInput,
+    ) -> Result<Option<Self>, ModelError> {
+        let template = template.normalize()?;
+        sqlx::query_file_as!(
+            EntryTemplateRow,
+            "sql/entries/update_template.sql",
+            space_id,
+            id,
+            expected_version,
+            template.name,
+            template.key_pattern,
+            template.display_name,
+            &template.tags,
+            template.reference_note_id,
+            template.components_value()?,
+        )
+        .fetch_optional(db)
+        .await?
+        .map(TryFrom::try_from)
+        .transpose()
+    }
+
+    pub async fn delete<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+        id: Uuid,
+    ) -> Result<bool, ModelError> {
+        let result = sqlx::query_file!("sql/entries/delete_template.sql", space_id, id)
+            .execute(db)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Import templates by name. A template with the name of an existing one is a conflict,
+    /// unless `replace_existing` is set.
+    pub async fn import(
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        space_id: Uuid,
+        templates: Vec<EntryTemplateInput>,
+        replace_existing: bool,
+    ) -> Result<Vec<Self>, ModelError> {
+        let mut imported = Vec::with_capacity(templates.len());
+        for template in templates {
+            let template = template.normalize()?;
+            let row = if replace_existing {
+                sqlx::query_file_as!(
+                    EntryTemplateRow,
+                    "sql/entries/upsert_template.sql",
+                    space_id,
+                    template.name,
+                    template.key_pattern,
+                    template.display_name,
+                    &template.tags,
+                    template.reference_note_id,
+                    template.components_value()?,
+                )
+                .fetch_one(&mut **db)
+                .await?
+            } else {
+                sqlx::query_file_as!(
+                    EntryTemplateRow,
+                    "sql/entries/insert_template.sql",
+                    space_id,
+                    template.name,
+                    template.key_pattern,
+                    template.display_name,
+                    &template.tags,
+                    template.reference_note_id,
+                    template.components_value()?,
+                )
+                .fetch_one(&mut **db)
+                .await?
+            };
+            imported.push(row.try_into()?);
+        }
+        Ok(imported)
+    }
+
+    /// Create `count` Entries from the template, numbered from the lowest numbers whose keys
+    /// are not taken in the Scope.
+    pub async fn instantiate(
+        &self,
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        scope_id: Uuid,
+        count: u32,
+        before_entry_id: Option<Uuid>,
+    ) -> Result<Vec<Entry>, ModelError> {
+        if count == 0 || count > MAX_INSTANCES {
+            return Err(
+                ValidationFailed("An Entry template can make 1 to 50 Entries at once.").into(),
+            );
+        }
+        let template = &self.template;
+        let mut entries = Vec::with_capacity(count as usize);
+        let mut number = 0;
+        while entries.len() < count as usize {
+            number += 1;
+            if number > MAX_INSTANCE_NUMBER {
+                return Err(ValidationFailed(
+                    "There are no more free keys for the Entry template.",
+                )
+                .into());
+            }
+            let key = template.instance_key(number);
+            if Entry::exists_identifier(&mut **db, scope_id, Some(&key), &[]).await? {
+                continue;
+            }
+            let entry = Entry::create(
+                db,
+                scope_id,
+                key.clone(),
+                Vec::new(),
+                template.instance_display_name(&key, number),
+                template.reference_note_id,
+                template.components.clone(),
+                template.tags.clone(),
+                before_entry_id,
+            )
+            .await?;
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn template(key_pattern: &str, display_name: &str) -> EntryTemplateInput {
+        EntryTemplateInput {
+            name: " Goblin ".to_string(),
+            key_pattern: key_pattern.to_string(),
+            display_name: display_name.to_string(),
+            tags: vec!["monster".to_string(), " monster ".to_string()],
+            reference_note_id: None,
+            components: BTreeMap::from([(
+                "example/stats".to_string(),
+                EntryComponentPayloadInput::json(json!({ "hp": 7 })),
+            )]),
+        }
+    }
+
+    #[test]
+    fn instances_are_numbered() {
+        let goblin = template("goblin", "").normalize().unwrap();
+        assert_eq!(goblin.name, "Goblin");
+        assert_eq!(goblin.tags.len(), 1);
+        assert_eq!(goblin.instance_key(2), "goblin-2");
+        assert_eq!(goblin.instance_display_name("goblin-2", 2), "goblin-2");
+
+        let archer = template("archer{n}", "Archer #{n}").normalize().unwrap();
+        assert_eq!(archer.instance_key(12), "archer12");
+        assert_eq!(archer.instance_display_name("archer12", 12), "Archer #12");
+    }
+
+    #[test]
+    fn invalid_templates_are_rejected() {
+        assert!(template("", "").normalize().is_err());
+        assert!(template("gob lin/", "").normalize().is_err());
+        assert!(template(&"g".repeat(60), "").normalize().is_err());
+        let mut with_asset = template("goblin", "");
+        with_asset.components.insert(
+            "core/portrait".to_string(),
+            EntryComponentPayloadInput::Asset {
+                asset_id: Uuid::nil(),
+            },
+        );
+        assert!(with_asset.normalize().is_err());
+
+        let exported = serde_json::to_value(EntryTemplateExport {
+            templates: vec![template("goblin", "")],
+        })
+        .unwrap();
+        let imported: EntryTemplateExport = serde_json::from_value(exported).unwrap();
+        assert_eq!(imported.templates, [template("goblin", "")]);
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn db_test_instantiate_skips_taken_keys(pool: sqlx::PgPool) {
+        use crate::spaces::Space;
+        use crate::users::User;
+
+        let raw = Uuid::new_v4().simple().to_string();
+        let user = User::register(
+            &pool,
+            &format!("entry_template_{raw}@example.com"),
+            &format!("etemplate_{}", &raw[..8]),
+            "Entry Template Tester",
+            "EntryTemplatePass123!",
+        )
+        .await
+        .expect("create user failed");
+        let space = Space::create(
+            &pool,
+            format!("entry_template_{}", &raw[..8]),
+            &user.id,
+            "Entry template test".to_string(),
+            None,
+            Some("d20"),
+        )
+        .await
+        .expect("create space failed");
+
+        let goblin = EntryTemplate::create(&pool, space.id, template("goblin", "Goblin {n}"))
+            .await
+            .expect("create template failed");
+        assert!(
+            EntryTemplate::create(&pool, space.id, template("orc", ""))
+                .await
+                .is_err(),
+            "template names are unique in a space"
+        );
+
+        let mut transaction = pool.begin().await.unwrap();
+        Entry::create(
+            &mut transaction,
+            space.scope_id,
+            "goblin-2".to_string(),
+            Vec::new(),
+            "Goblin".to_string(),
+            None,
+            BTreeMap::new(),
+            Vec::new(),
+            None,
+        )
+        .await
+        .expect("create entry failed");
+        let entries = goblin
+            .instantiate(&mut transaction, space.scope_id, 3, None)
+            .await
+            .expect("instantiate failed");
+        let keys: Vec<_> = entries.iter().map(|entry| entry.key.as_str()).collect();
+        assert_eq!(keys, ["goblin-1", "goblin-3", "goblin-4"]);
+        assert_eq!(entries[1].display_name, "Goblin 3");
+        assert_eq!(entries[0].tags, ["monster"]);
+        assert!(entries[0].components.contains_key("example/stats"));
+        assert!(
+            goblin
+                .instantiate(&mut transaction, space.scope_id, MAX_INSTANCES + 1, None)
+                .await
+                .is_err()
+        );
+        transaction.commit().await.unwrap();
+
+        let exported = EntryTemplate::list_by_space(&pool, space.id).await.unwrap();
+        let mut transaction = pool.begin().await.unwrap();
+        let imported = EntryTemplate::import(
+            &mut transaction,
+            space.id,
+            exported
+                .into_iter()
+                .map(|template| template.template)
+                .collect(),
+            true,
+        )
+        .await
+        .expect("import failed");
+        transaction.commit().await.unwrap();
+        assert_eq!(imported.len(), 1);
+        assert_eq!(imported[0].id, goblin.id);
+        assert_ne!(imported[0].version, goblin.version);
+    }
+}
@@ -0,0 +1,474 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use super::models::{EntryComponentHistoryAction, EntryHistoryAction, without_migration_note};
+
+/// An Entry as recorded in the history at some point.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryState {
+    pub entry_id: Uuid,
+    pub key: String,
+    /// The history payloads of the components, by component type
+    pub components: BTreeMap<String, Value>,
+}
+
+/// The Entries of a Scope reconstructed from the history.
+///
+/// Only recorded changes are replayed. Changes made without history are not included, and the
+/// Entries only carry their keys and components.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ScopeState {
+    pub scope_id: Uuid,
+    #[specta(type = String)]
+    #[serde(with = "time::serde::rfc3339")]
+    pub at: OffsetDateTime,
+    /// Sorted by key
+    pub entries: Vec<EntryState>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, specta::Type)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum EntryStateChange {
+    Created,
+    Changed,
+    Deleted,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryComponentDiff {
+    pub component_type: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryStateDiff {
+    pub entry_id: Uuid,
+    /// The key at the end, or before the Entry was deleted
+    pub key: String,
+    /// The key at the start, if the Entry has been renamed
+    pub previous_key: Option<String>,
+    pub change: EntryStateChange,
+    pub components: Vec<EntryComponentDiff>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ScopeStateDiff {
+    pub scope_id: Uuid,
+    #[specta(type = String)]
+    #[serde(with = "time::serde::rfc3339")]
+    pub from: OffsetDateTime,
+    #[specta(type = String)]
+    #[serde(with = "time::serde::rfc3339")]
+    pub to: OffsetDateTime,
+    /// The Entries which changed, sorted by key
+    pub entries: Vec<EntryStateDiff>,
+}
+
+impl ScopeState {
+    /// Replay the history of the Scope up to and including `at`.
+    pub async fn load(
+        db: &sqlx::PgPool,
+        scope_id: Uuid,
+        at: OffsetDateTime,
+    ) -> Result<ScopeState, sqlx::Error> {
+        let entry_rows = sqlx::query_file!("sql/entries/entry_history_at.sql", scope_id, at)
+            .fetch_all(db)
+            .await?;
+        let component_rows =
+            sqlx::query_file!("sql/entries/component_history_at.sql", scope_id, at)
+                .fetch_all(db)
+                .await?;
+
+        // `None` for the Entries which had been deleted.
+        let mut states: HashMap<Uuid, Option<EntryState>> = entry_rows
+            .into_iter()
+            .map(|row| {
+                let state = (row.action != EntryHistoryAction::Delete).then(|| EntryState {
+                    entry_id: row.entry_id,
+                    key: row.key,
+                    components: BTreeMap::new(),
+                });
+                (row.entry_id, state)
+            })
+            .collect();
+        for row in component_rows {
+            let Some(payload) = row
+                .payload
+                .filter(|_| row.action == EntryComponentHistoryAction::Set)
+            else {
+                continue;
+            };
+            // Entries without entry history still count if their components were recorded.
+            let state = states.entry(row.entry_id).or_insert_with(|| {
+                Some(EntryState {
+                    entry_id: row.entry_id,
+                    key: row.key,
+                    components: BTreeMap::new(),
+                })
+            });
+            if let Some(state) = state {
+                state
+                    .components
+                    .insert(row.component_type, without_migration_note(&payload));
+            }
+        }
+        let mut entries = states.into_values().flatten().collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.key.cmp(&b.key).then(a.entry_id.cmp(&b.entry_id)));
+        Ok(ScopeState {
+            scope_id,
+            at,
+            entries,
+        })
+    }
+}
+
+impl ScopeStateDiff {
+    pub fn between(from: &ScopeState, to: &ScopeState) -> ScopeStateDiff {
+        let before = from
+            .entries
+            .iter()
+            .map(|entry| (entry.entry_id, entry))
+            .collect::<HashMap<_, _>>();
+        let after = to
+            .entries
+            .iter()
+            .map(|entry| (entry.entry_id, entry))
+            .collect::<HashMap<_, _>>();
+        let empty = BTreeMap::new();
+        let mut entries = Vec::new();
+        for entry in &to.entries {
+            let Some(previous) = before.get(&entry.entry_id) else {
+                entries.push(EntryStateDiff {
+                    entry_id: entry.entry_id,
+                    key: entry.key.clone(),
+                    previous_key: None,
+                    change: EntryStateChange::Created,
+                    components: diff_components(&empty, &entry.components),
+                });
+                continue;
+            };
+            let components = diff_components(&previous.components, &entry.components);
+            let renamed = previous.key != entry.key;
+            if components.is_empty() && !renamed {
+                continue;
+            }
+            entries.push(EntryStateDiff {
+                entry_id: entry.entry_id,
+                key: entry.key.clone(),
+                previous_key: renamed.then(|| previous.key.clone()),
+                change: EntryStateChange::Changed,
+                components,
+            });
+        }
+        for entry in &from.entries {
+            if !after.contains_key(&entry.entry_id) {
+                entries.push(EntryStateDiff {
+                    entry_id: entry.entry_id,
+                    key: entry.key.clone(),
+                    previous_key: None,
+                    change: EntryStateChange::Deleted,
+                    components: diff_components(&entry.components, &empty),
+                });
+            }
+        }
+        entries.sort_by(|a, b| a.key.cmp(&b.key).then(a.entry_id.cmp(&b.entry_id)));
+        ScopeStateDiff {
+            scope_id: to.scope_id,
+            from: from.at,
+            to: to.at,
+            entries,
+        }
+    }
+}
+
+fn diff_components(
+    before: &BTreeMap<String, Value>,
+    after: &BTreeMap<String, Value>,
+) -> Vec<EntryComponentDiff> {
+    before
+        .keys()
+        .chain(after.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter_map(|component_type| {
+            let before = before.get(component_type);
+            let after = after.get(component_type);
+            (before != after).then(|| EntryComponentDiff {
+                component_type: component_type.clone(),
+                before: before.cloned(),
+                after: after.cloned(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entry(entry_id: Uuid, key: &str, components: &[(&str, Value)]) -> EntryState {
+        EntryState {
+            entry_id,
+            key: key.to_string(),
+            components: components
+                .iter()
+                .map(|(component_type, payload)| (component_type.to_string(), payload.clone()))
+                .collect(),
+        }
+    }
+
+    fn state(at: OffsetDateTime, entries: Vec<EntryState>) -> ScopeState {
+        ScopeState {
+            scope_id: Uuid::nil(),
+            at,
+            entries,
+        }
+    }
+
+    #[test]
+    fn migration_note_is_ignored() {
+        let payload = json!({ "payloadType": "JSON", "schemaVersion": 2, "data": 1 });
+        let mut migrated = payload.clone();
+        migrated["migratedFrom"] = 1.into();
+        assert_eq!(without_migration_note(&migrated), payload);
+        assert_eq!(without_migration_note(&payload), payload);
+    }
+
+    #[test]
+    fn diff_reports_created_changed_and_deleted_entries() {
+        let start = OffsetDateTime::UNIX_EPOCH;
+        let end = start + time::Duration::hours(3);
+        let goblin = Uuid::now_v7();
+        let orc = Uuid::now_v7();
+        let troll = Uuid::now_v7();
+        let wolf = Uuid::now_v7();
+        let from = state(
+            start,
+            vec![
+                entry(goblin, "goblin", &[("example/hp", json!(10))]),
+                entry(orc, "orc", &[("example/hp", json!(5))]),
+                entry(wolf, "wolf", &[("example/hp", json!(4))]),
+            ],
+        );
+        let to = state(
+            end,
+            vec![
+                entry(
+                    goblin,
+                    "goblin",
+                    &[("example/hp", json!(7)), ("example/poisoned", json!(true))],
+                ),
+                entry(orc, "orc_chief", &[("example/hp", json!(5))]),
+                entry(troll, "troll", &[("example/hp", json!(20))]),
+                entry(wolf, "wolf", &[("example/hp", json!(4))]),
+            ],
+        );
+
+        let diff = ScopeStateDiff::between(&from, &to);
+        assert_eq!(diff.from, start);
+        assert_eq!(diff.to, end);
+        assert_eq!(
+            diff.entries,
+            vec![
+                EntryStateDiff {
+                    entry_id: goblin,
+                    key: "goblin".to_string(),
+                    previous_key: None,
+                    change: EntryStateChange::Changed,
+                    components: vec![
+                        EntryComponentDiff {
+                            component_type: "example/hp".to_string(),
+                            before: Some(json!(10)),
+                            after: Some(json!(7)),
+                        },
+                        EntryComponentDiff {
+                            component_type: "example/poisoned".to_string(),
+                            before: None,
+                            after: Some(json!(true)),
+                        },
+                    ],
+                },
+                EntryStateDiff {
+                    entry_id: orc,
+                    key: "orc_chief".to_string(),
+                    previous_key: Some("orc".to_string()),
+                    change: EntryStateChange::Changed,
+                    components: Vec::new(),
+                },
+                EntryStateDiff {
+                    entry_id: troll,
+                    key: "troll".to_string(),
+                    previous_key: None,
+                    change: EntryStateChange::Created,
+                    components: vec![EntryComponentDiff {
+                        component_type: "example/hp".to_string(),
+                        before: None,
+                        after: Some(json!(20)),
+                    }],
+                },
+            ]
+        );
+
+        let reversed = ScopeStateDiff::between(&to, &from);
+        let troll_diff = reversed
+            .entries
+            .iter()
+            .find(|entry| entry.entry_id == troll)
+            .unwrap();
+        assert_eq!(troll_diff.change, EntryStateChange::Deleted);
+        assert_eq!(troll_diff.components[0].after, None);
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn db_test_scope_state_replays_history(pool: sqlx::PgPool) {
+        use crate::entries::models::{
+            Entry, EntryComponentHistory, EntryComponentMutation, EntryComponentPayloadInput,
+            EntryEffect, EntryHistory, components_as_set_history_changes,
+        };
+        use crate::spaces::Space;
+        use crate::users::User;
+
+        let raw = Uuid::new_v4().simple().to_string();
+        let user = User::register(
+            &pool,
+            &format!("timeline_{raw}@example.com"),
+            &format!("timeline_{}", &raw[..8]),
+            "Timeline Tester",
+            "TimelinePass123!",
+        )
+        .await
+        .expect("create user failed");
+        let space = Space::create(
+            &pool,
+            format!("timeline_{}", &raw[..8]),
+            &user.id,
+            "Timeline test".to_string(),
+            None,
+            Some("d20"),
+        )
+        .await
+        .expect("create space failed");
+
+        let mut transaction = pool.begin().await.unwrap();
+        let entry = Entry::create(
+            &mut transaction,
+            space.scope_id,
+            "goblin".to_string(),
+            Vec::new(),
+            "Goblin".to_string(),
+            None,
+            BTreeMap::from([(
+                "example/hp".to_string(),
+                EntryComponentPayloadInput::json(json!(10)),
+            )]),
+            Vec::new(),
+            None,
+        )
+        .await
+        .expect("create entry failed");
+        let created = EntryEffect::create(&mut transaction, space.id, space.scope_id, user.id)
+            .await
+            .unwrap();
+        EntryHistory::record(
+            &mut transaction,
+            created.id,
+            entry.id,
+            &entry.key,
+            EntryHistoryAction::Create,
+        )
+        .await
+        .unwrap();
+        EntryComponentHistory::record(
+            &mut transaction,
+            created.id,
+            entry.id,
+            &entry.key,
+            &components_as_set_history_changes(&entry.components),
+        )
+        .await
+        .unwrap();
+        transaction.commit().await.unwrap();
+
+        let mut transaction = pool.begin().await.unwrap();
+        let result = Entry::apply_component_mutations(
+            &mut transaction,
+            entry.id,
+            &[EntryComponentMutation::Set {
+                component_type: "example/hp".to_string(),
+                expected_version: Some(entry.components["example/hp"].version()),
+                payload: EntryComponentPayloadInput::json(json!(4)),
+            }],
+        )
+        .await
+        .unwrap();
+        let hurt = EntryEffect::create(&mut transaction, space.id, space.scope_id, user.id)
+            .await
+            .unwrap();
+        EntryComponentHistory::record(
+            &mut transaction,
+            hurt.id,
+            entry.id,
+            &entry.key,
+            &result.history_changes,
+        )
+        .await
+        .unwrap();
+        transaction.commit().await.unwrap();
+
+        let mut transaction = pool.begin().await.unwrap();
+        let deleted = EntryEffect::create(&mut transaction, space.id, space.scope_id, user.id)
+            .await
+            .unwrap();
+        EntryHistory::record(
+            &mut transaction,
+            deleted.id,
+            entry.id,
+            &entry.key,
+            EntryHistoryAction::Delete,
+        )
+        .await
+        .unwrap();
+        transaction.commit().await.unwrap();
+
+        let before = ScopeState::load(
+            &pool,
+            space.scope_id,
+            created.created - time::Duration::SECOND,
+        )
+        .await
+        .unwrap();
+        assert!(before.entries.is_empty());
+        let at_created = ScopeState::load(&pool, space.scope_id, created.created)
+            .await
+            .unwrap();
+        let at_hurt = ScopeState::load(&pool, space.scope_id, hurt.created)
+            .await
+            .unwrap();
+        let at_deleted = ScopeState::load(&pool, space.scope_id, deleted.created)
+            .await
+            .unwrap();
+        let hp = |state: &ScopeState| state.entries[0].components["example/hp"]["data"].clone();
+        assert_eq!(at_created.entries.len(), 1);
+        assert_eq!(at_created.entries[0].key, "goblin");
+        assert_eq!(hp(&at_created), json!(10));
+        assert_eq!(hp(&at_hurt), json!(4));
+        assert!(at_deleted.entries.is_empty());
+
+        let diff = ScopeStateDiff::between(&at_created, &at_hurt);
+        assert_eq!(diff.entries.len(), 1);
+        assert_eq!(diff.entries[0].change, EntryStateChange::Changed);
+        let diff = ScopeStateDiff::between(&at_hurt, &at_deleted);
+        assert_eq!(diff.entries[0].change, EntryStateChange::Deleted);
+    }
+}
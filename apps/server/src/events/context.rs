@@ -1067,6 +1067,14 @@ pub fn mailbox_action_queue_depth() -> usize {
         .sum()
 }
 
+pub fn mailbox_queue_depth(id: &Uuid) -> Option<usize> {
+    store()
+        .mailboxes
+        .pin()
+        .get(id)
+        .map(|state| state.manager.sender.max_capacity() - state.manager.sender.capacity())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -9,6 +9,7 @@ use crate::events::models::StatusKind;
 use crate::events::token::SessionError;
 use crate::events::types::{ClientEvent, ConnectionError, GetFromStateError};
 use crate::interface::{Response, err_response, missing, ok_response, parse_query};
+use crate::notes::collab::{self, NoteSubscriptions};
 use crate::session::{AuthenticateFail, Session};
 use crate::spaces::{Space, SpaceMember};
 use crate::utils::timestamp;
@@ -191,6 +192,7 @@ async fn push_updates(
     mailbox: Uuid,
     outgoing: &mut Sender,
     mut error_receiver: tokio::sync::mpsc::Receiver<ConnectionError>,
+    mut note_receiver: tokio::sync::mpsc::Receiver<Utf8Bytes>,
     after: Option<i64>,
     seq: Option<Seq>,
     node: Option<u16>,
@@ -293,6 +295,10 @@ async fn push_updates(
                     break Ok(());
                 }
             }
+            Some(message) = note_receiver.recv() => {
+                events_sent_counter.increment(1);
+                outgoing.send(WsMessage::Text(message)).await?;
+            }
             message = mailbox_rx.recv() => {
                 let pending = mailbox_rx.len();
                 if pending > 0 {
@@ -331,6 +337,7 @@ async fn handle_client_event(
     mailbox: Uuid,
     error_sender: tokio::sync::mpsc::Sender<ConnectionError>,
     session: Option<Session>,
+    notes: &NoteSubscriptions,
     message: Utf8Bytes,
 ) {
     let deserialize_result = sonic_rs::from_str::<ClientEvent>(&message);
@@ -350,7 +357,10 @@ async fn handle_client_event(
                     manager.touch_activity().ok();
                 }
             }
-            ClientEvent::Status { .. } => {
+            ClientEvent::Status { .. }
+            | ClientEvent::NoteJoin { .. }
+            | ClientEvent::NoteLeave { .. }
+            | ClientEvent::NoteEdit { .. } => {
                 // Do nothing
             }
         }
@@ -395,9 +405,13 @@ async fn handle_client_event(
                 return;
             };
             metrics::counter!("boluo_server_events_preview_diff_total").increment(1);
-            if let Err(err) =
-                crate::events::preview::broadcast_preview_diff(preview, mailbox, session.user_id)
-                    .await
+            if let Err(err) = crate::events::preview::broadcast_preview_diff(
+                preview,
+                ctx,
+                mailbox,
+                session.user_id,
+            )
+            .await
             {
                 tracing::warn!(error = %err, "Failed to broadcast preview diff update");
             }
@@ -411,6 +425,33 @@ async fn handle_client_event(
                 }
             }
         }
+        ClientEvent::NoteJoin { note_id } => {
+            let user_id = session.map(|session| session.user_id);
+            if let Err(err) = collab::join(ctx, mailbox, note_id, user_id, notes).await {
+                tracing::warn!(error = %err, note_id = %note_id, "Failed to join the note");
+                notes.closed(mailbox, note_id).await;
+            }
+        }
+        ClientEvent::NoteLeave { note_id } => {
+            notes.leave(note_id);
+        }
+        ClientEvent::NoteEdit { edit } => {
+            let Some(session) = session else {
+                tracing::warn!("An user tried to edit a note without authentication");
+                error_sender
+                    .send(ConnectionError::Unauthenticated)
+                    .await
+                    .ok();
+                return;
+            };
+            let (note_id, edit_id) = (edit.note_id, edit.edit_id);
+            if let Err(err) = collab::edit(ctx, mailbox, session.user_id, edit).await {
+                tracing::warn!(error = %err, note_id = %note_id, "Failed to edit the note");
+                notes
+                    .rejected(mailbox, note_id, edit_id, err.to_string())
+                    .await;
+            }
+        }
     }
 }
 
@@ -528,6 +569,7 @@ async fn connect(ctx: &crate::context::AppContext, req: hyper::Request<Incoming>
         event_connections_active.increment(1);
         let (mut outgoing, incoming) = ws_stream.split();
         let (error_sender, error_receiver) = tokio::sync::mpsc::channel(1);
+        let (notes, note_receiver) = NoteSubscriptions::new();
 
         static BASIC_INFO: std::sync::LazyLock<Utf8Bytes> =
             std::sync::LazyLock::new(|| sonic_rs::to_string(&Update::app_info()).unwrap().into());
@@ -540,6 +582,7 @@ async fn connect(ctx: &crate::context::AppContext, req: hyper::Request<Incoming>
                 mailbox,
                 &mut outgoing,
                 error_receiver,
+                note_receiver,
                 after,
                 seq,
                 node,
@@ -582,8 +625,15 @@ async fn connect(ctx: &crate::context::AppContext, req: hyper::Request<Incoming>
                         if message == "♡" {
                             return Ok(());
                         }
-                        handle_client_event(&ctx, mailbox, error_sender, session.ok(), message)
-                            .await
+                        handle_client_event(
+                            &ctx,
+                            mailbox,
+                            error_sender,
+                            session.ok(),
+                            &notes,
+                            message,
+                        )
+                        .await
                     }
                     Ok(())
                 }
@@ -3,17 +3,57 @@ use crate::error::AppError;
 use crate::error::Find;
 use crate::events::Update;
 use crate::messages::Entities;
-use crate::spaces::SpaceMember;
+use crate::spaces::{ContentFilter, SpaceMember};
+use std::borrow::Cow;
+use std::sync::Arc;
 use time::OffsetDateTime;
 use uuid::Uuid;
 
-pub use shared_types::preview::{Preview, PreviewDiff, PreviewDiffPost, PreviewEdit, PreviewPost};
+pub use shared_types::preview::{
+    Preview, PreviewDiff, PreviewDiffOp, PreviewDiffPost, PreviewEdit, PreviewPost,
+};
+
+/// Previews are drafts, so the content rules mask them instead of rejecting them.
+///
+/// A stale snapshot is fine here, the space is loaded only if it's not loaded yet.
+async fn preview_content_filter(
+    ctx: &crate::context::AppContext,
+    space_id: Uuid,
+) -> Result<Option<Arc<ContentFilter>>, AppError> {
+    let filter = match ctx.space_store.loaded_snapshot_maybe_stale(space_id) {
+        Some(snapshot) => snapshot.content_filter.clone(),
+        None => ctx.space_store.resolve_content_filter(space_id).await?,
+    };
+    Ok((!filter.is_empty()).then_some(filter))
+}
+
+fn mask_in_place(filter: &ContentFilter, text: &mut String) {
+    if let Cow::Owned(masked) = filter.mask(text) {
+        *text = masked;
+    }
+}
+
+/// Words split between two ops are not masked, but the sent message is filtered as a whole.
+fn mask_diff_ops(filter: &ContentFilter, ops: &mut [PreviewDiffOp]) {
+    for op in ops {
+        match op {
+            PreviewDiffOp::Splice { text, .. } | PreviewDiffOp::Append { text } => {
+                mask_in_place(filter, text)
+            }
+            PreviewDiffOp::ChangeName { name } => mask_in_place(filter, name),
+        }
+    }
+}
 
 pub async fn broadcast_preview_diff(
-    post: PreviewDiffPost,
+    mut post: PreviewDiffPost,
+    ctx: &crate::context::AppContext,
     space_id: Uuid,
     user_id: Uuid,
 ) -> Result<(), AppError> {
+    if let Some(filter) = preview_content_filter(ctx, space_id).await? {
+        mask_diff_ops(&filter, &mut post.op);
+    }
     Update::preview_diff(
         space_id,
         PreviewDiff {
@@ -54,11 +94,11 @@ pub async fn broadcast_preview_post(
         id,
         version,
         channel_id,
-        name,
+        mut name,
         media_id,
         in_game,
         is_action,
-        text,
+        mut text,
         entities,
         mut edit_for,
         clear,
@@ -126,6 +166,12 @@ pub async fn broadcast_preview_post(
         );
         false
     };
+    if let Some(filter) = preview_content_filter(ctx, space_id).await? {
+        mask_in_place(&filter, &mut name);
+        if let Some(text) = text.as_mut() {
+            mask_in_place(&filter, text);
+        }
+    }
     let whisper_to_users = None;
     let preview = Box::new(Preview {
         id,
@@ -198,6 +244,29 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn diff_ops_are_masked() {
+        use crate::spaces::{ContentRule, ContentRuleAction};
+
+        let filter = ContentFilter::compile(vec![ContentRule {
+            pattern: "darn".to_string(),
+            is_regex: false,
+            action: ContentRuleAction::Reject,
+        }])
+        .unwrap();
+        let mut ops = vec![
+            PreviewDiffOp::Append {
+                text: "oh darn".to_string(),
+            },
+            PreviewDiffOp::ChangeName {
+                name: "Darnell".to_string(),
+            },
+        ];
+        mask_diff_ops(&filter, &mut ops);
+        assert!(matches!(&ops[0], PreviewDiffOp::Append { text } if text == "oh ****"));
+        assert!(matches!(&ops[1], PreviewDiffOp::ChangeName { name } if name == "****ell"));
+    }
+
     #[test]
     fn legacy_edit_for_preview_does_not_cancel_position() {
         assert!(!should_cancel_preview_position(
@@ -6,6 +6,7 @@ use crate::events::models::{StatusKind, UserStatus};
 use crate::events::preview::{Preview, PreviewDiff, PreviewDiffPost, PreviewPost};
 use crate::info::BasicInfo;
 use crate::messages::Message;
+use crate::notes::collab::{NoteDocumentSnapshot, NoteEditPost, NoteEdited};
 use crate::spaces::api::SpaceWithRelated;
 use serde::{Deserialize, Serialize};
 use std::sync::OnceLock;
@@ -79,6 +80,12 @@ pub enum ClientEvent {
     Diff { preview: PreviewDiffPost },
     #[serde(rename_all = "camelCase")]
     Status { kind: StatusKind, focus: Vec<Uuid> },
+    #[serde(rename_all = "camelCase")]
+    NoteJoin { note_id: Uuid },
+    #[serde(rename_all = "camelCase")]
+    NoteLeave { note_id: Uuid },
+    #[serde(rename_all = "camelCase")]
+    NoteEdit { edit: NoteEditPost },
 }
 
 #[derive(Serialize, Debug, Clone, specta::Type)]
@@ -157,6 +164,35 @@ pub enum UpdateBody {
         #[serde(rename = "noteId")]
         note_id: Uuid,
     },
+    EncounterChanged {
+        #[serde(rename = "channelId")]
+        channel_id: Uuid,
+    },
+    PlaybackChanged {
+        #[serde(rename = "channelId")]
+        channel_id: Uuid,
+    },
+    /// The live document of a joined note, sent on joining it.
+    NoteSnapshot {
+        snapshot: Box<NoteDocumentSnapshot>,
+    },
+    NoteEdited {
+        edited: Box<NoteEdited>,
+    },
+    /// The live document of a joined note was closed, join it again to keep editing.
+    NoteClosed {
+        #[serde(rename = "noteId")]
+        note_id: Uuid,
+    },
+    /// An edit of a joined note was not applied and the note was left, join it again to keep
+    /// editing.
+    NoteEditRejected {
+        #[serde(rename = "noteId")]
+        note_id: Uuid,
+        #[serde(rename = "editId")]
+        edit_id: Uuid,
+        reason: String,
+    },
     Error {
         code: ConnectionError,
         reason: String,
@@ -215,6 +251,8 @@ impl UpdateBody {
             | Members { channel_id, .. } => Some(*channel_id),
             | ChannelDeleted { channel_id } => Some(*channel_id),
             | Diff { channel_id, .. } => Some(*channel_id),
+            | EncounterChanged { channel_id } => Some(*channel_id),
+            | PlaybackChanged { channel_id } => Some(*channel_id),
 
             | Initialized
             | StatusMap { .. }
@@ -222,6 +260,10 @@ impl UpdateBody {
             | EntryChanged { .. }
             | CharacterChanged { .. }
             | NoteChanged { .. }
+            | NoteSnapshot { .. }
+            | NoteEdited { .. }
+            | NoteClosed { .. }
+            | NoteEditRejected { .. }
             | Error { .. }
             | AppUpdated { .. }
             | AppInfo { .. } => None,
@@ -237,6 +279,8 @@ impl UpdateBody {
 
             | MessagePreview { .. }
             | Diff { .. }
+            | EncounterChanged { .. }
+            | PlaybackChanged { .. }
             | ChannelEdited { .. }
             | ChannelDeleted { .. }
             | Members { .. }
@@ -246,6 +290,10 @@ impl UpdateBody {
             | EntryChanged { .. }
             | CharacterChanged { .. }
             | NoteChanged { .. }
+            | NoteSnapshot { .. }
+            | NoteEdited { .. }
+            | NoteClosed { .. }
+            | NoteEditRejected { .. }
             | Error { .. }
             | AppUpdated { .. }
             | AppInfo { .. } => None,
@@ -451,6 +499,53 @@ impl Update {
         Update::transient(space_id, UpdateBody::NoteChanged { note_id })
     }
 
+    pub fn encounter_changed(space_id: Uuid, channel_id: Uuid) {
+        Update::transient(space_id, UpdateBody::EncounterChanged { channel_id })
+    }
+
+    pub fn playback_changed(space_id: Uuid, channel_id: Uuid) {
+        Update::transient(space_id, UpdateBody::PlaybackChanged { channel_id })
+    }
+
+    /// Sent only to the connection joining the note.
+    pub fn note_snapshot(space_id: Uuid, snapshot: NoteDocumentSnapshot) -> Utf8Bytes {
+        let body = UpdateBody::NoteSnapshot {
+            snapshot: Box::new(snapshot),
+        };
+        Update::build(body, space_id, UpdateLifetime::Transient).encoded
+    }
+
+    pub fn note_closed(space_id: Uuid, note_id: Uuid) -> Utf8Bytes {
+        let body = UpdateBody::NoteClosed { note_id };
+        Update::build(body, space_id, UpdateLifetime::Transient).encoded
+    }
+
+    pub fn note_edit_rejected(
+        space_id: Uuid,
+        note_id: Uuid,
+        edit_id: Uuid,
+        reason: String,
+    ) -> Utf8Bytes {
+        let body = UpdateBody::NoteEditRejected {
+            note_id,
+            edit_id,
+            reason,
+        };
+        Update::build(body, space_id, UpdateLifetime::Transient).encoded
+    }
+
+    /// Send the edit to the connections which joined the note, in the order of the edits.
+    pub fn note_edited(space_id: Uuid, edited: NoteEdited) {
+        let note_id = edited.note_id;
+        let body = UpdateBody::NoteEdited {
+            edited: Box::new(edited),
+        };
+        let update = Update::build(body, space_id, UpdateLifetime::Transient);
+        if let Some(sender) = super::get_broadcast_table().pin().get(&note_id) {
+            sender.send(update.encoded.clone()).ok();
+        }
+    }
+
     pub async fn get_from_state(
         mailbox_id: &Uuid,
         after: Option<i64>,
@@ -679,6 +774,12 @@ impl Update {
             UpdateBody::EntryChanged { .. } => "EntryChanged",
             UpdateBody::CharacterChanged { .. } => "CharacterChanged",
             UpdateBody::NoteChanged { .. } => "NoteChanged",
+            UpdateBody::EncounterChanged { .. } => "EncounterChanged",
+            UpdateBody::PlaybackChanged { .. } => "PlaybackChanged",
+            UpdateBody::NoteSnapshot { .. } => "NoteSnapshot",
+            UpdateBody::NoteEdited { .. } => "NoteEdited",
+            UpdateBody::NoteClosed { .. } => "NoteClosed",
+            UpdateBody::NoteEditRejected { .. } => "NoteEditRejected",
             UpdateBody::Error { .. } => "Error",
             UpdateBody::AppUpdated { .. } => "AppUpdated",
             UpdateBody::AppInfo { .. } => "AppInfo",
@@ -1,7 +1,11 @@
 pub(crate) mod api;
+pub(crate) mod audio;
+pub(crate) mod gc;
 mod handlers;
+mod images;
 pub(crate) mod models;
 
 pub use handlers::{
-    check_upload_rate_limit, router, start_rate_limiter_cleanup, upload, upload_params,
+    check_upload_rate_limit, ensure_audio_duration, router, start_rate_limiter_cleanup, upload,
+    upload_params,
 };
@@ -9,6 +9,9 @@ pub struct Upload {
     #[serde(default)]
     #[specta(type = f64)]
     pub size: usize,
+    /// The space the file is uploaded for, to count it to the quota of the space
+    #[serde(default)]
+    pub space_id: Option<Uuid>,
 }
 
 #[derive(Deserialize, specta::Type)]
@@ -18,6 +21,21 @@ pub struct MediaQuery {
     pub id: Option<Uuid>,
     #[serde(default)]
     pub download: bool,
+    /// Get a thumbnail fitting in a square of the size, rounded up to a fixed size
+    #[serde(default)]
+    pub size: Option<u32>,
+    /// Get the image converted to the format, WebP if only `size` is given
+    #[serde(default)]
+    pub format: Option<MediaFormat>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, specta::Type)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaFormat {
+    Webp,
+    Avif,
+    Png,
+    Jpeg,
 }
 
 #[derive(Deserialize, specta::Type)]
@@ -26,6 +44,12 @@ pub struct PreSign {
     pub filename: String,
     pub mime_type: String,
     pub size: i32,
+    /// Hex encoded SHA-256 of the file, to reuse the media having the same content
+    #[serde(default)]
+    pub hash: Option<String>,
+    /// The space the file is uploaded for, to count it to the quota of the space
+    #[serde(default)]
+    pub space_id: Option<Uuid>,
 }
 
 #[derive(Deserialize, Serialize, specta::Type)]
@@ -33,4 +57,33 @@ pub struct PreSign {
 pub struct PreSignResult {
     pub url: String,
     pub media_id: Uuid,
+    /// The file is already stored, no need to upload it to `url`
+    pub duplicate: bool,
+}
+
+/// Sent after the file is uploaded to a presigned URL.
+#[derive(Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CompleteUpload {
+    pub media_id: Uuid,
+    /// Hex encoded SHA-256 the client expects the uploaded file to have
+    #[serde(default)]
+    pub hash: Option<String>,
+    /// The space the file is uploaded for, to count it to the quota of the space
+    #[serde(default)]
+    pub space_id: Option<Uuid>,
+}
+
+#[derive(Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryStorageUsage {
+    #[serde(default)]
+    pub space_id: Option<Uuid>,
+}
+
+#[derive(Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageUsageResult {
+    pub user: super::gc::StorageUsage,
+    pub space: Option<super::gc::StorageUsage>,
 }
@@ -0,0 +1,134 @@
+//! Audio media: the accepted formats and reading the duration.
+//!
+//! Only the container is read to find the duration, the audio is never decoded.
+
+use crate::error::ValidationFailed;
+use std::io::Cursor;
+use symphonia::core::codecs::CODEC_TYPE_NULL;
+use symphonia::core::errors::Error as ProbeError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+/// The audio types browsers can play, and the server can read.
+pub const AUDIO_MIME_TYPES: [&str; 13] = [
+    "audio/mpeg",
+    "audio/mp3",
+    "audio/ogg",
+    "audio/opus",
+    "audio/wav",
+    "audio/wave",
+    "audio/x-wav",
+    "audio/flac",
+    "audio/x-flac",
+    "audio/aac",
+    "audio/mp4",
+    "audio/x-m4a",
+    "audio/webm",
+];
+
+pub fn is_audio(mime_type: &str) -> bool {
+    mime_type.starts_with("audio/")
+}
+
+/// Reject audio of a type not in [`AUDIO_MIME_TYPES`], other media are not checked.
+pub fn check_mime_type(mime_type: &str) -> Result<(), ValidationFailed> {
+    if is_audio(mime_type) && !AUDIO_MIME_TYPES.contains(&mime_type) {
+        return Err(ValidationFailed("This audio format is not supported."));
+    }
+    Ok(())
+}
+
+/// Read the duration of an audio file in milliseconds.
+///
+/// Fails if the file is not audio in a supported format. The duration comes from the header
+/// if it's there, otherwise the packets are counted.
+pub fn duration_ms(bytes: Vec<u8>, mime_type: &str) -> anyhow::Result<u32> {
+    let source = MediaSourceStream::new(Box::new(Cursor::new(bytes)), Default::default());
+    let mut hint = Hint::new();
+    hint.mime_type(mime_type);
+    let probed = symphonia::default::get_probe().format(
+        &hint,
+        source,
+        &FormatOptions::default(),
+        &MetadataOptions::default(),
+    )?;
+    let mut format = probed.format;
+    let track = format
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| anyhow::anyhow!("No audio track"))?;
+    let track_id = track.id;
+    let params = track.codec_params.clone();
+    let time_base = params
+        .time_base
+        .or_else(|| {
+            params
+                .sample_rate
+                .map(|rate| symphonia::core::units::TimeBase::new(1, rate))
+        })
+        .ok_or_else(|| anyhow::anyhow!("The audio track has no time base"))?;
+
+    let frames = match params.n_frames {
+        Some(frames) => frames,
+        None => {
+            let mut frames = 0;
+            loop {
+                match format.next_packet() {
+                    Ok(packet) if packet.track_id() == track_id => frames += packet.dur,
+                    Ok(_) => {}
+                    Err(ProbeError::IoError(err))
+                        if err.kind() == std::io::ErrorKind::UnexpectedEof =>
+                    {
+                        break;
+                    }
+                    Err(err) => return Err(err.into()),
+                }
+            }
+            frames
+        }
+    };
+    let time = time_base.calc_time(frames);
+    let millis = time.seconds as f64 * 1000.0 + time.frac * 1000.0;
+    Ok(millis.round().min(u32::MAX as f64) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A silent 16-bit mono WAV file.
+    fn wav(sample_rate: u32, samples: u32) -> Vec<u8> {
+        let data_size = samples * 2;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_size).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_size.to_le_bytes());
+        bytes.resize(bytes.len() + data_size as usize, 0);
+        bytes
+    }
+
+    #[test]
+    fn audio_duration_is_read_from_the_file() {
+        assert_eq!(duration_ms(wav(8000, 12000), "audio/wav").unwrap(), 1500);
+        assert!(duration_ms(b"not audio at all".to_vec(), "audio/mpeg").is_err());
+    }
+
+    #[test]
+    fn only_known_audio_types_are_accepted() {
+        assert!(check_mime_type("audio/ogg").is_ok());
+        assert!(check_mime_type("audio/x-ms-wma").is_err());
+        assert!(check_mime_type("image/png").is_ok());
+    }
+}
@@ -0,0 +1,217 @@
+//! Removing media that nothing refers to, and the storage usage of users and spaces.
+
+use super::images;
+use super::models::Media;
+use crate::error::AppError;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// Media uploaded or reused within this are kept, they may be for a message not sent yet.
+pub const DEFAULT_GRACE_PERIOD: Duration = Duration::from_secs(60 * 60 * 24 * 7);
+
+const MAX_BATCH: i64 = 1000;
+
+#[derive(Serialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaGcReport {
+    pub dry_run: bool,
+    /// The media removed, or to be removed in a dry run
+    pub media_ids: Vec<Uuid>,
+    #[specta(type = f64)]
+    pub bytes: i64,
+    /// Media whose files failed to be removed from the storage, their rows are removed anyway
+    pub failed_ids: Vec<Uuid>,
+}
+
+/// Remove up to `limit` unreferenced media not used within the grace period.
+pub async fn collect(
+    ctx: &crate::context::AppContext,
+    grace_period: Duration,
+    limit: i64,
+    dry_run: bool,
+) -> Result<MediaGcReport, AppError> {
+    let before = OffsetDateTime::now_utc() - grace_period;
+    let limit = limit.clamp(1, MAX_BATCH);
+    let candidates = Media::list_unreferenced(&ctx.db, before, limit).await?;
+    let media = if dry_run {
+        candidates
+    } else {
+        let ids: Vec<Uuid> = candidates.iter().map(|media| media.id).collect();
+        // The references are checked again, in case one was added since the listing.
+        Media::delete_unreferenced(&ctx.db, &ids, before).await?
+    };
+    let mut report = MediaGcReport {
+        dry_run,
+        media_ids: media.iter().map(|media| media.id).collect(),
+        bytes: media.iter().map(|media| i64::from(media.size)).sum(),
+        failed_ids: Vec::new(),
+    };
+    if dry_run {
+        metrics::gauge!("boluo_server_media_gc_unreferenced").set(report.media_ids.len() as f64);
+        return Ok(report);
+    }
+    for media in &media {
+        if let Err(err) = delete_objects(ctx, &media.id).await {
+            tracing::warn!(media_id = %media.id, "Failed to remove the files of media: {err:?}");
+            report.failed_ids.push(media.id);
+        }
+    }
+    metrics::counter!("boluo_server_media_gc_deleted_total")
+        .increment(report.media_ids.len() as u64);
+    metrics::counter!("boluo_server_media_gc_deleted_bytes_total")
+        .increment(report.bytes.max(0) as u64);
+    metrics::counter!("boluo_server_media_gc_failed_total")
+        .increment(report.failed_ids.len() as u64);
+    Ok(report)
+}
+
+async fn delete_objects(ctx: &crate::context::AppContext, media_id: &Uuid) -> anyhow::Result<()> {
+    let storage = ctx.storage();
+    storage
+        .delete(&media_id.as_hyphenated().to_string())
+        .await?;
+    for key in images::variant_keys(media_id) {
+        storage.delete(&key).await?;
+    }
+    Ok(())
+}
+
+/// Collect unreferenced media periodically, on every node running it.
+pub fn start_gc_task(ctx: Arc<crate::context::AppContext>, interval: Duration) {
+    tokio::task::spawn(async move {
+        let mut interval = crate::utils::cleaner_interval(interval.as_secs().max(60));
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    match collect(&ctx, DEFAULT_GRACE_PERIOD, MAX_BATCH, false).await {
+                        Ok(report) if !report.media_ids.is_empty() => tracing::info!(
+                            deleted = report.media_ids.len(),
+                            bytes = report.bytes,
+                            failed = report.failed_ids.len(),
+                            "Unreferenced media were removed"
+                        ),
+                        Ok(_) => {}
+                        Err(err) => tracing::error!("Failed to collect unreferenced media: {err:?}"),
+                    }
+                }
+                _ = crate::shutdown::SHUTDOWN.notified() => {
+                    break;
+                }
+            }
+        }
+    });
+}
+
+#[derive(Serialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageUsage {
+    #[specta(type = f64)]
+    pub used: i64,
+    /// `None` if there is no quota
+    #[specta(type = Option<f64>)]
+    pub quota: Option<u64>,
+}
+
+pub async fn user_usage(
+    ctx: &crate::context::AppContext,
+    user_id: Uuid,
+) -> Result<StorageUsage, AppError> {
+    Ok(StorageUsage {
+        used: Media::user_usage(&ctx.db, user_id).await?,
+        quota: ctx.config.user_media_quota,
+    })
+}
+
+pub async fn space_usage(
+    ctx: &crate::context::AppContext,
+    space_id: Uuid,
+) -> Result<StorageUsage, AppError> {
+    Ok(StorageUsage {
+        used: Media::space_usage(&ctx.db, space_id).await?,
+        quota: ctx.config.space_media_quota,
+    })
+}
+
+impl StorageUsage {
+    fn exceeded_by(&self, size: u64) -> bool {
+        self.quota
+            .is_some_and(|quota| (self.used.max(0) as u64).saturating_add(size) > quota)
+    }
+}
+
+/// Fail if a new file of `size` bytes would exceed the quota of the user or the space.
+pub async fn check_quota(
+    ctx: &crate::context::AppContext,
+    user_id: Uuid,
+    space_id: Option<Uuid>,
+    size: u64,
+) -> Result<(), AppError> {
+    check_usage(ctx, user_id, size, space_id, size).await
+}
+
+/// Fail if the stored file of `size` bytes exceeds the quota of the user or the space. Unlike
+/// [`check_quota`], the file is already counted to its uploader.
+pub async fn check_stored_quota(
+    ctx: &crate::context::AppContext,
+    user_id: Uuid,
+    space_id: Option<Uuid>,
+    size: u64,
+) -> Result<(), AppError> {
+    check_usage(ctx, user_id, 0, space_id, size).await
+}
+
+async fn check_usage(
+    ctx: &crate::context::AppContext,
+    user_id: Uuid,
+    user_size: u64,
+    space_id: Option<Uuid>,
+    space_size: u64,
+) -> Result<(), AppError> {
+    if ctx.config.user_media_quota.is_some()
+        && user_usage(ctx, user_id).await?.exceeded_by(user_size)
+    {
+        metrics::counter!("boluo_server_media_quota_exceeded_total", "kind" => "user").increment(1);
+        return Err(AppError::LimitExceeded("user storage quota"));
+    }
+    if let Some(space_id) = space_id
+        && ctx.config.space_media_quota.is_some()
+        && space_usage(ctx, space_id).await?.exceeded_by(space_size)
+    {
+        metrics::counter!("boluo_server_media_quota_exceeded_total", "kind" => "space")
+            .increment(1);
+        return Err(AppError::LimitExceeded("space storage quota"));
+    }
+    Ok(())
+}
+
+/// Remove an upload that was rejected, along with its row if nothing refers to it yet.
+pub async fn discard(ctx: &crate::context::AppContext, media: &Media) -> Result<(), AppError> {
+    Media::delete_unreferenced(&ctx.db, &[media.id], OffsetDateTime::now_utc()).await?;
+    if let Err(err) = delete_objects(ctx, &media.id).await {
+        tracing::warn!(media_id = %media.id, "Failed to remove the files of media: {err:?}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quota_is_exceeded_by_the_new_file() {
+        let usage = StorageUsage {
+            used: 90,
+            quota: Some(100),
+        };
+        assert!(!usage.exceeded_by(10));
+        assert!(usage.exceeded_by(11));
+        let unlimited = StorageUsage {
+            used: i64::MAX,
+            quota: None,
+        };
+        assert!(!unlimited.exceeded_by(u64::MAX));
+    }
+}
@@ -1,21 +1,33 @@
 use super::api::Upload;
 use super::models::Media;
+use super::{audio, images};
 use crate::csrf::authenticate;
 use crate::error::{AppError, Find, ValidationFailed};
 use crate::interface::{Response, missing, ok_response, parse_query};
-use crate::media::api::{MediaQuery, PreSign, PreSignResult};
+use crate::media::api::{CompleteUpload, MediaFormat, MediaQuery, PreSign, PreSignResult};
 use crate::media::models::MediaFile;
 use crate::rate_limit;
+use crate::spaces::resolve_space_access;
+use crate::storage::{SignedPut, Storage};
 use crate::utils::id;
+use bytes::{Bytes, BytesMut};
 use governor::{DefaultKeyedRateLimiter, RateLimiter};
-use http_body_util::BodyExt;
+use http_body_util::{BodyExt, Limited};
 use hyper::body::{Body, Incoming};
 use hyper::header::{self, HeaderValue};
-use hyper::{Request, Uri};
-use rusty_s3::S3Action;
+use hyper::{Request, StatusCode, Uri};
+use std::ops::Range;
 use std::sync::LazyLock;
 use uuid::Uuid;
 
+const MAX_MEDIA_SIZE: usize = 1024 * 1024 * 16;
+
+/// The number of image variants made at the same time.
+const VARIANT_CONCURRENCY: usize = 2;
+
+static VARIANT_PERMITS: tokio::sync::Semaphore =
+    tokio::sync::Semaphore::const_new(VARIANT_CONCURRENCY);
+
 static UPLOAD_LIMITER: LazyLock<DefaultKeyedRateLimiter<Uuid>> =
     LazyLock::new(|| RateLimiter::keyed(rate_limit::per_hour(rate_limit::UPLOAD_USER_PER_HOUR)));
 
@@ -44,6 +56,15 @@ fn content_disposition(attachment: bool, filename: &str) -> HeaderValue {
     HeaderValue::from_str(&format!("{kind}; filename*=utf-8''{filename}")).unwrap()
 }
 
+/// The media types shown inline. Anything else, HTML and SVG included, could run scripts on this
+/// origin, so it's served as a download of opaque bytes.
+fn is_inline_type(mime_type: &str) -> bool {
+    images::is_convertible(mime_type)
+        || mime_type == "image/avif"
+        || audio::AUDIO_MIME_TYPES.contains(&mime_type)
+        || matches!(mime_type, "video/mp4" | "video/webm")
+}
+
 fn filename_sanitizer(filename: String) -> String {
     let filename_replace = regex!(r"[/?*:|<>\\]");
     filename_replace.replace_all(&filename, "_").to_string()
@@ -54,15 +75,20 @@ pub fn upload_params(uri: &Uri) -> Result<Upload, AppError> {
         filename,
         mime_type,
         size,
+        space_id,
     } = parse_query(uri)?;
     if filename.len() > 200 {
         return Err(ValidationFailed("File Name is too long").into());
     }
+    if let Some(mime_type) = &mime_type {
+        audio::check_mime_type(mime_type)?;
+    }
     let filename = filename_sanitizer(filename);
     Ok(Upload {
         filename,
         mime_type,
         size,
+        space_id,
     })
 }
 
@@ -76,8 +102,53 @@ fn check_size(size: usize, max_size: usize) -> Result<(), AppError> {
     Ok(())
 }
 
+/// Read a request body and its SHA-256 as the chunks arrive.
+async fn read_hashed(body: Incoming, max_size: usize) -> Result<(String, Bytes), AppError> {
+    use http_body_util::LengthLimitError;
+    use ring::digest::{Context, SHA256};
+
+    let mut body = Limited::new(body, max_size);
+    let mut context = Context::new(&SHA256);
+    let mut buffer = BytesMut::new();
+    while let Some(frame) = body.frame().await {
+        let frame = frame.map_err(|err| {
+            if err.is::<LengthLimitError>() {
+                ValidationFailed("File size must be less than 16MB.").into()
+            } else {
+                AppError::Unexpected(anyhow::anyhow!("Failed to read request body: {err}"))
+            }
+        })?;
+        if let Ok(data) = frame.into_data() {
+            context.update(&data);
+            buffer.extend_from_slice(&data);
+        }
+    }
+    Ok((hex::encode(context.finish()), buffer.freeze()))
+}
+
+/// Find media with the same content whose file is still in the storage.
+///
+/// A hash sent by the client is not trusted, `uploader_id` limits the search to the user's own
+/// media then. Without it, only files the server hashed as it stored them are found.
+async fn find_stored(
+    ctx: &crate::context::AppContext,
+    hash: &str,
+    size: usize,
+    uploader_id: Option<&Uuid>,
+) -> Result<Option<Media>, AppError> {
+    let Some(media) = Media::get_by_hash(&ctx.db, hash, size as i32, uploader_id).await? else {
+        return Ok(None);
+    };
+    let stored_size = ctx
+        .storage()
+        .size(&media.id.as_hyphenated().to_string())
+        .await
+        .map_err(error_unexpected!("Failed to stat object"))?;
+    Ok((stored_size == Some(size as u64)).then_some(media))
+}
+
 pub async fn upload(
-    storage: &crate::s3::Storage,
+    ctx: &crate::context::AppContext,
     req: Request<Incoming>,
     id: Uuid,
     params: Upload,
@@ -87,6 +158,7 @@ pub async fn upload(
         filename,
         mime_type,
         size,
+        space_id: _,
     } = params;
     metrics::counter!("boluo_server_media_upload_total").increment(1);
     metrics::histogram!("boluo_server_media_upload_size_bytes").record(size as f64);
@@ -94,21 +166,31 @@ pub async fn upload(
     let mime_type = mime_type.unwrap_or_default();
 
     check_size(size, max_size)?;
-    let body = req.into_body();
-    put_object(
-        storage,
-        &id.as_hyphenated().to_string(),
-        body,
-        &mime_type,
-        size as i32,
-    )
-    .await?;
+    let (hash, bytes) = read_hashed(req.into_body(), max_size).await?;
+    let size = bytes.len();
+    check_size(size, max_size)?;
+    if let Some(media) = find_stored(ctx, &hash, size, None).await? {
+        metrics::counter!("boluo_server_media_duplicate_total").increment(1);
+        return Ok(MediaFile {
+            id: media.id,
+            mime_type,
+            filename: String::new(),
+            original_filename: filename,
+            hash,
+            size,
+            duplicate: true,
+        });
+    }
+    ctx.storage()
+        .put(&id.as_hyphenated().to_string(), &mime_type, bytes)
+        .await
+        .map_err(error_unexpected!("Failed to upload object"))?;
     let media_file = MediaFile {
         id,
         mime_type,
         filename: String::new(),
         original_filename: filename,
-        hash: String::new(),
+        hash,
         size,
         duplicate: false,
     };
@@ -122,14 +204,200 @@ async fn media_upload(
     let session = authenticate(ctx, &req).await?;
     check_upload_rate_limit(&session.user_id)?;
     let params = upload_params(req.uri())?;
+    gc::check_quota(ctx, session.user_id, params.space_id, params.size as u64).await?;
     let media_id = id();
-    let media_file = upload(ctx.storage(), req, media_id, params, 1024 * 1024 * 16).await?;
+    let media_file = upload(ctx, req, media_id, params, MAX_MEDIA_SIZE).await?;
     media_file
         .create(&ctx.db, session.user_id, "")
         .await
         .map_err(Into::into)
 }
 
+/// Parse a single range of the `Range` header. `Ok(None)` is the whole object, which is also
+/// what a malformed or multiple range header gets. `Err` is a range out of the object.
+fn parse_range(header: Option<&HeaderValue>, size: u64) -> Result<Option<Range<u64>>, ()> {
+    let Some(spec) = header
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().strip_prefix("bytes="))
+        .filter(|spec| !spec.contains(','))
+    else {
+        return Ok(None);
+    };
+    let Some((start, end)) = spec.trim().split_once('-') else {
+        return Ok(None);
+    };
+    let range = match (start.parse::<u64>(), end.parse::<u64>()) {
+        (Ok(start), Ok(end)) if start <= end => start..end.saturating_add(1).min(size),
+        (Ok(start), Err(_)) if end.is_empty() => start..size,
+        (Err(_), Ok(suffix)) if start.is_empty() => {
+            if suffix == 0 {
+                return Err(());
+            }
+            size.saturating_sub(suffix)..size
+        }
+        _ => return Ok(None),
+    };
+    if range.start >= size {
+        return Err(());
+    }
+    Ok(Some(range))
+}
+
+async fn serve_object(
+    ctx: &crate::context::AppContext,
+    req: &Request<impl Body>,
+    key: &str,
+    mime_type: &str,
+    filename: &str,
+    download: bool,
+) -> Result<Response, AppError> {
+    let storage = ctx.storage();
+    let size = storage
+        .size(key)
+        .await
+        .map_err(error_unexpected!("Failed to stat object"))?
+        .ok_or(AppError::NotFound("media file"))?;
+    let builder = hyper::Response::builder()
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::CACHE_CONTROL, "public, max-age=31536000, immutable")
+        .header(header::X_CONTENT_TYPE_OPTIONS, "nosniff")
+        .header(
+            header::CONTENT_SECURITY_POLICY,
+            "default-src 'none'; sandbox",
+        );
+    let range = match parse_range(req.headers().get(header::RANGE), size) {
+        Ok(range) => range,
+        Err(()) => {
+            return builder
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(header::CONTENT_RANGE, format!("bytes */{size}"))
+                .body(Vec::new())
+                .map_err(error_unexpected!("Failed to build media response"));
+        }
+    };
+    let (mime_type, download) = if is_inline_type(mime_type) {
+        (mime_type, download)
+    } else {
+        ("application/octet-stream", true)
+    };
+    let mut builder = builder.header(header::CONTENT_TYPE, mime_type).header(
+        header::CONTENT_DISPOSITION,
+        content_disposition(download, filename),
+    );
+    let (status, range) = match range {
+        Some(range) => {
+            builder = builder.header(
+                header::CONTENT_RANGE,
+                format!("bytes {}-{}/{size}", range.start, range.end - 1),
+            );
+            (StatusCode::PARTIAL_CONTENT, range)
+        }
+        None => (StatusCode::OK, 0..size),
+    };
+    let body = if req.method() == hyper::Method::HEAD {
+        Vec::new()
+    } else {
+        storage
+            .read(key, range.clone())
+            .await
+            .map_err(error_unexpected!("Failed to read object"))?
+    };
+    builder
+        .status(status)
+        .header(header::CONTENT_LENGTH, range.end - range.start)
+        .body(body)
+        .map_err(error_unexpected!("Failed to build media response"))
+}
+
+/// Read the whole stored file of the media.
+async fn read_original(
+    ctx: &crate::context::AppContext,
+    media: &Media,
+) -> Result<Vec<u8>, AppError> {
+    let storage = ctx.storage();
+    let key = media.id.as_hyphenated().to_string();
+    let size = storage
+        .size(&key)
+        .await
+        .map_err(error_unexpected!("Failed to stat object"))?
+        .ok_or(AppError::NotFound("media file"))?;
+    storage
+        .read(&key, 0..size)
+        .await
+        .map_err(error_unexpected!("Failed to read object"))
+}
+
+/// Check that an audio media is readable and record its duration, if it's not known yet.
+pub async fn ensure_audio_duration(
+    ctx: &crate::context::AppContext,
+    media: Media,
+) -> Result<Media, AppError> {
+    if media.duration_ms.is_some() {
+        return Ok(media);
+    }
+    if !audio::AUDIO_MIME_TYPES.contains(&media.mime_type.as_str()) {
+        return Err(ValidationFailed("This audio format is not supported.").into());
+    }
+    let bytes = read_original(ctx, &media).await?;
+    let mime_type = media.mime_type.clone();
+    let duration_ms = tokio::task::spawn_blocking(move || audio::duration_ms(bytes, &mime_type))
+        .await
+        .map_err(error_unexpected!("The audio probing task failed"))?
+        .map_err(|err| AppError::BadRequest(format!("Failed to read the audio file: {err}")))?;
+    Media::set_duration(&ctx.db, &media.id, duration_ms as i32)
+        .await
+        .map_err(Into::into)
+}
+
+/// Make the variant of an image media if it's not stored yet, and return its key.
+async fn ensure_variant(
+    ctx: &crate::context::AppContext,
+    media: &Media,
+    size: Option<u32>,
+    format: MediaFormat,
+) -> Result<String, AppError> {
+    if !images::is_convertible(&media.mime_type) {
+        return Err(AppError::BadRequest(format!(
+            "Media of type {:?} can't be converted",
+            media.mime_type
+        )));
+    }
+    let size = images::variant_size(size, format);
+    let key = images::variant_key(&media.id, size, format);
+    let storage = ctx.storage();
+    let stored = storage
+        .size(&key)
+        .await
+        .map_err(error_unexpected!("Failed to stat object"))?;
+    if stored.is_some() {
+        return Ok(key);
+    }
+    let _permit = VARIANT_PERMITS
+        .acquire()
+        .await
+        .map_err(error_unexpected!("The variant semaphore is closed"))?;
+    // The same variant may have been made while waiting for the permit.
+    let stored = storage
+        .size(&key)
+        .await
+        .map_err(error_unexpected!("Failed to stat object"))?;
+    if stored.is_some() {
+        return Ok(key);
+    }
+    let original = read_original(ctx, media).await?;
+    let variant =
+        tokio::task::spawn_blocking(move || images::make_variant(&original, size, format))
+            .await
+            .map_err(error_unexpected!("The image conversion task failed"))?
+            .map_err(|err| AppError::BadRequest(format!("Failed to convert the image: {err}")))?;
+    metrics::counter!("boluo_server_media_variant_created_total").increment(1);
+    storage
+        .put(&key, format.mime_type(), variant.into())
+        .await
+        .map_err(error_unexpected!("Failed to upload object"))?;
+    Ok(key)
+}
+
 async fn get(
     ctx: &crate::context::AppContext,
     req: Request<impl Body>,
@@ -137,10 +405,11 @@ async fn get(
     let MediaQuery {
         id,
         filename,
-        download: _,
+        download,
+        size,
+        format,
     } = parse_query(req.uri())?;
     metrics::counter!("boluo_server_media_get_total").increment(1);
-    let _method = req.method().clone();
 
     let mut media: Option<Media> = None;
     if let Some(id) = id {
@@ -155,8 +424,30 @@ async fn get(
     let media = media.ok_or_else(|| {
         AppError::BadRequest("Filename or media id must be specified.".to_string())
     })?;
+    let (key, mime_type) = if size.is_some() || format.is_some() {
+        let format = format.unwrap_or(MediaFormat::Webp);
+        let key = ensure_variant(ctx, &media, size, format).await?;
+        (key, format.mime_type())
+    } else {
+        (
+            media.id.as_hyphenated().to_string(),
+            media.mime_type.as_str(),
+        )
+    };
 
-    let url = format!("{}/{}", ctx.media_public_url(), media.id);
+    // Without a public URL, the files are served by the server.
+    if ctx.media_public_url().is_empty() {
+        return serve_object(
+            ctx,
+            &req,
+            &key,
+            mime_type,
+            &media.original_filename,
+            download,
+        )
+        .await;
+    }
+    let url = format!("{}/{key}", ctx.media_public_url());
     let response = hyper::Response::builder()
         .status(hyper::StatusCode::MOVED_PERMANENTLY)
         .header(header::LOCATION, url)
@@ -165,56 +456,63 @@ async fn get(
     Ok(response)
 }
 
-async fn put_object(
-    storage: &crate::s3::Storage,
+async fn media_by_key(ctx: &crate::context::AppContext, key: &str) -> Result<Media, AppError> {
+    let id: Uuid = key.parse().map_err(|_| AppError::NotFound("media file"))?;
+    Media::get_by_id(&ctx.db, &id).await.or_not_found()
+}
+
+/// Serve a file the way a public bucket does, so `PUBLIC_MEDIA_URL` can point here.
+async fn object(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
     key: &str,
-    body: hyper::body::Incoming,
-    content_type: &str,
-    content_length: i32,
-) -> Result<(), AppError> {
-    let bytes = body
-        .collect()
-        .await
-        .map_err(error_unexpected!("Failed to read request body"))?
-        .to_bytes();
-
-    let mut action = storage
-        .bucket()
-        .put_object(Some(storage.credentials()), key);
-    action.headers_mut().insert("content-type", content_type);
-    let url = action.sign(std::time::Duration::from_secs(60));
-
-    let response = storage
-        .client()
-        .put(url.as_str())
-        .header("content-type", content_type)
-        .header("content-length", content_length)
-        .body(bytes)
-        .send()
-        .await
-        .map_err(error_unexpected!("Failed to upload object"))?;
-    if !response.status().is_success() {
-        return Err(AppError::Unexpected(anyhow::anyhow!(
-            "S3 PUT failed with status {}",
-            response.status()
-        )));
-    }
-    Ok(())
+) -> Result<Response, AppError> {
+    // Variants are keyed as `{media id}.{size}.{extension}`.
+    let (id, variant) = match key.split_once('.') {
+        Some((id, variant)) => (id, Some(variant)),
+        None => (key, None),
+    };
+    let media = media_by_key(ctx, id).await?;
+    let mime_type = match variant {
+        Some(variant) => {
+            let extension = variant.rsplit('.').next().unwrap_or_default();
+            images::extension_mime_type(extension).ok_or(AppError::NotFound("media file"))?
+        }
+        None => media.mime_type.as_str(),
+    };
+    serve_object(ctx, &req, key, mime_type, &media.original_filename, false).await
 }
 
-fn put_object_presigned(
-    storage: &crate::s3::Storage,
+/// Receive an upload to a URL made by `presigned` with the local storage.
+async fn signed_put(
+    ctx: &crate::context::AppContext,
+    req: Request<Incoming>,
     key: &str,
-    expires_in: u64,
-    content_type: &str,
-) -> String {
-    let mut action = storage
-        .bucket()
-        .put_object(Some(storage.credentials()), key);
-    action.headers_mut().insert("content-type", content_type);
-    action
-        .sign(std::time::Duration::from_secs(expires_in))
-        .to_string()
+) -> Result<bool, AppError> {
+    let storage = ctx
+        .storage()
+        .local()
+        .ok_or(AppError::NotFound("local storage"))?;
+    let query: SignedPut = parse_query(req.uri())?;
+    let content_type = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    if content_type != query.content_type || storage.verify_put(key, &query).is_err() {
+        return Err(AppError::NoPermission(
+            "The upload URL is invalid or has expired".to_string(),
+        ));
+    }
+    let media = media_by_key(ctx, key).await?;
+    let (hash, bytes) = read_hashed(req.into_body(), MAX_MEDIA_SIZE).await?;
+    check_size(bytes.len(), MAX_MEDIA_SIZE)?;
+    storage
+        .put(key, content_type, bytes)
+        .await
+        .map_err(error_unexpected!("Failed to write object"))?;
+    Media::set_hash(&ctx.db, &media.id, &hash, true).await?;
+    Ok(true)
 }
 
 const EXPIRES_IN_SEC: u64 = 60 * 10;
@@ -227,6 +525,8 @@ async fn presigned(
         filename,
         mime_type,
         size,
+        hash,
+        space_id,
     } = parse_query(req.uri())?;
     check_upload_rate_limit(&session.user_id)?;
     metrics::counter!("boluo_server_media_presigned_total").increment(1);
@@ -235,9 +535,22 @@ async fn presigned(
     if size <= 0 {
         return Err(ValidationFailed("File size must be greater than 0.").into());
     }
-    if size > 1024 * 1024 * 16 {
+    if size as usize > MAX_MEDIA_SIZE {
         return Err(ValidationFailed("File size must be less than 16MB.").into());
     }
+    audio::check_mime_type(&mime_type)?;
+    if let Some(hash) = hash {
+        let hash = hash.to_ascii_lowercase();
+        if let Some(media) = find_stored(ctx, &hash, size as usize, Some(&session.user_id)).await? {
+            metrics::counter!("boluo_server_media_duplicate_total").increment(1);
+            return Ok(PreSignResult {
+                url: String::new(),
+                media_id: media.id,
+                duplicate: true,
+            });
+        }
+    }
+    gc::check_quota(ctx, session.user_id, space_id, size as u64).await?;
     let media_id = id();
     let media = Media::create(
         &ctx.db,
@@ -251,15 +564,97 @@ async fn presigned(
         "",
     )
     .await?;
-    let uri = put_object_presigned(
-        ctx.storage(),
+    let uri = ctx.storage().presigned_put(
         &media.id.as_hyphenated().to_string(),
-        EXPIRES_IN_SEC,
         &mime_type,
+        std::time::Duration::from_secs(EXPIRES_IN_SEC),
     );
     Ok(PreSignResult {
         url: uri.to_string(),
         media_id: media.id,
+        duplicate: false,
+    })
+}
+
+/// Hash a file uploaded to a presigned URL. Returns the media to use, which is an earlier one if
+/// the server stored the same file before.
+async fn complete(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Media, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let CompleteUpload {
+        media_id,
+        hash,
+        space_id,
+    } = parse_query(req.uri())?;
+    let media = Media::get_by_id(&ctx.db, &media_id).await.or_not_found()?;
+    if media.uploader_id != session.user_id {
+        return Err(AppError::NoPermission(
+            "Only the uploader can complete the upload".to_string(),
+        ));
+    }
+    let key = media.id.as_hyphenated().to_string();
+    let size = ctx
+        .storage()
+        .size(&key)
+        .await
+        .map_err(error_unexpected!("Failed to stat object"))?
+        .ok_or_else(|| AppError::BadRequest("The file has not been uploaded".to_string()))?;
+    // The presigned URL doesn't limit the size, so the quota is checked again with the real one.
+    if size != media.size as u64 {
+        gc::discard(ctx, &media).await?;
+        return Err(AppError::BadRequest(format!(
+            "The uploaded file has {size} bytes, {} bytes are expected",
+            media.size
+        )));
+    }
+    if let Err(err) = gc::check_stored_quota(ctx, session.user_id, space_id, size).await {
+        gc::discard(ctx, &media).await?;
+        return Err(err);
+    }
+    let bytes = ctx
+        .storage()
+        .read(&key, 0..size)
+        .await
+        .map_err(error_unexpected!("Failed to read object"))?;
+    let actual = super::models::content_hash(&bytes);
+    if hash.is_some_and(|hash| !hash.eq_ignore_ascii_case(&actual)) {
+        return Err(AppError::BadRequest(
+            "The uploaded file does not match its hash".to_string(),
+        ));
+    }
+    // The file may still be replaced through the presigned URL, so its hash isn't trusted.
+    let media = if media.hash != actual {
+        Media::set_hash(&ctx.db, &media.id, &actual, false).await?
+    } else {
+        media
+    };
+    let earlier = Media::get_by_hash(&ctx.db, &actual, media.size, None).await?;
+    Ok(earlier.unwrap_or(media))
+}
+
+async fn usage(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<StorageUsageResult, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let QueryStorageUsage { space_id } = parse_query(req.uri())?;
+    let space = match space_id {
+        Some(space_id) => {
+            let access = resolve_space_access(ctx, space_id, Some(session.user_id)).await?;
+            if !access.can_access {
+                return Err(AppError::NoPermission(
+                    "You don't have permission to view this space".to_string(),
+                ));
+            }
+            Some(gc::space_usage(ctx, space_id).await?)
+        }
+        None => None,
+    };
+    Ok(StorageUsageResult {
+        user: gc::user_usage(ctx, session.user_id).await?,
+        space,
     })
 }
 
@@ -270,11 +665,55 @@ pub async fn router(
 ) -> Result<Response, AppError> {
     use hyper::Method;
 
+    if let Some(key) = path.strip_prefix("/objects/") {
+        return match req.method().clone() {
+            Method::GET | Method::HEAD => object(ctx, req, key).await,
+            Method::PUT => signed_put(ctx, req, key).await.map(ok_response),
+            _ => missing(),
+        };
+    }
     match (path, req.method().clone()) {
         ("/get", Method::GET) => get(ctx, req).await,
         ("/get", Method::HEAD) => get(ctx, req).await,
         ("/upload", Method::POST) => media_upload(ctx, req).await.map(ok_response),
         ("/presigned", Method::POST) => presigned(ctx, req).await.map(ok_response),
+        ("/complete", Method::POST) => complete(ctx, req).await.map(ok_response),
+        ("/usage", Method::GET) => usage(ctx, req).await.map(ok_response),
         _ => missing(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(value: &str, size: u64) -> Result<Option<Range<u64>>, ()> {
+        parse_range(Some(&HeaderValue::from_str(value).unwrap()), size)
+    }
+
+    #[test]
+    fn only_safe_types_are_inline() {
+        assert!(is_inline_type("image/png"));
+        assert!(is_inline_type("image/avif"));
+        assert!(is_inline_type("audio/ogg"));
+        assert!(!is_inline_type("text/html"));
+        assert!(!is_inline_type("image/svg+xml"));
+        assert!(!is_inline_type("image/png; charset=utf-8"));
+        assert!(!is_inline_type(""));
+    }
+
+    #[test]
+    fn range_header_is_parsed() {
+        assert_eq!(parse_range(None, 100), Ok(None));
+        assert_eq!(range("bytes=0-9", 100), Ok(Some(0..10)));
+        assert_eq!(range("bytes=90-200", 100), Ok(Some(90..100)));
+        assert_eq!(range("bytes=50-", 100), Ok(Some(50..100)));
+        assert_eq!(range("bytes=-10", 100), Ok(Some(90..100)));
+        assert_eq!(range("bytes=-200", 100), Ok(Some(0..100)));
+        assert_eq!(range("bytes=0-1,5-6", 100), Ok(None));
+        assert_eq!(range("items=0-1", 100), Ok(None));
+        assert_eq!(range("bytes=5-1", 100), Ok(None));
+        assert_eq!(range("bytes=100-", 100), Err(()));
+        assert_eq!(range("bytes=-0", 100), Err(()));
+    }
+}
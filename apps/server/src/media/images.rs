@@ -0,0 +1,195 @@
+//! Derived variants of image media: thumbnails and format conversion.
+//!
+//! A variant is decoded and encoded again, so it has no EXIF or other metadata of the
+//! original. The orientation in the EXIF is applied to the pixels before it's dropped.
+
+use super::api::MediaFormat;
+use image::{DynamicImage, ImageDecoder, ImageReader, Limits, imageops::FilterType};
+use std::io::Cursor;
+use uuid::Uuid;
+
+/// The sizes of thumbnails, a request for other sizes gets the next larger one.
+pub const THUMBNAIL_SIZES: [u32; 6] = [48, 96, 192, 384, 768, 1536];
+
+/// The largest width or height of an image that will be decoded.
+const MAX_DIMENSION: u32 = 8192;
+
+/// The most memory a decoder may allocate for an image.
+const MAX_ALLOC: u64 = 256 * 1024 * 1024;
+
+/// The media types that can be decoded to make variants.
+pub fn is_convertible(mime_type: &str) -> bool {
+    matches!(
+        mime_type,
+        "image/png" | "image/jpeg" | "image/gif" | "image/webp"
+    )
+}
+
+pub fn thumbnail_size(size: u32) -> u32 {
+    THUMBNAIL_SIZES
+        .into_iter()
+        .find(|&thumbnail| thumbnail >= size)
+        .unwrap_or(THUMBNAIL_SIZES[THUMBNAIL_SIZES.len() - 1])
+}
+
+/// The size of the variant to make for a request.
+///
+/// Encoding AVIF is slow, so an AVIF variant is never larger than the largest thumbnail.
+pub fn variant_size(size: Option<u32>, format: MediaFormat) -> Option<u32> {
+    match (size, format) {
+        (None, MediaFormat::Avif) => Some(THUMBNAIL_SIZES[THUMBNAIL_SIZES.len() - 1]),
+        (size, _) => size.map(thumbnail_size),
+    }
+}
+
+impl MediaFormat {
+    const ALL: [MediaFormat; 4] = [
+        MediaFormat::Webp,
+        MediaFormat::Avif,
+        MediaFormat::Png,
+        MediaFormat::Jpeg,
+    ];
+
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            MediaFormat::Webp => "image/webp",
+            MediaFormat::Avif => "image/avif",
+            MediaFormat::Png => "image/png",
+            MediaFormat::Jpeg => "image/jpeg",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            MediaFormat::Webp => "webp",
+            MediaFormat::Avif => "avif",
+            MediaFormat::Png => "png",
+            MediaFormat::Jpeg => "jpg",
+        }
+    }
+}
+
+pub fn extension_mime_type(extension: &str) -> Option<&'static str> {
+    MediaFormat::ALL
+        .into_iter()
+        .find(|format| format.extension() == extension)
+        .map(MediaFormat::mime_type)
+}
+
+/// The storage key of a variant, next to the original keyed by the media id.
+pub fn variant_key(media_id: &Uuid, size: Option<u32>, format: MediaFormat) -> String {
+    let id = media_id.as_hyphenated();
+    match size {
+        Some(size) => format!("{id}.{size}.{}", format.extension()),
+        None => format!("{id}.{}", format.extension()),
+    }
+}
+
+/// The keys of every variant the media may have.
+pub fn variant_keys(media_id: &Uuid) -> impl Iterator<Item = String> + '_ {
+    let sizes = std::iter::once(None).chain(THUMBNAIL_SIZES.map(Some));
+    sizes.flat_map(move |size| {
+        MediaFormat::ALL
+            .into_iter()
+            .map(move |format| variant_key(media_id, size, format))
+    })
+}
+
+/// Make a variant of an image, fitting in a `size` square if it's larger.
+pub fn make_variant(
+    original: &[u8],
+    size: Option<u32>,
+    format: MediaFormat,
+) -> anyhow::Result<Vec<u8>> {
+    use image::codecs::{avif::AvifEncoder, jpeg::JpegEncoder, png::PngEncoder, webp::WebPEncoder};
+
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+    limits.max_alloc = Some(MAX_ALLOC);
+    let mut reader = ImageReader::new(Cursor::new(original)).with_guessed_format()?;
+    reader.limits(limits);
+    let mut decoder = reader.into_decoder()?;
+    let orientation = decoder.orientation()?;
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+    if let Some(size) = size
+        && (image.width() > size || image.height() > size)
+    {
+        image = image.resize(size, size, FilterType::Triangle);
+    }
+
+    let mut buffer = Vec::new();
+    match format {
+        MediaFormat::Webp => DynamicImage::ImageRgba8(image.to_rgba8())
+            .write_with_encoder(WebPEncoder::new_lossless(&mut buffer))?,
+        MediaFormat::Avif => DynamicImage::ImageRgba8(image.to_rgba8())
+            .write_with_encoder(AvifEncoder::new_with_speed_quality(&mut buffer, 8, 70))?,
+        MediaFormat::Png => DynamicImage::ImageRgba8(image.to_rgba8())
+            .write_with_encoder(PngEncoder::new(&mut buffer))?,
+        MediaFormat::Jpeg => DynamicImage::ImageRgb8(image.to_rgb8())
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut buffer, 85))?,
+    }
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = DynamicImage::ImageRgba8(image::RgbaImage::new(width, height));
+        let mut buffer = Vec::new();
+        image
+            .write_with_encoder(image::codecs::png::PngEncoder::new(&mut buffer))
+            .unwrap();
+        buffer
+    }
+
+    fn dimensions(bytes: &[u8]) -> (u32, u32) {
+        let image = image::load_from_memory(bytes).unwrap();
+        (image.width(), image.height())
+    }
+
+    #[test]
+    fn thumbnails_keep_the_aspect_ratio() {
+        assert_eq!(thumbnail_size(1), 48);
+        assert_eq!(thumbnail_size(100), 192);
+        assert_eq!(thumbnail_size(5000), 1536);
+
+        let original = png(400, 200);
+        let thumbnail = make_variant(&original, Some(96), MediaFormat::Webp).unwrap();
+        assert_eq!(dimensions(&thumbnail), (96, 48));
+        let small = make_variant(&original, Some(768), MediaFormat::Jpeg).unwrap();
+        assert_eq!(dimensions(&small), (400, 200));
+        assert!(make_variant(b"not an image", None, MediaFormat::Png).is_err());
+    }
+
+    #[test]
+    fn large_images_are_limited() {
+        assert_eq!(variant_size(None, MediaFormat::Png), None);
+        assert_eq!(variant_size(Some(100), MediaFormat::Png), Some(192));
+        assert_eq!(variant_size(None, MediaFormat::Avif), Some(1536));
+
+        let huge = png(MAX_DIMENSION + 1, 1);
+        assert!(make_variant(&huge, Some(48), MediaFormat::Png).is_err());
+    }
+
+    #[test]
+    fn variant_keys_are_next_to_the_original() {
+        let id = Uuid::nil();
+        assert_eq!(
+            variant_key(&id, Some(48), MediaFormat::Webp),
+            "00000000-0000-0000-0000-000000000000.48.webp"
+        );
+        assert_eq!(
+            variant_key(&id, None, MediaFormat::Jpeg),
+            "00000000-0000-0000-0000-000000000000.jpg"
+        );
+        assert_eq!(extension_mime_type("avif"), Some("image/avif"));
+        assert_eq!(extension_mime_type("exe"), None);
+        let keys: Vec<String> = variant_keys(&id).collect();
+        assert_eq!(keys.len(), 28);
+        assert!(keys.contains(&variant_key(&id, Some(1536), MediaFormat::Avif)));
+    }
+}
@@ -2,6 +2,11 @@ use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
 use uuid::Uuid;
 
+/// Hex encoded SHA-256 of a file, the `hash` of media.
+pub fn content_hash(bytes: &[u8]) -> String {
+    hex::encode(ring::digest::digest(&ring::digest::SHA256, bytes))
+}
+
 pub struct MediaFile {
     pub id: Uuid,
     pub mime_type: String,
@@ -19,6 +24,12 @@ impl MediaFile {
         user_id: Uuid,
         source: &str,
     ) -> Result<Media, sqlx::Error> {
+        if self.duplicate {
+            // The file is already stored as this media.
+            return Media::get_by_id(db, &self.id)
+                .await?
+                .ok_or(sqlx::Error::RowNotFound);
+        }
         Media::create(
             db,
             &self.id,
@@ -125,6 +136,149 @@ mod tests {
         assert_eq!(created.size, size as i32);
         assert_eq!(created.source, source);
     }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn db_test_media_duplicate_by_hash(pool: sqlx::PgPool) {
+        let user = create_test_user(&pool, "mediahash").await;
+        let hash = content_hash(b"portrait");
+        assert_eq!(hash.len(), 64);
+        let first = MediaFile {
+            id: uuid::Uuid::new_v4(),
+            mime_type: "image/png".to_string(),
+            filename: String::new(),
+            original_filename: "portrait.png".to_string(),
+            hash: hash.clone(),
+            size: 8,
+            duplicate: false,
+        }
+        .create(&pool, user.id, "")
+        .await
+        .expect("failed to create media");
+
+        let pending = Media::create(
+            &pool,
+            &uuid::Uuid::new_v4(),
+            "image/png",
+            user.id,
+            "",
+            "portrait.png",
+            String::new(),
+            8,
+            "",
+        )
+        .await
+        .expect("failed to create pending media");
+        assert!(
+            Media::get_by_hash(&pool, "", 8, None)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            Media::get_by_hash(&pool, &hash, 9, None)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let pending = Media::set_hash(&pool, &pending.id, &hash, false)
+            .await
+            .expect("failed to set hash");
+        assert_eq!(pending.hash, hash);
+        assert!(first.hash_trusted);
+        assert!(!pending.hash_trusted);
+        let found = Media::get_by_hash(&pool, &hash, 8, Some(&user.id))
+            .await
+            .unwrap()
+            .expect("media not found by hash");
+        assert_eq!(found.id, first.id);
+        assert!(found.last_used > first.last_used);
+        let other = create_test_user(&pool, "mediahashother").await;
+        assert!(
+            Media::get_by_hash(&pool, &hash, 8, Some(&other.id))
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let duplicate = MediaFile {
+            id: found.id,
+            mime_type: "image/png".to_string(),
+            filename: String::new(),
+            original_filename: "copy.png".to_string(),
+            hash,
+            size: 8,
+            duplicate: true,
+        }
+        .create(&pool, user.id, "")
+        .await
+        .expect("failed to reuse media");
+        assert_eq!(duplicate.id, first.id);
+        assert_eq!(duplicate.original_filename, "portrait.png");
+
+        // A hash the server didn't compute as it stored the file is only used for the uploader.
+        let untrusted_hash = content_hash(b"replaced");
+        Media::set_hash(&pool, &pending.id, &untrusted_hash, false)
+            .await
+            .expect("failed to set hash");
+        assert!(
+            Media::get_by_hash(&pool, &untrusted_hash, 8, None)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        let found = Media::get_by_hash(&pool, &untrusted_hash, 8, Some(&user.id))
+            .await
+            .unwrap()
+            .expect("media not found by hash");
+        assert_eq!(found.id, pending.id);
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn db_test_media_unreferenced_and_usage(pool: sqlx::PgPool) {
+        let user = create_test_user(&pool, "mediagc").await;
+        let pool_ref = &pool;
+        let create = |size: i32| async move {
+            Media::create(
+                pool_ref,
+                &uuid::Uuid::new_v4(),
+                "image/png",
+                user.id,
+                "",
+                "file.png",
+                String::new(),
+                size,
+                "",
+            )
+            .await
+        };
+        let orphan = create(100).await.expect("failed to create media");
+        let avatar = create(50).await.expect("failed to create media");
+        User::edit(&pool, &user.id, None, None, Some(avatar.id), None)
+            .await
+            .expect("failed to set avatar");
+        assert_eq!(Media::user_usage(&pool, user.id).await.unwrap(), 150);
+
+        let past = OffsetDateTime::now_utc() - time::Duration::minutes(1);
+        assert!(
+            Media::list_unreferenced(&pool, past, 10)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        let future = OffsetDateTime::now_utc() + time::Duration::minutes(1);
+        let unreferenced = Media::list_unreferenced(&pool, future, 10).await.unwrap();
+        let ids: Vec<Uuid> = unreferenced.iter().map(|media| media.id).collect();
+        assert_eq!(ids, vec![orphan.id]);
+
+        let deleted = Media::delete_unreferenced(&pool, &[orphan.id, avatar.id], future)
+            .await
+            .expect("failed to delete media");
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].id, orphan.id);
+        assert!(Media::get_by_id(&pool, &orphan.id).await.unwrap().is_none());
+        assert_eq!(Media::user_usage(&pool, user.id).await.unwrap(), 50);
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, specta::Type, sqlx::Type)]
@@ -143,6 +297,15 @@ pub struct Media {
     #[specta(type = String)]
     #[serde(with = "time::serde::rfc3339")]
     pub created: OffsetDateTime,
+    /// Whether the server hashed the file as it stored it
+    #[serde(skip)]
+    pub hash_trusted: bool,
+    /// When the media was uploaded or last reused by a duplicate upload
+    #[specta(type = String)]
+    #[serde(with = "time::serde::rfc3339")]
+    pub last_used: OffsetDateTime,
+    /// The length of audio media, once it's been read
+    pub duration_ms: Option<i32>,
 }
 
 impl Media {
@@ -166,6 +329,90 @@ impl Media {
         Ok(media)
     }
 
+    /// The earliest media having the content, only hashes computed by the server are recorded.
+    ///
+    /// With `uploader_id`, only media uploaded by the user is found. Without it, only media whose
+    /// file the server hashed as it stored it is found, as other files may be replaced after they
+    /// were hashed. The media found is marked as used, so the garbage collection keeps it until
+    /// it's referenced.
+    pub async fn get_by_hash<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        hash: &str,
+        size: i32,
+        uploader_id: Option<&Uuid>,
+    ) -> Result<Option<Media>, sqlx::Error> {
+        if hash.is_empty() {
+            return Ok(None);
+        }
+        sqlx::query_file_scalar!("sql/media/get_by_hash.sql", hash, size, uploader_id)
+            .fetch_optional(db)
+            .await
+    }
+
+    pub async fn set_duration<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        media_id: &Uuid,
+        duration_ms: i32,
+    ) -> Result<Media, sqlx::Error> {
+        sqlx::query_file_scalar!("sql/media/set_duration.sql", media_id, duration_ms)
+            .fetch_one(db)
+            .await
+    }
+
+    /// Record the hash of the stored file, `trusted` if the server hashed it as it stored it.
+    pub async fn set_hash<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        media_id: &Uuid,
+        hash: &str,
+        trusted: bool,
+    ) -> Result<Media, sqlx::Error> {
+        sqlx::query_file_scalar!("sql/media/set_hash.sql", media_id, hash, trusted)
+            .fetch_one(db)
+            .await
+    }
+
+    /// Media not used since `before` that no message, avatar, asset or open report refers to.
+    pub async fn list_unreferenced<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        before: OffsetDateTime,
+        limit: i64,
+    ) -> Result<Vec<Media>, sqlx::Error> {
+        sqlx::query_file_scalar!("sql/media/list_unreferenced.sql", before, limit)
+            .fetch_all(db)
+            .await
+    }
+
+    /// Delete the media in `ids` which are still unreferenced, and return the deleted ones.
+    pub async fn delete_unreferenced<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        ids: &[Uuid],
+        before: OffsetDateTime,
+    ) -> Result<Vec<Media>, sqlx::Error> {
+        sqlx::query_file_scalar!("sql/media/delete_unreferenced.sql", ids, before)
+            .fetch_all(db)
+            .await
+    }
+
+    /// Total bytes of the media uploaded by the user.
+    pub async fn user_usage<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        user_id: Uuid,
+    ) -> Result<i64, sqlx::Error> {
+        sqlx::query_file_scalar!("sql/media/user_usage.sql", user_id)
+            .fetch_one(db)
+            .await
+    }
+
+    /// Total bytes of the media used by messages and assets of the space, each counted once.
+    pub async fn space_usage<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+    ) -> Result<i64, sqlx::Error> {
+        sqlx::query_file_scalar!("sql/media/space_usage.sql", space_id)
+            .fetch_one(db)
+            .await
+    }
+
     pub async fn create<'c, T: sqlx::PgExecutor<'c>>(
         db: T,
         media_id: &Uuid,
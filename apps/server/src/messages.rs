@@ -2,6 +2,7 @@ pub mod api;
 mod handlers;
 mod models;
 mod position;
+mod slow_mode;
 
 pub use handlers::{router, start_rate_limiter_cleanup};
 pub use models::Entities;
@@ -28,6 +28,9 @@ pub struct EditMessage {
     #[specta(type = Option<String>)]
     #[serde(with = "time::serde::rfc3339::option")]
     pub expect_modified: Option<OffsetDateTime>,
+    /// The space of the message, which saves looking it up to check the content rules.
+    #[serde(default)]
+    pub space_id: Option<Uuid>,
 }
 
 #[derive(Deserialize, Debug, specta::Type)]
@@ -68,6 +71,17 @@ pub struct MessageIdQuery {
     pub space_id: Option<Uuid>,
 }
 
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteMessage {
+    pub id: Uuid,
+    #[serde(default)]
+    pub space_id: Option<Uuid>,
+    /// Revert the Entry Effects of the message first. Fails if they conflict with later changes.
+    #[serde(default)]
+    pub revert_entry_effects: bool,
+}
+
 #[derive(Deserialize, Debug, Clone, Copy, specta::Type)]
 #[serde(rename_all = "lowercase")]
 pub enum SearchDirection {
@@ -1,5 +1,6 @@
 use super::Message;
 use super::api::{EditMessage, NewMessage};
+use super::slow_mode::SLOW_MODE;
 use crate::channels::{Channel, ChannelMember};
 use crate::csrf::authenticate;
 use crate::error::{AppError, Find};
@@ -7,16 +8,17 @@ use crate::events::Update;
 use crate::interface;
 use crate::interface::{Response, missing, ok_response, parse_query, response};
 use crate::messages::api::{
-    GetMessagesByChannel, MessageIdQuery, MoveMessageBetween, SearchDirection, SearchFilter,
-    SearchMessagesParams, SearchMessagesResult, SearchNameFilter,
+    DeleteMessage, GetMessagesByChannel, MessageIdQuery, MoveMessageBetween, SearchDirection,
+    SearchFilter, SearchMessagesParams, SearchMessagesResult, SearchNameFilter,
 };
 use crate::notify;
+use crate::random_tables::RandomTables;
 use crate::rate_limit;
-use crate::spaces::SpaceMember;
+use crate::spaces::{ContentFilter, SpaceMember};
 use governor::{DefaultKeyedRateLimiter, RateLimiter};
 use hyper::Request;
 use hyper::body::Body;
-use std::sync::LazyLock;
+use std::sync::{Arc, LazyLock};
 use uuid::Uuid;
 
 static SEND_MESSAGE_LIMITER: LazyLock<DefaultKeyedRateLimiter<Uuid>> = LazyLock::new(|| {
@@ -29,13 +31,59 @@ pub fn start_rate_limiter_cleanup() {
     rate_limit::start_cleanup_task(
         || {
             SEND_MESSAGE_LIMITER.retain_recent();
+            SLOW_MODE.retain_recent(std::time::Instant::now());
         },
         || {
             SEND_MESSAGE_LIMITER.shrink_to_fit();
+            SLOW_MODE.shrink_to_fit();
         },
     );
 }
 
+/// Resolve the channel a message is posted in, the sender's memberships and the content filter.
+async fn resolve_sender(
+    ctx: &crate::context::AppContext,
+    channel_id: Uuid,
+    space_id: Option<Uuid>,
+    user_id: Uuid,
+) -> Result<(Channel, ChannelMember, SpaceMember, Arc<ContentFilter>), AppError> {
+    let resolved = ctx
+        .space_store
+        .resolve_channel(channel_id, space_id)
+        .await?
+        .or_not_found()?;
+    let content_filter = match &resolved.snapshot {
+        Some(snapshot) => snapshot.content_filter.clone(),
+        None => {
+            ctx.space_store
+                .resolve_content_filter(resolved.channel.space_id)
+                .await?
+        }
+    };
+    let (channel, channel_member, space_member) = if let Some(snapshot) = resolved.snapshot {
+        let channel_member = snapshot
+            .channel_members
+            .get(&channel_id)
+            .and_then(|members| members.get(&user_id))
+            .cloned()
+            .or_no_permission()?;
+        let space_member = snapshot
+            .space_members
+            .get(&user_id)
+            .cloned()
+            .or_no_permission()?;
+        (resolved.channel, channel_member, space_member)
+    } else {
+        let channel = resolved.channel;
+        let (channel_member, space_member) =
+            ChannelMember::get_with_space_member(&ctx.db, user_id, channel_id, &channel.space_id)
+                .await
+                .or_no_permission()?;
+        (channel, channel_member, space_member)
+    };
+    Ok((channel, channel_member, space_member, content_filter))
+}
+
 async fn send(
     ctx: &crate::context::AppContext,
     req: Request<impl Body>,
@@ -63,36 +111,12 @@ async fn send(
         pos: request_pos,
         mut color,
     } = *new_message;
-    let resolved = ctx
-        .space_store
-        .resolve_channel(channel_id, space_id)
-        .await?
-        .or_not_found()?;
-    let (channel, channel_member, space_member) = if let Some(snapshot) = resolved.snapshot {
-        let channel_member = snapshot
-            .channel_members
-            .get(&channel_id)
-            .and_then(|members| members.get(&session.user_id))
-            .cloned()
-            .or_no_permission()?;
-        let space_member = snapshot
-            .space_members
-            .get(&session.user_id)
-            .cloned()
-            .or_no_permission()?;
-        (resolved.channel, channel_member, space_member)
-    } else {
-        let channel = resolved.channel;
-        let (channel_member, space_member) = ChannelMember::get_with_space_member(
-            &ctx.db,
-            session.user_id,
-            channel_id,
-            &channel.space_id,
-        )
-        .await
-        .or_no_permission()?;
-        (channel, channel_member, space_member)
-    };
+    let (channel, channel_member, space_member, content_filter) =
+        resolve_sender(ctx, channel_id, space_id, session.user_id).await?;
+    channel.check_posting(channel_member.is_master, in_game, &text)?;
+    let filtered = content_filter.apply(&text)?;
+    let flagged_by = filtered.flagged_by.cloned();
+    let text = filtered.text.into_owned();
     if let Some(character_id) = character_id {
         if !in_game {
             return Err(AppError::BadRequest(
@@ -116,6 +140,8 @@ async fn send(
             )
             .await?;
         }
+    } else {
+        name = content_filter.check(&name)?.into_owned();
     }
     if let Some(portrait_id) = portrait_id {
         let portrait =
@@ -128,6 +154,15 @@ async fn send(
             ));
         }
     }
+    if channel.slow_mode_seconds > 0 && !channel_member.is_master {
+        let interval = std::time::Duration::from_secs(channel.slow_mode_seconds as u64);
+        SLOW_MODE
+            .check(channel_id, session.user_id, interval, start_time)
+            .map_err(|_| {
+                AppError::LimitExceeded("Slow mode is on, please wait before sending again.")
+            })?;
+    }
+    let tables = RandomTables::for_entities(&ctx.db, channel.space_id, &entities).await?;
     let message = Message::create(
         &ctx.db,
         preview_id,
@@ -152,8 +187,15 @@ async fn send(
     .inspect_err(|_| {
         metrics::counter!("boluo_server_messages_created_failed_total").increment(1);
     })?;
+    let message = match &tables {
+        Some(tables) => message.roll_tables(&ctx.db, tables).await?,
+        None => message,
+    };
     notify::space_activity(ctx, channel.space_id, Some(message.created));
     Update::new_message(space_member.space_id, message.clone(), preview_id).await;
+    if let Some(rule) = flagged_by {
+        crate::reports::flag_message(ctx, channel.space_id, &message, &rule).await;
+    }
 
     metrics::counter!("boluo_server_messages_created_total").increment(1);
     metrics::histogram!("boluo_server_messages_create_duration_ms")
@@ -178,15 +220,30 @@ async fn edit(
         media_id,
         color,
         expect_modified,
+        space_id,
     } = *edit_message;
-    let text = &*text;
-    let name = &*name;
+    let space_id = match space_id {
+        Some(space_id) => space_id,
+        None => Message::get_space_id(&ctx.db, &message_id)
+            .await
+            .or_not_found()?,
+    };
+    let message = Message::get(&ctx.db, &message_id, Some(&session.user_id))
+        .await
+        .or_not_found()?;
+    let (channel, channel_member, _, content_filter) =
+        resolve_sender(ctx, message.channel_id, Some(space_id), session.user_id).await?;
+    channel.check_posting(channel_member.is_master, in_game, &text)?;
+    let filtered = content_filter.apply(&text)?;
+    let name = content_filter.check(&name)?;
+    let tables = RandomTables::for_entities(&ctx.db, space_id, &entities).await?;
     let edit_outcome = Message::edit(
         &ctx.db,
         session.user_id,
-        name,
+        space_id,
+        &name,
         &message_id,
-        text,
+        &filtered.text,
         entities,
         in_game,
         is_action,
@@ -212,8 +269,15 @@ async fn edit(
             ));
         }
     };
+    let edited_message = match &tables {
+        Some(tables) => edited_message.roll_tables(&ctx.db, tables).await?,
+        None => edited_message,
+    };
     metrics::counter!("boluo_server_messages_edited_total").increment(1);
     Update::message_edited(space_id, edited_message.clone(), edited_message.pos).await;
+    if let Some(rule) = filtered.flagged_by {
+        crate::reports::flag_message(ctx, space_id, &edited_message, rule).await;
+    }
     metrics::histogram!("boluo_server_messages_edit_duration_ms")
         .record(start_time.elapsed().as_millis() as f64);
     Ok(edited_message)
@@ -359,7 +423,11 @@ async fn delete(
     req: Request<impl Body>,
 ) -> Result<Message, AppError> {
     let session = authenticate(ctx, &req).await?;
-    let MessageIdQuery { id, space_id } = interface::parse_query(req.uri())?;
+    let DeleteMessage {
+        id,
+        space_id,
+        revert_entry_effects,
+    } = interface::parse_query(req.uri())?;
     let message = Message::get(&ctx.db, &id, Some(&session.user_id))
         .await
         .or_not_found()?;
@@ -369,7 +437,28 @@ async fn delete(
     if !space_member.is_admin && message.sender_id != session.user_id {
         return Err(AppError::NoPermission("user id mismatch".to_string()));
     }
-    Message::delete(&ctx.db, &id).await?;
+    if revert_entry_effects && message.has_entry_effects {
+        // The effects are reverted along with the deletion, or neither happens.
+        let mutation = ctx.space_store.acquire_mutation(space_id).await?;
+        let mut transaction = ctx.db.begin().await?;
+        let (revert, changes) = crate::entries::handlers::revert_message_effects_in(
+            ctx,
+            &mut transaction,
+            space_id,
+            message.id,
+            session.user_id,
+            false,
+        )
+        .await?;
+        if !revert.conflicts.is_empty() {
+            return Err(AppError::Conflict("EntryComponent".to_string()));
+        }
+        Message::delete(&mut *transaction, &id).await?;
+        let mutation = mutation.commit(transaction).await?;
+        changes.apply_with_mutation(ctx, &mutation).await;
+    } else {
+        Message::delete(&ctx.db, &id).await?;
+    }
     Update::message_deleted(space_id, message.channel_id, message.id, message.pos).await;
     crate::messages::MESSAGE_POSITIONS.cancel(message.channel_id, message.id);
     metrics::counter!("boluo_server_messages_deleted_total").increment(1);
@@ -600,9 +600,24 @@ impl Message {
             .await
             .map_err(Into::into)
     }
+    /// Store the rolls on random tables, which are made from the seed of the stored message.
+    pub(super) async fn roll_tables<'c, T: sqlx::PgExecutor<'c>>(
+        self,
+        db: T,
+        tables: &crate::random_tables::RandomTables,
+    ) -> Result<Message, ModelError> {
+        let mut entities = self.entities;
+        tables.resolve(&self.seed, &mut entities);
+        let entities = serde_json::to_value(entities).unwrap_or(JsonValue::Array(vec![]));
+        sqlx::query_file_scalar!("sql/messages/set_table_rolls.sql", self.id, entities)
+            .fetch_one(db)
+            .await
+            .map_err(Into::into)
+    }
     pub(super) async fn edit(
         db: &sqlx::PgPool,
         user_id: Uuid,
+        space_id: Uuid,
         name: &str,
         id: &Uuid,
         text: &str,
@@ -627,7 +642,8 @@ impl Message {
             media_id,
             color,
             expect_modified,
-            user_id
+            user_id,
+            space_id
         )
         .fetch_optional(db)
         .await?;
@@ -646,7 +662,8 @@ impl Message {
             "sql/messages/get_edit_status.sql",
             id,
             user_id,
-            expect_modified
+            expect_modified,
+            space_id
         )
         .fetch_one(db)
         .await?;
@@ -670,6 +687,15 @@ impl Message {
         Ok(MessageEditOutcome::Conflict)
     }
 
+    pub async fn get_space_id<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        id: &Uuid,
+    ) -> Result<Option<Uuid>, sqlx::Error> {
+        sqlx::query_file_scalar!("sql/messages/get_space_id.sql", id)
+            .fetch_optional(db)
+            .await
+    }
+
     pub async fn delete<'c, T: sqlx::PgExecutor<'c>>(db: T, id: &Uuid) -> Result<u64, sqlx::Error> {
         sqlx::query_file!("sql/messages/delete.sql", id)
             .execute(db)
@@ -1181,6 +1207,7 @@ mod tests {
         let edited = Message::edit(
             &pool,
             owner.id,
+            space.id,
             "GM Updated",
             &message.id,
             "Updated text",
@@ -1225,6 +1252,7 @@ mod tests {
         let edited_after_delete = Message::edit(
             &pool,
             owner.id,
+            space.id,
             "Deleted",
             &message.id,
             "Deleted text",
@@ -2066,6 +2094,7 @@ mod tests {
         let edited_by_a = Message::edit(
             &pool,
             owner.id,
+            space.id,
             "GM",
             &message.id,
             "Tab A's text",
@@ -2093,6 +2122,7 @@ mod tests {
         let edited_by_b = Message::edit(
             &pool,
             owner.id,
+            space.id,
             "GM",
             &message.id,
             "Tab B's text",
@@ -2123,6 +2153,7 @@ mod tests {
         let edited_without_precondition = Message::edit(
             &pool,
             owner.id,
+            space.id,
             "GM",
             &message.id,
             "Tab C's text",
@@ -2188,6 +2219,7 @@ mod tests {
             Message::edit(
                 &pool,
                 user_id,
+                space.id,
                 "Player",
                 &message.id,
                 "Unauthorized edit",
@@ -2263,6 +2295,7 @@ mod tests {
         let document_outcome = Message::edit(
             &pool,
             member.id,
+            space.id,
             "Player",
             &document_message.id,
             "Member edit",
@@ -2283,6 +2316,7 @@ mod tests {
         let missing_outcome = Message::edit(
             &pool,
             owner.id,
+            space.id,
             "GM",
             &Uuid::new_v4(),
             "Missing",
@@ -2300,6 +2334,27 @@ mod tests {
             MessageEditOutcome::MessageNotFound
         ));
 
+        let wrong_space_outcome = Message::edit(
+            &pool,
+            owner.id,
+            Uuid::new_v4(),
+            "GM",
+            &message.id,
+            "Wrong space",
+            sample_entities("Wrong space"),
+            false,
+            false,
+            None,
+            "#abcdef".to_string(),
+            None,
+        )
+        .await
+        .expect("wrong space edit attempt errored");
+        assert!(matches!(
+            wrong_space_outcome,
+            MessageEditOutcome::ChannelNotFound
+        ));
+
         let current = Message::get(&pool, &message.id, Some(&owner.id))
             .await
             .expect("failed to reload message")
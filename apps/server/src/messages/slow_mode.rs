@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use uuid::Uuid;
+
+use crate::channels::models::MAX_SLOW_MODE_SECONDS;
+
+/// The last message time of each user in each channel with slow mode on.
+///
+/// Like the other rate limiters, this is kept in memory and per node.
+pub(super) struct SlowMode {
+    last_sent: Mutex<HashMap<(Uuid, Uuid), Instant>>,
+}
+
+pub(super) static SLOW_MODE: LazyLock<SlowMode> = LazyLock::new(SlowMode::new);
+
+impl SlowMode {
+    fn new() -> Self {
+        SlowMode {
+            last_sent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Take the next message slot of the user, or return how long they still have to wait.
+    pub(super) fn check(
+        &self,
+        channel_id: Uuid,
+        user_id: Uuid,
+        interval: Duration,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        let mut last_sent = self.last_sent.lock().expect("slow mode mutex poisoned");
+        if let Some(last) = last_sent.get(&(channel_id, user_id)) {
+            let elapsed = now.saturating_duration_since(*last);
+            if elapsed < interval {
+                return Err(interval - elapsed);
+            }
+        }
+        last_sent.insert((channel_id, user_id), now);
+        Ok(())
+    }
+
+    pub(super) fn retain_recent(&self, now: Instant) {
+        let max_interval = Duration::from_secs(MAX_SLOW_MODE_SECONDS as u64);
+        self.last_sent
+            .lock()
+            .expect("slow mode mutex poisoned")
+            .retain(|_, last| now.saturating_duration_since(*last) < max_interval);
+    }
+
+    pub(super) fn shrink_to_fit(&self) {
+        self.last_sent
+            .lock()
+            .expect("slow mode mutex poisoned")
+            .shrink_to_fit();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slow_mode_waits_per_user_and_channel() {
+        let slow_mode = SlowMode::new();
+        let channel = Uuid::now_v7();
+        let other_channel = Uuid::now_v7();
+        let user = Uuid::now_v7();
+        let other_user = Uuid::now_v7();
+        let interval = Duration::from_secs(10);
+        let start = Instant::now();
+
+        assert!(slow_mode.check(channel, user, interval, start).is_ok());
+        assert_eq!(
+            slow_mode.check(channel, user, interval, start + Duration::from_secs(4)),
+            Err(Duration::from_secs(6))
+        );
+        assert!(
+            slow_mode
+                .check(
+                    channel,
+                    other_user,
+                    interval,
+                    start + Duration::from_secs(4)
+                )
+                .is_ok()
+        );
+        assert!(
+            slow_mode
+                .check(
+                    other_channel,
+                    user,
+                    interval,
+                    start + Duration::from_secs(4)
+                )
+                .is_ok()
+        );
+        assert!(
+            slow_mode
+                .check(channel, user, interval, start + Duration::from_secs(10))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn retain_recent_drops_expired_entries() {
+        let slow_mode = SlowMode::new();
+        let start = Instant::now();
+        let interval = Duration::from_secs(1);
+        slow_mode
+            .check(Uuid::now_v7(), Uuid::now_v7(), interval, start)
+            .unwrap();
+        slow_mode.retain_recent(start + Duration::from_secs(MAX_SLOW_MODE_SECONDS as u64));
+        assert!(slow_mode.last_sent.lock().unwrap().is_empty());
+    }
+}
@@ -1,6 +1,9 @@
 mod api;
+pub(crate) mod collab;
+mod diff;
 pub(crate) mod handlers;
+mod links;
 mod models;
 
 pub use handlers::router;
-pub use models::{Note, NoteContentRevision, NoteMetadata};
+pub use models::{Note, NoteContentRevision, NoteMetadata, NoteRevisionPolicy};
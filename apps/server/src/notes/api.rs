@@ -1,7 +1,9 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use shared_types::entities::WikiLinkKind;
 use shared_types::messages::Entities;
 use uuid::Uuid;
 
+use super::diff::DiffLine;
 use crate::spaces::AccessPolicy;
 
 #[derive(Deserialize, Debug, specta::Type)]
@@ -52,6 +54,10 @@ pub struct EditNote {
     pub entities: Entities,
     pub access_policy: AccessPolicy,
     pub access_channel_id: Option<Uuid>,
+    /// Merge the changes into the note if it was changed since `expected_revision`, instead of
+    /// failing with a conflict
+    #[serde(default)]
+    pub merge: bool,
 }
 
 #[derive(Deserialize, Debug, specta::Type)]
@@ -71,3 +77,158 @@ pub struct RestoreNote {
     #[specta(type = f64)]
     pub expected_revision: i64,
 }
+
+/// Compare two content revisions of a note, or a revision with the current content.
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffNoteRevisions {
+    pub space_id: Uuid,
+    pub note_id: Uuid,
+    #[specta(type = f64)]
+    pub from: i64,
+    /// The current content if not given
+    #[serde(default)]
+    #[specta(type = Option<f64>)]
+    pub to: Option<i64>,
+    /// Only return the changed lines and this many lines around them
+    #[serde(default)]
+    pub context: Option<u32>,
+}
+
+#[derive(Serialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteRevisionDiff {
+    #[specta(type = f64)]
+    pub from: i64,
+    #[specta(type = f64)]
+    pub to: i64,
+    pub old_title: String,
+    pub new_title: String,
+    pub lines: Vec<DiffLine>,
+}
+
+/// Edit the note back to the content of an old revision, as a new revision.
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreNoteRevision {
+    pub space_id: Uuid,
+    pub note_id: Uuid,
+    #[specta(type = f64)]
+    pub revision: i64,
+    #[specta(type = f64)]
+    pub expected_revision: i64,
+}
+
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryNoteRevisionPolicy {
+    pub space_id: Uuid,
+}
+
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateNoteRevisionPolicy {
+    pub space_id: Uuid,
+    #[serde(default)]
+    pub keep_latest: Option<i32>,
+    #[serde(default)]
+    pub keep_days: Option<i32>,
+}
+
+/// The target of a link, with its current name.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(tag = "kind", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum LinkTarget {
+    #[serde(rename_all = "camelCase")]
+    Note { note_id: Uuid, title: String },
+    #[serde(rename_all = "camelCase")]
+    Entry {
+        scope_id: Uuid,
+        entry_id: Uuid,
+        display_name: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Character { character_id: Uuid, name: String },
+}
+
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteLink {
+    pub kind: Option<WikiLinkKind>,
+    pub target: String,
+    /// `None` if nothing the user can view matches the link
+    pub resolved: Option<LinkTarget>,
+}
+
+/// The notes linking to a note, an entry or a character.
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryBacklinks {
+    pub space_id: Uuid,
+    pub kind: WikiLinkKind,
+    pub id: Uuid,
+    /// Required for entries
+    #[serde(default)]
+    pub scope_id: Option<Uuid>,
+}
+
+/// Replace `len` characters at `i` with `text`. Positions and lengths are in UTF-16 code units.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteSplice {
+    pub i: u32,
+    pub len: u32,
+    #[serde(default)]
+    pub text: String,
+}
+
+/// An edit of the live document of a note, sent over the events connection.
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteEditPost {
+    pub note_id: Uuid,
+    /// The session of the document, from the snapshot
+    pub session: Uuid,
+    /// The version of the document the splices are based on
+    pub version: u32,
+    /// Chosen by the client to recognize its own edit when it is sent back
+    pub edit_id: Uuid,
+    /// Sorted and not overlapping, with the positions in the text of `version`
+    pub splices: Vec<NoteSplice>,
+    /// The entities of the text after the edit, ignored if the document has a newer version
+    #[serde(default)]
+    pub entities: Option<Entities>,
+    /// The revision of the note whose text the splices are on, when `version` may be of another
+    /// session or too old. The edit is then merged from the text of the revision.
+    #[serde(default)]
+    #[specta(type = Option<f64>)]
+    pub revision: Option<i64>,
+}
+
+/// The live document of a note, sent to a connection when it joins the note.
+#[derive(Serialize, Debug, Clone, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteDocumentSnapshot {
+    pub note_id: Uuid,
+    /// Changes every time the document is opened, edits made on another session are merged
+    /// from their revision
+    pub session: Uuid,
+    pub version: u32,
+    /// The revision of the note the document was last saved as
+    #[specta(type = f64)]
+    pub revision: i64,
+    pub text: String,
+}
+
+/// An edit applied to the live document of a note, making its next version.
+#[derive(Serialize, Debug, Clone, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteEdited {
+    pub note_id: Uuid,
+    pub session: Uuid,
+    pub version: u32,
+    pub sender: Uuid,
+    pub edit_id: Uuid,
+    /// Splices on the text of the previous version, applied from the last to the first
+    pub splices: Vec<NoteSplice>,
+}
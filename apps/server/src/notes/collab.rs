@@ -0,0 +1,867 @@
+//! Live documents of notes, edited by several users at once.
+//!
+//! An edit is a set of splices on the text of one version of the document. Edits based on an
+//! older version are moved over the edits made since, so concurrent edits merge instead of
+//! overwriting each other, and the result is sent to the connections which joined the note.
+//! Edits of a closed session or older than the kept versions are merged by lines from the
+//! revision of the note they are based on.
+//! Documents are saved as a revision of the note once nobody has edited them for a while. If the
+//! note was changed meanwhile, the text of the document is merged into it. Edits which can't be
+//! saved are rejected, so the editors know to keep their text.
+
+pub use super::api::{NoteDocumentSnapshot, NoteEditPost, NoteEdited, NoteSplice};
+use super::diff::{DIFF_TIMEOUT, merge_text};
+use super::handlers::{can_edit_note, can_view_note, prune_revisions};
+use super::models::{Note, NoteContentRevision};
+use crate::committed_changes::CommittedChanges;
+use crate::error::{AppError, Find, ModelError};
+use crate::events::Update;
+use shared_types::entities::{Entity, Span};
+use shared_types::messages::Entities;
+use similar::{ChangeTag, TextDiff};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, OwnedMutexGuard, broadcast, mpsc};
+use tokio_tungstenite::tungstenite::Utf8Bytes;
+use uuid::Uuid;
+
+/// Edits based on a version older than this many versions are merged from their revision.
+const HISTORY_LEN: usize = 256;
+const MAX_SPLICES: usize = 256;
+const TEXT_MAX_LEN: u64 = 1 << 20;
+const MAX_JOINED_NOTES: usize = 8;
+/// Save a document once nobody has edited it for this long.
+const SAVE_IDLE: Duration = Duration::from_secs(5);
+/// Save a document edited without a pause at least this often.
+const SAVE_MAX_DELAY: Duration = Duration::from_secs(60);
+const SAVE_INTERVAL_SECS: u64 = 5;
+
+type Documents = papaya::HashMap<Uuid, Arc<Mutex<NoteDocument>>, ahash::RandomState>;
+
+static DOCUMENTS: LazyLock<Documents> =
+    LazyLock::new(|| papaya::HashMap::with_hasher(ahash::RandomState::new()));
+
+fn utf16_len(text: &str) -> u32 {
+    text.encode_utf16().count() as u32
+}
+
+/// The byte offset of a position in UTF-16 code units, `None` if it is out of the text or in
+/// the middle of a character.
+fn byte_offset(text: &str, position: u32) -> Option<usize> {
+    let mut units = 0;
+    for (offset, c) in text.char_indices() {
+        if units == position {
+            return Some(offset);
+        }
+        if units > position {
+            return None;
+        }
+        units += c.len_utf16() as u32;
+    }
+    (units == position).then_some(text.len())
+}
+
+fn validate_splices(splices: &[NoteSplice], text_len: u32) -> Result<(), AppError> {
+    let invalid = || AppError::BadRequest("The splices are out of order or range".to_string());
+    if splices.len() > MAX_SPLICES {
+        return Err(AppError::BadRequest("Too many splices".to_string()));
+    }
+    let mut previous: Option<(u32, u32)> = None;
+    for splice in splices {
+        let end = splice
+            .i
+            .checked_add(splice.len)
+            .filter(|end| *end <= text_len)
+            .ok_or_else(invalid)?;
+        if let Some((start, previous_end)) = previous
+            && (splice.i <= start || splice.i < previous_end)
+        {
+            return Err(invalid());
+        }
+        previous = Some((splice.i, end));
+    }
+    Ok(())
+}
+
+/// Apply splices made on the same text, `None` without changing the text if one of them is in
+/// the middle of a character.
+fn apply_splices(text: &mut String, splices: &[NoteSplice]) -> Option<()> {
+    let ranges = splices
+        .iter()
+        .map(|splice| Some(byte_offset(text, splice.i)?..byte_offset(text, splice.i + splice.len)?))
+        .collect::<Option<Vec<_>>>()?;
+    for (splice, range) in splices.iter().zip(ranges).rev() {
+        text.replace_range(range, &splice.text);
+    }
+    Some(())
+}
+
+/// Where a position ends up after the splices. The start of a range moves after the text
+/// inserted at it and the end stays before, so a range never replaces text inserted next to it.
+fn map_position(position: u32, over: &[NoteSplice], start: bool) -> u32 {
+    let mut delta: i64 = 0;
+    for splice in over {
+        if position < splice.i || (!start && position == splice.i) {
+            break;
+        }
+        let inserted = utf16_len(&splice.text) as i64;
+        if position >= splice.i + splice.len {
+            delta += inserted - splice.len as i64;
+            continue;
+        }
+        // The position was replaced.
+        let moved = if start { inserted } else { 0 };
+        return (splice.i as i64 + delta + moved) as u32;
+    }
+    (position as i64 + delta) as u32
+}
+
+/// Move splices made on one version over the splices which made the next version.
+///
+/// Text inserted by `over` within a replaced range is kept, by cutting the range around it.
+fn rebase(splices: &[NoteSplice], over: &[NoteSplice]) -> Vec<NoteSplice> {
+    let mut inserted = Vec::new();
+    let mut delta: i64 = 0;
+    for splice in over {
+        let len = utf16_len(&splice.text);
+        if len > 0 {
+            inserted.push(((splice.i as i64 + delta) as u32, len));
+        }
+        delta += len as i64 - splice.len as i64;
+    }
+    let mut rebased = Vec::with_capacity(splices.len());
+    for splice in splices {
+        let start = map_position(splice.i, over, true);
+        let end = map_position(splice.i + splice.len, over, false).max(start);
+        let mut text = Some(splice.text.clone());
+        let mut cursor = start;
+        if splice.len > 0 {
+            for &(at, len) in &inserted {
+                if at >= cursor && at + len <= end {
+                    rebased.push(NoteSplice {
+                        i: cursor,
+                        len: at - cursor,
+                        text: text.take().unwrap_or_default(),
+                    });
+                    cursor = at + len;
+                }
+            }
+        }
+        rebased.push(NoteSplice {
+            i: cursor,
+            len: end - cursor,
+            text: text.take().unwrap_or_default(),
+        });
+    }
+    rebased.retain(|splice| splice.len > 0 || !splice.text.is_empty());
+    rebased
+}
+
+/// The splices which turn `old` into `new`.
+fn diff_splices(old: &str, new: &str) -> Vec<NoteSplice> {
+    let diff = TextDiff::configure()
+        .timeout(DIFF_TIMEOUT)
+        .diff_chars(old, new);
+    let mut splices: Vec<NoteSplice> = Vec::new();
+    let mut position = 0;
+    for change in diff.iter_all_changes() {
+        let len = utf16_len(change.value());
+        if change.tag() == ChangeTag::Equal {
+            position += len;
+            continue;
+        }
+        let splice = match splices.last_mut() {
+            Some(last) if last.i + last.len == position => last,
+            _ => {
+                splices.push(NoteSplice {
+                    i: position,
+                    len: 0,
+                    text: String::new(),
+                });
+                splices.last_mut().expect("just pushed")
+            }
+        };
+        if change.tag() == ChangeTag::Delete {
+            splice.len += len;
+            position += len;
+        } else {
+            splice.text.push_str(change.value());
+        }
+    }
+    splices
+}
+
+struct HistoryEntry {
+    /// The length of the text the splices were made on
+    text_len: u32,
+    splices: Vec<NoteSplice>,
+}
+
+pub struct NoteDocument {
+    space_id: Uuid,
+    note_id: Uuid,
+    session: Uuid,
+    /// The revision of the note the document was loaded from or last saved as
+    revision: i64,
+    text: String,
+    /// `None` if the entities sent by the editors are stale
+    entities: Option<Entities>,
+    version: u32,
+    saved_version: u32,
+    /// The edits which made the latest versions, the oldest first
+    history: VecDeque<HistoryEntry>,
+    last_editor: Option<Uuid>,
+    /// The last unsaved edit of each editor, rejected if the edits can't be saved
+    unsaved_edits: HashMap<Uuid, Uuid>,
+    edited_at: Instant,
+    unsaved_since: Option<Instant>,
+    closed: bool,
+}
+
+impl NoteDocument {
+    fn load(note: Note) -> Self {
+        Self {
+            space_id: note.metadata.space_id,
+            note_id: note.metadata.id,
+            session: Uuid::now_v7(),
+            revision: note.metadata.revision,
+            text: note.text,
+            entities: Some(note.entities),
+            version: 0,
+            saved_version: 0,
+            history: VecDeque::new(),
+            last_editor: None,
+            unsaved_edits: HashMap::new(),
+            edited_at: Instant::now(),
+            unsaved_since: None,
+            closed: false,
+        }
+    }
+
+    fn snapshot(&self) -> NoteDocumentSnapshot {
+        NoteDocumentSnapshot {
+            note_id: self.note_id,
+            session: self.session,
+            version: self.version,
+            revision: self.revision,
+            text: self.text.clone(),
+        }
+    }
+
+    pub fn has_unsaved_edits(&self) -> bool {
+        self.version != self.saved_version
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The entities of the text, or the text as plain text if the entities are stale.
+    pub fn entities(&self) -> Entities {
+        match &self.entities {
+            Some(entities) => entities.clone(),
+            None => plain_entities(&self.text),
+        }
+    }
+
+    fn save_due(&self) -> bool {
+        self.unsaved_since.is_some_and(|since| {
+            self.edited_at.elapsed() >= SAVE_IDLE || since.elapsed() >= SAVE_MAX_DELAY
+        })
+    }
+
+    /// Whether the version the edit is based on is kept, so its splices can be moved over the
+    /// versions since.
+    fn has_base(&self, post: &NoteEditPost) -> bool {
+        post.session == self.session
+            && post.version <= self.version
+            && (self.version - post.version) as usize <= self.history.len()
+    }
+
+    /// Apply an edit based on a kept version, see [`Self::has_base`].
+    fn apply(&mut self, user_id: Uuid, post: NoteEditPost) -> Result<NoteEdited, AppError> {
+        let behind = (self.version - post.version) as usize;
+        let missed = self.history.range(self.history.len() - behind..);
+        let base_len = missed
+            .clone()
+            .next()
+            .map_or_else(|| utf16_len(&self.text), |entry| entry.text_len);
+        validate_splices(&post.splices, base_len)?;
+        let splices = missed.fold(post.splices, |splices, entry| {
+            rebase(&splices, &entry.splices)
+        });
+        let entities = if behind == 0 { post.entities } else { None };
+        self.push(user_id, post.edit_id, splices, entities)
+    }
+
+    /// Apply an edit made on the text of an earlier revision of the note, by merging the text it
+    /// makes into the document.
+    fn merge(
+        &mut self,
+        user_id: Uuid,
+        post: NoteEditPost,
+        base: &str,
+    ) -> Result<NoteEdited, AppError> {
+        validate_splices(&post.splices, utf16_len(base))?;
+        let mut theirs = base.to_string();
+        apply_splices(&mut theirs, &post.splices).ok_or_else(|| {
+            AppError::BadRequest("The splices are in the middle of a character".to_string())
+        })?;
+        let merged = merge_text(base, &self.text, &theirs);
+        let entities = if merged == theirs {
+            post.entities
+        } else {
+            None
+        };
+        let splices = diff_splices(&self.text, &merged);
+        self.push(user_id, post.edit_id, splices, entities)
+    }
+
+    /// Make the next version with splices on the current text.
+    fn push(
+        &mut self,
+        user_id: Uuid,
+        edit_id: Uuid,
+        splices: Vec<NoteSplice>,
+        entities: Option<Entities>,
+    ) -> Result<NoteEdited, AppError> {
+        let text_len = utf16_len(&self.text);
+        let new_len = splices.iter().fold(text_len as i64, |len, splice| {
+            len + utf16_len(&splice.text) as i64 - splice.len as i64
+        });
+        if new_len as u64 > TEXT_MAX_LEN {
+            return Err(AppError::BadRequest("The note is too long".to_string()));
+        }
+        apply_splices(&mut self.text, &splices).ok_or_else(|| {
+            AppError::BadRequest("The splices are in the middle of a character".to_string())
+        })?;
+        self.entities = entities;
+        self.version += 1;
+        self.history.push_back(HistoryEntry {
+            text_len,
+            splices: splices.clone(),
+        });
+        if self.history.len() > HISTORY_LEN {
+            self.history.pop_front();
+        }
+        let now = Instant::now();
+        self.last_editor = Some(user_id);
+        self.unsaved_edits.insert(user_id, edit_id);
+        self.edited_at = now;
+        self.unsaved_since.get_or_insert(now);
+        Ok(NoteEdited {
+            note_id: self.note_id,
+            session: self.session,
+            version: self.version,
+            sender: user_id,
+            edit_id,
+            splices,
+        })
+    }
+
+    /// Save the text as a new revision of the note. If the note was changed since the document
+    /// was last saved, the text is merged into the current one, and the saved text differs from
+    /// the document's. If the revision the document was loaded from is pruned, there is nothing to
+    /// merge from and the text replaces the current one, which is still kept as a revision.
+    pub async fn save(
+        &self,
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        note: &Note,
+    ) -> Result<Option<Note>, ModelError> {
+        let Some(editor) = self.last_editor else {
+            return Ok(None);
+        };
+        let metadata = &note.metadata;
+        let (text, entities) = if metadata.revision == self.revision {
+            (self.text.clone(), self.entities())
+        } else {
+            let base = NoteContentRevision::get(&mut **db, &self.note_id, self.revision).await?;
+            let merged = match base {
+                Some(base) => merge_text(&base.text, &note.text, &self.text),
+                None => self.text.clone(),
+            };
+            let entities = if merged == self.text {
+                self.entities()
+            } else if merged == note.text {
+                note.entities.clone()
+            } else {
+                plain_entities(&merged)
+            };
+            (merged, entities)
+        };
+        Note::update(
+            db,
+            self.space_id,
+            self.note_id,
+            metadata.revision,
+            metadata.title.to_string(),
+            metadata.keywords.iter().map(ToString::to_string).collect(),
+            metadata.tags.iter().map(ToString::to_string).collect(),
+            text,
+            entities,
+            metadata.access_policy,
+            metadata.access_channel_id,
+            editor,
+        )
+        .await
+    }
+
+    /// Call after the transaction of `save` is committed.
+    pub fn mark_saved(&mut self, revision: i64) {
+        self.revision = revision;
+        self.saved_version = self.version;
+        self.unsaved_edits.clear();
+        self.unsaved_since = None;
+    }
+
+    /// Tell the editors their unsaved edits were not kept, before the document is closed.
+    fn reject_unsaved(&self, reason: &str) {
+        let Some(sender) = crate::events::get_broadcast_table()
+            .pin()
+            .get(&self.note_id)
+            .cloned()
+        else {
+            return;
+        };
+        for edit_id in self.unsaved_edits.values() {
+            let update =
+                Update::note_edit_rejected(self.space_id, self.note_id, *edit_id, reason.into());
+            sender.send(update).ok();
+        }
+    }
+}
+
+pub(super) fn plain_entities(text: &str) -> Entities {
+    if text.is_empty() {
+        return Entities::default();
+    }
+    let len = utf16_len(text) as i32;
+    Entities(vec![Entity::Text(Span { start: 0, len })])
+}
+
+/// Lock the live document of the note if it is open, so it can be saved or merged along with
+/// other changes of the note.
+pub async fn lock_document(note_id: Uuid) -> Option<OwnedMutexGuard<NoteDocument>> {
+    let document = DOCUMENTS.pin().get(&note_id).cloned()?;
+    let document = document.lock_owned().await;
+    (!document.closed).then_some(document)
+}
+
+/// Close the document. The connections which joined the note are asked to join it again.
+pub fn close(mut document: OwnedMutexGuard<NoteDocument>) {
+    document.closed = true;
+    DOCUMENTS.pin().remove(&document.note_id);
+    // Dropping the sender ends the subscriptions.
+    crate::events::get_broadcast_table()
+        .pin()
+        .remove(&document.note_id);
+}
+
+async fn open(
+    ctx: &crate::context::AppContext,
+    space_id: Uuid,
+    note_id: Uuid,
+) -> Result<OwnedMutexGuard<NoteDocument>, AppError> {
+    let document = match lock_document(note_id).await {
+        Some(document) => document,
+        None => {
+            let note = Note::get_by_id(&ctx.db, space_id, note_id)
+                .await?
+                .or_not_found()?;
+            let document = Arc::new(Mutex::new(NoteDocument::load(note)));
+            let document = DOCUMENTS.pin().get_or_insert(note_id, document).clone();
+            let document = document.lock_owned().await;
+            if document.closed {
+                return Err(AppError::Conflict("The note was just changed".to_string()));
+            }
+            document
+        }
+    };
+    if document.space_id != space_id {
+        return Err(AppError::NotFound("note"));
+    }
+    Ok(document)
+}
+
+/// The notes a connection joined, with their updates sent to the connection.
+pub struct NoteSubscriptions {
+    sender: mpsc::Sender<Utf8Bytes>,
+    joined: parking_lot::Mutex<HashMap<Uuid, tokio::task::AbortHandle>>,
+}
+
+impl NoteSubscriptions {
+    pub fn new() -> (Self, mpsc::Receiver<Utf8Bytes>) {
+        let (sender, receiver) = mpsc::channel(64);
+        let subscriptions = Self {
+            sender,
+            joined: parking_lot::Mutex::new(HashMap::new()),
+        };
+        (subscriptions, receiver)
+    }
+
+    async fn subscribe(
+        &self,
+        space_id: Uuid,
+        note_id: Uuid,
+        snapshot: Utf8Bytes,
+        mut receiver: broadcast::Receiver<Utf8Bytes>,
+    ) -> Result<(), AppError> {
+        {
+            let mut joined = self.joined.lock();
+            joined.retain(|_, task| !task.is_finished());
+            if !joined.contains_key(&note_id) && joined.len() >= MAX_JOINED_NOTES {
+                return Err(AppError::BadRequest("Too many notes joined".to_string()));
+            }
+        }
+        self.send(snapshot).await;
+        let sender = self.sender.clone();
+        let task = tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(update) => {
+                        if sender.send(update).await.is_err() {
+                            break;
+                        }
+                    }
+                    // Lagging behind loses edits, so the document has to be joined again too.
+                    Err(_) => {
+                        sender
+                            .send(Update::note_closed(space_id, note_id))
+                            .await
+                            .ok();
+                        break;
+                    }
+                }
+            }
+        });
+        if let Some(previous) = self.joined.lock().insert(note_id, task.abort_handle()) {
+            previous.abort();
+        }
+        Ok(())
+    }
+
+    pub fn leave(&self, note_id: Uuid) {
+        if let Some(task) = self.joined.lock().remove(&note_id) {
+            task.abort();
+        }
+    }
+
+    /// Leave the note and tell the client so, when joining it failed.
+    pub async fn closed(&self, space_id: Uuid, note_id: Uuid) {
+        self.leave(note_id);
+        self.send(Update::note_closed(space_id, note_id)).await;
+    }
+
+    /// Leave the note and tell the client its edit was not applied.
+    pub async fn rejected(&self, space_id: Uuid, note_id: Uuid, edit_id: Uuid, reason: String) {
+        self.leave(note_id);
+        self.send(Update::note_edit_rejected(
+            space_id, note_id, edit_id, reason,
+        ))
+        .await;
+    }
+
+    async fn send(&self, update: Utf8Bytes) {
+        self.sender.send(update).await.ok();
+    }
+}
+
+impl Drop for NoteSubscriptions {
+    fn drop(&mut self) {
+        for (_, task) in self.joined.lock().drain() {
+            task.abort();
+        }
+    }
+}
+
+/// Join the live document of a note, and receive its snapshot and then its edits.
+pub async fn join(
+    ctx: &crate::context::AppContext,
+    space_id: Uuid,
+    note_id: Uuid,
+    user_id: Option<Uuid>,
+    subscriptions: &NoteSubscriptions,
+) -> Result<(), AppError> {
+    let note = ctx
+        .space_store
+        .resolve_note_metadata(space_id, note_id)
+        .await?
+        .or_not_found()?;
+    if !can_view_note(ctx, &note, user_id).await? {
+        return Err(AppError::NoPermission(
+            "You don't have permission to view this note".to_string(),
+        ));
+    }
+    let document = open(ctx, space_id, note_id).await?;
+    // Subscribe before the lock is released, so no edit is missed after the snapshot.
+    let receiver = crate::events::get_mailbox_broadcast_rx(note_id);
+    let snapshot = Update::note_snapshot(space_id, document.snapshot());
+    subscriptions
+        .subscribe(space_id, note_id, snapshot, receiver)
+        .await
+}
+
+pub async fn edit(
+    ctx: &crate::context::AppContext,
+    space_id: Uuid,
+    user_id: Uuid,
+    post: NoteEditPost,
+) -> Result<(), AppError> {
+    let note_id = post.note_id;
+    let note = ctx
+        .space_store
+        .resolve_note_metadata(space_id, note_id)
+        .await?
+        .or_not_found()?;
+    if note.archived_at.is_some() || !can_edit_note(ctx, &note, user_id).await? {
+        return Err(AppError::NoPermission(
+            "You don't have permission to edit this note".to_string(),
+        ));
+    }
+    // The document is opened again if it was closed since the edit was made.
+    let mut document = open(ctx, space_id, note_id).await?;
+    let edited = if document.has_base(&post) {
+        document.apply(user_id, post)?
+    } else {
+        let revision = post.revision.ok_or_else(|| {
+            AppError::Conflict("The version the edit is based on is not kept".to_string())
+        })?;
+        let base = NoteContentRevision::get(&ctx.db, &note_id, revision)
+            .await?
+            .ok_or_else(|| {
+                AppError::Conflict("The revision the edit is based on was pruned".to_string())
+            })?;
+        document.merge(user_id, post, &base.text)?
+    };
+    // Sent before the lock is released, so the edits are sent in order.
+    Update::note_edited(space_id, edited);
+    Ok(())
+}
+
+async fn save_document(
+    ctx: &crate::context::AppContext,
+    space_id: Uuid,
+    note_id: Uuid,
+) -> Result<(), AppError> {
+    let mutation = ctx.space_store.acquire_mutation(space_id).await?;
+    let Some(mut document) = lock_document(note_id).await else {
+        return Ok(());
+    };
+    if !document.has_unsaved_edits() {
+        return Ok(());
+    }
+    let mut transaction = ctx.db.begin().await?;
+    let saved = match Note::get_by_id(&mut *transaction, space_id, note_id).await? {
+        Some(note) if note.metadata.archived_at.is_none() => {
+            document.save(&mut transaction, &note).await?
+        }
+        _ => None,
+    };
+    let Some(saved) = saved else {
+        tracing::warn!(
+            note_id = %note_id,
+            "The live note could not be saved, the unsaved edits are rejected"
+        );
+        document.reject_unsaved("The note was archived or deleted");
+        close(document);
+        return Ok(());
+    };
+    prune_revisions(&mut transaction, space_id, note_id).await?;
+    let mutation = mutation.commit(transaction).await?;
+    if saved.text == document.text {
+        document.mark_saved(saved.metadata.revision);
+    } else {
+        // The edits were merged with other changes of the note, the document is loaded again.
+        close(document);
+    }
+    let mut changes = CommittedChanges::default();
+    changes.note_updated(&saved.metadata);
+    changes.apply_with_mutation(ctx, &mutation).await;
+    Ok(())
+}
+
+/// Save the documents due to be saved, or all of them, and close the ones nobody has joined.
+async fn save_documents(ctx: &crate::context::AppContext, all: bool) {
+    let documents: Vec<_> = DOCUMENTS.pin().values().cloned().collect();
+    for document in documents {
+        let document = document.lock_owned().await;
+        if document.closed {
+            continue;
+        }
+        let (space_id, note_id) = (document.space_id, document.note_id);
+        if !document.has_unsaved_edits() {
+            let joined = crate::events::get_broadcast_table()
+                .pin()
+                .get(&note_id)
+                .is_some_and(|sender| sender.receiver_count() > 0);
+            if !joined {
+                close(document);
+            }
+            continue;
+        }
+        let due = all || document.save_due();
+        drop(document);
+        if due && let Err(err) = save_document(ctx, space_id, note_id).await {
+            tracing::warn!(error = %err, note_id = %note_id, "Failed to save a live note");
+        }
+    }
+}
+
+pub fn start_save_task(ctx: Arc<crate::context::AppContext>) {
+    tokio::task::spawn(async move {
+        let mut interval = crate::utils::cleaner_interval(SAVE_INTERVAL_SECS);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    save_documents(&ctx, false).await;
+                }
+                _ = crate::shutdown::SHUTDOWN.notified() => {
+                    save_documents(&ctx, true).await;
+                    break;
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn splice(i: u32, len: u32, text: &str) -> NoteSplice {
+        NoteSplice {
+            i,
+            len,
+            text: text.to_string(),
+        }
+    }
+
+    fn applied(text: &str, splices: &[NoteSplice]) -> String {
+        let mut text = text.to_string();
+        apply_splices(&mut text, splices).expect("failed to apply");
+        text
+    }
+
+    /// Both orders of applying two concurrent edits give the same text.
+    fn converge(text: &str, first: &[NoteSplice], second: &[NoteSplice]) -> String {
+        let one = applied(&applied(text, first), &rebase(second, first));
+        let other = applied(&applied(text, second), &rebase(first, second));
+        assert_eq!(one, other);
+        one
+    }
+
+    #[test]
+    fn concurrent_edits_merge() {
+        let text = "The dragon sleeps";
+        let first = [splice(4, 0, "red ")];
+        let second = [splice(11, 6, "wakes")];
+        assert_eq!(converge(text, &first, &second), "The red dragon wakes");
+        // Insertions at the same place keep the order of the versions.
+        let first = [splice(3, 0, " big")];
+        let second = [splice(3, 0, " old")];
+        assert_eq!(
+            applied(&applied(text, &first), &rebase(&second, &first)),
+            "The big old dragon sleeps"
+        );
+    }
+
+    #[test]
+    fn replaced_ranges_keep_concurrent_insertions() {
+        let text = "one two three";
+        let first = [splice(5, 0, "and ")];
+        let second = [splice(4, 4, "")];
+        assert_eq!(
+            applied(&applied(text, &first), &rebase(&second, &first)),
+            "one and three"
+        );
+        // Even when the whole text is replaced.
+        let first = [splice(4, 3, "2")];
+        let second = [splice(0, 13, "gone")];
+        assert_eq!(
+            applied(&applied(text, &first), &rebase(&second, &first)),
+            "gone2"
+        );
+    }
+
+    #[test]
+    fn positions_are_in_utf16_code_units() {
+        let text = "龍🐉!";
+        assert_eq!(byte_offset(text, 1), Some(3));
+        assert_eq!(byte_offset(text, 2), None);
+        assert_eq!(byte_offset(text, 3), Some(7));
+        assert_eq!(byte_offset(text, 5), None);
+        let mut changed = text.to_string();
+        assert!(apply_splices(&mut changed, &[splice(2, 1, "")]).is_none());
+        assert_eq!(changed, text);
+        assert_eq!(applied(text, &[splice(1, 2, "")]), "龍!");
+    }
+
+    #[test]
+    fn splices_must_be_sorted_and_in_range() {
+        assert!(validate_splices(&[splice(0, 2, "a"), splice(3, 0, "b")], 5).is_ok());
+        assert!(validate_splices(&[splice(3, 0, "b"), splice(0, 2, "a")], 5).is_err());
+        assert!(validate_splices(&[splice(0, 3, "a"), splice(2, 0, "b")], 5).is_err());
+        assert!(validate_splices(&[splice(4, 2, "")], 5).is_err());
+    }
+
+    #[test]
+    fn diffs_are_splices_in_utf16_code_units() {
+        let old = "龍🐉 sleeps";
+        let new = "🐉 wakes!";
+        let splices = diff_splices(old, new);
+        assert!(validate_splices(&splices, utf16_len(old)).is_ok());
+        assert_eq!(applied(old, &splices), new);
+        assert!(diff_splices(old, old).is_empty());
+    }
+
+    #[test]
+    fn edits_too_far_behind_are_merged_from_their_revision() {
+        let base = "one\ntwo\n";
+        let mut document = NoteDocument {
+            space_id: Uuid::nil(),
+            note_id: Uuid::nil(),
+            session: Uuid::nil(),
+            revision: 1,
+            text: base.to_string(),
+            entities: None,
+            version: 0,
+            saved_version: 0,
+            history: VecDeque::new(),
+            last_editor: None,
+            unsaved_edits: HashMap::new(),
+            edited_at: Instant::now(),
+            unsaved_since: None,
+            closed: false,
+        };
+        let post = |version: u32, splices: Vec<NoteSplice>| NoteEditPost {
+            note_id: Uuid::nil(),
+            session: Uuid::nil(),
+            version,
+            edit_id: Uuid::nil(),
+            splices,
+            entities: None,
+            revision: Some(1),
+        };
+        for version in 0..=HISTORY_LEN as u32 {
+            document
+                .apply(Uuid::nil(), post(version, vec![splice(0, 0, "a")]))
+                .unwrap();
+        }
+        assert_eq!(document.history.len(), HISTORY_LEN);
+        assert!(document.has_base(&post(1, Vec::new())));
+        let stale = post(0, vec![splice(8, 0, "three\n")]);
+        assert!(!document.has_base(&stale));
+        let edited = document.merge(Uuid::nil(), stale, base).unwrap();
+        let prefix = "a".repeat(HISTORY_LEN + 1);
+        assert_eq!(document.text, format!("{prefix}one\ntwo\nthree\n"));
+        assert_eq!(edited.version, document.version);
+        // Edits of another session are merged the same way.
+        let other = NoteEditPost {
+            session: Uuid::max(),
+            ..post(document.version, Vec::new())
+        };
+        assert!(!document.has_base(&other));
+        assert!(document.has_unsaved_edits());
+    }
+}
@@ -0,0 +1,269 @@
+//! Line diffs between the contents of notes, with the changed words of each line marked, and
+//! merges of concurrent changes.
+
+use serde::Serialize;
+use similar::{Algorithm, ChangeTag, TextDiff, capture_diff_slices_deadline};
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+/// Gives up on finding the smallest diff of huge notes, the diff is still correct.
+pub(super) const DIFF_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, specta::Type)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DiffTag {
+    Equal,
+    Delete,
+    Insert,
+}
+
+impl From<ChangeTag> for DiffTag {
+    fn from(tag: ChangeTag) -> Self {
+        match tag {
+            ChangeTag::Equal => DiffTag::Equal,
+            ChangeTag::Delete => DiffTag::Delete,
+            ChangeTag::Insert => DiffTag::Insert,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffSpan {
+    pub text: String,
+    /// The words changed within a changed line
+    pub emphasized: bool,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    pub tag: DiffTag,
+    /// Zero-based line number in the old text, `None` for inserted lines
+    pub old_line: Option<u32>,
+    /// Zero-based line number in the new text, `None` for deleted lines
+    pub new_line: Option<u32>,
+    /// The line without its line break
+    pub spans: Vec<DiffSpan>,
+}
+
+/// Diff two texts by lines. With `context`, only the changed lines and that many unchanged
+/// lines around them are returned.
+pub fn diff_lines(old: &str, new: &str, context: Option<usize>) -> Vec<DiffLine> {
+    let diff = TextDiff::configure()
+        .timeout(DIFF_TIMEOUT)
+        .diff_lines(old, new);
+    let groups = match context {
+        Some(context) => diff.grouped_ops(context),
+        None => vec![diff.ops().to_vec()],
+    };
+    let mut lines = Vec::new();
+    for op in groups.iter().flatten() {
+        for change in diff.iter_inline_changes(op) {
+            let mut spans: Vec<DiffSpan> = Vec::new();
+            for (emphasized, text) in change.iter_strings_lossy() {
+                let text = text.trim_end_matches(['\n', '\r']);
+                if text.is_empty() {
+                    continue;
+                }
+                match spans.last_mut() {
+                    Some(last) if last.emphasized == emphasized => last.text.push_str(text),
+                    _ => spans.push(DiffSpan {
+                        text: text.to_string(),
+                        emphasized,
+                    }),
+                }
+            }
+            lines.push(DiffLine {
+                tag: change.tag().into(),
+                old_line: change.old_index().map(|index| index as u32),
+                new_line: change.new_index().map(|index| index as u32),
+                spans,
+            });
+        }
+    }
+    lines
+}
+
+/// Lines of the base replaced by lines of one side.
+#[derive(Debug, Clone)]
+struct Hunk {
+    ours: bool,
+    old: Range<usize>,
+    new: Range<usize>,
+}
+
+fn hunks(base: &[&str], side: &[&str], ours: bool) -> Vec<Hunk> {
+    let deadline = Some(Instant::now() + DIFF_TIMEOUT);
+    capture_diff_slices_deadline(Algorithm::Myers, base, side, deadline)
+        .iter()
+        .filter_map(|op| {
+            let (tag, old, new) = op.as_tag_tuple();
+            (tag != similar::DiffTag::Equal).then_some(Hunk { ours, old, new })
+        })
+        .collect()
+}
+
+/// Whether the hunk changes the same lines as the lines `start..end`, or inserts at the same place.
+fn conflicts(start: usize, end: usize, hunk: &Hunk) -> bool {
+    if start == end {
+        hunk.old.start == start && hunk.old.is_empty()
+    } else {
+        hunk.old.start < end && (!hunk.old.is_empty() || hunk.old.start > start)
+    }
+}
+
+/// The lines `range` of the base with the hunks applied.
+fn apply_hunks(base: &[&str], side: &[&str], range: Range<usize>, hunks: &[&Hunk]) -> String {
+    let mut text = String::new();
+    let mut cursor = range.start;
+    for hunk in hunks {
+        text.extend(base[cursor..hunk.old.start].iter().copied());
+        text.extend(side[hunk.new.clone()].iter().copied());
+        cursor = hunk.old.end;
+    }
+    text.extend(base[cursor..range.end].iter().copied());
+    text
+}
+
+/// Merge the changes made to `base` in `ours` and in `theirs` by lines.
+///
+/// When both sides changed the same lines differently, both versions of the lines are kept, ours
+/// first, so nothing written on either side is lost.
+pub fn merge_text(base: &str, ours: &str, theirs: &str) -> String {
+    if ours == theirs || base == theirs {
+        return ours.to_string();
+    }
+    if base == ours {
+        return theirs.to_string();
+    }
+    let base_lines: Vec<&str> = base.split_inclusive('\n').collect();
+    let our_lines: Vec<&str> = ours.split_inclusive('\n').collect();
+    let their_lines: Vec<&str> = theirs.split_inclusive('\n').collect();
+    let mut all = hunks(&base_lines, &our_lines, true);
+    all.extend(hunks(&base_lines, &their_lines, false));
+    all.sort_by_key(|hunk| (hunk.old.start, hunk.old.end, !hunk.ours));
+
+    let lines = |hunk: &Hunk| {
+        if hunk.ours { &our_lines } else { &their_lines }
+    };
+    let mut merged = String::new();
+    let mut cursor = 0;
+    let mut index = 0;
+    while index < all.len() {
+        let first = &all[index];
+        let (start, mut end) = (first.old.start, first.old.end);
+        let mut next = index + 1;
+        while next < all.len() && conflicts(start, end, &all[next]) {
+            end = end.max(all[next].old.end);
+            next += 1;
+        }
+        let group = &all[index..next];
+        merged.extend(base_lines[cursor..start].iter().copied());
+        if group.len() == 1 {
+            merged.extend(lines(first)[first.new.clone()].iter().copied());
+        } else {
+            let (our_hunks, their_hunks): (Vec<&Hunk>, Vec<&Hunk>) =
+                group.iter().partition(|hunk| hunk.ours);
+            let our_text = apply_hunks(&base_lines, &our_lines, start..end, &our_hunks);
+            let their_text = apply_hunks(&base_lines, &their_lines, start..end, &their_hunks);
+            merged.push_str(&our_text);
+            if their_text != our_text {
+                if !our_text.is_empty() && !our_text.ends_with('\n') {
+                    merged.push('\n');
+                }
+                merged.push_str(&their_text);
+            }
+        }
+        cursor = end;
+        index = next;
+    }
+    merged.extend(base_lines[cursor..].iter().copied());
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(lines: &[DiffLine]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|line| {
+                let sign = match line.tag {
+                    DiffTag::Equal => ' ',
+                    DiffTag::Delete => '-',
+                    DiffTag::Insert => '+',
+                };
+                let text = line
+                    .spans
+                    .iter()
+                    .map(|span| {
+                        if span.emphasized {
+                            format!("[{}]", span.text)
+                        } else {
+                            span.text.clone()
+                        }
+                    })
+                    .collect::<String>();
+                format!("{sign}{text}")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn changed_lines_mark_the_changed_words() {
+        let old = "The dragon sleeps.\nThe gold is hidden\nThe end.\n";
+        let new = "The dragon sleeps.\nThe gold is stolen\nThe end.\nEpilogue\n";
+        let lines = diff_lines(old, new, None);
+        assert_eq!(
+            render(&lines),
+            vec![
+                " The dragon sleeps.",
+                "-The gold is [hidden]",
+                "+The gold is [stolen]",
+                " The end.",
+                "+Epilogue",
+            ]
+        );
+        assert_eq!(lines[1].old_line, Some(1));
+        assert_eq!(lines[1].new_line, None);
+        assert_eq!(lines[4].new_line, Some(3));
+    }
+
+    #[test]
+    fn context_leaves_out_distant_lines() {
+        let old = (0..20).map(|n| format!("{n}\n")).collect::<String>();
+        let new = old.replace("10\n", "ten\n");
+        let lines = diff_lines(&old, &new, Some(1));
+        // Lines too different to mark the words are changed as a whole.
+        assert_eq!(render(&lines), vec![" 9", "-10", "+ten", " 11"]);
+        assert!(diff_lines(&old, &old, Some(1)).is_empty());
+    }
+
+    #[test]
+    fn merge_keeps_changes_of_both_sides() {
+        let base = "one\ntwo\nthree\nfour\n";
+        let ours = "one\n2\nthree\nfour\n";
+        let theirs = "one\ntwo\nthree\nfour\nfive\n";
+        assert_eq!(
+            merge_text(base, ours, theirs),
+            "one\n2\nthree\nfour\nfive\n"
+        );
+        // Changes of neighbouring lines don't conflict.
+        let theirs = "one\ntwo\n3\nfour\n";
+        assert_eq!(merge_text(base, ours, theirs), "one\n2\n3\nfour\n");
+    }
+
+    #[test]
+    fn merge_keeps_both_versions_of_conflicting_lines() {
+        let base = "one\ntwo\nthree";
+        let ours = "one\nTWO\nthree";
+        let theirs = "one\n2\nthree";
+        assert_eq!(merge_text(base, ours, theirs), "one\nTWO\n2\nthree");
+        // The same change is made once.
+        assert_eq!(merge_text(base, ours, ours), ours);
+        let theirs = "one\nTWO\nthree\nfour";
+        assert_eq!(merge_text(base, ours, theirs), "one\nTWO\nthree\nfour");
+    }
+}
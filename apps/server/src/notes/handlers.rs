@@ -1,12 +1,22 @@
-use super::api::{ArchiveNote, CreateNote, EditNote, ListNotes, QueryNote, RestoreNote};
-use super::models::{Note, NoteContentRevision, NoteMetadata};
+use super::api::{
+    ArchiveNote, CreateNote, DiffNoteRevisions, EditNote, LinkTarget, ListNotes, NoteLink,
+    NoteRevisionDiff, QueryBacklinks, QueryNote, QueryNoteRevisionPolicy, RestoreNote,
+    RestoreNoteRevision, UpdateNoteRevisionPolicy,
+};
+use super::collab::{self, plain_entities};
+use super::diff::{diff_lines, merge_text};
+use super::links::{self, ResolvedLink, normalize_target};
+use super::models::{Note, NoteContentRevision, NoteMetadata, NoteRevisionPolicy};
+use crate::characters::handlers::can_view_character_in_space;
 use crate::committed_changes::CommittedChanges;
 use crate::csrf::{authenticate, authenticate_optional};
+use crate::entries::handlers::can_view_scope;
 use crate::error::{AppError, Find};
 use crate::interface::{missing, parse_body, parse_query, response};
 use crate::spaces::{ResourceAccessContext, resolve_resource_access_context, resolve_space_access};
 use hyper::Request;
 use hyper::body::Body;
+use shared_types::entities::WikiLinkKind;
 use uuid::Uuid;
 
 pub(crate) async fn can_view_note(
@@ -109,13 +119,21 @@ async fn create(
 async fn edit(ctx: &crate::context::AppContext, req: Request<impl Body>) -> Result<Note, AppError> {
     let session = authenticate(ctx, &req).await?;
     let payload: EditNote = parse_body(req).await?;
+    edit_note(ctx, session.user_id, payload).await
+}
+
+async fn edit_note(
+    ctx: &crate::context::AppContext,
+    user_id: Uuid,
+    payload: EditNote,
+) -> Result<Note, AppError> {
     let mutation = ctx.space_store.acquire_mutation(payload.space_id).await?;
     let note = ctx
         .space_store
         .resolve_note_metadata(payload.space_id, payload.note_id)
         .await?
         .or_not_found()?;
-    if !can_edit_note(ctx, &note, session.user_id).await? {
+    if !can_edit_note(ctx, &note, user_id).await? {
         return Err(AppError::NoPermission(
             "You don't have permission to edit this note".to_string(),
         ));
@@ -124,41 +142,94 @@ async fn edit(ctx: &crate::context::AppContext, req: Request<impl Body>) -> Resu
         ctx,
         payload.space_id,
         payload.access_channel_id,
-        Some(session.user_id),
+        Some(user_id),
     )
     .await?;
     if !payload
         .access_policy
-        .can_edit(note.creator_id, session.user_id, target_context)
+        .can_edit(note.creator_id, user_id, target_context)
     {
         return Err(AppError::NoPermission(
             "You cannot edit notes with this access policy and context".to_string(),
         ));
     }
+    let document = collab::lock_document(note.id).await;
     let mut transaction = ctx.db.begin().await?;
+    let current = Note::get_by_id(&mut *transaction, payload.space_id, note.id)
+        .await?
+        .or_not_found()?;
+    let (current_text, current_entities) = match &document {
+        Some(document) if document.has_unsaved_edits() => {
+            (document.text().to_string(), document.entities())
+        }
+        _ => (current.text.clone(), current.entities.clone()),
+    };
+    let EditNote {
+        mut title,
+        mut text,
+        mut entities,
+        ..
+    } = payload;
+    if payload.expected_revision != current.revision || current_text != current.text {
+        // The note was changed since the editor loaded it, by another editor or live. The
+        // changes are merged into the current text only if asked, as they would replace it.
+        if !payload.merge {
+            return Err(AppError::Conflict("Note revision is stale".to_string()));
+        }
+        let base = NoteContentRevision::get(&mut *transaction, &note.id, payload.expected_revision)
+            .await?
+            .ok_or_else(|| AppError::Conflict("Note revision is stale".to_string()))?;
+        let merged = merge_text(&base.text, &current_text, &text);
+        if merged == current_text {
+            entities = current_entities;
+        } else if merged != text {
+            entities = plain_entities(&merged);
+        }
+        text = merged;
+        if base.title == title {
+            title = current.title.to_string();
+        }
+    }
     let updated = Note::update(
         &mut transaction,
         payload.space_id,
         note.id,
-        payload.expected_revision,
-        payload.title,
+        current.revision,
+        title,
         payload.keywords,
         payload.tags,
-        payload.text,
-        payload.entities,
+        text,
+        entities,
         payload.access_policy,
         payload.access_channel_id,
-        session.user_id,
+        user_id,
     )
     .await?
     .ok_or_else(|| AppError::Conflict("Note revision is stale".to_string()))?;
+    prune_revisions(&mut transaction, payload.space_id, note.id).await?;
     let mutation = mutation.commit(transaction).await?;
+    // The live document is loaded again from the new revision.
+    if let Some(document) = document {
+        collab::close(document);
+    }
     let mut changes = CommittedChanges::default();
     changes.note_updated(&updated.metadata);
     changes.apply_with_mutation(ctx, &mutation).await;
     Ok(updated)
 }
 
+pub(super) async fn prune_revisions(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    space_id: Uuid,
+    note_id: Uuid,
+) -> Result<(), AppError> {
+    let policy = NoteRevisionPolicy::get(&mut **transaction, space_id).await?;
+    if !policy.is_unlimited() {
+        NoteContentRevision::prune(&mut **transaction, space_id, Some(note_id), &policy).await?;
+    }
+    Ok(())
+}
+
 async fn archive(
     ctx: &crate::context::AppContext,
     req: Request<impl Body>,
@@ -176,12 +247,29 @@ async fn archive(
             "You don't have permission to archive this note".to_string(),
         ));
     }
+    let document = collab::lock_document(note.id).await;
     let mut transaction = ctx.db.begin().await?;
+    let mut expected_revision = payload.expected_revision;
+    // Unsaved live edits are kept in a revision before the note is archived.
+    if let Some(document) = document
+        .as_ref()
+        .filter(|document| document.has_unsaved_edits())
+    {
+        let current = Note::get_by_id(&mut *transaction, payload.space_id, note.id)
+            .await?
+            .or_not_found()?;
+        if let Some(saved) = document.save(&mut transaction, &current).await? {
+            prune_revisions(&mut transaction, payload.space_id, note.id).await?;
+            if expected_revision == current.revision {
+                expected_revision = saved.revision;
+            }
+        }
+    }
     if !Note::archive(
         &mut transaction,
         payload.space_id,
         note.id,
-        payload.expected_revision,
+        expected_revision,
     )
     .await?
     {
@@ -191,6 +279,9 @@ async fn archive(
         .await?
         .or_not_found()?;
     let mutation = mutation.commit(transaction).await?;
+    if let Some(document) = document {
+        collab::close(document);
+    }
     let mut changes = CommittedChanges::default();
     changes.note_updated(&updated);
     changes.apply_with_mutation(ctx, &mutation).await;
@@ -284,6 +375,22 @@ async fn by_space(
     Ok(visible)
 }
 
+async fn check_content_revisions_access(
+    ctx: &crate::context::AppContext,
+    note: &NoteMetadata,
+    user_id: Option<Uuid>,
+) -> Result<(), AppError> {
+    let context =
+        resolve_resource_access_context(ctx, note.space_id, note.access_channel_id, user_id)
+            .await?;
+    if !can_view_note_content_revisions_with_context(note, user_id, context) {
+        return Err(AppError::NoPermission(
+            "You don't have permission to view these note content revisions".to_string(),
+        ));
+    }
+    Ok(())
+}
+
 async fn content_revisions(
     ctx: &crate::context::AppContext,
     req: Request<impl Body>,
@@ -295,20 +402,364 @@ async fn content_revisions(
         .resolve_note_metadata(space_id, note_id)
         .await?
         .or_not_found()?;
-    let user_id = session.map(|session| session.user_id);
-    let context =
-        resolve_resource_access_context(ctx, note.space_id, note.access_channel_id, user_id)
-            .await?;
-    if !can_view_note_content_revisions_with_context(&note, user_id, context) {
+    check_content_revisions_access(ctx, &note, session.map(|session| session.user_id)).await?;
+    NoteContentRevision::list_by_note(&ctx.db, &note_id)
+        .await
+        .map_err(Into::into)
+}
+
+async fn diff(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<NoteRevisionDiff, AppError> {
+    let session = authenticate_optional(ctx, &req).await?;
+    let DiffNoteRevisions {
+        space_id,
+        note_id,
+        from,
+        to,
+        context,
+    } = parse_query(req.uri())?;
+    let note = ctx
+        .space_store
+        .resolve_note_metadata(space_id, note_id)
+        .await?
+        .or_not_found()?;
+    check_content_revisions_access(ctx, &note, session.map(|session| session.user_id)).await?;
+    let old = NoteContentRevision::get(&ctx.db, &note_id, from)
+        .await?
+        .ok_or(AppError::NotFound("note revision"))?;
+    let (to, new_title, new_text) = match to {
+        Some(to) => {
+            let new = NoteContentRevision::get(&ctx.db, &note_id, to)
+                .await?
+                .ok_or(AppError::NotFound("note revision"))?;
+            (new.revision, new.title.to_string(), new.text)
+        }
+        None => {
+            let current = Note::get_by_id(&ctx.db, space_id, note_id)
+                .await?
+                .or_not_found()?;
+            (current.revision, current.title.to_string(), current.text)
+        }
+    };
+    let old_text = old.text;
+    let lines = tokio::task::spawn_blocking(move || {
+        diff_lines(
+            &old_text,
+            &new_text,
+            context.map(|context| context as usize),
+        )
+    })
+    .await
+    .map_err(error_unexpected!("The note diff task failed"))?;
+    Ok(NoteRevisionDiff {
+        from: old.revision,
+        to,
+        old_title: old.title.to_string(),
+        new_title,
+        lines,
+    })
+}
+
+/// Restoring goes through the same path as editing, so the old content becomes a new revision.
+async fn restore_revision(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Note, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let RestoreNoteRevision {
+        space_id,
+        note_id,
+        revision,
+        expected_revision,
+    } = parse_body(req).await?;
+    let note = ctx
+        .space_store
+        .resolve_note_metadata(space_id, note_id)
+        .await?
+        .or_not_found()?;
+    if !can_edit_note(ctx, &note, session.user_id).await? {
         return Err(AppError::NoPermission(
-            "You don't have permission to view these note content revisions".to_string(),
+            "You don't have permission to edit this note".to_string(),
         ));
     }
-    NoteContentRevision::list_by_note(&ctx.db, &note_id)
+    let old = NoteContentRevision::get(&ctx.db, &note_id, revision)
+        .await?
+        .ok_or(AppError::NotFound("note revision"))?;
+    let payload = EditNote {
+        space_id,
+        note_id,
+        expected_revision,
+        title: old.title.to_string(),
+        keywords: note.keywords.iter().map(ToString::to_string).collect(),
+        tags: note.tags.iter().map(ToString::to_string).collect(),
+        text: old.text,
+        entities: old.entities,
+        access_policy: note.access_policy,
+        access_channel_id: note.access_channel_id,
+        merge: false,
+    };
+    edit_note(ctx, session.user_id, payload).await
+}
+
+async fn revision_policy(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<NoteRevisionPolicy, AppError> {
+    let session = authenticate_optional(ctx, &req).await?;
+    let QueryNoteRevisionPolicy { space_id } = parse_query(req.uri())?;
+    let access =
+        resolve_space_access(ctx, space_id, session.map(|session| session.user_id)).await?;
+    if !access.can_access {
+        return Err(AppError::NoPermission(
+            "You don't have permission to view this space".to_string(),
+        ));
+    }
+    NoteRevisionPolicy::get(&ctx.db, space_id)
         .await
         .map_err(Into::into)
 }
 
+/// Also prunes the revisions of every note in the Space by the new policy.
+async fn update_revision_policy(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<NoteRevisionPolicy, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let UpdateNoteRevisionPolicy {
+        space_id,
+        keep_latest,
+        keep_days,
+    } = parse_body(req).await?;
+    let access = resolve_space_access(ctx, space_id, Some(session.user_id)).await?;
+    if !access.can_manage() {
+        return Err(AppError::NoPermission(
+            "Only admins can change how note revisions are kept".to_string(),
+        ));
+    }
+    let policy = NoteRevisionPolicy {
+        keep_latest,
+        keep_days,
+    };
+    let mut transaction = ctx.db.begin().await?;
+    policy.put(&mut *transaction, space_id).await?;
+    let pruned = if policy.is_unlimited() {
+        0
+    } else {
+        NoteContentRevision::prune(&mut *transaction, space_id, None, &policy).await?
+    };
+    transaction.commit().await?;
+    tracing::info!(
+        %space_id,
+        user_id = %session.user_id,
+        pruned,
+        "The note revision policy of a space was updated"
+    );
+    Ok(policy)
+}
+
+/// Describe the target of a link with its current name, or `None` if the user can't view it.
+async fn describe_link_target(
+    ctx: &crate::context::AppContext,
+    space_id: Uuid,
+    target: ResolvedLink,
+    user_id: Option<Uuid>,
+) -> Result<Option<LinkTarget>, AppError> {
+    match target {
+        ResolvedLink::Note(note_id) => {
+            let Some(note) = ctx
+                .space_store
+                .resolve_note_metadata(space_id, note_id)
+                .await?
+            else {
+                return Ok(None);
+            };
+            if !can_view_note(ctx, &note, user_id).await? {
+                return Ok(None);
+            }
+            Ok(Some(LinkTarget::Note {
+                note_id,
+                title: note.title.to_string(),
+            }))
+        }
+        ResolvedLink::Entry { scope_id, entry_id } => {
+            let Some(scope) = ctx.space_store.resolve_scope(space_id, scope_id).await? else {
+                return Ok(None);
+            };
+            if !can_view_scope(ctx, &scope, user_id).await? {
+                return Ok(None);
+            }
+            let entry = ctx
+                .space_store
+                .resolve_entry(space_id, scope_id, entry_id)
+                .await?;
+            Ok(entry.map(|entry| LinkTarget::Entry {
+                scope_id,
+                entry_id,
+                display_name: entry.metadata.display_name.to_string(),
+            }))
+        }
+        ResolvedLink::Character(character_id) => {
+            let Some(character) = ctx
+                .space_store
+                .resolve_character(space_id, character_id)
+                .await?
+            else {
+                return Ok(None);
+            };
+            if !can_view_character_in_space(ctx, &character, user_id).await? {
+                return Ok(None);
+            }
+            Ok(Some(LinkTarget::Character {
+                character_id,
+                name: character.name.to_string(),
+            }))
+        }
+    }
+}
+
+async fn note_links(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Vec<NoteLink>, AppError> {
+    let session = authenticate_optional(ctx, &req).await?;
+    let QueryNote { space_id, note_id } = parse_query(req.uri())?;
+    let user_id = session.map(|session| session.user_id);
+    let note = ctx
+        .space_store
+        .resolve_note_metadata(space_id, note_id)
+        .await?
+        .or_not_found()?;
+    if !can_view_note(ctx, &note, user_id).await? {
+        return Err(AppError::NoPermission(
+            "You don't have permission to view this note".to_string(),
+        ));
+    }
+    let stored = links::list_links(&ctx.db, note_id).await?;
+    let mut note_links = Vec::with_capacity(stored.len());
+    for link in stored {
+        // Links without a target may have one now that something was created or renamed.
+        let resolved = match link.resolved {
+            Some(resolved) => Some(resolved),
+            None => links::resolve_link(&ctx.db, space_id, link.kind, &link.target).await?,
+        };
+        let resolved = match resolved {
+            Some(resolved) => describe_link_target(ctx, space_id, resolved, user_id).await?,
+            None => None,
+        };
+        note_links.push(NoteLink {
+            kind: link.kind,
+            target: link.target,
+            resolved,
+        });
+    }
+    Ok(note_links)
+}
+
+async fn backlinks(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Vec<NoteMetadata>, AppError> {
+    let session = authenticate_optional(ctx, &req).await?;
+    let QueryBacklinks {
+        space_id,
+        kind,
+        id,
+        scope_id,
+    } = parse_query(req.uri())?;
+    let user_id = session.map(|session| session.user_id);
+    let (target, names) = match kind {
+        WikiLinkKind::Note => {
+            let note = ctx
+                .space_store
+                .resolve_note_metadata(space_id, id)
+                .await?
+                .or_not_found()?;
+            if !can_view_note(ctx, &note, user_id).await? {
+                return Err(AppError::NoPermission(
+                    "You don't have permission to view this note".to_string(),
+                ));
+            }
+            let mut names: Vec<String> = note.keywords.iter().map(ToString::to_string).collect();
+            names.push(note.title.to_string());
+            (ResolvedLink::Note(id), names)
+        }
+        WikiLinkKind::Entry => {
+            let Some(scope_id) = scope_id else {
+                return Err(AppError::BadRequest(
+                    "The scope of the entry is required".to_string(),
+                ));
+            };
+            let scope = ctx
+                .space_store
+                .resolve_scope(space_id, scope_id)
+                .await?
+                .or_not_found()?;
+            if !can_view_scope(ctx, &scope, user_id).await? {
+                return Err(AppError::NoPermission(
+                    "You don't have permission to view this entry".to_string(),
+                ));
+            }
+            let entry = ctx
+                .space_store
+                .resolve_entry(space_id, scope_id, id)
+                .await?
+                .or_not_found()?;
+            let metadata = entry.metadata;
+            let mut names: Vec<String> = metadata.aliases.iter().map(ToString::to_string).collect();
+            names.push(metadata.key.to_string());
+            names.push(metadata.display_name.to_string());
+            (
+                ResolvedLink::Entry {
+                    scope_id,
+                    entry_id: id,
+                },
+                names,
+            )
+        }
+        WikiLinkKind::Character => {
+            let character = ctx
+                .space_store
+                .resolve_character(space_id, id)
+                .await?
+                .or_not_found()?;
+            if !can_view_character_in_space(ctx, &character, user_id).await? {
+                return Err(AppError::NoPermission(
+                    "You don't have permission to view this character".to_string(),
+                ));
+            }
+            let mut names: Vec<String> =
+                character.aliases.iter().map(ToString::to_string).collect();
+            names.push(character.key.to_string());
+            names.push(character.name.to_string());
+            (ResolvedLink::Character(id), names)
+        }
+    };
+    let mut names: Vec<String> = names
+        .iter()
+        .map(|name| normalize_target(name))
+        .filter(|name| !name.is_empty())
+        .collect();
+    names.sort();
+    names.dedup();
+    let note_ids = links::list_backlinks(&ctx.db, space_id, target, &names).await?;
+    let mut visible = Vec::with_capacity(note_ids.len());
+    for note_id in note_ids {
+        let Some(note) = ctx
+            .space_store
+            .resolve_note_metadata(space_id, note_id)
+            .await?
+        else {
+            continue;
+        };
+        if note.archived_at.is_none() && can_view_note(ctx, &note, user_id).await? {
+            visible.push(note);
+        }
+    }
+    Ok(visible)
+}
+
 pub async fn router(
     ctx: &crate::context::AppContext,
     req: Request<impl Body>,
@@ -324,6 +775,14 @@ pub async fn router(
         ("/archive", Method::POST) => response(archive(ctx, req).await).await,
         ("/restore", Method::POST) => response(restore(ctx, req).await).await,
         ("/content_revisions", Method::GET) => response(content_revisions(ctx, req).await).await,
+        ("/diff", Method::GET) => response(diff(ctx, req).await).await,
+        ("/restore_revision", Method::POST) => response(restore_revision(ctx, req).await).await,
+        ("/links", Method::GET) => response(note_links(ctx, req).await).await,
+        ("/backlinks", Method::GET) => response(backlinks(ctx, req).await).await,
+        ("/revision_policy", Method::GET) => response(revision_policy(ctx, req).await).await,
+        ("/update_revision_policy", Method::POST) => {
+            response(update_revision_policy(ctx, req).await).await
+        }
         _ => missing(),
     }
 }
@@ -0,0 +1,258 @@
+//! The index of wiki links from notes to notes, entries and characters.
+
+use shared_types::entities::{Entity, Href, LinkEntity, WikiLinkKind};
+use shared_types::messages::Entities;
+use unicode_normalization::UnicodeNormalization;
+use uuid::Uuid;
+
+const MAX_LINKS: usize = 200;
+const TARGET_MAX_LEN: usize = 200;
+
+/// In the same form as note keywords, so the keywords can be matched as they are.
+pub fn normalize_target(target: &str) -> String {
+    target.trim().to_lowercase().nfc().collect()
+}
+
+/// The distinct wiki links in the entities of a note, in order.
+pub fn extract_links(entities: &Entities) -> Vec<(Option<WikiLinkKind>, String)> {
+    let mut links = Vec::new();
+    for entity in &entities.0 {
+        let Entity::Link(LinkEntity {
+            href: Href::Wiki(link),
+            ..
+        }) = entity
+        else {
+            continue;
+        };
+        let target = normalize_target(&link.target);
+        if target.is_empty() || target.chars().count() > TARGET_MAX_LEN {
+            continue;
+        }
+        let link = (link.kind, target);
+        if !links.contains(&link) {
+            links.push(link);
+            if links.len() == MAX_LINKS {
+                break;
+            }
+        }
+    }
+    links
+}
+
+/// What a wiki link points to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResolvedLink {
+    Note(Uuid),
+    Entry { scope_id: Uuid, entry_id: Uuid },
+    Character(Uuid),
+}
+
+impl ResolvedLink {
+    pub fn kind(self) -> WikiLinkKind {
+        match self {
+            ResolvedLink::Note(_) => WikiLinkKind::Note,
+            ResolvedLink::Entry { .. } => WikiLinkKind::Entry,
+            ResolvedLink::Character(_) => WikiLinkKind::Character,
+        }
+    }
+
+    pub fn id(self) -> Uuid {
+        match self {
+            ResolvedLink::Note(id) | ResolvedLink::Character(id) => id,
+            ResolvedLink::Entry { entry_id, .. } => entry_id,
+        }
+    }
+}
+
+/// A wiki link as written in a note, and its target if one was found when the note was saved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredLink {
+    pub kind: Option<WikiLinkKind>,
+    pub target: String,
+    pub resolved: Option<ResolvedLink>,
+}
+
+/// Find the target of a link by name. Notes come first, then characters, then entries.
+pub async fn resolve_link<'c, T: sqlx::PgExecutor<'c>>(
+    db: T,
+    space_id: Uuid,
+    kind: Option<WikiLinkKind>,
+    target: &str,
+) -> Result<Option<ResolvedLink>, sqlx::Error> {
+    let row = sqlx::query_file!(
+        "sql/notes/resolve_link.sql",
+        space_id,
+        kind.map(WikiLinkKind::as_str),
+        target
+    )
+    .fetch_optional(db)
+    .await?;
+    Ok(row.and_then(|row| match WikiLinkKind::parse(&row.kind)? {
+        WikiLinkKind::Note => Some(ResolvedLink::Note(row.id)),
+        WikiLinkKind::Entry => Some(ResolvedLink::Entry {
+            scope_id: row.scope_id?,
+            entry_id: row.id,
+        }),
+        WikiLinkKind::Character => Some(ResolvedLink::Character(row.id)),
+    }))
+}
+
+/// Replace the links of a note with the links in its entities.
+pub async fn index_links(
+    db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    space_id: Uuid,
+    note_id: Uuid,
+    entities: &Entities,
+) -> Result<(), sqlx::Error> {
+    sqlx::query_file!("sql/notes/delete_links.sql", note_id)
+        .execute(&mut **db)
+        .await?;
+    let links = extract_links(entities);
+    if links.is_empty() {
+        return Ok(());
+    }
+    let mut positions = Vec::with_capacity(links.len());
+    let mut kinds = Vec::with_capacity(links.len());
+    let mut targets = Vec::with_capacity(links.len());
+    let mut note_ids = Vec::with_capacity(links.len());
+    let mut entry_ids = Vec::with_capacity(links.len());
+    let mut character_ids = Vec::with_capacity(links.len());
+    for (position, (kind, target)) in links.into_iter().enumerate() {
+        let resolved = resolve_link(&mut **db, space_id, kind, &target).await?;
+        positions.push(position as i32);
+        kinds.push(kind.map(|kind| kind.as_str().to_string()));
+        targets.push(target);
+        note_ids.push(match resolved {
+            Some(ResolvedLink::Note(id)) => Some(id),
+            _ => None,
+        });
+        entry_ids.push(match resolved {
+            Some(ResolvedLink::Entry { entry_id, .. }) => Some(entry_id),
+            _ => None,
+        });
+        character_ids.push(match resolved {
+            Some(ResolvedLink::Character(id)) => Some(id),
+            _ => None,
+        });
+    }
+    sqlx::query_file!(
+        "sql/notes/insert_links.sql",
+        note_id,
+        space_id,
+        &positions,
+        &kinds as &[Option<String>],
+        &targets,
+        &note_ids as &[Option<Uuid>],
+        &entry_ids as &[Option<Uuid>],
+        &character_ids as &[Option<Uuid>],
+    )
+    .execute(&mut **db)
+    .await?;
+    Ok(())
+}
+
+pub async fn list_links<'c, T: sqlx::PgExecutor<'c>>(
+    db: T,
+    note_id: Uuid,
+) -> Result<Vec<StoredLink>, sqlx::Error> {
+    let rows = sqlx::query_file!("sql/notes/list_links.sql", note_id)
+        .fetch_all(db)
+        .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let resolved = if let Some(id) = row.target_note_id {
+                Some(ResolvedLink::Note(id))
+            } else if let (Some(entry_id), Some(scope_id)) =
+                (row.target_entry_id, row.target_entry_scope_id)
+            {
+                Some(ResolvedLink::Entry { scope_id, entry_id })
+            } else {
+                row.target_character_id.map(ResolvedLink::Character)
+            };
+            StoredLink {
+                kind: row.kind.as_deref().and_then(WikiLinkKind::parse),
+                target: row.target,
+                resolved,
+            }
+        })
+        .collect())
+}
+
+/// The notes linking to the target, by its id or by one of `names` for the links which had no
+/// target when they were saved.
+pub async fn list_backlinks<'c, T: sqlx::PgExecutor<'c>>(
+    db: T,
+    space_id: Uuid,
+    target: ResolvedLink,
+    names: &[String],
+) -> Result<Vec<Uuid>, sqlx::Error> {
+    sqlx::query_file_scalar!(
+        "sql/notes/list_backlinks.sql",
+        space_id,
+        target.kind().as_str(),
+        target.id(),
+        names
+    )
+    .fetch_all(db)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared_types::entities::{ChildText, Span, WikiLink};
+
+    fn wiki(kind: Option<WikiLinkKind>, target: &str) -> Entity {
+        let span = Span { start: 0, len: 1 };
+        Entity::Link(LinkEntity {
+            span,
+            href: Href::Wiki(WikiLink {
+                kind,
+                target: target.to_string(),
+            }),
+            child: ChildText::Text(span),
+            title: None,
+        })
+    }
+
+    #[test]
+    fn wiki_links_are_normalized_and_deduplicated() {
+        let span = Span { start: 0, len: 1 };
+        let entities = Entities(vec![
+            wiki(None, " The Dragon "),
+            Entity::Text(span),
+            wiki(None, "the dragon"),
+            wiki(Some(WikiLinkKind::Entry), "the dragon"),
+            wiki(None, "   "),
+            Entity::Link(LinkEntity {
+                span,
+                href: Href::Link("https://example.com".to_string()),
+                child: ChildText::Text(span),
+                title: None,
+            }),
+        ]);
+        assert_eq!(
+            extract_links(&entities),
+            vec![
+                (None, "the dragon".to_string()),
+                (Some(WikiLinkKind::Entry), "the dragon".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn wiki_links_deserialize_from_href() {
+        let href: Href =
+            serde_json::from_str(r#"{ "kind": "CHARACTER", "target": "Alice" }"#).unwrap();
+        assert!(matches!(
+            href,
+            Href::Wiki(WikiLink {
+                kind: Some(WikiLinkKind::Character),
+                ..
+            })
+        ));
+        let href: Href = serde_json::from_str(r#"{ "start": 1, "len": 2 }"#).unwrap();
+        assert!(matches!(href, Href::Position(_)));
+    }
+}
@@ -143,6 +143,7 @@ impl Note {
         .execute(&mut **db)
         .await?;
         insert_content_revision(db, note_id, 1, Some(creator_id), &title, &text, &entities).await?;
+        super::links::index_links(db, space_id, note_id, &entities).await?;
         Self::get_by_id(&mut **db, space_id, note_id)
             .await?
             .ok_or(ModelError::NotFound("Note"))
@@ -228,6 +229,7 @@ impl Note {
             &entities,
         )
         .await?;
+        super::links::index_links(db, space_id, note_id, &entities).await?;
         Self::get_by_id(&mut **db, space_id, note_id)
             .await
             .map_err(Into::into)
@@ -331,6 +333,114 @@ impl NoteContentRevision {
             })
             .collect()
     }
+
+    pub async fn get<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        note_id: &Uuid,
+        revision: i64,
+    ) -> Result<Option<NoteContentRevision>, sqlx::Error> {
+        let row = sqlx::query_file!("sql/notes/get_content_revision.sql", note_id, revision)
+            .fetch_optional(db)
+            .await?;
+        Ok(row.map(|row| NoteContentRevision {
+            note_id: row.note_id,
+            revision: row.revision,
+            operator_id: row.operator_id,
+            title: row.title,
+            text: row.text,
+            entities: row.entities,
+            created: row.created,
+        }))
+    }
+
+    /// Delete the revisions outside the policy, of a note or of all notes in the Space.
+    pub async fn prune<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+        note_id: Option<Uuid>,
+        policy: &NoteRevisionPolicy,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query_file!(
+            "sql/notes/prune_content_revisions.sql",
+            space_id,
+            note_id,
+            policy.keep_latest,
+            policy.keep_days,
+        )
+        .execute(db)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+const REVISION_KEEP_LATEST_MAX: i32 = 10_000;
+const REVISION_KEEP_DAYS_MAX: i32 = 3650;
+
+/// How long the content revisions of the notes in a Space are kept.
+///
+/// A revision is pruned once it is neither one of the latest `keepLatest` revisions of its note,
+/// nor younger than `keepDays`. The latest revision of a note is never pruned. Without limits,
+/// every revision is kept.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteRevisionPolicy {
+    pub keep_latest: Option<i32>,
+    pub keep_days: Option<i32>,
+}
+
+impl NoteRevisionPolicy {
+    pub fn validate(&self) -> Result<(), ValidationFailed> {
+        if let Some(keep_latest) = self.keep_latest
+            && !(1..=REVISION_KEEP_LATEST_MAX).contains(&keep_latest)
+        {
+            return Err(ValidationFailed(
+                "The number of revisions to keep must be between 1 and 10000.",
+            ));
+        }
+        if let Some(keep_days) = self.keep_days
+            && !(1..=REVISION_KEEP_DAYS_MAX).contains(&keep_days)
+        {
+            return Err(ValidationFailed(
+                "The days to keep revisions must be between 1 and 3650.",
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.keep_latest.is_none() && self.keep_days.is_none()
+    }
+
+    pub async fn get<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+    ) -> Result<NoteRevisionPolicy, sqlx::Error> {
+        sqlx::query_file_as!(
+            NoteRevisionPolicy,
+            "sql/notes/get_revision_policy.sql",
+            space_id
+        )
+        .fetch_optional(db)
+        .await
+        .map(Option::unwrap_or_default)
+    }
+
+    pub async fn put<'c, T: sqlx::PgExecutor<'c>>(
+        &self,
+        db: T,
+        space_id: Uuid,
+    ) -> Result<(), ModelError> {
+        self.validate()?;
+        sqlx::query_file!(
+            "sql/notes/put_revision_policy.sql",
+            space_id,
+            self.keep_latest,
+            self.keep_days,
+        )
+        .execute(db)
+        .await?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -513,6 +623,272 @@ mod tests {
             serde_json::to_value(&formatted_entities).expect("serialize expected entities")
         );
     }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn db_test_note_revision_policy_prunes_old_revisions(pool: sqlx::PgPool) {
+        let user = create_test_user(&pool).await;
+        let space = Space::create(
+            &pool,
+            format!("note_{}", &Uuid::new_v4().simple().to_string()[..8]),
+            &user.id,
+            "notes".to_string(),
+            None,
+            Some("d20"),
+        )
+        .await
+        .expect("failed to create space");
+        assert_eq!(
+            NoteRevisionPolicy::get(&pool, space.id)
+                .await
+                .expect("get default policy failed"),
+            NoteRevisionPolicy::default()
+        );
+        let mut tx = pool.begin().await.expect("begin failed");
+        let mut note = Note::create(
+            &mut tx,
+            space.id,
+            "Journal".to_string(),
+            Vec::new(),
+            Vec::new(),
+            user.id,
+            "Day 1".to_string(),
+            Entities::default(),
+            AccessPolicy::Public,
+            None,
+        )
+        .await
+        .expect("create failed");
+        for day in 2..=4 {
+            note = Note::update(
+                &mut tx,
+                space.id,
+                note.id,
+                note.revision,
+                "Journal".to_string(),
+                Vec::new(),
+                Vec::new(),
+                format!("Day {day}"),
+                Entities::default(),
+                AccessPolicy::Public,
+                None,
+                user.id,
+            )
+            .await
+            .expect("update failed")
+            .expect("revision matched");
+        }
+        tx.commit().await.expect("commit failed");
+
+        let first = NoteContentRevision::get(&pool, &note.id, 1)
+            .await
+            .expect("get revision failed")
+            .expect("first revision missing");
+        assert_eq!(first.text, "Day 1");
+
+        let recent = NoteRevisionPolicy {
+            keep_latest: None,
+            keep_days: Some(1),
+        };
+        assert_eq!(
+            NoteContentRevision::prune(&pool, space.id, None, &recent)
+                .await
+                .expect("prune failed"),
+            0
+        );
+        let latest = NoteRevisionPolicy {
+            keep_latest: Some(2),
+            keep_days: None,
+        };
+        latest
+            .put(&pool, space.id)
+            .await
+            .expect("put policy failed");
+        assert_eq!(
+            NoteRevisionPolicy::get(&pool, space.id)
+                .await
+                .expect("get policy failed"),
+            latest
+        );
+        assert_eq!(
+            NoteContentRevision::prune(&pool, space.id, Some(note.id), &latest)
+                .await
+                .expect("prune failed"),
+            2
+        );
+        let revisions = NoteContentRevision::list_by_note(&pool, &note.id)
+            .await
+            .expect("content revisions failed");
+        assert_eq!(
+            revisions
+                .iter()
+                .map(|revision| revision.revision)
+                .collect::<Vec<_>>(),
+            vec![4, 3]
+        );
+        assert!(
+            NoteRevisionPolicy {
+                keep_latest: Some(0),
+                keep_days: None,
+            }
+            .put(&pool, space.id)
+            .await
+            .is_err()
+        );
+    }
+
+    fn wiki_entities(targets: &[&str]) -> Entities {
+        use shared_types::entities::{ChildText, Entity, Href, LinkEntity, Span, WikiLink};
+
+        let span = Span { start: 0, len: 1 };
+        Entities(
+            targets
+                .iter()
+                .map(|target| {
+                    Entity::Link(LinkEntity {
+                        span,
+                        href: Href::Wiki(WikiLink {
+                            kind: None,
+                            target: target.to_string(),
+                        }),
+                        child: ChildText::Text(span),
+                        title: None,
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn db_test_note_links_follow_renames_and_list_backlinks(pool: sqlx::PgPool) {
+        use super::super::links::{ResolvedLink, list_backlinks, list_links};
+        use crate::characters::Character;
+
+        let user = create_test_user(&pool).await;
+        let space = Space::create(
+            &pool,
+            format!("note_{}", &Uuid::new_v4().simple().to_string()[..8]),
+            &user.id,
+            "notes".to_string(),
+            None,
+            Some("d20"),
+        )
+        .await
+        .expect("failed to create space");
+        let mut tx = pool.begin().await.expect("begin failed");
+        let dragon = Note::create(
+            &mut tx,
+            space.id,
+            "The Dragon".to_string(),
+            vec!["Wyrm".to_string()],
+            Vec::new(),
+            user.id,
+            String::new(),
+            Entities::default(),
+            AccessPolicy::Public,
+            None,
+        )
+        .await
+        .expect("create failed");
+        let journal = Note::create(
+            &mut tx,
+            space.id,
+            "Journal".to_string(),
+            Vec::new(),
+            Vec::new(),
+            user.id,
+            "x y".to_string(),
+            wiki_entities(&["wyrm", "Alice"]),
+            AccessPolicy::Public,
+            None,
+        )
+        .await
+        .expect("create failed");
+        tx.commit().await.expect("commit failed");
+
+        let links = list_links(&pool, journal.id).await.expect("links failed");
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].target, "wyrm");
+        assert_eq!(links[0].resolved, Some(ResolvedLink::Note(dragon.id)));
+        assert_eq!(links[1].target, "alice");
+        assert_eq!(links[1].resolved, None);
+
+        let mut tx = pool.begin().await.expect("begin failed");
+        Note::update(
+            &mut tx,
+            space.id,
+            dragon.id,
+            dragon.revision,
+            "The Red Dragon".to_string(),
+            Vec::new(),
+            Vec::new(),
+            String::new(),
+            Entities::default(),
+            AccessPolicy::Public,
+            None,
+            user.id,
+        )
+        .await
+        .expect("update failed")
+        .expect("revision matched");
+        let alice = Character::create(
+            &mut tx,
+            space.id,
+            user.id,
+            "Alice",
+            "alice",
+            Vec::new(),
+            "",
+            "",
+            AccessPolicy::Public,
+            None,
+            Vec::new(),
+        )
+        .await
+        .expect("create character failed");
+        tx.commit().await.expect("commit failed");
+
+        // The link resolved when saved follows the rename, the dangling one matches by name.
+        let renamed = vec!["the red dragon".to_string()];
+        assert_eq!(
+            list_backlinks(&pool, space.id, ResolvedLink::Note(dragon.id), &renamed)
+                .await
+                .expect("backlinks failed"),
+            vec![journal.id]
+        );
+        let names = vec!["alice".to_string()];
+        assert_eq!(
+            list_backlinks(&pool, space.id, ResolvedLink::Character(alice.id), &names)
+                .await
+                .expect("backlinks failed"),
+            vec![journal.id]
+        );
+
+        let mut tx = pool.begin().await.expect("begin failed");
+        Note::update(
+            &mut tx,
+            space.id,
+            journal.id,
+            journal.revision,
+            "Journal".to_string(),
+            Vec::new(),
+            Vec::new(),
+            String::new(),
+            Entities::default(),
+            AccessPolicy::Public,
+            None,
+            user.id,
+        )
+        .await
+        .expect("update failed")
+        .expect("revision matched");
+        tx.commit().await.expect("commit failed");
+        assert!(
+            list_links(&pool, journal.id)
+                .await
+                .expect("links failed")
+                .is_empty()
+        );
+    }
 }
 #[test]
 fn keywords_use_lowercase_nfc_for_deduplication() {
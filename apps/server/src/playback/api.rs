@@ -0,0 +1,55 @@
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryPlayback {
+    pub space_id: Uuid,
+    pub channel_id: Uuid,
+}
+
+/// A change to what is playing in the channel.
+#[derive(Deserialize, Debug, Clone, PartialEq, specta::Type)]
+#[serde(tag = "action", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PlaybackAction {
+    /// Start playing an audio Asset, in place of anything playing before.
+    #[serde(rename_all = "camelCase")]
+    Play {
+        asset_id: Uuid,
+        #[serde(default)]
+        #[specta(type = f64)]
+        position_ms: i64,
+        #[serde(default)]
+        looping: bool,
+        /// Keeps the volume of the previous playback if not given.
+        #[serde(default)]
+        volume: Option<f32>,
+    },
+    Pause,
+    Resume,
+    #[serde(rename_all = "camelCase")]
+    Seek {
+        #[specta(type = f64)]
+        position_ms: i64,
+    },
+    #[serde(rename_all = "camelCase")]
+    SetLoop {
+        looping: bool,
+    },
+    #[serde(rename_all = "camelCase")]
+    SetVolume {
+        volume: f32,
+    },
+    Stop,
+}
+
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ControlPlayback {
+    pub space_id: Uuid,
+    pub channel_id: Uuid,
+    pub action: PlaybackAction,
+    /// Fails with a conflict if the playback has changed since this version.
+    #[serde(default)]
+    pub expected_version: Option<Uuid>,
+}
@@ -0,0 +1,164 @@
+use super::api::{ControlPlayback, PlaybackAction, QueryPlayback};
+use super::models::{ChannelPlayback, PlaybackState};
+use crate::assets::Asset;
+use crate::csrf::{authenticate, authenticate_optional};
+use crate::error::{AppError, Find};
+use crate::events::Update;
+use crate::interface::{missing, parse_body, parse_query, response};
+use crate::media::models::Media;
+use crate::media::{audio, ensure_audio_duration};
+use crate::spaces::resolve_resource_access_context;
+use hyper::Request;
+use hyper::body::Body;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// Whether the user can control the playback. Everyone who can view the channel can listen.
+async fn resolve_playback_access(
+    ctx: &crate::context::AppContext,
+    space_id: Uuid,
+    channel_id: Uuid,
+    user_id: Option<Uuid>,
+) -> Result<bool, AppError> {
+    let context = resolve_resource_access_context(ctx, space_id, Some(channel_id), user_id).await?;
+    if !context.can_view {
+        return Err(AppError::NoPermission(
+            "You don't have permission to access this channel".to_string(),
+        ));
+    }
+    Ok(context.is_game_master || context.can_manage)
+}
+
+/// Find the audio Asset to play, reading its duration if it's not known yet.
+async fn playable_asset(
+    ctx: &crate::context::AppContext,
+    space_id: Uuid,
+    asset_id: Uuid,
+) -> Result<Asset, AppError> {
+    let asset = Asset::get_by_id_in_space(&ctx.db, space_id, asset_id)
+        .await
+        .or_not_found()?;
+    if !audio::is_audio(&asset.mime_type) {
+        return Err(AppError::BadRequest("The Asset is not audio".to_string()));
+    }
+    if asset.duration_ms.is_none() {
+        let media = Media::get_by_id(&ctx.db, &asset.media_id)
+            .await
+            .or_not_found()?;
+        ensure_audio_duration(ctx, media).await?;
+    }
+    Ok(asset)
+}
+
+async fn query(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Option<ChannelPlayback>, AppError> {
+    let session = authenticate_optional(ctx, &req).await?;
+    let QueryPlayback {
+        space_id,
+        channel_id,
+    } = parse_query(req.uri())?;
+    resolve_playback_access(
+        ctx,
+        space_id,
+        channel_id,
+        session.map(|session| session.user_id),
+    )
+    .await?;
+    Ok(ChannelPlayback::get(&ctx.db, space_id, channel_id).await?)
+}
+
+async fn control(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Option<ChannelPlayback>, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let ControlPlayback {
+        space_id,
+        channel_id,
+        action,
+        expected_version,
+    } = parse_body(req).await?;
+    let user_id = session.user_id;
+    if !resolve_playback_access(ctx, space_id, channel_id, Some(user_id)).await? {
+        return Err(AppError::NoPermission(
+            "Only the game master can control the playback".to_string(),
+        ));
+    }
+    if let PlaybackAction::Play { asset_id, .. } = action {
+        playable_asset(ctx, space_id, asset_id).await?;
+    }
+
+    let mut transaction = ctx.db.begin().await?;
+    let current = ChannelPlayback::get_for_update(&mut transaction, space_id, channel_id).await?;
+    if let Some(expected_version) = expected_version
+        && current.as_ref().map(|playback| playback.version) != Some(expected_version)
+    {
+        return Err(AppError::Conflict("Playback version is stale".to_string()));
+    }
+    let now = OffsetDateTime::now_utc();
+    let playback = match action {
+        PlaybackAction::Play {
+            asset_id,
+            position_ms,
+            looping,
+            volume,
+        } => {
+            let volume = volume
+                .or(current.as_ref().map(|playback| playback.volume))
+                .unwrap_or(1.0);
+            let state = PlaybackState {
+                playing: true,
+                position_ms,
+                looping,
+                volume,
+            };
+            let playback = ChannelPlayback::play(
+                &mut transaction,
+                space_id,
+                channel_id,
+                asset_id,
+                state,
+                user_id,
+                now,
+            )
+            .await?;
+            Some(playback)
+        }
+        PlaybackAction::Stop => {
+            if !ChannelPlayback::delete(&mut *transaction, space_id, channel_id).await? {
+                return Err(AppError::NotFound("playback"));
+            }
+            None
+        }
+        action => {
+            let current = current.ok_or(AppError::NotFound("playback"))?;
+            let state = current
+                .state_after(&action, now)
+                .ok_or_else(|| AppError::BadRequest("Unexpected playback action".to_string()))?;
+            Some(
+                current
+                    .update(&mut transaction, state, user_id, now)
+                    .await?,
+            )
+        }
+    };
+    transaction.commit().await?;
+    Update::playback_changed(space_id, channel_id);
+    Ok(playback)
+}
+
+pub async fn router(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+    path: &str,
+) -> Result<hyper::Response<Vec<u8>>, AppError> {
+    use hyper::Method;
+
+    match (path, req.method().clone()) {
+        ("/query", Method::GET) => response(query(ctx, req).await).await,
+        ("/control", Method::POST) => response(control(ctx, req).await).await,
+        _ => missing(),
+    }
+}
@@ -0,0 +1,455 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use super::api::PlaybackAction;
+use crate::error::{ModelError, ValidationFailed};
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct PlaybackRow {
+    channel_id: Uuid,
+    space_id: Uuid,
+    asset_id: Uuid,
+    media_id: Uuid,
+    asset_name: String,
+    mime_type: String,
+    duration_ms: Option<i32>,
+    playing: bool,
+    position_ms: i64,
+    looping: bool,
+    volume: f32,
+    updated_by: Option<Uuid>,
+    version: Uuid,
+    updated: OffsetDateTime,
+}
+
+/// The audio playing in a channel. A channel plays at most one Asset at a time.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelPlayback {
+    pub channel_id: Uuid,
+    pub space_id: Uuid,
+    pub asset_id: Uuid,
+    pub media_id: Uuid,
+    pub asset_name: String,
+    pub mime_type: String,
+    pub duration_ms: Option<i32>,
+    /// `false` when paused
+    pub playing: bool,
+    /// Where the playback was at `updated`.
+    #[specta(type = f64)]
+    pub position_ms: i64,
+    /// Where the playback is when it's read, so clients can sync without trusting their clocks.
+    #[specta(type = f64)]
+    pub current_position_ms: i64,
+    pub looping: bool,
+    /// From 0 to 1
+    pub volume: f32,
+    pub updated_by: Option<Uuid>,
+    pub version: Uuid,
+    #[specta(type = String)]
+    #[serde(with = "time::serde::rfc3339")]
+    pub updated: OffsetDateTime,
+}
+
+/// The parts of the playback a control changes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlaybackState {
+    pub playing: bool,
+    pub position_ms: i64,
+    pub looping: bool,
+    pub volume: f32,
+}
+
+impl PlaybackState {
+    pub fn validate(&self) -> Result<(), ValidationFailed> {
+        if self.position_ms < 0 {
+            return Err(ValidationFailed("The position can not be negative."));
+        }
+        if !(0.0..=1.0).contains(&self.volume) {
+            return Err(ValidationFailed("The volume must be between 0 and 1."));
+        }
+        Ok(())
+    }
+}
+
+/// Where a playback started at `position_ms` is after `elapsed_ms`.
+///
+/// Looping playback wraps around the duration, otherwise it stops at the end.
+fn advance(position_ms: i64, elapsed_ms: i64, duration_ms: Option<i32>, looping: bool) -> i64 {
+    let position_ms = position_ms.saturating_add(elapsed_ms.max(0));
+    match duration_ms.map(i64::from) {
+        Some(duration_ms) if duration_ms > 0 && looping => position_ms % duration_ms,
+        Some(duration_ms) => position_ms.min(duration_ms),
+        None => position_ms,
+    }
+}
+
+impl ChannelPlayback {
+    fn from_row(row: PlaybackRow, now: OffsetDateTime) -> Self {
+        let mut playback = ChannelPlayback {
+            channel_id: row.channel_id,
+            space_id: row.space_id,
+            asset_id: row.asset_id,
+            media_id: row.media_id,
+            asset_name: row.asset_name,
+            mime_type: row.mime_type,
+            duration_ms: row.duration_ms,
+            playing: row.playing,
+            position_ms: row.position_ms,
+            current_position_ms: row.position_ms,
+            looping: row.looping,
+            volume: row.volume,
+            updated_by: row.updated_by,
+            version: row.version,
+            updated: row.updated,
+        };
+        playback.current_position_ms = playback.position_at(now);
+        playback
+    }
+
+    pub fn position_at(&self, now: OffsetDateTime) -> i64 {
+        if !self.playing {
+            return self.position_ms;
+        }
+        let elapsed_ms = (now - self.updated).whole_milliseconds();
+        let elapsed_ms = elapsed_ms.clamp(0, i64::MAX as i128) as i64;
+        advance(self.position_ms, elapsed_ms, self.duration_ms, self.looping)
+    }
+
+    /// The state after a control, anchored at `now`.
+    ///
+    /// `None` for the actions which replace or stop the playback rather than change it.
+    pub fn state_after(
+        &self,
+        action: &PlaybackAction,
+        now: OffsetDateTime,
+    ) -> Option<PlaybackState> {
+        let mut state = PlaybackState {
+            playing: self.playing,
+            position_ms: self.position_at(now),
+            looping: self.looping,
+            volume: self.volume,
+        };
+        match *action {
+            PlaybackAction::Play { .. } | PlaybackAction::Stop => return None,
+            PlaybackAction::Pause => state.playing = false,
+            PlaybackAction::Resume => {
+                // A finished playback starts over.
+                if self.duration_ms.map(i64::from) == Some(state.position_ms) {
+                    state.position_ms = 0;
+                }
+                state.playing = true;
+            }
+            PlaybackAction::Seek { position_ms } => {
+                state.position_ms = advance(position_ms, 0, self.duration_ms, false);
+            }
+            PlaybackAction::SetLoop { looping } => state.looping = looping,
+            PlaybackAction::SetVolume { volume } => state.volume = volume,
+        }
+        Some(state)
+    }
+
+    pub async fn get<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+        channel_id: Uuid,
+    ) -> Result<Option<Self>, ModelError> {
+        let row = sqlx::query_file_as!(PlaybackRow, "sql/playback/get.sql", space_id, channel_id)
+            .fetch_optional(db)
+            .await?;
+        let now = OffsetDateTime::now_utc();
+        Ok(row.map(|row| Self::from_row(row, now)))
+    }
+
+    /// Lock the playback until the transaction ends.
+    pub async fn get_for_update(
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        space_id: Uuid,
+        channel_id: Uuid,
+    ) -> Result<Option<Self>, ModelError> {
+        let row = sqlx::query_file_as!(
+            PlaybackRow,
+            "sql/playback/get_for_update.sql",
+            space_id,
+            channel_id
+        )
+        .fetch_optional(&mut **db)
+        .await?;
+        let now = OffsetDateTime::now_utc();
+        Ok(row.map(|row| Self::from_row(row, now)))
+    }
+
+    /// Play the Asset in the channel, replacing the current playback.
+    pub async fn play(
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        space_id: Uuid,
+        channel_id: Uuid,
+        asset_id: Uuid,
+        state: PlaybackState,
+        user_id: Uuid,
+        now: OffsetDateTime,
+    ) -> Result<Self, ModelError> {
+        state.validate()?;
+        let row = sqlx::query_file_as!(
+            PlaybackRow,
+            "sql/playback/play.sql",
+            space_id,
+            channel_id,
+            asset_id,
+            state.position_ms,
+            state.looping,
+            state.volume,
+            user_id,
+            now,
+        )
+        .fetch_one(&mut **db)
+        .await?;
+        Ok(Self::from_row(row, now))
+    }
+
+    pub async fn update(
+        &self,
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        state: PlaybackState,
+        user_id: Uuid,
+        now: OffsetDateTime,
+    ) -> Result<Self, ModelError> {
+        state.validate()?;
+        let row = sqlx::query_file_as!(
+            PlaybackRow,
+            "sql/playback/update.sql",
+            self.space_id,
+            self.channel_id,
+            state.playing,
+            state.position_ms,
+            state.looping,
+            state.volume,
+            user_id,
+            now,
+        )
+        .fetch_one(&mut **db)
+        .await?;
+        Ok(Self::from_row(row, now))
+    }
+
+    pub async fn delete<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+        channel_id: Uuid,
+    ) -> Result<bool, ModelError> {
+        let result = sqlx::query_file!("sql/playback/delete.sql", space_id, channel_id)
+            .execute(db)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::Duration;
+
+    fn playback(playing: bool, position_ms: i64, looping: bool) -> ChannelPlayback {
+        ChannelPlayback {
+            channel_id: Uuid::nil(),
+            space_id: Uuid::nil(),
+            asset_id: Uuid::nil(),
+            media_id: Uuid::nil(),
+            asset_name: "Tavern".to_string(),
+            mime_type: "audio/ogg".to_string(),
+            duration_ms: Some(60_000),
+            playing,
+            position_ms,
+            current_position_ms: position_ms,
+            looping,
+            volume: 0.5,
+            updated_by: None,
+            version: Uuid::nil(),
+            updated: OffsetDateTime::UNIX_EPOCH,
+        }
+    }
+
+    #[test]
+    fn position_follows_the_clock_while_playing() {
+        let now = OffsetDateTime::UNIX_EPOCH + Duration::seconds(10);
+        assert_eq!(playback(true, 5_000, false).position_at(now), 15_000);
+        assert_eq!(playback(false, 5_000, false).position_at(now), 5_000);
+
+        let later = OffsetDateTime::UNIX_EPOCH + Duration::seconds(70);
+        assert_eq!(playback(true, 5_000, false).position_at(later), 60_000);
+        assert_eq!(playback(true, 5_000, true).position_at(later), 15_000);
+
+        let earlier = OffsetDateTime::UNIX_EPOCH - Duration::seconds(1);
+        assert_eq!(playback(true, 5_000, false).position_at(earlier), 5_000);
+    }
+
+    #[test]
+    fn controls_anchor_the_state_at_now() {
+        let now = OffsetDateTime::UNIX_EPOCH + Duration::seconds(10);
+        let current = playback(true, 5_000, false);
+
+        let paused = current.state_after(&PlaybackAction::Pause, now).unwrap();
+        assert!(!paused.playing);
+        assert_eq!(paused.position_ms, 15_000);
+
+        let seeked = current
+            .state_after(
+                &PlaybackAction::Seek {
+                    position_ms: 90_000,
+                },
+                now,
+            )
+            .unwrap();
+        assert!(seeked.playing);
+        assert_eq!(seeked.position_ms, 60_000);
+
+        let louder = current
+            .state_after(&PlaybackAction::SetVolume { volume: 2.0 }, now)
+            .unwrap();
+        assert!(louder.validate().is_err());
+
+        let finished = playback(false, 60_000, false);
+        let resumed = finished.state_after(&PlaybackAction::Resume, now).unwrap();
+        assert!(resumed.playing);
+        assert_eq!(resumed.position_ms, 0);
+
+        assert!(current.state_after(&PlaybackAction::Stop, now).is_none());
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn db_test_playback_is_stored_and_replaced(pool: sqlx::PgPool) {
+        use crate::assets::{Asset, AssetPolicy};
+        use crate::channels::{Channel, ChannelType};
+        use crate::media::models::Media;
+        use crate::spaces::Space;
+        use crate::users::User;
+
+        let user = User::register(
+            &pool,
+            "playback@example.com",
+            "playback",
+            "Playback Tester",
+            "PlaybackPass123!",
+        )
+        .await
+        .expect("create user failed");
+        let space = Space::create(
+            &pool,
+            "Playback".to_string(),
+            &user.id,
+            String::new(),
+            None,
+            Some("d20"),
+        )
+        .await
+        .expect("create space failed");
+        let channel = Channel::create(
+            &pool,
+            &space.id,
+            "Tavern",
+            true,
+            Some("d20"),
+            ChannelType::InGame,
+        )
+        .await
+        .expect("create channel failed");
+        let media = Media::create(
+            &pool,
+            &Uuid::now_v7(),
+            "audio/ogg",
+            user.id,
+            "tavern.ogg",
+            "tavern.ogg",
+            String::new(),
+            1024,
+            "test",
+        )
+        .await
+        .expect("create media failed");
+        Media::set_duration(&pool, &media.id, 60_000)
+            .await
+            .expect("set duration failed");
+
+        let mut transaction = pool.begin().await.unwrap();
+        let asset = Asset::create(
+            &mut transaction,
+            space.id,
+            media.id,
+            user.id,
+            "Tavern",
+            AssetPolicy::Listed,
+        )
+        .await
+        .expect("create asset failed");
+        let now = OffsetDateTime::now_utc();
+        let state = PlaybackState {
+            playing: true,
+            position_ms: 1_000,
+            looping: true,
+            volume: 0.5,
+        };
+        let playing = ChannelPlayback::play(
+            &mut transaction,
+            space.id,
+            channel.id,
+            asset.id,
+            state,
+            user.id,
+            now,
+        )
+        .await
+        .expect("play failed");
+        assert_eq!(playing.asset_name, "Tavern");
+        assert_eq!(playing.duration_ms, Some(60_000));
+        assert_eq!(playing.current_position_ms, 1_000);
+
+        let paused_state = playing
+            .state_after(&PlaybackAction::Pause, now + time::Duration::seconds(2))
+            .unwrap();
+        let paused = playing
+            .update(&mut transaction, paused_state, user.id, now)
+            .await
+            .expect("pause failed");
+        assert!(!paused.playing);
+        assert_eq!(paused.position_ms, 3_000);
+        assert_ne!(paused.version, playing.version);
+
+        let replaced = ChannelPlayback::play(
+            &mut transaction,
+            space.id,
+            channel.id,
+            asset.id,
+            PlaybackState {
+                position_ms: 0,
+                ..state
+            },
+            user.id,
+            now,
+        )
+        .await
+        .expect("replace failed");
+        assert!(replaced.playing);
+        assert_eq!(replaced.position_ms, 0);
+        transaction.commit().await.unwrap();
+
+        let stored = ChannelPlayback::get(&pool, space.id, channel.id)
+            .await
+            .unwrap()
+            .expect("playback not stored");
+        assert_eq!(stored.version, replaced.version);
+        assert!(stored.current_position_ms >= 0);
+
+        assert!(
+            ChannelPlayback::delete(&pool, space.id, channel.id)
+                .await
+                .unwrap()
+        );
+        assert!(
+            ChannelPlayback::get(&pool, space.id, channel.id)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
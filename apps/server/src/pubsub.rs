@@ -25,6 +25,15 @@ pub enum PubSubMessage {
         /// Timestamp in milliseconds
         ts: i64,
     },
+    /// Every entry of the topic is dropped.
+    Purge {
+        topic: Cow<'static, str>,
+        /// Node ID
+        #[serde(default)]
+        node: Cow<'static, str>,
+        /// Timestamp in milliseconds
+        ts: i64,
+    },
 }
 
 impl PubSubMessage {
@@ -38,4 +47,13 @@ impl PubSubMessage {
             ts: now.unix_timestamp_nanos() as i64 / 1_000_000,
         }
     }
+
+    pub fn purge(topic: Cow<'static, str>) -> Self {
+        let now = OffsetDateTime::now_utc();
+        PubSubMessage::Purge {
+            topic,
+            node: node_id().into(),
+            ts: now.unix_timestamp_nanos() as i64 / 1_000_000,
+        }
+    }
 }
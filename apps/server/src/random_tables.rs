@@ -0,0 +1,7 @@
+pub mod api;
+pub(crate) mod handlers;
+mod models;
+
+pub use handlers::router;
+pub(crate) use models::RandomTables;
+pub use models::{RandomTable, RandomTableExport, RandomTableInput, RandomTableRow};
@@ -0,0 +1,66 @@
+use serde::Deserialize;
+use uuid::Uuid;
+
+use super::models::RandomTableInput;
+
+#[derive(Debug, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ListRandomTables {
+    pub space_id: Uuid,
+}
+
+#[derive(Debug, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryRandomTable {
+    pub space_id: Uuid,
+    pub table_id: Uuid,
+}
+
+#[derive(Debug, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateRandomTable {
+    pub space_id: Uuid,
+    pub table: RandomTableInput,
+}
+
+#[derive(Debug, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EditRandomTable {
+    pub space_id: Uuid,
+    pub table_id: Uuid,
+    pub expected_version: Uuid,
+    pub table: RandomTableInput,
+}
+
+#[derive(Debug, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteRandomTable {
+    pub space_id: Uuid,
+    pub table_id: Uuid,
+}
+
+#[derive(Debug, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportRandomTables {
+    pub space_id: Uuid,
+    pub tables: Vec<RandomTableInput>,
+    /// Replace the tables with the same keys, instead of failing.
+    #[serde(default)]
+    pub replace_existing: bool,
+}
+
+fn default_preview_count() -> u32 {
+    1
+}
+
+/// Roll on a table without sending a message. Nothing is stored.
+#[derive(Debug, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewRandomTable {
+    pub space_id: Uuid,
+    /// The key of the table
+    pub table: String,
+    /// How many times to roll, 1 to 20.
+    #[serde(default = "default_preview_count")]
+    pub count: u32,
+}
@@ -0,0 +1,188 @@
+use super::api::{
+    CreateRandomTable, DeleteRandomTable, EditRandomTable, ImportRandomTables, ListRandomTables,
+    PreviewRandomTable, QueryRandomTable,
+};
+use super::models::{RandomTable, RandomTableExport, RandomTables, SeedRng};
+use crate::csrf::{authenticate, authenticate_optional};
+use crate::error::{AppError, Find};
+use crate::interface::{missing, parse_body, parse_query, response};
+use crate::spaces::resolve_space_access;
+use hyper::Request;
+use hyper::body::Body;
+use shared_types::entities::TableRollResult;
+use uuid::Uuid;
+
+const MAX_PREVIEW_ROLLS: u32 = 20;
+
+async fn resolve_table_access(
+    ctx: &crate::context::AppContext,
+    space_id: Uuid,
+    user_id: Option<Uuid>,
+    manage: bool,
+) -> Result<(), AppError> {
+    let access = resolve_space_access(ctx, space_id, user_id).await?;
+    if manage {
+        if !access.is_game_master && !access.can_manage() {
+            return Err(AppError::NoPermission(
+                "Only game masters can manage random tables".to_string(),
+            ));
+        }
+    } else if !access.can_access {
+        return Err(AppError::NoPermission(
+            "You don't have permission to view this space".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+async fn list(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Vec<RandomTable>, AppError> {
+    let session = authenticate_optional(ctx, &req).await?;
+    let ListRandomTables { space_id } = parse_query(req.uri())?;
+    let user_id = session.map(|session| session.user_id);
+    resolve_table_access(ctx, space_id, user_id, false).await?;
+    RandomTable::list_by_space(&ctx.db, space_id)
+        .await
+        .map_err(Into::into)
+}
+
+async fn query(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<RandomTable, AppError> {
+    let session = authenticate_optional(ctx, &req).await?;
+    let QueryRandomTable { space_id, table_id } = parse_query(req.uri())?;
+    let user_id = session.map(|session| session.user_id);
+    resolve_table_access(ctx, space_id, user_id, false).await?;
+    RandomTable::get_by_id(&ctx.db, space_id, table_id)
+        .await?
+        .or_not_found()
+}
+
+async fn export(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<RandomTableExport, AppError> {
+    let session = authenticate_optional(ctx, &req).await?;
+    let ListRandomTables { space_id } = parse_query(req.uri())?;
+    let user_id = session.map(|session| session.user_id);
+    resolve_table_access(ctx, space_id, user_id, false).await?;
+    let tables = RandomTable::list_by_space(&ctx.db, space_id).await?;
+    Ok(RandomTableExport {
+        tables: tables.into_iter().map(|table| table.table).collect(),
+    })
+}
+
+async fn create(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<RandomTable, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let CreateRandomTable { space_id, table } = parse_body(req).await?;
+    resolve_table_access(ctx, space_id, Some(session.user_id), true).await?;
+    RandomTable::create(&ctx.db, space_id, table)
+        .await
+        .map_err(Into::into)
+}
+
+async fn edit(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<RandomTable, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let EditRandomTable {
+        space_id,
+        table_id,
+        expected_version,
+        table,
+    } = parse_body(req).await?;
+    resolve_table_access(ctx, space_id, Some(session.user_id), true).await?;
+    RandomTable::get_by_id(&ctx.db, space_id, table_id)
+        .await?
+        .or_not_found()?;
+    RandomTable::update(&ctx.db, space_id, table_id, expected_version, table)
+        .await?
+        .ok_or_else(|| AppError::Conflict("Random table version is stale".to_string()))
+}
+
+async fn delete(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<bool, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let DeleteRandomTable { space_id, table_id } = parse_body(req).await?;
+    resolve_table_access(ctx, space_id, Some(session.user_id), true).await?;
+    RandomTable::delete(&ctx.db, space_id, table_id)
+        .await
+        .map_err(Into::into)
+}
+
+async fn import(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Vec<RandomTable>, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let ImportRandomTables {
+        space_id,
+        tables,
+        replace_existing,
+    } = parse_body(req).await?;
+    resolve_table_access(ctx, space_id, Some(session.user_id), true).await?;
+    let mut transaction = ctx.db.begin().await?;
+    let imported =
+        RandomTable::import(&mut transaction, space_id, tables, replace_existing).await?;
+    transaction.commit().await?;
+    Ok(imported)
+}
+
+async fn preview(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Vec<TableRollResult>, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let PreviewRandomTable {
+        space_id,
+        table,
+        count,
+    } = parse_body(req).await?;
+    resolve_table_access(ctx, space_id, Some(session.user_id), true).await?;
+    if count == 0 || count > MAX_PREVIEW_ROLLS {
+        return Err(AppError::BadRequest(
+            "A preview rolls 1 to 20 times".to_string(),
+        ));
+    }
+    let tables = RandomTables::load(&ctx.db, space_id).await?;
+    let seed = Uuid::new_v4();
+    (0..count)
+        .map(|stream| {
+            tables
+                .roll(
+                    &table,
+                    &mut SeedRng::new(seed.as_bytes(), u64::from(stream)),
+                )
+                .ok_or(AppError::NotFound("random table"))
+        })
+        .collect()
+}
+
+pub async fn router(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+    path: &str,
+) -> Result<hyper::Response<Vec<u8>>, AppError> {
+    use hyper::Method;
+
+    match (path, req.method().clone()) {
+        ("/list", Method::GET) => response(list(ctx, req).await).await,
+        ("/query", Method::GET) => response(query(ctx, req).await).await,
+        ("/export", Method::GET) => response(export(ctx, req).await).await,
+        ("/create", Method::POST) => response(create(ctx, req).await).await,
+        ("/edit", Method::PUT) => response(edit(ctx, req).await).await,
+        ("/delete", Method::POST) => response(delete(ctx, req).await).await,
+        ("/import", Method::POST) => response(import(ctx, req).await).await,
+        ("/preview", Method::POST) => response(preview(ctx, req).await).await,
+        _ => missing(),
+    }
+}
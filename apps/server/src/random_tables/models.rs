@@ -0,0 +1,577 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use shared_types::entities::{Entity, ExprNode, TableRollResult};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::characters::normalize_ident;
+use crate::error::{ModelError, ValidationFailed};
+use crate::messages::Entities;
+
+const MAX_NAME_LENGTH: usize = 64;
+const MAX_DESCRIPTION_LENGTH: usize = 2000;
+const MAX_ROW_TEXT_LENGTH: usize = 1000;
+pub const MAX_ROWS: usize = 256;
+pub const MAX_WEIGHT: u32 = 10_000;
+/// Nested tables deeper than this are not rolled, which also stops tables referring to each other.
+const MAX_NESTING: usize = 8;
+/// The rolls on random tables a single message can make.
+const MAX_ROLLS_PER_MESSAGE: usize = 16;
+
+fn default_weight() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RandomTableRow {
+    /// How likely the row is, relative to the other rows. 1 to 10000.
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+    pub text: String,
+    /// The key of a table to roll on when this row comes up
+    #[serde(default)]
+    pub table: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RandomTableInput {
+    /// Rolled on in messages as `{table:key}`. Case-insensitive.
+    pub key: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub rows: Vec<RandomTableRow>,
+}
+
+/// A weighted random table of a space, such as loot or weather.
+#[derive(Debug, Clone, Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RandomTable {
+    pub id: Uuid,
+    pub space_id: Uuid,
+    #[serde(flatten)]
+    pub table: RandomTableInput,
+    pub version: Uuid,
+    #[specta(type = String)]
+    #[serde(with = "time::serde::rfc3339")]
+    pub created: OffsetDateTime,
+    #[specta(type = String)]
+    #[serde(with = "time::serde::rfc3339")]
+    pub modified: OffsetDateTime,
+}
+
+/// The random tables of a space, in the format they are imported.
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RandomTableExport {
+    pub tables: Vec<RandomTableInput>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct RandomTableRecord {
+    id: Uuid,
+    space_id: Uuid,
+    key: String,
+    name: String,
+    description: String,
+    rows: Value,
+    version: Uuid,
+    created: OffsetDateTime,
+    modified: OffsetDateTime,
+}
+
+impl TryFrom<RandomTableRecord> for RandomTable {
+    type Error = ModelError;
+
+    fn try_from(record: RandomTableRecord) -> Result<Self, Self::Error> {
+        let rows = serde_json::from_value(record.rows).map_err(|err| {
+            ModelError::Unexpected(anyhow::anyhow!(
+                "Failed to decode the rows of random table {}: {err}",
+                record.id
+            ))
+        })?;
+        Ok(RandomTable {
+            id: record.id,
+            space_id: record.space_id,
+            table: RandomTableInput {
+                key: record.key,
+                name: record.name,
+                description: record.description,
+                rows,
+            },
+            version: record.version,
+            created: record.created,
+            modified: record.modified,
+        })
+    }
+}
+
+pub(crate) fn normalize_table_key(key: &str) -> Result<String, ValidationFailed> {
+    normalize_ident(key).map(|key| key.to_lowercase())
+}
+
+impl RandomTableInput {
+    fn normalize(self) -> Result<Self, ValidationFailed> {
+        let key = normalize_table_key(&self.key)?;
+        let name = self.name.trim().to_string();
+        if name.is_empty() {
+            return Err(ValidationFailed("Table name can not be empty."));
+        }
+        if name.chars().count() > MAX_NAME_LENGTH {
+            return Err(ValidationFailed(
+                "Table name shall not be more than 64 characters.",
+            ));
+        }
+        let description = self.description.trim().to_string();
+        if description.chars().count() > MAX_DESCRIPTION_LENGTH {
+            return Err(ValidationFailed(
+                "Table description shall not be more than 2000 characters.",
+            ));
+        }
+        if self.rows.is_empty() || self.rows.len() > MAX_ROWS {
+            return Err(ValidationFailed("A random table has 1 to 256 rows."));
+        }
+        let rows = self
+            .rows
+            .into_iter()
+            .map(|row| {
+                if row.weight == 0 || row.weight > MAX_WEIGHT {
+                    return Err(ValidationFailed("Row weight shall be from 1 to 10000."));
+                }
+                let text = row.text.trim().to_string();
+                if text.chars().count() > MAX_ROW_TEXT_LENGTH {
+                    return Err(ValidationFailed(
+                        "Row text shall not be more than 1000 characters.",
+                    ));
+                }
+                let table = row
+                    .table
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|table| !table.is_empty())
+                    .map(normalize_table_key)
+                    .transpose()?;
+                if text.is_empty() && table.is_none() {
+                    return Err(ValidationFailed(
+                        "A row needs a text or a table to roll on.",
+                    ));
+                }
+                Ok(RandomTableRow {
+                    weight: row.weight,
+                    text,
+                    table,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(RandomTableInput {
+            key,
+            name,
+            description,
+            rows,
+        })
+    }
+
+    fn rows_value(&self) -> Result<Value, ModelError> {
+        serde_json::to_value(&self.rows).map_err(|err| ModelError::Unexpected(err.into()))
+    }
+}
+
+impl RandomTable {
+    pub async fn list_by_space<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+    ) -> Result<Vec<Self>, ModelError> {
+        sqlx::query_file_as!(RandomTableRecord, "sql/random_tables/list.sql", space_id)
+            .fetch_all(db)
+            .await?
+            .into_iter()
+            .map(TryFrom::try_from)
+            .collect()
+    }
+
+    pub async fn get_by_id<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+        id: Uuid,
+    ) -> Result<Option<Self>, ModelError> {
+        sqlx::query_file_as!(RandomTableRecord, "sql/random_tables/get.sql", space_id, id)
+            .fetch_optional(db)
+            .await?
+            .map(TryFrom::try_from)
+            .transpose()
+    }
+
+    pub async fn create<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+        table: RandomTableInput,
+    ) -> Result<Self, ModelError> {
+        let table = table.normalize()?;
+        sqlx::query_file_as!(
+            RandomTableRecord,
+            "sql/random_tables/insert.sql",
+            space_id,
+            table.key,
+            table.name,
+            table.description,
+            table.rows_value()?,
+        )
+        .fetch_one(db)
+        .await?
+        .try_into()
+    }
+
+    /// Returns `None` if the table has been changed since `expected_version`.
+    pub async fn update<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+        id: Uuid,
+        expected_version: Uuid,
+        table: RandomTableInput,
+    ) -> Result<Option<Self>, ModelError> {
+        let table = table.normalize()?;
+        sqlx::query_file_as!(
+            RandomTableRecord,
+            "sql/random_tables/update.sql",
+            space_id,
+            id,
+            expected_version,
+            table.key,
+            table.name,
+            table.description,
+            table.rows_value()?,
+        )
+        .fetch_optional(db)
+        .await?
+        .map(TryFrom::try_from)
+        .transpose()
+    }
+
+    pub async fn delete<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+        id: Uuid,
+    ) -> Result<bool, ModelError> {
+        let result = sqlx::query_file!("sql/random_tables/delete.sql", space_id, id)
+            .execute(db)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Import tables by key. A table with the key of an existing one is a conflict, unless
+    /// `replace_existing` is set.
+    pub async fn import(
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        space_id: Uuid,
+        tables: Vec<RandomTableInput>,
+        replace_existing: bool,
+    ) -> Result<Vec<Self>, ModelError> {
+        let mut imported = Vec::with_capacity(tables.len());
+        for table in tables {
+            let table = table.normalize()?;
+            let record = if replace_existing {
+                sqlx::query_file_as!(
+                    RandomTableRecord,
+                    "sql/random_tables/upsert.sql",
+                    space_id,
+                    table.key,
+                    table.name,
+                    table.description,
+                    table.rows_value()?,
+                )
+                .fetch_one(&mut **db)
+                .await?
+            } else {
+                sqlx::query_file_as!(
+                    RandomTableRecord,
+                    "sql/random_tables/insert.sql",
+                    space_id,
+                    table.key,
+                    table.name,
+                    table.description,
+                    table.rows_value()?,
+                )
+                .fetch_one(&mut **db)
+                .await?
+            };
+            imported.push(record.try_into()?);
+        }
+        Ok(imported)
+    }
+}
+
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// A deterministic random number generator (SplitMix64), so that a roll can be repeated from the
+/// seed of its message.
+#[derive(Debug, Clone)]
+pub struct SeedRng(u64);
+
+impl SeedRng {
+    /// `stream` separates the rolls of one seed, such as several tables in a message.
+    pub fn new(seed: &[u8], stream: u64) -> Self {
+        let seed = seed
+            .iter()
+            .fold(0u64, |state, byte| (state << 8) | u64::from(*byte));
+        // Mixed, so that the streams do not overlap as shifted copies of each other.
+        SeedRng(mix(seed ^ mix(stream)))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        mix(self.0)
+    }
+
+    /// From 1 to `bound`.
+    fn roll(&mut self, bound: u32) -> u32 {
+        (self.next_u64() % u64::from(bound.max(1))) as u32 + 1
+    }
+}
+
+/// The tables of a space by key, for rolling.
+#[derive(Debug, Clone, Default)]
+pub struct RandomTables(HashMap<String, RandomTable>);
+
+impl RandomTables {
+    pub fn new(tables: Vec<RandomTable>) -> Self {
+        RandomTables(
+            tables
+                .into_iter()
+                .map(|table| (table.table.key.clone(), table))
+                .collect(),
+        )
+    }
+
+    pub async fn load<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+    ) -> Result<Self, ModelError> {
+        RandomTable::list_by_space(db, space_id)
+            .await
+            .map(Self::new)
+    }
+
+    /// Load the tables if the message rolls on any, and check that they exist.
+    pub async fn for_entities<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+        entities: &Entities,
+    ) -> Result<Option<Self>, ModelError> {
+        let keys = table_keys(entities);
+        if keys.is_empty() {
+            return Ok(None);
+        }
+        if keys.len() > MAX_ROLLS_PER_MESSAGE {
+            return Err(ValidationFailed("A message can roll on up to 16 random tables.").into());
+        }
+        let tables = Self::load(db, space_id).await?;
+        for key in keys {
+            let known = normalize_table_key(key)
+                .ok()
+                .is_some_and(|key| tables.0.contains_key(&key));
+            if !known {
+                return Err(ModelError::NotFound("random table"));
+            }
+        }
+        Ok(Some(tables))
+    }
+
+    /// Roll on the table. A missing or too deeply nested table ends the chain of nested rolls.
+    pub fn roll(&self, key: &str, rng: &mut SeedRng) -> Option<TableRollResult> {
+        self.roll_nested(key, rng, 0)
+    }
+
+    fn roll_nested(&self, key: &str, rng: &mut SeedRng, depth: usize) -> Option<TableRollResult> {
+        if depth > MAX_NESTING {
+            return None;
+        }
+        let table = self.0.get(&normalize_table_key(key).ok()?)?;
+        let rows = &table.table.rows;
+        let total_weight = rows.iter().map(|row| row.weight).sum::<u32>();
+        let roll = rng.roll(total_weight);
+        let mut reached = 0;
+        let (index, row) = rows.iter().enumerate().find(|(_, row)| {
+            reached += row.weight;
+            reached >= roll
+        })?;
+        let nested = row
+            .table
+            .as_deref()
+            .and_then(|key| self.roll_nested(key, rng, depth + 1))
+            .map(Box::new);
+        Some(TableRollResult {
+            table_id: table.id,
+            table_version: table.version,
+            name: table.table.name.clone(),
+            roll,
+            total_weight,
+            row: index as u32,
+            text: row.text.clone(),
+            nested,
+        })
+    }
+
+    /// Fill in the results of the rolls on tables in a message. Every roll has its own stream of
+    /// the seed, so rolling again gives the same results while the tables are unchanged.
+    pub fn resolve(&self, seed: &[u8], entities: &mut Entities) {
+        let rolls = entities.0.iter_mut().filter_map(|entity| match entity {
+            Entity::Expr(expr) => match &mut expr.node {
+                ExprNode::Table(roll) => Some(roll),
+                _ => None,
+            },
+            _ => None,
+        });
+        for (stream, roll) in rolls.enumerate() {
+            let mut rng = SeedRng::new(seed, stream as u64);
+            roll.result = self.roll(&roll.table, &mut rng);
+        }
+    }
+}
+
+/// The tables a message rolls on.
+pub fn table_keys(entities: &Entities) -> Vec<&str> {
+    entities
+        .0
+        .iter()
+        .filter_map(|entity| match entity {
+            Entity::Expr(expr) => match &expr.node {
+                ExprNode::Table(roll) => Some(roll.table.as_str()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared_types::entities::{ExprEntity, Span, TableRoll};
+
+    fn row(weight: u32, text: &str, table: Option<&str>) -> RandomTableRow {
+        RandomTableRow {
+            weight,
+            text: text.to_string(),
+            table: table.map(str::to_string),
+        }
+    }
+
+    fn table(key: &str, rows: Vec<RandomTableRow>) -> RandomTable {
+        RandomTable {
+            id: Uuid::new_v4(),
+            space_id: Uuid::nil(),
+            table: RandomTableInput {
+                key: key.to_string(),
+                name: key.to_string(),
+                description: String::new(),
+                rows,
+            }
+            .normalize()
+            .unwrap(),
+            version: Uuid::nil(),
+            created: OffsetDateTime::UNIX_EPOCH,
+            modified: OffsetDateTime::UNIX_EPOCH,
+        }
+    }
+
+    fn table_roll(key: &str) -> Entity {
+        Entity::Expr(ExprEntity {
+            span: Span { start: 0, len: 1 },
+            node: ExprNode::Table(TableRoll {
+                table: key.to_string(),
+                result: None,
+            }),
+        })
+    }
+
+    #[test]
+    fn tables_are_validated() {
+        let input = |rows| RandomTableInput {
+            key: " Loot ".to_string(),
+            name: "Loot".to_string(),
+            description: String::new(),
+            rows,
+        };
+        let loot = input(vec![row(2, " Coins ", Some(" Coins "))])
+            .normalize()
+            .unwrap();
+        assert_eq!(loot.key, "loot");
+        assert_eq!(loot.rows, [row(2, "Coins", Some("coins"))]);
+        assert!(input(Vec::new()).normalize().is_err());
+        assert!(input(vec![row(0, "Nothing", None)]).normalize().is_err());
+        assert!(input(vec![row(1, " ", Some(""))]).normalize().is_err());
+    }
+
+    #[test]
+    fn rolls_follow_weights_and_nesting() {
+        let tables = RandomTables::new(vec![
+            table(
+                "loot",
+                vec![row(1, "Nothing", None), row(3, "Coins", Some("coins"))],
+            ),
+            table("coins", vec![row(1, "12 gp", None)]),
+            table("echo", vec![row(1, "Again", Some("echo"))]),
+        ]);
+        let mut counts = [0; 2];
+        for stream in 0..1000 {
+            let result = tables
+                .roll("LOOT", &mut SeedRng::new(&[1, 2, 3, 4], stream))
+                .unwrap();
+            assert_eq!(result.total_weight, 4);
+            assert!((1..=4).contains(&result.roll));
+            assert_eq!(result.row, if result.roll == 1 { 0 } else { 1 });
+            assert_eq!(result.nested.is_some(), result.row == 1);
+            counts[result.row as usize] += 1;
+        }
+        assert!(counts[1] > counts[0] * 2, "{counts:?}");
+
+        let mut depth = 0;
+        let mut echo = tables.roll("echo", &mut SeedRng::new(&[0; 4], 0));
+        while let Some(result) = echo {
+            depth += 1;
+            echo = result.nested.map(|nested| *nested);
+        }
+        assert_eq!(depth, MAX_NESTING + 1);
+        assert!(
+            tables
+                .roll("weather", &mut SeedRng::new(&[0; 4], 0))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn messages_resolve_the_same_every_time() {
+        let tables = RandomTables::new(vec![table(
+            "weather",
+            (1..=20).map(|n| row(1, &n.to_string(), None)).collect(),
+        )]);
+        let mut entities = Entities(vec![table_roll("weather"), table_roll("weather")]);
+        assert_eq!(table_keys(&entities), ["weather", "weather"]);
+        tables.resolve(&[9, 8, 7, 6], &mut entities);
+        let results = |entities: &Entities| {
+            entities
+                .0
+                .iter()
+                .map(|entity| match entity {
+                    Entity::Expr(ExprEntity {
+                        node: ExprNode::Table(roll),
+                        ..
+                    }) => roll.result.clone().unwrap(),
+                    _ => unreachable!(),
+                })
+                .collect::<Vec<_>>()
+        };
+        let first = results(&entities);
+        let mut again = Entities(vec![table_roll("weather"), table_roll("weather")]);
+        tables.resolve(&[9, 8, 7, 6], &mut again);
+        assert_eq!(results(&again), first);
+    }
+}
@@ -16,6 +16,7 @@ pub const SEND_MESSAGE_USER_PER_MINUTE: u32 = 120;
 pub const CREATE_SPACE_USER_PER_HOUR: u32 = 10;
 pub const CREATE_CHANNEL_USER_PER_HOUR: u32 = 30;
 pub const UPLOAD_USER_PER_HOUR: u32 = 60;
+pub const REPORT_USER_PER_HOUR: u32 = 30;
 
 pub fn per_minute(limit: u32) -> Quota {
     Quota::per_minute(NonZeroU32::new(limit).expect("rate limit must be non-zero"))
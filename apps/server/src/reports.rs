@@ -0,0 +1,6 @@
+pub mod api;
+mod handlers;
+pub mod models;
+
+pub use handlers::{flag_message, router, start_rate_limiter_cleanup};
+pub use models::Report;
@@ -0,0 +1,38 @@
+use serde::Deserialize;
+use uuid::Uuid;
+
+use super::models::{ReportReason, ReportStatus, ReportTargetKind};
+
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateReport {
+    pub target_kind: ReportTargetKind,
+    pub target_id: Uuid,
+    pub reason: ReportReason,
+    #[serde(default)]
+    pub comment: String,
+}
+
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportQueueQuery {
+    /// The queue of a space, omit it for the site-wide queue
+    #[serde(default)]
+    pub space_id: Option<Uuid>,
+    #[serde(default)]
+    pub status: Option<ReportStatus>,
+    /// The id of the last report of the previous page
+    #[serde(default)]
+    pub before: Option<Uuid>,
+    #[serde(default)]
+    #[specta(type = Option<f64>)]
+    pub limit: Option<i64>,
+}
+
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct HandleReport {
+    pub report_id: Uuid,
+    #[serde(default)]
+    pub note: String,
+}
@@ -0,0 +1,305 @@
+use std::sync::LazyLock;
+
+use super::api::{CreateReport, HandleReport, ReportQueueQuery};
+use super::models::{Report, ReportReason, ReportStatus, ReportTargetKind};
+use crate::admin::{AdminAction, AdminAuditEntry, is_site_admin};
+use crate::channels::{Channel, ChannelMember};
+use crate::csrf::authenticate;
+use crate::error::{AppError, Find};
+use crate::interface::{self, Response, missing, parse_query, response};
+use crate::media::models::Media;
+use crate::messages::Message;
+use crate::rate_limit;
+use crate::spaces::{ContentRule, Space, resolve_space_access};
+use crate::users::User;
+use governor::{DefaultKeyedRateLimiter, RateLimiter};
+use hyper::Request;
+use hyper::body::Body;
+use serde_json::json;
+use uuid::Uuid;
+
+const DEFAULT_QUEUE_SIZE: i64 = 50;
+const MAX_QUEUE_SIZE: i64 = 200;
+
+static REPORT_LIMITER: LazyLock<DefaultKeyedRateLimiter<Uuid>> =
+    LazyLock::new(|| RateLimiter::keyed(rate_limit::per_hour(rate_limit::REPORT_USER_PER_HOUR)));
+
+pub fn start_rate_limiter_cleanup() {
+    rate_limit::start_cleanup_task(
+        || {
+            REPORT_LIMITER.retain_recent();
+        },
+        || {
+            REPORT_LIMITER.shrink_to_fit();
+        },
+    );
+}
+
+fn to_snapshot(value: &impl serde::Serialize) -> Result<serde_json::Value, AppError> {
+    serde_json::to_value(value).map_err(|err| AppError::Unexpected(err.into()))
+}
+
+/// Load the reported content as the reporter can see it.
+///
+/// Returns the space whose admins should triage the report, and the snapshot to keep. Reports
+/// on spaces, users and media go to the site admins, since space admins may be the subject.
+async fn resolve_target(
+    ctx: &crate::context::AppContext,
+    reporter_id: Uuid,
+    target_kind: ReportTargetKind,
+    target_id: Uuid,
+) -> Result<(Option<Uuid>, serde_json::Value), AppError> {
+    match target_kind {
+        ReportTargetKind::Message => {
+            let message = Message::get(&ctx.db, &target_id, Some(&reporter_id))
+                .await
+                .or_not_found()?;
+            let channel = Channel::get_by_id(&ctx.db, &message.channel_id)
+                .await
+                .or_not_found()?;
+            let access = resolve_space_access(ctx, channel.space_id, Some(reporter_id)).await?;
+            if !access.can_access {
+                return Err(AppError::NotFound("message"));
+            }
+            if !channel.is_public {
+                let mut conn = ctx.db.acquire().await?;
+                ChannelMember::get(&mut conn, reporter_id, channel.space_id, channel.id)
+                    .await
+                    .or_no_permission()?;
+            }
+            let snapshot = json!({
+                "message": to_snapshot(&message)?,
+                "channelName": channel.name,
+            });
+            Ok((Some(channel.space_id), snapshot))
+        }
+        ReportTargetKind::Space => {
+            let access = resolve_space_access(ctx, target_id, Some(reporter_id)).await?;
+            let space = Space::get_by_id(&ctx.db, &target_id).await.or_not_found()?;
+            if !access.can_access && !space.explorable {
+                return Err(AppError::NotFound("space"));
+            }
+            Ok((None, to_snapshot(&space)?))
+        }
+        ReportTargetKind::User => {
+            if target_id == reporter_id {
+                return Err(AppError::BadRequest(
+                    "You can not report yourself".to_string(),
+                ));
+            }
+            let user = User::get_by_id(&ctx.db, &target_id).await.or_not_found()?;
+            Ok((None, to_snapshot(&user)?))
+        }
+        ReportTargetKind::Media => {
+            let media = Media::get_by_id(&ctx.db, &target_id).await.or_not_found()?;
+            Ok((None, to_snapshot(&media)?))
+        }
+    }
+}
+
+async fn create(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Report, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    REPORT_LIMITER
+        .check_key(&session.user_id)
+        .map_err(|_| AppError::LimitExceeded("Too many reports, please try again later."))?;
+    let CreateReport {
+        target_kind,
+        target_id,
+        reason,
+        comment,
+    } = interface::parse_body(req).await?;
+    let (space_id, snapshot) = resolve_target(ctx, session.user_id, target_kind, target_id).await?;
+    let report = Report::create(
+        &ctx.db,
+        Some(session.user_id),
+        target_kind,
+        target_id,
+        space_id,
+        reason,
+        &comment,
+        snapshot,
+    )
+    .await?;
+    tracing::info!(
+        report_id = %report.id,
+        reporter_id = %session.user_id,
+        ?target_kind,
+        %target_id,
+        "A report was submitted"
+    );
+    Ok(report)
+}
+
+/// Queue a message that matched a `Flag` content rule for the space admins.
+///
+/// The message has already been sent, so a failure is only logged.
+pub async fn flag_message(
+    ctx: &crate::context::AppContext,
+    space_id: Uuid,
+    message: &Message,
+    rule: &ContentRule,
+) {
+    let mut message = message.clone();
+    if message.whisper_to_users.is_some() {
+        message.hide(None);
+    }
+    let comment = format!("Matched the content rule \"{}\"", rule.pattern);
+    let result = async {
+        let snapshot = json!({ "message": to_snapshot(&message)? });
+        Report::create(
+            &ctx.db,
+            None,
+            ReportTargetKind::Message,
+            message.id,
+            Some(space_id),
+            ReportReason::Other,
+            &comment,
+            snapshot,
+        )
+        .await
+        .map_err(AppError::from)
+    }
+    .await;
+    match result {
+        Ok(report) => tracing::info!(
+            report_id = %report.id,
+            message_id = %message.id,
+            "A message was flagged by a content rule"
+        ),
+        Err(err) => tracing::warn!(
+            message_id = %message.id,
+            error = %err,
+            "Failed to flag a message"
+        ),
+    }
+}
+
+async fn can_manage_space(
+    ctx: &crate::context::AppContext,
+    space_id: Uuid,
+    user_id: Uuid,
+) -> Result<bool, AppError> {
+    match resolve_space_access(ctx, space_id, Some(user_id)).await {
+        Ok(access) => Ok(access.can_manage()),
+        // The space has been deleted, only site admins can handle its reports.
+        Err(AppError::NotFound(_)) => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+async fn queue(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Vec<Report>, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let ReportQueueQuery {
+        space_id,
+        status,
+        before,
+        limit,
+    } = parse_query(req.uri())?;
+    let limit = limit.unwrap_or(DEFAULT_QUEUE_SIZE).clamp(1, MAX_QUEUE_SIZE);
+    let as_space_admin = match space_id {
+        Some(space_id) => can_manage_space(ctx, space_id, session.user_id).await?,
+        None => false,
+    };
+    if !as_space_admin && !is_site_admin(ctx, &session.user_id) {
+        return Err(AppError::NoPermission(
+            "Only space admins and site admins can view reports".to_string(),
+        ));
+    }
+    let reports = Report::list(&ctx.db, space_id, status, before, limit).await?;
+    if !as_space_admin {
+        AdminAuditEntry::record(
+            &ctx.db,
+            session.user_id,
+            AdminAction::ListReports,
+            space_id,
+            json!({ "status": status, "before": before, "limit": limit }),
+        )
+        .await?;
+    }
+    Ok(reports)
+}
+
+async fn handle(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+    status: ReportStatus,
+) -> Result<Report, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let HandleReport { report_id, note } = interface::parse_body(req).await?;
+    let mut trans = ctx.db.begin().await?;
+    let report = Report::get_for_update(&mut trans, report_id)
+        .await
+        .or_not_found()?;
+    if !report.status.is_pending() {
+        return Err(AppError::BadRequest(
+            "The report has already been handled".to_string(),
+        ));
+    }
+    if status == ReportStatus::Escalated
+        && (report.status == ReportStatus::Escalated || report.space_id.is_none())
+    {
+        return Err(AppError::BadRequest(
+            "The report is already with the site admins".to_string(),
+        ));
+    }
+    // Space admins triage open reports in their space; escalated ones are left to site admins.
+    let as_space_admin = match report.space_id {
+        Some(space_id) if report.status == ReportStatus::Open => {
+            can_manage_space(ctx, space_id, session.user_id).await?
+        }
+        _ => false,
+    };
+    if !as_space_admin && !is_site_admin(ctx, &session.user_id) {
+        return Err(AppError::NoPermission(
+            "You can not handle this report".to_string(),
+        ));
+    }
+    let report = Report::set_status(&mut trans, report_id, status, session.user_id, &note).await?;
+    if !as_space_admin {
+        AdminAuditEntry::record(
+            &mut *trans,
+            session.user_id,
+            AdminAction::HandleReport,
+            Some(report_id),
+            json!({ "status": status, "note": report.resolution_note }),
+        )
+        .await?;
+    }
+    trans.commit().await?;
+    tracing::info!(
+        %report_id,
+        handler_id = %session.user_id,
+        ?status,
+        "A report was handled"
+    );
+    Ok(report)
+}
+
+pub async fn router(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+    path: &str,
+) -> Result<Response, AppError> {
+    use hyper::Method;
+
+    match (path, req.method().clone()) {
+        ("/create", Method::POST) => response(create(ctx, req).await).await,
+        ("/queue", Method::GET) => response(queue(ctx, req).await).await,
+        ("/resolve", Method::POST) => {
+            response(handle(ctx, req, ReportStatus::Resolved).await).await
+        }
+        ("/dismiss", Method::POST) => {
+            response(handle(ctx, req, ReportStatus::Dismissed).await).await
+        }
+        ("/escalate", Method::POST) => {
+            response(handle(ctx, req, ReportStatus::Escalated).await).await
+        }
+        _ => missing(),
+    }
+}
@@ -0,0 +1,263 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::error::{ModelError, ValidationFailed};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, specta::Type, sqlx::Type)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[sqlx(type_name = "report_target_kind", rename_all = "PascalCase")]
+pub enum ReportTargetKind {
+    Message,
+    Space,
+    User,
+    Media,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, specta::Type, sqlx::Type)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[sqlx(type_name = "report_reason", rename_all = "PascalCase")]
+pub enum ReportReason {
+    Spam,
+    Harassment,
+    HateSpeech,
+    SexualContent,
+    Violence,
+    IllegalContent,
+    Impersonation,
+    Other,
+}
+
+/// Open reports wait for the space admins, or the site admins if the report has no space.
+/// Escalated reports are left to the site admins.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, specta::Type, sqlx::Type)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[sqlx(type_name = "report_status", rename_all = "PascalCase")]
+pub enum ReportStatus {
+    Open,
+    Escalated,
+    Resolved,
+    Dismissed,
+}
+
+impl ReportStatus {
+    pub fn is_pending(self) -> bool {
+        matches!(self, ReportStatus::Open | ReportStatus::Escalated)
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, specta::Type, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Report {
+    pub id: Uuid,
+    /// `None` for messages flagged by the content rules, or if the reporter has been deleted
+    pub reporter_id: Option<Uuid>,
+    pub target_kind: ReportTargetKind,
+    pub target_id: Uuid,
+    /// The space whose admins triage this report
+    pub space_id: Option<Uuid>,
+    pub reason: ReportReason,
+    pub comment: String,
+    /// The reported content at the time of the report
+    pub snapshot: serde_json::Value,
+    pub status: ReportStatus,
+    pub handler_id: Option<Uuid>,
+    pub resolution_note: String,
+    #[specta(type = String)]
+    #[serde(with = "time::serde::rfc3339")]
+    pub created: OffsetDateTime,
+    #[specta(type = Option<String>)]
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub handled: Option<OffsetDateTime>,
+}
+
+impl Report {
+    pub async fn create<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        reporter_id: Option<Uuid>,
+        target_kind: ReportTargetKind,
+        target_id: Uuid,
+        space_id: Option<Uuid>,
+        reason: ReportReason,
+        comment: &str,
+        snapshot: serde_json::Value,
+    ) -> Result<Report, ModelError> {
+        let comment = comment.trim();
+        check_note(comment)?;
+        sqlx::query_file_as!(
+            Report,
+            "sql/reports/create.sql",
+            reporter_id,
+            target_kind as ReportTargetKind,
+            target_id,
+            space_id,
+            reason as ReportReason,
+            comment,
+            snapshot
+        )
+        .fetch_one(db)
+        .await
+        .map_err(Into::into)
+    }
+
+    pub async fn get_for_update(
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        id: Uuid,
+    ) -> Result<Option<Report>, sqlx::Error> {
+        sqlx::query_file_as!(Report, "sql/reports/get_for_update.sql", id)
+            .fetch_optional(&mut **db)
+            .await
+    }
+
+    /// Newest first. `before` is the id of the last report of the previous page.
+    pub async fn list<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Option<Uuid>,
+        status: Option<ReportStatus>,
+        before: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<Report>, sqlx::Error> {
+        sqlx::query_file_as!(
+            Report,
+            "sql/reports/list.sql",
+            space_id,
+            status as Option<ReportStatus>,
+            before,
+            limit
+        )
+        .fetch_all(db)
+        .await
+    }
+
+    pub async fn set_status(
+        db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        id: Uuid,
+        status: ReportStatus,
+        handler_id: Uuid,
+        resolution_note: &str,
+    ) -> Result<Report, ModelError> {
+        let resolution_note = resolution_note.trim();
+        check_note(resolution_note)?;
+        sqlx::query_file_as!(
+            Report,
+            "sql/reports/set_status.sql",
+            id,
+            status as ReportStatus,
+            handler_id,
+            resolution_note
+        )
+        .fetch_one(&mut **db)
+        .await
+        .map_err(Into::into)
+    }
+}
+
+fn check_note(note: &str) -> Result<(), ValidationFailed> {
+    if note.chars().count() > 1024 {
+        return Err(ValidationFailed("The note is too long"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::users::User;
+
+    async fn create_test_user(pool: &sqlx::PgPool) -> User {
+        let raw = Uuid::new_v4().simple().to_string();
+        User::register(
+            pool,
+            &format!("report_{raw}@example.com"),
+            &format!("report_{}", &raw[..8]),
+            "Report Tester",
+            "ReportPass123!",
+        )
+        .await
+        .unwrap()
+    }
+
+    #[test]
+    fn long_notes_are_rejected() {
+        assert!(check_note(&"a".repeat(1024)).is_ok());
+        assert_eq!(
+            check_note(&"a".repeat(1025)),
+            Err(ValidationFailed("The note is too long"))
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn db_test_report_lifecycle(pool: sqlx::PgPool) {
+        let reporter = create_test_user(&pool).await;
+        let target = create_test_user(&pool).await;
+        let admin = create_test_user(&pool).await;
+        let snapshot = serde_json::json!({ "username": target.username });
+
+        let report = Report::create(
+            &pool,
+            Some(reporter.id),
+            ReportTargetKind::User,
+            target.id,
+            None,
+            ReportReason::Impersonation,
+            "  pretends to be me ",
+            snapshot.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.status, ReportStatus::Open);
+        assert_eq!(report.comment, "pretends to be me");
+        assert_eq!(report.snapshot, snapshot);
+
+        let duplicate = Report::create(
+            &pool,
+            Some(reporter.id),
+            ReportTargetKind::User,
+            target.id,
+            None,
+            ReportReason::Spam,
+            "",
+            snapshot.clone(),
+        )
+        .await;
+        assert!(matches!(duplicate, Err(ModelError::Conflict(_))));
+
+        let mut trans = pool.begin().await.unwrap();
+        let locked = Report::get_for_update(&mut trans, report.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(locked, report);
+        let resolved = Report::set_status(
+            &mut trans,
+            report.id,
+            ReportStatus::Resolved,
+            admin.id,
+            "Warned the user",
+        )
+        .await
+        .unwrap();
+        trans.commit().await.unwrap();
+        assert_eq!(resolved.handler_id, Some(admin.id));
+        assert!(resolved.handled.is_some());
+
+        let open = Report::list(&pool, None, Some(ReportStatus::Open), None, 10)
+            .await
+            .unwrap();
+        assert!(open.iter().all(|report| report.target_id != target.id));
+
+        // A handled report no longer blocks a new one.
+        Report::create(
+            &pool,
+            Some(reporter.id),
+            ReportTargetKind::User,
+            target.id,
+            None,
+            ReportReason::Harassment,
+            "",
+            snapshot,
+        )
+        .await
+        .unwrap();
+    }
+}
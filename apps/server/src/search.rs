@@ -0,0 +1,5 @@
+mod api;
+mod handlers;
+mod models;
+
+pub use handlers::router;
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::models::SearchKind;
+
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchSpace {
+    pub space_id: Uuid,
+    /// Split by whitespace, every word has to be found
+    pub keyword: String,
+    /// Search all kinds if absent
+    #[serde(default)]
+    pub kind: Option<SearchKind>,
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+/// A note, an entry or a character matching the search, the best matches first.
+#[derive(Serialize, Debug, Clone, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub kind: SearchKind,
+    pub id: Uuid,
+    /// The scope of an entry
+    pub scope_id: Option<Uuid>,
+    /// The title of a note, or the name of an entry or a character
+    pub title: String,
+    /// The line of the note text or the character description where a word was found
+    pub snippet: Option<String>,
+    pub rank: i32,
+}
@@ -0,0 +1,172 @@
+use super::api::{SearchHit, SearchSpace};
+use super::models::search_space;
+use crate::csrf::authenticate_optional;
+use crate::error::AppError;
+use crate::interface::{missing, parse_query, response};
+use crate::spaces::{ResourceAccessContext, resolve_resource_access_context, resolve_space_access};
+use hyper::Request;
+use hyper::body::Body;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+const KEYWORD_MAX_LEN: usize = 100;
+const MAX_TOKENS: usize = 8;
+const DEFAULT_LIMIT: u32 = 20;
+const MAX_LIMIT: u32 = 100;
+/// The matches the user can't view are dropped after the query, so the matches are fetched in
+/// pages of this size until there are enough hits.
+const CANDIDATE_PAGE_SIZE: i64 = 200;
+const SNIPPET_MAX_CHARS: usize = 160;
+/// How much of the line before the found word is kept in a snippet.
+const SNIPPET_LEADING_CHARS: usize = 40;
+
+/// The first line with one of the tokens, cut around the token if it's long.
+fn snippet(body: &str, tokens: &[String]) -> Option<String> {
+    body.lines().find_map(|line| {
+        let lowercase = line.to_lowercase();
+        let found = tokens
+            .iter()
+            .filter_map(|token| lowercase.find(token.as_str()))
+            .min()?;
+        let start = lowercase[..found]
+            .chars()
+            .count()
+            .saturating_sub(SNIPPET_LEADING_CHARS);
+        let snippet: String = line.chars().skip(start).take(SNIPPET_MAX_CHARS).collect();
+        Some(snippet.trim().to_string())
+    })
+}
+
+/// Search the notes, entries and characters of a space. Only the ones the user can view are
+/// returned.
+async fn space(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Vec<SearchHit>, AppError> {
+    let session = authenticate_optional(ctx, &req).await?;
+    let SearchSpace {
+        space_id,
+        keyword,
+        kind,
+        limit,
+    } = parse_query(req.uri())?;
+    let user_id = session.map(|session| session.user_id);
+    let keyword = keyword.trim();
+    if keyword.is_empty() {
+        return Err(AppError::BadRequest("keyword is empty".to_string()));
+    }
+    if keyword.len() > KEYWORD_MAX_LEN {
+        return Err(AppError::BadRequest(format!(
+            "keyword is too long (max {KEYWORD_MAX_LEN})"
+        )));
+    }
+    let mut tokens: Vec<String> = Vec::new();
+    for token in keyword.split_whitespace().map(str::to_lowercase) {
+        if !tokens.contains(&token) {
+            tokens.push(token);
+        }
+    }
+    if tokens.len() > MAX_TOKENS {
+        return Err(AppError::BadRequest(format!(
+            "keyword has too many words (max {MAX_TOKENS})"
+        )));
+    }
+    let access = resolve_space_access(ctx, space_id, user_id).await?;
+    if !access.can_access {
+        return Err(AppError::NoPermission(
+            "You don't have permission to view this space".to_string(),
+        ));
+    }
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT) as usize;
+
+    let mut contexts: HashMap<Option<Uuid>, ResourceAccessContext> = HashMap::new();
+    let mut hits = Vec::new();
+    let mut offset = 0;
+    loop {
+        let candidates = search_space(
+            &ctx.db,
+            space_id,
+            kind,
+            &tokens,
+            CANDIDATE_PAGE_SIZE,
+            offset,
+        )
+        .await?;
+        let last_page = (candidates.len() as i64) < CANDIDATE_PAGE_SIZE;
+        for candidate in candidates {
+            let context = match contexts.get(&candidate.access_channel_id) {
+                Some(context) => *context,
+                None => {
+                    let context = resolve_resource_access_context(
+                        ctx,
+                        space_id,
+                        candidate.access_channel_id,
+                        user_id,
+                    )
+                    .await?;
+                    contexts.insert(candidate.access_channel_id, context);
+                    context
+                }
+            };
+            if !candidate
+                .access_policy
+                .can_view(candidate.owner_id, user_id, context)
+            {
+                continue;
+            }
+            hits.push(SearchHit {
+                kind: candidate.kind,
+                id: candidate.id,
+                scope_id: candidate.scope_id,
+                snippet: candidate
+                    .body
+                    .as_deref()
+                    .and_then(|body| snippet(body, &tokens)),
+                title: candidate.title,
+                rank: candidate.rank,
+            });
+            if hits.len() == limit {
+                return Ok(hits);
+            }
+        }
+        if last_page {
+            break;
+        }
+        offset += CANDIDATE_PAGE_SIZE;
+    }
+    Ok(hits)
+}
+
+pub async fn router(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+    path: &str,
+) -> Result<hyper::Response<Vec<u8>>, AppError> {
+    use hyper::Method;
+
+    match (path, req.method().clone()) {
+        ("/space", Method::GET) => response(space(ctx, req).await).await,
+        _ => missing(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snippet_is_the_line_with_a_token() {
+        let tokens = vec!["dragon".to_string(), "cave".to_string()];
+        let body = "The village\n  A Dragon sleeps in the cave.  \nThe end";
+        assert_eq!(
+            snippet(body, &tokens).as_deref(),
+            Some("A Dragon sleeps in the cave.")
+        );
+        assert_eq!(snippet("Nothing here", &tokens), None);
+
+        let long = format!("{}龍 dragon{}", "x".repeat(100), "y".repeat(200));
+        let cut = snippet(&long, &tokens).unwrap();
+        assert_eq!(cut.chars().count(), SNIPPET_MAX_CHARS);
+        assert!(cut.starts_with(&format!("{}龍 dragon", "x".repeat(38))));
+    }
+}
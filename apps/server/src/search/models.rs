@@ -0,0 +1,211 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::spaces::AccessPolicy;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, specta::Type)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SearchKind {
+    Note,
+    Entry,
+    Character,
+}
+
+impl SearchKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SearchKind::Note => "NOTE",
+            SearchKind::Entry => "ENTRY",
+            SearchKind::Character => "CHARACTER",
+        }
+    }
+
+    pub fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "NOTE" => Some(SearchKind::Note),
+            "ENTRY" => Some(SearchKind::Entry),
+            "CHARACTER" => Some(SearchKind::Character),
+            _ => None,
+        }
+    }
+}
+
+/// A match of the search, before the access to it is checked.
+#[derive(Debug, Clone)]
+pub struct SearchCandidate {
+    pub kind: SearchKind,
+    pub id: Uuid,
+    pub scope_id: Option<Uuid>,
+    pub title: String,
+    /// The note text or the character description
+    pub body: Option<String>,
+    /// The creator of a note, or the owner of the scope of an entry or a character
+    pub owner_id: Option<Uuid>,
+    pub access_policy: AccessPolicy,
+    pub access_channel_id: Option<Uuid>,
+    pub rank: i32,
+}
+
+/// Search the notes, entries and characters of a space for lowercase `tokens`, the best
+/// matches first. Archived notes and characters are left out. The matches are paged by
+/// `limit` and `offset` in a stable order.
+pub async fn search_space<'c, T: sqlx::PgExecutor<'c>>(
+    db: T,
+    space_id: Uuid,
+    kind: Option<SearchKind>,
+    tokens: &[String],
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<SearchCandidate>, sqlx::Error> {
+    let phrase = tokens.join(" ");
+    let rows = sqlx::query_file!(
+        "sql/search/space.sql",
+        space_id,
+        kind.map(SearchKind::as_str),
+        tokens,
+        phrase,
+        limit,
+        offset
+    )
+    .fetch_all(db)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| {
+            Some(SearchCandidate {
+                kind: SearchKind::parse(&row.kind)?,
+                id: row.id,
+                scope_id: row.scope_id,
+                title: row.title,
+                body: row.body,
+                owner_id: row.owner_id,
+                access_policy: row.access_policy,
+                access_channel_id: row.access_channel_id,
+                rank: row.rank,
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::characters::Character;
+    use crate::entries::models::Entry;
+    use crate::notes::Note;
+    use crate::spaces::Space;
+    use crate::users::User;
+    use shared_types::messages::Entities;
+    use std::collections::BTreeMap;
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn db_test_search_space_ranks_names_before_text(pool: sqlx::PgPool) {
+        let raw = Uuid::new_v4().simple().to_string();
+        let user = User::register(
+            &pool,
+            &format!("search_{raw}@example.com"),
+            &format!("search_{}", &raw[..8]),
+            "Search Tester",
+            "SearchPass123!",
+        )
+        .await
+        .expect("failed to create test user");
+        let space = Space::create(
+            &pool,
+            format!("search_{}", &raw[..8]),
+            &user.id,
+            "search".to_string(),
+            None,
+            Some("d20"),
+        )
+        .await
+        .expect("failed to create space");
+        let mut tx = pool.begin().await.expect("begin failed");
+        let dragon = Note::create(
+            &mut tx,
+            space.id,
+            "The Red Dragon".to_string(),
+            vec!["Wyrm".to_string()],
+            Vec::new(),
+            user.id,
+            "Sleeps under the mountain".to_string(),
+            Entities::default(),
+            AccessPolicy::Public,
+            None,
+        )
+        .await
+        .expect("create failed");
+        let journal = Note::create(
+            &mut tx,
+            space.id,
+            "Journal".to_string(),
+            Vec::new(),
+            Vec::new(),
+            user.id,
+            "Day one\nWe met the red dragon today".to_string(),
+            Entities::default(),
+            AccessPolicy::GameMaster,
+            None,
+        )
+        .await
+        .expect("create failed");
+        let scale = Entry::create(
+            &mut tx,
+            space.scope_id,
+            "scale".to_string(),
+            vec!["red_scale".to_string()],
+            "Dragon Scale".to_string(),
+            None,
+            BTreeMap::new(),
+            Vec::new(),
+            None,
+        )
+        .await
+        .expect("create entry failed");
+        let alice = Character::create(
+            &mut tx,
+            space.id,
+            user.id,
+            "Alice",
+            "alice",
+            vec!["dragon_rider".to_string()],
+            "Rides a red dragon",
+            "",
+            AccessPolicy::Public,
+            None,
+            Vec::new(),
+        )
+        .await
+        .expect("create character failed");
+        tx.commit().await.expect("commit failed");
+
+        let tokens = vec!["red".to_string(), "dragon".to_string()];
+        let found = search_space(&pool, space.id, None, &tokens, 10, 0)
+            .await
+            .expect("search failed");
+        let ids: Vec<_> = found.iter().map(|hit| hit.id).collect();
+        assert_eq!(ids, vec![dragon.id, scale.id, alice.id, journal.id]);
+        assert_eq!(found[1].kind, SearchKind::Entry);
+        assert_eq!(found[1].scope_id, Some(space.scope_id));
+        assert_eq!(found[3].access_policy, AccessPolicy::GameMaster);
+
+        let page = search_space(&pool, space.id, None, &tokens, 2, 2)
+            .await
+            .expect("search failed");
+        let page_ids: Vec<_> = page.iter().map(|hit| hit.id).collect();
+        assert_eq!(page_ids, vec![alice.id, journal.id]);
+
+        let found = search_space(&pool, space.id, Some(SearchKind::Character), &tokens, 10, 0)
+            .await
+            .expect("search failed");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].body.as_deref(), Some("Rides a red dragon"));
+
+        let tokens = vec!["dragon".to_string(), "mountain".to_string()];
+        let found = search_space(&pool, space.id, None, &tokens, 10, 0)
+            .await
+            .expect("search failed");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, dragon.id);
+    }
+}
@@ -22,12 +22,12 @@ use tokio::sync::watch;
 
 use hyper::Request;
 use hyper::service::service_fn;
-use rusty_s3::S3Action;
 
 #[macro_use]
 mod utils;
 #[macro_use]
 mod error;
+mod admin;
 mod assets;
 mod cache;
 mod channels;
@@ -39,6 +39,7 @@ mod cors;
 mod csrf;
 mod db;
 mod disk_cache;
+mod encounters;
 mod entries;
 mod events;
 mod info;
@@ -48,19 +49,23 @@ mod media;
 mod messages;
 mod notes;
 mod notify;
+mod playback;
 mod pos;
 mod pubsub;
+mod random_tables;
 mod rate_limit;
 mod redis;
+mod reports;
 mod rs;
-mod s3;
 mod scopes;
+mod search;
 mod sentry_tunnel;
 mod server_metrics;
 mod session;
 mod shutdown;
 mod space_runtime;
 mod spaces;
+mod storage;
 mod ts;
 mod ttl;
 mod typegen;
@@ -106,6 +111,7 @@ async fn router(
         return Ok(sentry_tunnel::handler(ctx, req).await);
     }
     table!("/api/info", info::router);
+    table!("/api/admin", admin::router);
     table!("/api/assets", assets::router);
     table!("/api/messages", messages::router);
     table!("/api/users", users::router);
@@ -115,6 +121,11 @@ async fn router(
     table!("/api/spaces", spaces::router);
     table!("/api/notes", notes::router);
     table!("/api/entries", entries::router);
+    table!("/api/encounters", encounters::router);
+    table!("/api/playback", playback::router);
+    table!("/api/random_tables", random_tables::router);
+    table!("/api/reports", reports::router);
+    table!("/api/search", search::router);
     table!("/api/events", events::router);
     table!("/api/updates", events::router);
     missing()
@@ -228,27 +239,15 @@ async fn handler(
 }
 
 #[tracing::instrument(skip(storage))]
-async fn storage_check(storage: &s3::Storage, ci: bool) {
+async fn storage_check(storage: &dyn storage::Storage, ci: bool) {
     // Skip in CI
     if ci {
         return;
     }
-    let mut action = storage
-        .bucket()
-        .put_object(Some(storage.credentials()), "check");
-    action
-        .headers_mut()
-        .insert("content-type", "application/octet-stream");
-    let url = action.sign(std::time::Duration::from_secs(60));
-    storage
-        .client()
-        .put(url.as_str())
-        .header("content-type", "application/octet-stream")
-        .body(Vec::<u8>::new())
-        .send()
-        .await
-        .expect("Cannot connect to bucket");
-    tracing::info!("Object Storage is ready");
+    if let Err(err) = storage.check().await {
+        panic!("Cannot write to the {} storage: {err:?}", storage.name());
+    }
+    tracing::info!("Object Storage ({}) is ready", storage.name());
 }
 #[derive(Parser)]
 struct Cli {
@@ -309,6 +308,26 @@ struct ServeArgs {
     discourse_sso_secret: Option<String>,
     #[clap(long, env = "SECRET")]
     secret: String,
+    #[clap(long, env = "SITE_ADMINS", value_delimiter = ',')]
+    site_admins: Vec<uuid::Uuid>,
+    #[clap(
+        long,
+        env = "USER_MEDIA_QUOTA_MB",
+        help = "media storage quota of each user"
+    )]
+    user_media_quota_mb: Option<u64>,
+    #[clap(
+        long,
+        env = "SPACE_MEDIA_QUOTA_MB",
+        help = "media storage quota of each space"
+    )]
+    space_media_quota_mb: Option<u64>,
+    #[clap(
+        long,
+        env = "MEDIA_GC_INTERVAL_MINUTES",
+        help = "remove unreferenced media periodically, disabled if unset"
+    )]
+    media_gc_interval_minutes: Option<u64>,
     #[clap(long, env = "MAILGUN_DOMAIN", requires = "mailgun_api_key")]
     mailgun_domain: Option<String>,
     #[clap(long, env = "MAILGUN_API_KEY", requires = "mailgun_domain")]
@@ -321,6 +340,20 @@ struct ServeArgs {
     s3_access_key_id: Option<String>,
     #[clap(long, env = "S3_SECRET_ACCESS_KEY")]
     s3_secret_access_key: Option<String>,
+    #[clap(
+        long,
+        env = "LOCAL_STORAGE_PATH",
+        conflicts_with = "s3_endpoint_url",
+        help = "keep media in this directory instead of S3"
+    )]
+    local_storage_path: Option<PathBuf>,
+    #[clap(
+        long,
+        env = "LOCAL_STORAGE_URL",
+        default_value = "/api/media/objects",
+        help = "the URL of /api/media/objects for signed uploads to the local storage"
+    )]
+    local_storage_url: String,
     #[clap(long, help = "check only", default_value = "false")]
     check: bool,
     #[clap(long, env = "DISK_CACHE_PATH", help = "redb disk cache path")]
@@ -355,6 +388,37 @@ struct ServeArgs {
     entry_component_cache_mb: u64,
 }
 
+fn storage_config(args: &ServeArgs) -> storage::StorageConfig {
+    let s3 = (
+        args.s3_endpoint_url.clone(),
+        args.s3_bucket_name.clone(),
+        args.s3_access_key_id.clone(),
+        args.s3_secret_access_key.clone(),
+    );
+    match (s3, &args.local_storage_path) {
+        ((None, None, None, None), None) => {
+            tracing::error!("Neither S3 nor LOCAL_STORAGE_PATH is configured, uploads will fail");
+            storage::StorageConfig::Disabled
+        }
+        ((None, None, None, None), Some(path)) => storage::StorageConfig::Local {
+            path: path.clone(),
+            url: args.local_storage_url.clone(),
+        },
+        (
+            (Some(endpoint_url), Some(bucket_name), Some(access_key_id), Some(secret_access_key)),
+            _,
+        ) => storage::StorageConfig::S3(storage::S3Config {
+            endpoint_url,
+            bucket_name,
+            access_key_id,
+            secret_access_key,
+        }),
+        _ => panic!(
+            "S3_ENDPOINT_URL, S3_BUCKET_NAME, S3_ACCESS_KEY_ID and S3_SECRET_ACCESS_KEY must be configured together"
+        ),
+    }
+}
+
 fn disk_cache_config(args: &ServeArgs) -> Option<disk_cache::Config> {
     if args.disable_disk_cache {
         return None;
@@ -430,14 +494,8 @@ async fn main() {
         .from_env_lossy();
     tracing_subscriber::fmt().with_env_filter(filter).init();
 
-    let storage = std::sync::Arc::new(s3::Storage::new(s3::StorageConfig {
-        endpoint_url: args.s3_endpoint_url.clone(),
-        bucket_name: args.s3_bucket_name.clone(),
-        access_key_id: args.s3_access_key_id.clone(),
-        secret_access_key: args.s3_secret_access_key.clone(),
-    }));
-
-    storage_check(&storage, args.ci).await;
+    let storage = storage_config(&args).build(context::Signer::new(&args.secret));
+    storage_check(storage.as_ref(), args.ci).await;
 
     let socket = SocketAddr::new(args.host, args.port);
 
@@ -486,6 +544,13 @@ async fn main() {
             api_key: args.mailgun_api_key.clone(),
         },
         entry_component_cache_capacity: args.entry_component_cache_mb.saturating_mul(1024 * 1024),
+        site_admin_ids: args.site_admins.clone(),
+        user_media_quota: args
+            .user_media_quota_mb
+            .map(|mb| mb.saturating_mul(1024 * 1024)),
+        space_media_quota: args
+            .space_media_quota_mb
+            .map(|mb| mb.saturating_mul(1024 * 1024)),
     };
     let ctx = std::sync::Arc::new(context::AppContext::with_config(
         pool.clone(),
@@ -500,7 +565,7 @@ async fn main() {
     if ctx.config.app_url.is_none() {
         tracing::error!("APP_URL is not set");
     }
-    if ctx.config.public_media_url.is_none() {
+    if ctx.config.public_media_url.is_none() && ctx.storage().local().is_none() {
         tracing::error!("PUBLIC_MEDIA_URL is not set");
     }
 
@@ -541,6 +606,11 @@ async fn main() {
     spaces::start_rate_limiter_cleanup();
     channels::start_rate_limiter_cleanup();
     media::start_rate_limiter_cleanup();
+    if let Some(minutes) = args.media_gc_interval_minutes {
+        media::gc::start_gc_task(ctx.clone(), std::time::Duration::from_secs(minutes * 60));
+    }
+    reports::start_rate_limiter_cleanup();
+    notes::collab::start_save_task(ctx.clone());
     let timeout_counter = metrics::counter!("boluo_server_tcp_connections_timeout_total");
     let error_counter = metrics::counter!("boluo_server_tcp_connections_error_total");
 
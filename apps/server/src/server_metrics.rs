@@ -1,8 +1,9 @@
 use metrics::gauge;
 use std::time::Instant;
 
-#[derive(Debug, PartialEq, Eq)]
-struct ProcessMemorySnapshot {
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessMemorySnapshot {
     rss_bytes: u64,
     anonymous_bytes: u64,
     file_bytes: u64,
@@ -11,6 +12,23 @@ struct ProcessMemorySnapshot {
     threads: u64,
 }
 
+/// A point-in-time view of the gauges this module exports, for the admin API.
+#[derive(Debug, Clone, serde::Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerMetricsSnapshot {
+    pub memory: Option<ProcessMemorySnapshot>,
+    pub file_descriptors_used: u64,
+    pub file_descriptors_limit: Option<u64>,
+    pub db_pool_connections_total: u32,
+    pub db_pool_connections_idle: usize,
+    pub db_pool_connections_max: u32,
+    pub mailboxes: usize,
+    pub broadcast_mailboxes: usize,
+    pub mailbox_action_queue_depth: usize,
+    pub token_store_entries: usize,
+    pub pos_actors: usize,
+}
+
 #[cfg(target_os = "linux")]
 fn parse_status_value(status: &str, key: &str) -> Option<u64> {
     let line = status.lines().find(|line| line.starts_with(key))?;
@@ -211,6 +229,30 @@ pub fn update_runtime_metrics(space_store: &crate::space_runtime::SpaceStore) {
     space_store.update_metrics();
 }
 
+pub async fn snapshot(pool: &sqlx::PgPool) -> ServerMetricsSnapshot {
+    let memory = tokio::task::spawn_blocking(get_process_memory_snapshot)
+        .await
+        .ok()
+        .flatten();
+    let (file_descriptors_used, file_descriptors_limit) =
+        tokio::task::spawn_blocking(get_file_descriptor_snapshot)
+            .await
+            .unwrap_or((0, None));
+    ServerMetricsSnapshot {
+        memory,
+        file_descriptors_used,
+        file_descriptors_limit,
+        db_pool_connections_total: pool.size(),
+        db_pool_connections_idle: pool.num_idle(),
+        db_pool_connections_max: pool.options().get_max_connections(),
+        mailboxes: crate::events::context::mailbox_count(),
+        broadcast_mailboxes: crate::events::broadcast_table_len(),
+        mailbox_action_queue_depth: crate::events::context::mailbox_action_queue_depth(),
+        token_store_entries: crate::events::token_store_len(),
+        pos_actors: crate::messages::MESSAGE_POSITIONS.actor_count(),
+    }
+}
+
 pub fn start_update_metrics(
     pool: sqlx::PgPool,
     redis: Option<redis::aio::ConnectionManager>,
@@ -83,6 +83,23 @@ pub async fn revoke_session(
     Ok(())
 }
 
+/// Revoke every active session of a user, returning the number of sessions revoked.
+pub async fn revoke_user_sessions(
+    pool: &sqlx::PgPool,
+    redis: Option<&redis::aio::ConnectionManager>,
+    user_id: Uuid,
+) -> Result<usize, sqlx::Error> {
+    let session_ids = sqlx::query_file_scalar!("sql/users/session_revoke_by_user.sql", user_id)
+        .fetch_all(pool)
+        .await?;
+    for session_id in &session_ids {
+        CACHE
+            .invalidate(redis, CacheType::Session, *session_id)
+            .await;
+    }
+    Ok(session_ids.len())
+}
+
 #[test]
 fn test_session_sign() {
     let signer = crate::context::Signer::new("just a test");
@@ -13,10 +13,11 @@ use crate::channels::models::Member;
 use crate::channels::{Channel, ChannelMember};
 use crate::characters::Character;
 use crate::entries::component_cache::EntryComponentMemoryCache;
+use crate::entries::formula::{evaluate_formulas, refers_to_other_entries};
 use crate::entries::models::{Entry, EntryMetadata};
 use crate::notes::NoteMetadata;
 use crate::scopes::models::Scope;
-use crate::spaces::{Space, SpaceMember};
+use crate::spaces::{ContentFilter, Space, SpaceMember};
 
 type PersistentMap<K, V> = rpds::HashTrieMapSync<K, V>;
 
@@ -34,6 +35,8 @@ pub(crate) struct SpaceSnapshot {
     space: Space,
     latest_activity_us: Arc<AtomicI64>,
     pub(crate) settings: serde_json::Value,
+    /// Compiled when the snapshot is loaded or the rules change, and shared between revisions.
+    pub(crate) content_filter: Arc<ContentFilter>,
     pub(crate) channels: PersistentMap<Uuid, Channel>,
     pub(crate) characters: PersistentMap<Uuid, Character>,
     pub(crate) notes: PersistentMap<Uuid, NoteMetadata>,
@@ -47,6 +50,7 @@ pub(crate) struct SpaceSnapshot {
 struct SnapshotPayloadMismatch {
     space: bool,
     settings: bool,
+    content_rules: bool,
     channels: bool,
     characters: bool,
     notes: bool,
@@ -60,6 +64,7 @@ impl SnapshotPayloadMismatch {
     fn any(&self) -> bool {
         self.space
             || self.settings
+            || self.content_rules
             || self.channels
             || self.characters
             || self.notes
@@ -74,6 +79,7 @@ impl SnapshotPayloadMismatch {
 pub(crate) enum SpaceDelta {
     SpaceUpdated(Space),
     SettingsUpdated(serde_json::Value),
+    ContentRulesUpdated(Arc<ContentFilter>),
     InviteTokenUpdated(Uuid),
     ChannelUpserted(Channel),
     ChannelDeleted(Uuid),
@@ -145,6 +151,7 @@ impl SpaceSnapshot {
         SnapshotPayloadMismatch {
             space: current_space != reloaded_space,
             settings: self.settings != reloaded.settings,
+            content_rules: self.content_filter != reloaded.content_filter,
             channels: self.channels != reloaded.channels,
             characters: self.characters != reloaded.characters,
             notes: self.notes != reloaded.notes,
@@ -162,6 +169,7 @@ impl SpaceSnapshot {
             match delta {
                 SpaceDelta::SpaceUpdated(space) => next.space = space,
                 SpaceDelta::SettingsUpdated(settings) => next.settings = settings,
+                SpaceDelta::ContentRulesUpdated(filter) => next.content_filter = filter,
                 SpaceDelta::InviteTokenUpdated(token) => next.space.invite_token = token,
                 SpaceDelta::ChannelUpserted(channel) => {
                     next.channels.insert_mut(channel.id, channel);
@@ -345,6 +353,9 @@ impl SpaceRuntime {
             .fetch_optional(&mut *transaction)
             .await?
             .unwrap_or_else(|| serde_json::json!({}));
+        let content_rules = sqlx::query_file_scalar!("sql/spaces/get_content_rules.sql", space_id)
+            .fetch_optional(&mut *transaction)
+            .await?;
         let channels = sqlx::query_file_scalar!("sql/channels/get_by_space.sql", space_id)
             .fetch_all(&mut *transaction)
             .await?;
@@ -406,6 +417,10 @@ impl SpaceRuntime {
             })
             .collect();
 
+        let content_filter = content_rules
+            .map(|rules| ContentFilter::from_stored(space_id, rules))
+            .unwrap_or_default();
+
         let latest_activity = space.latest_activity;
         Ok(SpaceSnapshot {
             revision,
@@ -415,6 +430,7 @@ impl SpaceRuntime {
                 latest_activity.unix_timestamp_nanos() as i64 / 1_000,
             )),
             settings,
+            content_filter: Arc::new(content_filter),
             channels,
             characters,
             notes,
@@ -1071,6 +1087,7 @@ impl SpaceRuntime {
                             space_id = %runtime.space_id,
                             space_mismatch = mismatch.space,
                             settings_mismatch = mismatch.settings,
+                            content_rules_mismatch = mismatch.content_rules,
                             channels_mismatch = mismatch.channels,
                             characters_mismatch = mismatch.characters,
                             scopes_mismatch = mismatch.scopes,
@@ -1264,6 +1281,20 @@ struct SpaceStoreInner {
     load_count: std::sync::atomic::AtomicUsize,
 }
 
+/// Diagnostic view of a loaded runtime, exposed through the admin API.
+#[derive(Debug, Clone, serde::Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SpaceRuntimeStats {
+    pub(crate) space_id: Uuid,
+    pub(crate) revision: u64,
+    pub(crate) dirty: bool,
+    pub(crate) leases: u64,
+    pub(crate) idle_seconds: u64,
+    pub(crate) active_mutations: u64,
+    pub(crate) control_queue_depth: u64,
+    pub(crate) mutation_queue_depth: u64,
+}
+
 struct SpaceRuntimeHandle {
     runtime: tokio::sync::OnceCell<Arc<SpaceRuntime>>,
     state: std::sync::Mutex<SpaceRuntimeHandleState>,
@@ -1449,6 +1480,37 @@ impl SpaceStore {
         handle.runtime_if_active()
     }
 
+    pub(crate) fn runtime_stats(&self) -> Vec<SpaceRuntimeStats> {
+        let mut stats = Vec::new();
+        for (space_id, handle) in self.inner.runtimes.pin().iter() {
+            let (leases, idle_seconds) = {
+                let state = handle
+                    .state
+                    .lock()
+                    .expect("Space runtime handle state mutex poisoned");
+                if state.evicting {
+                    continue;
+                }
+                (state.leases, state.last_touched.elapsed().as_secs())
+            };
+            let Some(runtime) = handle.runtime.get() else {
+                continue;
+            };
+            stats.push(SpaceRuntimeStats {
+                space_id: *space_id,
+                revision: runtime.snapshot().revision,
+                dirty: runtime.dirty.load(Ordering::Acquire),
+                leases,
+                idle_seconds,
+                active_mutations: runtime.active_mutations.load(Ordering::Acquire),
+                control_queue_depth: runtime.control_queue_depth.load(Ordering::Acquire),
+                mutation_queue_depth: runtime.mutation_queue_depth.load(Ordering::Acquire),
+            });
+        }
+        stats.sort_by_key(|stats| stats.idle_seconds);
+        stats
+    }
+
     pub(crate) fn update_metrics(&self) {
         let mut loaded = 0_u64;
         let mut dirty = 0_u64;
@@ -1621,6 +1683,25 @@ impl SpaceStore {
             .map_err(Into::into)
     }
 
+    pub(crate) async fn resolve_content_filter(
+        &self,
+        space_id: Uuid,
+    ) -> Result<Arc<ContentFilter>, SpaceRuntimeError> {
+        let runtime = self.get_or_load(space_id).await?;
+        if let Some(snapshot) = runtime.authoritative_snapshot_after_wait().await {
+            metrics::counter!("boluo_server_space_runtime_read_total", "result" => "hit")
+                .increment(1);
+            return Ok(snapshot.content_filter.clone());
+        }
+
+        metrics::counter!("boluo_server_space_runtime_read_total", "result" => "fallback")
+            .increment(1);
+        Space::get_content_rules(&self.inner.db, space_id)
+            .await
+            .map(Arc::new)
+            .map_err(Into::into)
+    }
+
     pub(crate) async fn resolve_note_metadata(
         &self,
         space_id: Uuid,
@@ -1691,6 +1772,25 @@ impl SpaceStore {
             .map_err(Into::into)
     }
 
+    pub(crate) async fn list_scopes(
+        &self,
+        space_id: Uuid,
+    ) -> Result<Vec<Scope>, SpaceRuntimeError> {
+        let runtime = self.get_or_load(space_id).await?;
+        if let Some(snapshot) = runtime.authoritative_snapshot_after_wait().await {
+            metrics::counter!("boluo_server_space_runtime_read_total", "result" => "hit")
+                .increment(1);
+            return Ok(snapshot.scopes.values().cloned().collect());
+        }
+
+        metrics::counter!("boluo_server_space_runtime_read_total", "result" => "fallback")
+            .increment(1);
+        let mut conn = self.inner.db.acquire().await?;
+        Scope::list_by_space(&mut conn, space_id)
+            .await
+            .map_err(Into::into)
+    }
+
     pub(crate) async fn list_entry_metadata(
         &self,
         space_id: Uuid,
@@ -1763,11 +1863,12 @@ impl SpaceStore {
                 .await
                 .map_err(Into::into);
         }
-        return Ok(Some(
-            current_entry
-                .clone()
-                .with_components(components.to_response()),
-        ));
+        let mut components = components.to_response();
+        if refers_to_other_entries(&components) {
+            let mut conn = self.inner.db.acquire().await?;
+            evaluate_formulas(&mut conn, scope_id, entry_id, &mut components).await?;
+        }
+        return Ok(Some(current_entry.clone().with_components(components)));
     }
 
     pub(crate) async fn resolve_channel_member(
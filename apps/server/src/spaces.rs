@@ -1,5 +1,7 @@
 mod access;
 pub mod api;
+mod clone;
+pub mod content_rules;
 pub mod handlers;
 pub mod models;
 
@@ -7,5 +9,6 @@ pub use access::{
     AccessPolicy, ResourceAccessContext, SpaceAccess, resolve_resource_access_context,
     resolve_space_access, validate_access_channel,
 };
+pub use content_rules::{ContentFilter, ContentRule, ContentRuleAction};
 pub use handlers::{router, start_rate_limiter_cleanup};
-pub use models::{Space, SpaceMember, UserSpaces};
+pub use models::{Space, SpaceMember, SpaceTemplate, UserSpaces};
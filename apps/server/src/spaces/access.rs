@@ -19,6 +19,31 @@ impl SpaceAccess {
     pub fn can_manage(self) -> bool {
         self.is_owner || self.is_admin
     }
+
+    /// The access to a resource that isn't restricted to a channel.
+    pub fn resource_context(self) -> ResourceAccessContext {
+        ResourceAccessContext {
+            can_view: self.can_access,
+            is_member: self.is_member,
+            is_game_master: self.is_game_master,
+            can_manage: self.can_manage(),
+        }
+    }
+
+    /// The access to a resource restricted to a channel, given the membership of the user in it.
+    pub fn channel_resource_context(
+        self,
+        channel_is_public: bool,
+        channel_member: Option<&ChannelMember>,
+    ) -> ResourceAccessContext {
+        let is_member = self.is_member && channel_member.is_some();
+        ResourceAccessContext {
+            can_view: self.can_manage() || is_member || (channel_is_public && self.can_access),
+            is_member,
+            is_game_master: is_member && channel_member.is_some_and(|member| member.is_master),
+            can_manage: self.can_manage(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -132,12 +157,7 @@ pub async fn resolve_resource_access_context(
 ) -> Result<ResourceAccessContext, AppError> {
     let space_access = resolve_space_access(ctx, space_id, user_id).await?;
     let Some(channel_id) = access_channel_id else {
-        return Ok(ResourceAccessContext {
-            can_view: space_access.can_access,
-            is_member: space_access.is_member,
-            is_game_master: space_access.is_game_master,
-            can_manage: space_access.can_manage(),
-        });
+        return Ok(space_access.resource_context());
     };
 
     let resolved = ctx
@@ -163,18 +183,7 @@ pub async fn resolve_resource_access_context(
         }
         None => None,
     };
-    let is_member = space_access.is_member && channel_member.is_some();
-    let can_view = space_access.can_manage()
-        || is_member
-        || (resolved.channel.is_public && space_access.can_access);
-    let is_game_master = is_member && channel_member.is_some_and(|member| member.is_master);
-
-    Ok(ResourceAccessContext {
-        can_view,
-        is_member,
-        is_game_master,
-        can_manage: space_access.can_manage(),
-    })
+    Ok(space_access.channel_resource_context(resolved.channel.is_public, channel_member.as_ref()))
 }
 
 pub async fn validate_access_channel(
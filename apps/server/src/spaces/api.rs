@@ -5,6 +5,7 @@ use uuid::Uuid;
 
 use crate::channels::ChannelType;
 use crate::events::StatusMap;
+use crate::spaces::ContentRule;
 
 #[derive(Deserialize, Debug, specta::Type)]
 #[serde(rename_all = "camelCase")]
@@ -81,3 +82,58 @@ pub struct SpaceWithMember {
     pub member: super::SpaceMember,
     pub user: crate::users::User,
 }
+
+/// Which parts of the source space are copied into the clone.
+#[derive(Deserialize, Debug, Default, Clone, Copy, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SpaceCloneParts {
+    #[serde(default)]
+    pub channels: bool,
+    /// Space settings and content rules.
+    #[serde(default)]
+    pub settings: bool,
+    #[serde(default)]
+    pub characters: bool,
+    /// Entries in the space scope. Character entries follow `characters`. The component types
+    /// of the space are copied with either.
+    #[serde(default)]
+    pub entries: bool,
+    #[serde(default)]
+    pub notes: bool,
+    /// Listed assets. Assets referenced by cloned entries or messages are always copied.
+    #[serde(default)]
+    pub assets: bool,
+    /// Message history. Requires `channels`.
+    #[serde(default)]
+    pub messages: bool,
+}
+
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CloneSpace {
+    /// The source space or template.
+    pub space_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub parts: SpaceCloneParts,
+}
+
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PublishSpaceTemplate {
+    pub space_id: Uuid,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub is_public: bool,
+}
+
+#[derive(Deserialize, Debug, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateContentRules {
+    pub space_id: Uuid,
+    /// Replaces all the rules of the space
+    pub rules: Vec<ContentRule>,
+}
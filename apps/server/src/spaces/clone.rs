@@ -0,0 +1,791 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use super::api::SpaceCloneParts;
+use super::{AccessPolicy, ContentFilter, ResourceAccessContext, Space, SpaceAccess, SpaceMember};
+use crate::assets::{Asset, AssetFolder};
+use crate::channels::{Channel, ChannelMember};
+use crate::characters::Character;
+use crate::committed_changes::CommittedChanges;
+use crate::entries::component_types::ComponentTypeVersion;
+use crate::entries::models::{
+    Entry, EntryComponent, EntryComponentHistory, EntryComponentPayloadInput, EntryEffect,
+    EntryHistory, EntryHistoryAction, EntryMetadata, components_as_set_history_changes,
+};
+use crate::error::{ModelError, ValidationFailed};
+use crate::notes::{Note, NoteMetadata};
+use crate::scopes::Scope;
+
+/// Everything created by [`clone_space`], used to publish the new space after commit.
+pub(crate) struct ClonedSpace {
+    pub space: Space,
+    pub member: SpaceMember,
+    pub settings: Option<serde_json::Value>,
+    pub content_filter: Option<Arc<ContentFilter>>,
+    pub channels: Vec<Channel>,
+    pub channel_members: Vec<ChannelMember>,
+    pub characters: Vec<Character>,
+    pub notes: Vec<NoteMetadata>,
+    pub entries: Vec<EntryMetadata>,
+}
+
+impl ClonedSpace {
+    pub(crate) fn committed_changes(&self) -> CommittedChanges {
+        let mut changes = CommittedChanges::default();
+        changes.space_created(&self.space);
+        changes.space_member_added(&self.member);
+        if let Some(settings) = &self.settings {
+            changes.space_settings_updated(self.space.id, settings.clone());
+        }
+        if let Some(content_filter) = &self.content_filter {
+            changes.space_content_rules_updated(self.space.id, content_filter.clone());
+        }
+        for channel in &self.channels {
+            changes.channel_created(channel);
+        }
+        for member in &self.channel_members {
+            changes.channel_member_added(self.space.id, member);
+        }
+        for character in &self.characters {
+            changes.character_updated(character);
+        }
+        for note in &self.notes {
+            changes.note_updated(note);
+        }
+        for entry in &self.entries {
+            changes.entry_updated(self.space.id, entry);
+        }
+        changes
+    }
+}
+
+/// Old id to new id for every kind of row that other rows refer to.
+#[derive(Default)]
+struct IdMap {
+    channels: HashMap<Uuid, Uuid>,
+    characters: HashMap<Uuid, Uuid>,
+    scopes: HashMap<Uuid, Uuid>,
+    notes: HashMap<Uuid, Uuid>,
+    assets: HashMap<Uuid, Uuid>,
+    asset_folders: HashMap<Uuid, Uuid>,
+}
+
+impl IdMap {
+    fn access_channel(&self, channel_id: Option<Uuid>) -> Result<Option<Uuid>, ValidationFailed> {
+        let Some(channel_id) = channel_id else {
+            return Ok(None);
+        };
+        self.channels
+            .get(&channel_id)
+            .copied()
+            .map(Some)
+            .ok_or(ValidationFailed(
+                "Resources restricted to a channel can only be cloned together with channels.",
+            ))
+    }
+
+    fn pairs(map: &HashMap<Uuid, Uuid>) -> (Vec<Uuid>, Vec<Uuid>) {
+        map.iter()
+            .map(|(old_id, new_id)| (*old_id, *new_id))
+            .unzip()
+    }
+}
+
+/// What the operator can view in the source space, by access channel.
+struct SourceAccess {
+    space_id: Uuid,
+    operator_id: Uuid,
+    space: SpaceAccess,
+    contexts: HashMap<Option<Uuid>, ResourceAccessContext>,
+}
+
+impl SourceAccess {
+    async fn can_view(
+        &mut self,
+        db: &mut sqlx::PgConnection,
+        access_policy: AccessPolicy,
+        owner_id: Option<Uuid>,
+        access_channel_id: Option<Uuid>,
+    ) -> Result<bool, ModelError> {
+        let context = match self.contexts.get(&access_channel_id) {
+            Some(context) => *context,
+            None => {
+                let context = match access_channel_id {
+                    None => self.space.resource_context(),
+                    Some(channel_id) => {
+                        let channel_is_public = Channel::get_by_id(&mut *db, &channel_id)
+                            .await?
+                            .is_some_and(|channel| {
+                                channel.space_id == self.space_id && channel.is_public
+                            });
+                        let channel_member = if self.space.is_member {
+                            ChannelMember::get(db, self.operator_id, self.space_id, channel_id)
+                                .await?
+                        } else {
+                            None
+                        };
+                        self.space
+                            .channel_resource_context(channel_is_public, channel_member.as_ref())
+                    }
+                };
+                self.contexts.insert(access_channel_id, context);
+                context
+            }
+        };
+        Ok(access_policy.can_view(owner_id, Some(self.operator_id), context))
+    }
+}
+
+fn validate_parts(parts: &SpaceCloneParts) -> Result<(), ValidationFailed> {
+    if parts.messages && !parts.channels {
+        return Err(ValidationFailed(
+            "Message history can only be cloned together with channels.",
+        ));
+    }
+    Ok(())
+}
+
+/// Copy the selected parts of `source` into a new space owned by `operator_id`.
+///
+/// Everything runs inside `db`, so a failure leaves nothing behind. Every copied row gets a
+/// fresh id and references between rows (access channels, reference notes, asset components,
+/// message characters and portraits) are rewritten to point into the new space. Only the
+/// notes, characters and entry scopes the operator can view with `access` are copied, and they
+/// are handed to the operator. Messages keep their senders, but whispers are only copied if the
+/// operator sent or received them. Archived characters and notes are left out.
+pub(crate) async fn clone_space(
+    db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    source: &Space,
+    operator_id: Uuid,
+    access: SpaceAccess,
+    name: String,
+    description: String,
+    parts: SpaceCloneParts,
+) -> Result<ClonedSpace, ModelError> {
+    validate_parts(&parts)?;
+    let space = Space::create(
+        &mut **db,
+        name,
+        &operator_id,
+        description,
+        None,
+        Some(source.default_dice_type.as_str()),
+    )
+    .await?;
+    let member = SpaceMember::add_admin(&mut **db, &operator_id, &space.id).await?;
+    let mut ids = IdMap::default();
+    let mut source_access = SourceAccess {
+        space_id: source.id,
+        operator_id,
+        space: access,
+        contexts: HashMap::new(),
+    };
+    let mut cloned = ClonedSpace {
+        space,
+        member,
+        settings: None,
+        content_filter: None,
+        channels: Vec::new(),
+        channel_members: Vec::new(),
+        characters: Vec::new(),
+        notes: Vec::new(),
+        entries: Vec::new(),
+    };
+    let space_id = cloned.space.id;
+
+    if parts.channels {
+        for channel in Channel::get_by_space(&mut **db, &source.id).await? {
+            let created = Channel::create(
+                &mut **db,
+                &space_id,
+                &channel.name,
+                channel.is_public,
+                Some(channel.default_dice_type.as_str()),
+                channel.r#type,
+            )
+            .await?;
+            let created = Channel::edit(
+                &mut **db,
+                &created.id,
+                None,
+                Some(&channel.topic),
+                None,
+                Some(channel.default_roll_command.as_str()),
+                None,
+                Some(channel.is_document),
+                None,
+                Some(channel.is_archived),
+            )
+            .await?;
+            let created = Channel::set_posting_rules(
+                &mut **db,
+                &created.id,
+                Some(channel.slow_mode_seconds),
+                Some(channel.posting_policy),
+                Some(channel.max_message_length),
+            )
+            .await?;
+            let member =
+                ChannelMember::add_user(&mut **db, operator_id, created.id, "", true).await?;
+            ids.channels.insert(channel.id, created.id);
+            cloned.channels.push(created);
+            cloned.channel_members.push(member);
+        }
+    }
+
+    if parts.settings {
+        let settings = Space::get_settings(&mut **db, source.id).await?;
+        Space::put_settings(&mut **db, space_id, &settings).await?;
+        cloned.settings = Some(settings);
+        let content_filter = Space::get_content_rules(&mut **db, source.id).await?;
+        Space::put_content_rules(&mut **db, space_id, content_filter.rules()).await?;
+        cloned.content_filter = Some(Arc::new(content_filter));
+    }
+
+    if parts.assets {
+        for folder in AssetFolder::list_by_space(&mut **db, source.id).await? {
+            let parent_id = folder
+                .parent_id
+                .and_then(|parent_id| ids.asset_folders.get(&parent_id).copied());
+            let created =
+                AssetFolder::create(db, space_id, parent_id, operator_id, &folder.name).await?;
+            ids.asset_folders.insert(folder.id, created.id);
+        }
+        for asset in Asset::list_by_space(&mut **db, source.id).await? {
+            clone_asset(db, &mut ids, source.id, space_id, operator_id, asset.id).await?;
+        }
+    }
+
+    if parts.notes {
+        for metadata in NoteMetadata::list_by_space(&mut **db, source.id, false).await? {
+            if !source_access
+                .can_view(
+                    &mut **db,
+                    metadata.access_policy,
+                    metadata.creator_id,
+                    metadata.access_channel_id,
+                )
+                .await?
+            {
+                continue;
+            }
+            let Some(note) = Note::get_by_id(&mut **db, source.id, metadata.id).await? else {
+                continue;
+            };
+            let created = Note::create(
+                db,
+                space_id,
+                note.metadata.title.to_string(),
+                note.metadata
+                    .keywords
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect(),
+                note.metadata.tags.iter().map(ToString::to_string).collect(),
+                operator_id,
+                note.text,
+                note.entities,
+                note.metadata.access_policy,
+                ids.access_channel(note.metadata.access_channel_id)?,
+            )
+            .await?;
+            ids.notes.insert(metadata.id, created.id);
+            cloned.notes.push(created.metadata);
+        }
+    }
+
+    if parts.entries {
+        ids.scopes.insert(source.scope_id, cloned.space.scope_id);
+    }
+    if parts.characters {
+        let mut characters = Character::list_by_space(&mut **db, &source.id).await?;
+        characters.retain(|character| character.archived_at.is_none());
+        characters.sort_by_key(|character| character.created);
+        for character in characters {
+            if !source_access
+                .can_view(
+                    &mut **db,
+                    character.access_policy,
+                    character.owner_id,
+                    character.access_channel_id,
+                )
+                .await?
+            {
+                continue;
+            }
+            let created = Character::create(
+                db,
+                space_id,
+                operator_id,
+                &character.name,
+                &character.key,
+                character.aliases.iter().map(ToString::to_string).collect(),
+                &character.description,
+                &character.color,
+                character.access_policy,
+                ids.access_channel(character.access_channel_id)?,
+                character.tags.iter().map(ToString::to_string).collect(),
+            )
+            .await?;
+            ids.characters.insert(character.id, created.id);
+            ids.scopes.insert(character.scope_id, created.scope_id);
+            cloned.characters.push(created);
+        }
+    }
+
+    if parts.entries || parts.characters {
+        ComponentTypeVersion::copy_to_space(db, source.id, space_id).await?;
+    }
+    let mut scopes: Vec<_> = ids.scopes.iter().map(|(old, new)| (*old, *new)).collect();
+    scopes.sort();
+    for (source_scope_id, scope_id) in scopes {
+        let Some(scope) = Scope::get_by_id(&mut **db, source_scope_id).await? else {
+            continue;
+        };
+        if !source_access
+            .can_view(
+                &mut **db,
+                scope.access_policy,
+                scope.owner_id,
+                scope.access_channel_id,
+            )
+            .await?
+        {
+            continue;
+        }
+        let entries = clone_scope_entries(
+            db,
+            &mut ids,
+            source.id,
+            space_id,
+            operator_id,
+            source_scope_id,
+            scope_id,
+        )
+        .await?;
+        cloned.entries.extend(entries);
+    }
+
+    if parts.messages {
+        let source_channel_ids: Vec<Uuid> = ids.channels.keys().copied().collect();
+        let portrait_ids =
+            sqlx::query_file_scalar!("sql/spaces/list_message_portraits.sql", &source_channel_ids)
+                .fetch_all(&mut **db)
+                .await?;
+        for portrait_id in portrait_ids {
+            clone_asset(db, &mut ids, source.id, space_id, operator_id, portrait_id).await?;
+        }
+        let (old_channels, new_channels) = IdMap::pairs(&ids.channels);
+        let (old_characters, new_characters) = IdMap::pairs(&ids.characters);
+        let (old_assets, new_assets) = IdMap::pairs(&ids.assets);
+        sqlx::query_file!(
+            "sql/spaces/clone_messages.sql",
+            &old_channels,
+            &new_channels,
+            &old_characters,
+            &new_characters,
+            &old_assets,
+            &new_assets,
+            operator_id,
+        )
+        .execute(&mut **db)
+        .await?;
+    }
+
+    Ok(cloned)
+}
+
+async fn clone_asset(
+    db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ids: &mut IdMap,
+    source_space_id: Uuid,
+    space_id: Uuid,
+    operator_id: Uuid,
+    asset_id: Uuid,
+) -> Result<Option<Uuid>, ModelError> {
+    if let Some(cloned_id) = ids.assets.get(&asset_id) {
+        return Ok(Some(*cloned_id));
+    }
+    let Some(asset) = Asset::get_by_id_in_space(&mut **db, source_space_id, asset_id).await? else {
+        return Ok(None);
+    };
+    let folder_id = asset
+        .folder_id
+        .and_then(|folder_id| ids.asset_folders.get(&folder_id).copied());
+    let created = Asset::create_in_folder(
+        db,
+        space_id,
+        asset.media_id,
+        folder_id,
+        operator_id,
+        &asset.name,
+        asset.tags,
+        asset.policy,
+    )
+    .await?;
+    ids.assets.insert(asset.id, created.id);
+    Ok(Some(created.id))
+}
+
+async fn clone_scope_entries(
+    db: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ids: &mut IdMap,
+    source_space_id: Uuid,
+    space_id: Uuid,
+    operator_id: Uuid,
+    source_scope_id: Uuid,
+    scope_id: Uuid,
+) -> Result<Vec<EntryMetadata>, ModelError> {
+    let source_entries = EntryMetadata::list_by_scope(&mut **db, source_scope_id).await?;
+    if source_entries.is_empty() {
+        return Ok(Vec::new());
+    }
+    let effect = EntryEffect::create(db, space_id, scope_id, operator_id).await?;
+    let mut cloned = Vec::with_capacity(source_entries.len());
+    for metadata in source_entries {
+        let Some(entry) = Entry::get_by_id_in_transaction(db, source_scope_id, metadata.id).await?
+        else {
+            continue;
+        };
+        let mut components = BTreeMap::new();
+        for (component_type, component) in entry.components {
+            let payload = match component {
+                EntryComponent::Json {
+                    data,
+                    schema_version,
+                    ..
+                } => EntryComponentPayloadInput::json_with_schema(data, Some(schema_version)),
+                EntryComponent::Asset { asset_id, .. } => {
+                    let Some(asset_id) =
+                        clone_asset(db, ids, source_space_id, space_id, operator_id, asset_id)
+                            .await?
+                    else {
+                        continue;
+                    };
+                    EntryComponentPayloadInput::Asset { asset_id }
+                }
+                EntryComponent::Formula { formula, .. } => {
+                    EntryComponentPayloadInput::Formula { formula }
+                }
+            };
+            components.insert(component_type.to_string(), payload);
+        }
+        let created = Entry::create(
+            db,
+            scope_id,
+            entry.metadata.key.to_string(),
+            entry
+                .metadata
+                .aliases
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            entry.metadata.display_name.to_string(),
+            entry
+                .metadata
+                .reference_note_id
+                .and_then(|note_id| ids.notes.get(&note_id).copied()),
+            components,
+            entry
+                .metadata
+                .tags
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            None,
+        )
+        .await?;
+        EntryHistory::record(
+            db,
+            effect.id,
+            created.id,
+            &created.key,
+            EntryHistoryAction::Create,
+        )
+        .await?;
+        EntryComponentHistory::record(
+            db,
+            effect.id,
+            created.id,
+            &created.key,
+            &components_as_set_history_changes(&created.components),
+        )
+        .await?;
+        cloned.push(created.metadata);
+    }
+    Ok(cloned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channels::ChannelType;
+    use crate::spaces::AccessPolicy;
+    use crate::users::User;
+    use shared_types::entities::Entities;
+
+    async fn create_test_user(pool: &sqlx::PgPool) -> User {
+        let raw = Uuid::new_v4().simple().to_string();
+        User::register(
+            pool,
+            &format!("clone_{raw}@example.com"),
+            &format!("clone_{}", &raw[..8]),
+            "Clone Tester",
+            "ClonePass123!",
+        )
+        .await
+        .expect("failed to create test user")
+    }
+
+    /// The access of a user who isn't a member to a public template.
+    fn template_access() -> SpaceAccess {
+        SpaceAccess {
+            can_access: true,
+            is_member: false,
+            is_admin: false,
+            is_game_master: false,
+            is_owner: false,
+        }
+    }
+
+    #[test]
+    fn messages_require_channels() {
+        let parts = SpaceCloneParts {
+            messages: true,
+            ..SpaceCloneParts::default()
+        };
+        assert!(validate_parts(&parts).is_err());
+        let parts = SpaceCloneParts {
+            channels: true,
+            messages: true,
+            ..SpaceCloneParts::default()
+        };
+        assert!(validate_parts(&parts).is_ok());
+    }
+
+    #[test]
+    fn access_channels_are_remapped_or_rejected() {
+        let mut ids = IdMap::default();
+        let old_id = Uuid::now_v7();
+        let new_id = Uuid::now_v7();
+        assert_eq!(ids.access_channel(None), Ok(None));
+        assert!(ids.access_channel(Some(old_id)).is_err());
+        ids.channels.insert(old_id, new_id);
+        assert_eq!(ids.access_channel(Some(old_id)), Ok(Some(new_id)));
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn db_test_clone_space_remaps_references(pool: sqlx::PgPool) {
+        let owner = create_test_user(&pool).await;
+        let operator = create_test_user(&pool).await;
+        let mut tx = pool.begin().await.expect("begin failed");
+        let source = Space::create(
+            &mut *tx,
+            "Clone Source".to_string(),
+            &owner.id,
+            "source".to_string(),
+            None,
+            Some("d20"),
+        )
+        .await
+        .expect("failed to create source space");
+        let channel = Channel::create(
+            &mut *tx,
+            &source.id,
+            "Tavern",
+            true,
+            None,
+            ChannelType::InGame,
+        )
+        .await
+        .expect("failed to create channel");
+        let note = Note::create(
+            &mut tx,
+            source.id,
+            "Lore".to_string(),
+            Vec::new(),
+            Vec::new(),
+            owner.id,
+            "Once upon a time".to_string(),
+            Entities::default(),
+            AccessPolicy::Public,
+            Some(channel.id),
+        )
+        .await
+        .expect("failed to create note");
+        let character = Character::create(
+            &mut tx,
+            source.id,
+            owner.id,
+            "Homura",
+            "homura",
+            Vec::new(),
+            "",
+            "",
+            AccessPolicy::Public,
+            Some(channel.id),
+            Vec::new(),
+        )
+        .await
+        .expect("failed to create character");
+        let entry = Entry::create(
+            &mut tx,
+            character.scope_id,
+            "hp".to_string(),
+            Vec::new(),
+            "HP".to_string(),
+            Some(note.id),
+            BTreeMap::from([(
+                "test/value".to_string(),
+                EntryComponentPayloadInput::json(serde_json::json!(10)),
+            )]),
+            Vec::new(),
+            None,
+        )
+        .await
+        .expect("failed to create entry");
+
+        let restricted = clone_space(
+            &mut tx,
+            &source,
+            operator.id,
+            template_access(),
+            "No Channels".to_string(),
+            String::new(),
+            SpaceCloneParts {
+                notes: true,
+                ..SpaceCloneParts::default()
+            },
+        )
+        .await;
+        assert!(matches!(restricted, Err(ModelError::Validation(_))));
+
+        let cloned = clone_space(
+            &mut tx,
+            &source,
+            operator.id,
+            template_access(),
+            "Clone Target".to_string(),
+            String::new(),
+            SpaceCloneParts {
+                channels: true,
+                characters: true,
+                notes: true,
+                ..SpaceCloneParts::default()
+            },
+        )
+        .await
+        .expect("failed to clone space");
+        assert_ne!(cloned.space.id, source.id);
+        assert_eq!(cloned.space.owner_id, operator.id);
+        assert!(cloned.member.is_admin);
+        let [cloned_channel] = cloned.channels.as_slice() else {
+            panic!("expected one cloned channel");
+        };
+        assert_ne!(cloned_channel.id, channel.id);
+        assert_eq!(cloned_channel.name, channel.name);
+        let [cloned_note] = cloned.notes.as_slice() else {
+            panic!("expected one cloned note");
+        };
+        assert_eq!(cloned_note.access_channel_id, Some(cloned_channel.id));
+        assert_eq!(cloned_note.creator_id, Some(operator.id));
+        let [cloned_character] = cloned.characters.as_slice() else {
+            panic!("expected one cloned character");
+        };
+        assert_ne!(cloned_character.id, character.id);
+        assert_eq!(cloned_character.key, character.key);
+        assert_eq!(cloned_character.access_channel_id, Some(cloned_channel.id));
+        assert_eq!(cloned_character.owner_id, Some(operator.id));
+        let [cloned_entry] = cloned.entries.as_slice() else {
+            panic!("expected one cloned entry");
+        };
+        assert_ne!(cloned_entry.id, entry.id);
+        assert_eq!(cloned_entry.scope_id, cloned_character.scope_id);
+        assert_eq!(cloned_entry.reference_note_id, Some(cloned_note.id));
+        let cloned_entry =
+            Entry::get_by_id_in_transaction(&mut tx, cloned_character.scope_id, cloned_entry.id)
+                .await
+                .expect("failed to load cloned entry")
+                .expect("cloned entry missing");
+        assert_eq!(
+            cloned_entry.components["test/value"].json_data(),
+            serde_json::json!(10)
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn db_test_clone_space_skips_what_the_operator_cannot_view(pool: sqlx::PgPool) {
+        let owner = create_test_user(&pool).await;
+        let player = create_test_user(&pool).await;
+        let mut tx = pool.begin().await.expect("begin failed");
+        let source = Space::create(
+            &mut *tx,
+            "Clone Source".to_string(),
+            &owner.id,
+            "source".to_string(),
+            None,
+            Some("d20"),
+        )
+        .await
+        .expect("failed to create source space");
+        SpaceMember::add_admin(&mut *tx, &owner.id, &source.id)
+            .await
+            .expect("failed to add owner");
+        SpaceMember::add_user(&mut *tx, &player.id, &source.id)
+            .await
+            .expect("failed to add player");
+        let notes = [
+            ("Diary", player.id, AccessPolicy::Personal),
+            ("Plans", owner.id, AccessPolicy::Personal),
+            ("Map", player.id, AccessPolicy::Public),
+            ("Twist", player.id, AccessPolicy::GameMaster),
+        ];
+        for (title, creator_id, access_policy) in notes {
+            Note::create(
+                &mut tx,
+                source.id,
+                title.to_string(),
+                Vec::new(),
+                Vec::new(),
+                creator_id,
+                String::new(),
+                Entities::default(),
+                access_policy,
+                None,
+            )
+            .await
+            .expect("failed to create note");
+        }
+
+        let access = SpaceAccess {
+            can_access: true,
+            is_member: true,
+            is_admin: true,
+            is_game_master: false,
+            is_owner: true,
+        };
+        let cloned = clone_space(
+            &mut tx,
+            &source,
+            owner.id,
+            access,
+            "Clone Target".to_string(),
+            String::new(),
+            SpaceCloneParts {
+                notes: true,
+                ..SpaceCloneParts::default()
+            },
+        )
+        .await
+        .expect("failed to clone space");
+        let mut titles: Vec<String> = cloned
+            .notes
+            .iter()
+            .map(|note| note.title.to_string())
+            .collect();
+        titles.sort();
+        assert_eq!(titles, vec!["Map".to_string(), "Plans".to_string()]);
+    }
+}
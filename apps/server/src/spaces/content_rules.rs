@@ -0,0 +1,264 @@
+use std::borrow::Cow;
+use std::ops::Range;
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::error::ValidationFailed;
+
+pub const MAX_CONTENT_RULES: usize = 200;
+const MAX_PATTERN_LENGTH: usize = 256;
+/// Keeps a single rule from compiling into a huge automaton.
+const REGEX_SIZE_LIMIT: usize = 256 * 1024;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, specta::Type)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ContentRuleAction {
+    Reject,
+    Mask,
+    Flag,
+}
+
+/// A blocked word or regex of a space.
+///
+/// Words match anywhere in the text, ignoring case, since there are no spaces between words in
+/// CJK text. Use a regex with `\b` to match whole words only.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentRule {
+    pub pattern: String,
+    #[serde(default)]
+    pub is_regex: bool,
+    pub action: ContentRuleAction,
+}
+
+impl ContentRule {
+    fn compile(&self) -> Result<Regex, ValidationFailed> {
+        if self.pattern.trim().is_empty() {
+            return Err(ValidationFailed("A content rule can not be empty."));
+        }
+        if self.pattern.len() > MAX_PATTERN_LENGTH {
+            return Err(ValidationFailed(
+                "A content rule shall not be more than 256 bytes.",
+            ));
+        }
+        let pattern = if self.is_regex {
+            Cow::Borrowed(self.pattern.as_str())
+        } else {
+            Cow::Owned(regex::escape(&self.pattern))
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!self.is_regex)
+            .size_limit(REGEX_SIZE_LIMIT)
+            .build()
+            .map_err(|_| ValidationFailed("A content rule is not a valid regex."))
+    }
+}
+
+/// The result of a text that passed the filter.
+#[derive(Debug, PartialEq)]
+pub struct Filtered<'a> {
+    pub text: Cow<'a, str>,
+    /// The first `Flag` rule the text matched
+    pub flagged_by: Option<&'a ContentRule>,
+}
+
+/// The compiled rules of a space. The space snapshot compiles it once per change of the rules.
+#[derive(Debug, Default)]
+pub struct ContentFilter {
+    rules: Vec<ContentRule>,
+    compiled: Vec<Regex>,
+}
+
+impl PartialEq for ContentFilter {
+    fn eq(&self, other: &Self) -> bool {
+        self.rules == other.rules
+    }
+}
+
+impl ContentFilter {
+    pub fn compile(rules: Vec<ContentRule>) -> Result<ContentFilter, ValidationFailed> {
+        if rules.len() > MAX_CONTENT_RULES {
+            return Err(ValidationFailed(
+                "A space shall not have more than 200 content rules.",
+            ));
+        }
+        let compiled = rules
+            .iter()
+            .map(ContentRule::compile)
+            .collect::<Result<_, _>>()?;
+        Ok(ContentFilter { rules, compiled })
+    }
+
+    /// Load stored rules, which have been validated when they were saved.
+    pub fn from_stored(space_id: uuid::Uuid, rules: serde_json::Value) -> ContentFilter {
+        let compiled = serde_json::from_value(rules)
+            .map_err(|_| ValidationFailed("The stored content rules are malformed."))
+            .and_then(ContentFilter::compile);
+        compiled.unwrap_or_else(|err| {
+            tracing::warn!(%space_id, error = %err, "Failed to load content rules of a space");
+            ContentFilter::default()
+        })
+    }
+
+    pub fn rules(&self) -> &[ContentRule] {
+        &self.rules
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    fn matches<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> impl Iterator<Item = (&'a ContentRule, Range<usize>)> + 'a {
+        self.rules
+            .iter()
+            .zip(&self.compiled)
+            .flat_map(move |(rule, regex)| {
+                regex
+                    .find_iter(text)
+                    .filter(|found| !found.is_empty())
+                    .map(move |found| (rule, found.range()))
+            })
+    }
+
+    /// Reject the text, or mask it and report whether it should be flagged for review.
+    pub fn apply<'a>(&'a self, text: &'a str) -> Result<Filtered<'a>, ValidationFailed> {
+        if self.is_empty() {
+            return Ok(Filtered {
+                text: Cow::Borrowed(text),
+                flagged_by: None,
+            });
+        }
+        let mut masked = Vec::new();
+        let mut flagged_by = None;
+        for (rule, range) in self.matches(text) {
+            match rule.action {
+                ContentRuleAction::Reject => {
+                    return Err(ValidationFailed("The text contains blocked content."));
+                }
+                ContentRuleAction::Mask => masked.push(range),
+                ContentRuleAction::Flag => {
+                    flagged_by.get_or_insert(rule);
+                }
+            }
+        }
+        Ok(Filtered {
+            text: mask(text, masked),
+            flagged_by,
+        })
+    }
+
+    /// Like `apply`, for names and topics, which can not be queued for review.
+    pub fn check<'a>(&'a self, text: &'a str) -> Result<Cow<'a, str>, ValidationFailed> {
+        self.apply(text).map(|filtered| filtered.text)
+    }
+
+    /// Mask everything the rules would reject or mask.
+    ///
+    /// For drafts such as previews, which should not fail on every keystroke.
+    pub fn mask<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if self.is_empty() {
+            return Cow::Borrowed(text);
+        }
+        let ranges = self
+            .matches(text)
+            .filter(|(rule, _)| rule.action != ContentRuleAction::Flag)
+            .map(|(_, range)| range)
+            .collect();
+        mask(text, ranges)
+    }
+}
+
+/// Replace the characters in the ranges with `*`.
+///
+/// The UTF-16 length of the text is kept, so entities of the text are still valid.
+fn mask(text: &str, mut ranges: Vec<Range<usize>>) -> Cow<'_, str> {
+    if ranges.is_empty() {
+        return Cow::Borrowed(text);
+    }
+    ranges.sort_unstable_by_key(|range| range.start);
+    let mut masked = String::with_capacity(text.len());
+    let mut end = 0;
+    for range in ranges {
+        if range.end <= end {
+            continue;
+        }
+        let start = range.start.max(end);
+        masked.push_str(&text[end..start]);
+        for c in text[start..range.end].chars() {
+            for _ in 0..c.len_utf16() {
+                masked.push('*');
+            }
+        }
+        end = range.end;
+    }
+    masked.push_str(&text[end..]);
+    Cow::Owned(masked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str, is_regex: bool, action: ContentRuleAction) -> ContentRule {
+        ContentRule {
+            pattern: pattern.to_string(),
+            is_regex,
+            action,
+        }
+    }
+
+    #[test]
+    fn words_are_masked_ignoring_case() {
+        let filter = ContentFilter::compile(vec![
+            rule("darn", false, ContentRuleAction::Mask),
+            rule("坏词", false, ContentRuleAction::Mask),
+        ])
+        .unwrap();
+        let filtered = filter.apply("Darn, 这是坏词吧").unwrap();
+        assert_eq!(filtered.text, "****, 这是**吧");
+        assert_eq!(filtered.flagged_by, None);
+        assert_eq!(filter.apply("fine").unwrap().text, Cow::Borrowed("fine"));
+    }
+
+    #[test]
+    fn masking_keeps_utf16_length() {
+        let filter = ContentFilter::compile(vec![
+            rule("a😀", false, ContentRuleAction::Mask),
+            rule("😀b", false, ContentRuleAction::Mask),
+        ])
+        .unwrap();
+        let text = "xa😀by";
+        let masked = filter.apply(text).unwrap().text;
+        assert_eq!(masked, "x****y");
+        assert_eq!(masked.encode_utf16().count(), text.encode_utf16().count());
+    }
+
+    #[test]
+    fn reject_and_flag_rules() {
+        let filter = ContentFilter::compile(vec![
+            rule(r"\bspam+\b", true, ContentRuleAction::Reject),
+            rule("link", false, ContentRuleAction::Flag),
+        ])
+        .unwrap();
+        assert!(filter.apply("buy spammm now").is_err());
+        assert!(filter.apply("spamming is a word").is_ok());
+        let flagged = filter.apply("a LINK here").unwrap();
+        assert_eq!(flagged.text, "a LINK here");
+        assert_eq!(flagged.flagged_by, Some(&filter.rules()[1]));
+        assert_eq!(filter.mask("spam and link"), "**** and link");
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        assert!(ContentFilter::compile(vec![rule("(", true, ContentRuleAction::Reject)]).is_err());
+        assert!(ContentFilter::compile(vec![rule(" ", false, ContentRuleAction::Mask)]).is_err());
+        let too_many = vec![rule("a", false, ContentRuleAction::Flag); MAX_CONTENT_RULES + 1];
+        assert!(ContentFilter::compile(too_many).is_err());
+        let stored = serde_json::json!([{ "pattern": "(", "isRegex": true, "action": "MASK" }]);
+        assert!(ContentFilter::from_stored(uuid::Uuid::nil(), stored).is_empty());
+    }
+}
@@ -1,7 +1,13 @@
 use std::collections::HashMap;
 
-use super::api::{CreateSpace, EditSpace, QuerySpace, SpaceWithRelated};
-use super::{Space, SpaceMember};
+use super::api::{
+    CloneSpace, CreateSpace, EditSpace, PublishSpaceTemplate, QuerySpace, SpaceWithRelated,
+    UpdateContentRules,
+};
+use super::{
+    ContentFilter, ContentRule, Space, SpaceAccess, SpaceMember, SpaceTemplate,
+    resolve_space_access,
+};
 use crate::channels::models::Member;
 use crate::channels::{Channel, ChannelMember, ChannelType};
 use crate::committed_changes::CommittedChanges;
@@ -19,7 +25,7 @@ use arc_swap::ArcSwap;
 use governor::{DefaultKeyedRateLimiter, RateLimiter};
 use hyper::Request;
 use hyper::body::Body;
-use std::sync::LazyLock;
+use std::sync::{Arc, LazyLock};
 use uuid::Uuid;
 
 static CREATE_SPACE_LIMITER: LazyLock<DefaultKeyedRateLimiter<Uuid>> = LazyLock::new(|| {
@@ -391,6 +397,139 @@ async fn create(
     create_transactional(ctx, session.user_id, payload, std::future::ready(())).await
 }
 
+async fn clone(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<SpaceWithMember, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    CREATE_SPACE_LIMITER
+        .check_key(&session.user_id)
+        .map_err(|_| AppError::LimitExceeded("Too many spaces, please try again later."))?;
+    let CloneSpace {
+        space_id,
+        name,
+        description,
+        parts,
+    } = interface::parse_body(req).await?;
+    let access = resolve_space_access(ctx, space_id, Some(session.user_id)).await?;
+
+    let mut trans = ctx.db.begin().await?;
+    // Read the source from a single snapshot so the copy is self-consistent.
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ")
+        .execute(&mut *trans)
+        .await?;
+    let source = Space::get_by_id(&mut *trans, &space_id)
+        .await?
+        .or_not_found()?;
+    if !access.can_manage() {
+        let is_public_template = SpaceTemplate::get(&mut *trans, space_id)
+            .await?
+            .is_some_and(|template| template.is_public);
+        if !is_public_template {
+            tracing::warn!(
+                space_id = %space_id,
+                user_id = %session.user_id,
+                "A non-admin tries to clone space"
+            );
+            return Err(AppError::NoPermission(
+                "Only admins can clone this space".to_string(),
+            ));
+        }
+        if parts.messages {
+            return Err(AppError::NoPermission(
+                "Only admins can clone the message history".to_string(),
+            ));
+        }
+    }
+    let user = User::get_by_id(&mut *trans, &session.user_id)
+        .await?
+        .ok_or(AppError::NotFound("user"))?;
+    let description = description.unwrap_or_else(|| source.description.clone());
+    // Whoever may clone the space may read what isn't restricted any further.
+    let access = SpaceAccess {
+        can_access: true,
+        ..access
+    };
+    let cloned = super::clone::clone_space(
+        &mut trans,
+        &source,
+        user.id,
+        access,
+        name,
+        description,
+        parts,
+    )
+    .await?;
+    trans.commit().await?;
+    cloned.committed_changes().apply_with_context(ctx).await;
+    tracing::info!(
+        space_id = %cloned.space.id,
+        source_space_id = %source.id,
+        creator = %user.id,
+        "A space ({}) was cloned",
+        cloned.space.name
+    );
+    Ok(SpaceWithMember {
+        space: cloned.space,
+        member: cloned.member,
+        user,
+    })
+}
+
+async fn templates(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Vec<SpaceTemplate>, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    SpaceTemplate::list_visible(&ctx.db, session.user_id)
+        .await
+        .map_err(Into::into)
+}
+
+async fn publish_template(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<SpaceTemplate, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let PublishSpaceTemplate {
+        space_id,
+        name,
+        description,
+        is_public,
+    } = interface::parse_body(req).await?;
+    let mut trans = ctx.db.begin().await?;
+    let space = Space::get_by_id(&mut *trans, &space_id)
+        .await?
+        .or_not_found()?;
+    if space.owner_id != session.user_id {
+        return Err(AppError::NoPermission(
+            "Only the owner can publish a space as a template".to_string(),
+        ));
+    }
+    let template =
+        SpaceTemplate::put(&mut *trans, space_id, &name, &description, is_public).await?;
+    trans.commit().await?;
+    Ok(template)
+}
+
+async fn unpublish_template(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<bool, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let IdQuery { id } = parse_query(req.uri())?;
+    let mut trans = ctx.db.begin().await?;
+    let space = Space::get_by_id(&mut *trans, &id).await?.or_not_found()?;
+    if space.owner_id != session.user_id {
+        return Err(AppError::NoPermission(
+            "Only the owner can unpublish a template".to_string(),
+        ));
+    }
+    let removed = SpaceTemplate::delete(&mut *trans, id).await?;
+    trans.commit().await?;
+    Ok(removed)
+}
+
 async fn edit(
     ctx: &crate::context::AppContext,
     req: Request<impl Body>,
@@ -728,6 +867,64 @@ async fn update_settings(
     Ok(settings)
 }
 
+/// Only admins can see the rules, so members do not learn which words are flagged.
+async fn content_rules(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Vec<ContentRule>, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let IdQuery { id } = parse_query(req.uri())?;
+    let access = resolve_space_access(ctx, id, Some(session.user_id)).await?;
+    if !access.can_manage() {
+        return Err(AppError::NoPermission(
+            "Only admins can view content rules".to_string(),
+        ));
+    }
+    let filter = ctx.space_store.resolve_content_filter(id).await?;
+    Ok(filter.rules().to_vec())
+}
+
+async fn update_content_rules(
+    ctx: &crate::context::AppContext,
+    req: Request<impl Body>,
+) -> Result<Vec<ContentRule>, AppError> {
+    let session = authenticate(ctx, &req).await?;
+    let UpdateContentRules { space_id, rules } = interface::parse_body(req).await?;
+    let filter = Arc::new(ContentFilter::compile(rules)?);
+    let mutation = ctx.space_store.acquire_mutation(space_id).await?;
+    let mut trans = ctx.db.begin().await?;
+
+    let Some(space) = Space::get_by_id(&mut *trans, &space_id).await? else {
+        return Err(AppError::NotFound("space"));
+    };
+    let is_admin = SpaceMember::get(&mut *trans, &session.user_id, &space_id)
+        .await?
+        .map(|space_member| space_member.is_admin)
+        .unwrap_or(false);
+    if !is_admin && space.owner_id != session.user_id {
+        tracing::warn!(
+            %space_id,
+            user_id = %session.user_id,
+            "A non-admin tries to update content rules"
+        );
+        return Err(AppError::NoPermission(
+            "Only admins can update content rules".to_string(),
+        ));
+    }
+    Space::put_content_rules(&mut *trans, space_id, filter.rules()).await?;
+    let mutation = mutation.commit(trans).await?;
+    let mut changes = CommittedChanges::default();
+    changes.space_content_rules_updated(space_id, filter.clone());
+    changes.apply_with_mutation(ctx, &mutation).await;
+    tracing::info!(
+        %space_id,
+        user_id = %session.user_id,
+        rules = filter.rules().len(),
+        "The content rules of a space were updated"
+    );
+    Ok(filter.rules().to_vec())
+}
+
 pub async fn router(
     ctx: &crate::context::AppContext,
     req: Request<impl Body>,
@@ -743,11 +940,21 @@ pub async fn router(
         ("/settings", Method::GET) => space_settings(ctx, req).await.map(ok_response),
         ("/update_settings", Method::POST) => update_settings(ctx, req).await.map(ok_response),
         ("/update_settings", Method::PUT) => update_settings(ctx, req).await.map(ok_response),
+        ("/content_rules", Method::GET) => response(content_rules(ctx, req).await).await,
+        ("/update_content_rules", Method::POST) => {
+            response(update_content_rules(ctx, req).await).await
+        }
         ("/token", Method::GET) => token(ctx, req).await.map(ok_response),
         ("/refresh_token", Method::POST) => refresh_token(ctx, req).await.map(ok_response),
         ("/my", Method::GET) => response(my_spaces(ctx, req).await).await,
         ("/search", Method::GET) => response(search(ctx, req).await).await,
         ("/create", Method::POST) => response(create(ctx, req).await).await,
+        ("/clone", Method::POST) => response(clone(ctx, req).await).await,
+        ("/templates", Method::GET) => response(templates(ctx, req).await).await,
+        ("/publish_template", Method::POST) => response(publish_template(ctx, req).await).await,
+        ("/unpublish_template", Method::POST) => {
+            unpublish_template(ctx, req).await.map(ok_response)
+        }
         ("/edit", Method::POST) => response(edit(ctx, req).await).await,
         ("/join", Method::POST) => response(join(ctx, req).await).await,
         ("/leave", Method::POST) => leave(ctx, req).await.map(ok_response),
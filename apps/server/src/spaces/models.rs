@@ -9,6 +9,7 @@ use crate::cache::CACHE;
 use crate::channels::ChannelMember;
 use crate::error::ModelError;
 use crate::spaces::api::SpaceWithMember;
+use crate::spaces::content_rules::{ContentFilter, ContentRule};
 use crate::ttl::{self, Lifespan, fetch_entry};
 use crate::users::User;
 use crate::utils::merge_blank;
@@ -287,6 +288,104 @@ impl Space {
             .await?;
         Ok(())
     }
+
+    pub async fn get_content_rules<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+    ) -> Result<ContentFilter, sqlx::Error> {
+        let rules = sqlx::query_file_scalar!("sql/spaces/get_content_rules.sql", space_id)
+            .fetch_optional(db)
+            .await?;
+        Ok(rules
+            .map(|rules| ContentFilter::from_stored(space_id, rules))
+            .unwrap_or_default())
+    }
+
+    pub async fn put_content_rules<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+        rules: &[ContentRule],
+    ) -> Result<(), sqlx::Error> {
+        let rules = serde_json::to_value(rules).expect("content rules are always serializable");
+        sqlx::query_file_scalar!("sql/spaces/put_content_rules.sql", space_id, rules)
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, specta::Type, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct SpaceTemplate {
+    pub space_id: Uuid,
+    pub owner_id: Uuid,
+    pub name: String,
+    pub description: String,
+    pub is_public: bool,
+    #[specta(type = String)]
+    #[serde(with = "time::serde::rfc3339")]
+    pub created: OffsetDateTime,
+    #[specta(type = String)]
+    #[serde(with = "time::serde::rfc3339")]
+    pub modified: OffsetDateTime,
+}
+
+impl SpaceTemplate {
+    pub async fn put<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+        name: &str,
+        description: &str,
+        is_public: bool,
+    ) -> Result<SpaceTemplate, ModelError> {
+        use crate::validators::{DESCRIPTION, DISPLAY_NAME};
+        let name = merge_blank(name);
+        DISPLAY_NAME.run(&name)?;
+        DESCRIPTION.run(description)?;
+        sqlx::query_file_as!(
+            SpaceTemplate,
+            "sql/spaces/put_template.sql",
+            space_id,
+            name,
+            description,
+            is_public
+        )
+        .fetch_one(db)
+        .await
+        .map_err(Into::into)
+    }
+
+    pub async fn get<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+    ) -> Result<Option<SpaceTemplate>, sqlx::Error> {
+        sqlx::query_file_as!(SpaceTemplate, "sql/spaces/get_template.sql", space_id)
+            .fetch_optional(db)
+            .await
+    }
+
+    /// Public templates plus the ones owned by `user_id`.
+    pub async fn list_visible<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        user_id: Uuid,
+    ) -> Result<Vec<SpaceTemplate>, sqlx::Error> {
+        sqlx::query_file_as!(SpaceTemplate, "sql/spaces/list_templates.sql", user_id)
+            .fetch_all(db)
+            .await
+    }
+
+    pub async fn delete<'c, T: sqlx::PgExecutor<'c>>(
+        db: T,
+        space_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        Ok(
+            sqlx::query_file!("sql/spaces/delete_template.sql", space_id)
+                .execute(db)
+                .await?
+                .rows_affected()
+                > 0,
+        )
+    }
 }
 
 #[cfg(test)]
@@ -594,6 +693,41 @@ mod tests {
             .expect("search failed");
         assert!(search_results.iter().any(|item| item.id == space.id));
     }
+
+    #[sqlx::test(migrator = "crate::db::MIGRATOR")]
+    async fn db_test_space_content_rules_flow(pool: sqlx::PgPool) {
+        use crate::spaces::ContentRuleAction;
+
+        let owner = create_test_user(&pool, "owner").await;
+        let space = create_test_space(&pool, &owner, "rules").await;
+        let empty = Space::get_content_rules(&pool, space.id)
+            .await
+            .expect("get_content_rules failed");
+        assert!(empty.is_empty());
+
+        let settings = json!({ "theme": "dark" });
+        Space::put_settings(&pool, space.id, &settings)
+            .await
+            .expect("put_settings failed");
+        let rules = vec![ContentRule {
+            pattern: "darn".to_string(),
+            is_regex: false,
+            action: ContentRuleAction::Mask,
+        }];
+        Space::put_content_rules(&pool, space.id, &rules)
+            .await
+            .expect("put_content_rules failed");
+        let filter = Space::get_content_rules(&pool, space.id)
+            .await
+            .expect("get_content_rules after update failed");
+        assert_eq!(filter.rules(), &rules[..]);
+        assert_eq!(filter.check("Darn it").unwrap(), "**** it");
+        // Rules and settings share a row but are updated separately.
+        let stored_settings = Space::get_settings(&pool, space.id)
+            .await
+            .expect("get_settings failed");
+        assert_eq!(stored_settings, settings);
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, specta::Type, sqlx::Type)]
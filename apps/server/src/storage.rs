@@ -0,0 +1,105 @@
+//! Where the files of media are kept. The server can run with an S3 compatible bucket, or with a
+//! directory on the local disk.
+mod local;
+mod s3;
+
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub(crate) use local::{LocalStorage, SignedPut};
+pub(crate) use s3::{S3Config, S3Storage};
+
+pub(crate) trait Storage: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Write a small object, to fail early on a misconfigured storage.
+    fn check(&self) -> BoxFuture<'_, anyhow::Result<()>>;
+
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        content_type: &'a str,
+        body: Bytes,
+    ) -> BoxFuture<'a, anyhow::Result<()>>;
+
+    /// A URL which accepts a `PUT` of the object with the content type until it expires.
+    fn presigned_put(&self, key: &str, content_type: &str, expires_in: Duration) -> String;
+
+    /// The size of the object in bytes, or `None` if it does not exist.
+    fn size<'a>(&'a self, key: &'a str) -> BoxFuture<'a, anyhow::Result<Option<u64>>>;
+
+    /// Read the bytes in `range`, which must be within the object.
+    fn read<'a>(
+        &'a self,
+        key: &'a str,
+        range: Range<u64>,
+    ) -> BoxFuture<'a, anyhow::Result<Vec<u8>>>;
+
+    /// Remove the object. Removing a missing object is not an error.
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, anyhow::Result<()>>;
+
+    /// The storage which accepts the signed uploads of `presigned_put` on this server.
+    fn local(&self) -> Option<&LocalStorage> {
+        None
+    }
+}
+
+pub(crate) enum StorageConfig {
+    S3(S3Config),
+    Local {
+        path: PathBuf,
+        /// The URL `/api/media/objects` is reachable at, for signed uploads.
+        url: String,
+    },
+    Disabled,
+}
+
+impl StorageConfig {
+    pub(crate) fn build(self, signer: crate::context::Signer) -> Arc<dyn Storage> {
+        match self {
+            StorageConfig::S3(config) => Arc::new(S3Storage::new(config)),
+            StorageConfig::Local { path, url } => Arc::new(LocalStorage::new(path, url, signer)),
+            StorageConfig::Disabled => Arc::new(Disabled),
+        }
+    }
+}
+
+/// Used when no storage is configured. Every upload and read fails.
+pub(crate) struct Disabled;
+
+const DISABLED: &str = "Object storage is not configured";
+
+impl Storage for Disabled {
+    fn name(&self) -> &'static str {
+        "disabled"
+    }
+
+    /// The server can run without media, so there is nothing to check.
+    fn check(&self) -> BoxFuture<'_, anyhow::Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn put<'a>(&'a self, _: &'a str, _: &'a str, _: Bytes) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async { anyhow::bail!(DISABLED) })
+    }
+
+    fn presigned_put(&self, _: &str, _: &str, _: Duration) -> String {
+        String::new()
+    }
+
+    fn size<'a>(&'a self, _: &'a str) -> BoxFuture<'a, anyhow::Result<Option<u64>>> {
+        Box::pin(async { anyhow::bail!(DISABLED) })
+    }
+
+    fn read<'a>(&'a self, _: &'a str, _: Range<u64>) -> BoxFuture<'a, anyhow::Result<Vec<u8>>> {
+        Box::pin(async { anyhow::bail!(DISABLED) })
+    }
+
+    fn delete<'a>(&'a self, _: &'a str) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async { anyhow::bail!(DISABLED) })
+    }
+}
@@ -0,0 +1,199 @@
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::time::Duration;
+use time::OffsetDateTime;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use super::Storage;
+use crate::context::Signer;
+
+/// The query of a signed upload URL made by `LocalStorage::presigned_put`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SignedPut {
+    pub content_type: String,
+    /// Unix timestamp in seconds
+    pub expires: i64,
+    pub signature: String,
+}
+
+/// Keeps the objects as files in a directory, named by their keys.
+pub(crate) struct LocalStorage {
+    root: PathBuf,
+    url: String,
+    signer: Signer,
+}
+
+fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && !key.starts_with('.')
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+fn put_message(key: &str, content_type: &str, expires: i64) -> String {
+    format!("PUT\n{key}\n{content_type}\n{expires}")
+}
+
+impl LocalStorage {
+    pub(crate) fn new(root: PathBuf, url: String, signer: Signer) -> Self {
+        Self {
+            root,
+            url: url.trim_end_matches('/').to_string(),
+            signer,
+        }
+    }
+
+    fn path(&self, key: &str) -> anyhow::Result<PathBuf> {
+        if !is_valid_key(key) {
+            anyhow::bail!("Invalid object key {key:?}");
+        }
+        Ok(self.root.join(key))
+    }
+
+    /// Check a URL made by `presigned_put`.
+    pub(crate) fn verify_put(&self, key: &str, query: &SignedPut) -> anyhow::Result<()> {
+        if query.expires < OffsetDateTime::now_utc().unix_timestamp() {
+            anyhow::bail!("The upload URL of {key} has expired");
+        }
+        self.signer.verify(
+            &put_message(key, &query.content_type, query.expires),
+            &query.signature,
+        )
+    }
+}
+
+impl Storage for LocalStorage {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn check(&self) -> BoxFuture<'_, anyhow::Result<()>> {
+        Box::pin(async move {
+            tokio::fs::create_dir_all(&self.root).await?;
+            self.put("check", "application/octet-stream", Bytes::new())
+                .await
+        })
+    }
+
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        _content_type: &'a str,
+        body: Bytes,
+    ) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let path = self.path(key)?;
+            // Written aside and renamed, so a reader never sees half a file.
+            let temp = self
+                .root
+                .join(format!(".{key}.{}.tmp", uuid::Uuid::new_v4().simple()));
+            tokio::fs::write(&temp, &body).await?;
+            if let Err(err) = tokio::fs::rename(&temp, &path).await {
+                tokio::fs::remove_file(&temp).await.ok();
+                return Err(err.into());
+            }
+            Ok(())
+        })
+    }
+
+    fn presigned_put(&self, key: &str, content_type: &str, expires_in: Duration) -> String {
+        use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD as base64_engine};
+        use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
+
+        let expires = OffsetDateTime::now_utc().unix_timestamp() + expires_in.as_secs() as i64;
+        let signature = self.signer.sign(&put_message(key, content_type, expires));
+        format!(
+            "{}/{key}?contentType={}&expires={expires}&signature={}",
+            self.url,
+            utf8_percent_encode(content_type, NON_ALPHANUMERIC),
+            base64_engine.encode(signature),
+        )
+    }
+
+    fn size<'a>(&'a self, key: &'a str) -> BoxFuture<'a, anyhow::Result<Option<u64>>> {
+        Box::pin(async move {
+            match tokio::fs::metadata(self.path(key)?).await {
+                Ok(metadata) => Ok(Some(metadata.len())),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(err) => Err(err.into()),
+            }
+        })
+    }
+
+    fn read<'a>(
+        &'a self,
+        key: &'a str,
+        range: Range<u64>,
+    ) -> BoxFuture<'a, anyhow::Result<Vec<u8>>> {
+        Box::pin(async move {
+            let mut file = tokio::fs::File::open(self.path(key)?).await?;
+            file.seek(std::io::SeekFrom::Start(range.start)).await?;
+            let mut buffer = vec![0; (range.end - range.start) as usize];
+            file.read_exact(&mut buffer).await?;
+            Ok(buffer)
+        })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            match tokio::fs::remove_file(self.path(key)?).await {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+                _ => Ok(()),
+            }
+        })
+    }
+
+    fn local(&self) -> Option<&LocalStorage> {
+        Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn objects_are_written_read_and_signed() {
+        let root = std::env::temp_dir().join(format!("boluo-storage-{}", uuid::Uuid::new_v4()));
+        let storage = LocalStorage::new(
+            root.clone(),
+            "/api/media/objects/".to_string(),
+            Signer::new("secret"),
+        );
+        storage.check().await.expect("check failed");
+        storage
+            .put("object", "text/plain", Bytes::from_static(b"hello world"))
+            .await
+            .expect("put failed");
+        assert_eq!(storage.size("object").await.unwrap(), Some(11));
+        assert_eq!(storage.size("missing").await.unwrap(), None);
+        assert_eq!(storage.read("object", 6..11).await.unwrap(), b"world");
+        assert!(storage.read("object", 6..12).await.is_err());
+        assert!(storage.size("../object").await.is_err());
+        storage.delete("object").await.expect("delete failed");
+        storage
+            .delete("object")
+            .await
+            .expect("delete is not idempotent");
+        assert_eq!(storage.size("object").await.unwrap(), None);
+
+        let url = storage.presigned_put("object", "image/png", Duration::from_secs(60));
+        let (path, query) = url.split_once('?').unwrap();
+        assert_eq!(path, "/api/media/objects/object");
+        let query: SignedPut = serde_urlencoded::from_str(query).unwrap();
+        assert_eq!(query.content_type, "image/png");
+        storage.verify_put("object", &query).expect("verify failed");
+        assert!(storage.verify_put("other", &query).is_err());
+        let expired = SignedPut {
+            expires: query.expires - 120,
+            ..query
+        };
+        assert!(storage.verify_put("object", &expired).is_err());
+        tokio::fs::remove_dir_all(root).await.ok();
+    }
+}
@@ -0,0 +1,162 @@
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use rusty_s3::{Bucket, Credentials, S3Action, UrlStyle};
+use std::ops::Range;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use super::Storage;
+
+const SIGN_EXPIRES_IN: Duration = Duration::from_secs(60);
+
+pub(crate) struct S3Config {
+    pub(crate) endpoint_url: String,
+    pub(crate) bucket_name: String,
+    pub(crate) access_key_id: String,
+    pub(crate) secret_access_key: String,
+}
+
+pub(crate) struct S3Storage {
+    bucket: Bucket,
+    credentials: Credentials,
+    client: OnceLock<reqwest::Client>,
+}
+
+impl S3Storage {
+    pub(crate) fn new(config: S3Config) -> Self {
+        let bucket = Bucket::new(
+            config
+                .endpoint_url
+                .parse()
+                .expect("Invalid S3_ENDPOINT_URL"),
+            UrlStyle::Path,
+            config.bucket_name,
+            "auto",
+        )
+        .expect("Failed to initialize S3 bucket config");
+        let credentials = Credentials::new(config.access_key_id, config.secret_access_key);
+        Self {
+            bucket,
+            credentials,
+            client: OnceLock::new(),
+        }
+    }
+
+    fn client(&self) -> &reqwest::Client {
+        self.client.get_or_init(reqwest::Client::new)
+    }
+}
+
+impl Storage for S3Storage {
+    fn name(&self) -> &'static str {
+        "S3"
+    }
+
+    fn check(&self) -> BoxFuture<'_, anyhow::Result<()>> {
+        self.put("check", "application/octet-stream", Bytes::new())
+    }
+
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        content_type: &'a str,
+        body: Bytes,
+    ) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let mut action = self.bucket.put_object(Some(&self.credentials), key);
+            action.headers_mut().insert("content-type", content_type);
+            let url = action.sign(SIGN_EXPIRES_IN);
+            let response = self
+                .client()
+                .put(url.as_str())
+                .header("content-type", content_type)
+                .header("content-length", body.len())
+                .body(body)
+                .send()
+                .await?;
+            if !response.status().is_success() {
+                anyhow::bail!("S3 PUT failed with status {}", response.status());
+            }
+            Ok(())
+        })
+    }
+
+    fn presigned_put(&self, key: &str, content_type: &str, expires_in: Duration) -> String {
+        let mut action = self.bucket.put_object(Some(&self.credentials), key);
+        action.headers_mut().insert("content-type", content_type);
+        action.sign(expires_in).to_string()
+    }
+
+    fn size<'a>(&'a self, key: &'a str) -> BoxFuture<'a, anyhow::Result<Option<u64>>> {
+        Box::pin(async move {
+            let url = self
+                .bucket
+                .head_object(Some(&self.credentials), key)
+                .sign(SIGN_EXPIRES_IN);
+            let response = self.client().head(url.as_str()).send().await?;
+            if response.status() == reqwest::StatusCode::NOT_FOUND {
+                return Ok(None);
+            }
+            if !response.status().is_success() {
+                anyhow::bail!("S3 HEAD failed with status {}", response.status());
+            }
+            let size = response
+                .headers()
+                .get(reqwest::header::CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok())
+                .ok_or_else(|| anyhow::anyhow!("S3 HEAD returned no content length"))?;
+            Ok(Some(size))
+        })
+    }
+
+    fn read<'a>(
+        &'a self,
+        key: &'a str,
+        range: Range<u64>,
+    ) -> BoxFuture<'a, anyhow::Result<Vec<u8>>> {
+        Box::pin(async move {
+            if range.is_empty() {
+                return Ok(Vec::new());
+            }
+            let url = self
+                .bucket
+                .get_object(Some(&self.credentials), key)
+                .sign(SIGN_EXPIRES_IN);
+            let response = self
+                .client()
+                .get(url.as_str())
+                .header(
+                    reqwest::header::RANGE,
+                    format!("bytes={}-{}", range.start, range.end - 1),
+                )
+                .send()
+                .await?;
+            if !response.status().is_success() {
+                anyhow::bail!("S3 GET failed with status {}", response.status());
+            }
+            let bytes = response.bytes().await?;
+            if bytes.len() as u64 != range.end - range.start {
+                anyhow::bail!("S3 GET returned {} bytes for {range:?}", bytes.len());
+            }
+            Ok(bytes.to_vec())
+        })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let url = self
+                .bucket
+                .delete_object(Some(&self.credentials), key)
+                .sign(SIGN_EXPIRES_IN);
+            let response = self.client().delete(url.as_str()).send().await?;
+            // S3 answers 204 for a missing key too, but not every compatible storage does.
+            if !response.status().is_success()
+                && response.status() != reqwest::StatusCode::NOT_FOUND
+            {
+                anyhow::bail!("S3 DELETE failed with status {}", response.status());
+            }
+            Ok(())
+        })
+    }
+}
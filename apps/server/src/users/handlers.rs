@@ -330,7 +330,7 @@ pub async fn edit_avatar(
         return Err(ValidationFailed("Incorrect File Format").into());
     }
     let media_id = id();
-    let media = upload(ctx.storage(), req, media_id, params, 1024 * 1024).await?;
+    let media = upload(ctx, req, media_id, params, 1024 * 1024).await?;
     let mut conn = ctx.db.acquire().await?;
     let media = media.create(&mut *conn, session.user_id, "avatar").await?;
     User::edit(
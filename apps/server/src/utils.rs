@@ -123,6 +123,27 @@ pub fn is_true(v: &bool) -> bool {
     *v
 }
 
+/// Build an `ILIKE` pattern matching `query` anywhere, with wildcards in the input escaped.
+pub fn like_pattern(query: &str) -> String {
+    let query = query.trim();
+    let mut pattern = String::with_capacity(query.len() + 2);
+    pattern.push('%');
+    for c in query.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
+
+#[test]
+fn like_pattern_escapes_wildcards() {
+    assert_eq!(like_pattern(" alice "), "%alice%");
+    assert_eq!(like_pattern("100%_\\"), "%100\\%\\_\\\\%");
+}
+
 #[test]
 fn test_sign() {
     use base64::{Engine as _, engine::general_purpose::STANDARD as base64_engine};
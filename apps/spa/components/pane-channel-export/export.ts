@@ -94,7 +94,9 @@ const entityToExportEntity =
         href:
           typeof entity.href === 'string'
             ? entity.href
-            : text.substr(entity.href.start, entity.href.len),
+            : 'target' in entity.href
+              ? null
+              : text.substr(entity.href.start, entity.href.len),
         start: entity.start,
         len: entity.len,
       };
@@ -213,7 +215,7 @@ function entityBbCode(entity: ExportEntity, color: string): string {
     case 'Emphasis':
       return `[i]${entity.text}[/i]`;
     case 'Link':
-      return `[url=${entity.href}]${entity.text}[/url]`;
+      return entity.href == null ? entity.text : `[url=${entity.href}]${entity.text}[/url]`;
     case 'Strong':
       return `[b]${entity.text}[/b]`;
     case 'Text':
@@ -233,7 +235,7 @@ function entityMarkdown(entity: ExportEntity): string {
     case 'Emphasis':
       return `*${entity.text}*`;
     case 'Link':
-      return `[${entity.text}](${entity.href})`;
+      return entity.href == null ? entity.text : `[${entity.text}](${entity.href})`;
     case 'Strong':
       return `**${entity.text}**`;
     case 'Text':
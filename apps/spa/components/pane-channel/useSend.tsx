@@ -211,7 +211,7 @@ export const useSend = () => {
 
     let uploadResult: Awaited<ReturnType<typeof upload>> | null = null;
     if (composeState.media instanceof File) {
-      uploadResult = await upload(composeState.media);
+      uploadResult = await upload(composeState.media, myMember?.space.spaceId);
     }
     if (uploadResult?.isOk === false) {
       let key: string;
@@ -142,7 +142,7 @@ export const useCharacterPortraitMutation = ({
       let asset: Asset | null = null;
       let assetAttached = false;
       try {
-        const { mediaId } = (await upload(file)).unwrap();
+        const { mediaId } = (await upload(file, spaceId)).unwrap();
         const createdAsset = await post('/assets/create', null, {
           spaceId,
           mediaId,
@@ -100,15 +100,27 @@ export type UploadError =
 
 const TIMEOUT = 'TIMEOUT';
 
+export const hashFile = async (file: Blob): Promise<string> => {
+  const digest = await crypto.subtle.digest('SHA-256', await file.arrayBuffer());
+  const bytes = Array.from(new Uint8Array(digest));
+  return bytes.map((byte) => byte.toString(16).padStart(2, '0')).join('');
+};
+
 export const presign = async (
   file: File,
-): Promise<Result<{ url: string; mediaId: string }, UploadError>> => {
+  hash?: string,
+  spaceId?: string,
+): Promise<Result<{ url: string; mediaId: string; duplicate: boolean }, UploadError>> => {
   const validateResult = validateMedia(file);
   if (!validateResult.isOk) {
     return new Err({ type: 'MEDIA_VALIDATION_ERROR', err: validateResult.err });
   }
   const makePresignPromise = () =>
-    post('/media/presigned', { filename: file.name, mimeType: file.type, size: file.size }, {});
+    post(
+      '/media/presigned',
+      { filename: file.name, mimeType: file.type, size: file.size, hash, spaceId },
+      {},
+    );
   const makeTimeoutPromise = () => timeout(PRESIGN_TIMEOUT);
 
   let presignResult = await Promise.race([makePresignPromise(), makeTimeoutPromise()]);
@@ -122,24 +134,36 @@ export const presign = async (
   if (presignResult.isErr) {
     return new Err({ type: 'PRESIGN_FAIL', err: presignResult.err });
   }
-  const { url, mediaId } = presignResult.some;
-  return new Ok({ url, mediaId });
+  const { url, mediaId, duplicate } = presignResult.some;
+  return new Ok({ url, mediaId, duplicate });
 };
 
-export const upload = async (file: File): Promise<Result<{ mediaId: string }, UploadError>> => {
+/** Pass the space the file is uploaded for, to count it to the storage quota of the space. */
+export const upload = async (
+  file: File,
+  spaceId?: string,
+): Promise<Result<{ mediaId: string }, UploadError>> => {
   const validateResult = validateMedia(file);
   if (!validateResult.isOk) {
     return new Err({ type: 'MEDIA_VALIDATION_ERROR', err: validateResult.err });
   }
-  const presignResult = await presign(file);
+  const hash = await hashFile(file);
+  const presignResult = await presign(file, hash, spaceId);
   if (!presignResult.isOk) {
     return new Err(presignResult.err);
   }
-  const { url, mediaId } = presignResult.some;
+  const { url, mediaId, duplicate } = presignResult.some;
+  if (duplicate) {
+    return new Ok({ mediaId });
+  }
   const uploadResult = await Promise.race([uploadImageToS3(file, url), timeout(UPLOAD_TIMEOUT)]);
   if (uploadResult === TIMEOUT) {
     return new Err({ type: TIMEOUT });
   }
   if (uploadResult.isErr) return uploadResult;
-  return new Ok({ mediaId });
+  const completeResult = await post('/media/complete', { mediaId, hash, spaceId }, {});
+  if (completeResult.isErr) {
+    return new Err({ type: 'PRESIGN_FAIL', err: completeResult.err });
+  }
+  return new Ok({ mediaId: completeResult.some.id });
 };
@@ -116,6 +116,12 @@ export const toChatAction = (update: Update): ChatActionUnion | null | undefined
     case 'ENTRY_CHANGED':
     case 'CHARACTER_CHANGED':
     case 'NOTE_CHANGED':
+    case 'ENCOUNTER_CHANGED':
+    case 'PLAYBACK_CHANGED':
+    case 'NOTE_SNAPSHOT':
+    case 'NOTE_EDITED':
+    case 'NOTE_CLOSED':
+    case 'NOTE_EDIT_REJECTED':
     case 'APP_UPDATED':
       return null;
   }
@@ -220,6 +220,12 @@ const handleUpdate = (
       case 'DIFF':
       case 'APP_INFO':
       case 'NOTE_CHANGED':
+      case 'ENCOUNTER_CHANGED':
+      case 'PLAYBACK_CHANGED':
+      case 'NOTE_SNAPSHOT':
+      case 'NOTE_EDITED':
+      case 'NOTE_CLOSED':
+      case 'NOTE_EDIT_REJECTED':
       case 'APP_UPDATED':
         return [];
     }
@@ -1,6 +1,8 @@
 import type {
   AppSettings,
   Asset,
+  AssetFolder,
+  AssetUsages,
   Channel,
   ChannelMemberWithUser,
   ChannelMembers,
@@ -11,6 +13,8 @@ import type {
   CheckUsernameExists,
   Character,
   CharacterUsage,
+  DiffNoteRevisions,
+  ChannelPlayback,
   Entry,
   EntryComponentMatch,
   EntryMetadata,
@@ -23,6 +27,7 @@ import type {
   GetMessagesByChannel,
   ListCharacters,
   ListEntriesByComponent,
+  ListAssetFolders,
   ListAssets,
   ListEntries,
   ListNotes,
@@ -31,17 +36,27 @@ import type {
   Note,
   NoteContentRevision,
   NoteMetadata,
+  NoteLink,
+  NoteRevisionDiff,
+  NoteRevisionPolicy,
+  QueryBacklinks,
   QueryNote,
+  QueryNoteRevisionPolicy,
+  QueryPlayback,
   QueryAsset,
   QueryCharacter,
   QueryEntry,
+  QueryStorageUsage,
   SearchMessagesParams,
   SearchMessagesResult,
+  SearchHit,
+  SearchSpace,
   Space,
   SpaceMember,
   SpaceMemberWithUser,
   SpaceWithMember,
   SpaceWithRelated,
+  StorageUsageResult,
   User,
   UserStatus,
   VerifyEmail,
@@ -52,6 +67,8 @@ export interface Get {
   '/assets/query': { query: QueryAsset; result: Asset };
   '/assets/by_space': { query: ListAssets; result: Asset[] };
   '/assets/by_creator': { query: null; result: Asset[] };
+  '/assets/usages': { query: QueryAsset; result: AssetUsages };
+  '/assets/folders': { query: ListAssetFolders; result: AssetFolder[] };
   // users
   '/users/query': { query: { id: string | null }; result: User | null };
   '/users/query_self': { query: null; result: User | null };
@@ -91,6 +108,10 @@ export interface Get {
   '/notes/query': { query: QueryNote; result: Note };
   '/notes/by_space': { query: ListNotes; result: NoteMetadata[] };
   '/notes/content_revisions': { query: QueryNote; result: NoteContentRevision[] };
+  '/notes/diff': { query: DiffNoteRevisions; result: NoteRevisionDiff };
+  '/notes/links': { query: QueryNote; result: NoteLink[] };
+  '/notes/backlinks': { query: QueryBacklinks; result: NoteMetadata[] };
+  '/notes/revision_policy': { query: QueryNoteRevisionPolicy; result: NoteRevisionPolicy };
   // entries
   '/entries/by_scope': { query: ListEntries; result: EntryMetadata[] };
   '/entries/by_component': {
@@ -109,6 +130,12 @@ export interface Get {
   '/characters/by_space': { query: ListCharacters; result: Character[] };
   '/characters/usages': { query: QueryCharacter; result: CharacterUsage[] };
   '/characters/check_identifier': { query: CheckCharacterIdentifier; result: boolean };
+  // media
+  '/media/usage': { query: QueryStorageUsage; result: StorageUsageResult };
+  // playback
+  '/playback/query': { query: QueryPlayback; result: ChannelPlayback | null };
+  // search
+  '/search/space': { query: SearchSpace; result: SearchHit[] };
   // updates (formerly known as events)
   '/updates/token': { query: MakeToken; result: { token: string; issuedAt: number } };
   // info
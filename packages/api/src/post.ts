@@ -5,18 +5,25 @@ import type {
   ArchiveCharacter,
   ArchiveNote,
   Asset,
+  AssetFolder,
   Channel,
   ChannelMember,
+  ChannelPlayback,
   ChannelWithMember,
   Character,
+  CompleteUpload,
+  ControlPlayback,
   CreateCharacter,
   CreateAsset,
+  CreateAssetFolder,
   CreateChannel,
   CreateEntry,
   CreateNote,
   CreateSpace,
   DeleteAsset,
+  DeleteAssetFolder,
   DeleteEntry,
+  DeleteMessage,
   EditChannel,
   EditChannelMember,
   EditChannelTopic,
@@ -29,17 +36,21 @@ import type {
   Login as LoginData,
   LoginReturn,
   Entry,
+  Media,
   MessageEntryEffects,
   Message,
   MessageIdQuery,
+  MoveAssets,
   NewMessage,
   Note,
+  NoteRevisionPolicy,
   QueryEntryEffectsByMessages,
   Register as RegisterData,
   ResendEmailVerification,
   ResendEmailVerificationResult,
   RestoreCharacter,
   RestoreNote,
+  RestoreNoteRevision,
   ResetPassword,
   ResetPasswordConfirm,
   Space,
@@ -47,6 +58,8 @@ import type {
   SpaceWithMember,
   User,
   UpdateAsset,
+  UpdateAssetFolder,
+  UpdateNoteRevisionPolicy,
 } from '@boluo/types/bindings';
 
 export interface Post {
@@ -54,6 +67,10 @@ export interface Post {
   '/assets/create': { payload: CreateAsset; query: null; result: Asset };
   '/assets/update': { payload: UpdateAsset; query: null; result: Asset };
   '/assets/delete': { payload: DeleteAsset; query: null; result: Asset };
+  '/assets/move': { payload: MoveAssets; query: null; result: Asset[] };
+  '/assets/create_folder': { payload: CreateAssetFolder; query: null; result: AssetFolder };
+  '/assets/update_folder': { payload: UpdateAssetFolder; query: null; result: AssetFolder };
+  '/assets/delete_folder': { payload: DeleteAssetFolder; query: null; result: AssetFolder };
   // users
   '/users/login': { payload: LoginData; query: null; result: LoginReturn };
   '/users/register': { payload: RegisterData; query: null; result: User };
@@ -86,7 +103,7 @@ export interface Post {
   // messages
   '/messages/send': { payload: NewMessage; query: null; result: Message };
   '/messages/move_between': { payload: MoveMessageBetween; query: null; result: Message };
-  '/messages/delete': { payload: Empty; query: MessageIdQuery; result: Message };
+  '/messages/delete': { payload: Empty; query: DeleteMessage; result: Message };
   '/messages/toggle_fold': { payload: Empty; query: MessageIdQuery; result: Message };
   // channels
   '/channels/create': { payload: CreateChannel; query: null; result: ChannelWithMember };
@@ -103,6 +120,12 @@ export interface Post {
   '/notes/create': { payload: CreateNote; query: null; result: Note };
   '/notes/archive': { payload: ArchiveNote; query: null; result: true };
   '/notes/restore': { payload: RestoreNote; query: null; result: true };
+  '/notes/restore_revision': { payload: RestoreNoteRevision; query: null; result: Note };
+  '/notes/update_revision_policy': {
+    payload: UpdateNoteRevisionPolicy;
+    query: null;
+    result: NoteRevisionPolicy;
+  };
   // entries
   '/entries/create': { payload: CreateEntry; query: null; result: Entry };
   '/entries/delete': { payload: DeleteEntry; query: null; result: true };
@@ -117,4 +140,7 @@ export interface Post {
   '/characters/restore': { payload: RestoreCharacter; query: null; result: Character };
   // media
   '/media/presigned': { query: PreSign; payload: Empty; result: PreSignResult };
+  '/media/complete': { query: CompleteUpload; payload: Empty; result: Media };
+  // playback
+  '/playback/control': { payload: ControlPlayback; query: null; result: ChannelPlayback | null };
 }
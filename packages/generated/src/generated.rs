@@ -22,6 +22,14 @@ pub struct Channel {
     pub r#type: ChannelType,
     #[serde(default)]
     pub is_archived: bool,
+    /// Seconds a non-master has to wait between two messages, 0 if slow mode is off
+    #[serde(default)]
+    pub slow_mode_seconds: i32,
+    #[serde(default)]
+    pub posting_policy: ChannelPostingPolicy,
+    /// The maximum characters of a message, 0 if there is no channel limit
+    #[serde(default)]
+    pub max_message_length: i32,
 }
 
 #[allow(deprecated, non_camel_case_types, non_snake_case)]
@@ -37,6 +45,18 @@ pub struct ChannelMember {
     pub character_id: ::std::option::Option<::uuid::Uuid>,
 }
 
+/// Who can post in a channel. Read-only channels, such as announcements, are `MastersOnly`.
+/// `MastersOnlyInGame` lets everyone chat out-of-game but keeps in-game messages to masters.
+#[allow(deprecated, non_camel_case_types, non_snake_case)]
+#[derive(serde::Serialize, serde::Deserialize, Default)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ChannelPostingPolicy {
+    #[default]
+    Everyone,
+    MastersOnly,
+    MastersOnlyInGame,
+}
+
 #[allow(deprecated, non_camel_case_types, non_snake_case)]
 #[derive(serde::Serialize, serde::Deserialize, Default)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
@@ -9,7 +9,8 @@ export interface ExportExpr extends Span {
 
 export interface ExportLink extends Span {
   type: 'Link';
-  href: string;
+  /** `null` for wiki links, which only make sense within the space */
+  href: string | null;
   title?: string;
   text: string;
 }
@@ -183,6 +183,8 @@ export const evaluate = (node: ExprNode, rng: Prando, layer = 0): EvaluatedExprN
       evaluated.push(result);
     }
     return { ...node, evaluated, value };
+  } else if (node.type === 'Table') {
+    return { ...node, value: node.result?.roll ?? 0 };
   } else {
     return { type: 'Unknown', value: 0 };
   }
@@ -278,6 +280,12 @@ export const nodeToText = (intl: IntlShape, node: EvaluatedExprNode): string =>
   } else if (node.type === 'Repeat') {
     const textList: string[] = node.evaluated.map(toText);
     return textList.join(', ');
+  } else if (node.type === 'Table') {
+    const textList: string[] = [];
+    for (let result = node.result; result != null; result = result.nested) {
+      textList.push(`${result.name} ${result.roll}/${result.totalWeight} ⇒ ${result.text}`);
+    }
+    return textList.length > 0 ? textList.join(' → ') : `${node.table}: …`;
   }
   return '[???]';
 };
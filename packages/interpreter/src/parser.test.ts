@@ -47,6 +47,41 @@ test('parse link', () => {
   assert.deepStrictEqual(parse('hello [world](https://masiro.me/)!'), expected);
 });
 
+test('parse wiki link', () => {
+  const expected: Entity[] = [
+    {
+      start: 4,
+      len: 23,
+      type: 'Link',
+      href: {
+        kind: 'CHARACTER',
+        target: 'Alice',
+      },
+      child: {
+        type: 'Text',
+        start: 22,
+        len: 3,
+      },
+    },
+    {
+      start: 32,
+      len: 14,
+      type: 'Link',
+      href: {
+        kind: null,
+        target: 'The Dragon',
+      },
+      child: {
+        type: 'Text',
+        start: 34,
+        len: 10,
+      },
+    },
+  ];
+
+  assert.deepStrictEqual(parse('see [[character:Alice|her]] and [[The Dragon]].'), expected);
+});
+
 test('parse string', () => {
   const expected: Entity[] = [
     {
@@ -271,3 +306,22 @@ test('parse roll', () => {
 
   assert.deepStrictEqual(parse('/r {1d20}'), prefixedRoll);
 });
+
+test('parse table roll', () => {
+  const tableRoll: Entity[] = [
+    {
+      type: 'Expr',
+      start: 0,
+      len: 12,
+      node: {
+        type: 'Table',
+        table: 'loot',
+        result: null,
+      },
+    },
+  ];
+
+  assert.deepStrictEqual(parse('{table:Loot}'), tableRoll);
+  assert.deepStrictEqual(parse('{table: loot}')[0]?.type, 'Expr');
+  assert.deepStrictEqual(parse('{table:}'), []);
+});
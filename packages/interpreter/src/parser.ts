@@ -7,6 +7,7 @@ import type {
   ExprNode,
   PureExprNode,
   PureExprOf,
+  WikiLinkKind,
 } from '@boluo/api';
 import type { AsTarget, ParseResult } from './parse-result';
 
@@ -325,6 +326,29 @@ const link: P<Entity> = regex(LINK_REGEX).then(([match, { text, rest }]) => {
   return [entity, { text, rest }];
 });
 
+// [[target]], [[kind:target]] or [[target|label]]
+const WIKI_LINK_REGEX = /^\[\[(?:(note|entry|character):)?([^[\]|\n]+?)(?:\|([^[\]\n]+?))?]]/i;
+const wikiLink: P<Entity> = regex(WIKI_LINK_REGEX).then(([match, { text, rest }]) => {
+  const [entire, kind, target = '', label] = match;
+  const content = label ?? target;
+  const entity: EntityOf<'Link'> = {
+    type: 'Link',
+    start: text.length,
+    len: entire.length,
+    child: {
+      type: 'Text',
+      start: text.length + entire.lastIndexOf(content),
+      len: content.length,
+    },
+    href: {
+      kind: kind ? (kind.toUpperCase() as WikiLinkKind) : null,
+      target: target.trim(),
+    },
+  };
+  text += entire;
+  return [entity, { text, rest }];
+});
+
 const spaces: P<null> = regex(/^\s*/).then(([match, state]) => {
   return [
     null,
@@ -349,6 +373,12 @@ const fateRoll: P<ExprOf<'FateRoll'>> = regex(/^([Ff][Aa][Tt][Ee]|dF)\b/).map(()
   return { type: 'FateRoll' };
 });
 
+const TABLE_ROLL = /^table\s*[:：]\s*([^\s{}()（）[\]【】｛｝+\-*\/]+)/i;
+
+const tableRoll: P<ExprOf<'Table'>> = regex(TABLE_ROLL).map((match) => {
+  return { type: 'Table', table: match[1]!.toLowerCase(), result: null };
+});
+
 const srRoll: P<ExprOf<'DicePool'>> = regex(/^sr(p?) {0,2}(\d+)\b/).then(([match, state]) => {
   const push = Boolean(match[1]);
   const counterStr = match[2];
@@ -564,7 +594,7 @@ const atom = (): P<ExprNode> => {
       .skip(regex(/^\s*]/))
       .map(subExprMapper), // match [...]
   ]);
-  return choice([srRoll, roll, cocRoll, fateRoll, wodRoll, repeat(), num, subExpr]);
+  return choice([tableRoll, srRoll, roll, cocRoll, fateRoll, wodRoll, repeat(), num, subExpr]);
 };
 
 const atomPure = (): P<PureExprNode> => {
@@ -670,6 +700,7 @@ const entity = choice<Entity>([
   strongEmphasis,
   strong,
   emphasis,
+  wikiLink,
   link,
   autoUrl,
   expression,
@@ -694,6 +725,7 @@ const rollCommand: P<Entity[]> = new P((state, env) => {
     strongEmphasis,
     strong,
     emphasis,
+    wikiLink,
     link,
     autoUrl,
     expression,
@@ -1,10 +1,51 @@
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize, Clone, specta::Type)]
 #[serde(untagged)]
 pub enum Href {
     Link(String),
     Position(Span),
+    Wiki(WikiLink),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, specta::Type)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum WikiLinkKind {
+    Note,
+    Entry,
+    Character,
+}
+
+impl WikiLinkKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WikiLinkKind::Note => "NOTE",
+            WikiLinkKind::Entry => "ENTRY",
+            WikiLinkKind::Character => "CHARACTER",
+        }
+    }
+
+    pub fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "NOTE" => Some(WikiLinkKind::Note),
+            "ENTRY" => Some(WikiLinkKind::Entry),
+            "CHARACTER" => Some(WikiLinkKind::Character),
+            _ => None,
+        }
+    }
+}
+
+/// A link to a note, an entry or a character of the same space, written as `[[target]]`.
+///
+/// The target is matched by the title or a keyword of notes, and by the key, an alias or the
+/// name of characters and entries.
+#[derive(Debug, Serialize, Deserialize, Clone, specta::Type)]
+pub struct WikiLink {
+    /// Only match this kind, any kind if not given
+    #[serde(default)]
+    pub kind: Option<WikiLinkKind>,
+    pub target: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, specta::Type, Copy)]
@@ -80,6 +121,7 @@ pub enum ExprNode {
     DicePool(Box<DicePool>),
     FateRoll,
     Repeat(Repeat),
+    Table(TableRoll),
     #[default]
     Unknown,
 }
@@ -132,6 +174,35 @@ pub struct PureRepeat {
     count: u32,
 }
 
+/// A roll on a random table of the Space, such as `{table:loot}`.
+#[derive(Debug, Serialize, Deserialize, Clone, specta::Type)]
+pub struct TableRoll {
+    /// The key of the table
+    pub table: String,
+    /// Resolved by the server from the seed of the message, so that every client sees the same
+    /// row. A result sent by a client is replaced.
+    #[serde(default)]
+    pub result: Option<TableRollResult>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TableRollResult {
+    pub table_id: Uuid,
+    /// The version of the table at the time of the roll
+    pub table_version: Uuid,
+    pub name: String,
+    /// From 1 to `totalWeight`
+    pub roll: u32,
+    pub total_weight: u32,
+    /// The index of the row the roll landed on
+    pub row: u32,
+    pub text: String,
+    /// The roll on the table the row refers to
+    #[serde(default)]
+    pub nested: Option<Box<TableRollResult>>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, specta::Type)]
 #[serde(rename_all = "camelCase")]
 pub struct CocRoll {
@@ -207,6 +278,7 @@ pub enum EvaluatedExprNode {
     FateRoll(FateResult),
     DicePool(DicePoolResult),
     Repeat(RepeatResult),
+    Table(TableResult),
     Unknown { value: f64 },
 }
 
@@ -258,6 +330,13 @@ pub struct RepeatResult {
     value: f64,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, specta::Type)]
+pub struct TableResult {
+    #[serde(flatten)]
+    roll: TableRoll,
+    value: f64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, specta::Type)]
 pub struct BinaryResult {
     op: Operator,
@@ -11,6 +11,110 @@ export type AddChannelMember = {
   characterId?: string | null;
 };
 
+export type AddCombatant = {
+  spaceId: string;
+  channelId: string;
+  source: CombatantSource;
+  /**  Overrides the name of the character or the entry. */
+  name?: string | null;
+  initiative: number;
+  /**  The roll message in the channel the initiative was taken from. */
+  initiativeMessageId?: string | null;
+};
+
+export type AdminAuditEntry = {
+  id: string;
+  /**  `None` once the acting user has been removed. */
+  actorId: string | null;
+  action: string;
+  targetId: string | null;
+  detail: JsonValue;
+  created: string;
+};
+
+export type AdminAuditQuery = {
+  targetId?: string | null;
+  /**  The id of the last entry of the previous page */
+  before?: string | null;
+  limit?: number | null;
+};
+
+export type AdminCollectMedia = {
+  /**  Only report the media which would be removed */
+  dryRun: boolean;
+  /**  Keep media created in this many hours, 7 days by default */
+  graceHours?: number | null;
+  limit?: number | null;
+};
+
+export type AdminDeleteSpace = {
+  spaceId: string;
+  reason?: string | null;
+};
+
+export type AdminPurgeCache = {
+  /**  One of the `CacheType` names, e.g. `Session` */
+  cache: string;
+};
+
+export type AdminPurgeCacheResult = {
+  cache: string;
+  /**  The entries removed from the cache of the node which served the request */
+  removed: number;
+};
+
+export type AdminRuntimeOverview = {
+  mailboxes: number;
+  runtimes: SpaceRuntimeStats[];
+  /**  Pending actions of each space mailbox, keyed by space id */
+  mailboxQueueDepths: { [key in string]: number };
+};
+
+export type AdminSearchSpaces = {
+  /**  Matches name or description */
+  query?: string | null;
+  ownerId?: string | null;
+  includeDeleted?: boolean;
+  limit?: number | null;
+  offset?: number | null;
+};
+
+export type AdminSearchUsers = {
+  /**  Matches username, nickname or email */
+  query?: string | null;
+  id?: string | null;
+  limit?: number | null;
+  offset?: number | null;
+};
+
+export type AdminSpace = {
+  space: Space;
+  deleted: boolean;
+};
+
+/**  A user as seen by site admins, including the fields `User` never serializes. */
+export type AdminUser = {
+  id: string;
+  email: string;
+  username: string;
+  nickname: string;
+  joined: string;
+  deactivated: boolean;
+  avatarId: string | null;
+};
+
+export type AdminUserAction = {
+  userId: string;
+  reason?: string | null;
+};
+
+export type AdvanceTurn = {
+  spaceId: string;
+  channelId: string;
+  /**  The version of the encounter the client saw, so that a double click does not skip a turn. */
+  expectedVersion: string;
+};
+
 export type AppSettings = {
   mediaUrl?: string | null;
   appUrl?: string | null;
@@ -34,15 +138,53 @@ export type Asset = {
   id: string;
   spaceId: string;
   mediaId: string;
+  folderId: string | null;
   creatorId: string | null;
   name: string;
+  tags: string[];
   policy: AssetPolicy;
   mimeType: string;
+  /**  The length of audio Assets */
+  durationMs: number | null;
+  created: string;
+};
+
+export type AssetCharacterUsage = {
+  id: string;
+  name: string;
+};
+
+export type AssetEntryUsage = {
+  entryId: string;
+  scopeId: string;
+  displayName: string;
+  componentType: string;
+};
+
+export type AssetFolder = {
+  id: string;
+  spaceId: string;
+  parentId: string | null;
+  creatorId: string | null;
+  name: string;
   created: string;
 };
 
 export type AssetPolicy = 'UNLISTED' | 'LISTED';
 
+export type AssetSort = 'created' | 'name';
+
+/**  What refers to an Asset, to check before deleting it. */
+export type AssetUsages = {
+  /**  The Entry components using the Asset, in the Scopes the user can view */
+  entries: AssetEntryUsage[];
+  /**  The number of Entry components in Scopes the user can't view */
+  hiddenEntries: number;
+  characters: AssetCharacterUsage[];
+  /**  The messages with the Asset as the portrait, which lose it if the Asset is deleted */
+  messages: number;
+};
+
 export type BasicInfo = {
   version: string;
 };
@@ -60,6 +202,14 @@ export type BinaryResult = {
   value: number;
 };
 
+export type CacheStats = {
+  name: string;
+  items: number;
+  capacity: number;
+  hits: number;
+  misses: number;
+};
+
 export type Channel = {
   id: string;
   name: string;
@@ -72,8 +222,19 @@ export type Channel = {
   isDocument: boolean;
   type: ChannelType;
   isArchived?: boolean;
+  /**  Seconds a non-master has to wait between two messages, 0 if slow mode is off */
+  slowModeSeconds?: number;
+  postingPolicy?: ChannelPostingPolicy;
+  /**  The maximum characters of a message, 0 if there is no channel limit */
+  maxMessageLength?: number;
 };
 
+/**
+ *  Who can post in a channel. Read-only channels, such as announcements, are `MastersOnly`.
+ *  `MastersOnlyInGame` lets everyone chat out-of-game but keeps in-game messages to masters.
+ */
+export type ChannelPostingPolicy = 'EVERYONE' | 'MASTERS_ONLY' | 'MASTERS_ONLY_IN_GAME';
+
 export type ChannelMember = {
   userId: string;
   channelId: string;
@@ -96,6 +257,29 @@ export type ChannelMembers = {
   selfIndex: number | null;
 };
 
+/**  The audio playing in a channel. A channel plays at most one Asset at a time. */
+export type ChannelPlayback = {
+  channelId: string;
+  spaceId: string;
+  assetId: string;
+  mediaId: string;
+  assetName: string;
+  mimeType: string;
+  durationMs: number | null;
+  /**  `false` when paused */
+  playing: boolean;
+  /**  Where the playback was at `updated`. */
+  positionMs: number;
+  /**  Where the playback is when it's read, so clients can sync without trusting their clocks. */
+  currentPositionMs: number;
+  looping: boolean;
+  /**  From 0 to 1 */
+  volume: number;
+  updatedBy: string | null;
+  version: string;
+  updated: string;
+};
+
 export type ChannelType = 'IN_GAME' | 'OUT_OF_GAME' | 'DOCUMENT';
 
 export type ChannelWithMaybeMember = {
@@ -136,6 +320,51 @@ export type Character = {
   version: string;
 };
 
+/**
+ *  A Character with the Entries of its Scope, in a format which can be imported into another
+ *  space.
+ */
+export type CharacterBundle = {
+  format: number;
+  character: CharacterBundleCharacter;
+  /**  In the order of the Scope */
+  entries?: EntryImportRow[];
+  assets?: CharacterBundleAsset[];
+};
+
+/**
+ *  An Asset referenced by the Asset components of the bundle. Media are shared between the
+ *  spaces of a server, so the Asset is registered again in the space it is imported into.
+ */
+export type CharacterBundleAsset = {
+  /**  The id the Asset components of the bundle refer to */
+  id: string;
+  mediaId: string;
+  name: string;
+  mimeType: string;
+};
+
+/**  The fields of a Character which can be moved to another space. */
+export type CharacterBundleCharacter = {
+  name: string;
+  key: string;
+  aliases?: string[];
+  description?: string;
+  color?: string;
+  tags?: string[];
+};
+
+export type CharacterBundleImport = {
+  character: Character;
+  entries: EntryMetadata[];
+  assets: Asset[];
+  /**
+   *  The Assets of the bundle whose media is missing or not available to the user. The
+   *  components referring to them are left out.
+   */
+  skippedAssets: string[];
+};
+
 export type CharacterUsage = {
   channel: Channel;
   member: ChannelMember;
@@ -174,7 +403,18 @@ export type ChildText = {
 export type ClientEvent =
   | { type: 'PREVIEW'; preview: PreviewPost }
   | { type: 'DIFF'; preview: PreviewDiffPost }
-  | { type: 'STATUS'; kind: StatusKind; focus: string[] };
+  | { type: 'STATUS'; kind: StatusKind; focus: string[] }
+  | { type: 'NOTE_JOIN'; noteId: string }
+  | { type: 'NOTE_LEAVE'; noteId: string }
+  | { type: 'NOTE_EDIT'; edit: NoteEditPost };
+
+export type CloneSpace = {
+  /**  The source space or template. */
+  spaceId: string;
+  name: string;
+  description: string | null;
+  parts?: SpaceCloneParts;
+};
 
 export type CocRoll = {
   subType: CocRollSubType;
@@ -190,6 +430,81 @@ export type CocRollResult = {
 
 export type CocRollSubType = 'NORMAL' | 'BONUS' | 'BONUS_2' | 'PENALTY' | 'PENALTY_2';
 
+/**
+ *  One step of a migration, which upgrades component data from the previous schema version.
+ *
+ *  Paths are JSON Pointers, such as `/stats/hp`. `SET` replaces the field, while `DEFAULT` only
+ *  fills it in if it is missing. Steps whose source is missing do nothing.
+ */
+export type ComponentMigrationStep =
+  | { op: 'SET'; path: string; value: Value }
+  | { op: 'DEFAULT'; path: string; value: Value }
+  | { op: 'RENAME'; from: string; to: string }
+  | { op: 'COPY'; from: string; to: string }
+  | { op: 'REMOVE'; path: string };
+
+export type Combatant = {
+  id: string;
+  channelId: string;
+  name: string;
+  characterId: string | null;
+  entryId: string | null;
+  entryScopeId: string | null;
+  initiative: number;
+  /**  The roll message the initiative came from */
+  initiativeMessageId: string | null;
+  addedBy: string | null;
+  created: string;
+};
+
+/**  Who takes part in the encounter. */
+export type CombatantSource =
+  | { kind: 'CHARACTER'; characterId: string }
+  | { kind: 'ENTRY'; scopeId: string; entryId: string }
+  | { kind: 'CUSTOM'; name: string };
+
+/**  Sent after the file is uploaded to a presigned URL. */
+export type CompleteUpload = {
+  mediaId: string;
+  /**  Hex encoded SHA-256 the client expects the uploaded file to have */
+  hash?: string | null;
+  /**  The space the file is uploaded for, to count it to the quota of the space */
+  spaceId?: string | null;
+};
+
+/**  A condition on the data of a JSON component, such as `stats.hp < 10`. */
+export type ComponentCondition = {
+  componentType: string;
+  /**
+   *  A dot-separated path into the component data, empty for the data itself.
+   *  Numeric segments index arrays.
+   */
+  path?: string;
+  op: ComponentConditionOp;
+  /**  A string, number, boolean or null */
+  value?: Value;
+};
+
+export type ComponentConditionOp =
+  | 'EQ'
+  | 'NE'
+  | 'LT'
+  | 'LTE'
+  | 'GT'
+  | 'GTE'
+  | 'EXISTS'
+  | 'STARTS_WITH';
+
+/**  A schema version of a component type registered by a space. */
+export type ComponentTypeVersion = {
+  componentType: string;
+  schemaVersion: number;
+  schema: Value;
+  /**  Upgrades data of the previous schema version to this one. */
+  migration: ComponentMigrationStep[];
+  created: string;
+};
+
 export type ConfirmEmailChange = {
   token: string;
 };
@@ -202,11 +517,41 @@ export type ConnectionError =
   | 'UNEXPECTED'
   | 'BAD_REQUEST';
 
+/**
+ *  A blocked word or regex of a space.
+ *
+ *  Words match anywhere in the text, ignoring case, since there are no spaces between words in
+ *  CJK text. Use a regex with `\b` to match whole words only.
+ */
+export type ContentRule = {
+  pattern: string;
+  isRegex?: boolean;
+  action: ContentRuleAction;
+};
+
+export type ContentRuleAction = 'REJECT' | 'MASK' | 'FLAG';
+
+export type ControlPlayback = {
+  spaceId: string;
+  channelId: string;
+  action: PlaybackAction;
+  /**  Fails with a conflict if the playback has changed since this version. */
+  expectedVersion?: string | null;
+};
+
 export type CreateAsset = {
   spaceId: string;
   mediaId: string;
   name: string;
   policy?: AssetPolicy;
+  folderId?: string | null;
+  tags?: string[];
+};
+
+export type CreateAssetFolder = {
+  spaceId: string;
+  parentId?: string | null;
+  name: string;
 };
 
 export type CreateChannel = {
@@ -244,6 +589,11 @@ export type CreateEntry = {
   messageId: string | null;
 };
 
+export type CreateEntryTemplate = {
+  spaceId: string;
+  template: EntryTemplateInput;
+};
+
 export type CreateNote = {
   spaceId: string;
   title?: string;
@@ -255,6 +605,15 @@ export type CreateNote = {
   accessChannelId: string | null;
 };
 
+export type CreateRandomTable = { spaceId: string; table: RandomTableInput };
+
+export type CreateReport = {
+  targetKind: ReportTargetKind;
+  targetId: string;
+  reason: ReportReason;
+  comment?: string;
+};
+
 export type CreateSpace = {
   name: string;
   password: string | null;
@@ -264,10 +623,34 @@ export type CreateSpace = {
   firstChannelType: ChannelType | null;
 };
 
+/**  The CSV columns of the fields, by their headers. Aliases and tags are separated by `;`. */
+export type CsvColumnMapping = {
+  key: string;
+  aliases?: string | null;
+  displayName?: string | null;
+  tags?: string | null;
+  components?: CsvComponentColumn[];
+};
+
+/**  A CSV column holding a value in the data of a JSON component. */
+export type CsvComponentColumn = {
+  column: string;
+  componentType: string;
+  /**
+   *  A dot-separated path into the component data, empty for the data itself.
+   *  Numeric segments index arrays.
+   */
+  path?: string;
+};
+
 export type DeleteAsset = {
   assetId: string;
 };
 
+export type DeleteAssetFolder = {
+  folderId: string;
+};
+
 export type DeleteEntry = {
   spaceId: string;
   scopeId: string;
@@ -276,6 +659,20 @@ export type DeleteEntry = {
   messageId: string | null;
 };
 
+export type DeleteEntryTemplate = {
+  spaceId: string;
+  templateId: string;
+};
+
+export type DeleteMessage = {
+  id: string;
+  spaceId?: string | null;
+  /**  Revert the Entry Effects of the message first. Fails if they conflict with later changes. */
+  revertEntryEffects?: boolean;
+};
+
+export type DeleteRandomTable = { spaceId: string; tableId: string };
+
 export type DicePool = {
   counter: number;
   face: number;
@@ -290,6 +687,35 @@ export type DicePoolResult = {
   values: number[];
 } & DicePool;
 
+export type DiffLine = {
+  tag: DiffTag;
+  /**  Zero-based line number in the old text, `None` for inserted lines */
+  oldLine: number | null;
+  /**  Zero-based line number in the new text, `None` for deleted lines */
+  newLine: number | null;
+  /**  The line without its line break */
+  spans: DiffSpan[];
+};
+
+/**  Compare two content revisions of a note, or a revision with the current content. */
+export type DiffNoteRevisions = {
+  spaceId: string;
+  noteId: string;
+  from: number;
+  /**  The current content if not given */
+  to?: number | null;
+  /**  Only return the changed lines and this many lines around them */
+  context?: number | null;
+};
+
+export type DiffSpan = {
+  text: string;
+  /**  The words changed within a changed line */
+  emphasized: boolean;
+};
+
+export type DiffTag = 'EQUAL' | 'DELETE' | 'INSERT';
+
 export type DiscourseConnect = {
   sso: string;
   sig: string;
@@ -307,6 +733,9 @@ export type EditChannel = {
   isDocument: boolean | null;
   isArchived: boolean | null;
   type: ChannelType | null;
+  slowModeSeconds?: number | null;
+  postingPolicy?: ChannelPostingPolicy | null;
+  maxMessageLength?: number | null;
 };
 
 export type EditChannelMember = {
@@ -337,6 +766,15 @@ export type EditCharacter = {
   tags: string[];
 };
 
+export type EditEncounter = {
+  spaceId: string;
+  channelId: string;
+  expectedVersion: string;
+  announceTurns: boolean;
+  durationComponentType: string | null;
+  durationPath?: string;
+};
+
 export type EditEntry = {
   spaceId: string;
   scopeId: string;
@@ -361,6 +799,13 @@ export type EditEntryComponents = {
   changes: EntryComponentMutation[];
 };
 
+export type EditEntryTemplate = {
+  spaceId: string;
+  templateId: string;
+  expectedVersion: string;
+  template: EntryTemplateInput;
+};
+
 export type EditMessage = {
   messageId: string;
   name: string;
@@ -372,6 +817,8 @@ export type EditMessage = {
   color?: string;
   /**  The `modified` timestamp of the message at the time the client started editing it. */
   expectModified?: string | null;
+  /**  The space of the message, which saves looking it up to check the content rules. */
+  spaceId?: string | null;
 };
 
 export type EditNote = {
@@ -385,6 +832,18 @@ export type EditNote = {
   entities?: Entities;
   accessPolicy: AccessPolicy;
   accessChannelId: string | null;
+  /**
+   *  Merge the changes into the note if it was changed since `expected_revision`, instead of
+   *  failing with a conflict
+   */
+  merge?: boolean;
+};
+
+export type EditRandomTable = {
+  spaceId: string;
+  tableId: string;
+  expectedVersion: string;
+  table: RandomTableInput;
 };
 
 export type EditSpace = {
@@ -412,6 +871,33 @@ export type EmailVerificationStatus = {
   isVerified: boolean;
 };
 
+/**  The turn order of a channel. A channel has at most one encounter. */
+export type Encounter = {
+  channelId: string;
+  spaceId: string;
+  round: number;
+  /**  `None` before the first turn */
+  currentCombatantId: string | null;
+  /**  Post a message into the channel when the turn changes. */
+  announceTurns: boolean;
+  /**
+   *  At the end of the turn of a combatant, the number at `durationPath` in this JSON component
+   *  counts down on its Entry, or on the Entries of its Character.
+   */
+  durationComponentType: string | null;
+  durationPath: string;
+  /**  In turn order, by initiative from high to low. */
+  combatants: Combatant[];
+  version: string;
+  created: string;
+  modified: string;
+};
+
+export type EndEncounter = {
+  spaceId: string;
+  channelId: string;
+};
+
 export type Entities = Entity[];
 
 export type Entity =
@@ -446,7 +932,21 @@ export type Entry = {
 
 export type EntryComponent =
   | { payloadType: 'JSON'; data: Value; schemaVersion: number; version: string; modified: string }
-  | { payloadType: 'ASSET'; assetId: string; version: string; modified: string };
+  | { payloadType: 'ASSET'; assetId: string; version: string; modified: string }
+  | {
+      payloadType: 'FORMULA';
+      formula: string;
+      value?: number | null;
+      error?: string | null;
+      version: string;
+      modified: string;
+    };
+
+export type EntryComponentDiff = {
+  componentType: string;
+  before: Value | null;
+  after: Value | null;
+};
 
 export type EntryComponentHistory = {
   entryEffectId: string;
@@ -485,7 +985,8 @@ export type EntryComponentMutation =
 
 export type EntryComponentPayloadInput =
   | { payloadType: 'JSON'; schemaVersion?: number | null; data: Value }
-  | { payloadType: 'ASSET'; assetId: string };
+  | { payloadType: 'ASSET'; assetId: string }
+  | { payloadType: 'FORMULA'; formula: string };
 
 export type EntryEffect = {
   id: string;
@@ -494,6 +995,8 @@ export type EntryEffect = {
   operatorId: string | null;
   created: string;
   messageId: string | null;
+  /**  The Entry Effect which reverted this one */
+  revertedBy: string | null;
 };
 
 export type EntryEffectHistory = {
@@ -501,6 +1004,17 @@ export type EntryEffectHistory = {
   componentHistory: EntryComponentHistory[];
 } & EntryEffect;
 
+export type EntryFilter = {
+  /**  Entries with all of these tags */
+  tags?: string[];
+  /**  Matches the start of the key or an alias, ignoring case */
+  prefix?: string | null;
+  hasComponents?: string[];
+  lacksComponents?: string[];
+  /**  Entries matching all of the conditions */
+  conditions?: ComponentCondition[];
+};
+
 export type EntryHistory = {
   entryEffectId: string;
   operatorId: string | null;
@@ -520,6 +1034,39 @@ export type EntryHistoryQuery = {
   entryId?: string | null;
 };
 
+export type EntryImportReport = {
+  /**  Nothing is imported unless every row is valid and it is not a dry run. */
+  committed: boolean;
+  errors: EntryImportRowError[];
+  /**  The created Entries. A dry run lists the Entries it would create. */
+  entries: EntryMetadata[];
+  characters: Character[];
+};
+
+/**  One Entry or Character to import, in the shape of `CreateEntry`. */
+export type EntryImportRow = {
+  key: string;
+  aliases?: string[];
+  /**  The key is used if empty. */
+  displayName?: string;
+  tags?: string[];
+  components?: { [key in string]: EntryComponentPayloadInput };
+};
+
+export type EntryImportRowError = {
+  /**  Counted from 1, not counting the CSV header. */
+  row: number;
+  message: string;
+};
+
+export type EntryImportSource =
+  | { format: 'CSV'; data: string; mapping: CsvColumnMapping }
+  | { format: 'JSON'; rows: EntryImportRow[] };
+
+export type EntryImportTarget =
+  | { kind: 'SCOPE'; scopeId: string }
+  | { kind: 'CHARACTERS'; accessPolicy: AccessPolicy; accessChannelId?: string | null };
+
 export type EntryMetadata = {
   id: string;
   scopeId: string;
@@ -536,6 +1083,87 @@ export type EntryMetadata = {
   modified: string;
 };
 
+export type EntryQueryPage = { entries: EntryMetadata[]; nextOffset: number | null };
+
+export type EntryRevertConflict = {
+  /**  The last Entry Effect of the message which changed the component */
+  entryEffectId: string;
+  entryId: string;
+  key: string;
+  componentType: string;
+  reason: EntryRevertConflictReason;
+};
+
+export type EntryRevertConflictReason = 'CHANGED_LATER' | 'MODIFIED' | 'ENTRY_DELETED';
+
+export type EntrySort =
+  | { by: 'KEY' }
+  | { by: 'DISPLAY_NAME' }
+  | { by: 'CREATED' }
+  | { by: 'MODIFIED' }
+  | { by: 'COMPONENT'; componentType: string; path?: string };
+
+export type EntryState = {
+  entryId: string;
+  key: string;
+  /**  The history payloads of the components, by component type */
+  components: { [key in string]: Value };
+};
+
+export type EntryStateChange = 'CREATED' | 'CHANGED' | 'DELETED';
+
+export type EntryStateDiff = {
+  entryId: string;
+  /**  The key at the end, or before the Entry was deleted */
+  key: string;
+  /**  The key at the start, if the Entry has been renamed */
+  previousKey: string | null;
+  change: EntryStateChange;
+  components: EntryComponentDiff[];
+};
+
+/**
+ *  The Entries of a Scope reconstructed from the history.
+ *
+ *  Only recorded changes are replayed. Changes made without history are not included, and the
+ *  Entries only carry their keys and components.
+ */
+export type ScopeState = {
+  scopeId: string;
+  at: string;
+  /**  Sorted by key */
+  entries: EntryState[];
+};
+
+export type EntryTemplate = {
+  id: string;
+  spaceId: string;
+  version: string;
+  created: string;
+  modified: string;
+} & EntryTemplateInput;
+
+/**  The templates of a space, in the format they are imported. */
+export type EntryTemplateExport = {
+  templates: EntryTemplateInput[];
+};
+
+/**  A named bundle of Entry defaults, which can be exported and imported as JSON. */
+export type EntryTemplateInput = {
+  name: string;
+  /**
+   *  The key of the instances, where `{n}` is replaced with the number of the instance.
+   *  The number is appended as `-{n}` if the pattern has no `{n}`.
+   */
+  keyPattern: string;
+  /**  Replaces `{n}` like the key pattern. The instances are named after their keys if empty. */
+  displayName?: string;
+  tags?: string[];
+  /**  Dropped on import if the note is not in the space. */
+  referenceNoteId?: string | null;
+  components?: { [key in string]: EntryComponentPayloadInput };
+};
+
 export type EvaluatedExprNode =
   | ({
       type: 'Roll';
@@ -561,6 +1189,9 @@ export type EvaluatedExprNode =
   | ({
       type: 'Repeat';
     } & RepeatResult)
+  | ({
+      type: 'Table';
+    } & TableResult)
   | ({ type: 'Unknown'; value: number } & { node?: never });
 
 export type EventId = {
@@ -608,6 +1239,9 @@ export type ExprNode =
   | ({
       type: 'Repeat';
     } & Repeat)
+  | ({
+      type: 'Table';
+    } & TableRoll)
   | ({ type: 'Unknown' } & { node?: never; value?: never });
 
 export type FateResult = {
@@ -637,7 +1271,56 @@ export type GrantOrRemoveChannelMaster = {
 
 export type GrantOrRevoke = 'GRANT' | 'REVOKE';
 
-export type Href = string | Span;
+export type HandleReport = {
+  reportId: string;
+  note?: string;
+};
+
+export type Href = string | Span | WikiLink;
+
+export type ImportCharacterBundle = {
+  spaceId: string;
+  bundle: CharacterBundle;
+  /**  Replaces the key of the bundle, if it is taken in the space. */
+  key?: string | null;
+  /**  Replaces the aliases of the bundle, if some are taken in the space. */
+  aliases?: string[] | null;
+  accessPolicy: AccessPolicy;
+  accessChannelId?: string | null;
+};
+
+export type ImportEntries = {
+  spaceId: string;
+  target: EntryImportTarget;
+  source: EntryImportSource;
+  /**  Check every row without importing anything. */
+  dryRun?: boolean;
+};
+
+export type ImportEntryTemplates = {
+  spaceId: string;
+  templates: EntryTemplateInput[];
+  /**  Replace the templates with the same names, instead of failing. */
+  replaceExisting?: boolean;
+};
+
+export type ImportRandomTables = {
+  spaceId: string;
+  tables: RandomTableInput[];
+  /**  Replace the tables with the same keys, instead of failing. */
+  replaceExisting?: boolean;
+};
+
+export type InstantiateEntryTemplate = {
+  spaceId: string;
+  templateId: string;
+  scopeId: string;
+  /**  How many Entries to create, 1 to 50. */
+  count?: number;
+  /**  Omit or set to `null` to append the Entries to the end. */
+  beforeEntryId?: string | null;
+  messageId?: string | null;
+};
 
 export type JoinChannel = {
   channelId: string;
@@ -667,8 +1350,31 @@ export type LinkEntity = {
   title?: string | null;
 } & Span;
 
+export type LinkTarget =
+  | { kind: 'NOTE'; noteId: string; title: string }
+  | { kind: 'ENTRY'; scopeId: string; entryId: string; displayName: string }
+  | { kind: 'CHARACTER'; characterId: string; name: string };
+
+export type ListAssetFolders = {
+  spaceId: string;
+};
+
 export type ListAssets = {
   spaceId: string;
+  /**  Only the Assets in this folder, not including its subfolders */
+  folderId?: string | null;
+  /**  Only the Assets not in any folder */
+  root?: boolean;
+  /**  Search the names and tags */
+  query?: string | null;
+  tag?: string | null;
+  /**  Such as `image` or `audio` */
+  mimeFamily?: string | null;
+  sort?: AssetSort;
+  /**  Defaults to the newest first, or names from A to Z */
+  descending?: boolean | null;
+  offset?: number;
+  limit?: number | null;
 };
 
 export type ListCharacters = {
@@ -682,6 +1388,10 @@ export type ListCharacters = {
   portrayableOnly?: boolean;
 };
 
+export type ListComponentTypes = {
+  spaceId: string;
+};
+
 export type ListEntries = {
   spaceId: string;
   scopeId: string;
@@ -693,11 +1403,17 @@ export type ListEntriesByComponent = {
   componentType: string;
 };
 
+export type ListEntryTemplates = {
+  spaceId: string;
+};
+
 export type ListNotes = {
   spaceId: string;
   includeArchived?: boolean;
 };
 
+export type ListRandomTables = { spaceId: string };
+
 export type Login = {
   username: string;
   password: string;
@@ -725,12 +1441,31 @@ export type Media = {
   description: string;
   source: string;
   created: string;
+  /**  When the media was uploaded or last reused by a duplicate upload */
+  lastUsed: string;
+  /**  The length of audio media, once it's been read */
+  durationMs: number | null;
+};
+
+export type MediaFormat = 'webp' | 'avif' | 'png' | 'jpeg';
+
+export type MediaGcReport = {
+  dryRun: boolean;
+  /**  The media removed, or to be removed in a dry run */
+  mediaIds: string[];
+  bytes: number;
+  /**  Media whose files failed to be removed from the storage, their rows are removed anyway */
+  failedIds: string[];
 };
 
 export type MediaQuery = {
   filename: string | null;
   id: string | null;
   download?: boolean;
+  /**  Get a thumbnail fitting in a square of the size, rounded up to a fixed size */
+  size?: number | null;
+  /**  Get the image converted to the format, WebP if only `size` is given */
+  format?: MediaFormat | null;
 };
 
 export type Member = {
@@ -781,6 +1516,15 @@ export type Message = {
   hasEntryEffects?: boolean;
 };
 
+export type MessageEffectsRevert = {
+  messageId: string;
+  /**  The Entry Effects of the message which have been reverted */
+  reverted: string[];
+  /**  The Entry Effects recording the revert, one for each Scope */
+  revertEffects: EntryEffect[];
+  conflicts: EntryRevertConflict[];
+};
+
 export type MessageEntryEffects = {
   messageId: string;
   effects: EntryEffectHistory[];
@@ -793,6 +1537,13 @@ export type MessageIdQuery = {
 
 export type MessageMoveToMode = 'TOP' | 'BOTTOM';
 
+export type MoveAssets = {
+  spaceId: string;
+  assetIds: string[];
+  /**  Move to the top level if absent */
+  folderId?: string | null;
+};
+
 export type MoveEntry = {
   spaceId: string;
   scopeId: string;
@@ -843,6 +1594,58 @@ export type NoteContentRevision = {
   created: string;
 };
 
+/**  The live document of a note, sent to a connection when it joins the note. */
+export type NoteDocumentSnapshot = {
+  noteId: string;
+  /**
+   *  Changes every time the document is opened, edits made on another session are merged
+   *  from their revision
+   */
+  session: string;
+  version: number;
+  /**  The revision of the note the document was last saved as */
+  revision: number;
+  text: string;
+};
+
+/**  An edit of the live document of a note, sent over the events connection. */
+export type NoteEditPost = {
+  noteId: string;
+  /**  The session of the document, from the snapshot */
+  session: string;
+  /**  The version of the document the splices are based on */
+  version: number;
+  /**  Chosen by the client to recognize its own edit when it is sent back */
+  editId: string;
+  /**  Sorted and not overlapping, with the positions in the text of `version` */
+  splices: NoteSplice[];
+  /**  The entities of the text after the edit, ignored if the document has a newer version */
+  entities?: Entities | null;
+  /**
+   *  The revision of the note whose text the splices are on, when `version` may be of another
+   *  session or too old. The edit is then merged from the text of the revision.
+   */
+  revision?: number | null;
+};
+
+/**  An edit applied to the live document of a note, making its next version. */
+export type NoteEdited = {
+  noteId: string;
+  session: string;
+  version: number;
+  sender: string;
+  editId: string;
+  /**  Splices on the text of the previous version, applied from the last to the first */
+  splices: NoteSplice[];
+};
+
+export type NoteLink = {
+  kind: WikiLinkKind | null;
+  target: string;
+  /**  `None` if nothing the user can view matches the link */
+  resolved: LinkTarget | null;
+};
+
 export type NoteMetadata = {
   id: string;
   spaceId: string;
@@ -858,17 +1661,63 @@ export type NoteMetadata = {
   modified: string;
 };
 
+export type NoteRevisionDiff = {
+  from: number;
+  to: number;
+  oldTitle: string;
+  newTitle: string;
+  lines: DiffLine[];
+};
+
+/**
+ *  How long the content revisions of the notes in a Space are kept.
+ *
+ *  A revision is pruned once it is neither one of the latest `keepLatest` revisions of its note,
+ *  nor younger than `keepDays`. The latest revision of a note is never pruned. Without limits,
+ *  every revision is kept.
+ */
+export type NoteRevisionPolicy = {
+  keepLatest: number | null;
+  keepDays: number | null;
+};
+
+/**  Replace `len` characters at `i` with `text`. Positions and lengths are in UTF-16 code units. */
+export type NoteSplice = { i: number; len: number; text?: string };
+
 export type Operator = '+' | '-' | '×' | '÷';
 
+/**  A change to what is playing in the channel. */
+export type PlaybackAction =
+  | {
+      action: 'PLAY';
+      assetId: string;
+      positionMs?: number;
+      looping?: boolean;
+      /**  Keeps the volume of the previous playback if not given. */
+      volume?: number | null;
+    }
+  | { action: 'PAUSE' }
+  | { action: 'RESUME' }
+  | { action: 'SEEK'; positionMs: number }
+  | { action: 'SET_LOOP'; looping: boolean }
+  | { action: 'SET_VOLUME'; volume: number }
+  | { action: 'STOP' };
+
 export type PreSign = {
   filename: string;
   mimeType: string;
   size: number;
+  /**  Hex encoded SHA-256 of the file, to reuse the media having the same content */
+  hash?: string | null;
+  /**  The space the file is uploaded for, to count it to the quota of the space */
+  spaceId?: string | null;
 };
 
 export type PreSignResult = {
   url: string;
   mediaId: string;
+  /**  The file is already stored, no need to upload it to `url` */
+  duplicate: boolean;
 };
 
 export type Preview = {
@@ -986,12 +1835,37 @@ export type PreviewPost = {
   edit?: PreviewEdit | null;
 };
 
+/**  Roll on a table without sending a message. Nothing is stored. */
+export type PreviewRandomTable = {
+  spaceId: string;
+  /**  The key of the table */
+  table: string;
+  /**  How many times to roll, 1 to 20. */
+  count?: number;
+};
+
+export type ProcessMemorySnapshot = {
+  rssBytes: number;
+  anonymousBytes: number;
+  fileBytes: number;
+  sharedBytes: number;
+  swapBytes: number;
+  threads: number;
+};
+
 export type Proxy = {
   name: string;
   url: string;
   region: string;
 };
 
+export type PublishSpaceTemplate = {
+  spaceId: string;
+  name: string;
+  description?: string;
+  isPublic?: boolean;
+};
+
 export type PureBinary = {
   l: PureExprNode;
   r: PureExprNode;
@@ -1019,6 +1893,26 @@ export type QueryAsset = {
   assetId: string;
 };
 
+export type QueryBacklinks = {
+  spaceId: string;
+  kind: WikiLinkKind;
+  id: string;
+  /**  Required for entries */
+  scopeId?: string | null;
+};
+
+/**
+ *  A link to a note, an entry or a character of the same space, written as `[[target]]`.
+ *
+ *  The target is matched by the title or a keyword of notes, and by the key, an alias or the
+ *  name of characters and entries.
+ */
+export type WikiLink = {
+  /**  Only match this kind, any kind if not given */
+  kind?: WikiLinkKind | null;
+  target: string;
+};
+
 export type QueryChannel = {
   id: string;
   /**  Optional hint used to read an already-loaded Space runtime. */
@@ -1030,6 +1924,23 @@ export type QueryCharacter = {
   characterId: string;
 };
 
+export type QueryEncounter = {
+  spaceId: string;
+  channelId: string;
+};
+
+/**  Filter and sort the Entries of a Space, across the Scopes the user can view. */
+export type QueryEntries = {
+  spaceId: string;
+  /**  Only query these Scopes. All viewable Scopes of the Space if empty. */
+  scopeIds?: string[];
+  filter?: EntryFilter;
+  sort?: EntrySort;
+  descending?: boolean;
+  offset?: number;
+  limit?: number | null;
+};
+
 export type QueryEntry = {
   spaceId: string;
   scopeId: string;
@@ -1046,15 +1957,58 @@ export type QueryNote = {
   noteId: string;
 };
 
+export type QueryNoteRevisionPolicy = {
+  spaceId: string;
+};
+
+export type QueryPlayback = {
+  spaceId: string;
+  channelId: string;
+};
+
+export type QueryRandomTable = { spaceId: string; tableId: string };
+
 export type QuerySpace = {
   id: string;
   token: string | null;
 };
 
+export type QueryStorageUsage = {
+  spaceId?: string | null;
+};
+
 export type QueryUser = {
   id: string | null;
 };
 
+/**  A weighted random table of a space, such as loot or weather. */
+export type RandomTable = {
+  id: string;
+  spaceId: string;
+  version: string;
+  created: string;
+  modified: string;
+} & RandomTableInput;
+
+/**  The random tables of a space, in the format they are imported. */
+export type RandomTableExport = { tables: RandomTableInput[] };
+
+export type RandomTableInput = {
+  /**  Rolled on in messages as `{table:key}`. Case-insensitive. */
+  key: string;
+  name: string;
+  description?: string;
+  rows: RandomTableRow[];
+};
+
+export type RandomTableRow = {
+  /**  How likely the row is, relative to the other rows. 1 to 10000. */
+  weight?: number;
+  text: string;
+  /**  The key of a table to roll on when this row comes up */
+  table?: string | null;
+};
+
 export type Register = {
   email: string;
   username: string;
@@ -1062,6 +2016,23 @@ export type Register = {
   password: string;
 };
 
+export type RegisterComponentType = {
+  spaceId: string;
+  componentType: string;
+  /**  Must be the next version of the type, starting from 1. */
+  schemaVersion: number;
+  /**  A JSON Schema for the component data. */
+  schema: Value;
+  /**  Upgrades the stored components from the previous schema version. */
+  migration?: ComponentMigrationStep[];
+};
+
+export type RemoveCombatant = {
+  spaceId: string;
+  channelId: string;
+  combatantId: string;
+};
+
 export type Repeat = {
   node: ExprNode;
   count: number;
@@ -1072,6 +2043,52 @@ export type RepeatResult = {
   value: number;
 } & Repeat;
 
+export type Report = {
+  id: string;
+  /**  `None` for messages flagged by the content rules, or if the reporter has been deleted */
+  reporterId: string | null;
+  targetKind: ReportTargetKind;
+  targetId: string;
+  /**  The space whose admins triage this report */
+  spaceId: string | null;
+  reason: ReportReason;
+  comment: string;
+  /**  The reported content at the time of the report */
+  snapshot: JsonValue;
+  status: ReportStatus;
+  handlerId: string | null;
+  resolutionNote: string;
+  created: string;
+  handled: string | null;
+};
+
+export type ReportQueueQuery = {
+  /**  The queue of a space, omit it for the site-wide queue */
+  spaceId?: string | null;
+  status?: ReportStatus | null;
+  /**  The id of the last report of the previous page */
+  before?: string | null;
+  limit?: number | null;
+};
+
+export type ReportReason =
+  | 'SPAM'
+  | 'HARASSMENT'
+  | 'HATE_SPEECH'
+  | 'SEXUAL_CONTENT'
+  | 'VIOLENCE'
+  | 'ILLEGAL_CONTENT'
+  | 'IMPERSONATION'
+  | 'OTHER';
+
+/**
+ *  Open reports wait for the space admins, or the site admins if the report has no space.
+ *  Escalated reports are left to the site admins.
+ */
+export type ReportStatus = 'OPEN' | 'ESCALATED' | 'RESOLVED' | 'DISMISSED';
+
+export type ReportTargetKind = 'MESSAGE' | 'SPACE' | 'USER' | 'MEDIA';
+
 export type RequestEmailChange = {
   newEmail: string;
   lang?: string | null;
@@ -1109,6 +2126,21 @@ export type RestoreNote = {
   expectedRevision: number;
 };
 
+/**  Edit the note back to the content of an old revision, as a new revision. */
+export type RestoreNoteRevision = {
+  spaceId: string;
+  noteId: string;
+  revision: number;
+  expectedRevision: number;
+};
+
+export type RevertMessageEffects = {
+  spaceId: string;
+  messageId: string;
+  /**  Revert the rest when some components conflict with later changes, instead of nothing. */
+  skipConflicts?: boolean;
+};
+
 export type Roll = {
   face: number;
   counter: number;
@@ -1145,10 +2177,54 @@ export type Scope = {
 
 export type ScopeKind = 'SPACE' | 'CHARACTER';
 
+export type ScopeStateDiff = {
+  scopeId: string;
+  from: string;
+  to: string;
+  /**  The Entries which changed, sorted by key */
+  entries: EntryStateDiff[];
+};
+
+export type ScopeStateDiffQuery = {
+  spaceId: string;
+  scopeId: string;
+  from?: string | null;
+  fromMessageId?: string | null;
+  /**  Defaults to now. */
+  to?: string | null;
+  toMessageId?: string | null;
+};
+
+/**
+ *  A point in the history is either a time, or a message, which means the state when the
+ *  message was sent, before its own Entry Effects. Defaults to now.
+ */
+export type ScopeStateQuery = {
+  spaceId: string;
+  scopeId: string;
+  at?: string | null;
+  messageId?: string | null;
+};
+
 export type SearchDirection = 'asc' | 'desc';
 
 export type SearchFilter = 'ALL' | 'IN_GAME' | 'OUT_OF_GAME';
 
+/**  A note, an entry or a character matching the search, the best matches first. */
+export type SearchHit = {
+  kind: SearchKind;
+  id: string;
+  /**  The scope of an entry */
+  scopeId: string | null;
+  /**  The title of a note, or the name of an entry or a character */
+  title: string;
+  /**  The line of the note text or the character description where a word was found */
+  snippet: string | null;
+  rank: number;
+};
+
+export type SearchKind = 'NOTE' | 'ENTRY' | 'CHARACTER';
+
 export type SearchMessagesParams = {
   channelId: string;
   spaceId?: string | null;
@@ -1173,6 +2249,38 @@ export type SearchParams = {
   search: string;
 };
 
+export type SearchSpace = {
+  spaceId: string;
+  /**  Split by whitespace, every word has to be found */
+  keyword: string;
+  /**  Search all kinds if absent */
+  kind?: SearchKind | null;
+  limit?: number | null;
+};
+
+/**  A point-in-time view of the gauges this module exports, for the admin API. */
+export type ServerMetricsSnapshot = {
+  memory: ProcessMemorySnapshot | null;
+  fileDescriptorsUsed: number;
+  fileDescriptorsLimit: number | null;
+  dbPoolConnectionsTotal: number;
+  dbPoolConnectionsIdle: number;
+  dbPoolConnectionsMax: number;
+  mailboxes: number;
+  broadcastMailboxes: number;
+  mailboxActionQueueDepth: number;
+  tokenStoreEntries: number;
+  posActors: number;
+};
+
+export type SetInitiative = {
+  spaceId: string;
+  channelId: string;
+  combatantId: string;
+  initiative: number;
+  initiativeMessageId?: string | null;
+};
+
 export type Settings = {
   enterSend?: boolean;
   expandDice?: boolean;
@@ -1194,6 +2302,24 @@ export type Space = {
   scopeId: string;
 };
 
+/**  Which parts of the source space are copied into the clone. */
+export type SpaceCloneParts = {
+  channels?: boolean;
+  /**  Space settings and content rules. */
+  settings?: boolean;
+  characters?: boolean;
+  /**
+   *  Entries in the space scope. Character entries follow `characters`. The component types
+   *  of the space are copied with either.
+   */
+  entries?: boolean;
+  notes?: boolean;
+  /**  Listed assets. Assets referenced by cloned entries or messages are always copied. */
+  assets?: boolean;
+  /**  Message history. Requires `channels`. */
+  messages?: boolean;
+};
+
 export type SpaceMember = {
   userId: string;
   spaceId: string;
@@ -1207,6 +2333,28 @@ export type SpaceMemberWithUser = {
   user: User;
 };
 
+/**  Diagnostic view of a loaded runtime, exposed through the admin API. */
+export type SpaceRuntimeStats = {
+  spaceId: string;
+  revision: number;
+  dirty: boolean;
+  leases: number;
+  idleSeconds: number;
+  activeMutations: number;
+  controlQueueDepth: number;
+  mutationQueueDepth: number;
+};
+
+export type SpaceTemplate = {
+  spaceId: string;
+  ownerId: string;
+  name: string;
+  description: string;
+  isPublic: boolean;
+  created: string;
+  modified: string;
+};
+
 export type SpaceWithMember = {
   space: Space;
   member: SpaceMember;
@@ -1230,14 +2378,63 @@ export type SpanWithChild = {
   child: ChildText;
 } & Span;
 
+export type StartEncounter = {
+  spaceId: string;
+  channelId: string;
+  announceTurns?: boolean;
+  durationComponentType?: string | null;
+  durationPath?: string;
+};
+
 export type StatusKind = 'OFFLINE' | 'AWAY' | 'ONLINE';
 
+export type StorageUsage = {
+  used: number;
+  /**  `None` if there is no quota */
+  quota: number | null;
+};
+
+export type StorageUsageResult = {
+  user: StorageUsage;
+  space: StorageUsage | null;
+};
+
 export type SubExprResult = {
   node: ExprNode;
   evaluatedNode: EvaluatedExprNode;
   value: number;
 };
 
+export type TableResult = {
+  value: number;
+} & TableRoll;
+
+/**  A roll on a random table of the Space, such as `{table:loot}`. */
+export type TableRoll = {
+  /**  The key of the table */
+  table: string;
+  /**
+   *  Resolved by the server from the seed of the message, so that every client sees the same
+   *  row. A result sent by a client is replaced.
+   */
+  result?: TableRollResult | null;
+};
+
+export type TableRollResult = {
+  tableId: string;
+  /**  The version of the table at the time of the roll */
+  tableVersion: string;
+  name: string;
+  /**  From 1 to `totalWeight` */
+  roll: number;
+  totalWeight: number;
+  /**  The index of the row the roll landed on */
+  row: number;
+  text: string;
+  /**  The roll on the table the row refers to */
+  nested?: TableRollResult | null;
+};
+
 export type Token = {
   token: string;
   issuedAt: number;
@@ -1255,6 +2452,15 @@ export type UpdateAsset = {
   assetId: string;
   name: string;
   policy: AssetPolicy;
+  /**  Keep the tags if absent */
+  tags?: string[] | null;
+};
+
+export type UpdateAssetFolder = {
+  folderId: string;
+  name: string;
+  /**  Move to the top level if absent */
+  parentId?: string | null;
 };
 
 export type UpdateBody =
@@ -1272,10 +2478,22 @@ export type UpdateBody =
   | { type: 'ENTRY_CHANGED'; scopeId: string; entryId: string }
   | { type: 'CHARACTER_CHANGED'; characterId: string }
   | { type: 'NOTE_CHANGED'; noteId: string }
+  | { type: 'ENCOUNTER_CHANGED'; channelId: string }
+  | { type: 'PLAYBACK_CHANGED'; channelId: string }
+  | { type: 'NOTE_SNAPSHOT'; snapshot: NoteDocumentSnapshot }
+  | { type: 'NOTE_EDITED'; edited: NoteEdited }
+  | { type: 'NOTE_CLOSED'; noteId: string }
+  | { type: 'NOTE_EDIT_REJECTED'; noteId: string; editId: string; reason: string }
   | { type: 'ERROR'; code: ConnectionError; reason: string; span: string }
   | { type: 'APP_UPDATED'; version: string }
   | { type: 'APP_INFO'; info: BasicInfo };
 
+export type UpdateContentRules = {
+  spaceId: string;
+  /**  Replaces all the rules of the space */
+  rules: ContentRule[];
+};
+
 export type UpdateEncoding = 'plain' | 'gzip' | 'brotli';
 
 export type UpdateLifetime =
@@ -1293,6 +2511,12 @@ export type UpdateLifetime =
   /**  Persistent updates are stored in mailbox state and can be resumed. */
   | 'P';
 
+export type UpdateNoteRevisionPolicy = {
+  spaceId: string;
+  keepLatest?: number | null;
+  keepDays?: number | null;
+};
+
 export type UpdateQuery = {
   mailbox: string;
   token?: string | null;
@@ -1313,6 +2537,8 @@ export type Upload = {
   filename: string;
   mimeType: string | null;
   size?: number;
+  /**  The space the file is uploaded for, to count it to the quota of the space */
+  spaceId?: string | null;
 };
 
 export type User = {
@@ -1337,3 +2563,5 @@ export type Value = JsonValue;
 export type VerifyEmail = {
   token: string;
 };
+
+export type WikiLinkKind = 'NOTE' | 'ENTRY' | 'CHARACTER';
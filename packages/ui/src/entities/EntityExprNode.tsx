@@ -8,6 +8,7 @@ import { EntityExprMax } from './EntityExprMax';
 import { EntityExprMin } from './EntityExprMin';
 import { EntityExprRepeat } from './EntityExprRepeat';
 import { EntityExprRoll } from './EntityExprRoll';
+import { EntityExprTable } from './EntityExprTable';
 import { EntityExprNodeUnknown } from './EntityExprUnknown';
 import { EntityExprSubExpr } from './EntityExprSubExpr';
 
@@ -37,6 +38,8 @@ export const EntityExprNode = memo<Props>(({ node }: Props) => {
       return <EntityExprMin node={node} />;
     case 'SubExpr':
       return <EntityExprSubExpr node={node} />;
+    case 'Table':
+      return <EntityExprTable node={node} />;
     default:
       return <EntityExprNodeUnknown />;
   }
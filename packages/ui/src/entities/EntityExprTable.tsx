@@ -0,0 +1,48 @@
+import { type FC } from 'react';
+import React from 'react';
+import { FormattedMessage } from 'react-intl';
+import { type MaybeEvalutedExprOf, type TableRollResult } from '@boluo/api';
+import { RollBox } from './RollBox';
+import { Result } from './Result';
+import { useIsTopLevel } from './top-level';
+
+interface Props {
+  node: MaybeEvalutedExprOf<'Table'>;
+}
+
+const TableRollLine: FC<{ result: TableRollResult; final: boolean }> = ({ result, final }) => (
+  <span>
+    <span className="text-text-secondary pr-1">{result.name}</span>
+    <span className="text-text-muted">
+      {result.roll}/{result.totalWeight}
+    </span>
+    <Result final={final && result.nested == null}>{result.text}</Result>
+    {result.nested != null && (
+      <span className="pl-1">
+        <span>→ </span>
+        <TableRollLine result={result.nested} final={final} />
+      </span>
+    )}
+  </span>
+);
+
+export const EntityExprTable: FC<Props> = React.memo(({ node }: Props) => {
+  const topLevel = useIsTopLevel();
+  return (
+    <RollBox>
+      <span className="pr-1">
+        <FormattedMessage defaultMessage="Table" />
+      </span>
+      {node.result != null ? (
+        <TableRollLine result={node.result} final={topLevel} />
+      ) : (
+        <span>
+          <span className="text-text-secondary pr-1">{node.table}</span>
+          <span className="italic">???</span>
+        </span>
+      )}
+    </RollBox>
+  );
+});
+
+EntityExprTable.displayName = 'EntityExprTable';
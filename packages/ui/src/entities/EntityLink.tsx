@@ -10,6 +10,16 @@ interface Props {
 
 export const EntityLink: FC<Props> = ({ source, entity }) => {
   const { title } = entity;
+  if (typeof entity.href === 'object' && 'target' in entity.href) {
+    return (
+      <span
+        title={title ?? entity.href.target}
+        className="EntityLink underline decoration-dotted underline-offset-2"
+      >
+        <EntityText source={source} entity={entity.child} />
+      </span>
+    );
+  }
   const href =
     typeof entity.href === 'string'
       ? entity.href